@@ -1,6 +1,56 @@
-use k8s_openapi::api::core::v1::{Namespace, Node};
-use kube::{Api, Client};
-use obs::common::errors::K8sResourceError;
+use chrono::{DateTime, Utc};
+use k8s_openapi::{
+    api::core::v1::{ConfigMap, Namespace, Node},
+    chrono,
+};
+use kube::{
+    api::{ObjectMeta, PostParams},
+    Api, Client,
+};
+use obs::common::{constants::CLUSTER_IDENTITY_CONFIG_MAP, errors::K8sResourceError};
+use std::collections::BTreeMap;
+use tracing::info;
+
+/// Key of the uuid of the cluster in the cluster identity ConfigMap.
+const CLUSTER_UUID_KEY: &str = "clusterUuid";
+/// Key of the creation time of the identity in the cluster identity ConfigMap.
+const CREATED_AT_KEY: &str = "createdAt";
+
+/// ClusterIdentity is the stable identity of the cluster, which is generated once and persisted,
+/// so that the reports of a cluster can be told apart across reinstalls of the reporting pod.
+#[derive(Clone, Debug)]
+pub(crate) struct ClusterIdentity {
+    pub(crate) uuid: String,
+    pub(crate) created_at: DateTime<Utc>,
+}
+
+impl ClusterIdentity {
+    /// Generate a new identity.
+    fn generate() -> Self {
+        Self {
+            uuid: uuid::Uuid::new_v4().to_string(),
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Get the identity from the data of the cluster identity ConfigMap, if it's complete.
+    fn from_data(data: &BTreeMap<String, String>) -> Option<Self> {
+        Some(Self {
+            uuid: data.get(CLUSTER_UUID_KEY)?.clone(),
+            created_at: DateTime::parse_from_rfc3339(data.get(CREATED_AT_KEY)?)
+                .ok()?
+                .with_timezone(&Utc),
+        })
+    }
+
+    /// Get the data of the cluster identity ConfigMap.
+    fn to_data(&self) -> BTreeMap<String, String> {
+        BTreeMap::from([
+            (CLUSTER_UUID_KEY.to_string(), self.uuid.clone()),
+            (CREATED_AT_KEY.to_string(), self.created_at.to_rfc3339()),
+        ])
+    }
+}
 
 /// K8sClient contains k8s client.
 #[derive(Clone)]
@@ -28,4 +78,49 @@ impl K8sClient {
         let kube_system_namespace = namespace_api.get("kube-system").await?;
         Ok(kube_system_namespace.metadata.uid.unwrap_or_default())
     }
+
+    /// Get the identity of the cluster from the cluster identity ConfigMap of the namespace.
+    /// It's generated and persisted if there isn't one yet, or if it's incomplete.
+    pub(crate) async fn get_or_create_cluster_identity(
+        &self,
+        namespace: &str,
+    ) -> Result<ClusterIdentity, K8sResourceError> {
+        let config_maps: Api<ConfigMap> = Api::namespaced(self.client.clone(), namespace);
+        let existing = config_maps.get_opt(CLUSTER_IDENTITY_CONFIG_MAP).await?;
+        if let Some(identity) = existing
+            .as_ref()
+            .and_then(|config_map| config_map.data.as_ref())
+            .and_then(ClusterIdentity::from_data)
+        {
+            return Ok(identity);
+        }
+
+        let identity = ClusterIdentity::generate();
+        let config_map = ConfigMap {
+            metadata: ObjectMeta {
+                name: Some(CLUSTER_IDENTITY_CONFIG_MAP.to_string()),
+                ..Default::default()
+            },
+            data: Some(identity.to_data()),
+            ..Default::default()
+        };
+        match existing {
+            Some(_) => {
+                config_maps
+                    .replace(
+                        CLUSTER_IDENTITY_CONFIG_MAP,
+                        &PostParams::default(),
+                        &config_map,
+                    )
+                    .await?
+            }
+            None => {
+                config_maps
+                    .create(&PostParams::default(), &config_map)
+                    .await?
+            }
+        };
+        info!(uuid = identity.uuid, "Generated the cluster identity");
+        Ok(identity)
+    }
 }
@@ -1,3 +1,5 @@
+use crate::collector::k8s_client::ClusterIdentity;
+use k8s_openapi::chrono;
 use obs::common::{constants::ACTION, errors};
 use openapi::models::Volume;
 use prometheus_parse::{Sample, Value};
@@ -5,10 +7,12 @@ use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
 use reqwest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware};
 use serde::{Deserialize, Serialize};
 use snafu::ResultExt;
-use std::convert::TryFrom;
+use std::{collections::BTreeMap, convert::TryFrom};
 use url::Url;
 
-/// Volumes contains volume count, min, max, mean and capacity percentiles.
+/// Volumes contains volume count, min, max, mean and capacity percentiles, along with the
+/// bucketed counts by replica count, the bucketed provisioned and allocated capacity and the
+/// bucketed count of the volumes cloned from snapshots.
 #[derive(Serialize, Deserialize, Debug, Default)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct Volumes {
@@ -17,6 +21,10 @@ pub(crate) struct Volumes {
     mean_size_in_bytes: u64,
     max_size_in_bytes: u64,
     capacity_percentiles_in_bytes: Percentiles,
+    count_by_replica_count: BTreeMap<String, String>,
+    provisioned_capacity_bucket_in_bytes: u64,
+    allocated_capacity_bucket_in_bytes: u64,
+    clone_count: String,
     #[serde(skip_serializing_if = "is_zero")]
     created: u32,
     #[serde(skip_serializing_if = "is_zero")]
@@ -26,6 +34,32 @@ impl Volumes {
     /// Receives a openapi::models::Volumes object and returns a new report_models::volume object by
     /// using the data provided.
     pub(crate) fn new(volumes: openapi::models::Volumes, event_data: EventData) -> Self {
+        let mut count_by_replica_count = BTreeMap::<String, u64>::new();
+        let mut provisioned_bytes = 0u64;
+        let mut allocated_bytes = 0u64;
+        let mut clone_count = 0u64;
+        for volume in volumes.entries.iter() {
+            let replica_count = match volume.spec.num_replicas {
+                replicas if replicas >= MAX_REPLICA_COUNT_KEY => {
+                    format!("{MAX_REPLICA_COUNT_KEY}+")
+                }
+                replicas => replicas.to_string(),
+            };
+            *count_by_replica_count.entry(replica_count).or_default() += 1;
+            provisioned_bytes = provisioned_bytes.saturating_add(volume.spec.size);
+            allocated_bytes = allocated_bytes.saturating_add(
+                volume
+                    .state
+                    .usage
+                    .as_ref()
+                    .map(|usage| usage.allocated)
+                    .unwrap_or_default(),
+            );
+            if volume.spec.content_source.is_some() {
+                clone_count += 1;
+            }
+        }
+
         let volumes_size_vector = get_volumes_size_vector(volumes.entries);
         Self {
             count: volumes_size_vector.len() as u64,
@@ -33,12 +67,34 @@ impl Volumes {
             min_size_in_bytes: get_min_value(volumes_size_vector.clone()),
             mean_size_in_bytes: get_mean_value(volumes_size_vector.clone()),
             capacity_percentiles_in_bytes: Percentiles::new(volumes_size_vector),
+            count_by_replica_count: count_by_replica_count
+                .into_iter()
+                .map(|(replica_count, count)| (replica_count, count_bucket(count)))
+                .collect(),
+            provisioned_capacity_bucket_in_bytes: capacity_bucket(provisioned_bytes),
+            allocated_capacity_bucket_in_bytes: capacity_bucket(allocated_bytes),
+            clone_count: count_bucket(clone_count),
             created: event_data.volume_created.value(),
             deleted: event_data.volume_deleted.value(),
         }
     }
 }
 
+/// Snapshots contains the bucketed count of the volume snapshots.
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Snapshots {
+    count: String,
+}
+impl Snapshots {
+    /// Returns a new report_models::Snapshots object from the count of the volume snapshots.
+    pub(crate) fn new(snapshot_count: usize) -> Self {
+        Self {
+            count: count_bucket(snapshot_count as u64),
+        }
+    }
+}
+
 /// Pools contains pool count, min, max, mean and capacity percentiles.
 #[derive(Serialize, Deserialize, Debug, Default)]
 #[serde(rename_all = "camelCase")]
@@ -91,7 +147,8 @@ impl Replicas {
     }
 }
 
-/// Nexus contains nexus created, deleted counts, and rebuild started and rebuild ended counts.
+/// Nexus contains nexus created, deleted counts, rebuild started and rebuild ended counts, and
+/// the count of the failovers of the volume targets.
 #[derive(Serialize, Deserialize, Debug, Default)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct Nexus {
@@ -103,15 +160,21 @@ pub(crate) struct Nexus {
     rebuild_started: u32,
     #[serde(skip_serializing_if = "is_zero")]
     rebuild_ended: u32,
+    #[serde(skip_serializing_if = "is_zero")]
+    failover: u32,
+    /// Bucketed count of the failed rebuilds of the rebuild histories of the volumes.
+    rebuild_failed: String,
 }
 impl Nexus {
-    /// Returns nexus object using the event_data.
-    pub(crate) fn new(event_data: EventData) -> Self {
+    /// Returns nexus object using the event_data and the count of the failed rebuilds.
+    pub(crate) fn new(event_data: EventData, failed_rebuilds: u64) -> Self {
         Self {
             created: event_data.nexus_created.value(),
             deleted: event_data.nexus_deleted.value(),
             rebuild_started: event_data.rebuild_started.value(),
             rebuild_ended: event_data.rebuild_ended.value(),
+            failover: event_data.nexus_failover.value(),
+            rebuild_failed: count_bucket(failed_rebuilds),
         }
     }
 }
@@ -145,11 +208,35 @@ impl Percentiles {
     }
 }
 
+/// Heartbeat contains the seconds since the identity of the cluster was created and since the
+/// reporter was started, so that the receivers can tell long-running clusters from reinstalls.
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Heartbeat {
+    cluster_age_seconds: u64,
+    reporter_uptime_seconds: u64,
+}
+
+impl Heartbeat {
+    /// Returns the heartbeat of the cluster identity, whose reporter was started at the given
+    /// instant.
+    pub(crate) fn new(identity: &ClusterIdentity, reporter_started: std::time::Instant) -> Self {
+        Self {
+            cluster_age_seconds: (chrono::Utc::now() - identity.created_at)
+                .num_seconds()
+                .max(0) as u64,
+            reporter_uptime_seconds: reporter_started.elapsed().as_secs(),
+        }
+    }
+}
+
 /// Report contains all the values and objects that we want to include in JSON payload.
 #[derive(Serialize, Deserialize, Debug, Default)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct Report {
     pub(crate) k8s_cluster_id: String,
+    pub(crate) cluster_uuid: String,
+    pub(crate) heartbeat: Heartbeat,
     pub(crate) k8s_node_count: u8,
     pub(crate) product_name: String,
     pub(crate) product_version: String,
@@ -159,9 +246,39 @@ pub(crate) struct Report {
     pub(crate) volumes: Volumes,
     pub(crate) replicas: Replicas,
     pub(crate) nexus: Nexus,
+    pub(crate) snapshots: Snapshots,
     pub(crate) versions: Versions,
 }
 
+/// The replica count from which the volumes are counted together, as few volumes have more
+/// replicas.
+const MAX_REPLICA_COUNT_KEY: u8 = 4;
+
+/// Lower bounds of the buckets of the counts. The counts are reported as the range of their
+/// bucket, rather than exactly, so that they can't single out a cluster, and the counts below 5
+/// share a single bucket.
+const COUNT_BUCKETS: [u64; 9] = [1, 5, 10, 20, 50, 100, 200, 500, 1000];
+
+/// Get the range of the bucket of a count, eg: 1-4, 50-99 or 1000+.
+fn count_bucket(count: u64) -> String {
+    if count == 0 {
+        return "0".to_string();
+    }
+    match COUNT_BUCKETS.windows(2).find(|bounds| count < bounds[1]) {
+        Some(bounds) => format!("{}-{}", bounds[0], bounds[1] - 1),
+        None => format!("{}+", COUNT_BUCKETS[COUNT_BUCKETS.len() - 1]),
+    }
+}
+
+/// Get the upper bound of the bucket of a capacity, i.e. the capacity rounded up to a power of
+/// two, so that it can't single out a cluster.
+fn capacity_bucket(bytes: u64) -> u64 {
+    match bytes {
+        0 => 0,
+        bytes => bytes.checked_next_power_of_two().unwrap_or(u64::MAX),
+    }
+}
+
 /// Get maximum value from a vector.
 fn get_max_value(values: Vec<u64>) -> u64 {
     values.into_iter().max().unwrap_or_default()
@@ -241,6 +358,7 @@ pub(crate) struct EventData {
     pub(crate) nexus_deleted: NexusDeleted,
     pub(crate) rebuild_started: RebuildStarted,
     pub(crate) rebuild_ended: RebuildEnded,
+    pub(crate) nexus_failover: NexusFailover,
 }
 
 /// Record of events populated from prometheus.
@@ -335,6 +453,7 @@ pub(crate) enum Action {
     Deleted,
     RebuildStarted,
     RebuildEnded,
+    Failover,
 }
 
 impl From<&str> for Resource {
@@ -342,6 +461,7 @@ impl From<&str> for Resource {
         match s {
             "pool" => Resource::Pool,
             "volume" => Resource::Volume,
+            "nexus" => Resource::Nexus,
             _ => Resource::Unknown,
         }
     }
@@ -353,6 +473,7 @@ impl From<&str> for Action {
             "deleted" => Action::Deleted,
             "rebuild_started" => Action::RebuildStarted,
             "rebuild_ended" => Action::RebuildEnded,
+            "failover" => Action::Failover,
             _ => Action::Unknown,
         }
     }
@@ -396,6 +517,7 @@ make_counter!(NexusCreated, Resource::Nexus, Action::Created);
 make_counter!(NexusDeleted, Resource::Nexus, Action::Deleted);
 make_counter!(RebuildStarted, Resource::Nexus, Action::RebuildStarted);
 make_counter!(RebuildEnded, Resource::Nexus, Action::RebuildEnded);
+make_counter!(NexusFailover, Resource::Nexus, Action::Failover);
 
 impl<'a, T: TryFrom<Record<'a>>> From<&'a EventsRecord> for Option<T> {
     fn from(src: &'a EventsRecord) -> Option<T> {
@@ -1,12 +1,14 @@
 mod collector;
+mod metrics;
 mod transmitter;
 
 use crate::{
     collector::{
         k8s_client::K8sClient,
         report_models::{
-            event_stats, EventData, NexusCreated, NexusDeleted, PoolCreated, PoolDeleted, Pools,
-            RebuildEnded, RebuildStarted, Replicas, Report, VolumeCreated, VolumeDeleted, Volumes,
+            event_stats, EventData, Heartbeat, NexusCreated, NexusDeleted, NexusFailover,
+            PoolCreated, PoolDeleted, Pools, RebuildEnded, RebuildStarted, Replicas, Report,
+            Snapshots, VolumeCreated, VolumeDeleted, Volumes,
         },
     },
     transmitter::*,
@@ -14,9 +16,12 @@ use crate::{
 use clap::Parser;
 use collector::report_models::Nexus;
 use obs::common::constants::*;
-use openapi::tower::client::{ApiClient, Configuration};
+use openapi::{
+    models::RebuildJobState,
+    tower::client::{ApiClient, Configuration},
+};
 use sha256::digest;
-use std::time;
+use std::{net::SocketAddr, path::PathBuf, time};
 use tokio::time::sleep;
 use tracing::{error, info};
 use tracing_subscriber::EnvFilter;
@@ -41,6 +46,58 @@ struct CliArgs {
     /// The endpoint to fetch events stats.
     #[clap(long, short)]
     aggregator_url: Option<Url>,
+
+    /// The interval between the reports, which can't be shorter than an hour.
+    #[clap(
+        long,
+        env = "CALL_HOME_REPORT_INTERVAL",
+        default_value = DEFAULT_CALL_HOME_INTERVAL,
+        value_parser = parse_call_home_interval
+    )]
+    report_interval: time::Duration,
+
+    /// The URL of the remote collection endpoint the reports are sent to, eg: a receiver of an
+    /// air-gapped or a test environment.
+    #[clap(long, env = "CALL_HOME_RECEIVER_URL", default_value = RECEIVER_ENDPOINT)]
+    receiver_url: Url,
+
+    /// Writes each report as a JSON file to the directory, or prints it to stdout with '-',
+    /// instead of sending it to the remote collection endpoint, eg: in disconnected
+    /// environments, for the reports to be reviewed and forwarded manually.
+    #[clap(long, env = "CALL_HOME_OFFLINE_OUTPUT", value_name = "DIR_PATH")]
+    offline_output: Option<offline::OfflineOutput>,
+
+    /// Path to a PEM bundle of the CA certificates the certificate of the remote collection
+    /// endpoint, or of an intercepting proxy, is verified against. The reports are sent through
+    /// the proxy of the HTTPS_PROXY env variable, except for the hosts of NO_PROXY.
+    #[clap(long, env = "CALL_HOME_CA_BUNDLE", value_name = "PATH")]
+    ca_bundle: Option<PathBuf>,
+
+    /// Path to the public keyring the reports are encrypted with, eg: the one of a self-hosted
+    /// receiver. Defaults to the path of the KEY_FILEPATH env variable, or the bundled key.
+    #[clap(long, value_name = "PATH")]
+    encryption_key: Option<PathBuf>,
+
+    /// The recipients of the keyring the reports are encrypted for. The reports are encrypted for
+    /// each of them, so that the key can be rotated by adding the recipient of the new key
+    /// before the one of the old key is removed.
+    #[clap(
+        long = "encryption-recipient",
+        env = "CALL_HOME_ENCRYPTION_RECIPIENTS",
+        value_delimiter = ',',
+        default_value = DEFAULT_ENCRYPTION_RECIPIENT
+    )]
+    encryption_recipients: Vec<String>,
+
+    /// The id of the encryption key, which is sent along with the reports, so that the receiver
+    /// can tell which of its keys decrypts them.
+    #[clap(long, env = "CALL_HOME_ENCRYPTION_KEY_ID")]
+    encryption_key_id: Option<String>,
+
+    /// TCP address the metrics of the call-home are served at on `/metrics`, eg: the duration of
+    /// the report generation and the time of the last successful send. Disabled when not set.
+    #[clap(long, env = "CALL_HOME_METRICS_ENDPOINT")]
+    metrics_endpoint: Option<SocketAddr>,
 }
 impl CliArgs {
     fn args() -> Self {
@@ -66,10 +123,21 @@ async fn run() -> anyhow::Result<()> {
     let endpoint = args.endpoint;
     let aggregator_url = args.aggregator_url;
     let send_report = args.send_report;
-    let namespace = digest(args.namespace);
-    let sleep_duration = call_home_frequency();
+    let offline_output = args.offline_output;
+    let deploy_namespace = args.namespace;
+    let namespace = digest(deploy_namespace.as_str());
+    let sleep_duration = args.report_interval;
     let encryption_dir = encryption_dir();
-    let key_filepath = key_filepath();
+    let encryption_key = encryption::EncryptionKey::new(
+        args.encryption_key.unwrap_or_else(key_filepath),
+        args.encryption_recipients,
+        args.encryption_key_id,
+    );
+
+    if let Some(metrics_endpoint) = args.metrics_endpoint {
+        metrics::serve(metrics_endpoint)
+            .map_err(|error| anyhow::anyhow!("failed to serve the metrics: {:?}", error))?;
+    }
 
     // Generate kubernetes client.
     let k8s_client = K8sClient::new()
@@ -81,12 +149,21 @@ async fn run() -> anyhow::Result<()> {
         anyhow::anyhow!("failed to generate kubernetes cluster ID: {:?}", error)
     })?);
 
-    // Generate receiver API client.
-    let receiver = client::Receiver::new(&k8s_cluster_id)
+    // Get the persisted identity of the cluster, generating it on the first run.
+    let cluster_identity = k8s_client
+        .get_or_create_cluster_identity(&deploy_namespace)
         .await
-        .map_err(|error| {
-            anyhow::anyhow!("failed to generate metrics receiver client: {:?}", error)
-        })?;
+        .map_err(|error| anyhow::anyhow!("failed to get the cluster identity: {:?}", error))?;
+    let reporter_started = time::Instant::now();
+
+    // Generate receiver API client.
+    let receiver = client::Receiver::new(
+        &k8s_cluster_id,
+        args.receiver_url,
+        args.ca_bundle.as_deref(),
+    )
+    .await
+    .map_err(|error| anyhow::anyhow!("failed to generate metrics receiver client: {:?}", error))?;
 
     // Generate Mayastor REST client.
     let config = Configuration::builder()
@@ -98,7 +175,8 @@ async fn run() -> anyhow::Result<()> {
 
     loop {
         // Generate report.
-        let report = generate_report(
+        let timer = metrics::REPORT_GENERATION_DURATION.start_timer();
+        let mut report = generate_report(
             k8s_client.clone(),
             client.clone(),
             k8s_cluster_id.clone(),
@@ -107,21 +185,44 @@ async fn run() -> anyhow::Result<()> {
             aggregator_url.clone(),
         )
         .await;
+        report.cluster_uuid = cluster_identity.uuid.clone();
+        report.heartbeat = Heartbeat::new(&cluster_identity, reporter_started);
+        timer.observe_duration();
+
+        // Write the report locally instead, in the offline mode.
+        if let Some(offline_output) = &offline_output {
+            match offline_output.write(&report) {
+                Ok(Some(file_path)) => info!(?file_path, "Wrote report"),
+                Ok(None) => {}
+                Err(e) => error!(?e, "failed to write report"),
+            }
+            sleep(sleep_duration).await;
+            continue;
+        }
 
         // Encrypt data.
         let encryption_dir = encryption_dir.clone();
-        let key_filepath = key_filepath.clone();
+        let key = encryption_key.clone();
         let output = tokio::task::spawn_blocking(move || {
-            encryption::encrypt(&report, &encryption_dir, &key_filepath)
+            encryption::encrypt(&report, &encryption_dir, &key)
         })
         .await?;
-        let output = output.map_err(|error| anyhow::anyhow!("encryption failed: {:?}", error))?;
+        let output = output.map_err(|error| {
+            metrics::SEND_FAILURES.inc();
+            anyhow::anyhow!("encryption failed: {:?}", error)
+        })?;
 
         // POST data to receiver API.
         if send_report {
-            match receiver.post(output).await {
-                Ok(response) => info!(?response, "Success"),
-                Err(e) => error!(?e, "failed HTTP POST request"),
+            match receiver.post(output, encryption_key.key_id()).await {
+                Ok(response) => {
+                    metrics::record_successful_send();
+                    info!(?response, "Success");
+                }
+                Err(e) => {
+                    metrics::SEND_FAILURES.inc();
+                    error!(?e, "failed HTTP POST request");
+                }
             }
         }
 
@@ -162,6 +263,8 @@ async fn generate_report(
                 event_data.rebuild_started =
                     Option::<RebuildStarted>::from(&data).unwrap_or_default();
                 event_data.rebuild_ended = Option::<RebuildEnded>::from(&data).unwrap_or_default();
+                event_data.nexus_failover =
+                    Option::<NexusFailover>::from(&data).unwrap_or_default();
             }
             Err(err) => {
                 error!("{:?}", err);
@@ -206,6 +309,28 @@ async fn generate_report(
         report.volumes = Volumes::new(volumes.clone(), event_data.clone());
     }
 
+    // The failed rebuilds are counted from the rebuild history of each volume.
+    let mut failed_rebuilds = 0;
+    for volume in volumes.iter().flat_map(|volumes| volumes.entries.iter()) {
+        match http_client
+            .volumes_api()
+            .get_rebuild_history(&volume.spec.uuid)
+            .await
+        {
+            Ok(history) => {
+                failed_rebuilds += history
+                    .into_body()
+                    .records
+                    .iter()
+                    .filter(|record| matches!(record.rebuild_job_state, RebuildJobState::Failed))
+                    .count() as u64
+            }
+            Err(err) => {
+                error!("{:?}", err);
+            }
+        };
+    }
+
     let replicas = http_client.replicas_api().get_replicas().await;
     match replicas {
         Ok(replicas) => report.replicas = Replicas::new(replicas.into_body().len(), volumes),
@@ -214,6 +339,17 @@ async fn generate_report(
         }
     };
 
-    report.nexus = Nexus::new(event_data);
+    let snapshots = http_client
+        .snapshots_api()
+        .get_volumes_snapshots(0, None, None, None)
+        .await;
+    match snapshots {
+        Ok(snapshots) => report.snapshots = Snapshots::new(snapshots.into_body().entries.len()),
+        Err(err) => {
+            error!("{:?}", err);
+        }
+    };
+
+    report.nexus = Nexus::new(event_data, failed_rebuilds);
     report
 }
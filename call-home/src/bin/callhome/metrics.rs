@@ -0,0 +1,83 @@
+use actix_web::{http::header, web, HttpResponse, HttpServer, Responder};
+use once_cell::sync::Lazy;
+use prometheus::{Encoder, Gauge, Histogram, HistogramOpts, IntCounter, Registry};
+use std::net::SocketAddr;
+use tracing::{error, info};
+
+/// Duration of the generation of the reports.
+pub(crate) static REPORT_GENERATION_DURATION: Lazy<Histogram> = Lazy::new(|| {
+    Histogram::with_opts(
+        HistogramOpts::new(
+            "callhome_report_generation_duration_seconds",
+            "Duration of the generation of the call-home reports",
+        )
+        .buckets(vec![0.1, 0.5, 1.0, 5.0, 10.0, 30.0, 60.0, 300.0]),
+    )
+    .expect("Unable to create histogram metric type for report generation duration")
+});
+
+/// Unix timestamp of the last report which was sent successfully.
+pub(crate) static LAST_SUCCESSFUL_SEND: Lazy<Gauge> = Lazy::new(|| {
+    Gauge::new(
+        "callhome_last_successful_send_timestamp_seconds",
+        "Unix timestamp of the last call-home report which was sent successfully",
+    )
+    .expect("Unable to create gauge metric type for last successful send")
+});
+
+/// Number of the reports which failed to be encrypted or sent.
+pub(crate) static SEND_FAILURES: Lazy<IntCounter> = Lazy::new(|| {
+    IntCounter::new(
+        "callhome_send_failures_total",
+        "Number of the call-home reports which failed to be encrypted or sent",
+    )
+    .expect("Unable to create counter metric type for send failures")
+});
+
+/// Registry of the metrics of the call-home.
+static REGISTRY: Lazy<Registry> = Lazy::new(|| {
+    let registry = Registry::new();
+    for collector in [
+        Box::new(REPORT_GENERATION_DURATION.clone()) as Box<dyn prometheus::core::Collector>,
+        Box::new(LAST_SUCCESSFUL_SEND.clone()),
+        Box::new(SEND_FAILURES.clone()),
+    ] {
+        if let Err(error) = registry.register(collector) {
+            error!(%error, "Failed to register the call-home metric");
+        }
+    }
+    registry
+});
+
+/// Record the successful send of a report.
+pub(crate) fn record_successful_send() {
+    LAST_SUCCESSFUL_SEND.set(chrono::Utc::now().timestamp() as f64);
+}
+
+/// Serve the metrics of the call-home on `/metrics` of the given address, so that it can be told
+/// whether the reports are actually generated and sent.
+pub(crate) fn serve(metrics_endpoint: SocketAddr) -> std::io::Result<()> {
+    let server =
+        HttpServer::new(|| actix_web::App::new().route("/metrics", web::get().to(metrics_handler)))
+            .workers(1)
+            .bind(metrics_endpoint)?
+            .run();
+    info!(%metrics_endpoint, "Serving the call-home metrics at /metrics");
+    tokio::spawn(async move {
+        if let Err(error) = server.await {
+            error!(%error, "Failed to serve the call-home metrics");
+        }
+    });
+    Ok(())
+}
+
+async fn metrics_handler() -> impl Responder {
+    let mut buffer = Vec::new();
+    if let Err(error) = prometheus::TextEncoder::new().encode(&REGISTRY.gather(), &mut buffer) {
+        error!(%error, "Failed to encode the call-home metrics");
+        return HttpResponse::InternalServerError().finish();
+    }
+    HttpResponse::Ok()
+        .insert_header(header::ContentType(mime::TEXT_PLAIN))
+        .body(buffer)
+}
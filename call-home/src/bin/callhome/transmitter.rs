@@ -5,3 +5,7 @@ pub(crate) mod client;
 /// 'encryption' module contains helper function(s) which enable the use of the GnuPG
 /// encryption tool.
 pub(crate) mod encryption;
+
+/// 'offline' module contains type(s) which write the reports locally, instead of sending them
+/// to the Receiver's call-home API endpoint.
+pub(crate) mod offline;
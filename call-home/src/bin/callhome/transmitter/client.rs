@@ -1,9 +1,16 @@
 use chrono::prelude::*;
 use k8s_openapi::chrono;
-use obs::common::{constants::*, errors::ReceiverError};
-use reqwest::Response;
+use obs::common::{
+    constants::*,
+    errors::{CaBundleEmptyError, CaBundleReadError, ReceiverError},
+};
+use reqwest::{Certificate, NoProxy, Response};
 use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
 use reqwest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware};
+use snafu::{ensure, ResultExt};
+use std::path::Path;
+use tracing::info;
+use url::Url;
 
 /// Struct used to make calls to the receiver API.
 pub struct Receiver {
@@ -14,31 +21,55 @@ pub struct Receiver {
 
 impl Receiver {
     /// 'Receiver::new()' creates a new instance of Receiver
-    /// which is initialized with sane default values.
-    pub(crate) async fn new<T>(cluster_id: T) -> Result<Self, ReceiverError>
+    /// which is initialized with sane default values, posting to the given url.
+    /// The requests go through the proxy of the HTTPS_PROXY env variable, except for the hosts
+    /// of the NO_PROXY env variable. If a CA bundle is given, the certificate of the receiver,
+    /// or of an intercepting proxy, is verified against it.
+    pub(crate) async fn new<T>(
+        cluster_id: T,
+        url: Url,
+        ca_bundle: Option<&Path>,
+    ) -> Result<Self, ReceiverError>
     where
         T: ToString,
     {
         // Retry up to 3 times with increasing intervals between attempts.
         let retry_policy = ExponentialBackoff::builder().build_with_max_retries(3);
 
-        let client_config = reqwest::Client::builder()
-            .danger_accept_invalid_certs(true)
-            .build()?;
+        let mut client_builder =
+            reqwest::Client::builder().danger_accept_invalid_certs(ca_bundle.is_none());
+        if let Some(proxy_url) = proxy_url_from_env() {
+            info!(proxy = %proxy_url, "Sending reports through the https proxy");
+            client_builder = client_builder
+                .proxy(reqwest::Proxy::https(proxy_url.as_str())?.no_proxy(NoProxy::from_env()));
+        }
+        if let Some(path) = ca_bundle {
+            for certificate in read_ca_bundle(path)? {
+                client_builder = client_builder.add_root_certificate(certificate);
+            }
+        }
+        let client_config = client_builder.build()?;
         Ok(Self {
             cluster_id: cluster_id.to_string(),
             client: ClientBuilder::new(client_config)
                 .with(RetryTransientMiddleware::new_with_policy(retry_policy))
                 .build(),
-            url: RECEIVER_ENDPOINT.to_string(),
+            url: url.to_string(),
         })
     }
 
-    /// 'post()' method attempts an HTTP POST with some headers
-    pub(crate) async fn post(&self, body: Vec<u8>) -> Result<Response, ReceiverError> {
-        Ok(self
-            .client
-            .post(&self.url)
+    /// 'post()' method attempts an HTTP POST with some headers, including the id of the key
+    /// the body is encrypted with, if any.
+    pub(crate) async fn post(
+        &self,
+        body: Vec<u8>,
+        key_id: Option<&str>,
+    ) -> Result<Response, ReceiverError> {
+        let mut request = self.client.post(&self.url);
+        if let Some(key_id) = key_id {
+            request = request.header("CAStor-Key-Id", key_id);
+        }
+        Ok(request
             .header("CAStor-Cluster-Id", &self.cluster_id)
             .header("CAStor-Version", release_version())
             .header("CAStor-Report-Type", "health_report")
@@ -47,6 +78,28 @@ impl Receiver {
             .header("Content-Type", "text/PGP; charset=binary")
             .body(body)
             .send()
-            .await?)
+            .await?
+            .error_for_status()?)
     }
 }
+
+/// Get the url of the https proxy from the HTTPS_PROXY env variable, if set.
+fn proxy_url_from_env() -> Option<String> {
+    ["HTTPS_PROXY", "https_proxy"]
+        .iter()
+        .find_map(|name| std::env::var(name).ok().filter(|url| !url.is_empty()))
+}
+
+/// Read the PEM encoded certificates of the CA bundle.
+fn read_ca_bundle(path: &Path) -> Result<Vec<Certificate>, ReceiverError> {
+    const BEGIN_CERTIFICATE: &str = "-----BEGIN CERTIFICATE-----";
+
+    let bundle = std::fs::read_to_string(path).context(CaBundleReadError { path })?;
+    let certificates = bundle
+        .split(BEGIN_CERTIFICATE)
+        .skip(1)
+        .map(|pem| Certificate::from_pem(format!("{BEGIN_CERTIFICATE}{pem}").as_bytes()))
+        .collect::<Result<Vec<_>, _>>()?;
+    ensure!(!certificates.is_empty(), CaBundleEmptyError { path });
+    Ok(certificates)
+}
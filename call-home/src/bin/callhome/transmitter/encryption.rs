@@ -1,5 +1,5 @@
 use crate::collector::report_models::Report;
-use obs::common::errors::EncryptError;
+use obs::common::errors::{EncryptError, GpgError};
 use rand::{distributions::Alphanumeric, Rng};
 use std::{
     fs,
@@ -8,11 +8,42 @@ use std::{
 };
 use tracing::debug;
 
+/// The public keyring the reports are encrypted with, and the recipients of the keyring the
+/// reports are encrypted for.
+#[derive(Debug, Clone)]
+pub(crate) struct EncryptionKey {
+    key_filepath: PathBuf,
+    recipients: Vec<String>,
+    key_id: Option<String>,
+}
+
+impl EncryptionKey {
+    /// Create a new EncryptionKey. The reports are encrypted for every recipient, so that the
+    /// key can be rotated by adding the recipient of the new key before the old one is removed.
+    pub(crate) fn new(
+        key_filepath: PathBuf,
+        recipients: Vec<String>,
+        key_id: Option<String>,
+    ) -> Self {
+        Self {
+            key_filepath,
+            recipients,
+            key_id,
+        }
+    }
+
+    /// The id of the key, sent along with the reports, so that the receiver can tell which of
+    /// its private keys decrypts them.
+    pub(crate) fn key_id(&self) -> Option<&str> {
+        self.key_id.as_deref()
+    }
+}
+
 /// 'encrypt' accepts a crate::collector::Report, marshals it into JSON and encrypts it.
 pub(crate) fn encrypt(
     report: &Report,
     encryption_dir: &PathBuf,
-    key_filepath: &Path,
+    key: &EncryptionKey,
 ) -> Result<Vec<u8>, EncryptError> {
     // The underlying filesystem resource is garbage collected
     // when this function returns.
@@ -32,12 +63,31 @@ pub(crate) fn encrypt(
     let output_filepath = Path::new(encryption_dir).join(random_name + ".gpg");
 
     // TODO: Use a library instead of the gpg binary.
-    let command = format!("gpg --yes --trust-model=always --homedir={} --keyring={} --recipient=openebs-phonehome@datacore.com --no-default-keyring --encrypt -z=9 --output={} {}",
-                          encryption_dir.to_string_lossy(),
-                          key_filepath.to_string_lossy(),
-                          output_filepath.to_string_lossy(),
-                          input_filepath.to_string_lossy());
-    let _ = Command::new("sh").args(["-c", command.trim()]).output()?;
+    let mut command = Command::new("gpg");
+    command.args([
+        "--yes".to_string(),
+        "--trust-model=always".to_string(),
+        format!("--homedir={}", encryption_dir.to_string_lossy()),
+        format!("--keyring={}", key.key_filepath.to_string_lossy()),
+    ]);
+    for recipient in &key.recipients {
+        command.arg(format!("--recipient={recipient}"));
+    }
+    command.args([
+        "--no-default-keyring".to_string(),
+        "--encrypt".to_string(),
+        "-z=9".to_string(),
+        format!("--output={}", output_filepath.to_string_lossy()),
+        input_filepath.to_string_lossy().to_string(),
+    ]);
+    let command_output = command.output()?;
+    if !command_output.status.success() {
+        return GpgError {
+            status: command_output.status,
+            stderr: String::from_utf8_lossy(&command_output.stderr).to_string(),
+        }
+        .fail();
+    }
     debug!("Successfully executed gpg command.");
 
     let output = fs::read(&output_filepath)?;
@@ -0,0 +1,51 @@
+use crate::collector::report_models::Report;
+use chrono::Utc;
+use obs::common::errors::OfflineReportError;
+use std::{convert::Infallible, fs, io::Write, path::PathBuf, str::FromStr};
+
+/// The value of the offline output which prints the reports to stdout.
+const STDOUT: &str = "-";
+
+/// 'OfflineOutput' is where the reports are written in the offline mode, instead of being
+/// sent to the Receiver's API endpoint, so that they can be reviewed and forwarded manually.
+#[derive(Clone, Debug)]
+pub(crate) enum OfflineOutput {
+    /// Each report is printed to stdout, as a single line of JSON.
+    Stdout,
+    /// Each report is written as a JSON file to the directory, eg: a mounted volume.
+    Directory(PathBuf),
+}
+
+impl FromStr for OfflineOutput {
+    type Err = Infallible;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Ok(match value {
+            STDOUT => Self::Stdout,
+            path => Self::Directory(PathBuf::from(path)),
+        })
+    }
+}
+
+impl OfflineOutput {
+    /// 'write()' writes the unencrypted report, and returns the path of its file, if any.
+    pub(crate) fn write(&self, report: &Report) -> Result<Option<PathBuf>, OfflineReportError> {
+        match self {
+            Self::Stdout => {
+                let mut stdout = std::io::stdout().lock();
+                serde_json::to_writer(&mut stdout, report)?;
+                writeln!(stdout)?;
+                Ok(None)
+            }
+            Self::Directory(dir) => {
+                fs::create_dir_all(dir)?;
+                let file_path = dir.join(format!(
+                    "report-{}.json",
+                    Utc::now().format("%Y-%m-%dT%H-%M-%SZ")
+                ));
+                fs::write(&file_path, serde_json::to_vec_pretty(report)?)?;
+                Ok(Some(file_path))
+            }
+        }
+    }
+}
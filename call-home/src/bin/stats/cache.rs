@@ -9,3 +9,12 @@ pub(crate) mod volume;
 
 /// Modules for nexus.
 pub(crate) mod nexus;
+
+/// Modules for the persistent volumes index.
+pub(crate) mod pv_index;
+
+/// Modules for the events rate limiter.
+pub(crate) mod rate_limit;
+
+/// Modules for the retention policy of the persisted event aggregates.
+pub(crate) mod retention;
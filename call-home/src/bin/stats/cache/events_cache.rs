@@ -1,14 +1,24 @@
-use crate::cache::{nexus, pools, volume};
+use crate::{
+    cache::{
+        nexus, pools, pv_index::PvIndex, rate_limit::RateLimiter, retention::RetentionPolicy,
+        volume,
+    },
+    notify::{email::EmailNotifier, k8s_events::K8sEventNotifier, webhook::WebhookNotifier},
+    rules::{EventRules, Sink},
+};
 use events_api::{
-    event::{EventAction, EventCategory, EventMessage},
+    event::{EventAction, EventCategory, EventMessage, SwitchOverStatus},
     mbus_nats::BusSubscription,
 };
 use k8s_openapi::api::core::v1::ConfigMap;
-use obs::common::{constants::EVENT_STATS_DATA, errors};
+use obs::common::{
+    constants::{EVENT_STATS_DATA, UNKNOWN_NAMESPACE},
+    errors,
+};
 use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
 use snafu::ResultExt;
-use std::{ops::DerefMut, sync::Mutex};
+use std::{collections::BTreeMap, ops::DerefMut, sync::Mutex};
 
 static CACHE: OnceCell<Mutex<Cache>> = OnceCell::new();
 
@@ -19,8 +29,19 @@ pub struct EventSet {
     pub(crate) pool: pools::Pool,
     pub(crate) volume: volume::Volume,
     pub(crate) nexus: nexus::Nexus,
+    /// Volume events, keyed by the namespace of the PVC which owns the volume.
+    pub(crate) volume_by_namespace: BTreeMap<String, volume::Volume>,
+    /// Number of events suppressed by the rate limiter, keyed by the event category.
+    pub(crate) suppressed: BTreeMap<String, u32>,
+    /// Unix timestamp of the last update of the keyed aggregates, keyed by the aggregate key.
+    pub(crate) last_updated: BTreeMap<String, i64>,
 }
 
+/// Prefix of the aggregate keys of the volume events per namespace.
+const VOLUME_NAMESPACE_KEY_PREFIX: &str = "volume_namespace/";
+/// Prefix of the aggregate keys of the suppressed events per category.
+const SUPPRESSED_KEY_PREFIX: &str = "suppressed/";
+
 impl EventSet {
     pub fn from_event_store(init_data: ConfigMap) -> errors::Result<Self> {
         let data = init_data
@@ -33,19 +54,77 @@ impl EventSet {
             .build(),
         )?;
 
-        let event_set = serde_json::from_str(value)
+        let mut event_set: Self = serde_json::from_str(value)
             .context(errors::EventSerdeDeserialization { event: value })?;
+        // Aggregates persisted before their updates were tracked are considered fresh.
+        let now = chrono::Utc::now().timestamp();
+        for (key, _) in event_set.aggregate_keys() {
+            event_set.last_updated.entry(key).or_insert(now);
+        }
         Ok(event_set)
     }
 
-    fn inc_counter(&mut self, category: EventCategory, action: EventAction) {
+    /// Keys of the keyed aggregates, along with the unix timestamp of their last update.
+    pub(crate) fn aggregate_keys(&self) -> Vec<(String, i64)> {
+        let namespaces = self
+            .volume_by_namespace
+            .keys()
+            .map(|namespace| format!("{VOLUME_NAMESPACE_KEY_PREFIX}{namespace}"));
+        let categories = self
+            .suppressed
+            .keys()
+            .map(|category| format!("{SUPPRESSED_KEY_PREFIX}{category}"));
+        namespaces
+            .chain(categories)
+            .map(|key| {
+                let updated = self.last_updated.get(&key).copied().unwrap_or_default();
+                (key, updated)
+            })
+            .collect()
+    }
+
+    /// Remove the keyed aggregate.
+    pub(crate) fn remove_aggregate(&mut self, key: &str) {
+        if let Some(namespace) = key.strip_prefix(VOLUME_NAMESPACE_KEY_PREFIX) {
+            self.volume_by_namespace.remove(namespace);
+        } else if let Some(category) = key.strip_prefix(SUPPRESSED_KEY_PREFIX) {
+            self.suppressed.remove(category);
+        }
+        self.last_updated.remove(key);
+    }
+
+    /// Record the update of the keyed aggregate.
+    fn touch(&mut self, key: String) {
+        self.last_updated
+            .insert(key, chrono::Utc::now().timestamp());
+    }
+
+    fn inc_counter(
+        &mut self,
+        category: EventCategory,
+        action: EventAction,
+        namespace: Option<String>,
+    ) {
         match category {
             EventCategory::Pool => self.pool.update_counter(action),
-            EventCategory::Volume => self.volume.update_counter(action),
+            EventCategory::Volume => {
+                let namespace = namespace.unwrap_or_else(|| UNKNOWN_NAMESPACE.to_string());
+                self.volume.update_counter(action);
+                self.touch(format!("{VOLUME_NAMESPACE_KEY_PREFIX}{namespace}"));
+                self.volume_by_namespace
+                    .entry(namespace)
+                    .or_default()
+                    .update_counter(action);
+            }
             EventCategory::Nexus => self.nexus.update_counter(action),
             _ => {}
         }
     }
+
+    fn inc_suppressed(&mut self, category: String) {
+        self.touch(format!("{SUPPRESSED_KEY_PREFIX}{category}"));
+        *self.suppressed.entry(category).or_default() += 1;
+    }
 }
 
 impl From<&mut EventSet> for EventSet {
@@ -54,19 +133,44 @@ impl From<&mut EventSet> for EventSet {
             pool: event_set.pool.clone(),
             volume: event_set.volume.clone(),
             nexus: event_set.nexus.clone(),
+            volume_by_namespace: event_set.volume_by_namespace.clone(),
+            suppressed: event_set.suppressed.clone(),
+            last_updated: event_set.last_updated.clone(),
         }
     }
 }
 
+/// Snapshot of the aggregated stats, as served by the json stats endpoint.
+#[derive(Serialize, Debug)]
+pub(crate) struct StatsSnapshot {
+    #[serde(flatten)]
+    events: EventSet,
+    evictions: BTreeMap<String, u64>,
+    dropped: BTreeMap<String, u64>,
+}
+
 /// Cache to store data that has to be exposed though exporter.
 pub struct Cache {
     events: EventSet,
+    /// Number of aggregates evicted by the retention policy, keyed by the eviction reason.
+    evictions: BTreeMap<String, u64>,
+    /// Number of events dropped by the event rules, keyed by the rule name.
+    dropped: BTreeMap<String, u64>,
+    /// Seconds between the emission and the consumption of the last consumed event.
+    consumer_lag: f64,
 }
 
 impl Cache {
     /// Initialize the cache with default value.
     pub(crate) fn initialize(events: EventSet) {
-        CACHE.get_or_init(|| Mutex::new(Self { events }));
+        CACHE.get_or_init(|| {
+            Mutex::new(Self {
+                events,
+                evictions: BTreeMap::new(),
+                dropped: BTreeMap::new(),
+                consumer_lag: 0.0,
+            })
+        });
     }
 
     /// Returns cache.
@@ -79,20 +183,142 @@ impl Cache {
     pub fn data_mut(&mut self) -> &mut EventSet {
         &mut self.events
     }
+
+    /// Get the number of aggregates evicted by the retention policy, per eviction reason.
+    pub(crate) fn evictions(&self) -> &BTreeMap<String, u64> {
+        &self.evictions
+    }
+
+    /// Get the number of events dropped by the event rules, per rule.
+    pub(crate) fn dropped(&self) -> &BTreeMap<String, u64> {
+        &self.dropped
+    }
+
+    /// Get the seconds between the emission and the consumption of the last consumed event.
+    pub(crate) fn consumer_lag(&self) -> f64 {
+        self.consumer_lag
+    }
+
+    /// Get a snapshot of the aggregated stats.
+    pub(crate) fn snapshot(&mut self) -> StatsSnapshot {
+        StatsSnapshot {
+            events: EventSet::from(&mut self.events),
+            evictions: self.evictions.clone(),
+            dropped: self.dropped.clone(),
+        }
+    }
+
+    /// Count an event dropped by the rule.
+    fn inc_dropped(&mut self, rule: String) {
+        *self.dropped.entry(rule).or_default() += 1;
+    }
+
+    /// Evict the aggregates which exceed the retention policy.
+    pub(crate) fn garbage_collect(&mut self, policy: &RetentionPolicy) {
+        policy.apply(&mut self.events, &mut self.evictions);
+    }
 }
 
 /// To store data in shared variable i.e cache.
-pub(crate) async fn store_events(mut sub: BusSubscription<EventMessage>) -> errors::Result<()> {
+/// Volume events are attributed to the namespace of the PVC which owns the volume.
+/// The event rules are applied first, the events they drop are only counted as dropped.
+/// Events exceeding the rate limit of their category are only counted as suppressed, the others
+/// are emailed when the email notifications are enabled, and the significant volume events are
+/// created as Kubernetes Events when those are enabled, and posted to the webhook when it is set,
+/// unless the rules route them elsewhere.
+pub(crate) async fn store_events(
+    mut sub: BusSubscription<EventMessage>,
+    pv_index: PvIndex,
+    event_rules: EventRules,
+    mut rate_limiter: RateLimiter,
+    email_notifier: Option<EmailNotifier>,
+    k8s_event_notifier: Option<K8sEventNotifier>,
+    webhook_notifier: Option<WebhookNotifier>,
+) -> errors::Result<()> {
     while let Some(message) = sub.next().await {
+        if let Some(lag) = consumer_lag(&message) {
+            let mut cache = Cache::cache_init().lock().expect("not poisoned");
+            cache.consumer_lag = lag;
+        }
+        let verdict = event_rules.apply(&message);
+        if let Some(rule) = verdict.dropped_by {
+            let mut cache = Cache::cache_init().lock().expect("not poisoned");
+            cache.inc_dropped(rule);
+            continue;
+        }
+        let category = category_name(message.category());
+        if !rate_limiter.admit(&category) {
+            let mut cache = Cache::cache_init().lock().expect("not poisoned");
+            cache.data_mut().inc_suppressed(category);
+            continue;
+        }
+        if let Some(email_notifier) = &email_notifier {
+            if verdict.routes_to(Sink::Email) {
+                email_notifier.notify(&message, &verdict.tags);
+            }
+        }
+        if let Some(k8s_event_notifier) = &k8s_event_notifier {
+            if verdict.routes_to(Sink::K8sEvents) {
+                k8s_event_notifier.notify(&message);
+            }
+        }
+        if let Some(webhook_notifier) = &webhook_notifier {
+            if verdict.routes_to(Sink::Webhook) {
+                webhook_notifier.notify(&message, &verdict.tags);
+            }
+        }
+        if !verdict.routes_to(Sink::Stats) {
+            continue;
+        }
+        let namespace = match message.category() {
+            EventCategory::Volume => pv_index.namespace(&message.target),
+            _ => None,
+        };
         let mut cache = Cache::cache_init().lock().expect("not poisoned");
         let events_cache = cache.deref_mut();
         events_cache
             .data_mut()
-            .inc_counter(message.category(), message.action());
+            .inc_counter(message.category(), message.action(), namespace);
+        if is_failover(&message) {
+            events_cache.data_mut().nexus.inc_failover();
+        }
     }
     Ok(())
 }
 
+/// Seconds between the emission of the event and now, if the event has a timestamp.
+fn consumer_lag(message: &EventMessage) -> Option<f64> {
+    let timestamp = message.metadata.as_ref()?.timestamp.as_ref()?;
+    let emitted = timestamp.seconds as f64 + timestamp.nanos as f64 / 1e9;
+    let now = chrono::Utc::now().timestamp_micros() as f64 / 1e6;
+    Some((now - emitted).max(0.0))
+}
+
+/// Whether the event is the completion of a switch over of a volume target, i.e. a failover.
+/// The switch over emits an event for each of its stages, only the completed ones are counted.
+fn is_failover(message: &EventMessage) -> bool {
+    message.action() == EventAction::SwitchOver
+        && message
+            .metadata
+            .as_ref()
+            .and_then(|meta| meta.source.as_ref())
+            .and_then(|source| source.event_details.as_ref())
+            .and_then(|details| details.switch_over_details.as_ref())
+            .is_some_and(|switch_over| {
+                switch_over.switch_over_status() == SwitchOverStatus::SwitchOverCompleted
+            })
+}
+
+/// Name of the event category, as used by the rate limits and the suppressed events stats.
+pub(crate) fn category_name(category: EventCategory) -> String {
+    format!("{category:?}").to_lowercase()
+}
+
+/// Name of the event action, as used by the email notifications.
+pub(crate) fn action_name(action: EventAction) -> String {
+    format!("{action:?}").to_lowercase()
+}
+
 /// Trait for updating the counters.
 pub(crate) trait StatsCounter {
     fn update_counter(&mut self, action: EventAction);
@@ -9,6 +9,16 @@ pub(crate) struct Nexus {
     pub(crate) nexus_deleted: u32,
     pub(crate) rebuild_started: u32,
     pub(crate) rebuild_ended: u32,
+    /// Number of the completed switch overs of the volume targets to another node.
+    #[serde(default)]
+    pub(crate) nexus_failovers: u32,
+}
+
+impl Nexus {
+    /// Count a completed switch over of a volume target.
+    pub(crate) fn inc_failover(&mut self) {
+        self.nexus_failovers += 1;
+    }
 }
 
 impl StatsCounter for Nexus {
@@ -0,0 +1,56 @@
+use futures::StreamExt;
+use k8s_openapi::api::core::v1::PersistentVolume;
+use kube::{
+    runtime::{reflector, reflector::Store, watcher, WatchStreamExt},
+    Api, Client,
+};
+use obs::common::errors;
+use snafu::ResultExt;
+use std::sync::Arc;
+use tracing::error;
+
+/// Index of the PersistentVolumes, used to attribute volume events to the namespace of the PVC
+/// which owns the volume. The index is kept up to date by watching the PersistentVolumes.
+#[derive(Clone)]
+pub(crate) struct PvIndex {
+    store: Store<PersistentVolume>,
+}
+
+impl PvIndex {
+    /// Create the index and start watching the PersistentVolumes.
+    pub(crate) async fn new() -> errors::Result<Self> {
+        let client = Client::try_default().await.context(errors::K8sClient)?;
+        let api: Api<PersistentVolume> = Api::all(client);
+        let (store, writer) = reflector::store();
+        let stream = reflector(writer, watcher(api, watcher::Config::default()))
+            .default_backoff()
+            .touched_objects();
+        tokio::spawn(async move {
+            stream
+                .for_each(|result| async move {
+                    if let Err(error) = result {
+                        error!(%error, "Error while watching persistent volumes");
+                    }
+                })
+                .await
+        });
+        Ok(Self { store })
+    }
+
+    /// Get the PV of the given volume, if any.
+    pub(crate) fn persistent_volume(&self, volume_id: &str) -> Option<Arc<PersistentVolume>> {
+        self.store.state().into_iter().find(|pv| {
+            pv.spec
+                .as_ref()
+                .and_then(|spec| spec.csi.as_ref())
+                .map_or(false, |csi| csi.volume_handle == volume_id)
+        })
+    }
+
+    /// Get the namespace of the PVC bound to the PV of the given volume, if any.
+    pub(crate) fn namespace(&self, volume_id: &str) -> Option<String> {
+        let pv = self.persistent_volume(volume_id)?;
+        let spec = pv.spec.as_ref()?;
+        spec.claim_ref.as_ref()?.namespace.clone()
+    }
+}
@@ -0,0 +1,73 @@
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+/// Maximum number of events of a category which are counted within a rate limit window.
+#[derive(Debug, Clone)]
+pub(crate) struct CategoryLimit {
+    category: String,
+    limit: u32,
+}
+
+impl FromStr for CategoryLimit {
+    type Err = String;
+
+    /// Parse a limit in the `<category>=<limit>` format, eg: `nexus=100`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (category, limit) = s.split_once('=').ok_or_else(|| {
+            format!("Invalid event rate limit '{s}', expected <category>=<limit>")
+        })?;
+        let limit = limit
+            .parse()
+            .map_err(|error| format!("Invalid event rate limit '{s}': {error}"))?;
+        Ok(Self {
+            category: category.trim().to_lowercase(),
+            limit,
+        })
+    }
+}
+
+/// Fixed window rate limiter for the event categories. Events of the categories without a limit
+/// are always admitted.
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    window: Duration,
+    limits: HashMap<String, u32>,
+    /// Start of the current window and number of events admitted within it, per category.
+    windows: HashMap<String, (Instant, u32)>,
+}
+
+impl RateLimiter {
+    /// Create a rate limiter with the given limits per window.
+    pub(crate) fn new(limits: &[CategoryLimit], window: Duration) -> Self {
+        Self {
+            window,
+            limits: limits
+                .iter()
+                .map(|limit| (limit.category.clone(), limit.limit))
+                .collect(),
+            windows: HashMap::new(),
+        }
+    }
+
+    /// Whether an event of the category should be counted, or suppressed as its limit has been
+    /// reached within the current window.
+    pub(crate) fn admit(&mut self, category: &str) -> bool {
+        let Some(limit) = self.limits.get(category) else {
+            return true;
+        };
+        let now = Instant::now();
+        let (start, admitted) = self.windows.entry(category.to_string()).or_insert((now, 0));
+        if now.duration_since(*start) >= self.window {
+            *start = now;
+            *admitted = 0;
+        }
+        if *admitted >= *limit {
+            return false;
+        }
+        *admitted += 1;
+        true
+    }
+}
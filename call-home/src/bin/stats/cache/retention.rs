@@ -0,0 +1,118 @@
+use crate::cache::events_cache::{Cache, EventSet};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    time::Duration,
+};
+use tracing::{debug, error};
+
+/// Reason why a persisted event aggregate was evicted.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum EvictionReason {
+    /// The aggregate was not updated within the maximum age.
+    Age,
+    /// The number of aggregates exceeded the maximum number of keys.
+    Keys,
+    /// The size of the persisted aggregates exceeded the maximum number of bytes.
+    Bytes,
+}
+
+impl EvictionReason {
+    /// Name of the reason, as used by the evictions stats.
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            EvictionReason::Age => "age",
+            EvictionReason::Keys => "keys",
+            EvictionReason::Bytes => "bytes",
+        }
+    }
+}
+
+/// Retention policy of the keyed event aggregates, eg: the volume events per namespace, which
+/// are persisted in the event store. The totals are always retained.
+#[derive(Debug, Clone)]
+pub(crate) struct RetentionPolicy {
+    max_age: Option<Duration>,
+    max_keys: Option<usize>,
+    max_bytes: Option<usize>,
+}
+
+impl RetentionPolicy {
+    /// Create a retention policy, the limits which are not given are not enforced.
+    pub(crate) fn new(
+        max_age: Option<Duration>,
+        max_keys: Option<usize>,
+        max_bytes: Option<usize>,
+    ) -> Self {
+        Self {
+            max_age,
+            max_keys,
+            max_bytes,
+        }
+    }
+
+    /// Whether any of the limits is enforced.
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.max_age.is_some() || self.max_keys.is_some() || self.max_bytes.is_some()
+    }
+
+    /// Evict the aggregates which exceed the limits, least recently updated first, and count
+    /// the evictions per reason.
+    pub(crate) fn apply(&self, events: &mut EventSet, evictions: &mut BTreeMap<String, u64>) {
+        // Keys ordered from the least to the most recently updated.
+        let mut keys = events.aggregate_keys();
+        keys.sort_by_key(|(_, updated)| *updated);
+        let mut keys = VecDeque::from(keys);
+
+        let mut evict = |events: &mut EventSet, key: &str, reason: EvictionReason| {
+            debug!(key, reason = reason.as_str(), "Evicting event aggregate");
+            events.remove_aggregate(key);
+            *evictions.entry(reason.as_str().to_string()).or_default() += 1;
+        };
+
+        if let Some(max_age) = self.max_age {
+            let cutoff = chrono::Utc::now().timestamp() - max_age.as_secs() as i64;
+            while let Some((key, _)) = keys.front().filter(|(_, updated)| *updated < cutoff) {
+                evict(events, key, EvictionReason::Age);
+                keys.pop_front();
+            }
+        }
+
+        if let Some(max_keys) = self.max_keys {
+            while keys.len() > max_keys {
+                if let Some((key, _)) = keys.pop_front() {
+                    evict(events, &key, EvictionReason::Keys);
+                }
+            }
+        }
+
+        if let Some(max_bytes) = self.max_bytes {
+            loop {
+                let size = match serde_json::to_vec(events) {
+                    Ok(value) => value.len(),
+                    Err(error) => {
+                        error!(%error, "Failed to compute the size of the event aggregates");
+                        break;
+                    }
+                };
+                if size <= max_bytes {
+                    break;
+                }
+                let Some((key, _)) = keys.pop_front() else {
+                    break;
+                };
+                evict(events, &key, EvictionReason::Bytes);
+            }
+        }
+    }
+}
+
+/// Periodically apply the retention policy to the cached event aggregates, before they get
+/// persisted to the event store.
+pub(crate) async fn garbage_collect(policy: RetentionPolicy, period: Duration) {
+    let mut interval = tokio::time::interval(period);
+    loop {
+        interval.tick().await;
+        let mut cache = Cache::cache_init().lock().expect("not poisoned");
+        cache.garbage_collect(&policy);
+    }
+}
@@ -1,21 +1,28 @@
 use crate::cache::events_cache::{Cache, EventSet};
 use obs::common::constants::{
-    ACTION, CREATED, DELETED, NEXUS_STATS, POOL_STATS, REBUILD_ENDED, REBUILD_STARTED, VOLUME_STATS,
+    ACTION, CATEGORY, CREATED, DELETED, EVENTS_CONSUMER_LAG_STATS, EVENTS_DROPPED_STATS,
+    EVENTS_EVICTED_STATS, EVENTS_SUPPRESSED_STATS, FAILOVER, NAMESPACE, NEXUS_STATS, POOL_STATS,
+    REASON, REBUILD_ENDED, REBUILD_STARTED, RULE, VOLUME_NAMESPACE_STATS, VOLUME_STATS,
 };
 use prometheus::{
     core::{Collector, Desc},
-    CounterVec, Opts,
+    CounterVec, Gauge, Opts,
 };
 use serde::{Deserialize, Serialize};
-use std::{fmt::Debug, ops::DerefMut};
+use std::{collections::BTreeMap, fmt::Debug, ops::DerefMut};
 use tracing::error;
 
 /// StatsCollector contains the list of custom metrics that has to be exposed by exporter.
 #[derive(Clone, Debug)]
 pub struct StatsCollector {
     volumes: CounterVec,
+    volumes_by_namespace: CounterVec,
     pools: CounterVec,
     nexus: CounterVec,
+    suppressed: CounterVec,
+    evicted: CounterVec,
+    dropped: CounterVec,
+    consumer_lag: Gauge,
     descs: Vec<Desc>,
 }
 
@@ -30,7 +37,12 @@ impl Default for StatsCollector {
 pub enum Metrics {
     Pool,
     Volume,
+    VolumeNamespace,
     Nexus,
+    Suppressed,
+    Evicted,
+    Dropped,
+    ConsumerLag,
     Unknown,
 }
 
@@ -39,7 +51,12 @@ impl ToString for Metrics {
         match self {
             Metrics::Pool => "pool".to_string(),
             Metrics::Volume => "volume".to_string(),
+            Metrics::VolumeNamespace => "volume_namespace".to_string(),
             Metrics::Nexus => "nexus".to_string(),
+            Metrics::Suppressed => "events_suppressed".to_string(),
+            Metrics::Evicted => "events_evicted".to_string(),
+            Metrics::Dropped => "events_dropped".to_string(),
+            Metrics::ConsumerLag => "events_consumer_lag_seconds".to_string(),
             Metrics::Unknown => "".to_string(),
         }
     }
@@ -50,26 +67,55 @@ impl StatsCollector {
     pub fn new() -> Self {
         let volume_opts = Opts::new(Metrics::Volume.to_string(), VOLUME_STATS)
             .variable_labels(vec![ACTION.to_string()]);
+        let volume_namespace_opts =
+            Opts::new(Metrics::VolumeNamespace.to_string(), VOLUME_NAMESPACE_STATS)
+                .variable_labels(vec![ACTION.to_string(), NAMESPACE.to_string()]);
         let pool_opts = Opts::new(Metrics::Pool.to_string(), POOL_STATS)
             .variable_labels(vec![ACTION.to_string()]);
         let nexus_opts = Opts::new(Metrics::Nexus.to_string(), NEXUS_STATS)
             .variable_labels(vec![ACTION.to_string()]);
+        let suppressed_opts = Opts::new(Metrics::Suppressed.to_string(), EVENTS_SUPPRESSED_STATS)
+            .variable_labels(vec![CATEGORY.to_string()]);
+        let evicted_opts = Opts::new(Metrics::Evicted.to_string(), EVENTS_EVICTED_STATS)
+            .variable_labels(vec![REASON.to_string()]);
+        let dropped_opts = Opts::new(Metrics::Dropped.to_string(), EVENTS_DROPPED_STATS)
+            .variable_labels(vec![RULE.to_string()]);
         let mut descs = Vec::new();
 
         let volumes = CounterVec::new(volume_opts, &[ACTION])
             .expect("Unable to create counter metric type for volume stats");
+        let volumes_by_namespace = CounterVec::new(volume_namespace_opts, &[ACTION, NAMESPACE])
+            .expect("Unable to create counter metric type for volume namespace stats");
         let pools = CounterVec::new(pool_opts, &[ACTION])
             .expect("Unable to create counter metric type for pool stats");
         let nexus = CounterVec::new(nexus_opts, &[ACTION])
             .expect("Unable to create counter metric type for nexus stats");
+        let suppressed = CounterVec::new(suppressed_opts, &[CATEGORY])
+            .expect("Unable to create counter metric type for suppressed events stats");
+        let evicted = CounterVec::new(evicted_opts, &[REASON])
+            .expect("Unable to create counter metric type for evicted events stats");
+        let dropped = CounterVec::new(dropped_opts, &[RULE])
+            .expect("Unable to create counter metric type for dropped events stats");
+        let consumer_lag = Gauge::new(Metrics::ConsumerLag.to_string(), EVENTS_CONSUMER_LAG_STATS)
+            .expect("Unable to create gauge metric type for events consumer lag");
         descs.extend(volumes.desc().into_iter().cloned());
+        descs.extend(volumes_by_namespace.desc().into_iter().cloned());
         descs.extend(pools.desc().into_iter().cloned());
         descs.extend(nexus.desc().into_iter().cloned());
+        descs.extend(suppressed.desc().into_iter().cloned());
+        descs.extend(evicted.desc().into_iter().cloned());
+        descs.extend(dropped.desc().into_iter().cloned());
+        descs.extend(consumer_lag.desc().into_iter().cloned());
 
         Self {
             volumes,
+            volumes_by_namespace,
             pools,
             nexus,
+            suppressed,
+            evicted,
+            dropped,
+            consumer_lag,
             descs,
         }
     }
@@ -97,6 +143,37 @@ impl StatsCollector {
         metric_family
     }
 
+    fn volume_namespace_metrics(&self, events: &EventSet) -> Vec<prometheus::proto::MetricFamily> {
+        let mut metric_family = Vec::new();
+        for (namespace, volume) in &events.volume_by_namespace {
+            let volumes_created = match self
+                .volumes_by_namespace
+                .get_metric_with_label_values(&[CREATED, namespace])
+            {
+                Ok(volumes) => volumes,
+                Err(error) => {
+                    error!(%error,"Error while creating metrics(volumes created) with label values: {CREATED}, {namespace}");
+                    return metric_family;
+                }
+            };
+            volumes_created.inc_by(volume.volume_created as f64);
+            let volumes_deleted = match self
+                .volumes_by_namespace
+                .get_metric_with_label_values(&[DELETED, namespace])
+            {
+                Ok(volumes) => volumes,
+                Err(error) => {
+                    error!(%error,"Error while creating metrics(volumes deleted) with label values: {DELETED}, {namespace}");
+                    return metric_family;
+                }
+            };
+            volumes_deleted.inc_by(volume.volume_deleted as f64);
+            metric_family.extend(volumes_created.collect());
+            metric_family.extend(volumes_deleted.collect());
+        }
+        metric_family
+    }
+
     fn pool_metrics(&self, events: &EventSet) -> Vec<prometheus::proto::MetricFamily> {
         let mut metric_family = Vec::new();
         let pools_created = match self.pools.get_metric_with_label_values(&[CREATED]) {
@@ -154,10 +231,74 @@ impl StatsCollector {
             }
         };
         rebuild_ended.inc_by(events.nexus.rebuild_ended as f64);
+        let nexus_failovers = match self.nexus.get_metric_with_label_values(&[FAILOVER]) {
+            Ok(nexus) => nexus,
+            Err(error) => {
+                error!(%error,"Error while creating metrics(nexus failovers) with label values: {FAILOVER}");
+                return metric_family;
+            }
+        };
+        nexus_failovers.inc_by(events.nexus.nexus_failovers as f64);
         metric_family.extend(nexus_created.collect());
         metric_family.extend(nexus_deleted.collect());
         metric_family.extend(rebuild_started.collect());
         metric_family.extend(rebuild_ended.collect());
+        metric_family.extend(nexus_failovers.collect());
+        metric_family
+    }
+
+    fn suppressed_metrics(&self, events: &EventSet) -> Vec<prometheus::proto::MetricFamily> {
+        let mut metric_family = Vec::new();
+        for (category, suppressed) in &events.suppressed {
+            let events_suppressed = match self.suppressed.get_metric_with_label_values(&[category])
+            {
+                Ok(events) => events,
+                Err(error) => {
+                    error!(%error,"Error while creating metrics(events suppressed) with label values: {category}");
+                    return metric_family;
+                }
+            };
+            events_suppressed.inc_by(*suppressed as f64);
+            metric_family.extend(events_suppressed.collect());
+        }
+        metric_family
+    }
+
+    fn evicted_metrics(
+        &self,
+        evictions: &BTreeMap<String, u64>,
+    ) -> Vec<prometheus::proto::MetricFamily> {
+        let mut metric_family = Vec::new();
+        for (reason, evicted) in evictions {
+            let events_evicted = match self.evicted.get_metric_with_label_values(&[reason]) {
+                Ok(events) => events,
+                Err(error) => {
+                    error!(%error,"Error while creating metrics(events evicted) with label values: {reason}");
+                    return metric_family;
+                }
+            };
+            events_evicted.inc_by(*evicted as f64);
+            metric_family.extend(events_evicted.collect());
+        }
+        metric_family
+    }
+
+    fn dropped_metrics(
+        &self,
+        dropped: &BTreeMap<String, u64>,
+    ) -> Vec<prometheus::proto::MetricFamily> {
+        let mut metric_family = Vec::new();
+        for (rule, count) in dropped {
+            let events_dropped = match self.dropped.get_metric_with_label_values(&[rule]) {
+                Ok(events) => events,
+                Err(error) => {
+                    error!(%error,"Error while creating metrics(events dropped) with label values: {rule}");
+                    return metric_family;
+                }
+            };
+            events_dropped.inc_by(*count as f64);
+            metric_family.extend(events_dropped.collect());
+        }
         metric_family
     }
 }
@@ -179,8 +320,14 @@ impl Collector for StatsCollector {
         let cp = c.deref_mut();
         let mut metric_family = Vec::new();
         metric_family.extend(self.volume_metrics(cp.data_mut().deref_mut()));
+        metric_family.extend(self.volume_namespace_metrics(cp.data_mut().deref_mut()));
         metric_family.extend(self.pool_metrics(cp.data_mut().deref_mut()));
         metric_family.extend(self.nexus_metrics(cp.data_mut().deref_mut()));
+        metric_family.extend(self.suppressed_metrics(cp.data_mut().deref_mut()));
+        metric_family.extend(self.evicted_metrics(cp.evictions()));
+        metric_family.extend(self.dropped_metrics(cp.dropped()));
+        self.consumer_lag.set(cp.consumer_lag());
+        metric_family.extend(self.consumer_lag.collect());
         metric_family
     }
 }
@@ -1,8 +1,15 @@
 use clap::Parser;
 
 use crate::{
-    cache::events_cache::{Cache, EventSet},
+    cache::{
+        events_cache::{Cache, EventSet},
+        pv_index::PvIndex,
+        rate_limit::{CategoryLimit, RateLimiter},
+        retention::RetentionPolicy,
+    },
     exporter::{events_collector::StatsCollector, exporter_config::ExporterConfig},
+    notify::{email::EmailArgs, k8s_events::K8sEventsArgs, webhook::WebhookArgs},
+    rules::EventRules,
     store::events_store::initialize,
 };
 use actix_web::{http::header, middleware, web, HttpResponse, HttpServer, Responder};
@@ -18,7 +25,7 @@ use obs::common::{
 };
 use prometheus::{Encoder, Registry};
 use snafu::ResultExt;
-use std::net::SocketAddr;
+use std::{net::SocketAddr, path::PathBuf};
 use tracing::{error, info, trace};
 use utils::{
     raw_version_str,
@@ -26,6 +33,8 @@ use utils::{
 };
 mod cache;
 mod exporter;
+mod notify;
+mod rules;
 mod store;
 
 #[derive(Parser, Debug)]
@@ -51,6 +60,49 @@ struct Cli {
     #[clap(short, long, default_value = "300s")]
     update_period: humantime::Duration,
 
+    /// Maximum number of events of a category which are counted per rate limit window, in the
+    /// `<category>=<limit>` format, eg: `nexus=100`. The events over the limit are only counted
+    /// as suppressed. Categories without a limit are not rate limited.
+    #[clap(long, value_delimiter = ',')]
+    event_rate_limit: Vec<CategoryLimit>,
+
+    /// Window of the event rate limits.
+    #[clap(long, default_value = "1s")]
+    event_rate_window: humantime::Duration,
+
+    /// Maximum age of the persisted event aggregates, eg: the volume events of a namespace.
+    /// Aggregates which are not updated within it are evicted.
+    #[clap(long)]
+    events_max_age: Option<humantime::Duration>,
+
+    /// Maximum number of persisted event aggregates. The least recently updated ones are evicted
+    /// beyond it.
+    #[clap(long)]
+    events_max_keys: Option<usize>,
+
+    /// Maximum size in bytes of the persisted event stats. The least recently updated aggregates
+    /// are evicted beyond it.
+    #[clap(long)]
+    events_max_bytes: Option<usize>,
+
+    /// Interval of the garbage collection of the persisted event aggregates.
+    #[clap(long, default_value = "5m")]
+    events_gc_period: humantime::Duration,
+
+    /// Yaml file of the rules which tag, drop or route the events matching their fields, before
+    /// they are rate limited, counted and notified. Eg: to drop the known-noisy events.
+    #[clap(long, value_name = "PATH")]
+    event_rules: Option<PathBuf>,
+
+    #[clap(flatten)]
+    email: EmailArgs,
+
+    #[clap(flatten)]
+    k8s_events: K8sEventsArgs,
+
+    #[clap(flatten)]
+    webhook: WebhookArgs,
+
     /// Sends opentelemetry spans to the Jaeger endpoint agent.
     #[clap(long, short)]
     jaeger: Option<String>,
@@ -108,20 +160,66 @@ async fn main() -> errors::Result<()> {
     initialize_events_cache(init_data).await?;
     info!("event cache initialized successfully!");
 
+    let pv_index = PvIndex::new().await?;
+    info!("persistent volume index initialized successfully!");
+
     initialize_exporter(&args);
     info!("exporter initialized successfully!");
 
+    let event_rules = match &args.event_rules {
+        Some(path) => EventRules::load(path)?,
+        None => EventRules::default(),
+    };
+
+    let rate_limiter = RateLimiter::new(&args.event_rate_limit, args.event_rate_window.into());
+
+    let email_notifier = args.email.start(&args.namespace, &args.release_name)?;
+    if email_notifier.is_some() {
+        info!("email notifications initialized successfully!");
+    }
+
+    let k8s_event_notifier = args.k8s_events.start(pv_index.clone()).await?;
+    if k8s_event_notifier.is_some() {
+        info!("kubernetes events initialized successfully!");
+    }
+
+    let webhook_notifier = args.webhook.start(&args.namespace, &args.release_name)?;
+    if webhook_notifier.is_some() {
+        info!("webhook notifications initialized successfully!");
+    }
+
     // spawn a new task to store the data in cache.
     tokio::spawn(async move {
-        cache::events_cache::store_events(bus_sub)
-            .await
-            .map_err(|error| {
-                error!(%error, "Error while storing the events to cahce");
-                flush_traces();
-                error
-            })
+        cache::events_cache::store_events(
+            bus_sub,
+            pv_index,
+            event_rules,
+            rate_limiter,
+            email_notifier,
+            k8s_event_notifier,
+            webhook_notifier,
+        )
+        .await
+        .map_err(|error| {
+            error!(%error, "Error while storing the events to cahce");
+            flush_traces();
+            error
+        })
     });
 
+    let retention_policy = RetentionPolicy::new(
+        args.events_max_age.map(Into::into),
+        args.events_max_keys,
+        args.events_max_bytes,
+    );
+    if retention_policy.is_enabled() {
+        // spawn a new task to evict the aggregates exceeding the retention policy from cache.
+        tokio::spawn(cache::retention::garbage_collect(
+            retention_policy,
+            args.events_gc_period.into(),
+        ));
+    }
+
     // spawn a new task to update the config map from cache.
     tokio::spawn(async move {
         store::events_store::update_config_map_data(
@@ -163,6 +261,21 @@ pub(crate) fn init_logging(args: &Cli) {
 fn stats_route(cfg: &mut web::ServiceConfig) {
     info!(" configuted at /stats");
     cfg.route("/stats", web::get().to(metrics_handlers));
+    info!(" configuted at /stats/json");
+    cfg.route("/stats/json", web::get().to(json_stats_handler));
+}
+
+/// Serve the aggregated stats as json, so that they can be inspected, eg: by the cluster admins
+/// to see what is being reported, or by their own tooling.
+async fn json_stats_handler() -> impl Responder {
+    let snapshot = match Cache::cache_init().lock() {
+        Ok(mut cache) => cache.snapshot(),
+        Err(error) => {
+            error!(%error, "Error while getting stats cache resource");
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+    HttpResponse::Ok().json(snapshot)
 }
 
 async fn metrics_handlers() -> impl Responder {
@@ -0,0 +1,11 @@
+/// Module for the email notifications of the events.
+pub(crate) mod email;
+
+/// Module for the Kubernetes Events of the volume events.
+pub(crate) mod k8s_events;
+
+/// Module for the webhook notifications of the events, optionally as CloudEvents.
+pub(crate) mod webhook;
+
+/// Module for the templates of the notifications.
+pub(crate) mod template;
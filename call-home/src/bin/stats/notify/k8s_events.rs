@@ -0,0 +1,201 @@
+use crate::cache::pv_index::PvIndex;
+use events_api::event::{EventAction, EventCategory, EventDetails, EventMessage, SwitchOverStatus};
+use k8s_openapi::api::core::v1::ObjectReference;
+use kube::{
+    runtime::events::{Event, EventType, Recorder, Reporter},
+    Client, Resource,
+};
+use obs::common::errors;
+use snafu::ResultExt;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+use tokio::sync::mpsc;
+use tracing::{error, warn};
+
+/// Name of the controller reporting the Kubernetes Events.
+const REPORTING_CONTROLLER: &str = "obs-callhome-stats";
+
+/// Number of volume events which can be queued for the Kubernetes Events, the events beyond it
+/// are dropped.
+const QUEUE_SIZE: usize = 1000;
+
+/// State of the volume, as reported by the state change events, when it is degraded.
+const DEGRADED_STATE: &str = "degraded";
+
+/// Arguments of the Kubernetes Events of the volume events.
+#[derive(clap::Args, Debug)]
+pub(crate) struct K8sEventsArgs {
+    /// Create Kubernetes Events on the PVC, or the PV if it is not bound, of the volumes which
+    /// are degraded, rebuilt or whose target moved, making them visible in
+    /// `kubectl describe pvc`.
+    #[clap(long)]
+    k8s_events: bool,
+}
+
+impl K8sEventsArgs {
+    /// Start creating the Kubernetes Events if enabled, returning the notifier the events are
+    /// passed to.
+    pub(crate) async fn start(
+        &self,
+        pv_index: PvIndex,
+    ) -> errors::Result<Option<K8sEventNotifier>> {
+        if !self.k8s_events {
+            return Ok(None);
+        }
+        let client = Client::try_default().await.context(errors::K8sClient)?;
+        let (sender, receiver) = mpsc::channel(QUEUE_SIZE);
+        let dropped = Arc::new(AtomicUsize::new(0));
+        tokio::spawn(publish_events(receiver, dropped.clone(), client, pv_index));
+        Ok(Some(K8sEventNotifier { sender, dropped }))
+    }
+}
+
+/// Significant events of a volume, which are reported as Kubernetes Events.
+#[derive(Debug)]
+enum VolumeEvent {
+    /// The volume became degraded.
+    Degraded,
+    /// A replica of the volume was rebuilt.
+    Rebuilt { replica: String },
+    /// The target of the volume moved to another node.
+    TargetMoved { path: String },
+}
+
+impl VolumeEvent {
+    /// Get the significant volume event from the event message, if it is one.
+    fn from_message(message: &EventMessage) -> Option<Self> {
+        let details = message
+            .metadata
+            .as_ref()
+            .and_then(|meta| meta.source.as_ref())
+            .and_then(|source| source.event_details.as_ref());
+        match (message.category(), message.action()) {
+            (EventCategory::Volume | EventCategory::Nexus, EventAction::StateChange) => {
+                let state = details?.state_change_details.as_ref()?;
+                state
+                    .next
+                    .eq_ignore_ascii_case(DEGRADED_STATE)
+                    .then_some(Self::Degraded)
+            }
+            (EventCategory::Nexus, EventAction::RebuildEnd) => {
+                let rebuild = details?.rebuild_details.as_ref()?;
+                rebuild.error.is_none().then(|| Self::Rebuilt {
+                    replica: rebuild.destination_replica.clone(),
+                })
+            }
+            (_, EventAction::SwitchOver) => Self::target_moved(details?),
+            _ => None,
+        }
+    }
+
+    /// Get the target moved event from the details of a completed switch over.
+    fn target_moved(details: &EventDetails) -> Option<Self> {
+        let switch_over = details.switch_over_details.as_ref()?;
+        (switch_over.switch_over_status() == SwitchOverStatus::SwitchOverCompleted).then(|| {
+            Self::TargetMoved {
+                path: switch_over.new_path.clone(),
+            }
+        })
+    }
+
+    /// Get the Kubernetes Event of the volume event.
+    fn k8s_event(&self, volume_id: &str, secondary: Option<ObjectReference>) -> Event {
+        let (type_, reason, action, note) = match self {
+            Self::Degraded => (
+                EventType::Warning,
+                "VolumeDegraded",
+                "StateChange",
+                format!("Volume {volume_id} is degraded"),
+            ),
+            Self::Rebuilt { replica } => (
+                EventType::Normal,
+                "VolumeRebuilt",
+                "Rebuild",
+                format!("Replica {replica} of volume {volume_id} was rebuilt"),
+            ),
+            Self::TargetMoved { path } => (
+                EventType::Normal,
+                "VolumeTargetMoved",
+                "SwitchOver",
+                format!("Target of volume {volume_id} moved to {path}"),
+            ),
+        };
+        Event {
+            type_,
+            reason: reason.to_string(),
+            note: Some(note),
+            action: action.to_string(),
+            secondary,
+        }
+    }
+}
+
+/// Queues the significant volume events to be created as Kubernetes Events.
+#[derive(Debug)]
+pub(crate) struct K8sEventNotifier {
+    sender: mpsc::Sender<(String, VolumeEvent)>,
+    /// Number of events which did not fit in the queue.
+    dropped: Arc<AtomicUsize>,
+}
+
+impl K8sEventNotifier {
+    /// Queue the event to be created as a Kubernetes Event, if it is a significant volume event.
+    pub(crate) fn notify(&self, message: &EventMessage) {
+        let Some(event) = VolumeEvent::from_message(message) else {
+            return;
+        };
+        if self
+            .sender
+            .try_send((message.target.clone(), event))
+            .is_err()
+        {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Create the Kubernetes Events of the queued volume events. The events are created on the PVC
+/// bound to the PV of the volume, referring to the PV, or on the PV if it is not bound.
+async fn publish_events(
+    mut receiver: mpsc::Receiver<(String, VolumeEvent)>,
+    dropped: Arc<AtomicUsize>,
+    client: Client,
+    pv_index: PvIndex,
+) {
+    let reporter = Reporter {
+        controller: REPORTING_CONTROLLER.to_string(),
+        instance: std::env::var("HOSTNAME").ok(),
+    };
+    while let Some((volume_id, event)) = receiver.recv().await {
+        let dropped = dropped.swap(0, Ordering::Relaxed);
+        if dropped > 0 {
+            warn!(
+                dropped,
+                "Events were dropped from the full Kubernetes Events queue"
+            );
+        }
+        let Some(pv) = pv_index.persistent_volume(&volume_id) else {
+            warn!(
+                volume_id,
+                ?event,
+                "No PV found for the volume, skipping its event"
+            );
+            continue;
+        };
+        let pv_reference = pv.object_ref(&());
+        let claim = pv.spec.as_ref().and_then(|spec| spec.claim_ref.clone());
+        let (reference, secondary) = match claim {
+            Some(claim) => (claim, Some(pv_reference)),
+            None => (pv_reference, None),
+        };
+        let recorder = Recorder::new(client.clone(), reporter.clone(), reference);
+        if let Err(error) = recorder
+            .publish(event.k8s_event(&volume_id, secondary))
+            .await
+        {
+            error!(%error, volume_id, ?event, "Failed to create the Kubernetes Event");
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use obs::common::errors;
+use std::collections::HashMap;
+
+/// Template of a notification, in which the `{{name}}` placeholders are replaced with the
+/// values of the notified events.
+#[derive(Debug, Clone)]
+pub(crate) struct Template {
+    segments: Vec<Segment>,
+}
+
+/// Literal text or placeholder of a template.
+#[derive(Debug, Clone)]
+enum Segment {
+    Text(String),
+    Placeholder(String),
+}
+
+impl Template {
+    /// Parse the template, which may only use the given placeholders.
+    pub(crate) fn parse(template: &str, placeholders: &[&str]) -> errors::Result<Self> {
+        let invalid = |reason: String| {
+            errors::InvalidTemplate {
+                template: template.to_string(),
+                reason,
+            }
+            .build()
+        };
+        let mut segments = Vec::new();
+        let mut rest = template;
+        while let Some(start) = rest.find("{{") {
+            let (text, tail) = rest.split_at(start);
+            let end = tail
+                .find("}}")
+                .ok_or_else(|| invalid("unterminated placeholder".to_string()))?;
+            let name = tail[2..end].trim();
+            if !placeholders.contains(&name) {
+                return Err(invalid(format!(
+                    "unknown placeholder '{name}', expected one of: {}",
+                    placeholders.join(", ")
+                )));
+            }
+            if !text.is_empty() {
+                segments.push(Segment::Text(text.to_string()));
+            }
+            segments.push(Segment::Placeholder(name.to_string()));
+            rest = &tail[end + 2..];
+        }
+        if !rest.is_empty() {
+            segments.push(Segment::Text(rest.to_string()));
+        }
+        Ok(Self { segments })
+    }
+
+    /// Render the template with the values of its placeholders, the missing ones are left
+    /// empty.
+    pub(crate) fn render(&self, values: &HashMap<&str, String>) -> String {
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Text(text) => text.as_str(),
+                Segment::Placeholder(name) => values.get(name.as_str()).map_or("", String::as_str),
+            })
+            .collect()
+    }
+}
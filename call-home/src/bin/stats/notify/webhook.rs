@@ -0,0 +1,244 @@
+use crate::{
+    cache::events_cache::{action_name, category_name},
+    notify::template::Template,
+};
+use events_api::event::EventMessage;
+use obs::common::errors;
+use serde::Serialize;
+use snafu::ResultExt;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::sync::mpsc;
+use tracing::{error, warn};
+
+/// Default template of the source of the CloudEvents.
+const DEFAULT_SOURCE_TEMPLATE: &str = "/namespaces/{{namespace}}/releases/{{release}}";
+
+/// Default template of the type of the CloudEvents.
+const DEFAULT_TYPE_TEMPLATE: &str = "io.openebs.mayastor.{{category}}.{{action}}";
+
+/// Placeholders of the source and type templates.
+const CLOUDEVENT_PLACEHOLDERS: &[&str] = &[
+    "release",
+    "namespace",
+    "category",
+    "action",
+    "component",
+    "node",
+];
+
+/// Version of the CloudEvents specification of the envelopes.
+const CLOUDEVENTS_SPEC_VERSION: &str = "1.0";
+
+/// Content type of the events in the structured mode of the CloudEvents http binding.
+const CLOUDEVENTS_CONTENT_TYPE: &str = "application/cloudevents+json";
+
+/// Number of events which can be queued for the webhook, the events beyond it are dropped.
+const QUEUE_SIZE: usize = 1000;
+
+/// Timeout of each request to the webhook.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Format of the events posted to the webhook.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum WebhookFormat {
+    /// The event along with its tags, as json.
+    Native,
+    /// The event along with its tags, as the data of a CloudEvents 1.0 envelope in the
+    /// structured json mode.
+    Cloudevents,
+}
+
+/// Arguments of the webhook notifications of the events.
+#[derive(clap::Args, Debug)]
+pub(crate) struct WebhookArgs {
+    /// Url the events are posted to, one request per event. The events are only posted when it
+    /// is set.
+    #[clap(long, env = "WEBHOOK_URL")]
+    webhook_url: Option<url::Url>,
+
+    /// Format of the events posted to the webhook.
+    #[clap(long, value_enum, default_value_t = WebhookFormat::Native)]
+    webhook_format: WebhookFormat,
+
+    /// Template of the source attribute of the CloudEvents, with the placeholders: release,
+    /// namespace, category, action, component and node.
+    #[clap(long, default_value = DEFAULT_SOURCE_TEMPLATE)]
+    cloudevents_source_template: String,
+
+    /// Template of the type attribute of the CloudEvents, with the placeholders: release,
+    /// namespace, category, action, component and node.
+    #[clap(long, default_value = DEFAULT_TYPE_TEMPLATE)]
+    cloudevents_type_template: String,
+}
+
+impl WebhookArgs {
+    /// Start posting the events to the webhook if its url is set, returning the notifier the
+    /// events are passed to.
+    pub(crate) fn start(
+        &self,
+        namespace: &str,
+        release_name: &str,
+    ) -> errors::Result<Option<WebhookNotifier>> {
+        let Some(url) = &self.webhook_url else {
+            return Ok(None);
+        };
+        let poster = Poster {
+            client: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .context(errors::WebhookClient)?,
+            url: url.clone(),
+            format: self.webhook_format,
+            source: Template::parse(&self.cloudevents_source_template, CLOUDEVENT_PLACEHOLDERS)?,
+            type_: Template::parse(&self.cloudevents_type_template, CLOUDEVENT_PLACEHOLDERS)?,
+            namespace: namespace.to_string(),
+            release_name: release_name.to_string(),
+        };
+
+        let (sender, receiver) = mpsc::channel(QUEUE_SIZE);
+        let dropped = Arc::new(AtomicUsize::new(0));
+        tokio::spawn(post_events(receiver, dropped.clone(), poster));
+        Ok(Some(WebhookNotifier { sender, dropped }))
+    }
+}
+
+/// Event as posted to the webhook.
+#[derive(Serialize, Debug)]
+struct NotifiedEvent {
+    event: EventMessage,
+    /// Tags added by the event rules.
+    tags: BTreeMap<String, String>,
+}
+
+/// Envelope of an event, as per the CloudEvents 1.0 specification.
+#[derive(Serialize, Debug)]
+struct CloudEvent<'a> {
+    specversion: &'static str,
+    id: String,
+    source: String,
+    #[serde(rename = "type")]
+    type_: String,
+    subject: &'a str,
+    time: String,
+    datacontenttype: &'static str,
+    data: &'a NotifiedEvent,
+}
+
+/// Queues the events to be posted to the webhook.
+#[derive(Debug)]
+pub(crate) struct WebhookNotifier {
+    sender: mpsc::Sender<NotifiedEvent>,
+    /// Number of events which did not fit in the queue.
+    dropped: Arc<AtomicUsize>,
+}
+
+impl WebhookNotifier {
+    /// Queue the event to be posted along with its tags.
+    pub(crate) fn notify(&self, message: &EventMessage, tags: &BTreeMap<String, String>) {
+        let event = NotifiedEvent {
+            event: message.clone(),
+            tags: tags.clone(),
+        };
+        if self.sender.try_send(event).is_err() {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Posts the events to the webhook.
+struct Poster {
+    client: reqwest::Client,
+    url: url::Url,
+    format: WebhookFormat,
+    source: Template,
+    type_: Template,
+    namespace: String,
+    release_name: String,
+}
+
+impl Poster {
+    /// Post the event in the configured format.
+    async fn post(&self, event: &NotifiedEvent) {
+        let (content_type, body) = match self.format {
+            WebhookFormat::Native => ("application/json", serde_json::to_vec(event)),
+            WebhookFormat::Cloudevents => (
+                CLOUDEVENTS_CONTENT_TYPE,
+                serde_json::to_vec(&self.cloud_event(event)),
+            ),
+        };
+        let body = match body {
+            Ok(body) => body,
+            Err(error) => {
+                error!(%error, "Failed to serialize the event for the webhook");
+                return;
+            }
+        };
+        let response = self
+            .client
+            .post(self.url.clone())
+            .header(reqwest::header::CONTENT_TYPE, content_type)
+            .body(body)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        if let Err(error) = response {
+            error!(%error, "Failed to post the event to the webhook");
+        }
+    }
+
+    /// Wrap the event in a CloudEvents envelope, whose source and type are rendered from the
+    /// templates. The id of the event is kept when it has one, so that the consumers can
+    /// deduplicate the redelivered events.
+    fn cloud_event<'a>(&self, event: &'a NotifiedEvent) -> CloudEvent<'a> {
+        let message = &event.event;
+        let meta = message.metadata.as_ref();
+        let source = meta.and_then(|meta| meta.source.as_ref());
+        let values = HashMap::from([
+            ("release", self.release_name.clone()),
+            ("namespace", self.namespace.clone()),
+            ("category", category_name(message.category())),
+            ("action", action_name(message.action())),
+            (
+                "component",
+                source.map(|s| s.component.clone()).unwrap_or_default(),
+            ),
+            ("node", source.map(|s| s.node.clone()).unwrap_or_default()),
+        ]);
+        let id = meta
+            .map(|meta| meta.id.clone())
+            .filter(|id| !id.is_empty())
+            .unwrap_or_else(|| format!("{:032x}", rand::random::<u128>()));
+        CloudEvent {
+            specversion: CLOUDEVENTS_SPEC_VERSION,
+            id,
+            source: self.source.render(&values),
+            type_: self.type_.render(&values),
+            subject: &message.target,
+            time: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            datacontenttype: "application/json",
+            data: event,
+        }
+    }
+}
+
+/// Post the queued events to the webhook, one at a time.
+async fn post_events(
+    mut receiver: mpsc::Receiver<NotifiedEvent>,
+    dropped: Arc<AtomicUsize>,
+    poster: Poster,
+) {
+    while let Some(event) = receiver.recv().await {
+        let dropped = dropped.swap(0, Ordering::Relaxed);
+        if dropped > 0 {
+            warn!(dropped, "Events were dropped from the full webhook queue");
+        }
+        poster.post(&event).await;
+    }
+}
@@ -0,0 +1,195 @@
+use crate::cache::events_cache::{action_name, category_name};
+use events_api::event::EventMessage;
+use obs::common::errors;
+use serde::Deserialize;
+use snafu::ResultExt;
+use std::{collections::BTreeMap, path::Path};
+
+/// Rules applied to the events before they are counted and notified, as read from a yaml file,
+/// eg:
+///
+/// ```yaml
+/// rules:
+///   - name: ignore-test-volumes
+///     match:
+///       category: volume
+///       target: "test-*"
+///     drop: true
+///   - name: page-on-deletes
+///     match:
+///       category: volume
+///       action: delete
+///     tag:
+///       severity: critical
+///     route: [stats, email]
+/// ```
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct EventRules {
+    rules: Vec<EventRule>,
+}
+
+/// A rule which tags, drops or routes the events matching all its fields.
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct EventRule {
+    /// Name of the rule, with which the dropped events are counted.
+    name: String,
+    /// Fields the events must match, all the events match when it is empty.
+    #[serde(default, rename = "match")]
+    matcher: EventMatcher,
+    /// Drop the events, they are neither counted nor notified.
+    #[serde(default)]
+    drop: bool,
+    /// Tags added to the events, listed in the email notifications.
+    #[serde(default)]
+    tag: BTreeMap<String, String>,
+    /// Sinks the events are passed to, all of them when it is not set.
+    route: Option<Vec<Sink>>,
+}
+
+/// Fields of the events, each of them may contain `*` wildcards. The category and the action are
+/// matched case-insensitively.
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+struct EventMatcher {
+    category: Option<String>,
+    action: Option<String>,
+    target: Option<String>,
+    component: Option<String>,
+    node: Option<String>,
+}
+
+/// Sink the events are passed to.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Sink {
+    /// The event stats.
+    Stats,
+    /// The email notifications.
+    Email,
+    /// The Kubernetes Events.
+    K8sEvents,
+    /// The webhook notifications.
+    Webhook,
+}
+
+/// Outcome of the rules for an event.
+#[derive(Debug, Default)]
+pub(crate) struct Verdict {
+    /// Name of the rule which dropped the event, if it was dropped.
+    pub(crate) dropped_by: Option<String>,
+    /// Tags added to the event.
+    pub(crate) tags: BTreeMap<String, String>,
+    route: Option<Vec<Sink>>,
+}
+
+impl Verdict {
+    /// Whether the event is passed to the sink.
+    pub(crate) fn routes_to(&self, sink: Sink) -> bool {
+        self.route
+            .as_ref()
+            .map_or(true, |route| route.contains(&sink))
+    }
+}
+
+impl EventRules {
+    /// Read the rules from the yaml file.
+    pub(crate) fn load(path: &Path) -> errors::Result<Self> {
+        let file = std::fs::read_to_string(path).context(errors::ReadEventRules {
+            path: path.display().to_string(),
+        })?;
+        serde_yaml::from_str(&file).context(errors::ParseEventRules {
+            path: path.display().to_string(),
+        })
+    }
+
+    /// Apply the matching rules to the event, in order. The tags of all the matching rules are
+    /// added, the route of the last matching rule which has one is used, and the first matching
+    /// rule which drops the event stops the evaluation.
+    pub(crate) fn apply(&self, message: &EventMessage) -> Verdict {
+        let mut verdict = Verdict::default();
+        if self.rules.is_empty() {
+            return verdict;
+        }
+        let fields = EventFields::new(message);
+        for rule in self
+            .rules
+            .iter()
+            .filter(|rule| rule.matcher.matches(&fields))
+        {
+            if rule.drop {
+                verdict.dropped_by = Some(rule.name.clone());
+                break;
+            }
+            verdict.tags.extend(rule.tag.clone());
+            if rule.route.is_some() {
+                verdict.route = rule.route.clone();
+            }
+        }
+        verdict
+    }
+}
+
+/// Fields of an event the rules match on.
+struct EventFields {
+    category: String,
+    action: String,
+    target: String,
+    component: String,
+    node: String,
+}
+
+impl EventFields {
+    fn new(message: &EventMessage) -> Self {
+        let source = message
+            .metadata
+            .as_ref()
+            .and_then(|meta| meta.source.as_ref());
+        Self {
+            category: category_name(message.category()),
+            action: action_name(message.action()),
+            target: message.target.clone(),
+            component: source
+                .map(|source| source.component.clone())
+                .unwrap_or_default(),
+            node: source.map(|source| source.node.clone()).unwrap_or_default(),
+        }
+    }
+}
+
+impl EventMatcher {
+    fn matches(&self, fields: &EventFields) -> bool {
+        let lowercase = |pattern: &Option<String>| pattern.as_ref().map(|p| p.to_lowercase());
+        [
+            (lowercase(&self.category), &fields.category),
+            (lowercase(&self.action), &fields.action),
+            (self.target.clone(), &fields.target),
+            (self.component.clone(), &fields.component),
+            (self.node.clone(), &fields.node),
+        ]
+        .into_iter()
+        .all(|(pattern, value)| pattern.map_or(true, |pattern| wildcard_match(&pattern, value)))
+    }
+}
+
+/// Whether the value matches the pattern, in which `*` matches any sequence of characters.
+fn wildcard_match(pattern: &str, value: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = value.strip_prefix(first) else {
+        return false;
+    };
+    let parts = parts.collect::<Vec<_>>();
+    let Some((last, middle)) = parts.split_last() else {
+        // The pattern has no wildcard.
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len() ..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
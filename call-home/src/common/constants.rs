@@ -34,9 +34,41 @@ pub const POOL_STATS: &str = "Pool stats";
 /// Defines the help argument for nexus stats need for promethueus library.
 pub const NEXUS_STATS: &str = "Nexus stats";
 
+/// Defines the help argument for volume stats per namespace need for promethueus library.
+pub const VOLUME_NAMESPACE_STATS: &str = "Volume stats per PVC namespace";
+
+/// Defines the help argument for the suppressed events stats need for promethueus library.
+pub const EVENTS_SUPPRESSED_STATS: &str = "Events suppressed by the rate limiter, per category";
+
+/// Defines the help argument for the evicted event aggregates stats need for promethueus library.
+pub const EVENTS_EVICTED_STATS: &str =
+    "Persisted event aggregates evicted by the retention policy, per reason";
+
+/// Defines the help argument for the dropped events stats need for promethueus library.
+pub const EVENTS_DROPPED_STATS: &str = "Events dropped by the event rules, per rule";
+
+/// Defines the help argument for the events consumer lag need for promethueus library.
+pub const EVENTS_CONSUMER_LAG_STATS: &str =
+    "Seconds between the emission and the consumption of the last consumed event";
+
 /// Variable label for promethueus library.
 pub const ACTION: &str = "action";
 
+/// Namespace variable label for promethueus library.
+pub const NAMESPACE: &str = "namespace";
+
+/// Category variable label for promethueus library.
+pub const CATEGORY: &str = "category";
+
+/// Reason variable label for promethueus library.
+pub const REASON: &str = "reason";
+
+/// Rule variable label for promethueus library.
+pub const RULE: &str = "rule";
+
+/// Namespace of the volume events which could not be attributed to a PVC.
+pub const UNKNOWN_NAMESPACE: &str = "unknown";
+
 /// Create action for events.
 pub const CREATED: &str = "created";
 
@@ -49,6 +81,9 @@ pub const REBUILD_STARTED: &str = "rebuild_started";
 /// rebuild ended action for events.
 pub const REBUILD_ENDED: &str = "rebuild_ended";
 
+/// Failover action for events, i.e. a completed switch over of a volume target.
+pub const FAILOVER: &str = "failover";
+
 /// Label for Volume.
 pub const VOLUME: &str = "volume";
 
@@ -111,21 +146,38 @@ pub fn key_filepath() -> PathBuf {
     }
 }
 
-/// RECEIVER_API_ENDPOINT is the URL to anonymous call-home metrics collection endpoint.
-pub const RECEIVER_ENDPOINT: &str = "https://openebs.phonehome.datacore.com/openebs/report";
+/// DEFAULT_ENCRYPTION_RECIPIENT is the recipient of the encryption key the reports are encrypted
+/// for, unless it's overridden.
+pub const DEFAULT_ENCRYPTION_RECIPIENT: &str = "openebs-phonehome@datacore.com";
 
-/// CALL_HOME_FREQUENCY_IN_HOURS is the frequency of call-home metrics transmission, in hours.
-/// The function call_home_frequency() returns the frequency as an std::time::Duration.
+/// Name of the ConfigMap the identity of the cluster is persisted in.
+pub const CLUSTER_IDENTITY_CONFIG_MAP: &str = "callhome-cluster-identity";
 
-const CALL_HOME_FREQUENCY_IN_HOURS: i64 = 24;
+/// RECEIVER_API_ENDPOINT is the URL to anonymous call-home metrics collection endpoint, unless
+/// it's overridden.
+pub const RECEIVER_ENDPOINT: &str = "https://openebs.phonehome.datacore.com/openebs/report";
 
-pub fn call_home_frequency() -> std::time::Duration {
-    chrono::Duration::hours(CALL_HOME_FREQUENCY_IN_HOURS)
-        .to_std()
-        .map_err(|error| {
-            anyhow::anyhow!("failed to parse call-home frequency duration: {:?}", error)
-        })
-        .unwrap()
+/// DEFAULT_CALL_HOME_INTERVAL is the default interval between the call-home metrics transmissions.
+pub const DEFAULT_CALL_HOME_INTERVAL: &str = "24h";
+
+/// MIN_CALL_HOME_INTERVAL is the shortest interval between the call-home metrics transmissions
+/// which is allowed, so that the receiver isn't flooded with reports.
+pub const MIN_CALL_HOME_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// Parses the interval between the call-home metrics transmissions, which can't be shorter than
+/// MIN_CALL_HOME_INTERVAL.
+pub fn parse_call_home_interval(value: &str) -> Result<std::time::Duration, String> {
+    let interval: std::time::Duration = value
+        .parse::<humantime::Duration>()
+        .map_err(|error| error.to_string())?
+        .into();
+    if interval < MIN_CALL_HOME_INTERVAL {
+        return Err(format!(
+            "the call-home interval can't be shorter than {}",
+            humantime::format_duration(MIN_CALL_HOME_INTERVAL)
+        ));
+    }
+    Ok(interval)
 }
 
 /// Returns the git tag version (if tag is found) or simply returns the commit hash (12 characters).
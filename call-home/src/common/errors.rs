@@ -35,6 +35,15 @@ pub enum ReceiverError {
 
     #[snafu(display("HTTP client (with middleware) error: {}", source))]
     HttpClientWithMiddlewareError { source: reqwest_middleware::Error },
+
+    #[snafu(display("failed to read CA bundle '{}': {}", path.display(), source))]
+    CaBundleReadError {
+        source: std::io::Error,
+        path: std::path::PathBuf,
+    },
+
+    #[snafu(display("no certificate found in CA bundle '{}'", path.display()))]
+    CaBundleEmptyError { path: std::path::PathBuf },
 }
 
 impl From<reqwest::Error> for ReceiverError {
@@ -60,6 +69,12 @@ pub enum EncryptError {
 
     #[snafu(display("file io error: {}", source))]
     IoError { source: std::io::Error },
+
+    #[snafu(display("gpg failed with {}: {}", status, stderr))]
+    GpgError {
+        status: std::process::ExitStatus,
+        stderr: String,
+    },
 }
 
 impl From<serde_json::Error> for EncryptError {
@@ -74,6 +89,31 @@ impl From<std::io::Error> for EncryptError {
     }
 }
 
+/// OfflineReportError is a custom error enum which is returned when writing a report
+/// locally with crate::transmitter::offline::OfflineOutput.
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub), context(suffix(false)))]
+#[allow(clippy::enum_variant_names)]
+pub enum OfflineReportError {
+    #[snafu(display("error during JSON marshalling: {}", source))]
+    SerdeSerializeError { source: serde_json::Error },
+
+    #[snafu(display("file io error: {}", source))]
+    IoError { source: std::io::Error },
+}
+
+impl From<serde_json::Error> for OfflineReportError {
+    fn from(source: serde_json::Error) -> Self {
+        Self::SerdeSerializeError { source }
+    }
+}
+
+impl From<std::io::Error> for OfflineReportError {
+    fn from(source: std::io::Error) -> Self {
+        Self::IoError { source }
+    }
+}
+
 /// A wrapper type to remove repeated Result<T, Error> returns.
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
@@ -189,4 +229,39 @@ pub enum Error {
     /// Error while getting the response body.
     #[snafu(display("Error while getting the response body"))]
     GetRsponseBodyFailure,
+
+    /// Error for an invalid notification template.
+    #[snafu(display("Invalid template '{}': {}", template, reason))]
+    InvalidTemplate { template: String, reason: String },
+
+    /// Error building the http client of the webhook.
+    #[snafu(display("Failed to build the webhook client: {}", source))]
+    WebhookClient { source: reqwest::Error },
+
+    /// Error for an invalid SMTP server url.
+    #[snafu(display("Invalid SMTP server url: {}", source))]
+    SmtpTransport {
+        source: lettre::transport::smtp::Error,
+    },
+
+    /// Error reading the event rules file.
+    #[snafu(display("Failed to read the event rules file {}: {}", path, source))]
+    ReadEventRules {
+        path: String,
+        source: std::io::Error,
+    },
+
+    /// Error parsing the event rules file.
+    #[snafu(display("Invalid event rules file {}: {}", path, source))]
+    ParseEventRules {
+        path: String,
+        source: serde_yaml::Error,
+    },
+
+    /// Error for an invalid email address.
+    #[snafu(display("Invalid email address '{}': {}", address, source))]
+    InvalidEmailAddress {
+        address: String,
+        source: lettre::address::AddressError,
+    },
 }
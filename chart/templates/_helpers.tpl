@@ -13,6 +13,20 @@ Usage:
     {{- end }}
 {{- end -}}
 
+{{/*
+Renders the image of a product component, or its override from image.overrides, if any
+Usage:
+{{ include "component_image" (dict "component" "agent-core" "repoTag" .Values.image.repoTags.controlPlane "context" $) }}
+*/}}
+{{- define "component_image" -}}
+    {{- $image := .context.Values.image }}
+    {{- with index ($image.overrides | default dict) .component }}
+        {{- . }}
+    {{- else }}
+        {{- printf "%s/%s/%s-%s:%s" $image.registry $image.repo .context.Chart.Name .component (default $image.tag .repoTag) }}
+    {{- end }}
+{{- end -}}
+
 {{/*
 Renders the CORE server init container, if enabled
 Usage:
@@ -0,0 +1,56 @@
+use crate::ApiVersion;
+
+/// Error of the io-engine gRPC client.
+#[derive(Debug)]
+pub enum Error {
+    /// The connection to the io-engine of the node timed out.
+    ConnectTimeout { node: String },
+    /// The connection to the io-engine of the node failed.
+    Connect {
+        node: String,
+        source: tonic::transport::Error,
+    },
+    /// None of the api versions of the client is served by the io-engine of the node.
+    NoSupportedApiVersion { node: String },
+    /// The client isn't connected with the api version.
+    UnsupportedApiVersion { api_version: ApiVersion },
+    /// The gRPC call to the io-engine failed.
+    Response {
+        call: &'static str,
+        source: tonic::Status,
+    },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::ConnectTimeout { node } => {
+                write!(f, "Timed out connecting to the io-engine of node {node}")
+            }
+            Error::Connect { node, source } => {
+                write!(
+                    f,
+                    "Failed to connect to the io-engine of node {node}: {source}"
+                )
+            }
+            Error::NoSupportedApiVersion { node } => write!(
+                f,
+                "None of the api versions is served by the io-engine of node {node}"
+            ),
+            Error::UnsupportedApiVersion { api_version } => {
+                write!(f, "Could not get {} client", api_version.as_ref())
+            }
+            Error::Response { call, source } => write!(f, "gRPC call {call} failed: {source}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Connect { source, .. } => Some(source),
+            Error::Response { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use crate::{ApiVersion, Error};
+use rpc::io_engine::IoEngineClientV0;
+
+use std::time::Duration;
+use tonic::{
+    transport::{Channel, Endpoint, Uri},
+    Code,
+};
+
+/// Timeout for gRPC.
+#[derive(Debug, Clone)]
+pub struct Timeouts {
+    connect: Duration,
+    request: Duration,
+}
+
+impl Timeouts {
+    /// Return a new `Self` with the connect and request timeouts.
+    pub fn new(connect: Duration, request: Duration) -> Self {
+        Self { connect, request }
+    }
+    /// Timeout to establish connection to the node.
+    pub fn connect(&self) -> Duration {
+        self.connect
+    }
+    /// Timeout for the request itself.
+    pub fn request(&self) -> Duration {
+        self.request
+    }
+}
+
+/// Context for Grpc client.
+#[derive(Debug, Clone)]
+pub struct GrpcContext {
+    node_name: String,
+    endpoint: Endpoint,
+    timeouts: Timeouts,
+    api_versions: Vec<ApiVersion>,
+}
+
+impl GrpcContext {
+    /// initialize context, with the api versions the io-engine may be dialed with, all of them
+    /// if none are given
+    pub fn new(
+        node_name: String,
+        endpoint: Uri,
+        timeouts: Timeouts,
+        api_versions: Vec<ApiVersion>,
+    ) -> Self {
+        let endpoint = Endpoint::from(endpoint)
+            .connect_timeout(timeouts.connect())
+            .timeout(timeouts.request());
+        Self {
+            node_name,
+            endpoint,
+            timeouts,
+            api_versions,
+        }
+    }
+
+    /// Get the name of the node the io-engine is running on.
+    pub fn node_name(&self) -> &str {
+        &self.node_name
+    }
+}
+
+/// The V0 Mayastor client.
+pub type MayaClientV0 = IoEngineClientV0<Channel>;
+
+/// The V1 PoolClient.
+type PoolClient = rpc::v1::pool::pool_rpc_client::PoolRpcClient<Channel>;
+/// The V1 ReplicaClient.
+type ReplicaClient = rpc::v1::replica::replica_rpc_client::ReplicaRpcClient<Channel>;
+/// The V1 NexusClient.
+type NexusClient = rpc::v1::nexus::nexus_rpc_client::NexusRpcClient<Channel>;
+/// The V1 SnapshotClient.
+type SnapshotClient = rpc::v1::snapshot::snapshot_rpc_client::SnapshotRpcClient<Channel>;
+/// The V1 JsonClient.
+type JsonClient = rpc::v1::json::json_rpc_client::JsonRpcClient<Channel>;
+/// The V1 HostClient.
+type HostClient = rpc::v1::host::host_rpc_client::HostRpcClient<Channel>;
+
+/// A wrapper for client for the V1 dataplane interface.
+#[derive(Clone, Debug)]
+pub struct MayaClientV1 {
+    pub pool: PoolClient,
+    pub replica: ReplicaClient,
+    pub nexus: NexusClient,
+    pub snapshot: SnapshotClient,
+    pub json: JsonClient,
+    pub host: HostClient,
+}
+
+impl MayaClientV1 {
+    /// Create the V1 clients over the channel.
+    fn new(channel: Channel) -> Self {
+        Self {
+            pool: PoolClient::new(channel.clone()),
+            replica: ReplicaClient::new(channel.clone()),
+            nexus: NexusClient::new(channel.clone()),
+            snapshot: SnapshotClient::new(channel.clone()),
+            json: JsonClient::new(channel.clone()),
+            host: HostClient::new(channel),
+        }
+    }
+}
+
+/// Dataplane grpc client. It holds the client of every api version which the io-engine serves,
+/// eg: both while the io-engine is upgraded from the v0 to the v1 api, the calls common to both
+/// being made with the latest one.
+#[derive(Debug, Clone)]
+pub struct GrpcClient {
+    node_name: String,
+    api_version: ApiVersion,
+    api_versions: Vec<ApiVersion>,
+    served_api_versions: Vec<ApiVersion>,
+    channel: Channel,
+    v0_client: Option<MayaClientV0>,
+    v1_client: Option<MayaClientV1>,
+}
+
+impl GrpcClient {
+    /// Connect to the io-engine of the context, with the api versions of the context which it
+    /// serves. A single attempt is made, retrying is left to the caller.
+    pub async fn connect(context: &GrpcContext) -> Result<Self, Error> {
+        let channel = tokio::time::timeout(context.timeouts.connect(), context.endpoint.connect())
+            .await
+            .map_err(|_| Error::ConnectTimeout {
+                node: context.node_name.clone(),
+            })?
+            .map_err(|source| Error::Connect {
+                node: context.node_name.clone(),
+                source,
+            })?;
+        let mut client = Self::from_channel(context.node_name.clone(), ApiVersion::V1, channel);
+        client.api_versions = context.api_versions.clone();
+        client.detect_api_version().await?;
+        Ok(client)
+    }
+
+    /// Create the client of the api version over an already established channel, eg: one
+    /// which is port-forwarded to the io-engine pod.
+    pub fn from_channel(node_name: String, api_version: ApiVersion, channel: Channel) -> Self {
+        let mut client = Self {
+            node_name,
+            api_version: api_version.clone(),
+            api_versions: vec![api_version.clone()],
+            served_api_versions: vec![],
+            channel,
+            v0_client: None,
+            v1_client: None,
+        };
+        client.use_api_versions(vec![api_version]);
+        client
+    }
+
+    /// Detect the api versions served by the io-engine, eg: again after it was upgraded, and
+    /// return the latest one. Each api version of the client is probed, those which the
+    /// io-engine doesn't implement are left out. On error the client is left with the api
+    /// versions probed so far.
+    pub async fn detect_api_version(&mut self) -> Result<ApiVersion, Error> {
+        let mut api_versions = match self.api_versions.as_slice() {
+            [] => vec![ApiVersion::V0, ApiVersion::V1],
+            api_versions => api_versions.to_vec(),
+        };
+        api_versions.sort_by(|a, b| b.cmp(a));
+        api_versions.dedup();
+        let mut served = Vec::new();
+        for api_version in api_versions {
+            self.use_api_versions(vec![api_version.clone()]);
+            match self.list_pools().await {
+                Ok(_) => served.push(api_version),
+                Err(Error::Response { source, .. }) if source.code() == Code::Unimplemented => {}
+                Err(error) => return Err(error),
+            }
+        }
+        if served.is_empty() {
+            return Err(Error::NoSupportedApiVersion {
+                node: self.node_name.clone(),
+            });
+        }
+        self.use_api_versions(served);
+        Ok(self.api_version())
+    }
+
+    /// Use the api versions, sorted from the latest one, for the calls to the io-engine.
+    fn use_api_versions(&mut self, api_versions: Vec<ApiVersion>) {
+        let channel = &self.channel;
+        self.v0_client = api_versions
+            .contains(&ApiVersion::V0)
+            .then(|| MayaClientV0::new(channel.clone()));
+        self.v1_client = api_versions
+            .contains(&ApiVersion::V1)
+            .then(|| MayaClientV1::new(channel.clone()));
+        if let Some(api_version) = api_versions.first() {
+            self.api_version = api_version.clone();
+        }
+        self.served_api_versions = api_versions;
+    }
+
+    /// Get the v0 api client.
+    pub fn client_v0(&self) -> Result<MayaClientV0, Error> {
+        self.v0_client.clone().ok_or(Error::UnsupportedApiVersion {
+            api_version: ApiVersion::V0,
+        })
+    }
+
+    /// Get the v1 api client.
+    pub fn client_v1(&self) -> Result<MayaClientV1, Error> {
+        self.v1_client.clone().ok_or(Error::UnsupportedApiVersion {
+            api_version: ApiVersion::V1,
+        })
+    }
+
+    /// Get the api version, the latest one which the io-engine serves.
+    pub fn api_version(&self) -> ApiVersion {
+        self.api_version.clone()
+    }
+
+    /// Get the api versions which the io-engine serves, from the latest one.
+    pub fn served_api_versions(&self) -> &[ApiVersion] {
+        &self.served_api_versions
+    }
+
+    /// Get the name of the node this client is connected to.
+    pub fn node_name(&self) -> &str {
+        &self.node_name
+    }
+}
@@ -0,0 +1,35 @@
+//! Client of the gRPC api of the io-engine, for both the v0 and the v1 api, along with typed
+//! wrappers of the pools, replicas and nexuses which are common to both api versions.
+
+/// Error module.
+mod error;
+/// Grpc client module.
+mod grpc_client;
+/// Nexus module.
+pub mod nexus;
+/// Pool module.
+pub mod pool;
+/// Replica module.
+pub mod replica;
+
+pub use error::Error;
+pub use grpc_client::{GrpcClient, GrpcContext, MayaClientV0, MayaClientV1, Timeouts};
+
+/// Version of the gRPC api of the io-engine.
+#[derive(
+    Debug,
+    strum_macros::EnumString,
+    strum_macros::AsRefStr,
+    serde::Serialize,
+    Clone,
+    Ord,
+    PartialOrd,
+    Eq,
+    PartialEq,
+)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ApiVersion {
+    V0,
+    V1,
+}
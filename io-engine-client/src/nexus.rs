@@ -0,0 +1,79 @@
+use crate::{ApiVersion, Error, GrpcClient};
+
+use serde::Serialize;
+
+/// A nexus of the io-engine, as reported by either api version.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Nexus {
+    /// Name of the nexus, which is the uuid of its volume. The v0 nexuses are named by their
+    /// uuid.
+    pub name: String,
+    /// Uuid of the nexus.
+    pub uuid: String,
+    /// Size of the nexus, in bytes.
+    pub size: u64,
+    /// State of the nexus, as the value of the state enum of the api.
+    pub state: i32,
+    /// Uris of the children of the nexus.
+    pub children: Vec<String>,
+}
+
+impl From<rpc::io_engine::Nexus> for Nexus {
+    fn from(value: rpc::io_engine::Nexus) -> Self {
+        Self {
+            name: value.uuid.clone(),
+            uuid: value.uuid,
+            size: value.size,
+            state: value.state,
+            children: value.children.into_iter().map(|child| child.uri).collect(),
+        }
+    }
+}
+
+impl From<rpc::v1::nexus::Nexus> for Nexus {
+    fn from(value: rpc::v1::nexus::Nexus) -> Self {
+        Self {
+            name: value.name,
+            uuid: value.uuid,
+            size: value.size,
+            state: value.state,
+            children: value.children.into_iter().map(|child| child.uri).collect(),
+        }
+    }
+}
+
+impl GrpcClient {
+    /// List the nexuses of the io-engine.
+    pub async fn list_nexuses(&self) -> Result<Vec<Nexus>, Error> {
+        let nexuses = match self.api_version() {
+            ApiVersion::V0 => self
+                .client_v0()?
+                .list_nexus(rpc::io_engine::Null {})
+                .await
+                .map_err(|source| Error::Response {
+                    call: "list_nexus",
+                    source,
+                })?
+                .into_inner()
+                .nexus_list
+                .into_iter()
+                .map(Nexus::from)
+                .collect(),
+            ApiVersion::V1 => self
+                .client_v1()?
+                .nexus
+                .list_nexus(rpc::v1::nexus::ListNexusOptions::default())
+                .await
+                .map_err(|source| Error::Response {
+                    call: "list_nexus",
+                    source,
+                })?
+                .into_inner()
+                .nexus_list
+                .into_iter()
+                .map(Nexus::from)
+                .collect(),
+        };
+        Ok(nexuses)
+    }
+}
@@ -0,0 +1,86 @@
+use crate::{ApiVersion, Error, GrpcClient};
+
+use serde::Serialize;
+
+/// A pool of the io-engine, as reported by either api version.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Pool {
+    /// Name of the pool.
+    pub name: String,
+    /// Uuid of the pool, only reported by the v1 api.
+    pub uuid: Option<String>,
+    /// Disks the pool is created on.
+    pub disks: Vec<String>,
+    /// Used capacity of the pool, in bytes.
+    pub used: u64,
+    /// Total capacity of the pool, in bytes.
+    pub capacity: u64,
+    /// Bytes committed to the replicas of the pool, only reported by the v1 api.
+    pub committed: Option<u64>,
+    /// State of the pool, as the value of the state enum of the api.
+    pub state: i32,
+}
+
+impl From<rpc::io_engine::Pool> for Pool {
+    fn from(value: rpc::io_engine::Pool) -> Self {
+        Self {
+            name: value.name,
+            uuid: None,
+            disks: value.disks,
+            used: value.used,
+            capacity: value.capacity,
+            committed: None,
+            state: value.state,
+        }
+    }
+}
+
+impl From<rpc::v1::pool::Pool> for Pool {
+    fn from(value: rpc::v1::pool::Pool) -> Self {
+        Self {
+            name: value.name,
+            uuid: Some(value.uuid),
+            disks: value.disks,
+            used: value.used,
+            capacity: value.capacity,
+            committed: Some(value.committed),
+            state: value.state,
+        }
+    }
+}
+
+impl GrpcClient {
+    /// List the pools of the io-engine.
+    pub async fn list_pools(&self) -> Result<Vec<Pool>, Error> {
+        let pools = match self.api_version() {
+            ApiVersion::V0 => self
+                .client_v0()?
+                .list_pools(rpc::io_engine::Null {})
+                .await
+                .map_err(|source| Error::Response {
+                    call: "list_pools",
+                    source,
+                })?
+                .into_inner()
+                .pools
+                .into_iter()
+                .map(Pool::from)
+                .collect(),
+            ApiVersion::V1 => self
+                .client_v1()?
+                .pool
+                .list_pools(rpc::v1::pool::ListPoolOptions::default())
+                .await
+                .map_err(|source| Error::Response {
+                    call: "list_pools",
+                    source,
+                })?
+                .into_inner()
+                .pools
+                .into_iter()
+                .map(Pool::from)
+                .collect(),
+        };
+        Ok(pools)
+    }
+}
@@ -0,0 +1,94 @@
+use crate::{ApiVersion, Error, GrpcClient};
+
+use serde::Serialize;
+
+/// A replica of the io-engine, as reported by either api version.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Replica {
+    /// Uuid of the replica.
+    pub uuid: String,
+    /// Name of the pool of the replica.
+    pub pool: String,
+    /// Size of the replica, in bytes.
+    pub size: u64,
+    /// Whether the replica is thin provisioned.
+    pub thin: bool,
+    /// Uuid of the volume owning the replica, only reported by the v1 api.
+    pub entity_id: Option<String>,
+    /// Space usage of the replica, only reported by the v1 api.
+    pub usage: Option<ReplicaUsage>,
+}
+
+/// Space usage of a replica.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ReplicaUsage {
+    /// Capacity of the replica, in bytes.
+    pub capacity_bytes: u64,
+    /// Bytes allocated by the replica.
+    pub allocated_bytes: u64,
+}
+
+impl From<rpc::io_engine::Replica> for Replica {
+    fn from(value: rpc::io_engine::Replica) -> Self {
+        Self {
+            uuid: value.uuid,
+            pool: value.pool,
+            size: value.size,
+            thin: value.thin,
+            entity_id: None,
+            usage: None,
+        }
+    }
+}
+
+impl From<rpc::v1::replica::Replica> for Replica {
+    fn from(value: rpc::v1::replica::Replica) -> Self {
+        Self {
+            uuid: value.uuid,
+            pool: value.poolname,
+            size: value.size,
+            thin: value.thin,
+            entity_id: value.entity_id,
+            usage: value.usage.map(|usage| ReplicaUsage {
+                capacity_bytes: usage.capacity_bytes,
+                allocated_bytes: usage.allocated_bytes,
+            }),
+        }
+    }
+}
+
+impl GrpcClient {
+    /// List the replicas of the io-engine.
+    pub async fn list_replicas(&self) -> Result<Vec<Replica>, Error> {
+        let replicas = match self.api_version() {
+            ApiVersion::V0 => self
+                .client_v0()?
+                .list_replicas(rpc::io_engine::Null {})
+                .await
+                .map_err(|source| Error::Response {
+                    call: "list_replicas",
+                    source,
+                })?
+                .into_inner()
+                .replicas
+                .into_iter()
+                .map(Replica::from)
+                .collect(),
+            ApiVersion::V1 => self
+                .client_v1()?
+                .replica
+                .list_replicas(rpc::v1::replica::ListReplicaOptions::default())
+                .await
+                .map_err(|source| Error::Response {
+                    call: "list_replicas",
+                    source,
+                })?
+                .into_inner()
+                .replicas
+                .into_iter()
+                .map(Replica::from)
+                .collect(),
+        };
+        Ok(replicas)
+    }
+}
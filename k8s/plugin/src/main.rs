@@ -1,23 +1,31 @@
 use crate::resources::GetResourcesK8s;
-use anyhow::Result;
-use clap::Parser;
-use openapi::tower::client::Url;
+use anyhow::{anyhow, Result};
+use clap::{parser::ValueSource, CommandFactory, FromArgMatches, Parser};
+use openapi::tower::client::{Configuration, Url};
 use opentelemetry::global;
 use plugin::{
     operations::{
-        Cordoning, Drain, Get, GetBlockDevices, GetSnapshots, List, ListExt, RebuildHistory,
-        ReplicaTopology, Scale,
+        Cordoning, Get, GetBlockDevices, GetSnapshots, List, ListExt, RebuildHistory,
+        ReplicaTopology,
     },
     resources::{
-        blockdevice, cordon, drain, node, pool, snapshot, volume, CordonResources, DrainResources,
-        GetCordonArgs, GetDrainArgs, GetResources, ScaleResources,
+        blockdevice, cordon, drain, node, pool, snapshot, utils::OutputFormat, volume,
+        CordonResources, GetCordonArgs, GetDrainArgs, GetResources,
     },
     rest_wrapper::RestClient,
 };
-use resources::Operations;
-use upgrade::plugin::{preflight_validations, upgrade::DeleteResources};
+use resources::{
+    filter::{augment_listings, ListFilterArgs},
+    watch::watch,
+    Operations,
+};
+use upgrade::plugin::{
+    preflight_validations,
+    simulate::UpgradeCommand,
+    upgrade::{DeleteResources, UpgradeStatusOutput},
+};
 
-use std::{env, path::PathBuf};
+use std::{env, path::PathBuf, time::Duration};
 
 mod resources;
 
@@ -45,16 +53,85 @@ struct CliArgs {
     jaeger: Option<String>,
 
     /// Timeout for the REST operations.
-    #[clap(long, short, default_value = "10s")]
+    #[clap(long, short, env = "KUBECTL_MAYASTOR_TIMEOUT", default_value = "10s")]
     timeout: humantime::Duration,
 
+    /// Path to the PEM certificate of the CA of the rest endpoint given with `--rest`, eg: of an
+    /// ingress fronting the REST api.
+    #[clap(
+        global = true,
+        long,
+        env = "KUBECTL_MAYASTOR_CACERT",
+        requires = "rest"
+    )]
+    cacert: Option<PathBuf>,
+
+    /// Bearer token sent with the REST requests, eg: to an authenticating ingress.
+    #[clap(
+        global = true,
+        long,
+        env = "KUBECTL_MAYASTOR_TOKEN",
+        hide_env_values = true
+    )]
+    token: Option<String>,
+
     /// Kubernetes namespace of mayastor service
     #[clap(global = true, long, short = 'n', default_value = "mayastor")]
     namespace: String,
+
+    /// Only allow the operations which do not modify the cluster, so that only get/list
+    /// permissions are required.
+    #[clap(global = true, long)]
+    read_only: bool,
+
+    /// Keep the output of `get volumes`, `get pools` or `get nodes` updated, by re-running the
+    /// command every refresh interval until interrupted.
+    #[clap(global = true, long)]
+    watch: bool,
+
+    /// The refresh interval of `--watch`, and of `get upgrade-status --follow`, in seconds.
+    #[clap(
+        global = true,
+        long,
+        default_value_t = 2,
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    refresh: u64,
+
+    /// The sorting and filtering of the listing of the volumes, pools or nodes.
+    #[clap(skip)]
+    list_filter: ListFilterArgs,
 }
 impl CliArgs {
     fn args() -> Self {
-        CliArgs::parse()
+        let matches = Self::cli_command().get_matches();
+        let mut args = Self::from_arg_matches(&matches).unwrap_or_else(|error| error.exit());
+        args.list_filter = ListFilterArgs::from_listing(&matches);
+        // The dump collects from the detected installation unless the namespace is given.
+        if let Operations::Dump(dump) = &mut args.operations {
+            let namespace = matches
+                .subcommand_matches("dump")
+                .and_then(|dump| dump.value_source("namespace"));
+            if namespace != Some(ValueSource::CommandLine) {
+                dump.detect_namespace();
+            }
+        }
+        args
+    }
+
+    /// The command of the plugin, along with the sorting and filtering arguments of the listings.
+    fn cli_command() -> clap::Command {
+        augment_listings(Self::command())
+    }
+
+    /// Whether the plugin runs in read-only mode, which is always the case for read-only builds.
+    fn read_only(&self) -> bool {
+        self.read_only || cfg!(feature = "read-only")
+    }
+
+    /// The refresh interval of the operation, if it's watched.
+    fn watch_refresh(&self) -> Option<Duration> {
+        self.watch.then(|| Duration::from_secs(self.refresh))
     }
 }
 
@@ -68,14 +145,29 @@ async fn main() {
 }
 
 async fn execute(cli_args: CliArgs) {
-    // Initialise the REST client.
-    if let Err(e) = init_rest(&cli_args).await {
-        eprintln!("Failed to initialise the REST client. Error {e}");
+    if cli_args.read_only() && cli_args.operations.is_mutating() {
+        eprintln!("The operation is not allowed in read-only mode");
         std::process::exit(1);
     }
 
+    if cli_args.watch && !cli_args.operations.is_watchable() {
+        eprintln!("--watch is only supported by get volumes, get pools and get nodes");
+        std::process::exit(1);
+    }
+
+    // Initialise the REST client, unless the operation doesn't use it.
+    if cli_args.operations.uses_rest_client() {
+        if let Err(e) = init_rest(&cli_args).await {
+            eprintln!("Failed to initialise the REST client. Error {e}");
+            std::process::exit(1);
+        }
+    }
+
     // Perform the operations based on the subcommand, with proper output format.
     let fut = async move {
+        let refresh = cli_args.watch_refresh();
+        let output = &cli_args.output;
+        let list_filter = Some(&cli_args.list_filter).filter(|filter| !filter.is_empty());
         match cli_args.operations {
             Operations::Get(resource) => match resource {
                 GetResourcesK8s::Rest(resource) => match resource {
@@ -92,7 +184,14 @@ async fn execute(cli_args: CliArgs) {
                         GetDrainArgs::Nodes => drain::NodeDrains::list(&cli_args.output).await,
                     },
                     GetResources::Volumes(volume_args) => {
-                        volume::Volumes::list(&cli_args.output, &volume_args).await
+                        let volume_args = &volume_args;
+                        watch(refresh, "get volumes", move || async move {
+                            match list_filter {
+                                Some(filter) => filter.volumes(output).await,
+                                None => volume::Volumes::list(output, volume_args).await,
+                            }
+                        })
+                        .await
                     }
                     GetResources::Volume { id } => volume::Volume::get(&id, &cli_args.output).await,
                     GetResources::VolumeReplicaTopologies(volume_args) => {
@@ -101,9 +200,25 @@ async fn execute(cli_args: CliArgs) {
                     GetResources::VolumeReplicaTopology { id } => {
                         volume::Volume::topology(&id, &cli_args.output).await
                     }
-                    GetResources::Pools => pool::Pools::list(&cli_args.output).await,
+                    GetResources::Pools => {
+                        watch(refresh, "get pools", move || async move {
+                            match list_filter {
+                                Some(filter) => filter.pools(output).await,
+                                None => pool::Pools::list(output).await,
+                            }
+                        })
+                        .await
+                    }
                     GetResources::Pool { id } => pool::Pool::get(&id, &cli_args.output).await,
-                    GetResources::Nodes => node::Nodes::list(&cli_args.output).await,
+                    GetResources::Nodes => {
+                        watch(refresh, "get nodes", move || async move {
+                            match list_filter {
+                                Some(filter) => filter.nodes(output).await,
+                                None => node::Nodes::list(output).await,
+                            }
+                        })
+                        .await
+                    }
                     GetResources::Node(args) => {
                         node::Node::get(&args.node_id(), &cli_args.output).await
                     }
@@ -128,25 +243,46 @@ async fn execute(cli_args: CliArgs) {
                     }
                 },
                 GetResourcesK8s::UpgradeStatus(resources) => {
-                    resources.get_upgrade(&cli_args.namespace).await;
+                    let output = match cli_args.output {
+                        OutputFormat::Json => UpgradeStatusOutput::Json,
+                        OutputFormat::Yaml => UpgradeStatusOutput::Yaml,
+                        _ => UpgradeStatusOutput::Table,
+                    };
+                    let refresh = Duration::from_secs(cli_args.refresh);
+                    resources
+                        .get_upgrade(&cli_args.namespace, output, refresh)
+                        .await;
                 }
-            },
-            Operations::Drain(resource) => match resource {
-                DrainResources::Node(drain_node_args) => {
-                    node::Node::drain(
-                        &drain_node_args.node_id(),
-                        drain_node_args.label(),
-                        drain_node_args.drain_timeout(),
+                GetResourcesK8s::VolumeReplicas(args) => {
+                    args.get(cli_args.kube_config_path, &cli_args.output).await;
+                }
+                GetResourcesK8s::Usage(args) => {
+                    args.get(cli_args.kube_config_path, &cli_args.output).await;
+                }
+                GetResourcesK8s::VolumesWide(args) => {
+                    args.get(&cli_args.output).await;
+                }
+                GetResourcesK8s::Snapshots(args) => {
+                    args.get(&cli_args.output).await;
+                }
+                GetResourcesK8s::SnapshotTopology(args) => {
+                    args.get(&cli_args.output).await;
+                }
+                GetResourcesK8s::BlockDevicesWide(args) => {
+                    args.get(&cli_args.output).await;
+                }
+                GetResourcesK8s::NvmeSessions(args) => {
+                    args.get(
+                        cli_args.kube_config_path,
+                        &cli_args.namespace,
                         &cli_args.output,
                     )
-                    .await
-                }
-            },
-            Operations::Scale(resource) => match resource {
-                ScaleResources::Volume { id, replica_count } => {
-                    volume::Volume::scale(&id, replica_count, &cli_args.output).await
+                    .await;
                 }
             },
+            Operations::Drain(resource) => resource.drain(&cli_args.output).await,
+            Operations::Scale(resource) => resource.scale(&cli_args.output).await,
+            Operations::Resize(resource) => resource.resize(&cli_args.output).await,
             Operations::Cordon(resource) => match resource {
                 CordonResources::Node { id, label } => {
                     node::Node::cordon(&id, &label, &cli_args.output).await
@@ -157,7 +293,17 @@ async fn execute(cli_args: CliArgs) {
                     node::Node::uncordon(&id, &label, &cli_args.output).await
                 }
             },
+            Operations::Dump(resources) if resources.is_analysis() => {
+                let _ignore = resources
+                    .dump(cli_args.kube_config_path)
+                    .await
+                    .map_err(|error| {
+                        eprintln!("{error}");
+                        std::process::exit(1);
+                    });
+            }
             Operations::Dump(resources) => {
+                let streams_to_stdout = resources.streams_to_stdout();
                 let _ignore = resources
                     .dump(cli_args.kube_config_path)
                     .await
@@ -165,28 +311,89 @@ async fn execute(cli_args: CliArgs) {
                         eprintln!("Partially collected dump information: {error:?}");
                         std::process::exit(1);
                     });
-                println!("Completed collection of dump !!");
+                match streams_to_stdout {
+                    true => eprintln!("Completed collection of dump !!"),
+                    false => println!("Completed collection of dump !!"),
+                }
             }
-            Operations::Upgrade(resources) => {
-                let _ignore = preflight_validations::preflight_check(
+            Operations::Upgrade(mut resources) => {
+                if let Some(UpgradeCommand::Simulate(simulate)) = &resources.command {
+                    _ = simulate.simulate(&resources).await.map_err(|error| {
+                        eprintln!("{error}");
+                        std::process::exit(error.into());
+                    });
+                    return;
+                }
+                if let Some(UpgradeCommand::Rollback(rollback)) = &resources.command {
+                    if resources.dry_run {
+                        eprintln!("The rollback can't be run with --dry-run");
+                        std::process::exit(1);
+                    }
+                    _ = rollback
+                        .rollback(&cli_args.namespace, &resources)
+                        .await
+                        .map_err(|error| {
+                            eprintln!("{error}");
+                            std::process::exit(error.into());
+                        });
+                    return;
+                }
+                if let Some(
+                    UpgradeCommand::Pause(pause_args) | UpgradeCommand::Resume(pause_args),
+                ) = &resources.command
+                {
+                    let paused = matches!(resources.command, Some(UpgradeCommand::Pause(_)));
+                    _ = pause_args
+                        .set_paused(&cli_args.namespace, paused)
+                        .await
+                        .map_err(|error| {
+                            eprintln!("{error}");
+                            std::process::exit(error.into());
+                        });
+                    return;
+                }
+
+                match preflight_validations::preflight_check(
                     &cli_args.namespace,
                     cli_args.kube_config_path.clone(),
                     cli_args.timeout,
                     &resources,
                 )
                 .await
-                .map_err(|error| {
-                    eprintln!("{error}");
-                    std::process::exit(error.into());
-                });
-
-                if resources.dry_run {
-                    _ = resources.dummy_apply(&cli_args.namespace).await;
-                } else {
-                    _ = resources.apply(&cli_args.namespace).await.map_err(|error| {
+                {
+                    Ok(held_nodes) => resources.held_nodes = held_nodes,
+                    Err(error) => {
                         eprintln!("{error}");
                         std::process::exit(error.into());
-                    });
+                    }
+                }
+
+                // The state of the cluster before the upgrade is dumped, for the diagnosis of a
+                // failed upgrade. The upgrade goes ahead if it can't be collected.
+                if !resources.dry_run && !resources.skip_preflight_dump {
+                    println!(
+                        "Collecting the pre-upgrade dump into '{}'...",
+                        resources.preflight_dump_dir
+                    );
+                    if let Err(error) = supportability::dump_before_upgrade(
+                        cli_args.kube_config_path.clone(),
+                        &cli_args.namespace,
+                        &resources.preflight_dump_dir,
+                        cli_args.timeout,
+                    )
+                    .await
+                    {
+                        eprintln!("Failed to collect the pre-upgrade dump: {error}");
+                    }
+                }
+
+                let result = match resources.dry_run {
+                    true => resources.dummy_apply(&cli_args.namespace).await,
+                    false => resources.apply(&cli_args.namespace).await,
+                };
+                if let Err(error) = result {
+                    eprintln!("{error}");
+                    std::process::exit(error.into());
                 }
             }
 
@@ -195,6 +402,31 @@ async fn execute(cli_args: CliArgs) {
                     res.delete(&cli_args.namespace).await;
                 }
             },
+            Operations::Doctor(args) => {
+                let _ignore = args
+                    .diagnose(
+                        cli_args.kube_config_path,
+                        &cli_args.namespace,
+                        cli_args.timeout,
+                    )
+                    .await
+                    .map_err(|error| {
+                        eprintln!("{error}");
+                        std::process::exit(1);
+                    });
+            }
+            Operations::Migrate(resources) => resources.migrate(&cli_args.output).await,
+            Operations::Explain(args) => args.explain(),
+            Operations::Completions(args) => args.generate(CliArgs::cli_command()),
+            Operations::CompleteValues(args) => args.complete().await,
+            Operations::Status(args) => {
+                args.get(
+                    cli_args.kube_config_path,
+                    &cli_args.namespace,
+                    &cli_args.output,
+                )
+                .await
+            }
         };
     };
 
@@ -208,11 +440,37 @@ async fn execute(cli_args: CliArgs) {
 async fn init_rest(args: &CliArgs) -> Result<()> {
     // Use the supplied URL if there is one otherwise obtain one from the kubeconfig file.
     match args.rest.clone() {
-        Some(url) => RestClient::init(url, *args.timeout),
+        Some(url) if args.cacert.is_none() && args.token.is_none() => {
+            RestClient::init(url, *args.timeout)
+        }
+        Some(url) => {
+            let config = Configuration::builder()
+                .with_timeout(*args.timeout)
+                .with_bearer_token(args.token.clone())
+                .with_tracing(true);
+            let config = match &args.cacert {
+                Some(path) => {
+                    let certificate = std::fs::read(path).map_err(|error| {
+                        anyhow!(
+                            "failed to read the CA certificate '{}': {error}",
+                            path.display()
+                        )
+                    })?;
+                    config.with_certificate(&certificate)
+                }
+                None => config,
+            };
+            let config = config
+                .build_url(url)
+                .map_err(|error| anyhow!("failed to create the REST config: {error:?}"))?;
+            RestClient::init_with_config(config)?;
+            Ok(())
+        }
         None => {
             let config = kube_proxy::ConfigBuilder::default_api_rest()
                 .with_kube_config(args.kube_config_path.clone())
                 .with_timeout(*args.timeout)
+                .with_jwt(args.token.clone())
                 .with_target_mod(|t| t.with_namespace(&args.namespace))
                 .build()
                 .await?;
@@ -0,0 +1,129 @@
+use crate::resources::placement::{print_output, print_table};
+use anyhow::{anyhow, Result};
+use openapi::models::{BlockDevice, Pool};
+use plugin::{resources::utils::OutputFormat, rest_wrapper::RestClient};
+use serde::Serialize;
+
+/// The size of the blocks the size of the block devices is given in.
+const BLOCK_SIZE: u64 = 512;
+
+/// Get the block devices of a node along with the pools using them.
+#[derive(clap::Args, Debug)]
+pub struct BlockDevicesWideArgs {
+    /// Id of the node.
+    node: String,
+
+    /// Also list the devices which aren't usable, eg: the partitioned or mounted devices.
+    #[clap(long)]
+    all: bool,
+}
+
+/// A block device of the node, for the planning of the pools.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct NodeBlockDevice {
+    devname: String,
+    size_bytes: u64,
+    model: String,
+    available: bool,
+    pool: Option<String>,
+}
+
+impl NodeBlockDevice {
+    fn new(device: &BlockDevice, pools: &[Pool]) -> Self {
+        Self {
+            devname: device.devname.clone(),
+            size_bytes: device.size * BLOCK_SIZE,
+            model: device.model.clone(),
+            available: device.available,
+            pool: pools
+                .iter()
+                .find(|pool| is_pool_device(pool, device))
+                .map(|pool| pool.id.clone()),
+        }
+    }
+}
+
+impl BlockDevicesWideArgs {
+    /// Print the block devices of the node.
+    pub async fn get(&self, output: &OutputFormat) {
+        if let Err(error) = self.execute(output).await {
+            eprintln!(
+                "Failed to get the block devices of node {}. Error {error}",
+                self.node
+            );
+            std::process::exit(1);
+        }
+    }
+
+    async fn execute(&self, output: &OutputFormat) -> Result<()> {
+        let devices = RestClient::client()
+            .block_devices_api()
+            .get_node_block_devices(&self.node, Some(self.all))
+            .await
+            .map_err(|error| anyhow!("{error}"))?
+            .into_body();
+        let pools = RestClient::client()
+            .pools_api()
+            .get_node_pools(&self.node)
+            .await
+            .map_err(|error| anyhow!("{error}"))?
+            .into_body();
+        let mut devices = devices
+            .iter()
+            .map(|device| NodeBlockDevice::new(device, &pools))
+            .collect::<Vec<_>>();
+        devices.sort_by(|a, b| a.devname.cmp(&b.devname));
+        print_output(&devices, output, print_devices)
+    }
+}
+
+/// Whether the device is a disk of the pool, given by its name, path or one of its links, with
+/// or without the scheme of the pool disk uri.
+fn is_pool_device(pool: &Pool, device: &BlockDevice) -> bool {
+    let disks = pool
+        .spec
+        .iter()
+        .flat_map(|spec| spec.disks.iter())
+        .chain(pool.state.iter().flat_map(|state| state.disks.iter()));
+    disks.map(|disk| disk_path(disk)).any(|disk| {
+        disk == device.devname
+            || disk == device.devpath
+            || device.devlinks.iter().any(|link| link == disk)
+    })
+}
+
+/// The path of the disk of a pool, eg: `/dev/sdb` for `aio:///dev/sdb?uuid=...`.
+fn disk_path(disk: &str) -> &str {
+    let path = disk.split_once("://").map_or(disk, |(_, path)| path);
+    path.split_once('?').map_or(path, |(path, _)| path)
+}
+
+/// Print the block devices as a table.
+fn print_devices(devices: &[NodeBlockDevice]) {
+    let cells = devices
+        .iter()
+        .map(|device| {
+            [
+                device.devname.clone(),
+                bytesize::ByteSize::b(device.size_bytes).to_string(),
+                device.model.clone(),
+                device.available.to_string(),
+                device.pool.clone().unwrap_or_else(|| "<none>".to_string()),
+            ]
+        })
+        .collect::<Vec<_>>();
+    let rows = cells
+        .iter()
+        .map(|[devname, size, model, available, pool]| {
+            [
+                devname.as_str(),
+                size.as_str(),
+                model.as_str(),
+                available.as_str(),
+                pool.as_str(),
+            ]
+        })
+        .collect::<Vec<_>>();
+    print_table(["DEVNAME", "SIZE", "MODEL", "AVAILABLE", "POOL"], &rows);
+}
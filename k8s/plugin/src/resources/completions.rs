@@ -0,0 +1,109 @@
+use crate::resources::placement::list_volumes;
+use anyhow::Result;
+use clap_complete::Shell;
+use plugin::rest_wrapper::RestClient;
+
+/// Name of the plugin binary the completions are generated for.
+const BIN_NAME: &str = "kubectl-mayastor";
+
+/// Completion of the volume uuids, node names and pool ids for bash, which replaces the generated
+/// completion function for the arguments following the subcommands taking them.
+const BASH_DYNAMIC_COMPLETION: &str = r#"
+_kubectl-mayastor_dynamic() {
+    local kind
+    case "${COMP_WORDS[COMP_CWORD-1]}" in
+        volume|volume-replica-topology|rebuild-history) kind=volumes ;;
+        node|block-devices|block-devices-wide) kind=nodes ;;
+        pool) kind=pools ;;
+        *)
+            _kubectl-mayastor "$@"
+            return
+            ;;
+    esac
+    COMPREPLY=( $(compgen -W "$(kubectl-mayastor complete-values "${kind}" 2>/dev/null)" -- "${COMP_WORDS[COMP_CWORD]}") )
+}
+complete -F _kubectl-mayastor_dynamic -o bashdefault -o default kubectl-mayastor
+"#;
+
+/// Completion of the volume uuids, node names and pool ids for fish.
+const FISH_DYNAMIC_COMPLETION: &str = r#"
+complete -c kubectl-mayastor -f -n '__fish_seen_subcommand_from volume volume-replica-topology rebuild-history' -a '(kubectl-mayastor complete-values volumes 2>/dev/null)'
+complete -c kubectl-mayastor -f -n '__fish_seen_subcommand_from node block-devices block-devices-wide' -a '(kubectl-mayastor complete-values nodes 2>/dev/null)'
+complete -c kubectl-mayastor -f -n '__fish_seen_subcommand_from pool' -a '(kubectl-mayastor complete-values pools 2>/dev/null)'
+"#;
+
+/// Generate the shell completions of the plugin.
+#[derive(clap::Args, Debug)]
+pub struct CompletionsArgs {
+    /// The shell to generate the completions for.
+    shell: Shell,
+}
+
+impl CompletionsArgs {
+    /// Print the completions of the given command for the shell. For bash and fish, the volume
+    /// uuids, node names and pool ids are completed as well, by querying the REST api.
+    pub fn generate(&self, mut command: clap::Command) {
+        let mut stdout = std::io::stdout();
+        clap_complete::generate(self.shell, &mut command, BIN_NAME, &mut stdout);
+        match self.shell {
+            Shell::Bash => print!("{BASH_DYNAMIC_COMPLETION}"),
+            Shell::Fish => print!("{FISH_DYNAMIC_COMPLETION}"),
+            _ => {}
+        }
+    }
+}
+
+/// The kind of the values to complete.
+#[derive(clap::ValueEnum, Debug, Clone, Copy)]
+enum ValueKind {
+    Volumes,
+    Nodes,
+    Pools,
+}
+
+/// Print the values of a kind one per line, for the shell completions.
+#[derive(clap::Args, Debug)]
+pub struct CompleteValuesArgs {
+    /// The kind of the values.
+    kind: ValueKind,
+}
+
+impl CompleteValuesArgs {
+    /// Print the volume uuids, node names or pool ids. Nothing is printed on failure, so that the
+    /// completion of the shell isn't cluttered with errors.
+    pub async fn complete(&self) {
+        if let Ok(values) = self.values().await {
+            values.iter().for_each(|value| println!("{value}"));
+        }
+    }
+
+    async fn values(&self) -> Result<Vec<String>> {
+        let mut values = match self.kind {
+            ValueKind::Volumes => list_volumes()
+                .await?
+                .into_iter()
+                .map(|volume| volume.spec.uuid.to_string())
+                .collect(),
+            ValueKind::Nodes => RestClient::client()
+                .nodes_api()
+                .get_nodes(None)
+                .await
+                .map_err(|error| anyhow::anyhow!("{error}"))?
+                .into_body()
+                .into_iter()
+                .map(|node| node.id)
+                .collect(),
+            ValueKind::Pools => RestClient::client()
+                .pools_api()
+                .get_pools()
+                .await
+                .map_err(|error| anyhow::anyhow!("{error}"))?
+                .into_body()
+                .into_iter()
+                .map(|pool| pool.id)
+                .collect::<Vec<_>>(),
+        };
+        values.sort();
+        Ok(values)
+    }
+}
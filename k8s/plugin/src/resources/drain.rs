@@ -0,0 +1,166 @@
+use crate::resources::placement::list_volumes;
+use anyhow::{anyhow, bail, Result};
+use openapi::models::{CordonDrainState, Node};
+use plugin::{resources::utils::OutputFormat, rest_wrapper::RestClient};
+use std::time::{Duration, Instant};
+
+/// Interval at which the drain of the node is polled.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Drain resources.
+#[derive(clap::Subcommand, Debug)]
+pub enum DrainResources {
+    /// Drain a node, which cordons it and moves the targets of its volumes to other nodes.
+    Node(DrainNodeArgs),
+}
+
+/// Drain a node, eg: `drain node <id> --label maintenance`.
+#[derive(clap::Args, Debug)]
+pub struct DrainNodeArgs {
+    /// Id of the node.
+    id: String,
+
+    /// The drain label, as a positional argument, for compatibility.
+    #[clap(
+        hide = true,
+        required_unless_present = "label",
+        conflicts_with = "label"
+    )]
+    drain_label: Option<String>,
+
+    /// The label of the drain, which must be given to uncordon the node again.
+    #[clap(long)]
+    label: Option<String>,
+
+    /// Maximum time to wait for the drain to complete. The drain is not cancelled when it
+    /// elapses, it carries on until the node is uncordoned with the label. By default the drain
+    /// is waited for until it completes.
+    #[clap(long, alias = "drain-timeout")]
+    timeout: Option<humantime::Duration>,
+}
+
+impl DrainResources {
+    /// Execute the drain operation.
+    pub async fn drain(&self, output: &OutputFormat) {
+        match self {
+            DrainResources::Node(args) => args.drain(output).await,
+        }
+    }
+}
+
+impl DrainNodeArgs {
+    /// Drain the node, reporting the progress of the moves of the targets off the node.
+    pub async fn drain(&self, output: &OutputFormat) {
+        match self.execute(output).await {
+            Ok(node) => match output {
+                OutputFormat::Yaml => print_result(serde_yaml::to_string(&node)),
+                OutputFormat::Json => print_result(serde_json::to_string_pretty(&node)),
+                _ => println!("Node {} successfully drained", self.id),
+            },
+            Err(error) => {
+                eprintln!("Failed to drain node {}. Error {error}", self.id);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    async fn execute(&self, output: &OutputFormat) -> Result<Node> {
+        let Some(label) = self.label.as_ref().or(self.drain_label.as_ref()) else {
+            bail!("the drain label is required");
+        };
+        RestClient::client()
+            .nodes_api()
+            .put_node_drain(&self.id, label)
+            .await
+            .map_err(|error| anyhow!("{error}"))?;
+        progress(
+            output,
+            format!("Draining node {} with label {label}", self.id),
+        );
+
+        let start = Instant::now();
+        let mut last_targets = None;
+        loop {
+            let node = get_node(&self.id).await?;
+            let drained = matches!(
+                node.spec
+                    .as_ref()
+                    .and_then(|spec| spec.cordondrainstate.as_ref()),
+                Some(CordonDrainState::drainedstate(_))
+            );
+            if drained {
+                return Ok(node);
+            }
+
+            let targets = self.targets().await?;
+            if last_targets.as_ref() != Some(&targets) {
+                let message = match targets.as_slice() {
+                    [] => "All the targets are moved, waiting for the drain to complete".into(),
+                    targets => format!(
+                        "Moving {} target(s) off node {}: {}",
+                        targets.len(),
+                        self.id,
+                        targets.join(", ")
+                    ),
+                };
+                progress(output, message);
+                last_targets = Some(targets);
+            }
+
+            if let Some(timeout) = self.timeout {
+                if start.elapsed() > *timeout {
+                    let id = &self.id;
+                    bail!(
+                        "node {id} was not drained within {timeout}, the drain carries on, \
+                        cancel it with: kubectl mayastor uncordon node {id} {label}"
+                    );
+                }
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    /// The volumes whose target is still on the node.
+    async fn targets(&self) -> Result<Vec<String>> {
+        let mut targets = list_volumes()
+            .await?
+            .into_iter()
+            .filter(|volume| {
+                volume
+                    .state
+                    .target
+                    .as_ref()
+                    .is_some_and(|target| target.node == self.id)
+            })
+            .map(|volume| volume.spec.uuid.to_string())
+            .collect::<Vec<_>>();
+        targets.sort();
+        Ok(targets)
+    }
+}
+
+/// Get the node.
+async fn get_node(id: &str) -> Result<Node> {
+    Ok(RestClient::client()
+        .nodes_api()
+        .get_node(id)
+        .await
+        .map_err(|error| anyhow!("failed to get node {id}: {error}"))?
+        .into_body())
+}
+
+/// Print the serialized node.
+fn print_result<E: std::fmt::Display>(result: Result<String, E>) {
+    match result {
+        Ok(node) => println!("{}", node.trim_end()),
+        Err(error) => eprintln!("Failed to print the node. Error {error}"),
+    }
+}
+
+/// Report the progress of the drain, on stderr when the node is printed on stdout.
+fn progress(output: &OutputFormat, message: String) {
+    match output {
+        OutputFormat::Yaml | OutputFormat::Json => eprintln!("{message}"),
+        _ => println!("{message}"),
+    }
+}
@@ -0,0 +1,374 @@
+use anyhow::{anyhow, Result};
+use k8s_operators::diskpool::crd::DiskPool;
+use kube::CustomResourceExt;
+use serde_yaml::Value;
+
+/// OpenAPI specification of the control-plane REST api, embedded at build time.
+const REST_API_SPEC: &str = include_str!(
+    "../../../../dependencies/control-plane/control-plane/rest/openapi-specs/v0_api_spec.yaml"
+);
+
+/// Version of the control-plane REST api whose specification is embedded.
+const REST_API_VERSION: &str = "v0";
+
+/// Names the DiskPool custom resource is explained by.
+const DISKPOOL_NAMES: [&str; 3] = ["diskpool", "diskpools", "dsp"];
+
+/// REST resources which can be explained, as the names they are explained by and the name of
+/// their schema in the REST api specification.
+const REST_RESOURCES: [(&[&str], &str); 7] = [
+    (&["volume", "volumes", "vol"], "Volume"),
+    (&["pool", "pools"], "Pool"),
+    (&["node", "nodes"], "Node"),
+    (&["replica", "replicas"], "Replica"),
+    (&["nexus", "nexuses"], "Nexus"),
+    (
+        &[
+            "volume-snapshot",
+            "volume-snapshots",
+            "snapshot",
+            "snapshots",
+        ],
+        "VolumeSnapshot",
+    ),
+    (
+        &[
+            "block-device",
+            "block-devices",
+            "blockdevice",
+            "blockdevices",
+        ],
+        "BlockDevice",
+    ),
+];
+
+/// Maximum depth of the fields printed with `--recursive`, which bounds recursive schemas.
+const MAX_RECURSION_DEPTH: usize = 10;
+
+/// Width the descriptions are wrapped at.
+const WRAP_WIDTH: usize = 80;
+
+/// Document the fields of the DiskPool custom resource or of a REST resource, along with their
+/// types and constraints, from the schemas embedded in the plugin, so that it works offline.
+#[derive(clap::Args, Debug)]
+pub struct ExplainArgs {
+    /// The resource, or the path of one of its fields, to explain, eg: diskpool or
+    /// diskpool.spec.topology. The DiskPool custom resource and the volume, pool, node, replica,
+    /// nexus, volume-snapshot and block-device REST resources can be explained.
+    #[clap(value_name = "RESOURCE[.FIELD]")]
+    resource: String,
+    /// Print the fields of the nested objects as well, recursively.
+    #[clap(long)]
+    recursive: bool,
+}
+
+/// Schema of an explained resource, along with the specification its references resolve in.
+struct ResourceSchema {
+    kind: String,
+    /// Where the schema comes from, eg: the group and version of the custom resource.
+    origin: (&'static str, String),
+    schema: Value,
+    spec: Option<Value>,
+}
+
+impl ExplainArgs {
+    /// Print the documentation of the resource or of its field.
+    pub fn explain(&self) {
+        if let Err(error) = self.execute() {
+            eprintln!("Failed to explain {}. Error {error}", self.resource);
+            std::process::exit(1);
+        }
+    }
+
+    fn execute(&self) -> Result<()> {
+        let mut path = self.resource.split('.');
+        let name = path.next().unwrap_or_default().to_lowercase();
+        let resource = resource_schema(&name)?;
+
+        let mut schema = resource.resolve(&resource.schema);
+        let mut field = None;
+        for segment in path {
+            let object = resource.resolve(resource.items(schema));
+            let property = object
+                .get("properties")
+                .and_then(|properties| properties.get(segment))
+                .ok_or_else(|| anyhow!("field \"{segment}\" does not exist"))?;
+            field = Some((segment.to_string(), resource.type_name(property)));
+            schema = resource.resolve(property);
+        }
+
+        println!("KIND:     {}", resource.kind);
+        println!(
+            "{:<10}{}",
+            format!("{}:", resource.origin.0),
+            resource.origin.1
+        );
+        println!();
+        if let Some((name, type_name)) = field {
+            println!("FIELD:    {name} <{type_name}>");
+            println!();
+        }
+        println!("DESCRIPTION:");
+        match description(schema) {
+            Some(description) => print_wrapped(description, 5),
+            None => println!("     <empty>"),
+        }
+        let constraints = constraints(schema);
+        if !constraints.is_empty() {
+            println!();
+            println!("CONSTRAINTS:");
+            print_wrapped(&constraints.join(", "), 5);
+        }
+
+        let object = resource.resolve(resource.items(schema));
+        if object.get("properties").is_some() {
+            println!();
+            println!("FIELDS:");
+            resource.print_fields(object, 3, self.recursive, 0);
+        }
+        Ok(())
+    }
+}
+
+/// Get the schema of the resource with the given name.
+fn resource_schema(name: &str) -> Result<ResourceSchema> {
+    if DISKPOOL_NAMES.contains(&name) {
+        let crd = DiskPool::crd();
+        let version = crd
+            .spec
+            .versions
+            .iter()
+            .find(|version| version.storage)
+            .or_else(|| crd.spec.versions.first())
+            .ok_or_else(|| anyhow!("the DiskPool CRD has no version"))?;
+        let schema = version
+            .schema
+            .as_ref()
+            .and_then(|schema| schema.open_api_v3_schema.as_ref())
+            .ok_or_else(|| anyhow!("the DiskPool CRD has no schema"))?;
+        return Ok(ResourceSchema {
+            kind: crd.spec.names.kind.clone(),
+            origin: ("VERSION", format!("{}/{}", crd.spec.group, version.name)),
+            schema: serde_yaml::to_value(schema)?,
+            spec: None,
+        });
+    }
+
+    let (_, schema_name) = REST_RESOURCES
+        .iter()
+        .find(|(names, _)| names.contains(&name))
+        .ok_or_else(|| {
+            anyhow!(
+                "the resource type is not known, it must be one of: {}",
+                known_resources()
+            )
+        })?;
+    let spec: Value = serde_yaml::from_str(REST_API_SPEC)?;
+    let schema = schema_ref(&spec, schema_name)
+        .cloned()
+        .ok_or_else(|| anyhow!("the REST api has no {schema_name} schema"))?;
+    Ok(ResourceSchema {
+        kind: schema_name.to_string(),
+        origin: ("REST API", REST_API_VERSION.to_string()),
+        schema,
+        spec: Some(spec),
+    })
+}
+
+/// Names of the resources which can be explained.
+fn known_resources() -> String {
+    std::iter::once(DISKPOOL_NAMES[0])
+        .chain(REST_RESOURCES.iter().map(|(names, _)| names[0]))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Get the schema with the given name from the components of the specification.
+fn schema_ref<'a>(spec: &'a Value, name: &str) -> Option<&'a Value> {
+    spec.get("components")?.get("schemas")?.get(name)
+}
+
+impl ResourceSchema {
+    /// Resolve the `$ref` of the schema, or of its single `allOf` schema, if any.
+    fn resolve<'a>(&'a self, schema: &'a Value) -> &'a Value {
+        let mut schema = schema;
+        for _ in 0 .. MAX_RECURSION_DEPTH {
+            if let Some(reference) = ref_name(schema) {
+                match self
+                    .spec
+                    .as_ref()
+                    .and_then(|spec| schema_ref(spec, reference))
+                {
+                    Some(referenced) => schema = referenced,
+                    None => break,
+                }
+            } else if let Some([single]) = schema
+                .get("allOf")
+                .and_then(Value::as_sequence)
+                .map(Vec::as_slice)
+            {
+                schema = single;
+            } else {
+                break;
+            }
+        }
+        schema
+    }
+
+    /// The schema of the items of the arrays, recursively, or the schema itself.
+    fn items<'a>(&'a self, schema: &'a Value) -> &'a Value {
+        let mut schema = schema;
+        for _ in 0 .. MAX_RECURSION_DEPTH {
+            match self.resolve(schema).get("items") {
+                Some(items) => schema = items,
+                None => break,
+            }
+        }
+        schema
+    }
+
+    /// Name of the type of the schema, in the notation of kubectl explain, eg: []string.
+    fn type_name(&self, schema: &Value) -> String {
+        if let Some(reference) = ref_name(schema) {
+            return reference.to_string();
+        }
+        let schema = self.resolve(schema);
+        if let Some(reference) = ref_name(schema) {
+            return reference.to_string();
+        }
+        match schema.get("type").and_then(Value::as_str) {
+            Some("array") => match schema.get("items") {
+                Some(items) => format!("[]{}", self.type_name(items)),
+                None => "[]Object".to_string(),
+            },
+            Some("object") | None => match schema.get("additionalProperties") {
+                Some(values) if values.is_mapping() => {
+                    format!("map[string]{}", self.type_name(values))
+                }
+                _ => "Object".to_string(),
+            },
+            Some(other) => other.to_string(),
+        }
+    }
+
+    /// Print the fields of the object, with their type, description and constraints, or only
+    /// their type and nested fields when printed recursively.
+    fn print_fields(&self, object: &Value, indent: usize, recursive: bool, depth: usize) {
+        let Some(properties) = object.get("properties").and_then(Value::as_mapping) else {
+            return;
+        };
+        let required = object
+            .get("required")
+            .and_then(Value::as_sequence)
+            .map(|required| {
+                required
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        for (name, property) in properties {
+            let Some(name) = name.as_str() else {
+                continue;
+            };
+            let required = match required.contains(&name) {
+                true => " -required-",
+                false => "",
+            };
+            println!(
+                "{:indent$}{name}\t<{}>{required}",
+                "",
+                self.type_name(property)
+            );
+            if recursive {
+                if depth < MAX_RECURSION_DEPTH {
+                    let nested = self.resolve(self.items(property));
+                    self.print_fields(nested, indent + 3, recursive, depth + 1);
+                }
+                continue;
+            }
+            let resolved = self.resolve(property);
+            match description(property).or_else(|| description(resolved)) {
+                Some(description) => print_wrapped(description, indent + 2),
+                None => println!("{:width$}<empty>", "", width = indent + 2),
+            }
+            let constraints = constraints(resolved);
+            if !constraints.is_empty() {
+                print_wrapped(
+                    &format!("Constraints: {}", constraints.join(", ")),
+                    indent + 2,
+                );
+            }
+            println!();
+        }
+    }
+}
+
+/// Name of the schema the `$ref` of the schema points to, if any.
+fn ref_name(schema: &Value) -> Option<&str> {
+    schema
+        .get("$ref")
+        .and_then(Value::as_str)
+        .and_then(|reference| reference.rsplit('/').next())
+}
+
+/// Description of the schema, if any.
+fn description(schema: &Value) -> Option<&str> {
+    schema
+        .get("description")
+        .and_then(Value::as_str)
+        .filter(|description| !description.trim().is_empty())
+}
+
+/// Constraints of the schema on its values, eg: its minimum or its allowed values.
+fn constraints(schema: &Value) -> Vec<String> {
+    let mut constraints = Vec::new();
+    if let Some(values) = schema.get("enum").and_then(Value::as_sequence) {
+        let values = values.iter().map(scalar).collect::<Vec<_>>();
+        constraints.push(format!("one of [{}]", values.join(", ")));
+    }
+    for (key, label) in [
+        ("format", "format"),
+        ("pattern", "pattern"),
+        ("minimum", "minimum"),
+        ("maximum", "maximum"),
+        ("minLength", "minimum length"),
+        ("maxLength", "maximum length"),
+        ("minItems", "minimum items"),
+        ("maxItems", "maximum items"),
+        ("default", "default"),
+    ] {
+        if let Some(value) = schema.get(key) {
+            constraints.push(format!("{label}: {}", scalar(value)));
+        }
+    }
+    constraints
+}
+
+/// Render a scalar value of a schema, eg: an enum value or a default.
+fn scalar(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.clone(),
+        other => serde_yaml::to_string(other)
+            .map(|value| value.trim().to_string())
+            .unwrap_or_default(),
+    }
+}
+
+/// Print the text wrapped at the wrap width, with every line indented.
+fn print_wrapped(text: &str, indent: usize) {
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            if !line.is_empty() && indent + line.len() + 1 + word.len() > WRAP_WIDTH {
+                println!("{:indent$}{line}", "");
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+        println!("{:indent$}{line}", "");
+    }
+}
@@ -0,0 +1,274 @@
+use crate::resources::placement::list_volumes;
+use anyhow::anyhow;
+use clap::{ArgMatches, Command, FromArgMatches};
+use openapi::models::{Node, Pool, Volume};
+use plugin::{
+    resources::utils::{print_table, OutputFormat},
+    rest_wrapper::RestClient,
+};
+use std::cmp::Ordering;
+
+/// The `get` subcommands of the REST resources which can be sorted and filtered.
+const LISTINGS: [&str; 3] = ["volumes", "pools", "nodes"];
+
+/// Add the sorting and filtering arguments to the listings of the REST resources, which are
+/// declared by the REST plugin, so they can't be added to their arguments.
+pub(crate) fn augment_listings(command: Command) -> Command {
+    command.mut_subcommand("get", |get| {
+        LISTINGS.into_iter().fold(get, |get, listing| {
+            get.mut_subcommand(listing, <ListFilterArgs as clap::Args>::augment_args)
+        })
+    })
+}
+
+/// Sorting and filtering of the volumes, pools and nodes, evaluated on the REST results.
+#[derive(clap::Args, Debug, Default, Clone)]
+pub struct ListFilterArgs {
+    /// Sort by the given field, eg: `--sort-by size`. The numeric fields are sorted by value.
+    #[clap(long)]
+    sort_by: Option<String>,
+
+    /// Only list the resources whose field has, or with `!=` hasn't, the given value, eg:
+    /// `--selector status=Degraded`. Can be given multiple times, all must match.
+    #[clap(long = "selector", value_delimiter = ',', value_parser = parse_selector)]
+    selectors: Vec<Selector>,
+
+    /// Only list the resources on the given node, i.e. the volumes with their target or a
+    /// replica on it, the pools of the node or the node itself.
+    #[clap(long)]
+    node: Option<String>,
+}
+
+/// A selector of the resources on the value of a field.
+#[derive(Debug, Clone)]
+pub struct Selector {
+    field: String,
+    value: String,
+    negated: bool,
+}
+
+/// Parse a `field=value` or `field!=value` selector.
+fn parse_selector(selector: &str) -> Result<Selector, String> {
+    let (field, value, negated) = match selector.split_once("!=") {
+        Some((field, value)) => (field, value, true),
+        None => match selector.split_once('=') {
+            Some((field, value)) => (field, value, false),
+            None => return Err("the selector must be <field>=<value> or <field>!=<value>".into()),
+        },
+    };
+    Ok(Selector {
+        field: field.trim().to_lowercase(),
+        value: value.trim().to_string(),
+        negated,
+    })
+}
+
+/// A listed resource, whose fields can be selected and sorted by.
+pub(crate) trait Listed {
+    /// The fields of the resource.
+    const FIELDS: &'static [&'static str];
+
+    /// The value of the field of the resource.
+    fn field(&self, field: &str) -> Option<String>;
+
+    /// Whether the resource is on the node.
+    fn is_on_node(&self, node: &str) -> bool;
+}
+
+impl Listed for Volume {
+    const FIELDS: &'static [&'static str] = &[
+        "id",
+        "status",
+        "replicas",
+        "size",
+        "target-node",
+        "protocol",
+        "thin",
+    ];
+
+    fn field(&self, field: &str) -> Option<String> {
+        let target = self.state.target.as_ref();
+        match field {
+            "id" => Some(self.spec.uuid.to_string()),
+            "status" => Some(format!("{:?}", self.state.status)),
+            "replicas" => Some(self.spec.num_replicas.to_string()),
+            "size" => Some(self.spec.size.to_string()),
+            "target-node" => target.map(|target| target.node.clone()),
+            "protocol" => target.map(|target| format!("{:?}", target.protocol)),
+            "thin" => Some(self.spec.thin.to_string()),
+            _ => None,
+        }
+    }
+
+    fn is_on_node(&self, node: &str) -> bool {
+        self.state
+            .target
+            .as_ref()
+            .is_some_and(|target| target.node == node)
+            || self
+                .state
+                .replica_topology
+                .values()
+                .any(|topology| topology.node.as_deref() == Some(node))
+    }
+}
+
+impl Listed for Pool {
+    const FIELDS: &'static [&'static str] =
+        &["id", "status", "node", "capacity", "used", "available"];
+
+    fn field(&self, field: &str) -> Option<String> {
+        let state = self.state.as_ref();
+        match field {
+            "id" => Some(self.id.clone()),
+            "status" => state.map(|state| format!("{:?}", state.status)),
+            "node" => pool_node(self).map(str::to_string),
+            "capacity" => state.map(|state| state.capacity.to_string()),
+            "used" => state.map(|state| state.used.to_string()),
+            "available" => state.map(|state| state.capacity.saturating_sub(state.used).to_string()),
+            _ => None,
+        }
+    }
+
+    fn is_on_node(&self, node: &str) -> bool {
+        pool_node(self) == Some(node)
+    }
+}
+
+/// The node of the pool, from its state or otherwise from its spec.
+fn pool_node(pool: &Pool) -> Option<&str> {
+    match (&pool.state, &pool.spec) {
+        (Some(state), _) => Some(state.node.as_str()),
+        (None, Some(spec)) => Some(spec.node.as_str()),
+        (None, None) => None,
+    }
+}
+
+impl Listed for Node {
+    const FIELDS: &'static [&'static str] = &["id", "status", "grpc-endpoint"];
+
+    fn field(&self, field: &str) -> Option<String> {
+        let state = self.state.as_ref();
+        match field {
+            "id" => Some(self.id.clone()),
+            "status" => state.map(|state| format!("{:?}", state.status)),
+            "grpc-endpoint" => state.map(|state| state.grpc_endpoint.clone()),
+            _ => None,
+        }
+    }
+
+    fn is_on_node(&self, node: &str) -> bool {
+        self.id == node
+    }
+}
+
+impl ListFilterArgs {
+    /// The sorting and filtering arguments of the listing of the REST resources, if it's the
+    /// command.
+    pub(crate) fn from_listing(matches: &ArgMatches) -> Self {
+        matches
+            .subcommand_matches("get")
+            .and_then(ArgMatches::subcommand)
+            .filter(|(listing, _)| LISTINGS.contains(listing))
+            .and_then(|(_, matches)| Self::from_arg_matches(matches).ok())
+            .unwrap_or_default()
+    }
+
+    /// Whether neither a sorting nor a filter is given.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.sort_by.is_none() && self.selectors.is_empty() && self.node.is_none()
+    }
+
+    /// Check that the fields of the sorting and of the selectors are fields of the resource,
+    /// exiting otherwise.
+    pub(crate) fn check_fields<T: Listed>(&self) {
+        let unknown = self
+            .selectors
+            .iter()
+            .map(|selector| selector.field.as_str())
+            .chain(self.sort_by.as_deref())
+            .find(|field| !T::FIELDS.contains(field));
+        if let Some(field) = unknown {
+            eprintln!(
+                "Unknown field '{field}', the fields are: {}",
+                T::FIELDS.join(", ")
+            );
+            std::process::exit(1);
+        }
+    }
+
+    /// Filter and sort the resources.
+    pub(crate) fn apply<T: Listed>(&self, resources: Vec<T>) -> Vec<T> {
+        let mut resources = resources
+            .into_iter()
+            .filter(|resource| {
+                self.node
+                    .as_deref()
+                    .map_or(true, |node| resource.is_on_node(node))
+            })
+            .filter(|resource| {
+                self.selectors.iter().all(|selector| {
+                    let matches = resource
+                        .field(&selector.field)
+                        .is_some_and(|value| value.eq_ignore_ascii_case(&selector.value));
+                    matches != selector.negated
+                })
+            })
+            .collect::<Vec<_>>();
+        if let Some(field) = &self.sort_by {
+            resources.sort_by(|a, b| compare(a.field(field), b.field(field)));
+        }
+        resources
+    }
+
+    /// Print the volumes, filtered and sorted, as `get volumes` does.
+    pub(crate) async fn volumes(&self, output: &OutputFormat) {
+        self.check_fields::<Volume>();
+        match list_volumes().await {
+            Ok(volumes) => print_table(output, self.apply(volumes)),
+            Err(error) => eprintln!("Failed to list the volumes. Error {error}"),
+        }
+    }
+
+    /// Print the pools, filtered and sorted, as `get pools` does.
+    pub(crate) async fn pools(&self, output: &OutputFormat) {
+        self.check_fields::<Pool>();
+        let pools = RestClient::client()
+            .pools_api()
+            .get_pools()
+            .await
+            .map_err(|error| anyhow!("{error}"));
+        match pools {
+            Ok(pools) => print_table(output, self.apply(pools.into_body())),
+            Err(error) => eprintln!("Failed to list the pools. Error {error}"),
+        }
+    }
+
+    /// Print the nodes, filtered and sorted, as `get nodes` does.
+    pub(crate) async fn nodes(&self, output: &OutputFormat) {
+        self.check_fields::<Node>();
+        let nodes = RestClient::client()
+            .nodes_api()
+            .get_nodes(None)
+            .await
+            .map_err(|error| anyhow!("{error}"));
+        match nodes {
+            Ok(nodes) => print_table(output, self.apply(nodes.into_body())),
+            Err(error) => eprintln!("Failed to list the nodes. Error {error}"),
+        }
+    }
+}
+
+/// Compare the values of a field, numerically if both are numbers. The resources without the
+/// field are sorted last.
+fn compare(a: Option<String>, b: Option<String>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => match (a.parse::<u64>(), b.parse::<u64>()) {
+            (Ok(a), Ok(b)) => a.cmp(&b),
+            _ => a.cmp(&b),
+        },
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
@@ -0,0 +1,423 @@
+use crate::resources::placement::print_output;
+use anyhow::{anyhow, bail, Result};
+use openapi::models::{ChildState, Volume};
+use plugin::{
+    resources::{utils::OutputFormat, VolumeId},
+    rest_wrapper::RestClient,
+};
+use serde::Serialize;
+use std::{
+    collections::BTreeSet,
+    time::{Duration, Instant},
+};
+
+/// Label of the cordons placed while migrating a replica, so that the new replica is only
+/// scheduled on the destination node, and of the drain of the source node when moving the target.
+const MIGRATE_LABEL: &str = "migrate-volume";
+
+/// Interval at which the rebuild of the new replica is polled.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Migrate resources.
+#[derive(clap::Subcommand, Debug)]
+pub enum MigrateResources {
+    /// Migrate a replica of a volume to another pool, by adding a replica on the destination
+    /// pool, waiting for its rebuild and then removing the source replica.
+    Volume(MigrateVolumeArgs),
+}
+
+/// Migrate a replica of a volume from a node or a pool to another pool.
+#[derive(clap::Args, Debug)]
+pub struct MigrateVolumeArgs {
+    /// Id of the volume.
+    id: VolumeId,
+
+    /// Node of the replica to migrate.
+    #[clap(
+        long,
+        required_unless_present = "from_pool",
+        conflicts_with = "from_pool"
+    )]
+    from_node: Option<String>,
+
+    /// Pool of the replica to migrate.
+    #[clap(long)]
+    from_pool: Option<String>,
+
+    /// Pool to migrate the replica to.
+    #[clap(long)]
+    to_pool: String,
+
+    /// Also move the target of the volume off the source node, by draining the node. This
+    /// moves the targets of all the volumes of the node.
+    #[clap(long)]
+    move_target: bool,
+
+    /// Maximum time to wait for the rebuild of the new replica, after which the source replica
+    /// is kept, and for the target to be moved.
+    #[clap(long, default_value = "1h")]
+    rebuild_timeout: humantime::Duration,
+}
+
+/// Outcome of the migration of a replica.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct MigrationSummary {
+    volume: String,
+    source_replica: String,
+    source_pool: String,
+    source_node: String,
+    replica: String,
+    pool: String,
+    node: String,
+    target_node: Option<String>,
+}
+
+/// Replica of the volume, along with its placement.
+#[derive(Debug, Clone)]
+struct Placement {
+    replica: String,
+    pool: String,
+    node: String,
+}
+
+impl MigrateResources {
+    /// Execute the migration.
+    pub async fn migrate(&self, output: &OutputFormat) {
+        match self {
+            MigrateResources::Volume(args) => args.migrate(output).await,
+        }
+    }
+}
+
+impl MigrateVolumeArgs {
+    /// Migrate the replica, reporting the progress of every step.
+    pub async fn migrate(&self, output: &OutputFormat) {
+        match self.execute(output).await {
+            Ok(summary) => {
+                let _ = print_output(&[summary], output, |_| {});
+            }
+            Err(error) => {
+                eprintln!("Failed to migrate the volume. Error {error}");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    async fn execute(&self, output: &OutputFormat) -> Result<MigrationSummary> {
+        let volume = get_volume(&self.id).await?;
+        let Some(target) = &volume.state.target else {
+            bail!(
+                "volume {} is not published, its new replica could not be rebuilt",
+                self.id
+            );
+        };
+        if target
+            .children
+            .iter()
+            .any(|child| child.rebuild_progress.is_some())
+        {
+            bail!("a replica of volume {} is already being rebuilt", self.id);
+        }
+
+        let replicas = placements(&volume);
+        let source = self.source_replica(&replicas)?;
+        let destination_node = pool_node(&self.to_pool).await?;
+        if let Some(replica) = replicas.iter().find(|r| r.node == destination_node) {
+            bail!(
+                "node {destination_node} of pool {} already holds replica {} of the volume",
+                self.to_pool,
+                replica.replica
+            );
+        }
+
+        progress(
+            output,
+            format!(
+                "Adding a replica to volume {} on pool {} of node {destination_node}",
+                self.id, self.to_pool
+            ),
+        );
+        let replica = add_replica(&volume, &replicas, &destination_node).await?;
+        if replica.pool != self.to_pool {
+            let pool = &replica.pool;
+            let message = format!("The replica was placed on pool {pool} of the destination node");
+            progress(output, format!("{message}, as it has several pools"));
+        }
+
+        progress(
+            output,
+            format!("Waiting for the rebuild of replica {}", replica.replica),
+        );
+        self.wait_for_rebuild(&replica.replica, output).await?;
+
+        progress(
+            output,
+            format!(
+                "Removing replica {} from pool {} of node {}",
+                source.replica, source.pool, source.node
+            ),
+        );
+        let volume = remove_replica(&self.id, &source).await?;
+
+        let mut target_node = volume.state.target.as_ref().map(|t| t.node.clone());
+        if self.move_target && target_node.as_deref() == Some(source.node.as_str()) {
+            progress(
+                output,
+                format!("Moving the target off node {} by draining it", source.node),
+            );
+            target_node = Some(self.move_target(&source.node).await?);
+            let node = &source.node;
+            let uncordon = format!("kubectl mayastor uncordon node {node} {MIGRATE_LABEL}");
+            progress(
+                output,
+                format!("Node {node} stays drained, uncordon it with: {uncordon}"),
+            );
+        }
+
+        progress(
+            output,
+            format!(
+                "Migrated the replica of volume {} from pool {} to pool {}",
+                self.id, source.pool, replica.pool
+            ),
+        );
+        Ok(MigrationSummary {
+            volume: self.id.to_string(),
+            source_replica: source.replica,
+            source_pool: source.pool,
+            source_node: source.node,
+            replica: replica.replica,
+            pool: replica.pool,
+            node: replica.node,
+            target_node,
+        })
+    }
+
+    /// Find the replica to migrate, which must be unique.
+    fn source_replica(&self, replicas: &[Placement]) -> Result<Placement> {
+        let matches = replicas
+            .iter()
+            .filter(|replica| match (&self.from_node, &self.from_pool) {
+                (_, Some(pool)) => &replica.pool == pool,
+                (Some(node), None) => &replica.node == node,
+                (None, None) => false,
+            })
+            .collect::<Vec<_>>();
+        match matches.as_slice() {
+            [replica] => Ok((*replica).clone()),
+            [] => bail!(
+                "volume {} has no replica on the given node or pool",
+                self.id
+            ),
+            _ => bail!(
+                "volume {} has several replicas on the given node, select one with --from-pool",
+                self.id
+            ),
+        }
+    }
+
+    /// Wait until the replica is an online child of the target, reporting the rebuild progress.
+    async fn wait_for_rebuild(&self, replica: &str, output: &OutputFormat) -> Result<()> {
+        let start = Instant::now();
+        let mut last_progress = None;
+        loop {
+            let volume = get_volume(&self.id).await?;
+            let child = volume
+                .state
+                .target
+                .as_ref()
+                .and_then(|target| target.children.iter().find(|c| c.uri.contains(replica)));
+            if let Some(child) = child {
+                if child.rebuild_progress.is_none() && child.state == ChildState::Online {
+                    progress(output, format!("Replica {replica} is rebuilt"));
+                    return Ok(());
+                }
+                if child.rebuild_progress.is_some() && child.rebuild_progress != last_progress {
+                    progress(
+                        output,
+                        format!(
+                            "Rebuilding replica {replica}: {}%",
+                            child.rebuild_progress.unwrap_or_default()
+                        ),
+                    );
+                    last_progress = child.rebuild_progress;
+                }
+            }
+            if start.elapsed() > *self.rebuild_timeout {
+                bail!(
+                    "replica {replica} was not rebuilt within {}, the source replica is kept",
+                    self.rebuild_timeout
+                );
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    /// Drain the source node, which moves the targets of its volumes to other nodes, and wait
+    /// until the target of the volume is moved. Returns the node of the target.
+    async fn move_target(&self, node: &str) -> Result<String> {
+        RestClient::client()
+            .nodes_api()
+            .put_node_drain(node, MIGRATE_LABEL)
+            .await
+            .map_err(|error| anyhow!("failed to drain node {node}: {error}"))?;
+        let start = Instant::now();
+        loop {
+            let volume = get_volume(&self.id).await?;
+            match volume.state.target.map(|target| target.node) {
+                Some(target_node) if target_node != node => return Ok(target_node),
+                _ if start.elapsed() > *self.rebuild_timeout => {
+                    bail!(
+                        "the target of volume {} was not moved off node {node}",
+                        self.id
+                    )
+                }
+                _ => tokio::time::sleep(POLL_INTERVAL).await,
+            }
+        }
+    }
+}
+
+/// Report the progress of the migration, on stderr when the summary is printed on stdout.
+fn progress(output: &OutputFormat, message: String) {
+    match output {
+        OutputFormat::Yaml | OutputFormat::Json => eprintln!("{message}"),
+        _ => println!("{message}"),
+    }
+}
+
+/// Get the volume.
+async fn get_volume(id: &VolumeId) -> Result<Volume> {
+    Ok(RestClient::client()
+        .volumes_api()
+        .get_volume(id)
+        .await
+        .map_err(|error| anyhow!("failed to get volume {id}: {error}"))?
+        .into_body())
+}
+
+/// Get the node of the pool, which must be online.
+async fn pool_node(pool: &str) -> Result<String> {
+    let pool = RestClient::client()
+        .pools_api()
+        .get_pool(pool)
+        .await
+        .map_err(|error| anyhow!("failed to get pool {pool}: {error}"))?
+        .into_body();
+    match pool.state {
+        Some(state) => Ok(state.node),
+        None => bail!("pool {} is not online", pool.id),
+    }
+}
+
+/// Get the placement of the replicas of the volume.
+fn placements(volume: &Volume) -> Vec<Placement> {
+    volume
+        .state
+        .replica_topology
+        .iter()
+        .map(|(replica, topology)| Placement {
+            replica: replica.clone(),
+            pool: topology.pool.clone().unwrap_or_default(),
+            node: topology.node.clone().unwrap_or_default(),
+        })
+        .collect()
+}
+
+/// Add a replica to the volume on the destination node, by scaling the volume up while the
+/// other nodes are cordoned. The cordons are always removed.
+async fn add_replica(
+    volume: &Volume,
+    replicas: &[Placement],
+    destination_node: &str,
+) -> Result<Placement> {
+    let nodes = RestClient::client()
+        .nodes_api()
+        .get_nodes(None)
+        .await
+        .map_err(|error| anyhow!("failed to list the nodes: {error}"))?
+        .into_body()
+        .into_iter()
+        .map(|node| node.id)
+        .filter(|node| node != destination_node)
+        .collect::<Vec<_>>();
+
+    let mut cordoned = Vec::new();
+    let mut result = Ok(());
+    for node in &nodes {
+        match RestClient::client()
+            .nodes_api()
+            .put_node_cordon(node, MIGRATE_LABEL)
+            .await
+        {
+            Ok(_) => cordoned.push(node),
+            Err(error) => {
+                result = Err(anyhow!("failed to cordon node {node}: {error}"));
+                break;
+            }
+        }
+    }
+    let result = match result {
+        Ok(()) => RestClient::client()
+            .volumes_api()
+            .put_volume_replica_count(&volume.spec.uuid, volume.spec.num_replicas + 1)
+            .await
+            .map(|volume| volume.into_body())
+            .map_err(|error| anyhow!("failed to scale the volume up: {error}")),
+        Err(error) => Err(error),
+    };
+    for node in cordoned {
+        if let Err(error) = RestClient::client()
+            .nodes_api()
+            .delete_node_cordon(node, MIGRATE_LABEL)
+            .await
+        {
+            let uncordon = format!("kubectl mayastor uncordon node {node} {MIGRATE_LABEL}");
+            eprintln!(
+                "Failed to uncordon node {node}, uncordon it with: {uncordon}. Error {error}"
+            );
+        }
+    }
+
+    let existing = replicas
+        .iter()
+        .map(|replica| replica.replica.as_str())
+        .collect::<BTreeSet<_>>();
+    placements(&result?)
+        .into_iter()
+        .find(|replica| !existing.contains(replica.replica.as_str()))
+        .ok_or_else(|| anyhow!("the new replica of the volume was not found"))
+}
+
+/// Remove the source replica from the target, so that it is the one removed when scaling the
+/// volume back down.
+async fn remove_replica(id: &VolumeId, source: &Placement) -> Result<Volume> {
+    let volume = get_volume(id).await?;
+    if let Some(target) = &volume.state.target {
+        if let Some(child) = target
+            .children
+            .iter()
+            .find(|child| child.uri.contains(&source.replica))
+        {
+            RestClient::client()
+                .children_api()
+                .del_nexus_child(&target.uuid, &child.uri)
+                .await
+                .map_err(|error| anyhow!("failed to remove the source replica: {error}"))?;
+        }
+    }
+    let volume = RestClient::client()
+        .volumes_api()
+        .put_volume_replica_count(id, volume.spec.num_replicas - 1)
+        .await
+        .map_err(|error| anyhow!("failed to scale the volume down: {error}"))?
+        .into_body();
+    if volume.state.replica_topology.contains_key(&source.replica) {
+        bail!(
+            "replica {} was kept while scaling the volume down, check the replicas of the volume",
+            source.replica
+        );
+    }
+    Ok(volume)
+}
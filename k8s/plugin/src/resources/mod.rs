@@ -1,7 +1,38 @@
 use clap::Parser;
-use plugin::resources::{CordonResources, DrainResources, GetResources, ScaleResources};
-use supportability::DumpArgs;
-use upgrade::plugin::upgrade::{DeleteResources, GetUpgradeArgs, UpgradeArgs};
+use plugin::resources::{CordonResources, GetResources};
+use supportability::{doctor::DoctorArgs, DumpArgs};
+use upgrade::plugin::{
+    simulate::UpgradeCommand,
+    upgrade::{DeleteResources, GetUpgradeArgs, UpgradeArgs},
+};
+
+pub mod block_devices;
+pub mod completions;
+pub mod drain;
+pub mod explain;
+pub mod filter;
+pub mod migrate;
+pub mod nvme_sessions;
+pub mod placement;
+pub mod scale;
+pub mod snapshots;
+pub mod status;
+pub mod usage;
+pub mod volumes_wide;
+pub mod watch;
+
+use block_devices::BlockDevicesWideArgs;
+use completions::{CompleteValuesArgs, CompletionsArgs};
+use drain::DrainResources;
+use explain::ExplainArgs;
+use migrate::MigrateResources;
+use nvme_sessions::NvmeSessionsArgs;
+use placement::VolumeReplicasArgs;
+use scale::{ResizeResources, ScaleResources};
+use snapshots::{SnapshotTopologyArgs, SnapshotsArgs};
+use status::StatusArgs;
+use usage::UsageArgs;
+use volumes_wide::VolumesWideArgs;
 
 #[derive(clap::Subcommand, Debug)]
 pub enum GetResourcesK8s {
@@ -9,6 +40,22 @@ pub enum GetResourcesK8s {
     Rest(GetResources),
     /// Get upgrade status
     UpgradeStatus(GetUpgradeArgs),
+    /// Get the replicas of all volumes, or audit their placement with `--placement`
+    VolumeReplicas(VolumeReplicasArgs),
+    /// Get the NVMe initiator sessions of the mayastor volumes on the given nodes
+    NvmeSessions(NvmeSessionsArgs),
+    /// Get the capacity usage of the volumes, in total or per StorageClass
+    Usage(UsageArgs),
+    /// Get the volumes along with the placement of their replicas, optionally only the given
+    /// columns
+    VolumesWide(VolumesWideArgs),
+    /// Get the volume snapshots along with the volumes cloned from them
+    Snapshots(SnapshotsArgs),
+    /// Get the topology of a volume snapshot, i.e. its replica snapshots and its clones
+    SnapshotTopology(SnapshotTopologyArgs),
+    /// Get the block devices of a node along with the pools using them, for the planning of the
+    /// pools
+    BlockDevicesWide(BlockDevicesWideArgs),
 }
 
 /// The types of operations that are supported.
@@ -23,6 +70,9 @@ pub enum Operations {
     /// 'Scale' resources.
     #[clap(subcommand)]
     Scale(ScaleResources),
+    /// 'Resize' resources.
+    #[clap(subcommand)]
+    Resize(ResizeResources),
     /// 'Cordon' resources.
     #[clap(subcommand)]
     Cordon(CordonResources),
@@ -36,4 +86,81 @@ pub enum Operations {
     /// `Delete` the upgrade resources.
     #[clap(subcommand)]
     Delete(DeleteResources),
+    /// `Doctor` runs connectivity and configuration checks.
+    Doctor(DoctorArgs),
+    /// 'Migrate' resources.
+    #[clap(subcommand)]
+    Migrate(MigrateResources),
+    /// `Explain` the fields of the DiskPool custom resource and of the REST resources.
+    Explain(ExplainArgs),
+    /// `Status` overview of the storage health of the cluster.
+    Status(StatusArgs),
+    /// Generate the shell `Completions` of the plugin.
+    Completions(CompletionsArgs),
+    /// Print the volume uuids, node names or pool ids, for the shell completions.
+    #[clap(hide = true)]
+    CompleteValues(CompleteValuesArgs),
+}
+
+impl Operations {
+    /// Whether the operation supports `--watch`, i.e. it's a listing of the volumes, the pools or
+    /// the nodes.
+    pub fn is_watchable(&self) -> bool {
+        matches!(
+            self,
+            Operations::Get(GetResourcesK8s::Rest(
+                GetResources::Volumes(_) | GetResources::Pools | GetResources::Nodes
+            ))
+        )
+    }
+
+    /// Whether the operation modifies the state of the cluster, as opposed to only requiring
+    /// get/list permissions.
+    pub fn is_mutating(&self) -> bool {
+        match self {
+            // The sessions are inspected through privileged pods created on the nodes.
+            Operations::Get(GetResourcesK8s::NvmeSessions(_)) => true,
+            Operations::Get(_)
+            | Operations::Dump(_)
+            | Operations::Doctor(_)
+            | Operations::Explain(_)
+            | Operations::Status(_)
+            | Operations::Completions(_)
+            | Operations::CompleteValues(_) => false,
+            Operations::Upgrade(args) => match args.command {
+                Some(UpgradeCommand::Simulate(_)) => false,
+                Some(
+                    UpgradeCommand::Rollback(_)
+                    | UpgradeCommand::Pause(_)
+                    | UpgradeCommand::Resume(_),
+                ) => true,
+                None => !args.dry_run,
+            },
+            Operations::Drain(_)
+            | Operations::Scale(_)
+            | Operations::Resize(_)
+            | Operations::Cordon(_)
+            | Operations::Uncordon(_)
+            | Operations::Delete(_)
+            | Operations::Migrate(_) => true,
+        }
+    }
+
+    /// Whether the operation runs without any access to the cluster, eg: the explain command,
+    /// whose schemas are embedded in the plugin.
+    pub fn is_offline(&self) -> bool {
+        match self {
+            Operations::Upgrade(args) => {
+                matches!(args.command, Some(UpgradeCommand::Simulate(_)))
+            }
+            Operations::Explain(_) | Operations::Completions(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Whether the operation uses the global REST client. The doctor checks the REST
+    /// connectivity by itself, so it must not fail early when the REST api is unreachable.
+    pub fn uses_rest_client(&self) -> bool {
+        !self.is_offline() && !matches!(self, Operations::Doctor(_))
+    }
 }
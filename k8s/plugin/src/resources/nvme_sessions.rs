@@ -0,0 +1,280 @@
+use crate::resources::placement::{list_volumes, print_output, print_table};
+use anyhow::Result;
+use k8s_openapi::api::core::v1::Pod;
+use kube::{
+    api::{DeleteParams, LogParams, PostParams},
+    Api, Client,
+};
+use openapi::models::Volume;
+use plugin::resources::utils::OutputFormat;
+use serde::Serialize;
+use serde_json::json;
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+/// Default image of the debug pods, it only needs a shell along with `nsenter`.
+const DEBUG_POD_IMAGE: &str = "busybox:1.36";
+
+/// Prefix of the names of the debug pods, the rest of the name is generated.
+const DEBUG_POD_PREFIX: &str = "mayastor-nvme-sessions-";
+
+/// Time after which a debug pod which did not complete is given up on.
+const DEBUG_POD_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Interval at which the completion of the debug pods is polled.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Prefix of the lines printed by the debug pod for every session.
+const SESSION_MARKER: &str = "session|";
+
+/// Script run in the host's mount namespace, which prints a line per NVMe controller connected
+/// to a mayastor subsystem: the controller, the subsystem nqn, the transport, the address, the
+/// state, the ANA states of its paths and the number of reconnect messages in the kernel log.
+const SESSIONS_SCRIPT: &str = r#"
+for ctrl in /sys/class/nvme/nvme*; do
+  nqn=$(cat "$ctrl/subsysnqn" 2>/dev/null)
+  case "$nqn" in *io.openebs*) ;; *) continue ;; esac
+  name=${ctrl##*/}
+  ana=$(cat "$ctrl"/nvme*c*n*/ana_state 2>/dev/null | sort -u | tr '\n' ',')
+  reconnects=$(dmesg | grep -c "$name: .*econnect")
+  echo "session|$name|$nqn|$(cat "$ctrl/transport")|$(tr '\n' ' ' < "$ctrl/address")|$(cat "$ctrl/state")|${ana%,}|$reconnects"
+done
+"#;
+
+/// Get the NVMe initiator sessions of the mayastor volumes on the given nodes.
+#[derive(clap::Args, Debug)]
+pub struct NvmeSessionsArgs {
+    /// Name of a kubernetes node whose sessions are listed, eg: a node running the applications.
+    /// Can be given multiple times.
+    #[clap(long = "node", required = true, value_name = "NODE")]
+    nodes: Vec<String>,
+    /// Image of the privileged debug pods run on the nodes, which must provide a shell along
+    /// with `nsenter`.
+    #[clap(long, default_value = DEBUG_POD_IMAGE)]
+    image: String,
+}
+
+/// NVMe initiator session of a node to the target of a volume.
+#[derive(Serialize, Debug)]
+struct NvmeSession {
+    node: String,
+    volume: String,
+    controller: String,
+    transport: String,
+    address: String,
+    state: String,
+    ana_state: String,
+    reconnects: u64,
+    volume_status: Option<String>,
+    target_node: Option<String>,
+}
+
+impl NvmeSessionsArgs {
+    /// List the NVMe initiator sessions of the nodes, correlated to the volumes.
+    pub async fn get(
+        &self,
+        kube_config_path: Option<PathBuf>,
+        namespace: &str,
+        output: &OutputFormat,
+    ) {
+        if let Err(error) = self.execute(kube_config_path, namespace, output).await {
+            eprintln!("Failed to get the NVMe sessions. Error {error}");
+            std::process::exit(1);
+        }
+    }
+
+    async fn execute(
+        &self,
+        kube_config_path: Option<PathBuf>,
+        namespace: &str,
+        output: &OutputFormat,
+    ) -> Result<()> {
+        let config = kube_proxy::config_from_kubeconfig(kube_config_path).await?;
+        let pods_api = Api::<Pod>::namespaced(Client::try_from(config)?, namespace);
+
+        let results = futures::future::join_all(
+            self.nodes
+                .iter()
+                .map(|node| node_sessions(&pods_api, node, &self.image)),
+        )
+        .await;
+        let mut sessions = Vec::new();
+        for (node, result) in self.nodes.iter().zip(results) {
+            match result {
+                Ok(node_sessions) => sessions.extend(node_sessions),
+                Err(error) => {
+                    eprintln!("Failed to get the NVMe sessions of node {node}. Error {error}")
+                }
+            }
+        }
+
+        // The sessions are still listed when the volumes cannot be, just without their status.
+        let volumes = match list_volumes().await {
+            Ok(volumes) => volumes
+                .into_iter()
+                .map(|volume| (volume.spec.uuid.to_string(), volume))
+                .collect(),
+            Err(error) => {
+                eprintln!("Failed to get the volumes. Error {error}");
+                HashMap::new()
+            }
+        };
+        for session in &mut sessions {
+            correlate(session, &volumes);
+        }
+        sessions.sort_by(|a, b| a.node.cmp(&b.node).then_with(|| a.volume.cmp(&b.volume)));
+        print_output(&sessions, output, print_sessions)
+    }
+}
+
+/// Run the debug pod on the node and parse the sessions it printed, the pod is always deleted.
+async fn node_sessions(pods_api: &Api<Pod>, node: &str, image: &str) -> Result<Vec<NvmeSession>> {
+    let pod: Pod = serde_json::from_value(debug_pod(node, image))?;
+    let pod = pods_api.create(&PostParams::default(), &pod).await?;
+    let name = pod.metadata.name.unwrap_or_default();
+
+    let result = match wait_for_completion(pods_api, &name).await {
+        Ok(()) => pods_api
+            .logs(&name, &LogParams::default())
+            .await
+            .map_err(anyhow::Error::from),
+        Err(error) => Err(error),
+    };
+    if let Err(error) = pods_api.delete(&name, &DeleteParams::default()).await {
+        eprintln!("Failed to delete the debug pod {name}. Error {error}");
+    }
+    Ok(parse_sessions(node, &result?))
+}
+
+/// Wait until the debug pod ran to completion.
+async fn wait_for_completion(pods_api: &Api<Pod>, name: &str) -> Result<()> {
+    let start = Instant::now();
+    loop {
+        let pod = pods_api.get(name).await?;
+        match pod.status.and_then(|status| status.phase).as_deref() {
+            Some("Succeeded") => return Ok(()),
+            Some("Failed") => anyhow::bail!("Debug pod {name} failed"),
+            _ if start.elapsed() > DEBUG_POD_TIMEOUT => anyhow::bail!(
+                "Debug pod {name} did not complete within {}",
+                humantime::format_duration(DEBUG_POD_TIMEOUT)
+            ),
+            _ => tokio::time::sleep(POLL_INTERVAL).await,
+        }
+    }
+}
+
+/// Parse the sessions printed by the debug pod of the node.
+fn parse_sessions(node: &str, output: &str) -> Vec<NvmeSession> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields = line
+                .strip_prefix(SESSION_MARKER)?
+                .split('|')
+                .collect::<Vec<_>>();
+            let [controller, nqn, transport, address, state, ana_state, reconnects] = fields[..]
+            else {
+                return None;
+            };
+            Some(NvmeSession {
+                node: node.to_string(),
+                // The nqn of the volume targets ends with the uuid of the volume.
+                volume: nqn.rsplit(':').next().unwrap_or(nqn).to_string(),
+                controller: controller.to_string(),
+                transport: transport.to_string(),
+                address: address.trim().to_string(),
+                state: state.to_string(),
+                ana_state: ana_state.to_string(),
+                reconnects: reconnects.trim().parse().unwrap_or_default(),
+                volume_status: None,
+                target_node: None,
+            })
+        })
+        .collect()
+}
+
+/// Fill in the status and the target node of the volume of the session.
+fn correlate(session: &mut NvmeSession, volumes: &HashMap<String, Volume>) {
+    let Some(volume) = volumes.get(&session.volume) else {
+        return;
+    };
+    session.volume_status = Some(format!("{:?}", volume.state.status));
+    session.target_node = volume
+        .state
+        .target
+        .as_ref()
+        .map(|target| target.node.clone());
+}
+
+/// Privileged pod pinned to the node, which shares the host's process namespace so that the
+/// script runs in the host's mount namespace with the host's view of sysfs.
+fn debug_pod(node: &str, image: &str) -> serde_json::Value {
+    json!({
+        "apiVersion": "v1",
+        "kind": "Pod",
+        "metadata": {
+            "generateName": DEBUG_POD_PREFIX,
+            "labels": { "app": "mayastor-nvme-sessions" },
+        },
+        "spec": {
+            "nodeName": node,
+            "hostPID": true,
+            "restartPolicy": "Never",
+            "tolerations": [{ "operator": "Exists" }],
+            "containers": [{
+                "name": "nvme-sessions",
+                "image": image,
+                "command": ["nsenter", "-t", "1", "-m", "--", "sh", "-c", SESSIONS_SCRIPT],
+                "securityContext": { "privileged": true },
+            }],
+        },
+    })
+}
+
+/// Print the sessions as a table.
+fn print_sessions(sessions: &[NvmeSession]) {
+    if sessions.is_empty() {
+        println!("No NVMe sessions to mayastor volumes found");
+        return;
+    }
+    let reconnects = sessions
+        .iter()
+        .map(|s| s.reconnects.to_string())
+        .collect::<Vec<_>>();
+    let rows = sessions
+        .iter()
+        .zip(reconnects.iter())
+        .map(|(s, reconnects)| {
+            [
+                s.node.as_str(),
+                s.volume.as_str(),
+                s.controller.as_str(),
+                s.transport.as_str(),
+                s.address.as_str(),
+                s.state.as_str(),
+                s.ana_state.as_str(),
+                reconnects.as_str(),
+                s.volume_status.as_deref().unwrap_or("<unknown>"),
+                s.target_node.as_deref().unwrap_or("<none>"),
+            ]
+        })
+        .collect::<Vec<_>>();
+    print_table(
+        [
+            "NODE",
+            "VOLUME",
+            "CONTROLLER",
+            "TRANSPORT",
+            "ADDRESS",
+            "STATE",
+            "ANA",
+            "RECONNECTS",
+            "VOLUME STATUS",
+            "TARGET NODE",
+        ],
+        &rows,
+    );
+}
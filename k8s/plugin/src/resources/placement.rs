@@ -0,0 +1,315 @@
+use anyhow::Result;
+use k8s_openapi::api::core::v1::Node as K8sNode;
+use kube::{api::ListParams, Api, Client};
+use openapi::models::Volume;
+use plugin::{resources::utils::OutputFormat, rest_wrapper::RestClient};
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    path::PathBuf,
+};
+
+/// Label of the kubernetes nodes which holds their zone.
+const ZONE_LABEL: &str = "topology.kubernetes.io/zone";
+
+/// The number of volumes to get per request.
+const MAX_ENTRIES: isize = 200;
+
+/// Get the replicas of all the volumes.
+#[derive(clap::Args, Debug)]
+pub struct VolumeReplicasArgs {
+    /// Audit the placement of the replicas against the topology rules, i.e. no two replicas of
+    /// a volume on the same node and the replicas spread across the zones, and only list the
+    /// offending volumes along with a suggested plan to fix them.
+    #[clap(long)]
+    placement: bool,
+}
+
+/// Placement of a replica of a volume.
+#[derive(Serialize, Debug)]
+struct ReplicaPlacement {
+    volume: String,
+    replica: String,
+    node: String,
+    pool: String,
+    zone: Option<String>,
+}
+
+/// Volume whose replicas violate the topology rules.
+#[derive(Serialize, Debug)]
+struct PlacementViolation {
+    volume: String,
+    replicas: u8,
+    violations: Vec<String>,
+    plan: Vec<String>,
+}
+
+impl VolumeReplicasArgs {
+    /// List the replicas of the volumes, or the volumes whose replicas violate the topology
+    /// rules when auditing their placement.
+    pub async fn get(&self, kube_config_path: Option<PathBuf>, output: &OutputFormat) {
+        if let Err(error) = self.execute(kube_config_path, output).await {
+            eprintln!("Failed to get the volume replicas. Error {error}");
+            std::process::exit(1);
+        }
+    }
+
+    async fn execute(
+        &self,
+        kube_config_path: Option<PathBuf>,
+        output: &OutputFormat,
+    ) -> Result<()> {
+        let volumes = list_volumes().await?;
+        // The zones are only used for the audit, the replicas are listed without them when the
+        // kubernetes nodes cannot be listed.
+        let zones = match node_zones(kube_config_path).await {
+            Ok(zones) => zones,
+            Err(error) if !self.placement => {
+                eprintln!("Failed to get the zones of the nodes. Error {error}");
+                HashMap::new()
+            }
+            Err(error) => return Err(error),
+        };
+        let replicas = volumes
+            .iter()
+            .flat_map(|volume| replica_placements(volume, &zones))
+            .collect::<Vec<_>>();
+
+        if !self.placement {
+            return print_output(&replicas, output, print_replicas);
+        }
+
+        let node_ids = list_node_ids().await?;
+        let total_zones = node_ids
+            .iter()
+            .filter_map(|node| zones.get(node))
+            .collect::<BTreeSet<_>>()
+            .len();
+        let violations = volumes
+            .iter()
+            .filter_map(|volume| {
+                let replicas = replicas
+                    .iter()
+                    .filter(|replica| replica.volume == volume.spec.uuid.to_string())
+                    .collect::<Vec<_>>();
+                audit_volume(volume, &replicas, total_zones)
+            })
+            .collect::<Vec<_>>();
+        print_output(&violations, output, print_violations)
+    }
+}
+
+/// List all the volumes, page by page.
+pub(crate) async fn list_volumes() -> Result<Vec<Volume>> {
+    let mut volumes = Vec::new();
+    let mut starting_token = Some(0_isize);
+    // The last paginated request will set the `starting_token` to `None`.
+    while starting_token.is_some() {
+        let page = RestClient::client()
+            .volumes_api()
+            .get_volumes(MAX_ENTRIES, None, starting_token)
+            .await
+            .map_err(|error| anyhow::anyhow!("{error}"))?
+            .into_body();
+        volumes.extend(page.entries);
+        starting_token = page.next_token;
+    }
+    Ok(volumes)
+}
+
+/// List the ids of the io-engine nodes.
+async fn list_node_ids() -> Result<Vec<String>> {
+    let nodes = RestClient::client()
+        .nodes_api()
+        .get_nodes(None)
+        .await
+        .map_err(|error| anyhow::anyhow!("{error}"))?
+        .into_body();
+    Ok(nodes.into_iter().map(|node| node.id).collect())
+}
+
+/// Get the zones of the kubernetes nodes, keyed by the node name.
+async fn node_zones(kube_config_path: Option<PathBuf>) -> Result<HashMap<String, String>> {
+    let config = kube_proxy::config_from_kubeconfig(kube_config_path).await?;
+    let client = Client::try_from(config)?;
+    let nodes = Api::<K8sNode>::all(client)
+        .list(&ListParams::default())
+        .await?;
+    Ok(nodes
+        .into_iter()
+        .filter_map(|node| {
+            let zone = node.metadata.labels?.remove(ZONE_LABEL)?;
+            Some((node.metadata.name?, zone))
+        })
+        .collect())
+}
+
+/// Get the placement of the replicas of the volume.
+fn replica_placements(volume: &Volume, zones: &HashMap<String, String>) -> Vec<ReplicaPlacement> {
+    let mut replicas = volume
+        .state
+        .replica_topology
+        .iter()
+        .map(|(replica, topology)| {
+            let node = topology.node.clone().unwrap_or_default();
+            ReplicaPlacement {
+                volume: volume.spec.uuid.to_string(),
+                replica: replica.clone(),
+                zone: zones.get(&node).cloned(),
+                node,
+                pool: topology.pool.clone().unwrap_or_default(),
+            }
+        })
+        .collect::<Vec<_>>();
+    replicas.sort_by(|a, b| a.node.cmp(&b.node).then_with(|| a.replica.cmp(&b.replica)));
+    replicas
+}
+
+/// Audit the placement of the replicas of the volume, returning the violations of the topology
+/// rules along with a suggested plan, if there are any.
+fn audit_volume(
+    volume: &Volume,
+    replicas: &[&ReplicaPlacement],
+    total_zones: usize,
+) -> Option<PlacementViolation> {
+    let mut violations = Vec::new();
+
+    let mut nodes = BTreeMap::<&str, Vec<&str>>::new();
+    for replica in replicas.iter().filter(|replica| !replica.node.is_empty()) {
+        nodes
+            .entry(replica.node.as_str())
+            .or_default()
+            .push(replica.replica.as_str());
+    }
+    for (node, node_replicas) in nodes.iter().filter(|(_, r)| r.len() > 1) {
+        violations.push(format!(
+            "{} replicas on the same node {node}: {}",
+            node_replicas.len(),
+            node_replicas.join(", ")
+        ));
+    }
+
+    let zones = replicas
+        .iter()
+        .filter_map(|replica| replica.zone.as_deref())
+        .collect::<BTreeSet<_>>();
+    let expected_zones = replicas.len().min(total_zones);
+    if total_zones > 1 && zones.len() < expected_zones {
+        violations.push(format!(
+            "{} replicas spread across {} of {total_zones} zones",
+            replicas.len(),
+            zones.len()
+        ));
+    }
+
+    if violations.is_empty() {
+        return None;
+    }
+
+    let uuid = volume.spec.uuid;
+    let num_replicas = volume.spec.num_replicas;
+    Some(PlacementViolation {
+        volume: uuid.to_string(),
+        replicas: num_replicas,
+        violations,
+        plan: vec![
+            format!(
+                "kubectl mayastor scale volume {uuid} {}, to add a replica on another node",
+                num_replicas.saturating_add(1)
+            ),
+            format!(
+                "wait for the rebuild of the new replica, eg: kubectl mayastor get volume {uuid}"
+            ),
+            format!(
+                "kubectl mayastor scale volume {uuid} {num_replicas}, to remove a replica again"
+            ),
+            "kubectl mayastor get volume-replicas --placement, to verify the placement".to_string(),
+        ],
+    })
+}
+
+/// Print the items in the given output format, or as a table.
+pub(crate) fn print_output<T: Serialize>(
+    items: &[T],
+    output: &OutputFormat,
+    print_table: fn(&[T]),
+) -> Result<()> {
+    match output {
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(items)?),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(items)?),
+        _ => print_table(items),
+    }
+    Ok(())
+}
+
+/// Print the replicas as a table.
+fn print_replicas(replicas: &[ReplicaPlacement]) {
+    let rows = replicas
+        .iter()
+        .map(|r| {
+            [
+                r.volume.as_str(),
+                r.replica.as_str(),
+                r.node.as_str(),
+                r.pool.as_str(),
+                r.zone.as_deref().unwrap_or("<none>"),
+            ]
+        })
+        .collect::<Vec<_>>();
+    print_table(["VOLUME", "REPLICA", "NODE", "POOL", "ZONE"], &rows);
+}
+
+/// Print the offending volumes as a table, followed by the suggested plans.
+fn print_violations(violations: &[PlacementViolation]) {
+    if violations.is_empty() {
+        println!("No replica placement violations found");
+        return;
+    }
+    let replicas = violations
+        .iter()
+        .map(|v| v.replicas.to_string())
+        .collect::<Vec<_>>();
+    let descriptions = violations
+        .iter()
+        .map(|v| v.violations.join("; "))
+        .collect::<Vec<_>>();
+    let rows = violations
+        .iter()
+        .zip(replicas.iter().zip(descriptions.iter()))
+        .map(|(v, (replicas, description))| {
+            [v.volume.as_str(), replicas.as_str(), description.as_str()]
+        })
+        .collect::<Vec<_>>();
+    print_table(["VOLUME", "REPLICAS", "VIOLATIONS"], &rows);
+
+    println!("\nSuggested plans:");
+    for violation in violations {
+        println!("- {}", violation.volume);
+        for (step, action) in violation.plan.iter().enumerate() {
+            println!("  {}. {action}", step + 1);
+        }
+    }
+}
+
+/// Print the rows as a table with left aligned columns.
+pub(crate) fn print_table<const N: usize>(header: [&str; N], rows: &[[&str; N]]) {
+    let mut widths = header.map(str::len);
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+    let format_row = |row: &[&str; N]| {
+        row.iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:width$}"))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+    println!("{}", format_row(&header));
+    for row in rows {
+        println!("{}", format_row(row));
+    }
+}
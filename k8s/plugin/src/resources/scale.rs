@@ -0,0 +1,309 @@
+use anyhow::{anyhow, bail, Result};
+use bytesize::ByteSize;
+use openapi::models::{ChildState, ResizeVolumeBody, Volume};
+use plugin::{
+    resources::{utils::OutputFormat, VolumeId},
+    rest_wrapper::RestClient,
+};
+use std::{
+    io::{IsTerminal, Write},
+    time::{Duration, Instant},
+};
+
+/// Interval at which the volume is polled while waiting for the operation to complete.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Scale resources.
+#[derive(clap::Subcommand, Debug)]
+pub enum ScaleResources {
+    /// Scale the replica count of a volume.
+    Volume(ScaleVolumeArgs),
+}
+
+/// Resize resources.
+#[derive(clap::Subcommand, Debug)]
+pub enum ResizeResources {
+    /// Expand the size of a volume.
+    Volume(ResizeVolumeArgs),
+}
+
+/// Scale the replica count of a volume, eg: `scale volume <uuid> --replicas 3`.
+#[derive(clap::Args, Debug)]
+pub struct ScaleVolumeArgs {
+    /// Id of the volume.
+    id: VolumeId,
+
+    /// The replica count, as a positional argument, for compatibility.
+    #[clap(
+        hide = true,
+        required_unless_present = "replicas",
+        conflicts_with = "replicas"
+    )]
+    replica_count: Option<u8>,
+
+    /// The replica count of the volume.
+    #[clap(long)]
+    replicas: Option<u8>,
+
+    #[clap(flatten)]
+    confirm: ConfirmArgs,
+}
+
+/// Expand the size of a volume, eg: `resize volume <uuid> --size 10GiB`.
+#[derive(clap::Args, Debug)]
+pub struct ResizeVolumeArgs {
+    /// Id of the volume.
+    id: VolumeId,
+
+    /// The new size of the volume, in bytes or with a unit, eg: 10GiB. Volumes can only be
+    /// expanded.
+    #[clap(long)]
+    size: ByteSize,
+
+    #[clap(flatten)]
+    confirm: ConfirmArgs,
+}
+
+/// Confirmation of an operation on a volume and the wait for its completion.
+#[derive(clap::Args, Debug)]
+struct ConfirmArgs {
+    /// Don't ask for a confirmation before the operation.
+    #[clap(long, short)]
+    yes: bool,
+
+    /// Wait until the operation completes, i.e. the new replicas are rebuilt or the volume
+    /// reports the new size.
+    #[clap(long)]
+    wait: bool,
+
+    /// Maximum time to wait for the operation to complete, with `--wait`.
+    #[clap(long, default_value = "10m")]
+    wait_timeout: humantime::Duration,
+}
+
+impl ConfirmArgs {
+    /// Whether the operation goes ahead, either with `--yes` or as confirmed by the user.
+    fn confirmed(&self, question: &str) -> bool {
+        self.yes || confirm(question)
+    }
+
+    /// Poll the volume until it satisfies the condition, or fail once the wait timeout elapses.
+    async fn wait_until(
+        &self,
+        id: &VolumeId,
+        output: &OutputFormat,
+        condition: impl Fn(&Volume) -> bool,
+    ) -> Result<Volume> {
+        let start = Instant::now();
+        loop {
+            let volume = get_volume(id).await?;
+            if condition(&volume) {
+                return Ok(volume);
+            }
+            if start.elapsed() > *self.wait_timeout {
+                bail!(
+                    "volume {id} did not complete the operation within {}",
+                    self.wait_timeout
+                );
+            }
+            progress(output, format!("Waiting for volume {id}..."));
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+}
+
+impl ScaleResources {
+    /// Execute the scale operation.
+    pub async fn scale(&self, output: &OutputFormat) {
+        match self {
+            ScaleResources::Volume(args) => args.scale(output).await,
+        }
+    }
+}
+
+impl ResizeResources {
+    /// Execute the resize operation.
+    pub async fn resize(&self, output: &OutputFormat) {
+        match self {
+            ResizeResources::Volume(args) => args.resize(output).await,
+        }
+    }
+}
+
+impl ScaleVolumeArgs {
+    /// Scale the volume, printing it once scaled.
+    pub async fn scale(&self, output: &OutputFormat) {
+        match self.execute(output).await {
+            Ok(Some(volume)) => print_volume(&volume, output, "Scaled"),
+            Ok(None) => println!("Scaling of volume {} cancelled", self.id),
+            Err(error) => {
+                eprintln!("Failed to scale volume {}. Error {error}", self.id);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    async fn execute(&self, output: &OutputFormat) -> Result<Option<Volume>> {
+        let Some(replicas) = self.replicas.or(self.replica_count) else {
+            bail!("the replica count is required");
+        };
+        let volume = get_volume(&self.id).await?;
+        let current = volume.spec.num_replicas;
+        if current == replicas {
+            progress(
+                output,
+                format!("Volume {} already has {replicas} replicas", self.id),
+            );
+            return Ok(Some(volume));
+        }
+        let question = format!(
+            "Scale volume {} from {current} to {replicas} replicas?",
+            self.id
+        );
+        if !self.confirm.confirmed(&question) {
+            return Ok(None);
+        }
+
+        let volume = RestClient::client()
+            .volumes_api()
+            .put_volume_replica_count(&self.id, replicas)
+            .await
+            .map_err(|error| anyhow!("{error}"))?
+            .into_body();
+        if !self.confirm.wait {
+            return Ok(Some(volume));
+        }
+        let volume = self
+            .confirm
+            .wait_until(&self.id, output, |volume| {
+                is_scaled(volume, replicas as usize)
+            })
+            .await?;
+        Ok(Some(volume))
+    }
+}
+
+impl ResizeVolumeArgs {
+    /// Resize the volume, printing it once resized.
+    pub async fn resize(&self, output: &OutputFormat) {
+        match self.execute(output).await {
+            Ok(Some(volume)) => print_volume(&volume, output, "Resized"),
+            Ok(None) => println!("Resizing of volume {} cancelled", self.id),
+            Err(error) => {
+                eprintln!("Failed to resize volume {}. Error {error}", self.id);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    async fn execute(&self, output: &OutputFormat) -> Result<Option<Volume>> {
+        let size = self.size.as_u64();
+        let volume = get_volume(&self.id).await?;
+        let current = volume.spec.size;
+        if size < current {
+            bail!(
+                "the volume can't be shrunk from {} to {}",
+                ByteSize::b(current),
+                self.size
+            );
+        }
+        if size == current {
+            progress(
+                output,
+                format!("Volume {} already has a size of {}", self.id, self.size),
+            );
+            return Ok(Some(volume));
+        }
+        let question = format!(
+            "Resize volume {} from {} to {}?",
+            self.id,
+            ByteSize::b(current),
+            self.size
+        );
+        if !self.confirm.confirmed(&question) {
+            return Ok(None);
+        }
+
+        let volume = RestClient::client()
+            .volumes_api()
+            .put_volume_size(&self.id, ResizeVolumeBody::new(size))
+            .await
+            .map_err(|error| anyhow!("{error}"))?
+            .into_body();
+        if !self.confirm.wait {
+            return Ok(Some(volume));
+        }
+        let volume = self
+            .confirm
+            .wait_until(&self.id, output, |volume| volume.state.size >= size)
+            .await?;
+        Ok(Some(volume))
+    }
+}
+
+/// Whether the volume has the replica count, with all of its replicas online children of the
+/// target when it's published, i.e. none of them is still being rebuilt.
+fn is_scaled(volume: &Volume, replicas: usize) -> bool {
+    let children_online = match &volume.state.target {
+        Some(target) => {
+            target.children.len() == replicas
+                && target.children.iter().all(|child| {
+                    child.state == ChildState::Online && child.rebuild_progress.is_none()
+                })
+        }
+        None => true,
+    };
+    volume.state.replica_topology.len() == replicas && children_online
+}
+
+/// Print the volume, or a summary line when the output is a table.
+fn print_volume(volume: &Volume, output: &OutputFormat, operation: &str) {
+    let result: Result<()> = match output {
+        OutputFormat::Yaml => serde_yaml::to_string(volume)
+            .map(|volume| print!("{volume}"))
+            .map_err(Into::into),
+        OutputFormat::Json => serde_json::to_string_pretty(volume)
+            .map(|volume| println!("{volume}"))
+            .map_err(Into::into),
+        _ => {
+            println!("Volume {} {operation} Successfully 🚀", volume.spec.uuid);
+            Ok(())
+        }
+    };
+    if let Err(error) = result {
+        eprintln!("Failed to print the volume. Error {error}");
+    }
+}
+
+/// Report the progress of the operation, on stderr when the volume is printed on stdout.
+fn progress(output: &OutputFormat, message: String) {
+    match output {
+        OutputFormat::Yaml | OutputFormat::Json => eprintln!("{message}"),
+        _ => println!("{message}"),
+    }
+}
+
+/// Get the volume.
+async fn get_volume(id: &VolumeId) -> Result<Volume> {
+    Ok(RestClient::client()
+        .volumes_api()
+        .get_volume(id)
+        .await
+        .map_err(|error| anyhow!("failed to get volume {id}: {error}"))?
+        .into_body())
+}
+
+/// Ask the user to confirm the given question on the console. When not running interactively,
+/// the user already opted in through the command line, so the confirmation is implied.
+pub(crate) fn confirm(question: &str) -> bool {
+    if !std::io::stdin().is_terminal() {
+        return true;
+    }
+    print!("{question} [y/N]: ");
+    let _ = std::io::stdout().flush();
+    let mut answer = String::new();
+    match std::io::stdin().read_line(&mut answer) {
+        Ok(_) => matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"),
+        Err(_) => false,
+    }
+}
@@ -0,0 +1,270 @@
+use crate::resources::placement::{list_volumes, print_output, print_table};
+use anyhow::{anyhow, Result};
+use openapi::{
+    apis::Uuid,
+    models::{ReplicaSnapshotState, Volume, VolumeContentSource, VolumeSnapshot},
+};
+use plugin::{
+    resources::{utils::OutputFormat, VolumeId},
+    rest_wrapper::RestClient,
+};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// The number of snapshots to get per request.
+const MAX_ENTRIES: isize = 200;
+
+/// Get the volume snapshots along with their clones.
+#[derive(clap::Args, Debug)]
+pub struct SnapshotsArgs {
+    /// Only get the snapshots of the given volume.
+    #[clap(long)]
+    volume: Option<VolumeId>,
+}
+
+/// Get the topology of a volume snapshot, i.e. its replica snapshots and its clones.
+#[derive(clap::Args, Debug)]
+pub struct SnapshotTopologyArgs {
+    /// Id of the snapshot.
+    id: Uuid,
+}
+
+/// A volume snapshot along with the volumes cloned from it.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct SnapshotSummary {
+    id: String,
+    source_volume: String,
+    timestamp: Option<String>,
+    size_bytes: u64,
+    allocated_bytes: u64,
+    ready_as_source: bool,
+    clones: Vec<String>,
+}
+
+/// Topology of a volume snapshot.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct SnapshotTopology {
+    #[serde(flatten)]
+    summary: SnapshotSummary,
+    replica_snapshots: Vec<ReplicaSnapshotTopology>,
+}
+
+/// A replica snapshot, on the pool of the snapshotted replica.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct ReplicaSnapshotTopology {
+    id: String,
+    pool: String,
+    online: bool,
+    allocated_bytes: Option<u64>,
+}
+
+impl SnapshotSummary {
+    fn new(snapshot: &VolumeSnapshot, clones: &BTreeMap<Uuid, Vec<String>>) -> Self {
+        let spec = &snapshot.definition.spec;
+        Self {
+            id: spec.uuid.to_string(),
+            source_volume: spec.source_volume.to_string(),
+            timestamp: snapshot.state.timestamp.clone(),
+            size_bytes: snapshot.definition.metadata.size,
+            allocated_bytes: snapshot.state.allocated_size,
+            ready_as_source: snapshot.state.ready_as_source,
+            clones: clones.get(&spec.uuid).cloned().unwrap_or_default(),
+        }
+    }
+}
+
+impl From<&ReplicaSnapshotState> for ReplicaSnapshotTopology {
+    fn from(state: &ReplicaSnapshotState) -> Self {
+        match state {
+            ReplicaSnapshotState::online(state) => Self {
+                id: state.uuid.to_string(),
+                pool: state.pool_id.clone(),
+                online: true,
+                allocated_bytes: Some(state.allocated_size),
+            },
+            ReplicaSnapshotState::offline(state) => Self {
+                id: state.uuid.to_string(),
+                pool: state.pool_id.clone(),
+                online: false,
+                allocated_bytes: None,
+            },
+        }
+    }
+}
+
+impl SnapshotsArgs {
+    /// Print the snapshots along with their clones.
+    pub async fn get(&self, output: &OutputFormat) {
+        if let Err(error) = self.execute(output).await {
+            eprintln!("Failed to get the snapshots. Error {error}");
+            std::process::exit(1);
+        }
+    }
+
+    async fn execute(&self, output: &OutputFormat) -> Result<()> {
+        let clones = clones_by_snapshot(&list_volumes().await?);
+        let mut snapshots = list_snapshots()
+            .await?
+            .iter()
+            .filter(|snapshot| {
+                self.volume.as_ref().map_or(true, |volume| {
+                    &snapshot.definition.spec.source_volume == volume
+                })
+            })
+            .map(|snapshot| SnapshotSummary::new(snapshot, &clones))
+            .collect::<Vec<_>>();
+        snapshots.sort_by(|a, b| {
+            (&a.source_volume, &a.timestamp, &a.id).cmp(&(&b.source_volume, &b.timestamp, &b.id))
+        });
+        print_output(&snapshots, output, print_snapshots)
+    }
+}
+
+impl SnapshotTopologyArgs {
+    /// Print the topology of the snapshot.
+    pub async fn get(&self, output: &OutputFormat) {
+        if let Err(error) = self.execute(output).await {
+            eprintln!(
+                "Failed to get the topology of snapshot {}. Error {error}",
+                self.id
+            );
+            std::process::exit(1);
+        }
+    }
+
+    async fn execute(&self, output: &OutputFormat) -> Result<()> {
+        let snapshot = RestClient::client()
+            .snapshots_api()
+            .get_volumes_snapshot(&self.id)
+            .await
+            .map_err(|error| anyhow!("{error}"))?
+            .into_body();
+        let clones = clones_by_snapshot(&list_volumes().await?);
+        let mut replica_snapshots = snapshot
+            .state
+            .replica_snapshots
+            .iter()
+            .map(ReplicaSnapshotTopology::from)
+            .collect::<Vec<_>>();
+        replica_snapshots.sort_by(|a, b| a.pool.cmp(&b.pool));
+        let topology = SnapshotTopology {
+            summary: SnapshotSummary::new(&snapshot, &clones),
+            replica_snapshots,
+        };
+        print_output(&[topology], output, print_topology)
+    }
+}
+
+/// List all the volume snapshots.
+async fn list_snapshots() -> Result<Vec<VolumeSnapshot>> {
+    let mut snapshots = Vec::new();
+    let mut starting_token = Some(0_isize);
+    // The last paginated request will set the `starting_token` to `None`.
+    while starting_token.is_some() {
+        let page = RestClient::client()
+            .snapshots_api()
+            .get_volumes_snapshots(MAX_ENTRIES, None, None, starting_token)
+            .await
+            .map_err(|error| anyhow!("{error}"))?
+            .into_body();
+        snapshots.extend(page.entries);
+        starting_token = page.next_token;
+    }
+    Ok(snapshots)
+}
+
+/// The ids of the volumes cloned from each snapshot.
+fn clones_by_snapshot(volumes: &[Volume]) -> BTreeMap<Uuid, Vec<String>> {
+    let mut clones = BTreeMap::<Uuid, Vec<String>>::new();
+    for volume in volumes {
+        if let Some(VolumeContentSource::snapshot(source)) = &volume.spec.content_source {
+            clones
+                .entry(source.snapshot)
+                .or_default()
+                .push(volume.spec.uuid.to_string());
+        }
+    }
+    clones.values_mut().for_each(|clones| clones.sort());
+    clones
+}
+
+/// Print the snapshots as a table.
+fn print_snapshots(snapshots: &[SnapshotSummary]) {
+    let cells = snapshots
+        .iter()
+        .map(|snapshot| {
+            [
+                snapshot.id.clone(),
+                snapshot.source_volume.clone(),
+                snapshot
+                    .timestamp
+                    .clone()
+                    .unwrap_or_else(|| "<none>".to_string()),
+                bytesize::ByteSize::b(snapshot.size_bytes).to_string(),
+                bytesize::ByteSize::b(snapshot.allocated_bytes).to_string(),
+                snapshot.ready_as_source.to_string(),
+                snapshot.clones.len().to_string(),
+            ]
+        })
+        .collect::<Vec<_>>();
+    let rows = cells
+        .iter()
+        .map(|[id, volume, timestamp, size, allocated, ready, clones]| {
+            [
+                id.as_str(),
+                volume.as_str(),
+                timestamp.as_str(),
+                size.as_str(),
+                allocated.as_str(),
+                ready.as_str(),
+                clones.as_str(),
+            ]
+        })
+        .collect::<Vec<_>>();
+    print_table(
+        [
+            "ID",
+            "SOURCE-VOLUME",
+            "TIMESTAMP",
+            "SIZE",
+            "ALLOCATED",
+            "READY-AS-SOURCE",
+            "CLONES",
+        ],
+        &rows,
+    );
+}
+
+/// Print the topology of the snapshot as an indented tree.
+fn print_topology(topologies: &[SnapshotTopology]) {
+    for topology in topologies {
+        let snapshot = &topology.summary;
+        println!(
+            "snapshot {} of volume {} ({}, {}, {} allocated, ready as source: {})",
+            snapshot.id,
+            snapshot.source_volume,
+            snapshot.timestamp.as_deref().unwrap_or("<no timestamp>"),
+            bytesize::ByteSize::b(snapshot.size_bytes),
+            bytesize::ByteSize::b(snapshot.allocated_bytes),
+            snapshot.ready_as_source
+        );
+        for replica in &topology.replica_snapshots {
+            let state = match replica.allocated_bytes {
+                Some(allocated) if replica.online => {
+                    format!("Online, {} allocated", bytesize::ByteSize::b(allocated))
+                }
+                _ => "Offline".to_string(),
+            };
+            println!(
+                "  replica snapshot {} ({state}) -> pool {}",
+                replica.id, replica.pool
+            );
+        }
+        for clone in &snapshot.clones {
+            println!("  clone volume {clone}");
+        }
+    }
+}
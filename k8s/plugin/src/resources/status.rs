@@ -0,0 +1,338 @@
+use crate::resources::placement::{list_volumes, print_output, print_table};
+use anyhow::Result;
+use k8s_openapi::api::apps::v1::{DaemonSet, Deployment, StatefulSet};
+use kube::{api::ListParams, Api, Client, ResourceExt};
+use openapi::models::{NodeStatus, PoolStatus, Volume, VolumeStatus};
+use plugin::{resources::utils::OutputFormat, rest_wrapper::RestClient};
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// Print the storage health of the cluster on a single screen: the readiness of the components,
+/// the capacity of the pools, the degraded and faulted resources and the active rebuilds.
+#[derive(clap::Args, Debug)]
+pub struct StatusArgs {}
+
+/// Storage health of the cluster.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Status {
+    #[serde(skip)]
+    namespace: String,
+    components: Vec<ComponentStatus>,
+    nodes: NodeSummary,
+    pools: PoolSummary,
+    volumes: VolumeSummary,
+    rebuilds: Vec<Rebuild>,
+}
+
+/// Readiness of a workload of the mayastor namespace.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct ComponentStatus {
+    /// The workload, as <kind>/<name>.
+    name: String,
+    ready: i32,
+    desired: i32,
+}
+
+impl ComponentStatus {
+    fn is_ready(&self) -> bool {
+        self.ready >= self.desired
+    }
+}
+
+/// Number of the io-engine nodes, per status.
+#[derive(Serialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+struct NodeSummary {
+    total: usize,
+    online: usize,
+}
+
+/// Number of the pools per status, and their capacity.
+#[derive(Serialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+struct PoolSummary {
+    total: usize,
+    online: usize,
+    degraded: usize,
+    faulted: usize,
+    /// The pools whose state is not known, eg: as their node is offline.
+    unknown: usize,
+    /// Capacity of the pools, in bytes.
+    capacity_bytes: u64,
+    /// Capacity used by the replicas on the pools, in bytes.
+    used_bytes: u64,
+}
+
+/// Number of the volumes, per status.
+#[derive(Serialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+struct VolumeSummary {
+    total: usize,
+    online: usize,
+    degraded: usize,
+    faulted: usize,
+    /// The volumes whose status is not known, eg: as they are not published.
+    unknown: usize,
+}
+
+/// Rebuild of a child of the target of a volume.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Rebuild {
+    volume: String,
+    child: String,
+    /// Progress of the rebuild, in percent.
+    progress: u8,
+}
+
+impl StatusArgs {
+    /// Print the storage health of the cluster.
+    pub async fn get(
+        &self,
+        kube_config_path: Option<PathBuf>,
+        namespace: &str,
+        output: &OutputFormat,
+    ) {
+        if let Err(error) = self.execute(kube_config_path, namespace, output).await {
+            eprintln!("Failed to get the status. Error {error}");
+            std::process::exit(1);
+        }
+    }
+
+    async fn execute(
+        &self,
+        kube_config_path: Option<PathBuf>,
+        namespace: &str,
+        output: &OutputFormat,
+    ) -> Result<()> {
+        let config = kube_proxy::config_from_kubeconfig(kube_config_path).await?;
+        let client = Client::try_from(config)?;
+        let components = list_components(client, namespace).await?;
+
+        let nodes = RestClient::client()
+            .nodes_api()
+            .get_nodes(None)
+            .await
+            .map_err(|error| anyhow::anyhow!("{error}"))?
+            .into_body();
+        let node_summary = NodeSummary {
+            total: nodes.len(),
+            online: nodes
+                .iter()
+                .filter(|node| {
+                    matches!(
+                        node.state.as_ref().map(|state| &state.status),
+                        Some(NodeStatus::Online)
+                    )
+                })
+                .count(),
+        };
+
+        let pools = RestClient::client()
+            .pools_api()
+            .get_pools()
+            .await
+            .map_err(|error| anyhow::anyhow!("{error}"))?
+            .into_body();
+        let mut pool_summary = PoolSummary {
+            total: pools.len(),
+            ..Default::default()
+        };
+        for state in pools.iter().map(|pool| pool.state.as_ref()) {
+            let Some(state) = state else {
+                pool_summary.unknown += 1;
+                continue;
+            };
+            match state.status {
+                PoolStatus::Online => pool_summary.online += 1,
+                PoolStatus::Degraded => pool_summary.degraded += 1,
+                PoolStatus::Faulted => pool_summary.faulted += 1,
+                _ => pool_summary.unknown += 1,
+            }
+            pool_summary.capacity_bytes += state.capacity;
+            pool_summary.used_bytes += state.used;
+        }
+
+        let volumes = list_volumes().await?;
+        let mut volume_summary = VolumeSummary {
+            total: volumes.len(),
+            ..Default::default()
+        };
+        for volume in &volumes {
+            match volume.state.status {
+                VolumeStatus::Online => volume_summary.online += 1,
+                VolumeStatus::Degraded => volume_summary.degraded += 1,
+                VolumeStatus::Faulted => volume_summary.faulted += 1,
+                _ => volume_summary.unknown += 1,
+            }
+        }
+
+        let status = Status {
+            namespace: namespace.to_string(),
+            components,
+            nodes: node_summary,
+            pools: pool_summary,
+            volumes: volume_summary,
+            rebuilds: volumes.iter().flat_map(rebuilds).collect(),
+        };
+        print_output(&[status], output, print_status)
+    }
+}
+
+/// List the readiness of the Deployments, StatefulSets and DaemonSets of the namespace.
+async fn list_components(client: Client, namespace: &str) -> Result<Vec<ComponentStatus>> {
+    let params = ListParams::default();
+    let mut components = Vec::new();
+    for deployment in Api::<Deployment>::namespaced(client.clone(), namespace)
+        .list(&params)
+        .await?
+    {
+        let status = deployment.status.as_ref();
+        components.push(ComponentStatus {
+            name: format!("deployment/{}", deployment.name_any()),
+            ready: status.and_then(|s| s.ready_replicas).unwrap_or_default(),
+            desired: deployment
+                .spec
+                .as_ref()
+                .and_then(|spec| spec.replicas)
+                .unwrap_or(1),
+        });
+    }
+    for stateful_set in Api::<StatefulSet>::namespaced(client.clone(), namespace)
+        .list(&params)
+        .await?
+    {
+        let status = stateful_set.status.as_ref();
+        components.push(ComponentStatus {
+            name: format!("statefulset/{}", stateful_set.name_any()),
+            ready: status.and_then(|s| s.ready_replicas).unwrap_or_default(),
+            desired: stateful_set
+                .spec
+                .as_ref()
+                .and_then(|spec| spec.replicas)
+                .unwrap_or(1),
+        });
+    }
+    for daemon_set in Api::<DaemonSet>::namespaced(client, namespace)
+        .list(&params)
+        .await?
+    {
+        let status = daemon_set.status.as_ref();
+        components.push(ComponentStatus {
+            name: format!("daemonset/{}", daemon_set.name_any()),
+            ready: status.map(|s| s.number_ready).unwrap_or_default(),
+            desired: status
+                .map(|s| s.desired_number_scheduled)
+                .unwrap_or_default(),
+        });
+    }
+    Ok(components)
+}
+
+/// The rebuilds in progress of the children of the target of the volume.
+fn rebuilds(volume: &Volume) -> Vec<Rebuild> {
+    volume
+        .state
+        .target
+        .iter()
+        .flat_map(|target| target.children.iter())
+        .filter_map(|child| {
+            Some(Rebuild {
+                volume: volume.spec.uuid.to_string(),
+                child: child.uri.clone(),
+                progress: child.rebuild_progress?,
+            })
+        })
+        .collect()
+}
+
+/// Print the status as a summary, only detailing what needs attention, along with the commands
+/// which show more.
+fn print_status(statuses: &[Status]) {
+    let Some(status) = statuses.first() else {
+        return;
+    };
+
+    let ready = status.components.iter().filter(|c| c.is_ready()).count();
+    println!("Components: {ready}/{} ready", status.components.len());
+    let not_ready = status
+        .components
+        .iter()
+        .filter(|c| !c.is_ready())
+        .map(|c| (c.name.as_str(), format!("{}/{}", c.ready, c.desired)))
+        .collect::<Vec<_>>();
+    if !not_ready.is_empty() {
+        let rows = not_ready
+            .iter()
+            .map(|(name, ready)| [*name, ready.as_str()])
+            .collect::<Vec<_>>();
+        print_table(["NOT READY", "READY"], &rows);
+    }
+
+    println!(
+        "Nodes:      {}/{} online",
+        status.nodes.online, status.nodes.total
+    );
+
+    let pools = &status.pools;
+    println!(
+        "Pools:      {}/{} online, {} degraded, {} faulted, {} unknown",
+        pools.online, pools.total, pools.degraded, pools.faulted, pools.unknown
+    );
+    let usage = match pools.capacity_bytes {
+        0 => 0,
+        capacity => pools.used_bytes * 100 / capacity,
+    };
+    println!(
+        "Capacity:   {} used of {} ({usage}%)",
+        bytesize::ByteSize::b(pools.used_bytes),
+        bytesize::ByteSize::b(pools.capacity_bytes)
+    );
+
+    let volumes = &status.volumes;
+    println!(
+        "Volumes:    {}/{} online, {} degraded, {} faulted, {} unknown",
+        volumes.online, volumes.total, volumes.degraded, volumes.faulted, volumes.unknown
+    );
+
+    println!("Rebuilds:   {} in progress", status.rebuilds.len());
+    if !status.rebuilds.is_empty() {
+        let progress = status
+            .rebuilds
+            .iter()
+            .map(|r| format!("{}%", r.progress))
+            .collect::<Vec<_>>();
+        let rows = status
+            .rebuilds
+            .iter()
+            .zip(&progress)
+            .map(|(r, progress)| [r.volume.as_str(), r.child.as_str(), progress.as_str()])
+            .collect::<Vec<_>>();
+        print_table(["VOLUME", "CHILD", "PROGRESS"], &rows);
+    }
+
+    let mut hints = Vec::new();
+    if !not_ready.is_empty() {
+        hints.push(format!(
+            "kubectl -n {} get pods, for the pods which aren't ready",
+            status.namespace
+        ));
+    }
+    if pools.online < pools.total {
+        hints.push("kubectl mayastor get pools, for the state of every pool".to_string());
+    }
+    if volumes.online < volumes.total || !status.rebuilds.is_empty() {
+        hints.push(
+            "kubectl mayastor get volume-replica-topologies, for the state of the replicas"
+                .to_string(),
+        );
+    }
+    if !hints.is_empty() {
+        println!("\nFor more details, run:");
+        for hint in hints {
+            println!("- {hint}");
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use crate::resources::placement::{list_volumes, print_output, print_table};
+use anyhow::Result;
+use k8s_openapi::api::{core::v1::PersistentVolume, storage::v1::StorageClass};
+use kube::{api::ListParams, Api, Client};
+use openapi::models::Volume;
+use plugin::resources::utils::OutputFormat;
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+};
+
+/// Name of the mayastor CSI driver, which provisions the mayastor volumes.
+const MAYASTOR_CSI_DRIVER: &str = "io.openebs.csi-mayastor";
+
+/// Name of the group of the volumes which aren't bound to a PersistentVolume, or whose
+/// PersistentVolume has no StorageClass.
+const NO_STORAGE_CLASS: &str = "<none>";
+
+/// Get the capacity usage of the volumes.
+#[derive(clap::Args, Debug)]
+pub struct UsageArgs {
+    /// Aggregate the usage per StorageClass of the PersistentVolumes of the volumes, for
+    /// chargeback and quota planning.
+    #[clap(long)]
+    by_storageclass: bool,
+}
+
+/// Capacity usage of a group of volumes.
+#[derive(Serialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+struct Usage {
+    /// StorageClass of the volumes, if they are aggregated per StorageClass.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    storage_class: Option<String>,
+    volumes: u64,
+    /// Capacity provisioned for the volumes, in bytes.
+    provisioned_bytes: u64,
+    /// Capacity provisioned for all the replicas of the volumes, in bytes.
+    provisioned_replica_bytes: u64,
+    /// Capacity allocated by the volumes, in bytes.
+    allocated_bytes: u64,
+}
+
+impl Usage {
+    /// Add the volume to the usage.
+    fn add(&mut self, volume: &Volume) {
+        self.volumes += 1;
+        self.provisioned_bytes += volume.spec.size;
+        self.provisioned_replica_bytes += volume.spec.size * u64::from(volume.spec.num_replicas);
+        self.allocated_bytes += volume
+            .state
+            .usage
+            .as_ref()
+            .map(|usage| usage.allocated)
+            .unwrap_or_default();
+    }
+}
+
+impl UsageArgs {
+    /// Print the capacity usage of the volumes, in total or per StorageClass.
+    pub async fn get(&self, kube_config_path: Option<PathBuf>, output: &OutputFormat) {
+        if let Err(error) = self.execute(kube_config_path, output).await {
+            eprintln!("Failed to get the usage. Error {error}");
+            std::process::exit(1);
+        }
+    }
+
+    async fn execute(
+        &self,
+        kube_config_path: Option<PathBuf>,
+        output: &OutputFormat,
+    ) -> Result<()> {
+        let volumes = list_volumes().await?;
+        if !self.by_storageclass {
+            let mut usage = Usage::default();
+            volumes.iter().for_each(|volume| usage.add(volume));
+            return print_output(&[usage], output, print_usage);
+        }
+
+        let config = kube_proxy::config_from_kubeconfig(kube_config_path).await?;
+        let client = Client::try_from(config)?;
+        let storage_classes = volume_storage_classes(client.clone()).await?;
+        // The mayastor StorageClasses are listed even when none of their volumes exist yet.
+        let mut usages = Api::<StorageClass>::all(client)
+            .list(&ListParams::default())
+            .await?
+            .into_iter()
+            .filter(|class| class.provisioner == MAYASTOR_CSI_DRIVER)
+            .filter_map(|class| class.metadata.name)
+            .map(|name| (name, Usage::default()))
+            .collect::<BTreeMap<_, _>>();
+        for volume in &volumes {
+            let class = storage_classes
+                .get(&volume.spec.uuid.to_string())
+                .cloned()
+                .unwrap_or_else(|| NO_STORAGE_CLASS.to_string());
+            usages.entry(class).or_default().add(volume);
+        }
+        let usages = usages
+            .into_iter()
+            .map(|(class, usage)| Usage {
+                storage_class: Some(class),
+                ..usage
+            })
+            .collect::<Vec<_>>();
+        print_output(&usages, output, print_usage)
+    }
+}
+
+/// Get the StorageClasses of the mayastor volumes, keyed by the volume uuid, from their
+/// PersistentVolumes.
+async fn volume_storage_classes(client: Client) -> Result<HashMap<String, String>> {
+    let volumes = Api::<PersistentVolume>::all(client)
+        .list(&ListParams::default())
+        .await?;
+    Ok(volumes
+        .into_iter()
+        .filter_map(|volume| {
+            let spec = volume.spec?;
+            let csi = spec.csi.filter(|csi| csi.driver == MAYASTOR_CSI_DRIVER)?;
+            Some((csi.volume_handle, spec.storage_class_name?))
+        })
+        .filter(|(_, class)| !class.is_empty())
+        .collect())
+}
+
+/// Print the usages as a table.
+fn print_usage(usages: &[Usage]) {
+    let cells = usages
+        .iter()
+        .map(|usage| {
+            [
+                usage
+                    .storage_class
+                    .clone()
+                    .unwrap_or_else(|| "<all>".to_string()),
+                usage.volumes.to_string(),
+                bytesize::ByteSize::b(usage.provisioned_bytes).to_string(),
+                bytesize::ByteSize::b(usage.provisioned_replica_bytes).to_string(),
+                bytesize::ByteSize::b(usage.allocated_bytes).to_string(),
+            ]
+        })
+        .collect::<Vec<_>>();
+    let rows = cells
+        .iter()
+        .map(|[class, volumes, provisioned, replicas, allocated]| {
+            [
+                class.as_str(),
+                volumes.as_str(),
+                provisioned.as_str(),
+                replicas.as_str(),
+                allocated.as_str(),
+            ]
+        })
+        .collect::<Vec<_>>();
+    print_table(
+        [
+            "STORAGECLASS",
+            "VOLUMES",
+            "PROVISIONED",
+            "PROVISIONED (REPLICAS)",
+            "ALLOCATED",
+        ],
+        &rows,
+    );
+}
@@ -0,0 +1,210 @@
+use crate::resources::{
+    filter::ListFilterArgs,
+    placement::{list_volumes, print_output},
+};
+use anyhow::Result;
+use openapi::models::Volume;
+use plugin::resources::utils::OutputFormat;
+use serde::Serialize;
+
+/// Get the volumes along with the placement of their replicas.
+#[derive(clap::Args, Debug)]
+pub struct VolumesWideArgs {
+    /// The columns of the table, in order, eg: `--columns id,status,replica-nodes`. All the
+    /// columns are shown by default.
+    #[clap(long, value_delimiter = ',')]
+    columns: Vec<VolumeColumn>,
+
+    #[clap(flatten)]
+    filter: ListFilterArgs,
+}
+
+/// A column of the volumes table.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum VolumeColumn {
+    Id,
+    Replicas,
+    Status,
+    Size,
+    TargetNode,
+    Protocol,
+    Thin,
+    ReplicaNodes,
+}
+
+impl VolumeColumn {
+    /// All the columns, in their default order.
+    const ALL: [Self; 8] = [
+        Self::Id,
+        Self::Replicas,
+        Self::Status,
+        Self::Size,
+        Self::TargetNode,
+        Self::Protocol,
+        Self::Thin,
+        Self::ReplicaNodes,
+    ];
+
+    /// Header of the column.
+    fn header(&self) -> &'static str {
+        match self {
+            Self::Id => "ID",
+            Self::Replicas => "REPLICAS",
+            Self::Status => "STATUS",
+            Self::Size => "SIZE",
+            Self::TargetNode => "TARGET-NODE",
+            Self::Protocol => "PROTOCOL",
+            Self::Thin => "THIN-PROVISIONED",
+            Self::ReplicaNodes => "REPLICA-NODES",
+        }
+    }
+
+    /// Cell of the column for the volume.
+    fn cell(&self, volume: &WideVolume) -> String {
+        match self {
+            Self::Id => volume.id.clone(),
+            Self::Replicas => volume.replicas.to_string(),
+            Self::Status => volume.status.clone(),
+            Self::Size => bytesize::ByteSize::b(volume.size_bytes).to_string(),
+            Self::TargetNode => volume
+                .target_node
+                .as_deref()
+                .unwrap_or("<none>")
+                .to_string(),
+            Self::Protocol => volume.protocol.as_deref().unwrap_or("<none>").to_string(),
+            Self::Thin => volume.thin.to_string(),
+            Self::ReplicaNodes => match volume.replica_nodes.is_empty() {
+                true => "<none>".to_string(),
+                false => volume
+                    .replica_nodes
+                    .iter()
+                    .map(|replica| format!("{}/{}", replica.node, replica.pool))
+                    .collect::<Vec<_>>()
+                    .join(","),
+            },
+        }
+    }
+}
+
+/// Placement of a replica of a volume.
+#[derive(Serialize, Debug)]
+struct ReplicaNode {
+    node: String,
+    pool: String,
+}
+
+/// Volume along with the placement of its replicas.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct WideVolume {
+    id: String,
+    replicas: u8,
+    status: String,
+    size_bytes: u64,
+    target_node: Option<String>,
+    protocol: Option<String>,
+    thin: bool,
+    replica_nodes: Vec<ReplicaNode>,
+}
+
+impl From<&Volume> for WideVolume {
+    fn from(volume: &Volume) -> Self {
+        let mut replica_nodes = volume
+            .state
+            .replica_topology
+            .values()
+            .map(|topology| ReplicaNode {
+                node: topology.node.clone().unwrap_or_default(),
+                pool: topology.pool.clone().unwrap_or_default(),
+            })
+            .collect::<Vec<_>>();
+        replica_nodes.sort_by(|a, b| a.node.cmp(&b.node).then_with(|| a.pool.cmp(&b.pool)));
+        Self {
+            id: volume.spec.uuid.to_string(),
+            replicas: volume.spec.num_replicas,
+            status: format!("{:?}", volume.state.status),
+            size_bytes: volume.spec.size,
+            target_node: volume
+                .state
+                .target
+                .as_ref()
+                .map(|target| target.node.clone()),
+            protocol: volume
+                .state
+                .target
+                .as_ref()
+                .map(|target| format!("{:?}", target.protocol).to_lowercase()),
+            thin: volume.spec.thin,
+            replica_nodes,
+        }
+    }
+}
+
+impl VolumesWideArgs {
+    /// Print the volumes along with the placement of their replicas.
+    pub async fn get(&self, output: &OutputFormat) {
+        if let Err(error) = self.execute(output).await {
+            eprintln!("Failed to get the volumes. Error {error}");
+            std::process::exit(1);
+        }
+    }
+
+    async fn execute(&self, output: &OutputFormat) -> Result<()> {
+        self.filter.check_fields::<Volume>();
+        let mut volumes = list_volumes().await?;
+        volumes.sort_by(|a, b| a.spec.uuid.cmp(&b.spec.uuid));
+        let volumes = self
+            .filter
+            .apply(volumes)
+            .iter()
+            .map(WideVolume::from)
+            .collect::<Vec<_>>();
+        match output {
+            OutputFormat::Yaml | OutputFormat::Json => print_output(&volumes, output, |_| {}),
+            _ => {
+                let columns = match self.columns.is_empty() {
+                    true => VolumeColumn::ALL.to_vec(),
+                    false => self.columns.clone(),
+                };
+                print_columns(&columns, &volumes);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Print the given columns of the volumes as a table with left aligned columns.
+fn print_columns(columns: &[VolumeColumn], volumes: &[WideVolume]) {
+    let header = columns
+        .iter()
+        .map(|column| column.header().to_string())
+        .collect::<Vec<_>>();
+    let rows = volumes
+        .iter()
+        .map(|volume| {
+            columns
+                .iter()
+                .map(|column| column.cell(volume))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let mut widths = header.iter().map(String::len).collect::<Vec<_>>();
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+    let format_row = |row: &[String]| {
+        row.iter()
+            .zip(widths.iter().copied())
+            .map(|(cell, width)| format!("{cell:width$}"))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+    println!("{}", format_row(&header));
+    for row in &rows {
+        println!("{}", format_row(row));
+    }
+}
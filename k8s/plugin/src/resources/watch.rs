@@ -0,0 +1,38 @@
+use std::{
+    future::Future,
+    io::{IsTerminal, Write},
+    time::{Duration, SystemTime},
+};
+
+/// Escape sequence which clears the terminal and moves the cursor to its top left corner.
+const CLEAR_SCREEN: &str = "\x1B[2J\x1B[H";
+
+/// Run the get operation once or, with `--watch`, every refresh interval until interrupted. On a
+/// terminal the screen is redrawn every time, so that the output is updated in place, otherwise
+/// each output is appended after a header line.
+pub(crate) async fn watch<F, Fut>(refresh: Option<Duration>, command: &str, get: F)
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = ()>,
+{
+    let Some(refresh) = refresh else {
+        return get().await;
+    };
+    let terminal = std::io::stdout().is_terminal();
+    loop {
+        if terminal {
+            print!("{CLEAR_SCREEN}");
+        }
+        println!(
+            "Every {}: kubectl mayastor {command}    {}\n",
+            humantime::format_duration(refresh),
+            humantime::format_rfc3339_seconds(SystemTime::now())
+        );
+        get().await;
+        let _ = std::io::stdout().flush();
+        tokio::time::sleep(refresh).await;
+        if !terminal {
+            println!();
+        }
+    }
+}
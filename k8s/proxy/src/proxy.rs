@@ -1,3 +1,4 @@
+use crate::EgressProxy;
 use anyhow::anyhow;
 use openapi::{
     apis::Url,
@@ -26,6 +27,7 @@ pub struct ConfigBuilder<T> {
     jwt: Option<String>,
     method: ForwardingProxy,
     scheme: Scheme,
+    proxy: Option<EgressProxy>,
     builder_target: std::marker::PhantomData<T>,
 }
 
@@ -85,6 +87,7 @@ impl Default for ConfigBuilder<ApiRest> {
             jwt: None,
             method: ForwardingProxy::HTTP,
             scheme: Scheme::HTTP,
+            proxy: None,
             builder_target: Default::default(),
         }
     }
@@ -102,6 +105,7 @@ impl Default for ConfigBuilder<Etcd> {
             jwt: None,
             method: ForwardingProxy::TCP,
             scheme: Scheme::HTTP,
+            proxy: None,
             builder_target: Default::default(),
         }
     }
@@ -119,6 +123,7 @@ impl Default for ConfigBuilder<Loki> {
             jwt: None,
             method: ForwardingProxy::HTTP,
             scheme: Scheme::HTTP,
+            proxy: None,
             builder_target: Default::default(),
         }
     }
@@ -162,6 +167,11 @@ impl<T> ConfigBuilder<T> {
         self.target = modify(self.target);
         self
     }
+    /// Move self with the following proxy, through which the kube-apiserver is reached.
+    pub fn with_proxy(mut self, proxy: Option<EgressProxy>) -> Self {
+        self.proxy = proxy;
+        self
+    }
 }
 
 impl ConfigBuilder<ApiRest> {
@@ -180,6 +190,11 @@ impl ConfigBuilder<ApiRest> {
         self.scheme = scheme;
         self
     }
+    /// Move self with the following bearer token, sent with every request.
+    pub fn with_jwt(mut self, jwt: Option<String>) -> Self {
+        self.jwt = jwt;
+        self
+    }
 
     /// Tries to build a `Configuration` from the current self.
     pub async fn build(self) -> anyhow::Result<Configuration> {
@@ -194,8 +209,7 @@ impl ConfigBuilder<ApiRest> {
             .await?
             .uri()
             .await?;
-        let config = super::config_from_kubeconfig(self.kube_config).await?;
-        let client = kube::Client::try_from(config)?;
+        let client = super::client_from_kubeconfig(self.kube_config, self.proxy.as_ref()).await?;
         let proxy = kube_forward::HttpProxy::new(client);
 
         let config = Configuration::builder()
@@ -282,8 +296,7 @@ impl ConfigBuilder<Loki> {
             .await?
             .uri()
             .await?;
-        let config = super::config_from_kubeconfig(self.kube_config).await?;
-        let client = kube::Client::try_from(config)?;
+        let client = super::client_from_kubeconfig(self.kube_config, self.proxy.as_ref()).await?;
         let proxy = kube_forward::HttpProxy::new(client);
 
         let service = tower::ServiceBuilder::new()
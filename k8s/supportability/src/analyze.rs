@@ -0,0 +1,669 @@
+use crate::collect::{
+    constants::{DATA_PLANE_CONTAINER_NAME, MAYASTOR_SERVICE},
+    k8s_resources::client::ClientSet,
+    manifest::relative_path,
+    resources::utils::MAX_RESOURCE_ENTRIES,
+    rest_wrapper::RestClient,
+};
+use anyhow::Context;
+use k8s_openapi::api::core::v1::Pod;
+use kube_proxy::EgressProxy;
+use openapi::models::{ChildState, Pool, PoolStatus, Volume, VolumeStatus};
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+const VOLUME_CHECK: &str = "degraded volume";
+const POOL_CHECK: &str = "pool capacity";
+const CRASH_LOOP_CHECK: &str = "crash-looping pod";
+const VERSION_CHECK: &str = "io-engine version";
+const REBUILD_CHECK: &str = "stuck rebuild";
+
+const VOLUME_FIX: &str = "Check the state of the nodes and the pools of the missing replicas, \
+eg: `kubectl mayastor get volume-replica-topology <volume>`";
+const POOL_FIX: &str = "Free up space on the pool, eg: by moving replicas off it with `kubectl \
+mayastor migrate volume`, or create pools on new disks";
+const CRASH_LOOP_FIX: &str = "Check the logs of the previous run of the container, eg: \
+`kubectl logs --previous <pod> -c <container>`";
+const VERSION_FIX: &str = "Complete the upgrade of the io-engine pods, eg: `kubectl mayastor get \
+upgrade-status`, all nodes must run the same version";
+const REBUILD_FIX: &str = "Check the io-engine logs of the nodes of the rebuilding replica and of \
+the target for I/O errors";
+
+/// Analyzes a support bundle, or the live cluster, and reports the common problems: degraded
+/// volumes, pools near capacity, crash-looping pods, mismatched io-engine versions and stuck
+/// rebuilds.
+#[derive(Debug, Clone, clap::Args)]
+pub(crate) struct AnalyzeArgs {
+    /// Path of the support bundle to analyze, either an archive written by the dump or its
+    /// extracted directory. The live cluster is analyzed when it is omitted
+    bundle: Option<PathBuf>,
+
+    /// Usage of the capacity of a pool, in percent, from which it is reported as near capacity
+    #[clap(long, default_value_t = 85, value_parser = clap::value_parser!(u8).range(1..=100))]
+    pool_usage_threshold: u8,
+
+    /// Number of restarts of a container from which its pod is reported as crash-looping
+    #[clap(long, default_value_t = 5)]
+    restart_threshold: i32,
+
+    /// Interval between two samples of the rebuilds of the live cluster, the rebuilds which did
+    /// not progress in between are reported as stuck
+    #[clap(long, default_value = "30s")]
+    rebuild_sample_interval: humantime::Duration,
+}
+
+/// Severity of a finding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Severity {
+    Critical,
+    Warning,
+}
+
+/// A problem found by the analysis.
+struct Finding {
+    severity: Severity,
+    /// Name of the check which found the problem.
+    check: &'static str,
+    /// Directory of the cluster in multi-cluster bundles, empty otherwise.
+    cluster: String,
+    /// Resource which has the problem.
+    resource: String,
+    message: String,
+    /// Suggested fix of the problem.
+    fix: &'static str,
+}
+
+impl Finding {
+    fn new(
+        severity: Severity,
+        check: &'static str,
+        resource: impl Into<String>,
+        message: String,
+        fix: &'static str,
+    ) -> Self {
+        Self {
+            severity,
+            check,
+            cluster: String::new(),
+            resource: resource.into(),
+            message,
+            fix,
+        }
+    }
+}
+
+/// State of a cluster which is analyzed.
+#[derive(Default)]
+struct ClusterState {
+    volumes: Vec<Volume>,
+    pools: Vec<Pool>,
+    pods: Vec<Pod>,
+    /// Volumes sampled again after an interval, to check whether their rebuilds progress. Only
+    /// available for the live cluster.
+    resampled_volumes: Option<Vec<Volume>>,
+}
+
+impl AnalyzeArgs {
+    /// Analyze the bundle or the live cluster and print the findings, erroring out if any of
+    /// them is critical.
+    pub(crate) async fn analyze(
+        &self,
+        kube_config_path: Option<PathBuf>,
+        namespace: &str,
+        timeout: humantime::Duration,
+        proxy: Option<EgressProxy>,
+    ) -> anyhow::Result<()> {
+        let clusters = match &self.bundle {
+            Some(bundle) => read_bundle(bundle)?,
+            None => {
+                let state = self
+                    .read_live(kube_config_path, namespace, timeout, proxy)
+                    .await?;
+                BTreeMap::from([(String::new(), state)])
+            }
+        };
+
+        let mut findings = Vec::new();
+        for (cluster, state) in clusters {
+            let mut cluster_findings = self.analyze_cluster(&state);
+            for finding in cluster_findings.iter_mut() {
+                finding.cluster = cluster.clone();
+            }
+            findings.extend(cluster_findings);
+        }
+        findings.sort_by(|a, b| {
+            (a.severity, &a.cluster, a.check, &a.resource).cmp(&(
+                b.severity,
+                &b.cluster,
+                b.check,
+                &b.resource,
+            ))
+        });
+        print_findings(&findings);
+
+        let critical = findings
+            .iter()
+            .filter(|f| f.severity == Severity::Critical)
+            .count();
+        if critical > 0 {
+            return Err(anyhow::anyhow!("{critical} critical problems found"));
+        }
+        Ok(())
+    }
+
+    /// Run all the checks against the state of the cluster.
+    fn analyze_cluster(&self, state: &ClusterState) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        findings.extend(state.volumes.iter().filter_map(volume_finding));
+        findings.extend(
+            state
+                .pools
+                .iter()
+                .filter_map(|pool| pool_finding(pool, self.pool_usage_threshold)),
+        );
+        findings.extend(
+            state
+                .pods
+                .iter()
+                .flat_map(|pod| crash_loop_findings(pod, self.restart_threshold)),
+        );
+        findings.extend(version_finding(&state.pods));
+        findings.extend(rebuild_findings(
+            &state.volumes,
+            state.resampled_volumes.as_deref(),
+        ));
+        findings
+    }
+
+    /// Read the state of the live cluster, sampling the volumes again when some are rebuilding.
+    async fn read_live(
+        &self,
+        kube_config_path: Option<PathBuf>,
+        namespace: &str,
+        timeout: humantime::Duration,
+        proxy: Option<EgressProxy>,
+    ) -> anyhow::Result<ClusterState> {
+        let config = kube_proxy::ConfigBuilder::default_api_rest()
+            .with_kube_config(kube_config_path.clone())
+            .with_proxy(proxy)
+            .with_timeout(*timeout)
+            .with_target_mod(|t| t.with_namespace(namespace))
+            .build()
+            .await?;
+        let rest_client = RestClient::new_with_config(config);
+
+        let volumes = list_volumes(&rest_client).await?;
+        let pools = rest_client
+            .pools_api()
+            .get_pools()
+            .await
+            .map_err(|error| anyhow::anyhow!("Failed to list the pools: {error:?}"))?
+            .into_body();
+        let pods = ClientSet::new(kube_config_path, namespace.to_string())
+            .await
+            .map_err(|error| anyhow::anyhow!("Failed to create the kubernetes client: {error:?}"))?
+            .get_pods("", "")
+            .await
+            .map_err(|error| anyhow::anyhow!("Failed to list the pods: {error:?}"))?;
+
+        let resampled_volumes = match volumes.iter().any(is_rebuilding) {
+            true => {
+                println!(
+                    "Sampling the progress of the rebuilds again in {}...",
+                    self.rebuild_sample_interval
+                );
+                tokio::time::sleep(*self.rebuild_sample_interval).await;
+                Some(list_volumes(&rest_client).await?)
+            }
+            false => None,
+        };
+
+        Ok(ClusterState {
+            volumes,
+            pools,
+            pods,
+            resampled_volumes,
+        })
+    }
+}
+
+/// List all the volumes, page by page.
+async fn list_volumes(rest_client: &RestClient) -> anyhow::Result<Vec<Volume>> {
+    let mut volumes = Vec::new();
+    let mut next_token = Some(0);
+    while next_token.is_some() {
+        let page = rest_client
+            .volumes_api()
+            .get_volumes(MAX_RESOURCE_ENTRIES, None, next_token)
+            .await
+            .map_err(|error| anyhow::anyhow!("Failed to list the volumes: {error:?}"))?
+            .into_body();
+        volumes.extend(page.entries);
+        next_token = page.next_token;
+    }
+    Ok(volumes)
+}
+
+/// A volume is degraded when some of its replicas are not healthy, and faulted when none is.
+fn volume_finding(volume: &Volume) -> Option<Finding> {
+    let severity = match volume.state.status {
+        VolumeStatus::Online => return None,
+        VolumeStatus::Degraded => Severity::Warning,
+        _ => Severity::Critical,
+    };
+    let mut message = format!("The volume is {:?}", volume.state.status);
+    if let Some(target) = &volume.state.target {
+        let healthy = target
+            .children
+            .iter()
+            .filter(|child| child.state == ChildState::Online)
+            .count();
+        message.push_str(&format!(
+            ", {healthy} of {} replicas are healthy",
+            volume.spec.num_replicas
+        ));
+    }
+    Some(Finding::new(
+        severity,
+        VOLUME_CHECK,
+        volume.spec.uuid.to_string(),
+        message,
+        VOLUME_FIX,
+    ))
+}
+
+/// A pool is reported when it is not online, or when its usage reaches the threshold.
+fn pool_finding(pool: &Pool, usage_threshold: u8) -> Option<Finding> {
+    let Some(state) = &pool.state else {
+        return Some(Finding::new(
+            Severity::Critical,
+            POOL_CHECK,
+            &pool.id,
+            "The pool has no state, its node may be offline".to_string(),
+            POOL_FIX,
+        ));
+    };
+    if !matches!(state.status, PoolStatus::Online) {
+        return Some(Finding::new(
+            Severity::Critical,
+            POOL_CHECK,
+            &pool.id,
+            format!("The pool is {:?}", state.status),
+            POOL_FIX,
+        ));
+    }
+    if state.capacity == 0 {
+        return None;
+    }
+    let usage = state.used.saturating_mul(100) / state.capacity;
+    (usage >= u64::from(usage_threshold)).then(|| {
+        Finding::new(
+            Severity::Warning,
+            POOL_CHECK,
+            &pool.id,
+            format!(
+                "The pool is {usage}% full, {} of {} used",
+                bytesize::ByteSize(state.used),
+                bytesize::ByteSize(state.capacity)
+            ),
+            POOL_FIX,
+        )
+    })
+}
+
+/// A pod is crash-looping when one of its containers is backing off, or restarted too often.
+fn crash_loop_findings(pod: &Pod, restart_threshold: i32) -> Vec<Finding> {
+    let pod_name = pod.metadata.name.clone().unwrap_or_default();
+    let Some(status) = &pod.status else {
+        return Vec::new();
+    };
+    status
+        .init_container_statuses
+        .iter()
+        .chain(status.container_statuses.iter())
+        .flatten()
+        .filter_map(|container| {
+            let backing_off = container
+                .state
+                .as_ref()
+                .and_then(|state| state.waiting.as_ref())
+                .and_then(|waiting| waiting.reason.as_deref())
+                == Some("CrashLoopBackOff");
+            if !backing_off && container.restart_count < restart_threshold {
+                return None;
+            }
+            let last_reason = container
+                .last_state
+                .as_ref()
+                .and_then(|state| state.terminated.as_ref())
+                .and_then(|terminated| terminated.reason.clone())
+                .unwrap_or_else(|| "unknown".to_string());
+            let mut message = format!(
+                "Container {} restarted {} times, last terminated with reason: {last_reason}",
+                container.name, container.restart_count
+            );
+            if backing_off {
+                message.push_str(", it is in CrashLoopBackOff");
+            }
+            let severity = match backing_off {
+                true => Severity::Critical,
+                false => Severity::Warning,
+            };
+            Some(Finding::new(
+                severity,
+                CRASH_LOOP_CHECK,
+                &pod_name,
+                message,
+                CRASH_LOOP_FIX,
+            ))
+        })
+        .collect()
+}
+
+/// All the io-engine pods must run the same image.
+fn version_finding(pods: &[Pod]) -> Option<Finding> {
+    let mut images = BTreeMap::<String, Vec<String>>::new();
+    for pod in pods.iter().filter(|pod| is_io_engine(pod)) {
+        let Some(spec) = &pod.spec else {
+            continue;
+        };
+        let image = spec
+            .containers
+            .iter()
+            .find(|container| container.name == DATA_PLANE_CONTAINER_NAME)
+            .and_then(|container| container.image.clone());
+        if let Some(image) = image {
+            images
+                .entry(image)
+                .or_default()
+                .push(spec.node_name.clone().unwrap_or_default());
+        }
+    }
+    if images.len() < 2 {
+        return None;
+    }
+    let images = images
+        .iter()
+        .map(|(image, nodes)| format!("{image} on {}", nodes.join(", ")))
+        .collect::<Vec<_>>();
+    Some(Finding::new(
+        Severity::Warning,
+        VERSION_CHECK,
+        MAYASTOR_SERVICE,
+        format!(
+            "The io-engine pods run {} images: {}",
+            images.len(),
+            images.join("; ")
+        ),
+        VERSION_FIX,
+    ))
+}
+
+/// Whether the pod is an io-engine pod.
+fn is_io_engine(pod: &Pod) -> bool {
+    pod.metadata
+        .labels
+        .as_ref()
+        .and_then(|labels| labels.get("app"))
+        .map_or(false, |app| app == MAYASTOR_SERVICE)
+}
+
+/// Whether any replica of the volume is being rebuilt.
+fn is_rebuilding(volume: &Volume) -> bool {
+    volume.state.target.as_ref().map_or(false, |target| {
+        target
+            .children
+            .iter()
+            .any(|child| child.rebuild_progress.is_some())
+    })
+}
+
+/// The rebuilds which did not progress between the two samples are stuck. Without a second
+/// sample, as in the bundles, the rebuilds in progress are reported so that they can be
+/// checked on the live cluster.
+fn rebuild_findings(volumes: &[Volume], resampled: Option<&[Volume]>) -> Vec<Finding> {
+    let progress = resampled
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|volume| volume.state.target.as_ref())
+        .flat_map(|target| target.children.iter())
+        .filter_map(|child| Some((child.uri.as_str(), child.rebuild_progress?)))
+        .collect::<HashMap<_, _>>();
+
+    let mut findings = Vec::new();
+    for volume in volumes {
+        let Some(target) = &volume.state.target else {
+            continue;
+        };
+        for child in &target.children {
+            let Some(sampled) = child.rebuild_progress else {
+                continue;
+            };
+            let resource = format!("{}/{}", volume.spec.uuid, child.uri);
+            match (resampled, progress.get(child.uri.as_str())) {
+                (None, _) => findings.push(Finding::new(
+                    Severity::Warning,
+                    REBUILD_CHECK,
+                    resource,
+                    format!(
+                        "The replica was being rebuilt, at {sampled}% when collected, check that \
+                         the rebuild progresses on the live cluster"
+                    ),
+                    REBUILD_FIX,
+                )),
+                (Some(_), Some(&resampled)) if resampled <= sampled => findings.push(Finding::new(
+                    Severity::Critical,
+                    REBUILD_CHECK,
+                    resource,
+                    format!("The rebuild of the replica is stuck at {sampled}%"),
+                    REBUILD_FIX,
+                )),
+                _ => {}
+            }
+        }
+    }
+    findings
+}
+
+/// Volume topology file of a bundle, of which only the volume is analyzed.
+#[derive(Deserialize)]
+pub(crate) struct VolumeTopologyFile {
+    pub(crate) volume: Volume,
+}
+
+/// Pool topology file of a bundle, of which only the pool is analyzed.
+#[derive(Deserialize)]
+pub(crate) struct PoolTopologyFile {
+    pub(crate) pool: Pool,
+}
+
+/// Files of the bundles which are analyzed.
+enum BundleFile {
+    Volume,
+    Pool,
+    Pods,
+}
+
+/// Classify the file at the given path of the bundle, returning the directory of its cluster,
+/// which is empty unless the bundle holds several clusters.
+fn classify(path: &str) -> Option<(String, BundleFile)> {
+    let parts = path
+        .split('/')
+        .filter(|part| !part.is_empty() && *part != ".")
+        .collect::<Vec<_>>();
+    let root = parts
+        .iter()
+        .position(|part| *part == "topology" || *part == "k8s_resources")?;
+    let file = match &parts[root..] {
+        ["topology", "volume", name] if name.ends_with(".json") => BundleFile::Volume,
+        ["topology", "pool", name] if name.ends_with(".json") => BundleFile::Pool,
+        ["k8s_resources", "pods.yaml"] => BundleFile::Pods,
+        _ => return None,
+    };
+    Some((parts[..root].join("/"), file))
+}
+
+/// Read the state of the clusters of the bundle, keyed by the directory of the cluster.
+fn read_bundle(bundle: &Path) -> anyhow::Result<BTreeMap<String, ClusterState>> {
+    let mut clusters = BTreeMap::<String, ClusterState>::new();
+    let mut add_file = |path: &str, reader: &mut dyn Read| -> anyhow::Result<()> {
+        let Some((cluster, file)) = classify(path) else {
+            return Ok(());
+        };
+        let mut content = Vec::new();
+        reader.read_to_end(&mut content)?;
+        let state = clusters.entry(cluster).or_default();
+        match file {
+            BundleFile::Volume => state.volumes.push(
+                serde_json::from_slice::<VolumeTopologyFile>(&content)
+                    .with_context(|| format!("Failed to parse {path}"))?
+                    .volume,
+            ),
+            BundleFile::Pool => state.pools.push(
+                serde_json::from_slice::<PoolTopologyFile>(&content)
+                    .with_context(|| format!("Failed to parse {path}"))?
+                    .pool,
+            ),
+            BundleFile::Pods => state.pods.extend(
+                serde_yaml::from_slice::<Vec<Pod>>(&content)
+                    .with_context(|| format!("Failed to parse {path}"))?,
+            ),
+        }
+        Ok(())
+    };
+    walk_bundle(bundle, &|path| classify(path).is_some(), &mut add_file)?;
+
+    if clusters.is_empty() {
+        anyhow::bail!(
+            "No topology information nor pods were found in the bundle {}",
+            bundle.display()
+        );
+    }
+    Ok(clusters)
+}
+
+/// Call `read_file` with the path and the content of every file of the bundle which is wanted,
+/// the bundle being either an archive written by the dump or its extracted directory. The files
+/// are streamed, so that only the wanted ones are read out of large archives.
+pub(crate) fn walk_bundle(
+    bundle: &Path,
+    wanted: &dyn Fn(&str) -> bool,
+    read_file: &mut dyn FnMut(&str, &mut dyn Read) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let name = bundle.to_string_lossy();
+    if bundle.is_dir() {
+        read_dir(bundle, bundle, wanted, read_file)?;
+    } else if name.ends_with(".age") {
+        anyhow::bail!("The bundle is encrypted, decrypt it first, eg: `age -d -i <key> {name}`");
+    } else if name.ends_with(".zip") {
+        let mut archive = zip::ZipArchive::new(File::open(bundle)?)?;
+        for index in 0..archive.len() {
+            let mut file = archive.by_index(index)?;
+            if file.is_file() && wanted(file.name()) {
+                let path = file.name().to_string();
+                read_file(&path, &mut file)?;
+            }
+        }
+    } else {
+        let file = File::open(bundle)?;
+        let reader: Box<dyn Read> = if name.ends_with(".tar.zst") {
+            Box::new(zstd::Decoder::new(file)?)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Box::new(flate2::read::GzDecoder::new(file))
+        } else {
+            Box::new(file)
+        };
+        let mut archive = tar::Archive::new(reader);
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let path = entry.path()?.to_string_lossy().to_string();
+            if entry.header().entry_type().is_file() && wanted(&path) {
+                read_file(&path, &mut entry)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Recursively read the wanted files of the extracted bundle.
+fn read_dir(
+    root: &Path,
+    dir: &Path,
+    wanted: &dyn Fn(&str) -> bool,
+    read_file: &mut dyn FnMut(&str, &mut dyn Read) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            read_dir(root, &path, wanted, read_file)?;
+            continue;
+        }
+        // With `/` separators on every platform, as in the archives.
+        let relative = relative_path(root, &path);
+        if wanted(&relative) {
+            read_file(&relative, &mut File::open(&path)?)?;
+        }
+    }
+    Ok(())
+}
+
+/// Print the findings as a table, followed by the suggested fixes.
+fn print_findings(findings: &[Finding]) {
+    if findings.is_empty() {
+        println!("No problems found");
+        return;
+    }
+    let multi_cluster = findings.iter().any(|f| !f.cluster.is_empty());
+    let width = |header: &str, cell: fn(&Finding) -> &str| {
+        findings
+            .iter()
+            .map(|f| cell(f).len())
+            .chain(std::iter::once(header.len()))
+            .max()
+            .unwrap_or_default()
+    };
+    let cluster_width = width("CLUSTER", |f| &f.cluster);
+    let check_width = width("CHECK", |f| f.check);
+    let resource_width = width("RESOURCE", |f| &f.resource);
+
+    let cluster_column = |cluster: &str| match multi_cluster {
+        true => format!("{cluster:cluster_width$}  "),
+        false => String::new(),
+    };
+    println!(
+        "{}{:8}  {:check_width$}  {:resource_width$}  FINDING",
+        cluster_column("CLUSTER"),
+        "SEVERITY",
+        "CHECK",
+        "RESOURCE"
+    );
+    for finding in findings {
+        let severity = match finding.severity {
+            Severity::Critical => "CRITICAL",
+            Severity::Warning => "WARNING",
+        };
+        println!(
+            "{}{severity:8}  {:check_width$}  {:resource_width$}  {}",
+            cluster_column(&finding.cluster),
+            finding.check,
+            finding.resource,
+            finding.message
+        );
+    }
+
+    let mut checks = findings
+        .iter()
+        .map(|f| (f.check, f.fix))
+        .collect::<Vec<_>>();
+    checks.sort();
+    checks.dedup();
+    println!("\nSuggested fixes:");
+    for (check, fix) in checks {
+        println!("- {check}: {fix}");
+    }
+}
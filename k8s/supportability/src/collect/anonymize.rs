@@ -0,0 +1,337 @@
+use crate::collect::redact::collected_files;
+use regex::{Captures, Regex};
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    net::{Ipv4Addr, Ipv6Addr},
+    path::{Path, PathBuf},
+};
+
+/// UUIDs, eg: of the volumes, pools, replicas and nexuses
+const UUID_PATTERN: &str =
+    r"\b[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}\b";
+
+/// Candidate IPv4 addresses, validated before they are anonymized
+const IPV4_PATTERN: &str = r"\b(?:\d{1,3}\.){3}\d{1,3}\b";
+
+/// Candidate IPv6 addresses, validated before they are anonymized, so that eg: timestamps or MAC
+/// addresses are left alone
+const IPV6_PATTERN: &str = r"(?i)\b(?:[0-9a-f]{0,4}:){2,7}[0-9a-f]{0,4}\b|::[0-9a-f]{1,4}\b";
+
+/// Keys whose values are node names, in the kubernetes resources and the REST topologies
+const NODE_KEYS: &[&str] = &[
+    "nodeName",
+    "node",
+    "node_id",
+    "nodeId",
+    "hostname",
+    "kubernetes.io/hostname",
+];
+
+/// Keys whose values are pool names
+const POOL_KEYS: &[&str] = &["pool", "pool_id", "poolId"];
+
+/// Keys whose values are PVC names
+const PVC_KEYS: &[&str] = &["claimName", "csi.storage.k8s.io/pvc/name"];
+
+/// Kinds of the identifiers which are anonymized, along with the prefix of their pseudonyms
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "camelCase")]
+enum IdentifierKind {
+    Node,
+    Pool,
+    Pvc,
+    Uuid,
+    Ip,
+}
+
+/// Anonymizer replaces the identifying infrastructure data of the collected files, ie: the node
+/// names, IP addresses, volume and pool UUIDs, pool names and PVC names, with pseudonyms. The
+/// same identifier gets the same pseudonym across all the files of the bundle, so that they
+/// still refer to each other, eg: a volume in the REST topology and its nexus in the io-engine
+/// logs
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Anonymizer {
+    mapping_file: Option<PathBuf>,
+}
+
+impl Anonymizer {
+    /// Creates an anonymizer which writes the mapping of the identifiers to their pseudonyms to
+    /// the given local file, if any. The mapping file is never part of the bundle
+    pub(crate) fn new(mapping_file: Option<PathBuf>) -> Self {
+        Self { mapping_file }
+    }
+
+    /// Anonymizes all the collected files in the directory and its subdirectories in place,
+    /// including the names of the files, and writes the mapping file
+    pub(crate) fn anonymize_dir(&self, dir_path: &Path) -> Result<(), std::io::Error> {
+        let mut files = collected_files(dir_path)?;
+        // The pseudonyms are assigned in order of appearance, sorting makes them stable across
+        // runs collecting the same content.
+        files.sort();
+
+        let mut pseudonyms = Pseudonyms::default();
+        let mut contents = Vec::with_capacity(files.len());
+        for path in files {
+            let content = match fs::read_to_string(&path) {
+                Ok(content) => content,
+                // Skip the files which aren't text, they can't be anonymized
+                Err(error) if error.kind() == std::io::ErrorKind::InvalidData => continue,
+                Err(error) => return Err(error),
+            };
+            pseudonyms.discover(&content, path.extension().and_then(|e| e.to_str()));
+            contents.push((path, content));
+        }
+
+        let replacer = Replacer::new(&pseudonyms.names)?;
+        for (path, content) in contents {
+            let anonymized = replacer.replace(&content, &mut pseudonyms);
+            if anonymized != content {
+                fs::write(&path, anonymized)?;
+            }
+        }
+        rename_all(dir_path, &replacer, &mut pseudonyms)?;
+
+        if let Some(mapping_file) = &self.mapping_file {
+            let mapping = serde_json::to_string_pretty(&pseudonyms.mapping())
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+            fs::write(mapping_file, mapping)?;
+        }
+        Ok(())
+    }
+}
+
+/// The pseudonyms of the identifiers, assigned in order of appearance
+#[derive(Debug, Default)]
+struct Pseudonyms {
+    /// Names discovered from the structured files, which are replaced wherever they appear
+    names: BTreeMap<String, IdentifierKind>,
+    assigned: BTreeMap<String, (IdentifierKind, String)>,
+    counts: BTreeMap<IdentifierKind, u32>,
+}
+
+impl Pseudonyms {
+    /// Discovers the node, pool and PVC names from the yaml and json documents of the content
+    fn discover(&mut self, content: &str, extension: Option<&str>) {
+        match extension {
+            Some("yaml" | "yml") => {
+                for document in serde_yaml::Deserializer::from_str(content) {
+                    let Ok(value) =
+                        <serde_json::Value as serde::Deserialize>::deserialize(document)
+                    else {
+                        continue;
+                    };
+                    self.discover_value(&value);
+                }
+            }
+            Some("json") => {
+                if let Ok(value) = serde_json::from_str::<serde_json::Value>(content) {
+                    self.discover_value(&value);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Discovers the names from the keys they are held by, and from the names of the objects of
+    /// the kinds they identify
+    fn discover_value(&mut self, value: &serde_json::Value) {
+        match value {
+            serde_json::Value::Object(object) => {
+                let kind = match object.get("kind").and_then(|kind| kind.as_str()) {
+                    Some("Node") => Some(IdentifierKind::Node),
+                    Some("DiskPool" | "MayastorPool") => Some(IdentifierKind::Pool),
+                    Some("PersistentVolumeClaim") => Some(IdentifierKind::Pvc),
+                    _ => None,
+                };
+                if let Some(kind) = kind {
+                    let name = object
+                        .get("metadata")
+                        .and_then(|metadata| metadata.get("name"))
+                        .or_else(|| object.get("name"));
+                    self.add_name(name, kind);
+                }
+                for (key, value) in object {
+                    let kind = if NODE_KEYS.contains(&key.as_str()) {
+                        Some(IdentifierKind::Node)
+                    } else if POOL_KEYS.contains(&key.as_str()) {
+                        Some(IdentifierKind::Pool)
+                    } else if PVC_KEYS.contains(&key.as_str()) {
+                        Some(IdentifierKind::Pvc)
+                    } else {
+                        None
+                    };
+                    match kind {
+                        Some(kind) if value.is_string() => self.add_name(Some(value), kind),
+                        _ => self.discover_value(value),
+                    }
+                }
+            }
+            serde_json::Value::Array(array) => {
+                array.iter().for_each(|value| self.discover_value(value))
+            }
+            _ => {}
+        }
+    }
+
+    /// Adds the name, unless it's not a plain name, eg: a url, or it's an IP address, which is
+    /// anonymized regardless of where it appears
+    fn add_name(&mut self, name: Option<&serde_json::Value>, kind: IdentifierKind) {
+        let Some(name) = name.and_then(|name| name.as_str()) else {
+            return;
+        };
+        let plain = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+            && name.parse::<Ipv4Addr>().is_err()
+            && name != "localhost";
+        if plain {
+            self.names.entry(name.to_string()).or_insert(kind);
+        }
+    }
+
+    /// Gets the pseudonym of the identifier, assigning the next one of its kind if it has none
+    fn pseudonym(&mut self, identifier: &str, kind: IdentifierKind) -> String {
+        if let Some((_, pseudonym)) = self.assigned.get(identifier) {
+            return pseudonym.clone();
+        }
+        let count = self.counts.entry(kind).or_default();
+        *count += 1;
+        let pseudonym = match kind {
+            IdentifierKind::Node => format!("node-{count}"),
+            IdentifierKind::Pool => format!("pool-{count}"),
+            IdentifierKind::Pvc => format!("pvc-{count}"),
+            IdentifierKind::Uuid => format!("00000000-0000-4000-8000-{count:012x}"),
+            // Addresses of the benchmarking range of IPv4, and the documentation prefix of IPv6
+            IdentifierKind::Ip if identifier.contains(':') => format!("2001:db8::{count:x}"),
+            IdentifierKind::Ip => {
+                let [_, b, c, d] = count.to_be_bytes();
+                Ipv4Addr::new(198, 18 + (b & 1), c, d).to_string()
+            }
+        };
+        self.assigned
+            .insert(identifier.to_string(), (kind, pseudonym.clone()));
+        pseudonym
+    }
+
+    /// The mapping of the identifiers to their pseudonyms, by kind
+    fn mapping(&self) -> BTreeMap<IdentifierKind, BTreeMap<&str, &str>> {
+        let mut mapping = BTreeMap::<_, BTreeMap<_, _>>::new();
+        for (identifier, (kind, pseudonym)) in &self.assigned {
+            mapping
+                .entry(*kind)
+                .or_default()
+                .insert(identifier.as_str(), pseudonym.as_str());
+        }
+        mapping
+    }
+}
+
+/// Replaces the identifiers in the content with their pseudonyms
+struct Replacer {
+    names: Option<Regex>,
+    uuid: Regex,
+    ipv4: Regex,
+    ipv6: Regex,
+    kinds: BTreeMap<String, IdentifierKind>,
+}
+
+impl Replacer {
+    /// Creates the replacer of the discovered names, along with the UUIDs and IP addresses
+    fn new(names: &BTreeMap<String, IdentifierKind>) -> Result<Self, std::io::Error> {
+        // The longest names come first, so that a name isn't replaced within a longer one.
+        let mut alternatives = names
+            .keys()
+            .map(|name| regex::escape(name))
+            .collect::<Vec<_>>();
+        alternatives.sort_by(|a, b| b.len().cmp(&a.len()).then(a.cmp(b)));
+        let names_regex = match alternatives.is_empty() {
+            true => None,
+            false => Some(
+                Regex::new(&format!(r"\b(?:{})\b", alternatives.join("|")))
+                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?,
+            ),
+        };
+        let regex = |pattern| Regex::new(pattern).expect("anonymization patterns should be valid");
+        Ok(Self {
+            names: names_regex,
+            uuid: regex(UUID_PATTERN),
+            ipv4: regex(IPV4_PATTERN),
+            ipv6: regex(IPV6_PATTERN),
+            kinds: names.clone(),
+        })
+    }
+
+    /// Replaces the identifiers in the content, assigning the pseudonyms of the new ones
+    fn replace(&self, content: &str, pseudonyms: &mut Pseudonyms) -> String {
+        let content = self
+            .uuid
+            .replace_all(content, |captures: &Captures| {
+                pseudonyms.pseudonym(&captures[0].to_ascii_lowercase(), IdentifierKind::Uuid)
+            })
+            .into_owned();
+        let content = self
+            .ipv4
+            .replace_all(&content, |captures: &Captures| {
+                match captures[0].parse::<Ipv4Addr>() {
+                    Ok(ip) if !ip.is_loopback() && !ip.is_unspecified() && !ip.is_broadcast() => {
+                        pseudonyms.pseudonym(&captures[0], IdentifierKind::Ip)
+                    }
+                    _ => captures[0].to_string(),
+                }
+            })
+            .into_owned();
+        let content = self
+            .ipv6
+            .replace_all(&content, |captures: &Captures| {
+                match captures[0].parse::<Ipv6Addr>() {
+                    Ok(ip) if !ip.is_loopback() && !ip.is_unspecified() => {
+                        pseudonyms.pseudonym(&ip.to_string(), IdentifierKind::Ip)
+                    }
+                    _ => captures[0].to_string(),
+                }
+            })
+            .into_owned();
+        match &self.names {
+            Some(names) => names
+                .replace_all(&content, |captures: &Captures| {
+                    let name = &captures[0];
+                    pseudonyms.pseudonym(name, self.kinds[name])
+                })
+                .into_owned(),
+            None => content,
+        }
+    }
+}
+
+/// Renames the files and the directories whose names hold identifiers, eg: the topologies of
+/// the nodes and the volumes, deepest first so that the paths of the entries remain valid
+fn rename_all(
+    dir_path: &Path,
+    replacer: &Replacer,
+    pseudonyms: &mut Pseudonyms,
+) -> Result<(), std::io::Error> {
+    let mut entries = BTreeSet::new();
+    let mut dirs = vec![dir_path.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                dirs.push(path.clone());
+            }
+            entries.insert(path);
+        }
+    }
+    for path in entries.into_iter().rev() {
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let anonymized = replacer.replace(name, pseudonyms);
+        if anonymized != name {
+            fs::rename(&path, path.with_file_name(anonymized))?;
+        }
+    }
+    Ok(())
+}
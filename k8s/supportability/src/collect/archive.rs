@@ -1,48 +1,421 @@
-use crate::collect::error::Error;
+use crate::collect::{
+    anonymize::Anonymizer,
+    common::STDOUT_OUTPUT_PATH,
+    error::Error,
+    manifest::{relative_path, sha256_digest, ArtifactFailure, IntegritySummary, Manifest},
+    preview::BundlePreview,
+    progress,
+    redact::Redactor,
+};
 use chrono::Utc;
 use flate2::{write::GzEncoder, Compression};
-use std::fs::File;
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 use tar::Builder;
+use tracing::warn;
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
 
 // Holds prefix of archive file name
 const ARCHIVE_PREFIX: &str = "mayastor";
+// Holds extension appended to the name of encrypted archive files
+const ENCRYPTED_ARCHIVE_EXTENSION: &str = "age";
+// Holds the compression level of the zstd compressed archives, zstd's default
+const ZSTD_COMPRESSION_LEVEL: i32 = 3;
+// Holds extension appended to the name of the checksum file of the archive file
+const CHECKSUM_EXTENSION: &str = "sha256";
+// Holds the namespace of the SSH signatures of the archive files, which the signatures are
+// verified against
+const SIGNATURE_NAMESPACE: &str = "mayastor-support-bundle";
+// Holds the unix permissions of the directories of the zip archives
+const ZIP_DIR_PERMISSIONS: u32 = 0o755;
+// Holds the unix permissions of the files of the zip archives
+const ZIP_FILE_PERMISSIONS: u32 = 0o644;
 
-/// Archive is a wrapper around tar::Writer to create archive files
+/// Format of the archive file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub(crate) enum ArchiveFormat {
+    /// A zstd compressed tar file, faster to create and smaller than a gzip compressed one
+    #[default]
+    #[value(name = "tar.zst")]
+    TarZst,
+    /// A gzip compressed tar file
+    #[value(name = "tar.gz")]
+    TarGz,
+    /// A zip file, which can be opened natively on Windows
+    Zip,
+}
+
+impl ArchiveFormat {
+    /// Extension of the archive files of the format
+    fn extension(&self) -> &'static str {
+        match self {
+            Self::TarZst => "tar.zst",
+            Self::TarGz => "tar.gz",
+            Self::Zip => "zip",
+        }
+    }
+}
+
+/// Public key of a recipient of the encrypted archive, either an age public key
+/// (eg: age1...) or an SSH public key (eg: ssh-ed25519 AAAA...)
+#[derive(Debug, Clone)]
+pub(crate) struct ArchiveRecipient(String);
+
+impl ArchiveRecipient {
+    /// Parses the public key into a recipient which can be used by the age encryptor
+    fn to_age(&self) -> Result<Box<dyn age::Recipient + Send>, String> {
+        if let Ok(recipient) = age::x25519::Recipient::from_str(&self.0) {
+            return Ok(Box::new(recipient));
+        }
+        match age::ssh::Recipient::from_str(&self.0) {
+            Ok(recipient) => Ok(Box::new(recipient)),
+            Err(_) => Err(format!(
+                "'{}' is neither an age nor a supported SSH public key",
+                self.0
+            )),
+        }
+    }
+}
+
+impl FromStr for ArchiveRecipient {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let recipient = Self(s.trim().to_string());
+        recipient.to_age()?;
+        Ok(recipient)
+    }
+}
+
+/// Destination of the compressed archive, i.e. the archive file or stdout, which is encrypted
+/// on the fly when recipients are given so that the unencrypted archive is never written to disk
+enum ArchiveWriter {
+    Plain(Box<dyn Write + Send>),
+    Encrypted(age::stream::StreamWriter<Box<dyn Write + Send>>),
+}
+
+impl ArchiveWriter {
+    /// Flushes the remaining content and, if encrypted, writes the final chunk
+    fn finish(self) -> Result<(), std::io::Error> {
+        match self {
+            Self::Plain(mut output) => output.flush(),
+            Self::Encrypted(writer) => writer.finish()?.flush(),
+        }
+    }
+}
+
+impl Write for ArchiveWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Self::Plain(output) => output.write(buf),
+            Self::Encrypted(writer) => writer.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Self::Plain(output) => output.flush(),
+            Self::Encrypted(writer) => writer.flush(),
+        }
+    }
+}
+
+/// Builder of the content of the archive file, in its format
+enum ArchiveBuilder {
+    TarZst(Builder<zstd::Encoder<'static, ArchiveWriter>>),
+    TarGz(Builder<GzEncoder<ArchiveWriter>>),
+    // The zip central directory is written by seeking back into the file, hence zip archives
+    // are never encrypted on the fly
+    Zip(ZipWriter<File>),
+}
+
+impl ArchiveBuilder {
+    /// Copies source directory & it's contents recursively into destination directory of
+    /// the archive, and writes the end of the archive
+    fn append_dir_all_and_finish(
+        self,
+        dest_dir: &str,
+        src_dir: &str,
+    ) -> Result<(), std::io::Error> {
+        match self {
+            Self::TarZst(mut tar_writer) => {
+                tar_writer.append_dir_all(dest_dir, src_dir)?;
+                tar_writer.into_inner()?.finish()?.finish()
+            }
+            Self::TarGz(mut tar_writer) => {
+                tar_writer.append_dir_all(dest_dir, src_dir)?;
+                tar_writer.into_inner()?.finish()?.finish()
+            }
+            Self::Zip(mut zip_writer) => {
+                append_dir_to_zip(&mut zip_writer, Path::new(src_dir), Path::new(dest_dir))?;
+                zip_writer.finish()?.flush()
+            }
+        }
+    }
+}
+
+/// Recursively copies the contents of the source directory into the destination directory of
+/// the zip archive
+fn append_dir_to_zip(
+    zip_writer: &mut ZipWriter<File>,
+    src_dir: &Path,
+    dest_dir: &Path,
+) -> Result<(), std::io::Error> {
+    // The entries are named with `/` separators and given the permissions explicitly, rather
+    // than those of the platform the bundle is created on, eg: `\` separated and without unix
+    // permissions on Windows, so that the archive extracts the same everywhere.
+    let options = FileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .large_file(true);
+    // The root of the archive, i.e. `.`, has no entry of its own.
+    let dir_name = relative_path(Path::new(""), dest_dir);
+    if !dir_name.is_empty() {
+        zip_writer.add_directory(dir_name, options.unix_permissions(ZIP_DIR_PERMISSIONS))?;
+    }
+    let mut entries = std::fs::read_dir(src_dir)?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let dest_path = dest_dir.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            append_dir_to_zip(zip_writer, &entry.path(), &dest_path)?;
+        } else {
+            zip_writer.start_file(
+                relative_path(Path::new(""), &dest_path),
+                options.unix_permissions(ZIP_FILE_PERMISSIONS),
+            )?;
+            std::io::copy(&mut File::open(entry.path())?, zip_writer)?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes the SHA256 checksum of the file into `<file>.sha256`, in the format of sha256sum so
+/// that it can be verified with `sha256sum -c <file>.sha256`
+fn write_checksum_file(file_path: &Path) -> Result<(), std::io::Error> {
+    let file_name = file_path
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    let checksum = format!("{}  {file_name}\n", sha256_digest(file_path)?);
+    std::fs::write(
+        format!("{}.{CHECKSUM_EXTENSION}", file_path.display()),
+        checksum,
+    )
+}
+
+/// Signs the file with the SSH private key through `ssh-keygen -Y sign`, which writes the
+/// detached signature into `<file>.sig`. It can be verified with `ssh-keygen -Y verify -f
+/// <allowed_signers> -I <identity> -n mayastor-support-bundle -s <file>.sig < <file>`
+fn sign_file(file_path: &Path, sign_key: &Path) -> Result<(), std::io::Error> {
+    let output = std::process::Command::new("ssh-keygen")
+        .args(["-Y", "sign", "-n", SIGNATURE_NAMESPACE, "-f"])
+        .arg(sign_key)
+        .arg(file_path)
+        .output()?;
+    if !output.status.success() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!(
+                "failed to sign the archive file {} with ssh-keygen: {}",
+                file_path.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Archive is a wrapper around the tar and zip writers to create archive files
 pub(crate) struct Archive {
-    tar_writer: Option<Builder<GzEncoder<File>>>,
+    builder: Option<ArchiveBuilder>,
+    file_path: Option<PathBuf>,
+    finalized: bool,
+    redactor: Option<Redactor>,
+    anonymizer: Option<Anonymizer>,
+    sign_key: Option<PathBuf>,
+    failures: Vec<ArtifactFailure>,
 }
 
 impl Archive {
-    /// Creates new archive file with 'mayastor-<timestamp>.tar.gz' in provided directory
-    pub(crate) fn new(dir_path: Option<String>) -> Result<Self, Error> {
-        let tar = if let Some(dir_path) = dir_path {
+    /// Creates new archive file with 'mayastor-<timestamp>.<extension>' of the format in
+    /// provided directory, or 'mayastor-<timestamp>.<extension>.age' encrypted to the given
+    /// recipients. The archive is streamed to stdout instead when the directory is `-`
+    pub(crate) fn new(
+        dir_path: Option<String>,
+        recipients: &[ArchiveRecipient],
+        format: ArchiveFormat,
+    ) -> Result<Self, Error> {
+        let mut file_path = None;
+        let builder = if let Some(dir_path) = dir_path {
             let date = Utc::now();
-            let archive_file_name = format!(
-                "{}-{}.tar.gz",
+            let mut archive_file_name = format!(
+                "{}-{}.{}",
                 ARCHIVE_PREFIX,
-                date.format("%Y-%m-%d--%H-%M-%S-%Z")
+                date.format("%Y-%m-%d--%H-%M-%S-%Z"),
+                format.extension()
             );
+            let encryptor = if recipients.is_empty() {
+                None
+            } else if format == ArchiveFormat::Zip {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "zip archives can't be encrypted, use the tar.zst or tar.gz format instead",
+                )
+                .into());
+            } else if format == ArchiveFormat::Zip && dir_path == STDOUT_OUTPUT_PATH {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "zip archives can't be streamed to stdout, use the tar.zst or tar.gz format \
+                    instead",
+                )
+                .into());
+            } else {
+                archive_file_name = format!("{archive_file_name}.{ENCRYPTED_ARCHIVE_EXTENSION}");
+                let recipients = recipients
+                    .iter()
+                    .map(ArchiveRecipient::to_age)
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+                age::Encryptor::with_recipients(recipients)
+            };
             let tar_file_name = std::path::Path::new(&dir_path).join(archive_file_name);
-            let tar_file = File::create(tar_file_name)?;
-            let tar_gz = GzEncoder::new(tar_file, Compression::default());
-            Some(Builder::new(tar_gz))
+            if format == ArchiveFormat::Zip {
+                let tar_file = File::create(&tar_file_name)?;
+                file_path = Some(tar_file_name);
+                Some(ArchiveBuilder::Zip(ZipWriter::new(tar_file)))
+            } else {
+                let output: Box<dyn Write + Send> = match dir_path == STDOUT_OUTPUT_PATH {
+                    true => Box::new(BufWriter::new(std::io::stdout())),
+                    false => {
+                        let tar_file = File::create(&tar_file_name)?;
+                        file_path = Some(tar_file_name);
+                        Box::new(tar_file)
+                    }
+                };
+                let writer = match encryptor {
+                    Some(encryptor) => ArchiveWriter::Encrypted(encryptor.wrap_output(output)?),
+                    None => ArchiveWriter::Plain(output),
+                };
+                Some(match format {
+                    ArchiveFormat::TarGz => ArchiveBuilder::TarGz(Builder::new(GzEncoder::new(
+                        writer,
+                        Compression::default(),
+                    ))),
+                    _ => ArchiveBuilder::TarZst(Builder::new(zstd::Encoder::new(
+                        writer,
+                        ZSTD_COMPRESSION_LEVEL,
+                    )?)),
+                })
+            }
         } else {
             None
         };
-        Ok(Self { tar_writer: tar })
+        Ok(Self {
+            builder,
+            file_path,
+            finalized: false,
+            redactor: None,
+            anonymizer: None,
+            sign_key: None,
+            failures: Vec::new(),
+        })
+    }
+
+    /// Redacts the content with the given redactor before it is copied into the archive file
+    pub(crate) fn with_redactor(mut self, redactor: Option<Redactor>) -> Self {
+        self.redactor = redactor;
+        self
+    }
+
+    /// Anonymizes the content with the given anonymizer before it is copied into the archive
+    /// file
+    pub(crate) fn with_anonymizer(mut self, anonymizer: Option<Anonymizer>) -> Self {
+        self.anonymizer = anonymizer;
+        self
+    }
+
+    /// Signs the archive file with the given SSH private key once it is written
+    pub(crate) fn with_sign_key(mut self, sign_key: Option<PathBuf>) -> Self {
+        self.sign_key = sign_key;
+        self
+    }
+
+    /// Records the artifacts whose collection failed in the manifest of the archive
+    pub(crate) fn set_failures(&mut self, failures: Vec<ArtifactFailure>) {
+        self.failures = failures;
     }
 
     /// Copies source directory & it's contents recursively into destination
-    /// directory of archive file
+    /// directory of archive file, along with the manifest indexing them and its integrity
+    /// summary, and finalizes the archive file. The checksum file of the archive file, its
+    /// preview and its signature if a signing key is given, are then written next to it. The
+    /// preview is printed as well, for a final review of the bundle before it is uploaded
     pub(crate) fn copy_to_archive(
         &mut self,
         src_dir: String,
         dest_dir: String,
     ) -> Result<(), std::io::Error> {
-        if let Some(tar_writer) = self.tar_writer.as_mut() {
-            tar_writer.append_dir_all(dest_dir, src_dir)?;
-            tar_writer.finish()?;
+        if let Some(builder) = self.builder.take() {
+            if let Some(redactor) = &self.redactor {
+                redactor.redact_dir(Path::new(&src_dir))?;
+            }
+            if let Some(anonymizer) = &self.anonymizer {
+                anonymizer.anonymize_dir(Path::new(&src_dir))?;
+            }
+            // The manifest is built last, so that the checksums match the redacted and anonymized
+            // content.
+            // The archive is still worth having without it.
+            if let Err(e) =
+                Manifest::build(Path::new(&src_dir), &self.failures).and_then(|manifest| {
+                    manifest.dump(Path::new(&src_dir))?;
+                    IntegritySummary::build(Path::new(&src_dir), &manifest)?
+                        .dump(Path::new(&src_dir))
+                })
+            {
+                warn!("Failed to write the manifest of the archive, error: {e:?}");
+            }
+            let preview = BundlePreview::build(
+                Path::new(&src_dir),
+                self.redactor.is_some(),
+                self.anonymizer.is_some(),
+            )
+            .map_err(|e| warn!("Failed to list the content of the archive, error: {e:?}"))
+            .ok();
+            builder.append_dir_all_and_finish(&dest_dir, &src_dir)?;
+            self.finalized = true;
+
+            if let Some(file_path) = &self.file_path {
+                if let Err(e) = write_checksum_file(file_path) {
+                    warn!("Failed to write the checksum file of the archive, error: {e:?}");
+                }
+                if let Some(preview) = &preview {
+                    if let Err(e) = preview.dump(file_path) {
+                        warn!("Failed to write the preview of the archive, error: {e:?}");
+                    }
+                    if !progress::streams_json() {
+                        print!("{}", preview.render());
+                    }
+                }
+                if let Some(sign_key) = &self.sign_key {
+                    sign_file(file_path, sign_key)?;
+                }
+            } else if let Some(preview) = &preview {
+                // Stdout carries the archive.
+                eprint!("{}", preview.render());
+            }
         }
         Ok(())
     }
+
+    /// Path of the archive file, once its content has been successfully written
+    pub(crate) fn file_path(&self) -> Option<&Path> {
+        match self.finalized {
+            true => self.file_path.as_deref(),
+            false => None,
+        }
+    }
 }
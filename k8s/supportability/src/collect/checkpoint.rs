@@ -0,0 +1,64 @@
+use crate::collect::common::DUMP_TMP_PREFIX;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeSet,
+    fs::File,
+    path::{Path, PathBuf},
+};
+
+/// Name of the file which holds the checkpoint in the working directory of the dump.
+pub(crate) const CHECKPOINT_FILE: &str = "checkpoint.json";
+
+/// Checkpoint keeps track of the collectors which completed, so that an interrupted collection
+/// can be resumed from the same working directory without collecting them again.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub(crate) struct Checkpoint {
+    #[serde(skip)]
+    path: PathBuf,
+    completed: BTreeSet<String>,
+}
+
+impl Checkpoint {
+    /// Load the checkpoint of the working directory, or start a new one if there is none.
+    pub(crate) fn load_or_new(dir_path: &str) -> Result<Self, std::io::Error> {
+        let path = Path::new(dir_path).join(CHECKPOINT_FILE);
+        let mut checkpoint = match path.exists() {
+            true => serde_json::from_reader::<_, Self>(File::open(&path)?)?,
+            false => Self::default(),
+        };
+        checkpoint.path = path;
+        Ok(checkpoint)
+    }
+
+    /// Whether the collector already completed.
+    pub(crate) fn is_completed(&self, collector: &str) -> bool {
+        self.completed.contains(collector)
+    }
+
+    /// Mark the collector as completed and persist the checkpoint. The checkpoint is written to
+    /// a temporary file first, so that it is never left half written.
+    pub(crate) fn complete(&mut self, collector: &str) -> Result<(), std::io::Error> {
+        self.completed.insert(collector.to_string());
+        let tmp_path = self.path.with_extension("json.tmp");
+        serde_json::to_writer_pretty(File::create(&tmp_path)?, self)?;
+        std::fs::rename(tmp_path, &self.path)
+    }
+}
+
+/// Find the working directory of the last interrupted dump in the output directory, i.e. the
+/// latest temporary directory holding a checkpoint.
+pub(crate) fn find_resumable_directory(output_directory: &Path) -> Option<String> {
+    let mut dirs = std::fs::read_dir(output_directory)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .map_or(false, |name| name.starts_with(DUMP_TMP_PREFIX))
+                && path.join(CHECKPOINT_FILE).is_file()
+        })
+        .collect::<Vec<_>>();
+    // The directories are suffixed with their creation time, so the latest one sorts last.
+    dirs.sort();
+    dirs.pop()?.into_os_string().into_string().ok()
+}
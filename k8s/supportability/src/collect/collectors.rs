@@ -0,0 +1,154 @@
+/// A collector of the system dump. The name of each collector is the one recorded in the
+/// checkpoint and the artifacts report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub(crate) enum Collector {
+    /// The topology information of the volumes, snapshots, pools and nodes.
+    #[value(name = "topology")]
+    Topology,
+    /// The logs of the services, from Loki or the kube-apiserver.
+    #[value(name = "logs")]
+    Logs,
+    /// The kubernetes resources of the product, its events and helm releases.
+    #[value(name = "k8s_resources", alias = "k8s")]
+    K8sResources,
+    /// The application pods mounting the volumes.
+    #[value(name = "app_pods")]
+    AppPods,
+    /// The state of the data-plane, from the gRPC api of the io-engines.
+    #[value(name = "io_engine_state", alias = "io_engine")]
+    IoEngineState,
+    /// The state of the metrics-exporters and of the call-home.
+    #[value(name = "observability")]
+    Observability,
+    /// The storage diagnostics of the io-engine nodes.
+    #[value(name = "node_diagnostics")]
+    NodeDiagnostics,
+    /// The core dumps of the io-engine nodes.
+    #[value(name = "core_dumps")]
+    CoreDumps,
+    /// The packet traces of the NVMe-oF ports of the nodes.
+    #[value(name = "packet_capture")]
+    PacketCapture,
+    /// The etcd keys of the product.
+    #[value(name = "etcd_dump", alias = "etcd")]
+    EtcdDump,
+}
+
+impl Collector {
+    /// All the collectors, in the order in which they are run.
+    pub(crate) const ALL: [Collector; 10] = [
+        Collector::Topology,
+        Collector::Logs,
+        Collector::K8sResources,
+        Collector::AppPods,
+        Collector::IoEngineState,
+        Collector::Observability,
+        Collector::NodeDiagnostics,
+        Collector::CoreDumps,
+        Collector::PacketCapture,
+        Collector::EtcdDump,
+    ];
+
+    /// Name of the collector.
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Collector::Topology => "topology",
+            Collector::Logs => "logs",
+            Collector::K8sResources => "k8s_resources",
+            Collector::AppPods => "app_pods",
+            Collector::IoEngineState => "io_engine_state",
+            Collector::Observability => "observability",
+            Collector::NodeDiagnostics => "node_diagnostics",
+            Collector::CoreDumps => "core_dumps",
+            Collector::PacketCapture => "packet_capture",
+            Collector::EtcdDump => "etcd_dump",
+        }
+    }
+
+    /// Shorter name the collector can be selected with, if any.
+    fn alias(&self) -> Option<&'static str> {
+        match self {
+            Collector::K8sResources => Some("k8s"),
+            Collector::IoEngineState => Some("io_engine"),
+            Collector::EtcdDump => Some("etcd"),
+            _ => None,
+        }
+    }
+
+    /// What the collector collects.
+    fn description(&self) -> &'static str {
+        match self {
+            Collector::Topology => {
+                "Topology of the volumes, snapshots, pools and nodes, from the REST API"
+            }
+            Collector::Logs => "Logs of the product's services, from Loki or the kube-apiserver",
+            Collector::K8sResources => {
+                "Kubernetes resources, custom resources, events and helm releases of the product"
+            }
+            Collector::AppPods => "Describe output and recent logs of the application pods",
+            Collector::IoEngineState => {
+                "Pools, replicas, nexuses and NVMe controllers, from the gRPC api of the io-engines"
+            }
+            Collector::Observability => {
+                "Metrics of the metrics-exporters, metrics and stats of the call-home"
+            }
+            Collector::NodeDiagnostics => "Storage diagnostics of the io-engine nodes",
+            Collector::CoreDumps => "Most recent core dumps of the io-engine nodes",
+            Collector::PacketCapture => "Packet traces of the NVMe-oF ports of the nodes",
+            Collector::EtcdDump => "Keys of the product's etcd prefix",
+        }
+    }
+
+    /// Flag which enables the collector, if it isn't run by default.
+    fn enabled_by(&self) -> Option<&'static str> {
+        match self {
+            Collector::AppPods => Some("--include-app-pods"),
+            Collector::NodeDiagnostics => Some("--node-diagnostics"),
+            Collector::CoreDumps => Some("--core-dumps"),
+            Collector::PacketCapture => Some("--packet-capture-node"),
+            _ => None,
+        }
+    }
+}
+
+/// Selection of the collectors which are run, through the `--only` and `--exclude` arguments.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CollectorFilter {
+    only: Vec<Collector>,
+    exclude: Vec<Collector>,
+}
+
+impl CollectorFilter {
+    /// Run only the given collectors if any, except for the excluded ones.
+    pub(crate) fn new(only: Vec<Collector>, exclude: Vec<Collector>) -> Self {
+        Self { only, exclude }
+    }
+
+    /// Whether the collector is selected. The collectors which aren't run by default still
+    /// require their own flag.
+    pub(crate) fn is_enabled(&self, collector: Collector) -> bool {
+        (self.only.is_empty() || self.only.contains(&collector))
+            && !self.exclude.contains(&collector)
+    }
+}
+
+/// Print the collectors of the system dump, with their description.
+pub(crate) fn print_collectors() {
+    let names = Collector::ALL
+        .iter()
+        .map(|collector| match collector.alias() {
+            Some(alias) => format!("{} ({alias})", collector.name()),
+            None => collector.name().to_string(),
+        })
+        .collect::<Vec<_>>();
+    let width = names.iter().map(String::len).max().unwrap_or_default();
+    for (collector, name) in Collector::ALL.iter().zip(names) {
+        match collector.enabled_by() {
+            Some(flag) => println!(
+                "{name:width$}  {}, requires {flag}",
+                collector.description()
+            ),
+            None => println!("{name:width$}  {}", collector.description()),
+        }
+    }
+}
@@ -1,5 +1,19 @@
-use crate::collect::{error::Error, rest_wrapper::RestClient};
+use crate::collect::{
+    anonymize::Anonymizer,
+    archive::{ArchiveFormat, ArchiveRecipient},
+    collectors::CollectorFilter,
+    error::Error,
+    k8s_resources::{core_dumps::CoreDumpConfig, packet_capture::PacketCaptureConfig},
+    logs::LogTime,
+    redact::Redactor,
+    rest_wrapper::RestClient,
+    size_limits::SizeLimits,
+};
 use chrono::Local;
+use std::{
+    path::PathBuf,
+    sync::atomic::{AtomicBool, Ordering},
+};
 
 #[cfg(debug_assertions)]
 use crate::collect::resources::traits::Topologer;
@@ -11,18 +25,48 @@ pub(crate) struct DumpConfig {
     pub(crate) rest_client: RestClient,
     /// directory path to create archive files
     pub(crate) output_directory: String,
+    /// Recipients to encrypt the archive file to, if any
+    pub(crate) encrypt_recipients: Vec<ArchiveRecipient>,
+    /// Format of the archive file
+    pub(crate) archive_format: ArchiveFormat,
+    /// SSH private key to sign the archive file with, if any
+    pub(crate) sign_key: Option<std::path::PathBuf>,
+    /// Redactor of the collected content, if redaction is enabled
+    pub(crate) redactor: Option<Redactor>,
+    /// Anonymizer of the collected content, if anonymization is enabled
+    pub(crate) anonymizer: Option<Anonymizer>,
     /// namespace of mayastor system
     pub(crate) namespace: String,
     /// Address of Loki service endpoint
     pub(crate) loki_uri: Option<String>,
     /// Address of etcd service endpoint
     pub(crate) etcd_uri: Option<String>,
+    /// Number of etcd keys fetched per page
+    pub(crate) etcd_page_size: i64,
     /// Period states to collect logs from specified duration
     pub(crate) since: humantime::Duration,
+    /// Time from which Loki logs are collected, overrides since
+    pub(crate) log_start: Option<LogTime>,
+    /// Time until which Loki logs are collected
+    pub(crate) log_end: Option<LogTime>,
     /// Path to kubeconfig file, which requires to interact with Kube-Apiserver
     pub(crate) kube_config_path: Option<std::path::PathBuf>,
+    /// Proxy through which the REST API and Loki are reached, if any
+    pub(crate) proxy: Option<kube_proxy::EgressProxy>,
     /// Specifies the timeout value to interact with other systems
     pub(crate) timeout: humantime::Duration,
+    /// Maximum number of resources collected concurrently
+    pub(crate) concurrency: usize,
+    /// Limits of the size of the collected content
+    pub(crate) size_limits: SizeLimits,
+    /// Image of the pods collecting the storage diagnostics of the nodes, if they are collected
+    pub(crate) node_diagnostics_image: Option<String>,
+    /// Which core dumps of the nodes are collected, if they are collected
+    pub(crate) core_dumps: Option<CoreDumpConfig>,
+    /// How the packets of the NVMe-oF ports of the nodes are captured, if they are captured
+    pub(crate) packet_capture: Option<PacketCaptureConfig>,
+    /// Selection of the collectors of the system dump which are run
+    pub(crate) collectors: CollectorFilter,
     #[cfg(debug_assertions)]
     /// Topologer implements functionality to build topological information of system
     pub(crate) topologer: Option<Box<dyn Topologer>>,
@@ -41,11 +85,46 @@ pub(crate) enum OutputFormat {
 /// Defines prefix name of temporary directory to create dump files
 pub(crate) const DUMP_TMP_PREFIX: &str = "tmp-mayastor";
 
+/// Defines the output directory path which streams the archive to stdout, instead of writing
+/// it into a file
+pub(crate) const STDOUT_OUTPUT_PATH: &str = "-";
+
+/// Whether the archive is streamed to stdout, which then carries nothing else.
+static STREAMS_ARCHIVE: AtomicBool = AtomicBool::new(false);
+
+/// Stream the archive to stdout, the messages to the user are then printed on stderr.
+pub(crate) fn stream_archive_to_stdout() {
+    STREAMS_ARCHIVE.store(true, Ordering::Relaxed);
+}
+
+/// Whether the archive is streamed to stdout.
+pub(crate) fn streams_archive() -> bool {
+    STREAMS_ARCHIVE.load(Ordering::Relaxed)
+}
+
+/// Print a message to the user, on stderr when stdout carries the archive.
+pub(crate) fn print_message(message: &str) {
+    match streams_archive() {
+        true => eprintln!("{message}"),
+        false => println!("{message}"),
+    }
+}
+
+/// The directory in which the dump artifacts are collected before they are archived, i.e. the
+/// output directory or, when the archive is streamed to stdout, the temporary directory of the
+/// system.
+pub(crate) fn staging_directory(output_directory: &str) -> PathBuf {
+    match output_directory {
+        STDOUT_OUTPUT_PATH => std::env::temp_dir(),
+        output_directory => PathBuf::from(output_directory),
+    }
+}
+
 /// Creates new temporary directory in given path to store dump artifacts
 pub(crate) fn create_and_get_tmp_directory(dir_path: String) -> Result<String, Error> {
     let date = Local::now();
     let suffix_dir_name = format!("{}-{}", DUMP_TMP_PREFIX, date.format("%Y-%m-%d-%H-%M-%S"));
-    let new_dir_path = std::path::Path::new(&dir_path).join(suffix_dir_name);
+    let new_dir_path = staging_directory(&dir_path).join(suffix_dir_name);
     std::fs::create_dir_all(new_dir_path.clone())?;
     Ok(new_dir_path.into_os_string().into_string()?)
 }
@@ -1,9 +1,15 @@
 use lazy_static::lazy_static;
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Duration};
 
 /// Defines the name of the core-agent service
 pub(crate) const CORE_AGENT_SERVICE: &str = "agent-core";
 
+/// Defines the namespace collected from when no installation of the product is detected
+pub(crate) const DEFAULT_NAMESPACE: &str = "mayastor";
+
+/// Defines the name of the helm chart of the product
+pub(crate) const HELM_CHART_NAME: &str = "mayastor";
+
 /// Defines the name of the csi-controller service
 pub(crate) const CSI_CONTROLLER_SERVICE: &str = "csi-controller";
 
@@ -28,9 +34,83 @@ pub(crate) const CSI_NODE_SERVICE: &str = "csi-node";
 /// Defines the name of the etcd service
 pub(crate) const ETCD_SERVICE: &str = "etcd";
 
-/// Defines the name of the etcd service
+/// Defines the default number of etcd keys fetched per page
 pub(crate) const ETCD_PAGED_LIMIT: i64 = 1000;
 
+/// Defines the name of the directory of the etcd dump in the archive
+pub(crate) const ETCD_DUMP_DIR: &str = "etcd";
+
+/// Defines the default number of resources collected concurrently
+pub(crate) const DEFAULT_CONCURRENCY: usize = 4;
+
+/// Defines the deadline of the quick dump
+pub(crate) const QUICK_DUMP_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Defines the maximum size of the content collected by the quick dump, in bytes
+pub(crate) const QUICK_DUMP_MAX_BYTES: u64 = 50 * 1024 * 1024;
+
+/// Defines the period of the logs collected by the quick dump
+pub(crate) const QUICK_DUMP_LOG_SINCE: Duration = Duration::from_secs(10 * 60);
+
+/// Defines the default image of the pods collecting the storage diagnostics of the nodes, which
+/// needs a shell and nsenter
+pub(crate) const NODE_DIAGNOSTICS_IMAGE: &str = "busybox:1.36";
+
+/// Defines the time the storage diagnostics pod of a node is given to complete
+pub(crate) const NODE_DIAGNOSTICS_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Defines the port of the gRPC api of the io-engine
+pub(crate) const IO_ENGINE_GRPC_PORT: u16 = 10124;
+
+/// Defines the time given to the gRPC requests to the io-engine of a node, including connecting
+pub(crate) const IO_ENGINE_STATE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Defines the name of the metrics-exporter container of the io-engine pods
+pub(crate) const METRICS_EXPORTER_CONTAINER: &str = "metrics-exporter-io-engine";
+
+/// Defines the port of the metrics endpoint of the metrics-exporter
+pub(crate) const METRICS_EXPORTER_PORT: u16 = 9502;
+
+/// Defines the port of the metrics endpoint of the call-home
+pub(crate) const CALLHOME_METRICS_PORT: u16 = 9091;
+
+/// Defines the port of the stats endpoints of the call-home
+pub(crate) const CALLHOME_STATS_PORT: u16 = 9090;
+
+/// Defines the time given to the http requests to an observability component, including
+/// connecting
+pub(crate) const OBSERVABILITY_STATE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Defines the directories of the hosts in which the core dumps of the io-engine are looked for
+pub(crate) const CORE_DUMP_PATHS: &[&str] = &["/var/lib/systemd/coredump", "/var/crash"];
+
+/// Defines the default number of core dumps collected per node
+pub(crate) const MAX_CORE_DUMPS: &str = "3";
+
+/// Defines the default maximum size of the core dumps collected per node
+pub(crate) const MAX_CORE_DUMPS_SIZE: &str = "2GiB";
+
+/// Defines the lifetime of the pods collecting the core dumps of the nodes, after which they
+/// exit should they not have been deleted
+pub(crate) const CORE_DUMPS_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// Defines the default image of the pods capturing the packets of the NVMe-oF ports of the
+/// nodes, which needs a shell and tcpdump
+pub(crate) const PACKET_CAPTURE_IMAGE: &str = "nicolaka/netshoot:v0.11";
+
+/// Defines the default TCP ports of the NVMe-oF targets whose packets are captured
+pub(crate) const PACKET_CAPTURE_PORTS: &[&str] = &["4420", "8420"];
+
+/// Defines the default duration of the packet capture on each node
+pub(crate) const PACKET_CAPTURE_DURATION: &str = "10s";
+
+/// Defines the default maximum size of the packet trace of each node
+pub(crate) const PACKET_CAPTURE_MAX_SIZE: &str = "50MiB";
+
+/// Defines the default number of bytes captured of each packet, enough for the headers of the
+/// NVMe/TCP PDUs without the bulk of the data they carry
+pub(crate) const PACKET_CAPTURE_SNAPLEN: &str = "256";
+
 /// Defines the name of mayastor service
 pub(crate) const MAYASTOR_SERVICE: &str = "io-engine";
 
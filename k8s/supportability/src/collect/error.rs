@@ -1,6 +1,6 @@
 use crate::collect::{
     k8s_resources::k8s_resource_dump::K8sResourceDumperError, logs::LogError,
-    persistent_store::EtcdError, resources::ResourceError,
+    persistent_store::EtcdError, resources::ResourceError, upload::UploadError,
 };
 use std::ffi::OsString;
 
@@ -15,7 +15,11 @@ pub(crate) enum Error {
     K8sResourceDumperError(K8sResourceDumperError),
     OSStringError(OsString),
     EtcdDumpError(EtcdError),
+    UploadError(UploadError),
     MultipleErrors(Vec<Error>),
+    DeadlineExceeded(std::time::Duration),
+    KubeConfigError(kube::config::KubeconfigError),
+    ClusterDumpError(String, std::process::ExitStatus),
 }
 
 impl From<std::io::Error> for Error {
@@ -53,3 +57,15 @@ impl From<EtcdError> for Error {
         Error::EtcdDumpError(e)
     }
 }
+
+impl From<UploadError> for Error {
+    fn from(e: UploadError) -> Self {
+        Error::UploadError(e)
+    }
+}
+
+impl From<kube::config::KubeconfigError> for Error {
+    fn from(e: kube::config::KubeconfigError) -> Self {
+        Error::KubeConfigError(e)
+    }
+}
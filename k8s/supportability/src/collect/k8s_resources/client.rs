@@ -1,16 +1,24 @@
-use crate::collect::k8s_resources::common::KUBERNETES_HOST_LABEL_KEY;
+use crate::collect::{
+    k8s_resources::common::KUBERNETES_HOST_LABEL_KEY,
+    retry::{with_retries, RequestError},
+};
 use k8s_operators::diskpool::crd::DiskPool;
 
 use k8s_openapi::api::{
     apps::v1::{DaemonSet, Deployment, StatefulSet},
-    core::v1::{Event, Node, Pod},
+    core::v1::{Event, Node, PersistentVolume, Pod, Secret},
+    storage::v1::StorageClass,
 };
 use kube::{
-    api::{DynamicObject, ListParams},
+    api::{DynamicObject, ListParams, LogParams},
     discovery::{verbs, Scope},
     Api, Client, Discovery, Resource,
 };
-use std::{collections::HashMap, convert::TryFrom};
+use std::{
+    collections::{HashMap, HashSet},
+    convert::TryFrom,
+    time::Duration,
+};
 
 const SNAPSHOT_GROUP: &str = "snapshot.storage.k8s.io";
 const SNAPSHOT_VERSION: &str = "v1";
@@ -55,6 +63,15 @@ impl From<Box<dyn std::error::Error>> for K8sResourceError {
     }
 }
 
+impl From<RequestError<kube::Error>> for K8sResourceError {
+    fn from(e: RequestError<kube::Error>) -> K8sResourceError {
+        match e {
+            RequestError::Failed(e) => K8sResourceError::ClientError(e),
+            RequestError::TimedOut(_) => K8sResourceError::CustomError(e.to_string()),
+        }
+    }
+}
+
 impl From<String> for K8sResourceError {
     fn from(e: String) -> K8sResourceError {
         K8sResourceError::CustomError(e)
@@ -93,6 +110,11 @@ impl ClientSet {
         Ok(Self { client, namespace })
     }
 
+    /// Get the namespace of mayastor service.
+    pub(crate) fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
     /// Get a clone of the inner `kube::Client`.
     pub(crate) fn kube_client(&self) -> kube::Client {
         self.client.clone()
@@ -138,7 +160,7 @@ impl ClientSet {
     /// Fetch node objects from API-server then form and return map of node name to node object
     pub(crate) async fn get_nodes_map(&self) -> Result<HashMap<String, Node>, K8sResourceError> {
         let node_api: Api<Node> = Api::all(self.client.clone());
-        let nodes = node_api.list(&ListParams::default()).await?;
+        let nodes = with_retries(|| node_api.list(&ListParams::default())).await?;
         let mut node_map = HashMap::new();
         for node in nodes.items {
             node_map.insert(
@@ -171,7 +193,7 @@ impl ClientSet {
         let pods_api: Api<Pod> = Api::namespaced(self.client.clone(), &self.namespace);
         // Paginate to get 100 contents at a time
         loop {
-            let mut result = pods_api.list(&list_params).await?;
+            let mut result = with_retries(|| pods_api.list(&list_params)).await?;
             pods.append(&mut result.items);
             match result.metadata.continue_ {
                 None => break,
@@ -181,6 +203,108 @@ impl ClientSet {
         Ok(pods)
     }
 
+    /// Fetch the pods, across all namespaces, which mount a persistent volume provisioned by the
+    /// given csi driver
+    pub(crate) async fn get_volume_consumer_pods(
+        &self,
+        driver: &str,
+    ) -> Result<Vec<Pod>, K8sResourceError> {
+        let pvs_api: Api<PersistentVolume> = Api::all(self.client.clone());
+        let claims = with_retries(|| pvs_api.list(&ListParams::default()))
+            .await?
+            .items
+            .into_iter()
+            .filter_map(|pv| {
+                let spec = pv.spec?;
+                if spec.csi?.driver != driver {
+                    return None;
+                }
+                let claim = spec.claim_ref?;
+                Some((claim.namespace?, claim.name?))
+            })
+            .collect::<HashSet<_>>();
+
+        let pods_api: Api<Pod> = Api::all(self.client.clone());
+        let pods = with_retries(|| pods_api.list(&ListParams::default())).await?;
+        Ok(pods
+            .items
+            .into_iter()
+            .filter(|pod| {
+                let namespace = pod.metadata.namespace.clone().unwrap_or_default();
+                pod.spec
+                    .iter()
+                    .flat_map(|spec| spec.volumes.iter().flatten())
+                    .filter_map(|volume| volume.persistent_volume_claim.as_ref())
+                    .any(|claim| claims.contains(&(namespace.clone(), claim.claim_name.clone())))
+            })
+            .collect())
+    }
+
+    /// Fetch the events of the given object in the given namespace
+    pub(crate) async fn get_object_events(
+        &self,
+        namespace: &str,
+        name: &str,
+    ) -> Result<Vec<Event>, K8sResourceError> {
+        let list_params =
+            ListParams::default().fields(format!("involvedObject.name={name}").as_str());
+        let events_api: Api<Event> = Api::namespaced(self.client.clone(), namespace);
+        Ok(with_retries(|| events_api.list(&list_params)).await?.items)
+    }
+
+    /// Fetch the events of the given node, which are recorded outside of the product namespace
+    pub(crate) async fn get_node_events(&self, node: &str) -> Result<Vec<Event>, K8sResourceError> {
+        let list_params = ListParams::default()
+            .fields(format!("involvedObject.kind=Node,involvedObject.name={node}").as_str());
+        let events_api: Api<Event> = Api::all(self.client.clone());
+        Ok(with_retries(|| events_api.list(&list_params)).await?.items)
+    }
+
+    /// Fetch the logs of a container of the given pod in the given namespace, from the last
+    /// `since` duration
+    pub(crate) async fn get_container_logs(
+        &self,
+        namespace: &str,
+        pod_name: &str,
+        container_name: &str,
+        since: Duration,
+    ) -> Result<String, K8sResourceError> {
+        let log_params = LogParams {
+            container: Some(container_name.to_string()),
+            since_seconds: Some(since.as_secs() as i64),
+            ..Default::default()
+        };
+        let pods_api: Api<Pod> = Api::namespaced(self.client.clone(), namespace);
+        Ok(pods_api.logs(pod_name, &log_params).await?)
+    }
+
+    /// Stream the logs of a container of the given pod in the given namespace, from the last
+    /// `since` duration, into the writer as they are received
+    pub(crate) async fn write_container_logs<W: std::io::Write>(
+        &self,
+        namespace: &str,
+        pod_name: &str,
+        container_name: &str,
+        since: Duration,
+        writer: &mut W,
+    ) -> Result<(), K8sResourceError> {
+        let log_params = LogParams {
+            container: Some(container_name.to_string()),
+            since_seconds: Some(since.as_secs() as i64),
+            ..Default::default()
+        };
+        let pods_api: Api<Pod> = Api::namespaced(self.client.clone(), namespace);
+        let log_stream = pods_api.log_stream(pod_name, &log_params).await?;
+        let mut writer = futures::io::AllowStdIo::new(writer);
+        futures::io::copy_buf(log_stream, &mut writer)
+            .await
+            .map_err(|e| K8sResourceError::ResourceError(Box::new(e)))?;
+        writer
+            .into_inner()
+            .flush()
+            .map_err(|e| K8sResourceError::ResourceError(Box::new(e)))
+    }
+
     /// get the k8s pod api for pod operations, like logs_stream
     pub(crate) async fn get_pod_api(&self) -> Api<Pod> {
         Api::namespaced(self.client.clone(), &self.namespace)
@@ -197,9 +321,9 @@ impl ClientSet {
             .labels(label_selector.unwrap_or_default())
             .fields(field_selector.unwrap_or_default());
         let pools_api: Api<DiskPool> = Api::namespaced(self.client.clone(), &self.namespace);
-        let pools = match pools_api.list(&list_params).await {
+        let pools = match with_retries(|| pools_api.list(&list_params)).await {
             Ok(val) => val,
-            Err(kube_error) => match kube_error {
+            Err(RequestError::Failed(kube_error)) => match kube_error {
                 kube::Error::Api(e) => {
                     if e.code == 404 {
                         return Ok(vec![]);
@@ -208,10 +332,25 @@ impl ClientSet {
                 }
                 _ => return Err(K8sResourceError::ClientError(kube_error)),
             },
+            Err(error) => return Err(error.into()),
         };
         Ok(pools.items)
     }
 
+    /// Fetch list of storage classes which use the given provisioner
+    pub(crate) async fn list_storage_classes(
+        &self,
+        provisioner: &str,
+    ) -> Result<Vec<StorageClass>, K8sResourceError> {
+        let sc_api: Api<StorageClass> = Api::all(self.client.clone());
+        let storage_classes = with_retries(|| sc_api.list(&ListParams::default())).await?;
+        Ok(storage_classes
+            .items
+            .into_iter()
+            .filter(|sc| sc.provisioner == provisioner)
+            .collect())
+    }
+
     /// Fetch list of volume snapshot classes based on the driver if provided.
     pub(crate) async fn list_volumesnapshot_classes(
         &self,
@@ -230,9 +369,9 @@ impl ClientSet {
                 VOLUME_SNAPSHOT_CLASS,
             )
             .await?;
-        let vscs = match vsc_api.list(&list_params).await {
+        let vscs = match with_retries(|| vsc_api.list(&list_params)).await {
             Ok(val) => val,
-            Err(kube_error) => match kube_error {
+            Err(RequestError::Failed(kube_error)) => match kube_error {
                 kube::Error::Api(e) => {
                     if e.code == 404 {
                         return Ok(vec![]);
@@ -241,6 +380,7 @@ impl ClientSet {
                 }
                 _ => return Err(K8sResourceError::ClientError(kube_error)),
             },
+            Err(error) => return Err(error.into()),
         };
         Ok(vscs
             .items
@@ -280,9 +420,9 @@ impl ClientSet {
 
         let mut vscs_filtered: Vec<DynamicObject> = vec![];
         loop {
-            let vscs = match vsc_api.list(&list_params).await {
+            let vscs = match with_retries(|| vsc_api.list(&list_params)).await {
                 Ok(val) => val,
-                Err(kube_error) => match kube_error {
+                Err(RequestError::Failed(kube_error)) => match kube_error {
                     kube::Error::Api(e) => {
                         if e.code == 404 {
                             return Ok(vec![]);
@@ -291,6 +431,7 @@ impl ClientSet {
                     }
                     _ => return Err(K8sResourceError::ClientError(kube_error)),
                 },
+                Err(error) => return Err(error.into()),
             };
             vscs_filtered.append(
                 &mut vscs
@@ -337,7 +478,7 @@ impl ClientSet {
         let events_api: Api<Event> = Api::namespaced(self.client.clone(), &self.namespace);
         // Paginate to get 100 contents at a time
         loop {
-            let mut result = events_api.list(&list_params).await?;
+            let mut result = with_retries(|| events_api.list(&list_params)).await?;
             events.append(&mut result.items);
             match result.metadata.continue_ {
                 Some(token) if !token.is_empty() => {
@@ -350,6 +491,33 @@ impl ClientSet {
         Ok(events)
     }
 
+    /// Fetch list of secrets associated to given label_selector & field_selector
+    pub(crate) async fn get_secrets(
+        &self,
+        label_selector: &str,
+        field_selector: &str,
+    ) -> Result<Vec<Secret>, K8sResourceError> {
+        let list_params = ListParams::default()
+            .labels(label_selector)
+            .fields(field_selector);
+
+        let secrets_api: Api<Secret> = Api::namespaced(self.client.clone(), &self.namespace);
+        let secrets = with_retries(|| secrets_api.list(&list_params)).await?;
+        Ok(secrets.items)
+    }
+
+    /// Fetch list of secrets associated to given label_selector across all the namespaces
+    pub(crate) async fn get_all_namespaces_secrets(
+        &self,
+        label_selector: &str,
+    ) -> Result<Vec<Secret>, K8sResourceError> {
+        let list_params = ListParams::default().labels(label_selector);
+
+        let secrets_api: Api<Secret> = Api::all(self.client.clone());
+        let secrets = with_retries(|| secrets_api.list(&list_params)).await?;
+        Ok(secrets.items)
+    }
+
     /// Fetch list of deployments associated to given label_selector & field_selector
     pub(crate) async fn get_deployments(
         &self,
@@ -362,7 +530,19 @@ impl ClientSet {
 
         let deployments_api: Api<Deployment> =
             Api::namespaced(self.client.clone(), &self.namespace);
-        let deployments = deployments_api.list(&list_params).await?;
+        let deployments = with_retries(|| deployments_api.list(&list_params)).await?;
+        Ok(deployments.items)
+    }
+
+    /// Fetch list of deployments associated to given label_selector across all the namespaces
+    pub(crate) async fn get_all_namespaces_deployments(
+        &self,
+        label_selector: &str,
+    ) -> Result<Vec<Deployment>, K8sResourceError> {
+        let list_params = ListParams::default().labels(label_selector);
+
+        let deployments_api: Api<Deployment> = Api::all(self.client.clone());
+        let deployments = with_retries(|| deployments_api.list(&list_params)).await?;
         Ok(deployments.items)
     }
 
@@ -377,7 +557,7 @@ impl ClientSet {
             .fields(field_selector);
 
         let ds_api: Api<DaemonSet> = Api::namespaced(self.client.clone(), &self.namespace);
-        let daemonsets = ds_api.list(&list_params).await?;
+        let daemonsets = with_retries(|| ds_api.list(&list_params)).await?;
         Ok(daemonsets.items)
     }
 
@@ -392,7 +572,7 @@ impl ClientSet {
             .fields(field_selector);
 
         let sts_api: Api<StatefulSet> = Api::namespaced(self.client.clone(), &self.namespace);
-        let statefulsets = sts_api.list(&list_params).await?;
+        let statefulsets = with_retries(|| sts_api.list(&list_params)).await?;
         Ok(statefulsets.items)
     }
 
@@ -400,7 +580,7 @@ impl ClientSet {
     /// object labels
     pub(crate) async fn get_hostname(&self, node_name: &str) -> Result<String, K8sResourceError> {
         let node_api: Api<Node> = Api::all(self.client.clone());
-        let node = node_api.get(node_name).await?;
+        let node = with_retries(|| node_api.get(node_name)).await?;
 
         // Labels will definitely exists on Kubernetes node object
         let labels = node.meta().labels.as_ref().ok_or_else(|| {
@@ -421,12 +601,13 @@ impl ClientSet {
     /// Get node name from a specified hostname
     pub(crate) async fn get_nodename(&self, host_name: &str) -> Result<String, K8sResourceError> {
         let node_api: Api<Node> = Api::all(self.client.clone());
-        let node = node_api
-            .list(
+        let node = with_retries(|| {
+            node_api.list(
                 &ListParams::default()
                     .labels(format!("{KUBERNETES_HOST_LABEL_KEY}={host_name}").as_str()),
             )
-            .await?;
+        })
+        .await?;
         if node.items.is_empty() {
             return Err(K8sResourceError::CustomError(format!(
                 "No node found for hostname {host_name}"
@@ -0,0 +1,463 @@
+use crate::collect::{
+    constants::{
+        CORE_DUMPS_TIMEOUT, DATA_PLANE_CONTAINER_NAME, MAYASTOR_SERVICE, NODE_DIAGNOSTICS_TIMEOUT,
+    },
+    k8s_resources::{
+        client::{ClientSet, K8sResourceError},
+        k8s_resource_dump::K8sResourceDumperError,
+    },
+    logs::create_directory_if_not_exist,
+};
+use chrono::{DateTime, Utc};
+use k8s_openapi::api::core::v1::Pod;
+use kube::api::{Api, AttachParams, DeleteParams, PostParams};
+use serde::Serialize;
+use serde_json::json;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+use tokio::io::AsyncReadExt;
+use tracing::{info, warn};
+
+/// Prefix of the names of the core dump collector pods, the rest of the name is generated.
+const COLLECTOR_POD_PREFIX: &str = "mayastor-core-dumps-";
+
+/// Root of the host's filesystem, as seen from a pod sharing the host's process namespace.
+const HOST_ROOT: &str = "/proc/1/root";
+
+/// Name of the io-engine process, as reported by its `comm`.
+const IO_ENGINE_PROCESS: &str = "io-engine";
+
+/// Number of bytes read from the start of the io-engine binary to find its build-id, whose note
+/// is located right after the program headers.
+const ELF_HEADER_BYTES: usize = 64 * 1024;
+
+/// Interval at which the startup of the collector pods is polled.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Name of the file describing the collected core dumps and the io-engine binary.
+const CORES_FILE: &str = "cores.json";
+
+/// Which core dumps are collected from the io-engine nodes.
+#[derive(Debug, Clone)]
+pub(crate) struct CoreDumpConfig {
+    /// Directories of the hosts holding the core dumps and the crash directories.
+    pub(crate) paths: Vec<String>,
+    /// Maximum number of core dumps collected per node, the most recent ones are kept.
+    pub(crate) max_count: usize,
+    /// Maximum total size of the core dumps collected per node, in bytes.
+    pub(crate) max_size: u64,
+    /// Image of the pods collecting the core dumps, which needs a shell, find, stat and head.
+    pub(crate) image: String,
+}
+
+/// Core dump file found on a host.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+struct CoreFile {
+    /// Path of the file on the host.
+    path: String,
+    size: u64,
+    /// Time of the last modification of the file, in RFC 3339 format.
+    modified: String,
+    /// Why the file was not collected.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    skipped: Option<String>,
+    #[serde(skip)]
+    mtime: i64,
+}
+
+/// The io-engine running on a node, to match the core dumps with the binary which produced them.
+#[derive(Serialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+struct IoEngineBinary {
+    pod: String,
+    image: Option<String>,
+    pid: Option<u32>,
+    /// GNU build-id of the binary, in hex.
+    build_id: Option<String>,
+}
+
+/// Summary of the core dumps of a node, written into `cores.json`.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct CoresSummary {
+    io_engine: IoEngineBinary,
+    core_files: Vec<CoreFile>,
+}
+
+/// Collect the most recent core dumps of every node running the io-engine, along with the
+/// build-id of its io-engine binary, by running a privileged pod on each of them which reads the
+/// host's filesystem. The core dumps are written into `nodes/<name>/cores/` of the given root
+/// directory.
+pub(crate) async fn dump_core_dumps(
+    k8s_client: &ClientSet,
+    root_path: &str,
+    config: &CoreDumpConfig,
+) -> Result<(), K8sResourceDumperError> {
+    let nodes = k8s_client
+        .get_pods(&format!("app={MAYASTOR_SERVICE}"), "")
+        .await?
+        .into_iter()
+        .filter_map(|pod| {
+            let node = pod.spec.as_ref()?.node_name.clone()?;
+            Some((node, pod))
+        })
+        .collect::<BTreeMap<_, _>>();
+
+    let results = futures::future::join_all(
+        nodes
+            .iter()
+            .map(|(node, pod)| dump_node(k8s_client, node, pod, Path::new(root_path), config)),
+    )
+    .await;
+
+    let mut errors = Vec::new();
+    for (node, result) in nodes.keys().zip(results) {
+        match result {
+            Ok(count) => info!("\t Collected {count} core dumps of node: {node}"),
+            Err(error) => {
+                warn!("\t Failed to collect the core dumps of node: {node}, error: {error:?}");
+                errors.push(error);
+            }
+        }
+    }
+    if !errors.is_empty() {
+        return Err(K8sResourceDumperError::MultipleErrors(errors));
+    }
+    Ok(())
+}
+
+/// Run the collector pod on the node and copy the core dumps out of it, the pod is always
+/// deleted. Returns the number of collected core dumps.
+async fn dump_node(
+    k8s_client: &ClientSet,
+    node: &str,
+    io_engine_pod: &Pod,
+    root_dir: &Path,
+    config: &CoreDumpConfig,
+) -> Result<usize, K8sResourceDumperError> {
+    let pods_api = k8s_client.get_pod_api().await;
+    let pod: Pod = serde_json::from_value(collector_pod(node, &config.image))?;
+    let pod = pods_api
+        .create(&PostParams::default(), &pod)
+        .await
+        .map_err(K8sResourceError::from)?;
+    let name = pod.metadata.name.unwrap_or_default();
+
+    let mut cores_dir = root_dir.join("nodes");
+    cores_dir.push(node);
+    cores_dir.push("cores");
+    let result = match wait_for_running(&pods_api, &name).await {
+        Ok(()) => collect_cores(&pods_api, &name, io_engine_pod, cores_dir, config).await,
+        Err(error) => Err(error),
+    };
+    if let Err(error) = pods_api.delete(&name, &DeleteParams::default()).await {
+        warn!("\t Failed to delete the core dumps pod: {name}, error: {error:?}");
+    }
+    result
+}
+
+/// Wait until the collector pod is running, so that commands can be executed in it.
+async fn wait_for_running(pods_api: &Api<Pod>, name: &str) -> Result<(), K8sResourceDumperError> {
+    let start = Instant::now();
+    loop {
+        let pod = pods_api.get(name).await.map_err(K8sResourceError::from)?;
+        match pod.status.and_then(|status| status.phase).as_deref() {
+            Some("Running") => return Ok(()),
+            Some(phase @ ("Succeeded" | "Failed")) => {
+                return Err(K8sResourceError::CustomError(format!(
+                    "Core dumps pod {name} is {phase} instead of running"
+                ))
+                .into())
+            }
+            _ if start.elapsed() > NODE_DIAGNOSTICS_TIMEOUT => {
+                return Err(K8sResourceError::CustomError(format!(
+                    "Core dumps pod {name} did not start within {}",
+                    humantime::format_duration(NODE_DIAGNOSTICS_TIMEOUT)
+                ))
+                .into())
+            }
+            _ => tokio::time::sleep(POLL_INTERVAL).await,
+        }
+    }
+}
+
+/// Copy the most recent core dumps of the host, within the limits, and write their summary.
+async fn collect_cores(
+    pods_api: &Api<Pod>,
+    name: &str,
+    io_engine_pod: &Pod,
+    cores_dir: PathBuf,
+    config: &CoreDumpConfig,
+) -> Result<usize, K8sResourceDumperError> {
+    create_directory_if_not_exist(cores_dir.clone())?;
+
+    let mut core_files = list_core_files(pods_api, name, &config.paths).await?;
+    core_files.sort_by(|a, b| b.mtime.cmp(&a.mtime));
+
+    let mut collected = 0;
+    let mut remaining_size = config.max_size;
+    for core_file in core_files.iter_mut() {
+        if collected >= config.max_count {
+            core_file.skipped = Some("older than the collected core dumps".to_string());
+            continue;
+        }
+        if core_file.size > remaining_size {
+            core_file.skipped = Some("exceeds the size limit".to_string());
+            continue;
+        }
+        let target = cores_dir.join(core_file_name(&core_file.path));
+        match copy_file(pods_api, name, &core_file.path, &target).await {
+            Ok(()) => {
+                collected += 1;
+                remaining_size -= core_file.size;
+            }
+            Err(error) => {
+                let _ = std::fs::remove_file(&target);
+                core_file.skipped = Some(format!("failed to copy: {error:?}"));
+            }
+        }
+    }
+
+    let summary = CoresSummary {
+        io_engine: io_engine_binary(pods_api, name, io_engine_pod).await,
+        core_files,
+    };
+    let file = std::fs::File::create(cores_dir.join(CORES_FILE))?;
+    serde_json::to_writer_pretty(file, &summary)?;
+    Ok(collected)
+}
+
+/// List the files of the core dump directories of the host, along with their size and time of
+/// modification. The crash directories are looked into one level deep.
+async fn list_core_files(
+    pods_api: &Api<Pod>,
+    name: &str,
+    paths: &[String],
+) -> Result<Vec<CoreFile>, K8sResourceDumperError> {
+    let mut command = vec!["find".to_string()];
+    command.extend(paths.iter().map(|path| host_path(path)));
+    command.extend(
+        [
+            "-maxdepth",
+            "2",
+            "-type",
+            "f",
+            "-exec",
+            "stat",
+            "-c",
+            "%Y|%s|%n",
+            "{}",
+            ";",
+        ]
+        .iter()
+        .map(ToString::to_string),
+    );
+    // find fails when some of the paths don't exist on the host, which is expected.
+    let output = String::from_utf8_lossy(&exec_output(pods_api, name, command).await?).to_string();
+    Ok(output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '|');
+            let mtime = fields.next()?.parse::<i64>().ok()?;
+            let size = fields.next()?.parse::<u64>().ok()?;
+            let path = fields.next()?.strip_prefix(HOST_ROOT)?.to_string();
+            Some(CoreFile {
+                path,
+                size,
+                modified: DateTime::<Utc>::from_timestamp(mtime, 0)
+                    .map(|time| time.to_rfc3339())
+                    .unwrap_or_default(),
+                skipped: None,
+                mtime,
+            })
+        })
+        .collect())
+}
+
+/// Stream the content of the host file into the target file.
+async fn copy_file(
+    pods_api: &Api<Pod>,
+    name: &str,
+    path: &str,
+    target: &Path,
+) -> Result<(), K8sResourceDumperError> {
+    let mut process = pods_api
+        .exec(
+            name,
+            vec!["cat".to_string(), host_path(path)],
+            &exec_params(),
+        )
+        .await
+        .map_err(K8sResourceError::from)?;
+    let mut stdout = process.stdout().ok_or_else(|| {
+        K8sResourceError::CustomError(format!("No output of the core dumps pod {name}"))
+    })?;
+    let mut file = tokio::fs::File::create(target).await?;
+    tokio::io::copy(&mut stdout, &mut file).await?;
+    drop(stdout);
+    process
+        .join()
+        .await
+        .map_err(|error| K8sResourceError::CustomError(error.to_string()))?;
+    Ok(())
+}
+
+/// Find the io-engine process of the host and read the build-id of its binary. The collector
+/// shares the host's process namespace, hence the binary of the io-engine container can be
+/// read through the process.
+async fn io_engine_binary(pods_api: &Api<Pod>, name: &str, io_engine_pod: &Pod) -> IoEngineBinary {
+    let mut binary = IoEngineBinary {
+        pod: io_engine_pod.metadata.name.clone().unwrap_or_default(),
+        image: io_engine_pod.spec.as_ref().and_then(|spec| {
+            spec.containers
+                .iter()
+                .find(|container| container.name == DATA_PLANE_CONTAINER_NAME)
+                .and_then(|container| container.image.clone())
+        }),
+        ..Default::default()
+    };
+
+    let find_pid = format!(
+        "for p in /proc/[0-9]*; do \
+            [ \"$(cat $p/comm 2>/dev/null)\" = {IO_ENGINE_PROCESS} ] && echo ${{p#/proc/}} && break; \
+        done"
+    );
+    let command = vec!["sh".to_string(), "-c".to_string(), find_pid];
+    binary.pid = match exec_output(pods_api, name, command).await {
+        Ok(output) => String::from_utf8_lossy(&output).trim().parse().ok(),
+        Err(error) => {
+            warn!(
+                "\t Failed to find the io-engine process of pod: {}, error: {error:?}",
+                binary.pod
+            );
+            None
+        }
+    };
+    let Some(pid) = binary.pid else {
+        return binary;
+    };
+
+    let command = vec![
+        "head".to_string(),
+        "-c".to_string(),
+        ELF_HEADER_BYTES.to_string(),
+        format!("/proc/{pid}/exe"),
+    ];
+    match exec_output(pods_api, name, command).await {
+        Ok(elf) => binary.build_id = gnu_build_id(&elf),
+        Err(error) => warn!(
+            "\t Failed to read the io-engine binary of pod: {}, error: {error:?}",
+            binary.pod
+        ),
+    }
+    binary
+}
+
+/// Run the command in the collector pod and return its standard output.
+async fn exec_output(
+    pods_api: &Api<Pod>,
+    name: &str,
+    command: Vec<String>,
+) -> Result<Vec<u8>, K8sResourceDumperError> {
+    let mut process = pods_api
+        .exec(name, command, &exec_params())
+        .await
+        .map_err(K8sResourceError::from)?;
+    let mut output = Vec::new();
+    if let Some(mut stdout) = process.stdout() {
+        stdout.read_to_end(&mut output).await?;
+    }
+    process
+        .join()
+        .await
+        .map_err(|error| K8sResourceError::CustomError(error.to_string()))?;
+    Ok(output)
+}
+
+/// Only the standard output of the commands is streamed back.
+fn exec_params() -> AttachParams {
+    AttachParams::default()
+        .stdin(false)
+        .stdout(true)
+        .stderr(false)
+}
+
+/// Path of the host file, as seen from the collector pod.
+fn host_path(path: &str) -> String {
+    format!("{HOST_ROOT}/{}", path.trim_start_matches('/'))
+}
+
+/// Name of the collected core dump file, flattened from its host path so that the files of the
+/// crash directories don't collide, eg: `var_crash_202310011200_vmcore`.
+fn core_file_name(path: &str) -> String {
+    path.trim_start_matches('/').replace('/', "_")
+}
+
+/// Parse the GNU build-id note of a 64-bit little-endian ELF file, from its start.
+fn gnu_build_id(elf: &[u8]) -> Option<String> {
+    const PT_NOTE: usize = 4;
+    const NT_GNU_BUILD_ID: usize = 3;
+    let read = |offset: usize, len: usize| -> Option<usize> {
+        let bytes = elf.get(offset..offset.checked_add(len)?)?;
+        let mut value = [0u8; 8];
+        value[..len].copy_from_slice(bytes);
+        usize::try_from(u64::from_le_bytes(value)).ok()
+    };
+    let align = |value: usize| value.checked_add(3).map(|value| value & !3);
+
+    // 64-bit class and little-endian data encoding.
+    if elf.get(..6)? != b"\x7fELF\x02\x01" {
+        return None;
+    }
+    let (phoff, phentsize, phnum) = (read(0x20, 8)?, read(0x36, 2)?, read(0x38, 2)?);
+    for index in 0..phnum {
+        let header = phoff.checked_add(index.checked_mul(phentsize)?)?;
+        if read(header, 4)? != PT_NOTE {
+            continue;
+        }
+        let (mut note, size) = (read(header + 0x08, 8)?, read(header + 0x20, 8)?);
+        let end = note.checked_add(size)?;
+        while note.checked_add(12)? <= end {
+            let (name_size, desc_size, kind) =
+                (read(note, 4)?, read(note + 4, 4)?, read(note + 8, 4)?);
+            let name = note + 12;
+            let desc = name.checked_add(align(name_size)?)?;
+            if kind == NT_GNU_BUILD_ID && elf.get(name..name.checked_add(name_size)?)? == b"GNU\0" {
+                let id = elf.get(desc..desc.checked_add(desc_size)?)?;
+                return Some(id.iter().map(|byte| format!("{byte:02x}")).collect());
+            }
+            note = desc.checked_add(align(desc_size)?)?;
+        }
+    }
+    None
+}
+
+/// Privileged pod pinned to the node, which shares the host's process namespace so that the
+/// host's filesystem and the io-engine binary can be read. It exits on its own should it not be
+/// deleted.
+fn collector_pod(node: &str, image: &str) -> serde_json::Value {
+    json!({
+        "apiVersion": "v1",
+        "kind": "Pod",
+        "metadata": {
+            "generateName": COLLECTOR_POD_PREFIX,
+            "labels": { "app": "mayastor-core-dumps" },
+        },
+        "spec": {
+            "nodeName": node,
+            "hostPID": true,
+            "restartPolicy": "Never",
+            "tolerations": [{ "operator": "Exists" }],
+            "containers": [{
+                "name": "core-dumps",
+                "image": image,
+                "command": ["sleep", CORE_DUMPS_TIMEOUT.as_secs().to_string()],
+                "securityContext": { "privileged": true },
+            }],
+        },
+    })
+}
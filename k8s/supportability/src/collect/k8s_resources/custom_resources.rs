@@ -0,0 +1,134 @@
+use crate::collect::{
+    k8s_resources::{
+        client::{ClientSet, K8sResourceError},
+        k8s_resource_dump::K8sResourceDumperError,
+    },
+    logs::create_directory_if_not_exist,
+    retry::with_retries,
+    utils::write_yaml_file,
+};
+use k8s_openapi::apiextensions_apiserver::pkg::apis::apiextensions::v1::CustomResourceDefinition;
+use kube::{
+    api::{Api, DynamicObject, ListParams},
+    core::{ApiResource, GroupVersionKind, TypeMeta},
+};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Group of the CustomResourceDefinitions of the product, their sub-groups are included too.
+const PRODUCT_CRD_GROUP: &str = "openebs.io";
+
+/// Directory of the custom resources, in the kubernetes resources directory.
+const CUSTOM_RESOURCES_DIR: &str = "crs";
+
+/// Directory of the CustomResourceDefinitions, in the custom resources directory.
+const DEFINITIONS_DIR: &str = "definitions";
+
+/// Dump the CustomResourceDefinitions of the product, and all their custom resources with their
+/// spec and status, into `crs/` of the given kubernetes resources directory. The managed fields
+/// are stripped, as they only bloat the dumps.
+pub(crate) async fn dump_custom_resources(
+    k8s_client: &ClientSet,
+    root_dir: &Path,
+) -> Result<(), K8sResourceDumperError> {
+    let crds_api: Api<CustomResourceDefinition> = Api::all(k8s_client.kube_client());
+    let crds = with_retries(|| crds_api.list(&ListParams::default()))
+        .await
+        .map_err(K8sResourceError::from)?
+        .items
+        .into_iter()
+        .filter(|crd| is_product_group(&crd.spec.group))
+        .collect::<Vec<_>>();
+
+    let crs_dir = root_dir.join(CUSTOM_RESOURCES_DIR);
+    let definitions_dir = crs_dir.join(DEFINITIONS_DIR);
+    create_directory_if_not_exist(definitions_dir.clone())?;
+
+    let mut errors = Vec::new();
+    for mut crd in crds {
+        let crd_name = crd.metadata.name.clone().unwrap_or_default();
+        crd.metadata.managed_fields = None;
+        let file_path = definitions_dir.join(format!("{crd_name}.yaml"));
+        if let Err(error) = write_yaml(file_path, &crd) {
+            errors.push(error);
+        }
+        match dump_crd_resources(k8s_client, &crd, crs_dir.join(&crd_name)).await {
+            Ok(count) => info!("\t Collected {count} custom resource(s) of {crd_name}"),
+            Err(error) => {
+                warn!("\t Failed to collect the custom resources of {crd_name}, error: {error:?}");
+                errors.push(error);
+            }
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(K8sResourceDumperError::MultipleErrors(errors));
+    }
+    Ok(())
+}
+
+/// Whether the group is the product's one, or one of its sub-groups.
+fn is_product_group(group: &str) -> bool {
+    group == PRODUCT_CRD_GROUP || group.ends_with(&format!(".{PRODUCT_CRD_GROUP}"))
+}
+
+/// Dump all the custom resources of the CustomResourceDefinition, read through its storage
+/// version, into the given directory, one file per resource. Returns the number of resources.
+async fn dump_crd_resources(
+    k8s_client: &ClientSet,
+    crd: &CustomResourceDefinition,
+    dir_path: PathBuf,
+) -> Result<usize, K8sResourceDumperError> {
+    let version = crd
+        .spec
+        .versions
+        .iter()
+        .find(|version| version.storage)
+        .or_else(|| crd.spec.versions.iter().find(|version| version.served))
+        .ok_or_else(|| {
+            K8sResourceError::invalid_k8s_resource_value(format!(
+                "No served version of {}",
+                crd.metadata.name.as_deref().unwrap_or_default()
+            ))
+        })?;
+    let gvk = GroupVersionKind::gvk(&crd.spec.group, &version.name, &crd.spec.names.kind);
+    let api_resource = ApiResource::from_gvk_with_plural(&gvk, &crd.spec.names.plural);
+    let api: Api<DynamicObject> = Api::all_with(k8s_client.kube_client(), &api_resource);
+    let resources = with_retries(|| api.list(&ListParams::default()))
+        .await
+        .map_err(K8sResourceError::from)?
+        .items;
+
+    create_directory_if_not_exist(dir_path.clone())?;
+    let mut errors = Vec::new();
+    let count = resources.len();
+    for mut resource in resources {
+        resource.metadata.managed_fields = None;
+        // The items of a list don't carry their type.
+        resource.types = Some(TypeMeta {
+            api_version: api_resource.api_version.clone(),
+            kind: api_resource.kind.clone(),
+        });
+        let name = resource.metadata.name.clone().unwrap_or_default();
+        let file_name = match &resource.metadata.namespace {
+            Some(namespace) => format!("{namespace}_{name}.yaml"),
+            None => format!("{name}.yaml"),
+        };
+        if let Err(error) = write_yaml(dir_path.join(file_name), &resource) {
+            errors.push(error);
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(K8sResourceDumperError::MultipleErrors(errors));
+    }
+    Ok(count)
+}
+
+/// Write the object as yaml into the given file.
+fn write_yaml<T: serde::Serialize>(
+    file_path: PathBuf,
+    object: &T,
+) -> Result<(), K8sResourceDumperError> {
+    write_yaml_file(file_path, object)
+}
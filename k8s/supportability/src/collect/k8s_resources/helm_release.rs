@@ -0,0 +1,202 @@
+use crate::collect::{
+    k8s_resources::{
+        client::{ClientSet, K8sResourceError},
+        k8s_resource_dump::K8sResourceDumperError,
+    },
+    logs::create_directory_if_not_exist,
+};
+use base64::Engine;
+use flate2::read::GzDecoder;
+use k8s_openapi::api::core::v1::Secret;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::BTreeSet, io::Read, path::Path};
+use tracing::{info, warn};
+
+/// Label selector of the secrets in which helm stores its deployed releases.
+const HELM_RELEASE_SELECTOR: &str = "owner=helm,status=deployed";
+
+/// Key of the helm release secrets which holds the encoded release.
+const HELM_RELEASE_KEY: &str = "release";
+
+/// Release as stored by helm, only the fields of interest are decoded.
+#[derive(Deserialize, Debug)]
+struct HelmRelease {
+    name: String,
+    namespace: String,
+    version: u32,
+    info: HelmReleaseInfo,
+    chart: HelmChart,
+    #[serde(default)]
+    config: Value,
+    #[serde(default)]
+    manifest: String,
+}
+
+/// Information of a helm release.
+#[derive(Deserialize, Debug)]
+struct HelmReleaseInfo {
+    #[serde(default)]
+    first_deployed: String,
+    #[serde(default)]
+    last_deployed: String,
+    #[serde(default)]
+    status: String,
+    #[serde(default)]
+    description: String,
+}
+
+/// Chart of a helm release.
+#[derive(Deserialize, Debug)]
+struct HelmChart {
+    metadata: HelmChartMetadata,
+    #[serde(default)]
+    values: Value,
+}
+
+/// Metadata of a helm chart.
+#[derive(Deserialize, Debug)]
+struct HelmChartMetadata {
+    name: String,
+    version: String,
+    #[serde(default, rename = "appVersion")]
+    app_version: String,
+}
+
+/// Summary of a helm release, written along with its values and manifest.
+#[derive(Serialize, Debug)]
+struct ReleaseSummary<'a> {
+    name: &'a str,
+    namespace: &'a str,
+    revision: u32,
+    status: &'a str,
+    chart: &'a str,
+    chart_version: &'a str,
+    app_version: &'a str,
+    first_deployed: &'a str,
+    last_deployed: &'a str,
+    description: &'a str,
+}
+
+/// Dump the deployed helm releases of the namespace into `helm/<release>/` of the given root
+/// directory: a summary with the chart version, the user supplied and the computed values, and
+/// the rendered manifest. The releases are read from the secrets helm stores them in, so that
+/// neither the helm binary nor access to the chart repository is needed.
+pub(crate) async fn dump_helm_releases(
+    k8s_client: &ClientSet,
+    root_path: &Path,
+) -> Result<(), K8sResourceDumperError> {
+    info!("\t Collecting helm release information");
+    let secrets = k8s_client.get_secrets(HELM_RELEASE_SELECTOR, "").await?;
+    if secrets.is_empty() {
+        info!("\t No deployed helm release found");
+        return Ok(());
+    }
+
+    let mut errors = Vec::new();
+    for secret in secrets {
+        let name = secret.metadata.name.clone().unwrap_or_default();
+        if let Err(error) = dump_helm_release(&secret, root_path) {
+            warn!("Error collecting the helm release of secret : {name} , error: {error:?}");
+            errors.push(error);
+        }
+    }
+    if !errors.is_empty() {
+        return Err(K8sResourceDumperError::MultipleErrors(errors));
+    }
+    Ok(())
+}
+
+/// The namespaces of the deployed helm releases of the given chart, across all the namespaces.
+pub(crate) async fn chart_release_namespaces(
+    k8s_client: &ClientSet,
+    chart: &str,
+) -> Result<BTreeSet<String>, K8sResourceDumperError> {
+    let secrets = k8s_client
+        .get_all_namespaces_secrets(HELM_RELEASE_SELECTOR)
+        .await?;
+    Ok(secrets
+        .iter()
+        .filter_map(|secret| decode_release(secret).ok())
+        .filter(|release| release.chart.metadata.name == chart)
+        .map(|release| release.namespace)
+        .collect())
+}
+
+/// Decode the release of the helm secret and write its information.
+fn dump_helm_release(secret: &Secret, root_path: &Path) -> Result<(), K8sResourceDumperError> {
+    let release = decode_release(secret)?;
+    let mut release_dir = root_path.join("helm");
+    release_dir.push(&release.name);
+    create_directory_if_not_exist(release_dir.clone())?;
+
+    let summary = ReleaseSummary {
+        name: &release.name,
+        namespace: &release.namespace,
+        revision: release.version,
+        status: &release.info.status,
+        chart: &release.chart.metadata.name,
+        chart_version: &release.chart.metadata.version,
+        app_version: &release.chart.metadata.app_version,
+        first_deployed: &release.info.first_deployed,
+        last_deployed: &release.info.last_deployed,
+        description: &release.info.description,
+    };
+    std::fs::write(
+        release_dir.join("release.yaml"),
+        serde_yaml::to_string(&summary)?,
+    )?;
+    std::fs::write(
+        release_dir.join("user_values.yaml"),
+        serde_yaml::to_string(&release.config)?,
+    )?;
+
+    let mut values = release.chart.values;
+    coalesce_values(&mut values, release.config);
+    std::fs::write(
+        release_dir.join("values.yaml"),
+        serde_yaml::to_string(&values)?,
+    )?;
+    std::fs::write(release_dir.join("manifest.yaml"), release.manifest)?;
+    Ok(())
+}
+
+/// Decode the release of the helm secret, which is a gzipped JSON document encoded in base64
+/// on top of the base64 encoding of the secret data.
+fn decode_release(secret: &Secret) -> Result<HelmRelease, K8sResourceDumperError> {
+    let invalid = |reason: String| {
+        K8sResourceError::CustomError(format!(
+            "Invalid helm release secret {}: {reason}",
+            secret.metadata.name.as_deref().unwrap_or_default()
+        ))
+    };
+    let data = secret
+        .data
+        .as_ref()
+        .and_then(|data| data.get(HELM_RELEASE_KEY))
+        .ok_or_else(|| invalid(format!("no '{HELM_RELEASE_KEY}' key")))?;
+    let compressed = base64::engine::general_purpose::STANDARD
+        .decode(&data.0)
+        .map_err(|error| invalid(error.to_string()))?;
+    let mut json = Vec::new();
+    GzDecoder::new(compressed.as_slice()).read_to_end(&mut json)?;
+    Ok(serde_json::from_slice(&json)?)
+}
+
+/// Merge the user supplied values into the default values of the chart, as helm computes the
+/// values of a release: the maps are merged recursively, other values are replaced and a null
+/// value removes the default.
+fn coalesce_values(values: &mut Value, overrides: Value) {
+    match (values, overrides) {
+        (Value::Object(values), Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                if value.is_null() {
+                    values.remove(&key);
+                } else {
+                    coalesce_values(values.entry(key).or_insert(Value::Null), value);
+                }
+            }
+        }
+        (values, overrides) => *values = overrides,
+    }
+}
@@ -0,0 +1,184 @@
+use crate::collect::{
+    constants::{IO_ENGINE_GRPC_PORT, IO_ENGINE_STATE_TIMEOUT, MAYASTOR_SERVICE},
+    k8s_resources::{
+        client::{ClientSet, K8sResourceError},
+        k8s_resource_dump::K8sResourceDumperError,
+    },
+    logs::create_directory_if_not_exist,
+    utils::write_json_file,
+};
+use io_engine_client::{ApiVersion, GrpcClient};
+use k8s_openapi::api::core::v1::Pod;
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+use tonic::transport::Endpoint;
+use tracing::{info, warn};
+
+/// Collect the state of the data-plane straight from the gRPC api of the io-engine of every node,
+/// i.e. its pools, replicas, nexuses and NVMe controllers, so that the ground truth is known even
+/// when the control-plane is down. The io-engines are reached through a port-forward of their
+/// pod, and the state of each node is written as JSON into `nodes/<name>/io_engine/` of the given
+/// root directory.
+pub(crate) async fn dump_io_engine_state(
+    k8s_client: &ClientSet,
+    root_path: &str,
+) -> Result<(), K8sResourceDumperError> {
+    let pods = k8s_client
+        .get_pods(&format!("app={MAYASTOR_SERVICE}"), "")
+        .await?
+        .into_iter()
+        .filter(is_running)
+        .filter_map(|pod| {
+            let node = pod.spec.as_ref()?.node_name.clone()?;
+            Some((node, pod.metadata.name?))
+        })
+        .collect::<BTreeMap<_, _>>();
+
+    let results = futures::future::join_all(
+        pods.iter()
+            .map(|(node, pod)| dump_node(k8s_client, node, pod, Path::new(root_path))),
+    )
+    .await;
+
+    let mut errors = Vec::new();
+    for (node, result) in pods.keys().zip(results) {
+        match result {
+            Ok(()) => info!("\t Collected io-engine state of node: {node}"),
+            Err(error) => {
+                warn!("\t Failed to collect io-engine state of node: {node}, error: {error:?}");
+                errors.push(error);
+            }
+        }
+    }
+    if !errors.is_empty() {
+        return Err(K8sResourceDumperError::MultipleErrors(errors));
+    }
+    Ok(())
+}
+
+/// Whether the pod is running, the io-engine of the other pods can't be reached.
+fn is_running(pod: &Pod) -> bool {
+    pod.status
+        .as_ref()
+        .and_then(|status| status.phase.as_deref())
+        == Some("Running")
+}
+
+/// Dial the io-engine of the node through a port-forward of its pod and write its state. Every
+/// call is attempted, a failed one doesn't prevent the others from being written.
+async fn dump_node(
+    k8s_client: &ClientSet,
+    node: &str,
+    pod: &str,
+    root_dir: &Path,
+) -> Result<(), K8sResourceDumperError> {
+    let mut forwarder = k8s_client
+        .get_pod_api()
+        .await
+        .portforward(pod, &[IO_ENGINE_GRPC_PORT])
+        .await
+        .map_err(K8sResourceError::from)?;
+    let stream = forwarder.take_stream(IO_ENGINE_GRPC_PORT).ok_or_else(|| {
+        K8sResourceError::CustomError(format!(
+            "No port-forward stream to port {IO_ENGINE_GRPC_PORT} of pod {pod}"
+        ))
+    })?;
+
+    // The port-forward carries a single connection, which the channel is given on connect.
+    let stream = Arc::new(Mutex::new(Some(stream)));
+    let channel = Endpoint::from_static("http://io-engine")
+        .timeout(IO_ENGINE_STATE_TIMEOUT)
+        .connect_with_connector(tower::service_fn(move |_| {
+            let stream = stream.lock().ok().and_then(|mut stream| stream.take());
+            async move {
+                stream.ok_or_else(|| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::NotConnected,
+                        "the port-forward stream is already in use",
+                    )
+                })
+            }
+        }));
+    let channel = tokio::time::timeout(IO_ENGINE_STATE_TIMEOUT, channel)
+        .await
+        .map_err(|_| grpc_error(pod, "connect", "timed out"))?
+        .map_err(|error| grpc_error(pod, "connect", error))?;
+    let client = GrpcClient::from_channel(node.to_string(), ApiVersion::V1, channel);
+    let mut client_v1 = client
+        .client_v1()
+        .map_err(|error| grpc_error(pod, "connect", error))?;
+
+    let mut io_engine_dir = root_dir.join("nodes");
+    io_engine_dir.push(node);
+    io_engine_dir.push("io_engine");
+    create_directory_if_not_exist(io_engine_dir.clone())?;
+
+    let results = vec![
+        write_response(
+            io_engine_dir.join("pools.json"),
+            pod,
+            "list_pools",
+            client_v1
+                .pool
+                .list_pools(rpc::v1::pool::ListPoolOptions::default())
+                .await,
+        ),
+        write_response(
+            io_engine_dir.join("replicas.json"),
+            pod,
+            "list_replicas",
+            client_v1
+                .replica
+                .list_replicas(rpc::v1::replica::ListReplicaOptions::default())
+                .await,
+        ),
+        write_response(
+            io_engine_dir.join("nexuses.json"),
+            pod,
+            "list_nexus",
+            client_v1
+                .nexus
+                .list_nexus(rpc::v1::nexus::ListNexusOptions::default())
+                .await,
+        ),
+        write_response(
+            io_engine_dir.join("nvme_controllers.json"),
+            pod,
+            "list_nvme_controllers",
+            client_v1.host.list_nvme_controllers(()).await,
+        ),
+    ];
+    forwarder.abort();
+
+    let errors = results
+        .into_iter()
+        .filter_map(Result::err)
+        .collect::<Vec<_>>();
+    if !errors.is_empty() {
+        return Err(K8sResourceDumperError::MultipleErrors(errors));
+    }
+    Ok(())
+}
+
+/// Write the response of a gRPC call to the io-engine of the pod into the file, as JSON.
+fn write_response<T: Serialize>(
+    path: PathBuf,
+    pod: &str,
+    call: &str,
+    result: Result<tonic::Response<T>, tonic::Status>,
+) -> Result<(), K8sResourceDumperError> {
+    let response = result.map_err(|status| grpc_error(pod, call, status))?;
+    write_json_file::<K8sResourceDumperError>(path, &response.into_inner())
+}
+
+/// Error of a gRPC call to the io-engine of the pod.
+fn grpc_error(pod: &str, call: &str, error: impl std::fmt::Display) -> K8sResourceDumperError {
+    K8sResourceError::CustomError(format!(
+        "gRPC call {call} to the io-engine of pod {pod} failed: {error}"
+    ))
+    .into()
+}
@@ -1,24 +1,36 @@
-use crate::{
-    collect::{
-        k8s_resources::client::{ClientSet, K8sResourceError},
-        logs::create_directory_if_not_exist,
+use crate::collect::{
+    constants::MAYASTOR_SERVICE,
+    k8s_resources::{
+        client::{ClientSet, K8sResourceError},
+        core_dumps::{self, CoreDumpConfig},
+        custom_resources::dump_custom_resources,
+        helm_release::dump_helm_releases,
+        io_engine_state,
+        kustomize::{write_snapshot, SnapshotFile, KUSTOMIZE_SNAPSHOT_DIR},
+        node_diagnostics, observability_state,
+        packet_capture::{self, PacketCaptureConfig},
     },
-    log,
+    logs::create_directory_if_not_exist,
+    utils::{create_buffered_file, since_cutoff, write_json_file, write_yaml_file},
 };
 use k8s_openapi::{
-    api::{apps::v1, core::v1::Event},
+    api::{
+        apps::v1,
+        core::v1::{Event, Pod},
+    },
     apimachinery::pkg::apis::meta::v1::MicroTime,
 };
 use k8s_operators::diskpool::crd::DiskPool;
 use kube::Resource;
 use serde::Serialize;
 use std::{
-    collections::HashSet,
-    fs::File,
+    collections::{BTreeSet, HashSet},
     io::Write,
     iter::FromIterator,
     path::{Path, PathBuf},
+    time::Duration,
 };
+use tracing::{info, warn};
 
 const MAYASTOR_CSI_DRIVER: &str = "io.openebs.csi-mayastor";
 
@@ -26,6 +38,8 @@ const MAYASTOR_CSI_DRIVER: &str = "io.openebs.csi-mayastor";
 #[derive(Clone)]
 pub(crate) struct K8sResourceDumperClient {
     k8s_client: ClientSet,
+    /// Period from which events and logs needs to collect
+    since: Duration,
 }
 
 /// Errors pertaining to k8s resource dumper module
@@ -98,24 +112,27 @@ impl EntityName for StatefulSet {
 }
 
 impl K8sResourceDumperClient {
-    /// get a new k8s resource dumper client
+    /// get a new k8s resource dumper client, which collects the events and logs from the last
+    /// `since` duration
     pub(crate) async fn new(
         kube_config_path: Option<std::path::PathBuf>,
         namespace: String,
+        since: Duration,
     ) -> Result<Self, K8sResourceDumperError> {
         let k8s_client = ClientSet::new(kube_config_path, namespace).await?;
-        Ok(Self { k8s_client })
+        Ok(Self { k8s_client, since })
     }
 
-    /// dump the kubernetes resources like deployments, daemonsets,
-    /// pods, statefulsets, events, disk pools in the given root path
+    /// dump the kubernetes resources like deployments, daemonsets, pods, statefulsets, events,
+    /// disk pools, the product custom resources and helm releases in the given root path
     pub(crate) async fn dump_k8s_resources(
         &self,
         root_path: String,
         required_pools: Option<Vec<String>>,
     ) -> Result<(), K8sResourceDumperError> {
         // Create the root dir path
-        let mut root_dir = PathBuf::from(root_path);
+        let root_path = PathBuf::from(root_path);
+        let mut root_dir = root_path.clone();
         root_dir.push("k8s_resources");
         create_directory_if_not_exist(root_dir.to_path_buf())?;
 
@@ -127,8 +144,8 @@ impl K8sResourceDumperClient {
 
         let mut errors = Vec::new();
 
-        // Fetch all events in provided NAMESPACE
-        if let Err(error) = get_k8s_events(&self.k8s_client, &root_dir).await {
+        // Fetch all events in provided NAMESPACE and of the io-engine nodes
+        if let Err(error) = get_k8s_events(&self.k8s_client, &root_dir, self.since).await {
             errors.push(error)
         }
 
@@ -148,7 +165,25 @@ impl K8sResourceDumperClient {
         }
 
         // Fetch all DiskPools in provided NAMESPACE
-        if let Err(error) = get_k8s_diskpools(&self.k8s_client, &root_dir, required_pools).await {
+        let pools = match get_k8s_diskpools(&self.k8s_client, &root_dir, required_pools).await {
+            Ok(pools) => Some(pools),
+            Err(error) => {
+                errors.push(error);
+                None
+            }
+        };
+
+        // Create a re-applyable snapshot of the DiskPools and mayastor StorageClasses
+        if let Some(pools) = pools {
+            if let Err(error) =
+                get_k8s_kustomize_snapshot(&self.k8s_client, &root_dir, &pools).await
+            {
+                errors.push(error)
+            }
+        }
+
+        // Fetch the product CRDs and all their custom resources, with their status
+        if let Err(error) = dump_custom_resources(&self.k8s_client, &root_dir).await {
             errors.push(error)
         }
 
@@ -167,24 +202,106 @@ impl K8sResourceDumperClient {
             errors.push(error)
         }
 
+        // Fetch the deployed helm releases in provided NAMESPACE
+        if let Err(error) = dump_helm_releases(&self.k8s_client, &root_path).await {
+            errors.push(error)
+        }
+
         if !errors.is_empty() {
             return Err(K8sResourceDumperError::MultipleErrors(errors));
         }
         Ok(())
     }
-}
 
-/// Creates a file and writes the passed content in it
-fn create_file_and_write(
-    mut file_path: PathBuf,
-    file_name: String,
-    content: String,
-) -> Result<(), std::io::Error> {
-    file_path.push(file_name);
-    let mut file = File::create(file_path)?;
-    file.write_all(content.as_bytes())?;
-    file.flush().unwrap();
-    Ok(())
+    /// dump the describe output, i.e. the pod object and its events, and the logs from the last
+    /// `since` duration of the application pods which mount mayastor volumes
+    pub(crate) async fn dump_app_pods(
+        &self,
+        root_path: String,
+    ) -> Result<(), K8sResourceDumperError> {
+        let mut app_pods_dir = PathBuf::from(root_path);
+        app_pods_dir.push("k8s_resources");
+        app_pods_dir.push("app_pods");
+        create_directory_if_not_exist(app_pods_dir.to_path_buf())?;
+
+        info!("\t Collecting application pods mounting mayastor volumes");
+        let pods = self
+            .k8s_client
+            .get_volume_consumer_pods(MAYASTOR_CSI_DRIVER)
+            .await?;
+
+        let mut errors = Vec::new();
+        for pod in pods {
+            if let Err(error) =
+                dump_app_pod(&self.k8s_client, &pod, &app_pods_dir, self.since).await
+            {
+                warn!(
+                    "Error collecting application pod : {} , error: {:?}",
+                    pod.metadata.name.as_deref().unwrap_or_default(),
+                    error
+                );
+                errors.push(error);
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(K8sResourceDumperError::MultipleErrors(errors));
+        }
+        Ok(())
+    }
+
+    /// dump the storage diagnostics of the io-engine nodes, eg: their block devices, nvme
+    /// controllers and kernel messages, collected by privileged pods of the given image
+    pub(crate) async fn dump_node_diagnostics(
+        &self,
+        root_path: String,
+        image: &str,
+    ) -> Result<(), K8sResourceDumperError> {
+        info!("\t Collecting storage diagnostics of the io-engine nodes");
+        node_diagnostics::dump_node_diagnostics(&self.k8s_client, &root_path, image).await
+    }
+
+    /// dump the state of the data-plane, i.e. the pools, replicas, nexuses and NVMe controllers,
+    /// straight from the gRPC api of the io-engine of every node
+    pub(crate) async fn dump_io_engine_state(
+        &self,
+        root_path: String,
+    ) -> Result<(), K8sResourceDumperError> {
+        info!("\t Collecting the state of the io-engines from their gRPC api");
+        io_engine_state::dump_io_engine_state(&self.k8s_client, &root_path).await
+    }
+
+    /// dump the state of the observability components, i.e. the metrics of the metrics-exporters
+    /// and the metrics and stats of the call-home
+    pub(crate) async fn dump_observability_state(
+        &self,
+        root_path: String,
+    ) -> Result<(), K8sResourceDumperError> {
+        info!("\t Collecting the state of the observability components");
+        observability_state::dump_observability_state(&self.k8s_client, &root_path).await
+    }
+
+    /// dump the most recent core dumps of the io-engine nodes, along with the build-id of their
+    /// io-engine binary, copied out of privileged pods
+    pub(crate) async fn dump_core_dumps(
+        &self,
+        root_path: String,
+        config: &CoreDumpConfig,
+    ) -> Result<(), K8sResourceDumperError> {
+        info!("\t Collecting core dumps of the io-engine nodes");
+        core_dumps::dump_core_dumps(&self.k8s_client, &root_path, config).await
+    }
+
+    /// dump a short packet trace of the NVMe-oF ports of the selected nodes, captured by
+    /// privileged pods
+    pub(crate) async fn dump_packet_captures(
+        &self,
+        root_path: String,
+        config: &PacketCaptureConfig,
+    ) -> Result<(), K8sResourceDumperError> {
+        info!("\t Capturing the NVMe-oF packets of the selected nodes");
+        packet_capture::dump_packet_captures(&self.k8s_client, &root_path, config).await
+    }
 }
 
 /// create the app specific yamls
@@ -193,27 +310,13 @@ fn create_app_configurations<T: EntityName>(
     dir_path: PathBuf,
 ) -> Result<(), K8sResourceDumperError> {
     for app in apps {
-        let serialized = match serde_yaml::to_string(&app) {
-            Ok(value) => value,
-            Err(e) => {
-                log(format!(
-                    "Error serializing the app : {} , error: {}",
-                    app.name(),
-                    e
-                ));
-                continue;
-            }
-        };
-        match create_file_and_write(dir_path.clone(), format!("{}.yaml", app.name()), serialized) {
-            Ok(_) => {}
-            Err(e) => {
-                log(format!(
-                    "Error creating or writing file for the app : {} , error: {}",
-                    app.name(),
-                    e
-                ));
-                continue;
-            }
+        let file_path = dir_path.join(format!("{}.yaml", app.name()));
+        if let Err(e) = write_yaml_file::<K8sResourceDumperError>(file_path, &app) {
+            warn!(
+                "Error writing the file for the app : {} , error: {:?}",
+                app.name(),
+                e
+            );
         }
     }
     Ok(())
@@ -238,12 +341,20 @@ fn event_time(event: &Event) -> MicroTime {
     event.event_time.as_ref().unwrap().clone()
 }
 
+/// Sort the events based on event_time, leaving out the ones older than the `since` duration
+fn recent_events(mut events: Vec<Event>, since: Duration) -> Vec<Event> {
+    let cutoff = since_cutoff(since);
+    events.retain(|event| event_time(event).0 >= cutoff);
+    events.sort_unstable_by_key(event_time);
+    events
+}
+
 async fn get_k8s_daemonsets(
     k8s_client: &ClientSet,
     configurations_path: &Path,
 ) -> Result<(), K8sResourceDumperError> {
     // Fetch all Daemonsets in provided NAMESPACE
-    log("\t Collecting daemonsets configuration".to_string());
+    info!("\t Collecting daemonsets configuration");
     match k8s_client.get_daemonsets("", "").await {
         Ok(daemonsets) => {
             // Create all Daemonsets configurations
@@ -262,7 +373,7 @@ async fn get_k8s_deployments(
     configurations_path: &Path,
 ) -> Result<(), K8sResourceDumperError> {
     // Fetch all Deployments in provided NAMESPACE
-    log("\t Collecting deployments configuration".to_string());
+    info!("\t Collecting deployments configuration");
     match k8s_client.get_deployments("", "").await {
         Ok(deploys) => {
             // Create all Daemonsets configurations
@@ -281,7 +392,7 @@ async fn get_k8s_statefulsets(
     configurations_path: &Path,
 ) -> Result<(), K8sResourceDumperError> {
     // Fetch all StatefulSets in provided NAMESPACE
-    log("\t Collecting statefulsets configuration".to_string());
+    info!("\t Collecting statefulsets configuration");
     match k8s_client.get_statefulsets("", "").await {
         Ok(statefulsets) => {
             // Create all Daemonsets configurations
@@ -299,9 +410,9 @@ async fn get_k8s_diskpools(
     k8s_client: &ClientSet,
     root_dir: &Path,
     required_pools: Option<Vec<String>>,
-) -> Result<(), K8sResourceDumperError> {
+) -> Result<Vec<DiskPool>, K8sResourceDumperError> {
     // Fetch all DiskPools in provided NAMESPACE
-    log("\t Collecting Kubernetes disk pool resources".to_string());
+    info!("\t Collecting Kubernetes disk pool resources");
     match k8s_client.list_pools(None, None).await {
         Ok(disk_pools) => {
             let filtered_pools = match required_pools {
@@ -315,36 +426,47 @@ async fn get_k8s_diskpools(
                 None => disk_pools,
             };
             // NOTE: Unmarshalling object recevied from K8s API-server will not fail
-            create_file_and_write(
-                root_dir.to_path_buf(),
-                "k8s_disk_pools.yaml".to_string(),
-                serde_yaml::to_string(&filtered_pools)?,
-            )
-            .map_err(K8sResourceDumperError::IOError)?;
-            Ok(())
+            write_yaml_file::<K8sResourceDumperError>(
+                root_dir.join("k8s_disk_pools.yaml"),
+                &filtered_pools,
+            )?;
+            Ok(filtered_pools)
         }
         Err(error) => Err(K8sResourceDumperError::K8sResourceError(error)),
     }
 }
 
+async fn get_k8s_kustomize_snapshot(
+    k8s_client: &ClientSet,
+    root_dir: &Path,
+    pools: &[DiskPool],
+) -> Result<(), K8sResourceDumperError> {
+    info!("\t Creating re-applyable snapshot of disk pools and storage classes");
+    let storage_classes = k8s_client.list_storage_classes(MAYASTOR_CSI_DRIVER).await?;
+    let files = [
+        SnapshotFile::new("diskpools.yaml", pools)?,
+        SnapshotFile::new("storageclasses.yaml", &storage_classes)?,
+    ];
+    write_snapshot(
+        &root_dir.join(KUSTOMIZE_SNAPSHOT_DIR),
+        k8s_client.namespace(),
+        &files,
+    )?;
+    Ok(())
+}
+
 async fn get_k8s_vs_classes(
     k8s_client: &ClientSet,
     root_dir: &Path,
 ) -> Result<(), K8sResourceDumperError> {
-    log("\t Collecting Kubernetes VolumeSnapshotClass resources".to_string());
+    info!("\t Collecting Kubernetes VolumeSnapshotClass resources");
     match k8s_client
         .list_volumesnapshot_classes(Some(MAYASTOR_CSI_DRIVER), None, None)
         .await
     {
         Ok(vscs) => {
             // NOTE: Unmarshalling object recevied from K8s API-server will not fail
-            create_file_and_write(
-                root_dir.to_path_buf(),
-                "volume_snapshot_classes.yaml".to_string(),
-                serde_yaml::to_string(&vscs)?,
-            )
-            .map_err(K8sResourceDumperError::IOError)?;
-            Ok(())
+            write_yaml_file(root_dir.join("volume_snapshot_classes.yaml"), &vscs)
         }
         Err(error) => Err(K8sResourceDumperError::K8sResourceError(error)),
     }
@@ -354,20 +476,14 @@ async fn get_k8s_vsnapshot_contents(
     k8s_client: &ClientSet,
     root_dir: &Path,
 ) -> Result<(), K8sResourceDumperError> {
-    log("\t Collecting Kubernetes VolumeSnapshotContents resources".to_string());
+    info!("\t Collecting Kubernetes VolumeSnapshotContents resources");
     match k8s_client
         .list_volumesnapshotcontents(Some(MAYASTOR_CSI_DRIVER), None, None)
         .await
     {
         Ok(vscs) => {
             // NOTE: Unmarshalling object recevied from K8s API-server will not fail
-            create_file_and_write(
-                root_dir.to_path_buf(),
-                "volume_snapshot_contents.yaml".to_string(),
-                serde_yaml::to_string(&vscs)?,
-            )
-            .map_err(K8sResourceDumperError::IOError)?;
-            Ok(())
+            write_yaml_file(root_dir.join("volume_snapshot_contents.yaml"), &vscs)
         }
         Err(error) => Err(K8sResourceDumperError::K8sResourceError(error)),
     }
@@ -378,40 +494,119 @@ async fn get_k8s_pod_configurations(
     root_dir: &Path,
 ) -> Result<(), K8sResourceDumperError> {
     // Fetch all Pods in provided NAMESPACE
-    log("\t Collecting Kuberbetes pod resources".to_string());
+    info!("\t Collecting Kuberbetes pod resources");
     match k8s_client.get_pods("", "").await {
-        Ok(pods) => {
-            create_file_and_write(
-                root_dir.to_path_buf(),
-                "pods.yaml".to_string(),
-                serde_yaml::to_string(&pods)?,
-            )
-            .map_err(K8sResourceDumperError::IOError)?;
-            Ok(())
-        }
+        Ok(pods) => write_yaml_file(root_dir.join("pods.yaml"), &pods),
         Err(error) => Err(K8sResourceDumperError::K8sResourceError(error)),
     }
 }
 
+/// Fetch the events of the provided NAMESPACE and of the nodes hosting the io-engine pods, and
+/// write them sorted by time, both as YAML and as a human readable timeline
 async fn get_k8s_events(
     k8s_client: &ClientSet,
     root_dir: &Path,
+    since: Duration,
 ) -> Result<(), K8sResourceDumperError> {
-    // Fetch all events in provided NAMESPACE
-    log("\t Collecting Kubernetes events".to_string());
-    match k8s_client.get_events("", "").await {
-        Ok(mut events) => {
-            // Sort the events based on event_time
-            events.sort_unstable_by_key(event_time);
-            // NOTE: Unmarshalling object recevied from K8s API-server will not fail
-            create_file_and_write(
-                root_dir.to_path_buf(),
-                "k8s_events.json".to_string(),
-                serde_json::to_string_pretty(&events)?,
-            )
-            .map_err(K8sResourceDumperError::IOError)?;
-            Ok(())
+    info!("\t Collecting Kubernetes events");
+    let mut events = k8s_client.get_events("", "").await?;
+
+    let mut errors = Vec::<K8sResourceDumperError>::new();
+    match k8s_client
+        .get_pods(&format!("app={MAYASTOR_SERVICE}"), "")
+        .await
+    {
+        Ok(pods) => {
+            let nodes = pods
+                .into_iter()
+                .filter_map(|pod| pod.spec.and_then(|spec| spec.node_name))
+                .collect::<BTreeSet<_>>();
+            for node in nodes {
+                match k8s_client.get_node_events(&node).await {
+                    Ok(node_events) => events.extend(node_events),
+                    Err(error) => {
+                        warn!("Error collecting the events of node : {node} , error: {error:?}");
+                        errors.push(error.into());
+                    }
+                }
+            }
         }
-        Err(error) => Err(K8sResourceDumperError::K8sResourceError(error)),
+        Err(error) => errors.push(error.into()),
+    }
+
+    let events = recent_events(events, since);
+    // NOTE: Unmarshalling object recevied from K8s API-server will not fail
+    write_yaml_file::<K8sResourceDumperError>(root_dir.join("k8s_events.yaml"), &events)?;
+    let mut timeline_file = create_buffered_file(root_dir.join("k8s_events_timeline.log"))?;
+    write_events_timeline(&mut timeline_file, &events)?;
+    timeline_file.flush()?;
+
+    if !errors.is_empty() {
+        return Err(K8sResourceDumperError::MultipleErrors(errors));
+    }
+    Ok(())
+}
+
+/// Write the sorted events as a timeline, one line per event, eg:
+/// `2023-10-16T10:31:02Z Warning Pod/mayastor/io-engine-x2b5l BackOff: Back-off restarting
+/// failed container (x12)`
+fn write_events_timeline(writer: &mut impl Write, events: &[Event]) -> std::io::Result<()> {
+    for event in events {
+        let object = &event.involved_object;
+        let object = match &object.namespace {
+            Some(namespace) => format!(
+                "{}/{namespace}/{}",
+                object.kind.as_deref().unwrap_or_default(),
+                object.name.as_deref().unwrap_or_default()
+            ),
+            None => format!(
+                "{}/{}",
+                object.kind.as_deref().unwrap_or_default(),
+                object.name.as_deref().unwrap_or_default()
+            ),
+        };
+        writeln!(
+            writer,
+            "{} {} {object} {}: {} (x{})",
+            event_time(event)
+                .0
+                .to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            event.type_.as_deref().unwrap_or("Normal"),
+            event.reason.as_deref().unwrap_or_default(),
+            event.message.as_deref().unwrap_or_default().trim(),
+            event.count.unwrap_or(1)
+        )?;
+    }
+    Ok(())
+}
+
+/// dump the pod object, its events and the logs of its containers into a directory named
+/// namespace_podname
+async fn dump_app_pod(
+    k8s_client: &ClientSet,
+    pod: &Pod,
+    root_dir: &Path,
+    since: Duration,
+) -> Result<(), K8sResourceDumperError> {
+    let namespace = pod.metadata.namespace.clone().unwrap_or_default();
+    let name = pod.metadata.name.clone().unwrap_or_default();
+    let mut pod_dir = root_dir.to_path_buf();
+    pod_dir.push(format!("{namespace}_{name}"));
+    create_directory_if_not_exist(pod_dir.clone())?;
+
+    write_yaml_file::<K8sResourceDumperError>(pod_dir.join("pod.yaml"), pod)?;
+
+    let events = recent_events(
+        k8s_client.get_object_events(&namespace, &name).await?,
+        since,
+    );
+    write_json_file::<K8sResourceDumperError>(pod_dir.join("events.json"), &events)?;
+
+    for container in pod.spec.iter().flat_map(|spec| spec.containers.iter()) {
+        let mut log_file = create_buffered_file(pod_dir.join(format!("{}.log", container.name)))?;
+        k8s_client
+            .write_container_logs(&namespace, &name, &container.name, since, &mut log_file)
+            .await?;
     }
+    Ok(())
 }
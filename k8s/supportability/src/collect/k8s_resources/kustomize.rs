@@ -0,0 +1,94 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::{fs::File, io::Write, path::Path};
+
+/// Name of the directory of the kustomize snapshot in the k8s resources directory
+pub(crate) const KUSTOMIZE_SNAPSHOT_DIR: &str = "kustomize";
+
+/// Metadata fields which are kept in the snapshot, all the other ones are set by the server
+const KEPT_METADATA_FIELDS: [&str; 3] = ["name", "labels", "annotations"];
+
+/// Annotation which is set by kubectl rather than by the user
+const LAST_APPLIED_ANNOTATION: &str = "kubectl.kubernetes.io/last-applied-configuration";
+
+/// Resource file of the snapshot, holding objects of the same kind
+pub(crate) struct SnapshotFile {
+    /// Name of the file, which is listed in the resources of the kustomization
+    pub(crate) name: &'static str,
+    /// Sanitized objects of the file
+    pub(crate) objects: Vec<Value>,
+}
+
+impl SnapshotFile {
+    /// Sanitize the given objects into a new snapshot file
+    pub(crate) fn new<T: Serialize>(
+        name: &'static str,
+        objects: &[T],
+    ) -> Result<Self, serde_json::Error> {
+        let objects = objects
+            .iter()
+            .map(sanitize)
+            .collect::<Result<Vec<Value>, serde_json::Error>>()?;
+        Ok(Self { name, objects })
+    }
+}
+
+/// Sanitize the object so that it can be re-applied to another cluster, i.e. strip its status
+/// and the metadata fields which are set by the server
+fn sanitize<T: Serialize>(object: &T) -> Result<Value, serde_json::Error> {
+    let mut value = serde_json::to_value(object)?;
+    if let Some(object) = value.as_object_mut() {
+        object.remove("status");
+        if let Some(metadata) = object.get_mut("metadata").and_then(Value::as_object_mut) {
+            metadata.retain(|field, _| KEPT_METADATA_FIELDS.contains(&field.as_str()));
+            if let Some(annotations) = metadata
+                .get_mut("annotations")
+                .and_then(Value::as_object_mut)
+            {
+                annotations.remove(LAST_APPLIED_ANNOTATION);
+                if annotations.is_empty() {
+                    metadata.remove("annotations");
+                }
+            }
+        }
+    }
+    Ok(value)
+}
+
+/// Write the snapshot files and their kustomization into the given directory, so that the
+/// resources can be re-created in the given namespace with `kubectl apply -k <dir>`
+pub(crate) fn write_snapshot(
+    dir: &Path,
+    namespace: &str,
+    files: &[SnapshotFile],
+) -> Result<(), std::io::Error> {
+    std::fs::create_dir_all(dir)?;
+    let mut resources = Vec::new();
+    for file in files.iter().filter(|file| !file.objects.is_empty()) {
+        let mut content = String::new();
+        for object in file.objects.iter() {
+            let yaml = serde_yaml::to_string(object)
+                .map_err(|error| std::io::Error::new(std::io::ErrorKind::Other, error))?;
+            content.push_str("---\n");
+            content.push_str(&yaml);
+        }
+        File::create(dir.join(file.name))?.write_all(content.as_bytes())?;
+        resources.push(format!("- {}\n", file.name));
+    }
+    let resources = match resources.is_empty() {
+        true => " []\n".to_string(),
+        false => format!("\n{}", resources.concat()),
+    };
+
+    let kustomization = format!(
+        "# Sanitized snapshot of the mayastor resources, re-applyable with `kubectl apply -k`.\n\
+         # The DiskPools refer to the nodes and disks of the original cluster, which need to be\n\
+         # matched by the lab cluster.\n\
+         apiVersion: kustomize.config.k8s.io/v1beta1\n\
+         kind: Kustomization\n\
+         namespace: {namespace}\n\
+         resources:{resources}"
+    );
+    File::create(dir.join("kustomization.yaml"))?.write_all(kustomization.as_bytes())?;
+    Ok(())
+}
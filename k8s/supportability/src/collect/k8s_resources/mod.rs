@@ -1,3 +1,11 @@
 pub mod client;
 pub mod common;
+pub mod core_dumps;
+pub mod custom_resources;
+pub mod helm_release;
+pub mod io_engine_state;
 pub mod k8s_resource_dump;
+pub mod kustomize;
+pub mod node_diagnostics;
+pub mod observability_state;
+pub mod packet_capture;
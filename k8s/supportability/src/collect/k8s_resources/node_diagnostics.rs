@@ -0,0 +1,190 @@
+use crate::collect::{
+    constants::{MAYASTOR_SERVICE, NODE_DIAGNOSTICS_TIMEOUT},
+    k8s_resources::{
+        client::{ClientSet, K8sResourceError},
+        k8s_resource_dump::K8sResourceDumperError,
+    },
+    logs::create_directory_if_not_exist,
+};
+use k8s_openapi::api::core::v1::Pod;
+use kube::api::{DeleteParams, LogParams, PostParams};
+use serde_json::json;
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+use tracing::{info, warn};
+
+/// Prefix of the names of the diagnostics pods, the rest of the name is generated.
+const DIAGNOSTICS_POD_PREFIX: &str = "mayastor-node-diagnostics-";
+
+/// Marker printed by the diagnostics pod before the output of every command, followed by the
+/// name of the file the output is written to.
+const OUTPUT_MARKER: &str = "--- mayastor-node-diagnostics: ";
+
+/// Interval at which the completion of the diagnostics pods is polled.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Commands run on the host of the nodes, along with the file their output is written to.
+/// They must not contain single quotes, as they are quoted in the script of the pod.
+const HOST_COMMANDS: &[(&str, &str)] = &[
+    ("lsblk.json", "lsblk -O -J"),
+    ("nvme_list.json", "nvme list -o json"),
+    ("dmesg.log", "dmesg -T | tail -n 5000"),
+    (
+        "hugepages.txt",
+        "grep -i huge /proc/meminfo; grep . /sys/devices/system/node/node*/hugepages/*/nr_hugepages",
+    ),
+    (
+        "multipath.txt",
+        "cat /sys/module/nvme_core/parameters/multipath; multipath -ll",
+    ),
+];
+
+/// Collect the storage diagnostics of every node running the io-engine, by running a privileged
+/// pod on each of them which inspects the host through its namespaces. The output of every
+/// command is written into `nodes/<name>/hw/` of the given root directory.
+pub(crate) async fn dump_node_diagnostics(
+    k8s_client: &ClientSet,
+    root_path: &str,
+    image: &str,
+) -> Result<(), K8sResourceDumperError> {
+    let nodes = k8s_client
+        .get_pods(&format!("app={MAYASTOR_SERVICE}"), "")
+        .await?
+        .into_iter()
+        .filter_map(|pod| pod.spec.and_then(|spec| spec.node_name))
+        .collect::<BTreeSet<_>>();
+
+    let results = futures::future::join_all(
+        nodes
+            .iter()
+            .map(|node| dump_node(k8s_client, node, Path::new(root_path), image)),
+    )
+    .await;
+
+    let mut errors = Vec::new();
+    for (node, result) in nodes.iter().zip(results) {
+        match result {
+            Ok(()) => info!("\t Collected storage diagnostics of node: {node}"),
+            Err(error) => {
+                warn!("\t Failed to collect storage diagnostics of node: {node}, error: {error:?}");
+                errors.push(error);
+            }
+        }
+    }
+    if !errors.is_empty() {
+        return Err(K8sResourceDumperError::MultipleErrors(errors));
+    }
+    Ok(())
+}
+
+/// Run the diagnostics pod on the node and write its output, the pod is always deleted.
+async fn dump_node(
+    k8s_client: &ClientSet,
+    node: &str,
+    root_dir: &Path,
+    image: &str,
+) -> Result<(), K8sResourceDumperError> {
+    let pods_api = k8s_client.get_pod_api().await;
+    let pod: Pod = serde_json::from_value(diagnostics_pod(node, image))?;
+    let pod = pods_api
+        .create(&PostParams::default(), &pod)
+        .await
+        .map_err(K8sResourceError::from)?;
+    let name = pod.metadata.name.unwrap_or_default();
+
+    let result: Result<String, K8sResourceDumperError> =
+        match wait_for_completion(k8s_client, &name).await {
+            Ok(()) => pods_api
+                .logs(&name, &LogParams::default())
+                .await
+                .map_err(|error| K8sResourceError::from(error).into()),
+            Err(error) => Err(error),
+        };
+    if let Err(error) = pods_api.delete(&name, &DeleteParams::default()).await {
+        warn!("\t Failed to delete the diagnostics pod: {name}, error: {error:?}");
+    }
+
+    let mut hw_dir = root_dir.join("nodes");
+    hw_dir.push(node);
+    hw_dir.push("hw");
+    create_directory_if_not_exist(hw_dir.clone())?;
+    write_outputs(&result?, hw_dir)
+}
+
+/// Wait until the diagnostics pod ran to completion.
+async fn wait_for_completion(
+    k8s_client: &ClientSet,
+    name: &str,
+) -> Result<(), K8sResourceDumperError> {
+    let pods_api = k8s_client.get_pod_api().await;
+    let start = Instant::now();
+    loop {
+        let pod = pods_api.get(name).await.map_err(K8sResourceError::from)?;
+        match pod.status.and_then(|status| status.phase).as_deref() {
+            // The output of the failed commands is collected along with the successful ones.
+            Some("Succeeded") | Some("Failed") => return Ok(()),
+            _ if start.elapsed() > NODE_DIAGNOSTICS_TIMEOUT => {
+                return Err(K8sResourceError::CustomError(format!(
+                    "Diagnostics pod {name} did not complete within {}",
+                    humantime::format_duration(NODE_DIAGNOSTICS_TIMEOUT)
+                ))
+                .into())
+            }
+            _ => tokio::time::sleep(POLL_INTERVAL).await,
+        }
+    }
+}
+
+/// Split the output of the diagnostics pod into a file per command.
+fn write_outputs(output: &str, hw_dir: PathBuf) -> Result<(), K8sResourceDumperError> {
+    let mut files = Vec::<(&str, String)>::new();
+    for line in output.lines() {
+        if let Some(file_name) = line.strip_prefix(OUTPUT_MARKER) {
+            files.push((file_name, String::new()));
+        } else if let Some((_, content)) = files.last_mut() {
+            content.push_str(line);
+            content.push('\n');
+        }
+    }
+    for (file_name, content) in files {
+        std::fs::write(hw_dir.join(file_name), content)?;
+    }
+    Ok(())
+}
+
+/// Privileged pod pinned to the node, which shares the host's process namespace so that the
+/// commands run in the host's namespaces with the host's tools.
+fn diagnostics_pod(node: &str, image: &str) -> serde_json::Value {
+    let script = HOST_COMMANDS
+        .iter()
+        .map(|(file_name, command)| {
+            format!(
+                "echo '{OUTPUT_MARKER}{file_name}'; nsenter -t 1 -m -u -i -n -p -- sh -c '{command}' 2>&1"
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("; ");
+    json!({
+        "apiVersion": "v1",
+        "kind": "Pod",
+        "metadata": {
+            "generateName": DIAGNOSTICS_POD_PREFIX,
+            "labels": { "app": "mayastor-node-diagnostics" },
+        },
+        "spec": {
+            "nodeName": node,
+            "hostPID": true,
+            "restartPolicy": "Never",
+            "tolerations": [{ "operator": "Exists" }],
+            "containers": [{
+                "name": "diagnostics",
+                "image": image,
+                "command": ["sh", "-c", script],
+                "securityContext": { "privileged": true },
+            }],
+        },
+    })
+}
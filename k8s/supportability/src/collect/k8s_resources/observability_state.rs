@@ -0,0 +1,238 @@
+use crate::collect::{
+    constants::{
+        CALLHOME_METRICS_PORT, CALLHOME_SERVICE, CALLHOME_STATS_PORT, MAYASTOR_SERVICE,
+        METRICS_EXPORTER_CONTAINER, METRICS_EXPORTER_PORT, OBSERVABILITY_STATE_TIMEOUT,
+    },
+    k8s_resources::{
+        client::{ClientSet, K8sResourceError},
+        k8s_resource_dump::K8sResourceDumperError,
+    },
+    logs::create_directory_if_not_exist,
+};
+use k8s_openapi::api::core::v1::Pod;
+use kube::api::Portforwarder;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Directory of the archive in which the state of the observability components is written.
+pub(crate) const OBSERVABILITY_DIR: &str = "observability";
+
+/// Collect the state of the observability components themselves, so that it's known whether they
+/// were healthy during the incident window: the `/metrics` of the metrics-exporter of every
+/// io-engine pod, and the `/metrics`, `/stats` and `/stats/json` of the call-home pods. The
+/// components are reached through a port-forward of their pod, and their responses are written
+/// into `observability/metrics_exporter/<node>/` and `observability/callhome/<pod>/` of the given
+/// root directory.
+pub(crate) async fn dump_observability_state(
+    k8s_client: &ClientSet,
+    root_path: &str,
+) -> Result<(), K8sResourceDumperError> {
+    let root_dir = Path::new(root_path).join(OBSERVABILITY_DIR);
+    let mut errors = Vec::new();
+
+    let exporters = k8s_client
+        .get_pods(&format!("app={MAYASTOR_SERVICE}"), "")
+        .await?
+        .into_iter()
+        .filter(|pod| is_running(pod) && has_container(pod, METRICS_EXPORTER_CONTAINER))
+        .filter_map(|pod| Some((pod.spec?.node_name?, pod.metadata.name?)))
+        .collect::<Vec<_>>();
+    let results = futures::future::join_all(exporters.iter().map(|(node, pod)| {
+        dump_exporter(
+            k8s_client,
+            pod,
+            root_dir.join("metrics_exporter").join(node),
+        )
+    }))
+    .await;
+    for ((node, _), result) in exporters.iter().zip(results) {
+        match result {
+            Ok(()) => info!("\t Collected metrics-exporter state of node: {node}"),
+            Err(error) => {
+                warn!(
+                    "\t Failed to collect metrics-exporter state of node: {node}, error: {error:?}"
+                );
+                errors.push(error);
+            }
+        }
+    }
+
+    let callhome_pods = k8s_client
+        .get_pods(&format!("app={CALLHOME_SERVICE}"), "")
+        .await?
+        .into_iter()
+        .filter(is_running)
+        .filter_map(|pod| pod.metadata.name)
+        .collect::<Vec<_>>();
+    for pod in callhome_pods {
+        match dump_callhome(k8s_client, &pod, root_dir.join("callhome").join(&pod)).await {
+            Ok(()) => info!("\t Collected call-home state of pod: {pod}"),
+            Err(error) => {
+                warn!("\t Failed to collect call-home state of pod: {pod}, error: {error:?}");
+                errors.push(error);
+            }
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(K8sResourceDumperError::MultipleErrors(errors));
+    }
+    Ok(())
+}
+
+/// Whether the pod is running, the components of the other pods can't be reached.
+fn is_running(pod: &Pod) -> bool {
+    pod.status
+        .as_ref()
+        .and_then(|status| status.phase.as_deref())
+        == Some("Running")
+}
+
+/// Whether the pod has the container, eg: the metrics-exporter isn't deployed with the metrics
+/// disabled.
+fn has_container(pod: &Pod, container: &str) -> bool {
+    pod.spec
+        .as_ref()
+        .is_some_and(|spec| spec.containers.iter().any(|c| c.name == container))
+}
+
+/// Scrape the metrics-exporter of the io-engine pod through a port-forward.
+async fn dump_exporter(
+    k8s_client: &ClientSet,
+    pod: &str,
+    dir: PathBuf,
+) -> Result<(), K8sResourceDumperError> {
+    let mut forwarder = port_forward(k8s_client, pod, &[METRICS_EXPORTER_PORT]).await?;
+    create_directory_if_not_exist(dir.clone())?;
+    let result = http_get(&mut forwarder, pod, METRICS_EXPORTER_PORT, &["/metrics"])
+        .await
+        .and_then(|responses| write_responses(&dir, responses));
+    forwarder.abort();
+    result
+}
+
+/// Get the metrics and the aggregated stats of the call-home pod through a port-forward. Every
+/// endpoint is attempted, a failed one doesn't prevent the others from being written.
+async fn dump_callhome(
+    k8s_client: &ClientSet,
+    pod: &str,
+    dir: PathBuf,
+) -> Result<(), K8sResourceDumperError> {
+    let mut forwarder = port_forward(
+        k8s_client,
+        pod,
+        &[CALLHOME_METRICS_PORT, CALLHOME_STATS_PORT],
+    )
+    .await?;
+    create_directory_if_not_exist(dir.clone())?;
+    let results = vec![
+        http_get(&mut forwarder, pod, CALLHOME_METRICS_PORT, &["/metrics"])
+            .await
+            .and_then(|responses| write_responses(&dir, responses)),
+        http_get(
+            &mut forwarder,
+            pod,
+            CALLHOME_STATS_PORT,
+            &["/stats", "/stats/json"],
+        )
+        .await
+        .and_then(|responses| write_responses(&dir, responses)),
+    ];
+    forwarder.abort();
+
+    let errors = results
+        .into_iter()
+        .filter_map(Result::err)
+        .collect::<Vec<_>>();
+    if !errors.is_empty() {
+        return Err(K8sResourceDumperError::MultipleErrors(errors));
+    }
+    Ok(())
+}
+
+/// Port-forward the ports of the pod.
+async fn port_forward(
+    k8s_client: &ClientSet,
+    pod: &str,
+    ports: &[u16],
+) -> Result<Portforwarder, K8sResourceDumperError> {
+    Ok(k8s_client
+        .get_pod_api()
+        .await
+        .portforward(pod, ports)
+        .await
+        .map_err(K8sResourceError::from)?)
+}
+
+/// Get the paths over the single connection the port-forward carries for the port, one after
+/// the other, bounded by the observability state timeout.
+async fn http_get(
+    forwarder: &mut Portforwarder,
+    pod: &str,
+    port: u16,
+    paths: &[&'static str],
+) -> Result<Vec<(&'static str, String)>, K8sResourceDumperError> {
+    let stream = forwarder.take_stream(port).ok_or_else(|| {
+        K8sResourceError::CustomError(format!(
+            "No port-forward stream to port {port} of pod {pod}"
+        ))
+    })?;
+    let requests = async {
+        let (mut sender, connection) = hyper::client::conn::handshake(stream).await?;
+        let connection = tokio::spawn(connection);
+        let mut responses = Vec::new();
+        for path in paths {
+            let request = http::Request::get(*path)
+                .header(http::header::HOST, "localhost")
+                .body(hyper::Body::empty())
+                .expect("the request of a static path is valid");
+            let response = sender.send_request(request).await?;
+            let status = response.status();
+            let body = hyper::body::to_bytes(response.into_body()).await?;
+            responses.push((*path, status, String::from_utf8_lossy(&body).into_owned()));
+        }
+        connection.abort();
+        Ok::<_, hyper::Error>(responses)
+    };
+    let responses = tokio::time::timeout(OBSERVABILITY_STATE_TIMEOUT, requests)
+        .await
+        .map_err(|_| http_error(pod, port, "timed out"))?
+        .map_err(|error| http_error(pod, port, error))?;
+    responses
+        .into_iter()
+        .map(|(path, status, body)| {
+            if status.is_success() {
+                Ok((path, body))
+            } else {
+                Err(http_error(
+                    pod,
+                    port,
+                    format!("GET {path} returned {status}"),
+                ))
+            }
+        })
+        .collect()
+}
+
+/// Write the response of each path into the directory, eg: `/stats/json` into `stats.json`.
+fn write_responses(
+    dir: &Path,
+    responses: Vec<(&'static str, String)>,
+) -> Result<(), K8sResourceDumperError> {
+    for (path, body) in responses {
+        let name = match path.trim_start_matches('/').split_once('/') {
+            Some((name, extension)) => format!("{name}.{extension}"),
+            None => format!("{}.txt", path.trim_start_matches('/')),
+        };
+        std::fs::write(dir.join(name), body)?;
+    }
+    Ok(())
+}
+
+/// Error of an http request to the port of the pod.
+fn http_error(pod: &str, port: u16, error: impl std::fmt::Display) -> K8sResourceDumperError {
+    K8sResourceError::CustomError(format!(
+        "http request to port {port} of pod {pod} failed: {error}"
+    ))
+    .into()
+}
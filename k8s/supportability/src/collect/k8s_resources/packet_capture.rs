@@ -0,0 +1,245 @@
+use crate::collect::{
+    constants::NODE_DIAGNOSTICS_TIMEOUT,
+    k8s_resources::{
+        client::{ClientSet, K8sResourceError},
+        k8s_resource_dump::K8sResourceDumperError,
+    },
+    logs::create_directory_if_not_exist,
+};
+use k8s_openapi::api::core::v1::Pod;
+use kube::api::{Api, AttachParams, DeleteParams, PostParams};
+use serde::Serialize;
+use serde_json::json;
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+use tracing::{info, warn};
+
+/// Prefix of the names of the packet capture pods, the rest of the name is generated.
+const CAPTURE_POD_PREFIX: &str = "mayastor-packet-capture-";
+
+/// Interval at which the startup of the capture pods is polled.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Time the capture pods are given beyond the capture, to stream it back, after which they exit
+/// should they not have been deleted.
+const POD_LIFETIME_MARGIN: Duration = Duration::from_secs(5 * 60);
+
+/// Name of the collected packet trace of a node.
+const CAPTURE_FILE: &str = "nvmf.pcap";
+
+/// Name of the file describing how the packet trace of a node was captured.
+const CAPTURE_SUMMARY_FILE: &str = "capture.json";
+
+/// How the packet traces of the NVMe-oF ports are captured.
+#[derive(Debug, Clone)]
+pub(crate) struct PacketCaptureConfig {
+    /// Nodes on which the packets are captured.
+    pub(crate) nodes: Vec<String>,
+    /// TCP ports of the NVMe-oF targets whose packets are captured.
+    pub(crate) ports: Vec<u16>,
+    /// Duration of the capture on each node.
+    pub(crate) duration: Duration,
+    /// Maximum size of the packet trace of each node, in bytes, it's truncated beyond it.
+    pub(crate) max_size: u64,
+    /// Number of bytes captured of each packet.
+    pub(crate) snaplen: u32,
+    /// Image of the pods capturing the packets, which needs a shell, tcpdump, timeout and head.
+    pub(crate) image: String,
+}
+
+impl PacketCaptureConfig {
+    /// The tcpdump filter of the packets of the NVMe-oF ports.
+    fn filter(&self) -> String {
+        self.ports
+            .iter()
+            .map(|port| format!("tcp port {port}"))
+            .collect::<Vec<_>>()
+            .join(" or ")
+    }
+}
+
+/// How the packet trace of a node was captured.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct CaptureSummary {
+    node: String,
+    filter: String,
+    duration_secs: u64,
+    snaplen: u32,
+    size: u64,
+    /// Whether the trace was cut at the maximum size, its last packet may then be incomplete.
+    truncated: bool,
+}
+
+/// Capture the packets of the NVMe-oF ports of the selected nodes, by running a privileged pod in
+/// the host's network namespace of each of them. The packet traces are written into
+/// `nodes/<name>/pcap/` of the given root directory.
+pub(crate) async fn dump_packet_captures(
+    k8s_client: &ClientSet,
+    root_path: &str,
+    config: &PacketCaptureConfig,
+) -> Result<(), K8sResourceDumperError> {
+    let results = futures::future::join_all(
+        config
+            .nodes
+            .iter()
+            .map(|node| dump_node(k8s_client, node, Path::new(root_path), config)),
+    )
+    .await;
+
+    let mut errors = Vec::new();
+    for (node, result) in config.nodes.iter().zip(results) {
+        match result {
+            Ok(size) => info!(
+                "\t Captured {} of NVMe-oF packets on node: {node}",
+                bytesize::ByteSize::b(size)
+            ),
+            Err(error) => {
+                warn!("\t Failed to capture the NVMe-oF packets on node: {node}, error: {error:?}");
+                errors.push(error);
+            }
+        }
+    }
+    if !errors.is_empty() {
+        return Err(K8sResourceDumperError::MultipleErrors(errors));
+    }
+    Ok(())
+}
+
+/// Run the capture pod on the node and stream the packet trace out of it, the pod is always
+/// deleted. Returns the size of the packet trace.
+async fn dump_node(
+    k8s_client: &ClientSet,
+    node: &str,
+    root_dir: &Path,
+    config: &PacketCaptureConfig,
+) -> Result<u64, K8sResourceDumperError> {
+    let pods_api = k8s_client.get_pod_api().await;
+    let pod: Pod = serde_json::from_value(capture_pod(node, config))?;
+    let pod = pods_api
+        .create(&PostParams::default(), &pod)
+        .await
+        .map_err(K8sResourceError::from)?;
+    let name = pod.metadata.name.unwrap_or_default();
+
+    let mut pcap_dir = root_dir.join("nodes");
+    pcap_dir.push(node);
+    pcap_dir.push("pcap");
+    let result = match wait_for_running(&pods_api, &name).await {
+        Ok(()) => capture(&pods_api, &name, node, pcap_dir, config).await,
+        Err(error) => Err(error),
+    };
+    if let Err(error) = pods_api.delete(&name, &DeleteParams::default()).await {
+        warn!("\t Failed to delete the packet capture pod: {name}, error: {error:?}");
+    }
+    result
+}
+
+/// Wait until the capture pod is running, so that the capture can be executed in it.
+async fn wait_for_running(pods_api: &Api<Pod>, name: &str) -> Result<(), K8sResourceDumperError> {
+    let start = Instant::now();
+    loop {
+        let pod = pods_api.get(name).await.map_err(K8sResourceError::from)?;
+        match pod.status.and_then(|status| status.phase).as_deref() {
+            Some("Running") => return Ok(()),
+            Some(phase @ ("Succeeded" | "Failed")) => {
+                return Err(K8sResourceError::CustomError(format!(
+                    "Packet capture pod {name} is {phase} instead of running"
+                ))
+                .into())
+            }
+            _ if start.elapsed() > NODE_DIAGNOSTICS_TIMEOUT => {
+                return Err(K8sResourceError::CustomError(format!(
+                    "Packet capture pod {name} did not start within {}",
+                    humantime::format_duration(NODE_DIAGNOSTICS_TIMEOUT)
+                ))
+                .into())
+            }
+            _ => tokio::time::sleep(POLL_INTERVAL).await,
+        }
+    }
+}
+
+/// Capture the packets for the configured duration, streaming the trace into the pcap file, and
+/// write its summary.
+async fn capture(
+    pods_api: &Api<Pod>,
+    name: &str,
+    node: &str,
+    pcap_dir: PathBuf,
+    config: &PacketCaptureConfig,
+) -> Result<u64, K8sResourceDumperError> {
+    create_directory_if_not_exist(pcap_dir.clone())?;
+
+    // tcpdump is interrupted once the duration elapsed, so that it flushes the trace, and the
+    // trace is cut at the maximum size. The exit status of the capture is irrelevant.
+    let script = format!(
+        "timeout -s INT {} tcpdump -i any -nn -U -s {} -w - '{}' 2>/dev/null | head -c {}; true",
+        config.duration.as_secs().max(1),
+        config.snaplen,
+        config.filter(),
+        config.max_size
+    );
+    let params = AttachParams::default()
+        .stdin(false)
+        .stdout(true)
+        .stderr(false);
+    let mut process = pods_api
+        .exec(
+            name,
+            vec!["sh".to_string(), "-c".to_string(), script],
+            &params,
+        )
+        .await
+        .map_err(K8sResourceError::from)?;
+    let mut stdout = process.stdout().ok_or_else(|| {
+        K8sResourceError::CustomError(format!("No output of the packet capture pod {name}"))
+    })?;
+    let mut file = tokio::fs::File::create(pcap_dir.join(CAPTURE_FILE)).await?;
+    let size = tokio::io::copy(&mut stdout, &mut file).await?;
+    drop(stdout);
+    process
+        .join()
+        .await
+        .map_err(|error| K8sResourceError::CustomError(error.to_string()))?;
+
+    let summary = CaptureSummary {
+        node: node.to_string(),
+        filter: config.filter(),
+        duration_secs: config.duration.as_secs(),
+        snaplen: config.snaplen,
+        size,
+        truncated: size >= config.max_size,
+    };
+    let file = std::fs::File::create(pcap_dir.join(CAPTURE_SUMMARY_FILE))?;
+    serde_json::to_writer_pretty(file, &summary)?;
+    Ok(size)
+}
+
+/// Privileged pod pinned to the node, in the host's network namespace so that it sees the
+/// NVMe-oF traffic of the host. It's idle until the capture is executed in it.
+fn capture_pod(node: &str, config: &PacketCaptureConfig) -> serde_json::Value {
+    let lifetime = config.duration + POD_LIFETIME_MARGIN;
+    json!({
+        "apiVersion": "v1",
+        "kind": "Pod",
+        "metadata": {
+            "generateName": CAPTURE_POD_PREFIX,
+            "labels": { "app": "mayastor-packet-capture" },
+        },
+        "spec": {
+            "nodeName": node,
+            "hostNetwork": true,
+            "restartPolicy": "Never",
+            "tolerations": [{ "operator": "Exists" }],
+            "containers": [{
+                "name": "capture",
+                "image": config.image,
+                "command": ["sleep", lifetime.as_secs().to_string()],
+                "securityContext": { "privileged": true },
+            }],
+        },
+    })
+}
@@ -1,17 +1,23 @@
-use crate::{
-    collect::{
-        k8s_resources::{
-            client::{ClientSet, K8sResourceError},
-            common::{NODE_NAME_FIELD_SELECTOR, RUNNING_FIELD_SELECTOR},
-        },
-        logs::create_directory_if_not_exist,
+use crate::collect::{
+    k8s_resources::{
+        client::{ClientSet, K8sResourceError},
+        common::{NODE_NAME_FIELD_SELECTOR, RUNNING_FIELD_SELECTOR},
     },
-    log,
+    logs::create_directory_if_not_exist,
+    utils::create_buffered_file,
 };
 use futures::{AsyncBufReadExt, StreamExt};
-use k8s_openapi::api::core::v1::Pod;
+use k8s_openapi::{
+    api::core::v1::{ContainerStatus, Pod},
+    apimachinery::pkg::apis::meta::v1::Time,
+};
 use kube::{api::LogParams, Error, Resource};
-use std::{collections::HashMap, fs::File, io::Write, path::PathBuf};
+use serde::Serialize;
+use std::{collections::HashMap, fs::File, io::Write, path::PathBuf, time::Duration};
+use tracing::warn;
+
+/// Name of the file holding the restart history of the containers of a pod, in its directory.
+const RESTART_HISTORY_FILE: &str = "restarts.json";
 
 /// Possible errors can occur while interacting with K8s for logs, and file creations
 #[derive(Debug)]
@@ -44,10 +50,57 @@ impl From<kube::Error> for K8sLoggerError {
     }
 }
 
+/// Restart history of a container, as the crash which caused an incident is usually the last
+/// termination of the previous instance of the container.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct ContainerRestartHistory {
+    container: String,
+    restart_count: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_termination: Option<ContainerTermination>,
+}
+
+/// How the previous instance of a container terminated.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct ContainerTermination {
+    reason: Option<String>,
+    message: Option<String>,
+    exit_code: i32,
+    signal: Option<i32>,
+    started_at: Option<Time>,
+    finished_at: Option<Time>,
+}
+
+impl From<&ContainerStatus> for ContainerRestartHistory {
+    fn from(status: &ContainerStatus) -> Self {
+        let last_termination = status
+            .last_state
+            .as_ref()
+            .and_then(|state| state.terminated.as_ref())
+            .map(|terminated| ContainerTermination {
+                reason: terminated.reason.clone(),
+                message: terminated.message.clone(),
+                exit_code: terminated.exit_code,
+                signal: terminated.signal,
+                started_at: terminated.started_at.clone(),
+                finished_at: terminated.finished_at.clone(),
+            });
+        Self {
+            container: status.name.clone(),
+            restart_count: status.restart_count,
+            last_termination,
+        }
+    }
+}
+
 /// K8sLoggerClient to be used for fetching k8s logs on central log management's failure
 #[derive(Clone)]
 pub(crate) struct K8sLoggerClient {
     k8s_client: ClientSet,
+    /// Period from which logs needs to collect
+    since: Duration,
 }
 
 /// No of times we want to retry fetching from logs stream
@@ -55,8 +108,8 @@ pub(crate) const MAX_POLLING_RETRIES: u8 = 2;
 
 impl K8sLoggerClient {
     /// Create a new ClientSet, with the config if provided, otherwise with default.
-    pub(crate) fn new(k8s_client: ClientSet) -> Self {
-        Self { k8s_client }
+    pub(crate) fn new(k8s_client: ClientSet, since: Duration) -> Self {
+        Self { k8s_client, since }
     }
 
     /// get the k8s client
@@ -100,11 +153,11 @@ impl K8sLoggerClient {
             {
                 Ok(()) => {}
                 Err(err) => {
-                    log(format!(
+                    warn!(
                         "Error fetching logs for pod : {}, error: {:?}",
                         pod.meta().name.as_ref().unwrap_or(&"".to_string()),
                         err
-                    ));
+                    );
                     continue;
                 }
             }
@@ -113,7 +166,9 @@ impl K8sLoggerClient {
     }
 
     /// Creates the pod level log dumps, i.e would create list of directories of name
-    /// hostname_pod_name with specified container log files inside.
+    /// hostname_pod_name with specified container log files inside, along with the restart history
+    /// of the containers. The logs of the previous instance of a restarted container are collected
+    /// as well, failing to get them doesn't prevent collecting the current logs.
     async fn create_pod_logs(
         &self,
         pod: &Pod,
@@ -146,37 +201,46 @@ impl K8sLoggerClient {
         pod_dir.push(format!("{}_{}", host_name, pod_name.clone()));
         create_directory_if_not_exist(pod_dir.clone())?;
 
-        let mut container_restart_map: HashMap<String, bool> = HashMap::new();
-
-        match pod.status.as_ref() {
-            None => {}
-            Some(status) => match status.container_statuses.as_ref() {
-                None => {}
-                Some(container_statuses) => {
-                    for container_status in container_statuses {
-                        container_restart_map.insert(
-                            container_status.name.to_string(),
-                            container_status.restart_count > 0,
-                        );
-                    }
-                }
-            },
-        };
+        let container_statuses: HashMap<&str, &ContainerStatus> = pod
+            .status
+            .as_ref()
+            .and_then(|status| status.container_statuses.as_ref())
+            .into_iter()
+            .flatten()
+            .map(|status| (status.name.as_str(), status))
+            .collect();
+
+        let restart_history = containers
+            .iter()
+            .filter_map(|container| container_statuses.get(container))
+            .map(|status| ContainerRestartHistory::from(*status))
+            .collect::<Vec<_>>();
+        if !restart_history.is_empty() {
+            let history_file = File::create(pod_dir.join(RESTART_HISTORY_FILE))?;
+            serde_json::to_writer_pretty(history_file, &restart_history)
+                .map_err(std::io::Error::from)?;
+        }
 
         for container in containers {
             let container_name = container.to_string();
 
-            if *container_restart_map
-                .get(container_name.as_str())
-                .unwrap_or(&false)
-            {
-                self.create_container_log_file(
-                    true,
-                    pod_name.clone(),
-                    container_name.clone(),
-                    pod_dir.clone(),
-                )
-                .await?;
+            let restarted = container_statuses
+                .get(container)
+                .map_or(false, |status| status.restart_count > 0);
+            if restarted {
+                if let Err(error) = self
+                    .create_container_log_file(
+                        true,
+                        pod_name.clone(),
+                        container_name.clone(),
+                        pod_dir.clone(),
+                    )
+                    .await
+                {
+                    warn!(
+                        "Error fetching previous logs for pod: {pod_name}, container: {container_name}, error: {error:?}"
+                    );
+                }
             }
 
             self.create_container_log_file(
@@ -206,7 +270,7 @@ impl K8sLoggerClient {
             container_file.push(format!("{container_name}.log"));
         }
 
-        let log_file = File::create(container_file)?;
+        let log_file = create_buffered_file(container_file)?;
 
         let client_set = self.clone();
 
@@ -232,6 +296,7 @@ impl K8sLoggerClient {
         let log_params = LogParams {
             container: Some(container_name.to_string()),
             previous: previous_logs,
+            since_seconds: Some(self.since.as_secs() as i64),
             ..Default::default()
         };
 
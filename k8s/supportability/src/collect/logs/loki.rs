@@ -1,15 +1,44 @@
-use crate::{collect::utils::write_to_log_file, log};
+use crate::collect::utils::create_buffered_file;
 use chrono::Utc;
 use hyper::body::Buf;
 use serde::{Deserialize, Serialize};
-use std::{io::Write, path::PathBuf};
-use tower::{util::BoxService, Service, ServiceExt};
+use std::{io::Write, path::PathBuf, str::FromStr};
+use tower::{buffer::Buffer, util::BoxService, Service, ServiceExt};
+use tracing::{debug, warn};
 
 /// Loki endpoint to query for logs
 const ENDPOINT: &str = "/loki/api/v1/query_range";
 
+/// Loki endpoint which answers once Loki is ready to serve queries
+const READY_ENDPOINT: &str = "/ready";
+
 const SERVICE_NAME: &str = "loki";
 
+/// Maximum number of requests queued to the Loki service, shared by the concurrent collectors
+const REQUEST_BUFFER_SIZE: usize = 64;
+
+/// Loki service which can be shared by the concurrent collectors
+type LokiService = Buffer<kube_proxy::LokiClient, http::Request<hyper::body::Body>>;
+
+/// Length of the time window queried from Loki at once, in nanoseconds
+const QUERY_CHUNK_NANOS: SinceTime = 60 * 60 * 1_000_000_000;
+
+/// Build the Loki service over the given connector, bounded by the timeout
+fn loki_service<C>(connector: C, timeout: std::time::Duration) -> kube_proxy::LokiClient
+where
+    C: hyper::client::connect::Connect + Clone + Send + Sync + 'static,
+{
+    let client = hyper::Client::builder()
+        .http2_keep_alive_timeout(timeout)
+        .http2_keep_alive_interval(timeout / 2)
+        .build(connector);
+    BoxService::new(
+        tower::ServiceBuilder::new()
+            .timeout(timeout)
+            .service(client),
+    )
+}
+
 /// Possible errors can occur while interacting with Loki service
 #[derive(Debug)]
 pub(crate) enum LokiError {
@@ -78,6 +107,31 @@ struct LokiResponse {
 
 type SinceTime = u128;
 
+/// Point in time bounding the collected logs, either absolute in RFC3339 format
+/// (eg: 2023-10-01T12:00:00Z) or relative to now (eg: -2h)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct LogTime(SinceTime);
+
+impl FromStr for LogTime {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(relative) = s.strip_prefix('-') {
+            let duration = humantime::parse_duration(relative)
+                .map_err(|error| format!("Invalid relative time '{s}': {error}"))?;
+            return Ok(Self(
+                get_current_unix_time().saturating_sub(duration.as_nanos()),
+            ));
+        }
+        let time = chrono::DateTime::parse_from_rfc3339(s)
+            .map_err(|error| format!("Invalid RFC3339 time '{s}': {error}"))?;
+        time.timestamp_nanos_opt()
+            .and_then(|nanos| SinceTime::try_from(nanos).ok())
+            .map(Self)
+            .ok_or_else(|| format!("Time '{s}' is out of range"))
+    }
+}
+
 impl LokiResponse {
     // fetch last stream log epoch timestamp in nanoseconds
     fn get_last_stream_unix_time(&self) -> SinceTime {
@@ -114,16 +168,18 @@ impl LogDirection {
 
 /// Http client to interact with Loki (a log management system)
 /// to fetch historical log information
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct LokiClient {
     /// Address of Loki service
     uri: String,
     /// Loki client
-    inner_client: kube_proxy::LokiClient,
+    inner_client: LokiService,
     /// Endpoint of Loki logs service
     logs_endpoint: String,
     /// Defines period from which logs needs to collect
     since: SinceTime,
+    /// Defines the time until which logs needs to collect
+    until: SinceTime,
     /// Determines the sort order of logs. Supported values are "forward" or "backward".
     /// Defaults to forward
     direction: LogDirection,
@@ -132,27 +188,30 @@ pub(crate) struct LokiClient {
 }
 
 impl LokiClient {
-    /// Instantiate new instance of Http Loki client
+    /// Instantiate new instance of Http Loki client. The logs are collected from `start`, or
+    /// from the given duration ago, until `end` or now.
     pub(crate) async fn new(
         uri: Option<String>,
         kube_config_path: Option<std::path::PathBuf>,
         namespace: String,
         since: humantime::Duration,
+        start: Option<LogTime>,
+        end: Option<LogTime>,
         timeout: humantime::Duration,
+        proxy: Option<kube_proxy::EgressProxy>,
     ) -> Option<Self> {
         let (uri, client) = match uri {
             None => {
                 let (uri, svc) = match kube_proxy::ConfigBuilder::default_loki()
                     .with_kube_config(kube_config_path)
+                    .with_proxy(proxy)
                     .with_target_mod(|t| t.with_namespace(namespace))
                     .build()
                     .await
                 {
                     Ok(result) => result,
                     Err(error) => {
-                        log(format!(
-                            "Failed to create loki client ({error:?}). Continuing..."
-                        ));
+                        warn!("Failed to create loki client ({error:?}). Continuing...");
                         return None;
                     }
                 };
@@ -161,33 +220,58 @@ impl LokiClient {
             Some(uri) => {
                 let mut connector = hyper::client::HttpConnector::new();
                 connector.set_connect_timeout(Some(*timeout));
-                let client = hyper::Client::builder()
-                    .http2_keep_alive_timeout(*timeout)
-                    .http2_keep_alive_interval(*timeout / 2)
-                    .build(connector);
-                let service = tower::ServiceBuilder::new()
-                    .timeout(*timeout)
-                    .service(client);
-                (uri, BoxService::new(service))
+                let proxy = proxy.filter(|proxy| {
+                    hyper::Uri::from_str(&uri).map_or(false, |uri| proxy.intercepts(&uri))
+                });
+                let client = match proxy {
+                    Some(proxy) => match proxy.connector(connector) {
+                        Ok(connector) => loki_service(connector, *timeout),
+                        Err(error) => {
+                            warn!("Failed to create loki client ({error:?}). Continuing...");
+                            return None;
+                        }
+                    },
+                    None => loki_service(connector, *timeout),
+                };
+                (uri, client)
             }
         };
 
         Some(LokiClient {
             uri,
-            inner_client: client,
-            since: get_epoch_unix_time(since),
+            inner_client: Buffer::new(client, REQUEST_BUFFER_SIZE),
+            since: start
+                .map(|start| start.0)
+                .unwrap_or_else(|| get_epoch_unix_time(since)),
+            until: end.map(|end| end.0).unwrap_or_else(get_current_unix_time),
             logs_endpoint: ENDPOINT.to_string(),
             direction: LogDirection::Forward,
             limit: 3000,
         })
     }
 
+    /// Check that Loki is reachable and ready to serve queries.
+    pub(crate) async fn check_ready(&self) -> Result<(), LokiError> {
+        let request = http::Request::builder()
+            .method("GET")
+            .uri(format!("{}{READY_ENDPOINT}", self.uri))
+            .body(hyper::body::Body::empty())?;
+        let mut client = self.inner_client.clone();
+        let response = client.ready().await?.call(request).await?;
+        if !response.status().is_success() {
+            let body_bytes = hyper::body::to_bytes(response.into_body()).await?;
+            let text = String::from_utf8(body_bytes.to_vec()).unwrap_or_default();
+            return Err(LokiError::Response(text));
+        }
+        Ok(())
+    }
+
     /// fetch_and_dump_logs will do the following steps:
     /// 1. Creates poller to interact with Loki service based on provided arguments 1.1. Use poller
     ///    to fetch all available logs 1.2. Write fetched logs into file Continue above steps till
     ///    extraction all logs
     pub(crate) async fn fetch_and_dump_logs(
-        &mut self,
+        &self,
         label_selector: String,
         container_name: String,
         host_name: Option<String>,
@@ -234,13 +318,14 @@ impl LokiClient {
             uri: self.uri.clone(),
             endpoint: self.logs_endpoint.clone(),
             since: self.since,
+            until: self.until,
+            chunk_end: self.until.min(self.since + QUERY_CHUNK_NANOS),
             query_params,
-            next_start_epoch_timestamp: 0,
-            client: self,
+            client: self.inner_client.clone(),
         };
         let mut is_written = false;
         let file_path = service_dir.join(file_name.clone());
-        let mut log_file: std::fs::File = std::fs::File::create(file_path.clone())?;
+        let mut log_file = create_buffered_file(&file_path)?;
 
         loop {
             let result = match poller.poll_next().await {
@@ -253,53 +338,77 @@ impl LokiClient {
                 Err(e) => {
                     if !is_written {
                         if let Err(e) = std::fs::remove_file(file_path) {
-                            log(format!(
-                                "[Warning] Failed to remove empty historic log file {e}"
-                            ));
+                            warn!("Failed to remove empty historic log file {e}");
                         }
                     }
-                    write_to_log_file(format!("[Warning] While fetching logs from Loki {e:?}"))?;
+                    debug!("While fetching logs from Loki {e:?}");
                     return Err(e);
                 }
             };
             is_written = true;
-            for msg in result.iter() {
+            // Only a page of at most `limit` lines is held in memory, the lines are written
+            // through the buffer as they are received.
+            for msg in result {
                 writeln!(log_file, "{}", msg.trim_end())?;
             }
         }
+        log_file.flush()?;
         Ok(())
     }
 }
 
 fn get_epoch_unix_time(since: humantime::Duration) -> SinceTime {
+    get_current_unix_time() - since.as_nanos()
+}
+
+fn get_current_unix_time() -> SinceTime {
     // should be ok for ~584 years since epoch
-    let timestamp = Utc::now()
+    Utc::now()
         .timestamp_nanos_opt()
-        .expect("value can not be represented in a timestamp with nanosecond precision.");
-    timestamp as SinceTime - since.as_nanos()
+        .expect("value can not be represented in a timestamp with nanosecond precision.")
+        as SinceTime
 }
 
-struct LokiPoll<'a> {
-    client: &'a mut LokiClient,
+struct LokiPoll {
+    client: LokiService,
     uri: String,
     endpoint: String,
     since: SinceTime,
+    until: SinceTime,
+    /// End of the time window currently being queried
+    chunk_end: SinceTime,
     query_params: String,
-    next_start_epoch_timestamp: SinceTime,
 }
 
-impl<'a> LokiPoll<'a> {
+impl LokiPoll {
     // poll_next will extract response from Loki service and perform following actions:
     // 1. Get last log epoch timestamp
     // 2. Extract logs from response
+    // The time range is queried in chunks, once a chunk is exhausted the next one is queried
+    // until the end of the time range.
     async fn poll_next(&mut self) -> Result<Option<Vec<String>>, LokiError> {
-        let mut start_time = self.since;
-        if self.next_start_epoch_timestamp != 0 {
-            start_time = self.since;
+        loop {
+            if self.since >= self.until {
+                return Ok(None);
+            }
+            if self.since < self.chunk_end {
+                if let Some(logs) = self.poll_chunk().await? {
+                    return Ok(Some(logs));
+                }
+            }
+            if self.chunk_end >= self.until {
+                return Ok(None);
+            }
+            self.since = self.since.max(self.chunk_end);
+            self.chunk_end = self.until.min(self.chunk_end + QUERY_CHUNK_NANOS);
         }
+    }
+
+    // poll_chunk extracts the next logs of the current chunk, None once it is exhausted
+    async fn poll_chunk(&mut self) -> Result<Option<Vec<String>>, LokiError> {
         let request_str = format!(
-            "{}{}{}&start={}",
-            self.uri, self.endpoint, self.query_params, start_time
+            "{}{}{}&start={}&end={}",
+            self.uri, self.endpoint, self.query_params, self.since, self.chunk_end
         );
 
         // TODO: Test timeouts when Loki service is dropped unexpectedly
@@ -343,7 +452,7 @@ impl<'a> LokiPoll<'a> {
             .collect::<Vec<String>>();
         Ok(Some(logs))
     }
-    fn client(&mut self) -> &mut kube_proxy::LokiClient {
-        &mut self.client.inner_client
+    fn client(&mut self) -> &mut LokiService {
+        &mut self.client
     }
 }
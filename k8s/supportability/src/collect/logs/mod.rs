@@ -1,5 +1,7 @@
 mod k8s_log;
-mod loki;
+pub(crate) mod loki;
+
+pub(crate) use loki::LogTime;
 
 use crate::collect::{
     constants::{
@@ -11,11 +13,13 @@ use crate::collect::{
         common::KUBERNETES_HOST_LABEL_KEY,
     },
     logs::k8s_log::{K8sLoggerClient, K8sLoggerError},
-    utils::log,
+    progress,
 };
 use async_trait::async_trait;
+use futures::StreamExt;
 use k8s_openapi::api::core::v1::Pod;
 use std::{collections::HashSet, iter::Iterator, path::PathBuf};
+use tracing::{info, warn};
 
 /// Error that can occur while interacting with logs module
 #[derive(Debug)]
@@ -78,6 +82,8 @@ pub(crate) struct LogResource {
 pub(crate) struct LogCollection {
     loki_client: Option<loki::LokiClient>,
     k8s_logger_client: K8sLoggerClient,
+    /// Maximum number of resources whose logs are collected concurrently
+    concurrency: usize,
 }
 
 impl LogCollection {
@@ -86,13 +92,21 @@ impl LogCollection {
     /// Kube-API server param 'namespace' --> Defines the namespace of the product
     /// param 'loki_uri' --> Defines the address of loki instance
     /// param 'since'  --> Defines period from which logs needs to collect
+    /// param 'start' --> Defines the time from which Loki logs needs to collect, overrides 'since'
+    /// param 'end' --> Defines the time until which Loki logs needs to collect
     /// param 'timeout' --> Specifies the timeout while interacting with Loki Service
+    /// param 'concurrency' --> Maximum number of resources whose logs are collected concurrently
+    /// param 'proxy' --> Defines the proxy through which Loki is reached
     pub(crate) async fn new_logger(
         kube_config_path: Option<std::path::PathBuf>,
         namespace: String,
         loki_uri: Option<String>,
         since: humantime::Duration,
+        start: Option<LogTime>,
+        end: Option<LogTime>,
         timeout: humantime::Duration,
+        concurrency: usize,
+        proxy: Option<kube_proxy::EgressProxy>,
     ) -> Result<Box<dyn Logger>, LogError> {
         let client_set = ClientSet::new(kube_config_path.clone(), namespace.clone()).await?;
         Ok(Box::new(Self {
@@ -101,13 +115,58 @@ impl LogCollection {
                 kube_config_path,
                 namespace,
                 since,
+                start,
+                end,
                 timeout,
+                proxy,
             )
             .await,
-            k8s_logger_client: K8sLoggerClient::new(client_set),
+            k8s_logger_client: K8sLoggerClient::new(client_set, *since),
+            concurrency,
         }))
     }
 
+    /// Fetch the historical logs of the resource from Loki, if available, and its current logs
+    /// from the Kube-apiserver, returning the errors which occurred.
+    async fn dump_resource_logs(&self, resource: &LogResource, working_dir: &str) -> Vec<LogError> {
+        let mut errors = Vec::new();
+        let service_dir = std::path::Path::new(working_dir)
+            .join("logs")
+            .join(resource.service_type.clone());
+
+        if let Err(error) = create_directory_if_not_exist(service_dir.clone()) {
+            errors.push(LogError::IOError(error));
+            return errors;
+        }
+        if let Some(loki_client) = &self.loki_client {
+            if let Err(error) = loki_client
+                .fetch_and_dump_logs(
+                    resource.label_selector.clone(),
+                    resource.container_name.clone(),
+                    resource.host_name.clone(),
+                    service_dir.clone(),
+                )
+                .await
+            {
+                errors.push(LogError::Loki(error));
+            }
+        }
+
+        if let Err(error) = self
+            .k8s_logger_client
+            .dump_pod_logs(
+                resource.label_selector.as_str(),
+                service_dir,
+                resource.host_name.clone(),
+                &[resource.container_name.as_str()],
+            )
+            .await
+        {
+            errors.push(LogError::K8sLogger(error));
+        }
+        errors
+    }
+
     async fn get_logging_resources(
         &self,
         pods: Vec<Pod>,
@@ -205,56 +264,52 @@ impl LogCollection {
 
 #[async_trait(?Send)]
 impl Logger for LogCollection {
-    // Fetch logs of requested resource and dump into files
+    // Fetch logs of requested resources concurrently and dump into files. The outcome of each
+    // resource is logged in a stable order, once its logs are collected.
     async fn fetch_and_dump_logs(
         &mut self,
         resources: HashSet<LogResource>,
         working_dir: String,
     ) -> Result<(), LogError> {
+        let mut resources = resources.into_iter().collect::<Vec<LogResource>>();
+        resources.sort_by(|a, b| {
+            (&a.service_type, &a.host_name, &a.container_name).cmp(&(
+                &b.service_type,
+                &b.host_name,
+                &b.container_name,
+            ))
+        });
+
+        progress::set_total(progress::LOGS, resources.len() as u64);
         let mut errors = Vec::new();
-        for resource in resources.iter() {
-            log(format!(
-                "\t Collecting logs of service: {}, container: {} of host: {:?}",
-                resource.service_type, resource.container_name, resource.host_name,
-            ));
-            let service_dir = std::path::Path::new(&working_dir.clone())
-                .join("logs")
-                .join(resource.service_type.clone());
-
-            create_directory_if_not_exist(service_dir.clone())?;
-            if let Some(loki_client) = &mut self.loki_client {
-                let _ = loki_client
-                    .fetch_and_dump_logs(
-                        resource.label_selector.clone(),
-                        resource.container_name.clone(),
-                        resource.host_name.clone(),
-                        service_dir.clone(),
-                    )
-                    .await.map_err(|e| {
-                    log(format!(
-                        "\t Failed to collect historical logs of service: {}, container: {} of: host {:?}",
-                        resource.service_type, resource.container_name, resource.host_name,
-                    ));
-                    errors.push(LogError::Loki(e));
-                });
-            }
-
-            let _ = self
-                .k8s_logger_client
-                .dump_pod_logs(
-                    resource.label_selector.as_str(),
-                    service_dir.clone(),
-                    resource.host_name.clone(),
-                    &[resource.container_name.as_str()],
+        let this = &*self;
+        let working_dir = working_dir.as_str();
+        let mut results = futures::stream::iter(resources.iter())
+            .map(|resource| async move {
+                (
+                    resource,
+                    this.dump_resource_logs(resource, working_dir).await,
                 )
-                .await
-                .map_err(|e| {
-                    log(format!(
-                        "\t Failed to collect current logs of service: {}, container: {} of: host {:?}",
-                        resource.service_type, resource.container_name, resource.host_name,
-                    ));
-                    errors.push(LogError::K8sLogger(e));
-                });
+            })
+            .buffered(self.concurrency.max(1));
+        while let Some((resource, resource_errors)) = results.next().await {
+            progress::inc(progress::LOGS);
+            info!(
+                "\t Collected logs of service: {}, container: {} of host: {:?}",
+                resource.service_type, resource.container_name, resource.host_name
+            );
+            for error in resource_errors.iter() {
+                let kind = match error {
+                    LogError::Loki(_) => "historical logs",
+                    LogError::K8sLogger(_) => "current logs",
+                    _ => "logs",
+                };
+                warn!(
+                    "\t Failed to collect {} of service: {}, container: {} of: host {:?}",
+                    kind, resource.service_type, resource.container_name, resource.host_name
+                );
+            }
+            errors.extend(resource_errors);
         }
         if !errors.is_empty() {
             return Err(LogError::MultipleErrors(errors));
@@ -0,0 +1,257 @@
+use crate::collect::constants::ETCD_DUMP_DIR;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::{
+    fs::File,
+    path::{Component, Path},
+};
+
+/// Name of the file which indexes the artifacts at the root of the archive.
+pub(crate) const MANIFEST_FILE: &str = "manifest.json";
+
+/// Name of the file which summarizes the integrity of the content at the root of the archive.
+pub(crate) const INTEGRITY_FILE: &str = "integrity.json";
+
+/// Version of the manifest format, bumped on incompatible changes.
+const MANIFEST_VERSION: u32 = 1;
+
+/// Collection status of an artifact.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum ArtifactStatus {
+    /// The artifact was collected.
+    Ok,
+    /// The collection of the artifact failed, its files may be missing or partial.
+    Failed,
+}
+
+/// Collection failure of an artifact, recorded in the manifest along with its files.
+#[derive(Debug, Clone)]
+pub(crate) struct ArtifactFailure {
+    /// Name of the artifact, as recorded in the artifacts report.
+    pub(crate) name: String,
+    /// Time at which the collection of the artifact ended, in RFC 3339 format.
+    pub(crate) end_time: String,
+    /// Error which occurred while collecting the artifact.
+    pub(crate) error: String,
+}
+
+/// Entry of the manifest, either a file of the archive or a failed collection.
+#[derive(Serialize, Debug)]
+struct ManifestEntry {
+    /// Path of the file or of the artifact directory, relative to the root of the archive.
+    path: String,
+    /// Where the artifact was collected from, eg: `rest-api:/v0/volumes` or `pod-logs:io-engine`.
+    source: String,
+    /// Time at which the file was last written, or the collection failed, in RFC 3339 format.
+    timestamp: Option<String>,
+    /// Size of the file in bytes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size: Option<u64>,
+    /// Checksum of the content of the file, as `sha256:<hex>`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    checksum: Option<String>,
+    status: ArtifactStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Manifest is a machine-readable index of the artifacts of the archive, for the tooling which
+/// analyzes the bundles.
+#[derive(Serialize, Debug)]
+pub(crate) struct Manifest {
+    version: u32,
+    created: String,
+    artifacts: Vec<ManifestEntry>,
+}
+
+impl Manifest {
+    /// Index the files of the given directory, along with the artifacts whose collection failed.
+    pub(crate) fn build(
+        dir_path: &Path,
+        failures: &[ArtifactFailure],
+    ) -> Result<Self, std::io::Error> {
+        let mut files = Vec::new();
+        list_files(dir_path, &mut files)?;
+        files.sort();
+
+        let mut artifacts = Vec::new();
+        for path in files {
+            let relative = relative_path(dir_path, &path);
+            if relative == MANIFEST_FILE || relative == INTEGRITY_FILE {
+                continue;
+            }
+            let metadata = std::fs::metadata(&path)?;
+            artifacts.push(ManifestEntry {
+                source: artifact_source(&relative),
+                timestamp: metadata
+                    .modified()
+                    .ok()
+                    .map(|modified| DateTime::<Utc>::from(modified).to_rfc3339()),
+                size: Some(metadata.len()),
+                checksum: Some(sha256_checksum(&path)?),
+                status: ArtifactStatus::Ok,
+                error: None,
+                path: relative,
+            });
+        }
+        artifacts.extend(failures.iter().map(|failure| {
+            let path = artifact_path(&failure.name);
+            ManifestEntry {
+                source: artifact_source(&path),
+                timestamp: Some(failure.end_time.clone()),
+                size: None,
+                checksum: None,
+                status: ArtifactStatus::Failed,
+                error: Some(failure.error.clone()),
+                path,
+            }
+        }));
+
+        Ok(Self {
+            version: MANIFEST_VERSION,
+            created: Utc::now().to_rfc3339(),
+            artifacts,
+        })
+    }
+
+    /// Write the manifest as json at the root of the given directory.
+    pub(crate) fn dump(&self, dir_path: &Path) -> Result<(), std::io::Error> {
+        let file = File::create(dir_path.join(MANIFEST_FILE))?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+}
+
+/// IntegritySummary pins the manifest, and through its checksums every file of the archive, so
+/// that a bundle which was corrupted or tampered with in transit can be told apart once
+/// extracted. The archive file itself is covered by the `<archive>.sha256` file written next to
+/// it, and by its signature when signed.
+#[derive(Serialize, Debug)]
+pub(crate) struct IntegritySummary {
+    version: u32,
+    created: String,
+    /// Checksum of the manifest, as `sha256:<hex>`.
+    manifest_checksum: String,
+    /// Number of the files indexed by the manifest.
+    files: usize,
+    /// Total size of the files indexed by the manifest, in bytes.
+    total_size: u64,
+    /// Number of the artifacts whose collection failed.
+    failed_artifacts: usize,
+}
+
+impl IntegritySummary {
+    /// Summarize the manifest which was dumped at the root of the given directory.
+    pub(crate) fn build(dir_path: &Path, manifest: &Manifest) -> Result<Self, std::io::Error> {
+        let files = manifest
+            .artifacts
+            .iter()
+            .filter(|artifact| artifact.status == ArtifactStatus::Ok);
+        Ok(Self {
+            version: MANIFEST_VERSION,
+            created: Utc::now().to_rfc3339(),
+            manifest_checksum: sha256_checksum(&dir_path.join(MANIFEST_FILE))?,
+            files: files.clone().count(),
+            total_size: files.filter_map(|artifact| artifact.size).sum(),
+            failed_artifacts: manifest
+                .artifacts
+                .iter()
+                .filter(|artifact| artifact.status == ArtifactStatus::Failed)
+                .count(),
+        })
+    }
+
+    /// Write the summary as json at the root of the given directory.
+    pub(crate) fn dump(&self, dir_path: &Path) -> Result<(), std::io::Error> {
+        let file = File::create(dir_path.join(INTEGRITY_FILE))?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+}
+
+/// Recursively list the files of the directory.
+pub(crate) fn list_files(
+    dir: &Path,
+    files: &mut Vec<std::path::PathBuf>,
+) -> Result<(), std::io::Error> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            list_files(&entry.path(), files)?;
+        } else {
+            files.push(entry.path());
+        }
+    }
+    Ok(())
+}
+
+/// Path of the file relative to the root directory, with `/` separators.
+pub(crate) fn relative_path(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name.to_string_lossy()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Path of the directory holding the files of the artifact of the artifacts report.
+fn artifact_path(name: &str) -> String {
+    match name {
+        "app_pods" => "k8s_resources/app_pods".to_string(),
+        "io_engine_state" | "node_diagnostics" | "core_dumps" | "packet_capture" => {
+            "nodes".to_string()
+        }
+        "etcd_dump" => ETCD_DUMP_DIR.to_string(),
+        name => name.to_string(),
+    }
+}
+
+/// Where the artifact at the given path was collected from, according to the layout of the
+/// archive.
+fn artifact_source(path: &str) -> String {
+    let parts = path.split('/').collect::<Vec<_>>();
+    match parts.as_slice() {
+        ["topology", "volume", ..] => "rest-api:/v0/volumes".to_string(),
+        ["topology", "snapshot", ..] => "rest-api:/v0/volumes/snapshots".to_string(),
+        ["topology", "pool", ..] => "rest-api:/v0/pools".to_string(),
+        ["topology", "node", ..] => "rest-api:/v0/nodes".to_string(),
+        ["topology", ..] => "rest-api".to_string(),
+        ["logs", service, file, ..] if file.ends_with(".log") => {
+            format!("pod-logs:{service}/{}", file.trim_end_matches(".log"))
+        }
+        ["logs", service, pod, ..] => format!("pod-logs:{service}/{pod}"),
+        ["logs", ..] => "pod-logs".to_string(),
+        ["k8s_resources", "app_pods", pod, ..] => format!("kube-apiserver:pod/{pod}"),
+        ["k8s_resources", ..] | ["kustomize", ..] => "kube-apiserver".to_string(),
+        ["helm", release, ..] => format!("kube-apiserver:helm-release/{release}"),
+        ["helm", ..] => "kube-apiserver:helm-release".to_string(),
+        ["nodes", node, "io_engine", ..] => format!("io-engine-grpc:{node}"),
+        ["observability", "metrics_exporter", node, ..] => format!("metrics-exporter:{node}"),
+        ["observability", "callhome", pod, ..] => format!("callhome:{pod}"),
+        ["nodes", node, ..] => format!("diagnostics-pod:{node}"),
+        [dir, ..] if *dir == ETCD_DUMP_DIR => "etcd".to_string(),
+        _ => "support-tool".to_string(),
+    }
+}
+
+/// Checksum of the content of the file, as `sha256:<hex>`.
+fn sha256_checksum(path: &Path) -> Result<String, std::io::Error> {
+    Ok(format!("sha256:{}", sha256_digest(path)?))
+}
+
+/// SHA256 digest of the content of the file, as hex.
+pub(crate) fn sha256_digest(path: &Path) -> Result<String, std::io::Error> {
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<String>())
+}
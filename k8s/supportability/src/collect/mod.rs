@@ -1,12 +1,27 @@
+pub mod anonymize;
 pub mod archive;
+pub mod checkpoint;
+pub mod collectors;
 pub mod common;
 pub mod constants;
 pub mod error;
 pub mod k8s_resources;
 pub mod logs;
+pub mod manifest;
+pub mod multi_cluster;
+pub mod namespace;
 pub mod persistent_store;
+pub mod preview;
+pub mod progress;
+pub mod quick_dump;
+pub mod redact;
+pub mod report;
 pub mod resource_dump;
 pub mod resources;
 pub mod rest_wrapper;
+pub mod retry;
+pub mod size_limits;
 pub mod system_dump;
+pub mod tool_log;
+pub mod upload;
 pub mod utils;
@@ -0,0 +1,372 @@
+use crate::{
+    collect::{
+        archive::{Archive, ArchiveFormat},
+        common,
+        error::Error,
+        manifest::MANIFEST_FILE,
+        progress::ProgressFormat,
+        utils::{confirm, flush_tool_log_file, init_tool_log_file},
+    },
+    operations::{Operations, Resource},
+    SupportArgs,
+};
+use chrono::Utc;
+use serde::Serialize;
+use std::{
+    collections::BTreeSet,
+    ffi::OsString,
+    fs::File,
+    path::{Path, PathBuf},
+    process::{ExitStatus, Stdio},
+};
+use tracing::{info, warn};
+
+/// Env variable holding the kubeconfig of the cluster collected by a child process of the
+/// multi-cluster collection.
+const CLUSTER_KUBECONFIG_ENV: &str = "MAYASTOR_DUMP_CLUSTER_KUBECONFIG";
+/// Env variable holding the directory the child process writes the archive of its cluster to.
+const CLUSTER_OUTPUT_DIR_ENV: &str = "MAYASTOR_DUMP_CLUSTER_OUTPUT_DIR";
+/// Name of the file which summarizes the collection of every cluster in the combined archive.
+const CLUSTERS_SUMMARY_FILE: &str = "clusters_summary.json";
+/// Command line flag of the application pods collection, which is confirmed once for all the
+/// clusters.
+const INCLUDE_APP_PODS_FLAG: &str = "--include-app-pods";
+
+/// Collection of a single cluster, delegated to a child process by the multi-cluster collection.
+pub(crate) struct ClusterDelegation {
+    kube_config_path: PathBuf,
+    output_directory: String,
+}
+
+impl ClusterDelegation {
+    /// Get the delegation of the current process, if it collects a single cluster on behalf of a
+    /// multi-cluster collection.
+    pub(crate) fn from_env() -> Option<Self> {
+        Some(Self {
+            kube_config_path: std::env::var_os(CLUSTER_KUBECONFIG_ENV)?.into(),
+            output_directory: std::env::var(CLUSTER_OUTPUT_DIR_ENV).ok()?,
+        })
+    }
+
+    /// Get the kubeconfig of the delegated cluster.
+    pub(crate) fn kube_config_path(&self) -> PathBuf {
+        self.kube_config_path.clone()
+    }
+
+    /// Override the arguments inherited from the multi-cluster collection: the cluster archive is
+    /// a plain tar.zst which the parent process extracts, and only the combined archive is
+    /// encrypted, signed and uploaded.
+    pub(crate) fn apply(&self, args: &mut SupportArgs) {
+        args.contexts.clear();
+        args.kube_config = None;
+        args.output_directory_path = self.output_directory.clone();
+        args.archive_format = ArchiveFormat::TarZst;
+        args.encrypt_recipients.clear();
+        args.sign_key = None;
+        args.upload = None;
+        args.progress = Some(ProgressFormat::Plain);
+    }
+}
+
+/// Kubeconfig whose current context is the given one, written to a temporary file so that all
+/// the clients of the collection use that context. The file is removed when dropped.
+pub(crate) struct ContextKubeConfig {
+    path: PathBuf,
+}
+
+impl ContextKubeConfig {
+    /// Write the kubeconfig of the given file, or the default one, with the given context as its
+    /// current context.
+    pub(crate) fn write(kube_config_path: Option<&Path>, context: &str) -> Result<Self, Error> {
+        let mut kube_config = match kube_config_path {
+            Some(path) => kube::config::Kubeconfig::read_from(path)?,
+            None => kube::config::Kubeconfig::read()?,
+        };
+        if !kube_config
+            .contexts
+            .iter()
+            .any(|named| named.name == context)
+        {
+            return Err(kube::config::KubeconfigError::FindContext(context.to_string()).into());
+        }
+        kube_config.current_context = Some(context.to_string());
+
+        let path = std::env::temp_dir().join(format!(
+            "mayastor-kubeconfig-{}-{}",
+            std::process::id(),
+            dir_name(context)
+        ));
+        let mut options = File::options();
+        options.write(true).create(true).truncate(true);
+        // The kubeconfig holds the cluster credentials.
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let file = options.open(&path)?;
+        let kube_config_file = Self { path };
+        serde_yaml::to_writer(file, &kube_config)
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::Other, error))?;
+        Ok(kube_config_file)
+    }
+
+    /// Path of the kubeconfig file.
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for ContextKubeConfig {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Outcome of the collection of a cluster.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum ClusterStatus {
+    /// All the information of the cluster was collected.
+    Completed,
+    /// The archive of the cluster was created, but some information is missing.
+    Partial,
+    /// No archive could be created for the cluster.
+    Failed,
+}
+
+/// Summary of the collection of a cluster, in the merged summary of the combined archive.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct ClusterSummary {
+    /// Kubeconfig context of the cluster.
+    context: String,
+    /// Directory of the cluster in the combined archive.
+    directory: String,
+    status: ClusterStatus,
+    start_time: String,
+    end_time: String,
+    /// Number of artifacts of the cluster which were collected.
+    artifacts_ok: usize,
+    /// Number of artifacts of the cluster whose collection failed.
+    artifacts_failed: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Merged summary of the collection of all the clusters.
+#[derive(Serialize, Debug)]
+struct ClustersSummary {
+    clusters: Vec<ClusterSummary>,
+}
+
+/// Collect the bundles of the clusters of all the given contexts in parallel, each by a child
+/// process of this tool, into a combined archive with a directory per cluster and a merged
+/// summary.
+pub(crate) async fn dump_clusters(
+    args: SupportArgs,
+    kube_config_path: Option<PathBuf>,
+    operation: Operations,
+) -> Result<(), Error> {
+    let Operations::Dump(resource) = &operation;
+    if let Resource::Etcd { stdout: true } = resource {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "the etcd dump of several clusters can't be written to stdout",
+        )
+        .into());
+    }
+
+    let dir_path = common::create_and_get_tmp_directory(args.output_directory_path.clone())?;
+    init_tool_log_file(Path::new(&dir_path).join("support_tool_logs.log"))?;
+
+    // The confirmation is asked once for all the clusters, the child processes don't prompt.
+    let mut child_args = std::env::args_os().skip(1).collect::<Vec<_>>();
+    if let Resource::System(system) = resource {
+        if system.include_app_pods
+            && !confirm(
+                "Application pod logs may contain sensitive data, include them in the dump?",
+            )
+        {
+            child_args.retain(|arg| arg != INCLUDE_APP_PODS_FLAG);
+        }
+    }
+
+    let contexts = args.contexts.iter().collect::<BTreeSet<_>>();
+    let clusters = contexts
+        .into_iter()
+        .map(|context| dump_cluster(context, &dir_path, kube_config_path.as_deref(), &child_args));
+    let clusters = futures::future::join_all(clusters).await;
+
+    let mut errors = Vec::new();
+    let summary = ClustersSummary {
+        clusters: clusters
+            .into_iter()
+            .map(|(summary, error)| {
+                errors.extend(error);
+                summary
+            })
+            .collect(),
+    };
+    let summary_file = File::create(Path::new(&dir_path).join(CLUSTERS_SUMMARY_FILE))?;
+    serde_json::to_writer_pretty(summary_file, &summary).map_err(std::io::Error::from)?;
+
+    let mut archive = Archive::new(
+        Some(args.output_directory_path),
+        &args.encrypt_recipients,
+        args.archive_format,
+    )?
+    .with_sign_key(args.sign_key);
+    flush_tool_log_file()?;
+    archive.copy_to_archive(dir_path.clone(), ".".to_string())?;
+    std::fs::remove_dir_all(&dir_path)?;
+    info!("Completed collection of all the clusters");
+
+    if let (Some(upload), Some(archive_path)) = (args.upload, archive.file_path()) {
+        match upload.upload(archive_path).await {
+            Ok(url) => println!("Uploaded the archive file to {url}"),
+            Err(e) => {
+                warn!(
+                    "Failed to upload the archive file {}, error: {e:?}",
+                    archive_path.display()
+                );
+                errors.push(e.into());
+            }
+        }
+    }
+    if !errors.is_empty() {
+        return Err(Error::MultipleErrors(errors));
+    }
+    Ok(())
+}
+
+/// Collect the bundle of the cluster of the given context by a child process, and extract it
+/// into the directory of the cluster.
+async fn dump_cluster(
+    context: &str,
+    dir_path: &str,
+    kube_config_path: Option<&Path>,
+    child_args: &[OsString],
+) -> (ClusterSummary, Option<Error>) {
+    let directory = dir_name(context);
+    let cluster_dir = Path::new(dir_path).join(&directory);
+    let start_time = Utc::now();
+    info!("Collecting information of the cluster of context {context}");
+
+    let result = run_cluster_dump(context, &cluster_dir, kube_config_path, child_args).await;
+    let (status, error) = match result {
+        Ok(exit_status) if exit_status.success() => (ClusterStatus::Completed, None),
+        Ok(exit_status) => (
+            ClusterStatus::Partial,
+            Some(Error::ClusterDumpError(context.to_string(), exit_status)),
+        ),
+        Err(error) => (ClusterStatus::Failed, Some(error)),
+    };
+    match &error {
+        None => info!("Collected information of the cluster of context {context}"),
+        Some(error) => warn!(
+            "Failed to collect information of the cluster of context {context}, error: {error:?}"
+        ),
+    }
+
+    let (artifacts_ok, artifacts_failed) = count_artifacts(&cluster_dir);
+    let summary = ClusterSummary {
+        context: context.to_string(),
+        directory,
+        status,
+        start_time: start_time.to_rfc3339(),
+        end_time: Utc::now().to_rfc3339(),
+        artifacts_ok,
+        artifacts_failed,
+        error: error.as_ref().map(|error| format!("{error:?}")),
+    };
+    (summary, error)
+}
+
+/// Run the child process collecting the cluster of the given context, and extract its archive
+/// into the directory of the cluster.
+async fn run_cluster_dump(
+    context: &str,
+    cluster_dir: &Path,
+    kube_config_path: Option<&Path>,
+    child_args: &[OsString],
+) -> Result<ExitStatus, Error> {
+    std::fs::create_dir_all(cluster_dir)?;
+    let kube_config = ContextKubeConfig::write(kube_config_path, context)?;
+    let status = tokio::process::Command::new(std::env::current_exe()?)
+        .args(child_args)
+        .env(CLUSTER_KUBECONFIG_ENV, kube_config.path())
+        .env(CLUSTER_OUTPUT_DIR_ENV, cluster_dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await?;
+    extract_cluster_archive(cluster_dir)?;
+    Ok(status)
+}
+
+/// Extract the archive written by the child process into the directory of the cluster, and
+/// remove the archive along with the temporary directory the child process may have kept.
+fn extract_cluster_archive(cluster_dir: &Path) -> Result<(), Error> {
+    let entries = std::fs::read_dir(cluster_dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let Some(archive_path) = entries
+        .iter()
+        .find(|path| path.to_string_lossy().ends_with(".tar.zst"))
+    else {
+        // The installations of several namespaces are collected into a directory each.
+        let namespace_dirs = entries
+            .iter()
+            .filter(|path| path.is_dir())
+            .collect::<Vec<_>>();
+        if namespace_dirs.is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("no archive was written to {}", cluster_dir.display()),
+            )
+            .into());
+        }
+        return namespace_dirs
+            .into_iter()
+            .try_for_each(|dir| extract_cluster_archive(dir));
+    };
+    tar::Archive::new(zstd::Decoder::new(File::open(archive_path)?)?).unpack(cluster_dir)?;
+    for path in entries {
+        if path.is_dir() {
+            std::fs::remove_dir_all(path)?;
+        } else {
+            std::fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Count the collected and the failed artifacts listed in the manifest of the cluster bundle.
+fn count_artifacts(cluster_dir: &Path) -> (usize, usize) {
+    let manifest = File::open(cluster_dir.join(MANIFEST_FILE))
+        .ok()
+        .and_then(|file| serde_json::from_reader::<_, serde_json::Value>(file).ok());
+    let artifacts = manifest
+        .as_ref()
+        .and_then(|manifest| manifest["artifacts"].as_array())
+        .cloned()
+        .unwrap_or_default();
+    let failed = artifacts
+        .iter()
+        .filter(|artifact| artifact["status"] == "failed")
+        .count();
+    (artifacts.len() - failed, failed)
+}
+
+/// Name of the directory of the cluster of the given context, as contexts may contain
+/// characters which are not valid in paths, eg: `arn:aws:eks:eu-west-1:..:cluster/prod`.
+fn dir_name(context: &str) -> String {
+    context
+        .chars()
+        .map(
+            |c| match c.is_ascii_alphanumeric() || matches!(c, '-' | '_') {
+                true => c,
+                false => '_',
+            },
+        )
+        .collect()
+}
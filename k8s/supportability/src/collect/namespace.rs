@@ -0,0 +1,54 @@
+use crate::collect::{
+    constants::{CORE_AGENT_SERVICE, DEFAULT_NAMESPACE, HELM_CHART_NAME},
+    k8s_resources::{
+        client::ClientSet, helm_release::chart_release_namespaces,
+        k8s_resource_dump::K8sResourceDumperError,
+    },
+};
+use std::{collections::BTreeSet, path::PathBuf};
+use tracing::{info, warn};
+
+/// Detect the namespaces the product is installed in, falling back to the default namespace
+/// when no installation is found.
+pub(crate) async fn detect_namespaces(kube_config_path: Option<PathBuf>) -> Vec<String> {
+    match installation_namespaces(kube_config_path).await {
+        Ok(namespaces) if !namespaces.is_empty() => {
+            info!(
+                "Detected the installation in namespace(s): {}",
+                namespaces.join(", ")
+            );
+            namespaces
+        }
+        Ok(_) => {
+            warn!("No installation detected, using namespace {DEFAULT_NAMESPACE}");
+            vec![DEFAULT_NAMESPACE.to_string()]
+        }
+        Err(error) => {
+            warn!(
+                "Failed to detect the namespace of the installation, using namespace \
+                {DEFAULT_NAMESPACE}, error: {error:?}"
+            );
+            vec![DEFAULT_NAMESPACE.to_string()]
+        }
+    }
+}
+
+/// The namespaces the product is installed in, from the deployments of the core agent
+/// or, should there be none, e.g. while the control-plane is scaled down, from the deployed
+/// helm releases of the chart. Several installations may share the cluster, hence all the
+/// namespaces are returned, sorted.
+async fn installation_namespaces(
+    kube_config_path: Option<PathBuf>,
+) -> Result<Vec<String>, K8sResourceDumperError> {
+    let client = ClientSet::new(kube_config_path, DEFAULT_NAMESPACE.to_string()).await?;
+    let mut namespaces = client
+        .get_all_namespaces_deployments(&format!("app={CORE_AGENT_SERVICE}"))
+        .await?
+        .into_iter()
+        .filter_map(|deployment| deployment.metadata.namespace)
+        .collect::<BTreeSet<_>>();
+    if namespaces.is_empty() {
+        namespaces = chart_release_namespaces(&client, HELM_CHART_NAME).await?;
+    }
+    Ok(namespaces.into_iter().collect())
+}
@@ -1,5 +1,6 @@
 use crate::collect::{
-    constants::ETCD_PAGED_LIMIT, k8s_resources::client::ClientSet, persistent_store::EtcdError,
+    constants::ETCD_DUMP_DIR, k8s_resources::client::ClientSet, persistent_store::EtcdError,
+    utils::create_buffered_file,
 };
 use pstor::{etcd, StoreKv, API_VERSION};
 use std::{io::Write, path::PathBuf};
@@ -9,15 +10,17 @@ use std::{io::Write, path::PathBuf};
 pub(crate) struct EtcdStore {
     etcd: etcd::Etcd,
     key_prefix: String,
+    page_size: i64,
 }
 
 impl EtcdStore {
     /// Create a new etcd store client using the given endpoint or the kubeconfig path.
     /// The provided namespace will be used to search for etcd service, if the
-    /// etcd point is not provided
+    /// etcd point is not provided. The keys are fetched in pages of the given size.
     pub(crate) async fn new(
         kube_config_path: Option<std::path::PathBuf>,
         etcd_endpoint: Option<String>,
+        page_size: i64,
         namespace: String,
     ) -> Result<Self, EtcdError> {
         let client_set = ClientSet::new(kube_config_path.clone(), namespace.clone()).await?;
@@ -42,23 +45,39 @@ impl EtcdStore {
         };
         let etcd = etcd::Etcd::new(endpoint.as_str()).await?;
 
-        Ok(Self { etcd, key_prefix })
+        Ok(Self {
+            etcd,
+            key_prefix,
+            page_size,
+        })
     }
 
     /// dump all the data from etcd in the selected namespace into a file in
-    /// the given working directory.
+    /// the etcd directory of the given working directory.
     pub(crate) async fn dump(
         &mut self,
         working_dir: PathBuf,
         stdout: bool,
+    ) -> Result<(), EtcdError> {
+        self.dump_filtered(working_dir, stdout, |_| true).await
+    }
+
+    /// dump the data from etcd in the selected namespace whose keys match the given filter,
+    /// in the same way as `dump`.
+    pub(crate) async fn dump_filtered(
+        &mut self,
+        working_dir: PathBuf,
+        stdout: bool,
+        filter: impl Fn(&str) -> bool,
     ) -> Result<(), EtcdError> {
         let mut prefix = &self.key_prefix;
 
         let mut etcd_dump_file = match stdout {
             false => {
-                let file_name = "etcd_dump".to_string();
-                let file_path = working_dir.join(file_name);
-                Some(std::fs::File::create(file_path)?)
+                let dir_path = working_dir.join(ETCD_DUMP_DIR);
+                std::fs::create_dir_all(&dir_path)?;
+                let file_path = dir_path.join("etcd_dump");
+                Some(create_buffered_file(file_path)?)
             }
             true => None,
         };
@@ -66,13 +85,13 @@ impl EtcdStore {
         let mut first = true;
         let mut dump;
         loop {
-            dump = self.etcd.get_values_paged(prefix, ETCD_PAGED_LIMIT).await?;
+            dump = self.etcd.get_values_paged(prefix, self.page_size).await?;
             if !first && dump.get(0).is_some() {
                 dump.remove(0);
             }
             first = false;
 
-            for val in dump.iter_mut() {
+            for val in dump.iter_mut().filter(|val| filter(val.0.as_str())) {
                 // unwrap or default because we dont want the code to panic in case of errors. need
                 // to write all data to file even if parsing of one value fails.
                 let pretty_json = serde_json::to_string_pretty(&val.1).unwrap_or_default();
@@ -98,4 +117,10 @@ impl EtcdStore {
 
         Ok(())
     }
+
+    /// Check that etcd answers a read of the product's keys.
+    pub(crate) async fn check_health(&mut self) -> Result<(), EtcdError> {
+        self.etcd.get_values_paged(&self.key_prefix, 1).await?;
+        Ok(())
+    }
 }
@@ -0,0 +1,197 @@
+use crate::collect::{
+    checkpoint::CHECKPOINT_FILE,
+    constants::ETCD_DUMP_DIR,
+    manifest::{list_files, relative_path, INTEGRITY_FILE, MANIFEST_FILE},
+    redact::{collected_files, REDACTED},
+    report::ARTIFACTS_REPORT_FILE,
+};
+use bytesize::ByteSize;
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::Write,
+    path::Path,
+};
+
+/// Extension appended to the name of the preview file of the archive file
+pub(crate) const PREVIEW_EXTENSION: &str = "preview.txt";
+
+/// Short classification of the content of a file of the bundle, for the review of the bundle
+/// before it is uploaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum ContentClass {
+    /// Logs of the pods, of the nodes or of the tool.
+    Logs,
+    /// Kubernetes resources and helm releases.
+    Config,
+    /// State of the control-plane and the data-plane, eg: the topologies and the etcd keys.
+    State,
+    /// Diagnostics of the nodes, eg: core dumps and packet traces.
+    Diagnostics,
+    /// Reports and indexes written by the tool.
+    Report,
+    /// Any of the above, from which sensitive values were redacted.
+    SecretsRedacted,
+}
+
+impl ContentClass {
+    /// Name of the class, as listed in the preview.
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Logs => "logs",
+            Self::Config => "config",
+            Self::State => "state",
+            Self::Diagnostics => "diagnostics",
+            Self::Report => "report",
+            Self::SecretsRedacted => "secrets-redacted",
+        }
+    }
+
+    /// Classify the file at the given path, relative to the root of the bundle, according to the
+    /// layout of the bundle. The clusters of a multi-cluster bundle have a directory of their
+    /// own, so the layout is matched from the first known directory of the path.
+    fn of_path(path: &str) -> Self {
+        let parts = path.split('/').collect::<Vec<_>>();
+        let file_name = parts.last().copied().unwrap_or_default();
+        if file_name.ends_with(".log") {
+            return Self::Logs;
+        }
+        if [
+            MANIFEST_FILE,
+            INTEGRITY_FILE,
+            ARTIFACTS_REPORT_FILE,
+            CHECKPOINT_FILE,
+        ]
+        .contains(&file_name)
+            || file_name.ends_with("summary.json")
+        {
+            return Self::Report;
+        }
+        for (index, part) in parts.iter().enumerate() {
+            match *part {
+                "logs" => return Self::Logs,
+                "k8s_resources" | "helm" | "kustomize" => return Self::Config,
+                "topology" => return Self::State,
+                "nodes" => {
+                    return match parts.get(index + 2) {
+                        Some(&"io_engine") => Self::State,
+                        _ => Self::Diagnostics,
+                    }
+                }
+                dir if dir == ETCD_DUMP_DIR => return Self::State,
+                _ => {}
+            }
+        }
+        Self::Report
+    }
+}
+
+/// File of the bundle, as listed in the preview.
+struct PreviewEntry {
+    path: String,
+    size: u64,
+    class: ContentClass,
+}
+
+/// BundlePreview lists the files of the bundle with their size and a short classification of
+/// their content, so that the bundle can be reviewed quickly before it is uploaded.
+pub(crate) struct BundlePreview {
+    entries: Vec<PreviewEntry>,
+    redacted: bool,
+    anonymized: bool,
+}
+
+impl BundlePreview {
+    /// List the files of the given directory. When the content was redacted, the files holding
+    /// redacted values are classified as such.
+    pub(crate) fn build(
+        dir_path: &Path,
+        redacted: bool,
+        anonymized: bool,
+    ) -> Result<Self, std::io::Error> {
+        let mut files = Vec::new();
+        list_files(dir_path, &mut files)?;
+        files.sort();
+        // Only the files of the redacted formats may hold redacted values.
+        let redactable_files = match redacted {
+            true => collected_files(dir_path)?.into_iter().collect(),
+            false => HashSet::new(),
+        };
+
+        let mut entries = Vec::with_capacity(files.len());
+        for path in files {
+            let relative = relative_path(dir_path, &path);
+            let mut class = ContentClass::of_path(&relative);
+            if redactable_files.contains(&path) && contains_redacted_values(&path) {
+                class = ContentClass::SecretsRedacted;
+            }
+            entries.push(PreviewEntry {
+                size: std::fs::metadata(&path)?.len(),
+                path: relative,
+                class,
+            });
+        }
+        Ok(Self {
+            entries,
+            redacted,
+            anonymized,
+        })
+    }
+
+    /// Render the preview as text: a summary per class, followed by the listing of the files.
+    pub(crate) fn render(&self) -> String {
+        let on_off = |enabled: bool| match enabled {
+            true => "on",
+            false => "off",
+        };
+        let total_size = self.entries.iter().map(|entry| entry.size).sum::<u64>();
+        let mut classes = BTreeMap::<ContentClass, (usize, u64)>::new();
+        for entry in &self.entries {
+            let (files, size) = classes.entry(entry.class).or_default();
+            *files += 1;
+            *size += entry.size;
+        }
+
+        let mut text = String::new();
+        let _ = writeln!(
+            text,
+            "Bundle preview: {} files, {} before compression (redaction: {}, anonymization: {})",
+            self.entries.len(),
+            ByteSize(total_size).to_string_as(true),
+            on_off(self.redacted),
+            on_off(self.anonymized)
+        );
+        for (class, (files, size)) in &classes {
+            let _ = writeln!(
+                text,
+                "  {:<17} {files} files, {}",
+                class.name(),
+                ByteSize(*size).to_string_as(true)
+            );
+        }
+        let _ = writeln!(text);
+        let _ = writeln!(text, "{:>12}  {:<17} PATH", "SIZE", "CLASS");
+        for entry in &self.entries {
+            let _ = writeln!(
+                text,
+                "{:>12}  {:<17} {}",
+                ByteSize(entry.size).to_string_as(true),
+                entry.class.name(),
+                entry.path
+            );
+        }
+        text
+    }
+
+    /// Write the preview next to the archive file, as `<archive>.preview.txt`.
+    pub(crate) fn dump(&self, archive_path: &Path) -> Result<(), std::io::Error> {
+        std::fs::write(
+            format!("{}.{PREVIEW_EXTENSION}", archive_path.display()),
+            self.render(),
+        )
+    }
+}
+
+/// Whether the file holds values which were redacted.
+fn contains_redacted_values(path: &Path) -> bool {
+    std::fs::read_to_string(path).map_or(false, |content| content.contains(REDACTED))
+}
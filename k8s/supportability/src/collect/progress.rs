@@ -0,0 +1,259 @@
+use crate::collect::common;
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    io::IsTerminal,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Collector of the topology information of the resources.
+pub(crate) const TOPOLOGY: &str = "topology";
+/// Collector of the logs of the services.
+pub(crate) const LOGS: &str = "logs";
+/// Collector of the kubernetes resources.
+pub(crate) const K8S_RESOURCES: &str = "k8s resources";
+/// Collector of the application pods.
+pub(crate) const APP_PODS: &str = "app pods";
+/// Collector of the state of the data-plane, from the io-engines.
+pub(crate) const IO_ENGINE_STATE: &str = "io-engine state";
+/// Collector of the state of the observability components.
+pub(crate) const OBSERVABILITY: &str = "observability";
+/// Collector of the storage diagnostics of the nodes.
+pub(crate) const NODE_DIAGNOSTICS: &str = "node diagnostics";
+/// Collector of the core dumps of the nodes.
+pub(crate) const CORE_DUMPS: &str = "core dumps";
+/// Collector of the packet traces of the NVMe-oF ports of the nodes.
+pub(crate) const PACKET_CAPTURE: &str = "packet capture";
+/// Collector of the etcd keys.
+pub(crate) const ETCD: &str = "etcd";
+
+/// PROGRESS reports the progress of the collectors, once initialised.
+static PROGRESS: OnceCell<Progress> = OnceCell::new();
+
+/// Format of the progress of the collection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ProgressFormat {
+    /// Interactive progress bars per collector.
+    Bars,
+    /// A stream of json progress events on stdout, one per line.
+    Json,
+    /// Plain log lines.
+    Plain,
+}
+
+impl ProgressFormat {
+    /// Progress bars when running interactively, otherwise plain log lines.
+    pub(crate) fn detect() -> Self {
+        let interactive = match common::streams_archive() {
+            // The bars are drawn on stderr.
+            true => std::io::stderr().is_terminal(),
+            false => std::io::stdout().is_terminal(),
+        };
+        match interactive {
+            true => Self::Bars,
+            false => Self::Plain,
+        }
+    }
+}
+
+/// Status of a collector in a progress event.
+#[derive(Serialize, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum CollectorStatus {
+    Running,
+    Completed,
+    Failed,
+}
+
+/// Progress event of a collector, streamed as json.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct ProgressEvent<'a> {
+    collector: &'a str,
+    status: CollectorStatus,
+    completed: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    total: Option<u64>,
+    elapsed_seconds: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    eta_seconds: Option<f64>,
+}
+
+/// Progress of a single collector.
+struct CollectorProgress {
+    bar: Option<ProgressBar>,
+    start_time: Instant,
+    completed: u64,
+    total: Option<u64>,
+}
+
+impl CollectorProgress {
+    /// Estimated time until the collector completes, once the total is known.
+    fn eta(&self) -> Option<Duration> {
+        let total = self.total?;
+        if self.completed == 0 {
+            return None;
+        }
+        let per_item = self.start_time.elapsed() / self.completed as u32;
+        Some(per_item * total.saturating_sub(self.completed) as u32)
+    }
+}
+
+/// Progress of all the collectors.
+struct Progress {
+    format: ProgressFormat,
+    bars: MultiProgress,
+    collectors: Mutex<HashMap<String, CollectorProgress>>,
+}
+
+impl Progress {
+    /// Update the progress of the collector and report it.
+    fn update(&self, collector: &str, f: impl FnOnce(&mut CollectorProgress)) {
+        let mut collectors = match self.collectors.lock() {
+            Ok(collectors) => collectors,
+            Err(error) => error.into_inner(),
+        };
+        let format = self.format;
+        let progress =
+            collectors
+                .entry(collector.to_string())
+                .or_insert_with(|| CollectorProgress {
+                    bar: (format == ProgressFormat::Bars)
+                        .then(|| self.bars.add(new_bar(collector))),
+                    start_time: Instant::now(),
+                    completed: 0,
+                    total: None,
+                });
+        f(progress);
+    }
+
+    /// Stream the progress of the collector as a json event.
+    fn emit(&self, collector: &str, status: CollectorStatus, progress: &CollectorProgress) {
+        if self.format != ProgressFormat::Json {
+            return;
+        }
+        let event = ProgressEvent {
+            collector,
+            status,
+            completed: progress.completed,
+            total: progress.total,
+            elapsed_seconds: progress.start_time.elapsed().as_secs_f64(),
+            eta_seconds: progress.eta().map(|eta| eta.as_secs_f64()),
+        };
+        if let Ok(event) = serde_json::to_string(&event) {
+            println!("{event}");
+        }
+    }
+}
+
+/// Create the progress bar of a collector, which is a spinner until its total is known.
+fn new_bar(collector: &str) -> ProgressBar {
+    let bar = ProgressBar::new_spinner();
+    if let Ok(style) = ProgressStyle::with_template("{spinner} {prefix:<14} [{elapsed}] {msg}") {
+        bar.set_style(style);
+    }
+    bar.set_prefix(collector.to_string());
+    bar.enable_steady_tick(Duration::from_millis(100));
+    bar
+}
+
+/// Initialise the progress reporting in the given format.
+pub(crate) fn init_progress(format: ProgressFormat) {
+    let _ = PROGRESS.set(Progress {
+        format,
+        bars: MultiProgress::new(),
+        collectors: Mutex::new(HashMap::new()),
+    });
+}
+
+/// Whether the progress is streamed as json on stdout, which nothing else may be printed to.
+pub(crate) fn streams_json() -> bool {
+    PROGRESS
+        .get()
+        .map_or(false, |progress| progress.format == ProgressFormat::Json)
+}
+
+/// Report that the collector started.
+pub(crate) fn start(collector: &str) {
+    if let Some(progress) = PROGRESS.get() {
+        progress.update(collector, |state| {
+            state.start_time = Instant::now();
+            progress.emit(collector, CollectorStatus::Running, state);
+        });
+    }
+}
+
+/// Set the number of items the collector has to collect, which enables its ETA.
+pub(crate) fn set_total(collector: &str, total: u64) {
+    if let Some(progress) = PROGRESS.get() {
+        progress.update(collector, |state| {
+            state.total = Some(total);
+            if let Some(bar) = &state.bar {
+                if let Ok(style) = ProgressStyle::with_template(
+                    "{spinner} {prefix:<14} [{elapsed}] {bar:30} {pos}/{len} ETA {eta} {msg}",
+                ) {
+                    bar.set_style(style);
+                }
+                bar.set_length(total);
+            }
+            progress.emit(collector, CollectorStatus::Running, state);
+        });
+    }
+}
+
+/// Report that the collector collected one more item.
+pub(crate) fn inc(collector: &str) {
+    if let Some(progress) = PROGRESS.get() {
+        progress.update(collector, |state| {
+            state.completed += 1;
+            if let Some(bar) = &state.bar {
+                bar.inc(1);
+            }
+            progress.emit(collector, CollectorStatus::Running, state);
+        });
+    }
+}
+
+/// Report that the collector completed, successfully or not.
+pub(crate) fn finish(collector: &str, succeeded: bool) {
+    if let Some(progress) = PROGRESS.get() {
+        progress.update(collector, |state| {
+            let status = match succeeded {
+                true => CollectorStatus::Completed,
+                false => CollectorStatus::Failed,
+            };
+            if let Some(bar) = &state.bar {
+                bar.finish_with_message(match succeeded {
+                    true => "done",
+                    false => "failed",
+                });
+            }
+            progress.emit(collector, status, state);
+        });
+    }
+}
+
+/// Print a log line without garbling the progress display. Returns false if no progress is
+/// reported, in which case the caller prints it.
+pub(crate) fn println(content: &str) -> bool {
+    match PROGRESS
+        .get()
+        .map(|progress| (progress.format, &progress.bars))
+    {
+        Some((ProgressFormat::Bars, bars)) => bars.println(content).is_ok(),
+        // Stdout is reserved to the stream of json events.
+        Some((ProgressFormat::Json, _)) => {
+            eprintln!("{content}");
+            true
+        }
+        // Stdout is reserved to the archive.
+        _ if common::streams_archive() => {
+            eprintln!("{content}");
+            true
+        }
+        _ => false,
+    }
+}
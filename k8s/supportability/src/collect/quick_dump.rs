@@ -0,0 +1,372 @@
+use crate::collect::{
+    archive, common,
+    common::DumpConfig,
+    constants::{ETCD_DUMP_DIR, QUICK_DUMP_LOG_SINCE, QUICK_DUMP_MAX_BYTES, QUICK_DUMP_TIMEOUT},
+    error::Error,
+    k8s_resources::client::ClientSet,
+    persistent_store::etcd::EtcdStore,
+    resources::utils,
+    rest_wrapper::RestClient,
+    utils::{flush_tool_log_file, init_tool_log_file},
+};
+use k8s_openapi::api::core::v1::Pod;
+use openapi::models::{Volume, VolumeStatus};
+use serde_json::json;
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    process,
+};
+use tracing::{debug, error, info, warn};
+
+/// QuickDumper collects a minimal set of information for a first-response triage: the versions
+/// of the components, a summary of the resources, the recent logs of the unhealthy pods and the
+/// etcd keys of the unhealthy volumes. The collection is bounded in time and in size, whatever
+/// has been collected when either of the limits is reached is archived.
+pub(crate) struct QuickDumper {
+    rest_client: RestClient,
+    k8s_client: ClientSet,
+    etcd_dumper: Option<EtcdStore>,
+    archive: archive::Archive,
+    dir_path: String,
+    namespace: String,
+    /// Number of bytes which can still be written before the size limit is reached
+    remaining_bytes: u64,
+}
+
+impl QuickDumper {
+    /// Instantiate new quick dumper by creating the archive and the temporary directory in the
+    /// given directory, and the clients of the services it collects information from.
+    pub(crate) async fn get_or_panic_quick_dumper(config: DumpConfig) -> Self {
+        let new_dir = match common::create_and_get_tmp_directory(config.output_directory.clone()) {
+            Ok(val) => val,
+            Err(e) => {
+                eprintln!("Failed to create temporary directory to dump information, error: {e:?}");
+                process::exit(1);
+            }
+        };
+
+        // Create and initialise the support tool log file
+        if let Err(e) =
+            init_tool_log_file(PathBuf::from(format!("{new_dir}/support_tool_logs.log")))
+        {
+            eprintln!("Encountered error while creating log file: {e} ");
+            process::exit(1);
+        }
+
+        let archive = match archive::Archive::new(
+            Some(config.output_directory),
+            &config.encrypt_recipients,
+            config.archive_format,
+        ) {
+            Ok(val) => val
+                .with_redactor(config.redactor)
+                .with_anonymizer(config.anonymizer)
+                .with_sign_key(config.sign_key),
+            Err(err) => {
+                error!("Failed to create archive, {err:?}");
+                process::exit(1);
+            }
+        };
+
+        let k8s_client =
+            match ClientSet::new(config.kube_config_path.clone(), config.namespace.clone()).await {
+                Ok(val) => val,
+                Err(err) => {
+                    error!("Failed to instantiate kubernetes client, error: {err:?}");
+                    process::exit(1);
+                }
+            };
+
+        let etcd_dumper = match EtcdStore::new(
+            config.kube_config_path,
+            config.etcd_uri,
+            config.etcd_page_size,
+            config.namespace.clone(),
+        )
+        .await
+        {
+            Ok(val) => Some(val),
+            Err(err) => {
+                warn!("Failed to initialize etcd client, error: {err:?}");
+                None
+            }
+        };
+
+        QuickDumper {
+            rest_client: config.rest_client,
+            k8s_client,
+            etcd_dumper,
+            archive,
+            dir_path: new_dir,
+            namespace: config.namespace,
+            remaining_bytes: QUICK_DUMP_MAX_BYTES,
+        }
+    }
+
+    /// Collect the triage information within the deadline of the quick dump.
+    pub(crate) async fn dump_quick(&mut self) -> Result<(), Error> {
+        match tokio::time::timeout(QUICK_DUMP_TIMEOUT, self.collect()).await {
+            Ok(result) => result,
+            Err(_) => {
+                info!(
+                    "Quick dump deadline of {} exceeded, archiving the information collected so far",
+                    humantime::format_duration(QUICK_DUMP_TIMEOUT)
+                );
+                Err(Error::DeadlineExceeded(QUICK_DUMP_TIMEOUT))
+            }
+        }
+    }
+
+    /// Collect the triage information, from the smallest and most valuable to the largest, so
+    /// that the limits are mostly hit by the logs.
+    async fn collect(&mut self) -> Result<(), Error> {
+        let mut errors = Vec::new();
+
+        info!("Collecting component versions...");
+        let pods = match self.k8s_client.get_pods("", "").await {
+            Ok(pods) => pods,
+            Err(e) => {
+                warn!("Failed to list the pods, error: {e:?}");
+                errors.push(Error::K8sResourceDumperError(e.into()));
+                Vec::new()
+            }
+        };
+        let versions = pods.iter().map(pod_summary).collect::<Vec<_>>();
+        if let Err(e) = self.write_json("versions.json", &versions) {
+            errors.push(e);
+        }
+
+        info!("Collecting resource summaries...");
+        let volumes = match self.list_volumes().await {
+            Ok(volumes) => volumes,
+            Err(e) => {
+                warn!("Failed to list the volumes, error: {e:?}");
+                errors.push(e);
+                Vec::new()
+            }
+        };
+        if let Err(e) = self.dump_summary(&volumes).await {
+            errors.push(e);
+        }
+
+        info!("Collecting etcd keys of unhealthy volumes...");
+        let unhealthy_volumes = volumes
+            .iter()
+            .filter(|volume| !matches!(volume.state.status, VolumeStatus::Online))
+            .map(|volume| volume.spec.uuid.to_string())
+            .collect::<Vec<_>>();
+        if let Err(e) = self.dump_etcd(&unhealthy_volumes).await {
+            warn!("Failed to collect etcd keys of unhealthy volumes, error: {e:?}");
+            errors.push(e);
+        }
+
+        info!("Collecting recent logs of unhealthy pods...");
+        for pod in pods.iter().filter(|pod| !is_pod_healthy(pod)) {
+            if let Err(e) = self.dump_pod_logs(pod).await {
+                errors.push(e);
+            }
+        }
+        info!("Completed collection of quick dump");
+
+        if !errors.is_empty() {
+            return Err(Error::MultipleErrors(errors));
+        }
+        Ok(())
+    }
+
+    /// Write the summary of the disk pools, volumes and nodes.
+    async fn dump_summary(&mut self, volumes: &[Volume]) -> Result<(), Error> {
+        let pools = self
+            .k8s_client
+            .list_pools(None, None)
+            .await
+            .map_err(|e| Error::K8sResourceDumperError(e.into()))?
+            .into_iter()
+            .map(|pool| {
+                json!({
+                    "name": pool.metadata.name,
+                    "spec": pool.spec,
+                    "status": pool.status,
+                })
+            })
+            .collect::<Vec<_>>();
+        let nodes = self
+            .rest_client
+            .nodes_api()
+            .get_nodes(None)
+            .await
+            .map_err(|e| Error::ResourceError(e.into()))?
+            .into_body()
+            .into_iter()
+            .map(|node| {
+                json!({
+                    "id": node.id,
+                    "status": node.state.map(|state| state.status),
+                })
+            })
+            .collect::<Vec<_>>();
+        let volumes = volumes
+            .iter()
+            .map(|volume| {
+                json!({
+                    "uuid": volume.spec.uuid,
+                    "status": volume.state.status,
+                    "replicas": volume.spec.num_replicas,
+                    "targetNode": volume.state.target.as_ref().map(|target| &target.node),
+                })
+            })
+            .collect::<Vec<_>>();
+        self.write_json(
+            "resource_summary.json",
+            &json!({
+                "diskPools": pools,
+                "nodes": nodes,
+                "volumes": volumes,
+            }),
+        )
+    }
+
+    /// Dump the etcd keys which refer to any of the given volumes.
+    async fn dump_etcd(&mut self, volumes: &[String]) -> Result<(), Error> {
+        if volumes.is_empty() {
+            return Ok(());
+        }
+        let Some(etcd_dumper) = self.etcd_dumper.as_mut() else {
+            return Ok(());
+        };
+        etcd_dumper
+            .dump_filtered(PathBuf::from(&self.dir_path), false, |key| {
+                volumes.iter().any(|volume| key.contains(volume.as_str()))
+            })
+            .await?;
+        // The etcd keys of the unhealthy volumes are small, only account for them.
+        let etcd_dump = Path::new(&self.dir_path).join(ETCD_DUMP_DIR);
+        let size = std::fs::read_dir(etcd_dump)?
+            .filter_map(|entry| entry.ok()?.metadata().ok())
+            .map(|metadata| metadata.len())
+            .sum::<u64>();
+        self.remaining_bytes = self.remaining_bytes.saturating_sub(size);
+        Ok(())
+    }
+
+    /// Dump the recent logs of all the containers of the pod.
+    async fn dump_pod_logs(&mut self, pod: &Pod) -> Result<(), Error> {
+        let pod_name = pod.metadata.name.clone().unwrap_or_default();
+        let containers = pod
+            .spec
+            .as_ref()
+            .map(|spec| spec.containers.iter().map(|c| c.name.clone()).collect())
+            .unwrap_or_default();
+        for container in containers {
+            if self.remaining_bytes == 0 {
+                info!("Quick dump size limit reached, skipping the remaining logs");
+                return Ok(());
+            }
+            let logs = self
+                .k8s_client
+                .get_container_logs(&self.namespace, &pod_name, &container, QUICK_DUMP_LOG_SINCE)
+                .await
+                .map_err(|e| Error::K8sResourceDumperError(e.into()))?;
+            // Keep the most recent lines when the logs exceed the size limit.
+            let logs = logs.as_bytes();
+            let start = logs.len().saturating_sub(self.remaining_bytes as usize);
+            self.write_file(&format!("logs/{pod_name}/{container}.log"), &logs[start..])?;
+        }
+        Ok(())
+    }
+
+    /// Write the value as pretty json to the given file of the temporary directory.
+    fn write_json(&mut self, file_name: &str, value: &impl serde::Serialize) -> Result<(), Error> {
+        let content =
+            serde_json::to_vec_pretty(value).map_err(|e| Error::ResourceError(e.into()))?;
+        let end = content.len().min(self.remaining_bytes as usize);
+        self.write_file(file_name, &content[..end])
+    }
+
+    /// Write the content to the given file of the temporary directory, accounting for its size.
+    fn write_file(&mut self, file_name: &str, content: &[u8]) -> Result<(), Error> {
+        let path = Path::new(&self.dir_path).join(file_name);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::File::create(path)?.write_all(content)?;
+        self.remaining_bytes = self.remaining_bytes.saturating_sub(content.len() as u64);
+        Ok(())
+    }
+
+    async fn list_volumes(&self) -> Result<Vec<Volume>, Error> {
+        let mut volumes: Vec<Volume> = Vec::new();
+        let mut next_token: Option<isize> = Some(0);
+        loop {
+            let volumes_api_resp = self
+                .rest_client
+                .volumes_api()
+                .get_volumes(utils::MAX_RESOURCE_ENTRIES, None, next_token)
+                .await
+                .map_err(|e| Error::ResourceError(e.into()))?
+                .into_body();
+            volumes.extend(volumes_api_resp.entries);
+            if volumes_api_resp.next_token.is_none() {
+                break;
+            }
+            next_token = volumes_api_resp.next_token;
+        }
+        Ok(volumes)
+    }
+
+    /// Copies the temporary directory content into archive and delete temporary directory
+    pub(crate) fn fill_archive_and_delete_tmp(&mut self) -> Result<(), Error> {
+        debug!("Will copy temporary directory content to archive");
+        flush_tool_log_file()?;
+
+        self.archive
+            .copy_to_archive(self.dir_path.clone(), ".".to_string())
+            .map_err(|e| {
+                warn!("Failed to move content into archive file, error: {e}");
+                e
+            })?;
+
+        std::fs::remove_dir_all(&self.dir_path)?;
+        Ok(())
+    }
+
+    /// Path of the archive file, once its content has been successfully written
+    pub(crate) fn archive_path(&self) -> Option<PathBuf> {
+        self.archive.file_path().map(Into::into)
+    }
+}
+
+/// Summary of the pod, with the images of its containers as the versions of the components.
+fn pod_summary(pod: &Pod) -> serde_json::Value {
+    let images = pod
+        .spec
+        .as_ref()
+        .map(|spec| {
+            spec.containers
+                .iter()
+                .map(|container| (container.name.clone(), container.image.clone()))
+                .collect::<std::collections::BTreeMap<_, _>>()
+        })
+        .unwrap_or_default();
+    json!({
+        "name": pod.metadata.name,
+        "node": pod.spec.as_ref().and_then(|spec| spec.node_name.as_ref()),
+        "phase": pod.status.as_ref().and_then(|status| status.phase.as_ref()),
+        "healthy": is_pod_healthy(pod),
+        "images": images,
+    })
+}
+
+/// Whether the pod has the Ready condition, or completed successfully.
+fn is_pod_healthy(pod: &Pod) -> bool {
+    let Some(status) = pod.status.as_ref() else {
+        return false;
+    };
+    status.phase.as_deref() == Some("Succeeded")
+        || status.conditions.as_ref().map_or(false, |conditions| {
+            conditions
+                .iter()
+                .any(|condition| condition.type_ == "Ready" && condition.status == "True")
+        })
+}
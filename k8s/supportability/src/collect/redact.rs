@@ -0,0 +1,191 @@
+use regex::{Captures, Regex};
+use std::{
+    borrow::Cow,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Replacement of the redacted values
+pub(crate) const REDACTED: &str = "[REDACTED]";
+
+/// Name of the capture group which restricts the redaction of a match to the captured value
+const VALUE_GROUP: &str = "value";
+
+/// Default redaction rules, applied to all the collected files
+const DEFAULT_RULES: &[&str] = &[
+    // Bearer tokens, eg: in Authorization headers
+    r"(?i)\bbearer\s+(?P<value>[A-Za-z0-9\-._~+/]+=*)",
+    // Credentials given as key/value pairs, eg: `password: foo` or `"token":"foo"`
+    r#"(?i)\b(password|passwd|secret|token|api[_-]?key|access[_-]?key)"?\s*[:=]\s*"?(?P<value>[^\s",}]+)"#,
+    // Private keys
+    r"-----BEGIN [A-Z ]*PRIVATE KEY-----(?P<value>[\s\S]*?)-----END [A-Z ]*PRIVATE KEY-----",
+];
+
+/// Extensions of the collected files which are redacted
+const REDACTED_EXTENSIONS: &[&str] = &["yaml", "yml", "json", "log", "txt"];
+
+/// Redactor scrubs sensitive data from the collected files before they are archived: the data
+/// of kubernetes Secrets, bearer tokens and the values matching the redaction rules
+#[derive(Debug, Clone)]
+pub(crate) struct Redactor {
+    rules: Vec<Regex>,
+}
+
+impl Redactor {
+    /// Creates a redactor with the default rules, extended with the given ones. The matches of
+    /// a rule are redacted entirely, unless the rule has a capture group named `value`, in
+    /// which case only the captured value is redacted
+    pub(crate) fn new(rules: Vec<Regex>) -> Self {
+        let mut default_rules = DEFAULT_RULES
+            .iter()
+            .map(|rule| Regex::new(rule).expect("default redaction rules should be valid"))
+            .collect::<Vec<_>>();
+        default_rules.extend(rules);
+        Self {
+            rules: default_rules,
+        }
+    }
+
+    /// Redacts all the collected files in the directory and its subdirectories, in place
+    pub(crate) fn redact_dir(&self, dir_path: &Path) -> Result<(), std::io::Error> {
+        for path in collected_files(dir_path)? {
+            let content = match fs::read_to_string(&path) {
+                Ok(content) => content,
+                // Skip the files which aren't text, they can't be redacted
+                Err(error) if error.kind() == std::io::ErrorKind::InvalidData => continue,
+                Err(error) => return Err(error),
+            };
+            let redacted = self.redact(&content, path.extension().and_then(|e| e.to_str()));
+            if let Cow::Owned(redacted) = redacted {
+                fs::write(&path, redacted)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Redacts the content of a file with the given extension
+    fn redact<'a>(&self, content: &'a str, extension: Option<&str>) -> Cow<'a, str> {
+        let mut content = match extension {
+            Some("yaml" | "yml") => redact_yaml_secrets(content),
+            Some("json") => redact_json_secrets(content),
+            _ => None,
+        }
+        .map_or(Cow::Borrowed(content), Cow::Owned);
+
+        for rule in &self.rules {
+            if let Cow::Owned(redacted) = rule.replace_all(&content, redact_captures) {
+                content = Cow::Owned(redacted);
+            }
+        }
+        content
+    }
+}
+
+/// Redacts the captured value of the match, or the whole match if there is no value group
+fn redact_captures(captures: &Captures) -> String {
+    let (Some(whole), Some(value)) = (captures.get(0), captures.name(VALUE_GROUP)) else {
+        return REDACTED.to_string();
+    };
+    let start = value.start() - whole.start();
+    let end = value.end() - whole.start();
+    let matched = whole.as_str();
+    format!("{}{REDACTED}{}", &matched[..start], &matched[end..])
+}
+
+/// Redacts the data of the kubernetes Secrets in the yaml documents, if there are any
+fn redact_yaml_secrets(content: &str) -> Option<String> {
+    if !content.contains("Secret") {
+        return None;
+    }
+    let mut documents = Vec::new();
+    let mut redacted = false;
+    for document in serde_yaml::Deserializer::from_str(content) {
+        let mut value = <serde_yaml::Value as serde::Deserialize>::deserialize(document).ok()?;
+        redacted |= redact_yaml_value(&mut value);
+        documents.push(serde_yaml::to_string(&value).ok()?);
+    }
+    redacted.then(|| documents.join("---\n"))
+}
+
+/// Redacts the data of the kubernetes Secrets within the yaml value
+fn redact_yaml_value(value: &mut serde_yaml::Value) -> bool {
+    match value {
+        serde_yaml::Value::Mapping(mapping) => {
+            let mut redacted = false;
+            if mapping.get("kind").and_then(|kind| kind.as_str()) == Some("Secret") {
+                for field in ["data", "stringData"] {
+                    if let Some(serde_yaml::Value::Mapping(data)) = mapping.get_mut(field) {
+                        data.values_mut()
+                            .for_each(|v| *v = serde_yaml::Value::from(REDACTED));
+                        redacted = true;
+                    }
+                }
+            }
+            mapping
+                .values_mut()
+                .fold(redacted, |redacted, v| redact_yaml_value(v) || redacted)
+        }
+        serde_yaml::Value::Sequence(sequence) => sequence
+            .iter_mut()
+            .fold(false, |redacted, v| redact_yaml_value(v) || redacted),
+        _ => false,
+    }
+}
+
+/// Redacts the data of the kubernetes Secrets in the json document, if there are any
+fn redact_json_secrets(content: &str) -> Option<String> {
+    if !content.contains("Secret") {
+        return None;
+    }
+    let mut value = serde_json::from_str::<serde_json::Value>(content).ok()?;
+    match redact_json_value(&mut value) {
+        true => serde_json::to_string_pretty(&value).ok(),
+        false => None,
+    }
+}
+
+/// Redacts the data of the kubernetes Secrets within the json value
+fn redact_json_value(value: &mut serde_json::Value) -> bool {
+    match value {
+        serde_json::Value::Object(object) => {
+            let mut redacted = false;
+            if object.get("kind").and_then(|kind| kind.as_str()) == Some("Secret") {
+                for field in ["data", "stringData"] {
+                    if let Some(serde_json::Value::Object(data)) = object.get_mut(field) {
+                        data.values_mut()
+                            .for_each(|v| *v = serde_json::Value::from(REDACTED));
+                        redacted = true;
+                    }
+                }
+            }
+            object
+                .values_mut()
+                .fold(redacted, |redacted, v| redact_json_value(v) || redacted)
+        }
+        serde_json::Value::Array(array) => array
+            .iter_mut()
+            .fold(false, |redacted, v| redact_json_value(v) || redacted),
+        _ => false,
+    }
+}
+
+/// Lists the files with a redacted extension in the directory and its subdirectories
+pub(crate) fn collected_files(dir_path: &Path) -> Result<Vec<PathBuf>, std::io::Error> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir_path.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path
+                .extension()
+                .and_then(|e| e.to_str())
+                .map_or(false, |e| REDACTED_EXTENSIONS.contains(&e))
+            {
+                files.push(path);
+            }
+        }
+    }
+    Ok(files)
+}
@@ -0,0 +1,112 @@
+use crate::collect::{manifest::ArtifactFailure, size_limits::Truncation};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::{fs::File, path::Path, time::Duration};
+use tracing::debug;
+
+/// Name of the file which holds the artifacts report in the dump.
+pub(crate) const ARTIFACTS_REPORT_FILE: &str = "artifacts_report.json";
+
+/// Timing information of a single collected artifact.
+#[derive(Serialize, Debug)]
+pub(crate) struct ArtifactRecord {
+    /// Name of the collected artifact.
+    name: String,
+    /// Time at which the collection of the artifact started, in RFC 3339 format.
+    start_time: String,
+    /// Time at which the collection of the artifact ended, in RFC 3339 format.
+    end_time: String,
+    /// Time taken to collect the artifact.
+    duration: String,
+    /// Whether the artifact was collected without errors.
+    succeeded: bool,
+    /// Errors which occurred while collecting the artifact.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Overall status of the collection.
+#[derive(Serialize, Debug, Default, Clone, Copy)]
+pub(crate) enum ReportStatus {
+    /// All the artifacts were collected.
+    #[default]
+    #[serde(rename = "completed")]
+    Completed,
+    /// The collection was aborted by the overall timeout, the report only contains the artifacts
+    /// which were collected until then.
+    #[serde(rename = "deadline exceeded")]
+    DeadlineExceeded,
+}
+
+/// ArtifactReport keeps track of the time spent collecting every artifact of the dump.
+#[derive(Serialize, Debug, Default)]
+pub(crate) struct ArtifactReport {
+    status: ReportStatus,
+    artifacts: Vec<ArtifactRecord>,
+    /// Log files which were truncated to fit the size limits, only their newest lines are kept.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    truncations: Vec<Truncation>,
+}
+
+impl ArtifactReport {
+    /// Set the overall status of the collection.
+    pub(crate) fn set_status(&mut self, status: ReportStatus) {
+        self.status = status;
+    }
+
+    /// Record the log files which were truncated to fit the size limits.
+    pub(crate) fn record_truncations(&mut self, truncations: Vec<Truncation>) {
+        self.truncations.extend(truncations);
+    }
+
+    /// Record an artifact whose collection started at `start_time` and ended now, along with
+    /// the errors which occurred, if any.
+    pub(crate) fn record(&mut self, name: &str, start_time: DateTime<Utc>, error: Option<String>) {
+        self.record_span(name, start_time, Utc::now(), error);
+    }
+
+    /// Record an artifact whose collection started at `start_time` and ended at `end_time`.
+    pub(crate) fn record_span(
+        &mut self,
+        name: &str,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+        error: Option<String>,
+    ) {
+        let succeeded = error.is_none();
+        let elapsed = (end_time - start_time).to_std().unwrap_or_default();
+        let duration =
+            humantime::format_duration(Duration::from_millis(elapsed.as_millis() as u64))
+                .to_string();
+        debug!("Collection of {name} took {duration}, succeeded: {succeeded}");
+        self.artifacts.push(ArtifactRecord {
+            name: name.to_string(),
+            start_time: start_time.to_rfc3339(),
+            end_time: end_time.to_rfc3339(),
+            duration,
+            succeeded,
+            error,
+        });
+    }
+
+    /// Get the artifacts whose collection failed, to be recorded in the manifest.
+    pub(crate) fn failures(&self) -> Vec<ArtifactFailure> {
+        self.artifacts
+            .iter()
+            .filter_map(|artifact| {
+                artifact.error.as_ref().map(|error| ArtifactFailure {
+                    name: artifact.name.clone(),
+                    end_time: artifact.end_time.clone(),
+                    error: error.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Write the report as json into the given directory.
+    pub(crate) fn dump(&self, dir_path: &str) -> Result<(), std::io::Error> {
+        let file = File::create(Path::new(dir_path).join(ARTIFACTS_REPORT_FILE))?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+}
@@ -6,10 +6,11 @@ use crate::{
         persistent_store::{etcd::EtcdStore, EtcdError},
         utils::{init_no_log_file, init_tool_log_file},
     },
-    log, OutputFormat,
+    OutputFormat,
 };
 
 use std::{path::PathBuf, process};
+use tracing::{debug, error, info, warn};
 
 #[cfg(debug_assertions)]
 use crate::collect::{
@@ -19,7 +20,7 @@ use crate::collect::{
     logs::LogCollection,
     logs::{LogResource, Logger},
     resources::traits::Topologer,
-    utils::{flush_tool_log_file, write_to_log_file},
+    utils::flush_tool_log_file,
 };
 #[cfg(debug_assertions)]
 use futures::future;
@@ -38,6 +39,10 @@ pub(crate) struct ResourceDumper {
     k8s_resource_dumper: K8sResourceDumperClient,
     etcd_dumper: Option<EtcdStore>,
     output_format: OutputFormat,
+    /// Whether the data plane logs of all the resources of the topology are collected, as
+    /// opposed to only the ones of the unhealthy resources
+    #[cfg(debug_assertions)]
+    dependency_logs: bool,
 }
 
 impl ResourceDumper {
@@ -53,7 +58,7 @@ impl ResourceDumper {
                     match common::create_and_get_tmp_directory(config.output_directory.clone()) {
                         Ok(val) => val,
                         Err(e) => {
-                            println!(
+                            eprintln!(
                             "Failed to create temporary directory to dump information, error: {e:?}"
                         );
                             process::exit(1);
@@ -64,7 +69,7 @@ impl ResourceDumper {
                 if let Err(e) =
                     init_tool_log_file(PathBuf::from(format!("{new_dir}/support_tool_logs.log")))
                 {
-                    println!("Encountered error while creating log file: {e} ");
+                    eprintln!("Encountered error while creating log file: {e} ");
                     process::exit(1);
                 }
 
@@ -76,10 +81,17 @@ impl ResourceDumper {
             }
         };
 
-        let archive = match archive::Archive::new(output_directory) {
-            Ok(val) => val,
+        let archive = match archive::Archive::new(
+            output_directory,
+            &config.encrypt_recipients,
+            config.archive_format,
+        ) {
+            Ok(val) => val
+                .with_redactor(config.redactor)
+                .with_anonymizer(config.anonymizer)
+                .with_sign_key(config.sign_key),
             Err(err) => {
-                log(format!("Failed to create archive, {err:?}"));
+                error!("Failed to create archive, {err:?}");
                 process::exit(1);
             }
         };
@@ -90,15 +102,17 @@ impl ResourceDumper {
             config.namespace.clone(),
             config.loki_uri,
             config.since,
+            config.log_start,
+            config.log_end,
             config.timeout,
+            config.concurrency,
+            config.proxy.clone(),
         )
         .await
         {
             Ok(val) => val,
             Err(err) => {
-                log(format!(
-                    "Failed to initialize logging service, error: {err:?}"
-                ));
+                error!("Failed to initialize logging service, error: {err:?}");
                 process::exit(1);
             }
         };
@@ -107,14 +121,13 @@ impl ResourceDumper {
         let k8s_resource_dumper = match K8sResourceDumperClient::new(
             config.kube_config_path.clone(),
             config.namespace.clone(),
+            *config.since,
         )
         .await
         {
             Ok(val) => val,
             Err(err) => {
-                log(format!(
-                    "Failed to instantiate K8s resource dumper, error: {err:?}"
-                ));
+                error!("Failed to instantiate K8s resource dumper, error: {err:?}");
                 process::exit(1);
             }
         };
@@ -122,13 +135,14 @@ impl ResourceDumper {
         let etcd_dumper = match EtcdStore::new(
             config.kube_config_path,
             config.etcd_uri,
+            config.etcd_page_size,
             config.namespace,
         )
         .await
         {
             Ok(val) => Some(val),
             Err(err) => {
-                log(format!("Failed to initialize etcd client, error: {err:?}"));
+                warn!("Failed to initialize etcd client, error: {err:?}");
                 None
             }
         };
@@ -144,9 +158,19 @@ impl ResourceDumper {
             k8s_resource_dumper,
             etcd_dumper,
             output_format: config.output_format,
+            #[cfg(debug_assertions)]
+            dependency_logs: false,
         }
     }
 
+    #[cfg(debug_assertions)]
+    /// Collect the data plane logs of all the resources of the topology, eg: of all the nodes
+    /// hosting the target and the replicas of a volume, rather than only the unhealthy ones
+    pub(crate) fn with_dependency_logs(mut self) -> Self {
+        self.dependency_logs = true;
+        self
+    }
+
     #[cfg(debug_assertions)]
     /// Dumps information associated to given resource(s)
     pub(crate) async fn dump_info(&mut self, folder_path: String) -> Result<(), Error> {
@@ -154,31 +178,29 @@ impl ResourceDumper {
         let mut resources = match self.logger.get_control_plane_logging_services().await {
             Ok(list) => list,
             Err(e) => {
-                log(format!(
-                    "Failed to fetch control plane services, error: {e:?}"
-                ));
+                warn!("Failed to fetch control plane services, error: {e:?}");
                 errors.push(Error::LogCollectionError(e));
                 std::collections::HashSet::new()
             }
         };
         let mut k8s_resources: Option<Vec<String>> = None;
 
-        log("Collecting topology information of resource(s)...".to_string());
+        info!("Collecting topology information of resource(s)...");
         if let Some(topologer) = self.topologer.as_ref() {
             let _igonre = topologer
                 .dump_topology_info(format!("{}/{}", self.dir_path.clone(), folder_path))
                 .map_err(|e| {
-                    log(format!(
-                        "Failed to collect topology information, error: {e:?}"
-                    ));
+                    warn!("Failed to collect topology information, error: {e:?}");
                     errors.push(Error::ResourceError(e));
                 });
 
-            // Fetch dataplane resources associated to Unhealthy resources
-            // TODO: Check with team whether we have to collect data from all associated
-            //       (or) only from offline associated resources?
-            let unhealthy_resources = topologer.get_unhealthy_resource_info();
-            unhealthy_resources.into_iter().for_each(|resource| {
+            // Fetch dataplane resources associated to Unhealthy resources, or to all the
+            // resources when collecting the dependencies of a resource
+            let related_resources = match self.dependency_logs {
+                true => topologer.get_all_resource_info(),
+                false => topologer.get_unhealthy_resource_info(),
+            };
+            related_resources.into_iter().for_each(|resource| {
                 resources.insert(LogResource {
                     container_name: resource.get_container_name(),
                     label_selector: resource.get_label_selector().as_string(','),
@@ -188,33 +210,31 @@ impl ResourceDumper {
             });
             k8s_resources = Some(topologer.get_k8s_resource_names());
         }
-        log("Completed collection of topology information".to_string());
+        info!("Completed collection of topology information");
 
-        let _ = write_to_log_file(format!(
-            "Collecting logs from following services: {resources:#?}"
-        ));
-        log("Collecting logs...".to_string());
+        debug!("Collecting logs from following services: {resources:#?}");
+        info!("Collecting logs...");
         let _ = self
             .logger
             .fetch_and_dump_logs(resources, self.dir_path.clone())
             .await
             .map_err(|e| errors.push(Error::LogCollectionError(e)));
-        log("Completed collection of logs".to_string());
+        info!("Completed collection of logs");
 
         // Collect mayastor & kubernetes associated resources
-        log("Collecting Kubernetes resources specific to mayastor service".to_string());
+        info!("Collecting Kubernetes resources specific to mayastor service");
         let _ = self
             .k8s_resource_dumper
             .dump_k8s_resources(self.dir_path.clone(), k8s_resources)
             .await
             .map_err(|e| errors.push(Error::K8sResourceDumperError(e)));
-        log("Completed collection of Kubernetes resource specific information".to_string());
+        info!("Completed collection of Kubernetes resource specific information");
 
         let mut path: PathBuf = std::path::PathBuf::new();
         path.push(&self.dir_path.clone());
 
         // Collect ETCD dump specific to mayastor
-        log("Collecting mayastor specific information from Etcd...".to_string());
+        info!("Collecting mayastor specific information from Etcd...");
         let _ = future::try_join_all(
             self.etcd_dumper
                 .as_mut()
@@ -222,27 +242,21 @@ impl ResourceDumper {
         )
         .await
         .map_err(|e| {
-            log(format!(
-                "Failed to collect etcd dump information, error: {e:?}"
-            ));
+            warn!("Failed to collect etcd dump information, error: {e:?}");
             errors.push(Error::EtcdDumpError(e));
         });
-        log("Completed collection of mayastor specific resources from Etcd service".to_string());
+        info!("Completed collection of mayastor specific resources from Etcd service");
 
         let _ = self
             .archive
             .copy_to_archive(self.dir_path.clone(), ".".to_string())
             .map_err(|e| {
-                log(format!(
-                    "Failed to move content into archive file, error: {e}"
-                ));
+                warn!("Failed to move content into archive file, error: {e}");
                 errors.push(Error::ArchiveError(e));
             });
 
         let _ = self.delete_temporary_directory().map_err(|e| {
-            log(format!(
-                "Failed to delete temporary directory, error: {e:?}"
-            ));
+            warn!("Failed to delete temporary directory, error: {e:?}");
         });
 
         if !errors.is_empty() {
@@ -263,37 +277,36 @@ impl ResourceDumper {
             .dump(path, matches!(self.output_format, OutputFormat::Stdout))
             .await
             .map_err(|e| {
-                log(format!(
-                    "Failed to collect etcd dump information, error: {e:?}"
-                ));
+                warn!("Failed to collect etcd dump information, error: {e:?}");
                 e
             })?;
-        log("Completed collection of etcd dump information".to_string());
+        info!("Completed collection of etcd dump information");
 
         if matches!(self.output_format, OutputFormat::Tar) {
             self.archive
                 .copy_to_archive(self.dir_path.clone(), ".".to_string())
                 .map_err(|e| {
-                    log(format!(
-                        "Failed to move content into archive file, error: {e}"
-                    ));
+                    warn!("Failed to move content into archive file, error: {e}");
                     e
                 })?;
 
             let _ = self.delete_temporary_directory().map_err(|e| {
-                log(format!(
-                    "Failed to delete temporary directory, error: {e:?}"
-                ));
+                warn!("Failed to delete temporary directory, error: {e:?}");
             });
         }
         Ok(())
     }
 
+    /// Path of the archive file, once its content has been successfully written
+    pub(crate) fn archive_path(&self) -> Option<PathBuf> {
+        self.archive.file_path().map(Into::into)
+    }
+
     #[cfg(debug_assertions)]
     /// Copies the temporary directory content into archive and delete temporary directory
     pub fn fill_archive_and_delete_tmp(&mut self) -> Result<(), Error> {
         // Log which is visible in archive system log file
-        let _ = write_to_log_file("Will copy temporary directory content to archive".to_string());
+        debug!("Will copy temporary directory content to archive");
         // Flush log file before copying contents
         flush_tool_log_file()?;
 
@@ -301,16 +314,12 @@ impl ResourceDumper {
         self.archive
             .copy_to_archive(self.dir_path.clone(), ".".to_string())
             .map_err(|e| {
-                log(format!(
-                    "Failed to move content into archive file, error: {e}"
-                ));
+                warn!("Failed to move content into archive file, error: {e}");
                 e
             })?;
 
         self.delete_temporary_directory().map_err(|e| {
-            log(format!(
-                "Failed to delete temporary directory, error: {e:?}"
-            ));
+            warn!("Failed to delete temporary directory, error: {e:?}");
             e
         })?;
         Ok(())
@@ -1,3 +1,5 @@
+use crate::collect::retry::RequestError;
+
 /// ResourceError is a wrapper around possible errors that can occur while interacting
 /// interacting or processing results obtained from REST service
 #[derive(Debug)]
@@ -52,6 +54,19 @@ impl From<openapi::clients::tower::Error<openapi::models::RestJsonError>> for Re
     }
 }
 
+impl From<RequestError<openapi::clients::tower::Error<openapi::models::RestJsonError>>>
+    for ResourceError
+{
+    fn from(
+        e: RequestError<openapi::clients::tower::Error<openapi::models::RestJsonError>>,
+    ) -> ResourceError {
+        match e {
+            RequestError::Failed(e) => ResourceError::RestJsonError(e),
+            RequestError::TimedOut(_) => ResourceError::CustomError(e.to_string()),
+        }
+    }
+}
+
 impl From<serde_json::Error> for ResourceError {
     fn from(e: serde_json::Error) -> ResourceError {
         ResourceError::JSONError(e)
@@ -3,6 +3,7 @@ pub(crate) use traits::Resourcer;
 
 pub mod error;
 pub mod node;
+pub mod placement;
 pub mod pool;
 pub mod replica;
 pub mod snapshot;
@@ -1,8 +1,10 @@
-use crate::{
-    collect::{
-        logs::create_directory_if_not_exist, resources, resources::traits, rest_wrapper::RestClient,
-    },
-    log,
+use crate::collect::{
+    logs::create_directory_if_not_exist,
+    resources,
+    resources::traits,
+    rest_wrapper::RestClient,
+    retry::{with_retries, RequestError},
+    utils::write_json_file,
 };
 use async_trait::async_trait;
 use openapi::models::{BlockDevice, Node};
@@ -10,11 +12,10 @@ use resources::ResourceError;
 use serde::{Deserialize, Serialize};
 use std::{
     collections::HashSet,
-    fs::File,
-    io::Write,
     iter::FromIterator,
     path::{Path, PathBuf},
 };
+use tracing::info;
 use traits::{
     ResourceInformation, Resourcer, Topologer, MAYASTOR_DAEMONSET_LABEL, RESOURCE_TO_CONTAINER_NAME,
 };
@@ -78,11 +79,7 @@ impl Topologer for NodeTopology {
     fn dump_topology_info(&self, dir_path: String) -> Result<(), ResourceError> {
         create_directory_if_not_exist(PathBuf::from(dir_path.clone()))?;
         let file_path = Path::new(&dir_path).join(format!("node-{}-topology.json", self.node.id));
-        let mut topo_file = File::create(file_path)?;
-        let topology_as_pretty = serde_json::to_string_pretty(self)?;
-        topo_file.write_all(topology_as_pretty.as_bytes())?;
-        topo_file.flush()?;
-        Ok(())
+        write_json_file(file_path, self)
     }
 
     fn get_unhealthy_resource_info(&self) -> HashSet<ResourceInformation> {
@@ -140,38 +137,34 @@ impl NodeClientWrapper {
 
     // TODO: Add pagination support when REST service supports it
     async fn list_nodes(&self) -> Result<Vec<Node>, ResourceError> {
-        let nodes = self
-            .rest_client
-            .nodes_api()
-            .get_nodes(None)
+        let nodes = with_retries(|| self.rest_client.nodes_api().get_nodes(None))
             .await?
             .into_body();
         Ok(nodes)
     }
 
     async fn get_node(&self, id: String) -> Result<Node, ResourceError> {
-        let node = self
-            .rest_client
-            .nodes_api()
-            .get_node(&id)
+        let node = with_retries(|| self.rest_client.nodes_api().get_node(&id))
             .await?
             .into_body();
         Ok(node)
     }
 
     async fn list_node_block_devices(&self, id: String) -> Result<Vec<BlockDevice>, ResourceError> {
-        let devices = match self
-            .rest_client
-            .block_devices_api()
-            .get_node_block_devices(&id, Some(true))
-            .await
+        let devices = match with_retries(|| {
+            self.rest_client
+                .block_devices_api()
+                .get_node_block_devices(&id, Some(true))
+        })
+        .await
         {
             Ok(response) => response.into_body(),
-            Err(err) => {
+            Err(RequestError::Failed(err)) => {
                 let _is_not_found =
                     ResourceError::RestJsonError(err).not_found_rest_json_error()?;
                 Vec::new()
             }
+            Err(err) => return Err(err.into()),
         };
         Ok(devices)
     }
@@ -215,7 +208,7 @@ impl Resourcer for NodeClientWrapper {
             nodes_topology.push(node_topology);
         }
         if nodes_topology.is_empty() {
-            log("No Node resources, Are daemonset pods in Running State?!!".to_string());
+            info!("No Node resources, Are daemonset pods in Running State?!!");
             return Err(ResourceError::CustomError("No Node resources".to_string()));
         }
         Ok(Box::new(nodes_topology))
@@ -0,0 +1,214 @@
+use crate::collect::{
+    logs::create_directory_if_not_exist,
+    resources::{snapshot::VolumeSnapshotTopology, volume::VolumeTopology, ResourceError},
+    utils::create_buffered_file,
+    utils::write_json_file,
+};
+use openapi::models::{ChildState, NexusState, PoolStatus, ReplicaState, VolumeStatus};
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+/// Name of the human readable placement report, in the topology directory.
+const REPORT_TEXT_FILE: &str = "topology.txt";
+
+/// Name of the machine readable placement report, in the topology directory.
+const REPORT_JSON_FILE: &str = "topology.json";
+
+/// Placement of all the volumes of the cluster: volume --> target --> replicas --> pools --> nodes,
+/// along with the snapshots of each volume.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PlacementReport {
+    volumes: Vec<VolumePlacement>,
+    /// Snapshots whose source volume isn't listed, eg: it was deleted.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    orphaned_snapshots: Vec<SnapshotPlacement>,
+}
+
+/// Placement of a volume.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct VolumePlacement {
+    uuid: String,
+    status: VolumeStatus,
+    size: u64,
+    num_replicas: u8,
+    target: Option<TargetPlacement>,
+    replicas: Vec<ReplicaPlacement>,
+    snapshots: Vec<SnapshotPlacement>,
+}
+
+/// Placement of the target of a volume.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TargetPlacement {
+    uuid: String,
+    node: String,
+    state: NexusState,
+}
+
+/// Placement of a replica of a volume.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ReplicaPlacement {
+    uuid: String,
+    state: ReplicaState,
+    /// State of the replica as a child of the target, if it's one of its children.
+    child_state: Option<ChildState>,
+    pool: String,
+    pool_status: Option<PoolStatus>,
+    node: String,
+}
+
+/// A snapshot of a volume.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SnapshotPlacement {
+    uuid: String,
+    source_volume: String,
+}
+
+impl PlacementReport {
+    /// Build the placement report from the collected volume and snapshot topologies.
+    pub(crate) fn new(volumes: &[VolumeTopology], snapshots: &[VolumeSnapshotTopology]) -> Self {
+        let mut snapshots_by_volume = BTreeMap::<String, Vec<SnapshotPlacement>>::new();
+        for topology in snapshots {
+            let spec = &topology.snapshot().definition.spec;
+            snapshots_by_volume
+                .entry(spec.source_volume.to_string())
+                .or_default()
+                .push(SnapshotPlacement {
+                    uuid: spec.uuid.to_string(),
+                    source_volume: spec.source_volume.to_string(),
+                });
+        }
+
+        let mut volumes = volumes
+            .iter()
+            .map(|topology| {
+                let volume = topology.volume();
+                let uuid = volume.spec.uuid.to_string();
+                let target = topology.target();
+                let replicas = topology
+                    .replicas_topology()
+                    .iter()
+                    .map(|replica_topology| {
+                        let replica = replica_topology.replica();
+                        let replica_uuid = replica.uuid.to_string();
+                        let child_state = target.and_then(|target| {
+                            target
+                                .children
+                                .iter()
+                                .find(|child| child.uri.contains(&replica_uuid))
+                                .map(|child| child.state)
+                        });
+                        ReplicaPlacement {
+                            uuid: replica_uuid,
+                            state: replica.state,
+                            child_state,
+                            pool: replica.pool.clone(),
+                            pool_status: replica_topology
+                                .pool_topology()
+                                .pool()
+                                .state
+                                .as_ref()
+                                .map(|state| state.status),
+                            node: replica.node.clone(),
+                        }
+                    })
+                    .collect();
+                VolumePlacement {
+                    status: volume.state.status,
+                    size: volume.spec.size,
+                    num_replicas: volume.spec.num_replicas,
+                    target: target.map(|target| TargetPlacement {
+                        uuid: target.uuid.to_string(),
+                        node: target.node.clone(),
+                        state: target.state,
+                    }),
+                    replicas,
+                    snapshots: snapshots_by_volume.remove(&uuid).unwrap_or_default(),
+                    uuid,
+                }
+            })
+            .collect::<Vec<_>>();
+        volumes.sort_by(|a, b| a.uuid.cmp(&b.uuid));
+
+        Self {
+            volumes,
+            orphaned_snapshots: snapshots_by_volume.into_values().flatten().collect(),
+        }
+    }
+
+    /// Render the report as an indented tree, one volume after the other.
+    fn to_text(&self) -> String {
+        let mut text = String::new();
+        for volume in &self.volumes {
+            let _ = writeln!(
+                text,
+                "volume {} ({:?}, {}, {} replica(s))",
+                volume.uuid,
+                volume.status,
+                bytesize::ByteSize::b(volume.size),
+                volume.num_replicas
+            );
+            match &volume.target {
+                Some(target) => {
+                    let _ = writeln!(
+                        text,
+                        "  target nexus {} ({:?}) on node {}",
+                        target.uuid, target.state, target.node
+                    );
+                }
+                None => {
+                    let _ = writeln!(text, "  target: none (unpublished)");
+                }
+            }
+            for replica in &volume.replicas {
+                let child = match replica.child_state {
+                    Some(state) => format!("child {state:?}"),
+                    None => "not a child".to_string(),
+                };
+                let pool_status = match replica.pool_status {
+                    Some(status) => format!("{status:?}"),
+                    None => "Unknown".to_string(),
+                };
+                let _ = writeln!(
+                    text,
+                    "  replica {} ({:?}, {child}) -> pool {} ({pool_status}) -> node {}",
+                    replica.uuid, replica.state, replica.pool, replica.node
+                );
+            }
+            for snapshot in &volume.snapshots {
+                let _ = writeln!(text, "  snapshot {}", snapshot.uuid);
+            }
+            text.push('\n');
+        }
+        if !self.orphaned_snapshots.is_empty() {
+            let _ = writeln!(text, "snapshots of unlisted volumes");
+            for snapshot in &self.orphaned_snapshots {
+                let _ = writeln!(
+                    text,
+                    "  snapshot {} of volume {}",
+                    snapshot.uuid, snapshot.source_volume
+                );
+            }
+        }
+        text
+    }
+
+    /// Write the report as text and as json into the given topology directory.
+    pub(crate) fn dump(&self, dir_path: &str) -> Result<(), ResourceError> {
+        create_directory_if_not_exist(PathBuf::from(dir_path))?;
+        let mut text_file = create_buffered_file(Path::new(dir_path).join(REPORT_TEXT_FILE))?;
+        text_file.write_all(self.to_text().as_bytes())?;
+        text_file.flush()?;
+
+        write_json_file(Path::new(dir_path).join(REPORT_JSON_FILE), self)
+    }
+}
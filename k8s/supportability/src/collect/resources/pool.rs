@@ -1,8 +1,6 @@
-use crate::{
-    collect::{
-        logs::create_directory_if_not_exist, resources, resources::traits, rest_wrapper::RestClient,
-    },
-    log,
+use crate::collect::{
+    logs::create_directory_if_not_exist, resources, resources::traits, rest_wrapper::RestClient,
+    retry::with_retries, utils::write_json_file,
 };
 use async_trait::async_trait;
 use openapi::models::{BlockDevice, Node, Pool};
@@ -10,11 +8,10 @@ use resources::ResourceError;
 use serde::{Deserialize, Serialize};
 use std::{
     collections::HashSet,
-    fs::File,
-    io::Write,
     iter::FromIterator,
     path::{Path, PathBuf},
 };
+use tracing::warn;
 use traits::{
     ResourceInformation, Resourcer, Topologer, MAYASTOR_DAEMONSET_LABEL, RESOURCE_TO_CONTAINER_NAME,
 };
@@ -35,6 +32,11 @@ pub(crate) fn is_pool_not_online(pool_status: openapi::models::PoolStatus) -> bo
 }
 
 impl PoolTopology {
+    /// Get the pool.
+    pub(crate) fn pool(&self) -> &Pool {
+        &self.pool
+    }
+
     // fetch mayastor daemon information where mayastor pools are hosted
     fn get_pool_info(
         &self,
@@ -72,11 +74,7 @@ impl Topologer for PoolTopology {
     fn dump_topology_info(&self, dir_path: String) -> Result<(), ResourceError> {
         create_directory_if_not_exist(PathBuf::from(dir_path.clone()))?;
         let file_path = Path::new(&dir_path).join(format!("pool-{}-topology.json", self.pool.id));
-        let mut topo_file = File::create(file_path)?;
-        let topology_as_pretty = serde_json::to_string_pretty(self)?;
-        topo_file.write_all(topology_as_pretty.as_bytes())?;
-        topo_file.flush()?;
-        Ok(())
+        write_json_file(file_path, self)
     }
 
     fn get_unhealthy_resource_info(&self) -> HashSet<ResourceInformation> {
@@ -134,15 +132,14 @@ impl PoolClientWrapper {
 
     // TODO: Add pagination support when REST service supports it
     async fn list_pools(&self) -> Result<Vec<Pool>, ResourceError> {
-        let pools = self.rest_client.pools_api().get_pools().await?.into_body();
+        let pools = with_retries(|| self.rest_client.pools_api().get_pools())
+            .await?
+            .into_body();
         Ok(pools)
     }
 
     async fn get_pool(&self, id: String) -> Result<Pool, ResourceError> {
-        let pool = self
-            .rest_client
-            .pools_api()
-            .get_pool(&id)
+        let pool = with_retries(|| self.rest_client.pools_api().get_pool(&id))
             .await?
             .into_body();
         Ok(pool)
@@ -150,10 +147,7 @@ impl PoolClientWrapper {
 
     async fn get_pool_node_info(&self, pool: Pool) -> Result<Option<Node>, ResourceError> {
         if let Some(pool_spec) = pool.spec {
-            let node = self
-                .rest_client
-                .nodes_api()
-                .get_node(&pool_spec.node.clone())
+            let node = with_retries(|| self.rest_client.nodes_api().get_node(&pool_spec.node))
                 .await?
                 .into_body();
             return Ok(Some(node));
@@ -166,12 +160,13 @@ impl PoolClientWrapper {
         pool: Pool,
     ) -> Result<Option<Vec<BlockDevice>>, ResourceError> {
         if let Some(pool_spec) = pool.spec {
-            let devices = self
-                .rest_client
-                .block_devices_api()
-                .get_node_block_devices(&pool_spec.node.clone(), Some(true))
-                .await?
-                .into_body();
+            let devices = with_retries(|| {
+                self.rest_client
+                    .block_devices_api()
+                    .get_node_block_devices(&pool_spec.node, Some(true))
+            })
+            .await?
+            .into_body();
 
             let filtered_devices: Vec<BlockDevice> = devices
                 .into_iter()
@@ -210,9 +205,7 @@ impl Resourcer for PoolClientWrapper {
                 Ok(node_info) => node_info,
                 Err(e) => {
                     // TODO: Collect errors and return to caller at end
-                    log(format!(
-                        "Failed to get node information for pool: {pool_id}, error: {e:?}"
-                    ));
+                    warn!("Failed to get node information for pool: {pool_id}, error: {e:?}");
                     None
                 }
             };
@@ -220,9 +213,7 @@ impl Resourcer for PoolClientWrapper {
                 Ok(d_info) => d_info,
                 Err(e) => {
                     // TODO: Collect errors and return to caller at end
-                    log(format!(
-                        "Failed to get device information for pool: {pool_id}, error: {e:?}"
-                    ));
+                    warn!("Failed to get device information for pool: {pool_id}, error: {e:?}");
                     None
                 }
             };
@@ -243,9 +234,7 @@ impl Resourcer for PoolClientWrapper {
                 Ok(node_info) => node_info,
                 Err(e) => {
                     // TODO: Collect errors and return to caller at end
-                    log(format!(
-                        "Failed to get node information for pools, error: {e:?}"
-                    ));
+                    warn!("Failed to get node information for pools, error: {e:?}");
                     None
                 }
             };
@@ -253,9 +242,7 @@ impl Resourcer for PoolClientWrapper {
                 Ok(d_info) => d_info,
                 Err(e) => {
                     // TODO: Collect errors and return to caller at end
-                    log(format!(
-                        "Failed to get device information for pools, error: {e:?}"
-                    ));
+                    warn!("Failed to get device information for pools, error: {e:?}");
                     None
                 }
             };
@@ -7,6 +7,7 @@ use crate::collect::{
         Resourcer,
     },
     rest_wrapper::RestClient,
+    retry::with_retries,
 };
 use openapi::models::Replica;
 use resources::ResourceError;
@@ -56,6 +57,16 @@ impl ReplicaTopology {
         resources
     }
 
+    /// get the replica resource
+    pub(crate) fn replica(&self) -> &Replica {
+        &self.replica
+    }
+
+    /// get the topology of the pool the replica is stored on
+    pub(crate) fn pool_topology(&self) -> &PoolTopology {
+        &self.pool_topology
+    }
+
     /// fetch pool name of replica resource
     pub fn get_k8s_resource_names(&self) -> Vec<String> {
         self.pool_topology.get_k8s_resource_names()
@@ -81,20 +92,14 @@ impl ReplicaClientWrapper {
     // TODO: Add pagination support when REST service supports it
     #[allow(dead_code)]
     async fn list_replicas(&self) -> Result<Vec<Replica>, ResourceError> {
-        let replicas = self
-            .rest_client
-            .replicas_api()
-            .get_replicas()
+        let replicas = with_retries(|| self.rest_client.replicas_api().get_replicas())
             .await?
             .into_body();
         Ok(replicas)
     }
 
     async fn get_replica(&self, id: openapi::apis::Uuid) -> Result<Replica, ResourceError> {
-        let replicas = self
-            .rest_client
-            .replicas_api()
-            .get_replica(&id)
+        let replicas = with_retries(|| self.rest_client.replicas_api().get_replica(&id))
             .await?
             .into_body();
         Ok(replicas)
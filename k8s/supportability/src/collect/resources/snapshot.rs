@@ -5,14 +5,14 @@ use crate::collect::{
         utils, ResourceError, Resourcer,
     },
     rest_wrapper::RestClient,
+    retry::with_retries,
+    utils::write_json_file,
 };
 use async_trait::async_trait;
 use openapi::models::VolumeSnapshot;
 use serde::{Deserialize, Serialize};
 use std::{
     collections::HashSet,
-    fs::File,
-    io::Write,
     path::{Path, PathBuf},
 };
 
@@ -22,6 +22,13 @@ pub(crate) struct VolumeSnapshotTopology {
     snapshot: VolumeSnapshot,
 }
 
+impl VolumeSnapshotTopology {
+    /// Get the snapshot.
+    pub(crate) fn snapshot(&self) -> &VolumeSnapshot {
+        &self.snapshot
+    }
+}
+
 /// Implements functionality to inspect topological information of snapshot resource.
 impl Topologer for VolumeSnapshotTopology {
     fn get_printable_topology(&self) -> Result<(String, String), ResourceError> {
@@ -39,11 +46,7 @@ impl Topologer for VolumeSnapshotTopology {
             "snapshot-{}-topology.json",
             self.snapshot.definition.spec.uuid
         ));
-        let mut topo_file = File::create(file_path)?;
-        let topology_as_pretty = serde_json::to_string_pretty(self)?;
-        topo_file.write_all(topology_as_pretty.as_bytes())?;
-        topo_file.flush()?;
-        Ok(())
+        write_json_file(file_path, self)
     }
 
     fn get_unhealthy_resource_info(&self) -> HashSet<ResourceInformation> {
@@ -81,12 +84,16 @@ impl VolumeSnapshotClientWrapper {
         let mut next_token: Option<isize> = Some(0);
         let max_entries: isize = utils::MAX_RESOURCE_ENTRIES;
         loop {
-            let snapshot_api_resp = self
-                .rest_client
-                .snapshots_api()
-                .get_volumes_snapshots(max_entries, None, None, next_token)
-                .await?
-                .into_body();
+            let snapshot_api_resp = with_retries(|| {
+                self.rest_client.snapshots_api().get_volumes_snapshots(
+                    max_entries,
+                    None,
+                    None,
+                    next_token,
+                )
+            })
+            .await?
+            .into_body();
             volume_snapshots.extend(snapshot_api_resp.entries);
             if snapshot_api_resp.next_token.is_none() {
                 break;
@@ -97,10 +104,7 @@ impl VolumeSnapshotClientWrapper {
     }
 
     async fn get_snapshot(&self, id: openapi::apis::Uuid) -> Result<VolumeSnapshot, ResourceError> {
-        let snapshot = self
-            .rest_client
-            .snapshots_api()
-            .get_volumes_snapshot(&id)
+        let snapshot = with_retries(|| self.rest_client.snapshots_api().get_volumes_snapshot(&id))
             .await?
             .into_body();
         Ok(snapshot)
@@ -1,25 +1,26 @@
-use crate::{
-    collect::{
-        logs::create_directory_if_not_exist,
-        resources,
-        resources::{
-            replica::{ReplicaClientWrapper, ReplicaTopology},
-            traits, utils,
-        },
-        rest_wrapper::RestClient,
+use crate::collect::{
+    logs::create_directory_if_not_exist,
+    resources,
+    resources::{
+        replica::{ReplicaClientWrapper, ReplicaTopology},
+        traits, utils,
     },
-    log,
+    rest_wrapper::RestClient,
+    retry::with_retries,
+    utils::{since_cutoff, write_json_file},
 };
 use async_trait::async_trait;
-use openapi::models::{Nexus, RebuildHistory, Volume};
+use chrono::{DateTime, Utc};
+use futures::{stream, StreamExt, TryStreamExt};
+use openapi::models::{Nexus, Node, RebuildHistory, RebuildJobState, Volume};
 use resources::ResourceError;
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashSet,
-    fs::File,
-    io::Write,
+    collections::{BTreeSet, HashSet},
     path::{Path, PathBuf},
+    time::Duration,
 };
+use tracing::warn;
 use traits::{
     ResourceInformation, Resourcer, Topologer, MAYASTOR_DAEMONSET_LABEL, RESOURCE_TO_CONTAINER_NAME,
 };
@@ -32,6 +33,160 @@ pub(crate) struct VolumeTopology {
     target: Option<Nexus>,
     replicas_topology: Vec<ReplicaTopology>,
     rebuild_history: Option<RebuildHistory>,
+    /// Node hosting the target, only resolved for targeted collections of a single volume.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    target_node: Option<Node>,
+    /// Child faults which ended before this time are left out of the timeline.
+    #[serde(skip)]
+    timeline_cutoff: Option<DateTime<Utc>>,
+    /// Whether the dependency graph of the volume is dumped along with its topology.
+    #[serde(skip)]
+    dependency_graph: bool,
+}
+
+/// A resource the volume depends on, eg: one of its replicas or the node hosting its target.
+#[derive(Debug, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+struct DependencyVertex {
+    kind: &'static str,
+    id: String,
+}
+
+/// A dependency between two resources, eg: a replica is stored on a pool.
+#[derive(Debug, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+struct DependencyEdge {
+    from: String,
+    relation: &'static str,
+    to: String,
+}
+
+/// Dependency graph of a volume: its target, replicas, pools and the nodes hosting them.
+#[derive(Debug, Default, Serialize)]
+struct DependencyGraph {
+    vertices: BTreeSet<DependencyVertex>,
+    edges: BTreeSet<DependencyEdge>,
+}
+
+impl DependencyGraph {
+    /// Add a dependency of the resource `from` on the resource `to`, along with both resources.
+    fn add(
+        &mut self,
+        from: (&'static str, String),
+        relation: &'static str,
+        to: (&'static str, String),
+    ) {
+        let edge = DependencyEdge {
+            from: format!("{}/{}", from.0, from.1),
+            relation,
+            to: format!("{}/{}", to.0, to.1),
+        };
+        self.vertices.insert(DependencyVertex {
+            kind: from.0,
+            id: from.1,
+        });
+        self.vertices.insert(DependencyVertex {
+            kind: to.0,
+            id: to.1,
+        });
+        self.edges.insert(edge);
+    }
+}
+
+/// A fault of a volume target child, as recorded by the rebuild which recovered the child.
+/// Children which faulted transiently and recovered on their own are otherwise not visible
+/// once the incident is over.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct ChildFaultEvent {
+    /// Uri of the faulted child.
+    child_uri: String,
+    /// Uri of the healthy child the faulted child was rebuilt from.
+    src_uri: String,
+    /// Time at which the rebuild of the faulted child started.
+    start_time: String,
+    /// Time at which the rebuild of the faulted child ended.
+    end_time: String,
+    /// Final state of the rebuild.
+    rebuild_state: RebuildJobState,
+    /// Whether only the blocks written since the fault were rebuilt.
+    is_partial: bool,
+}
+
+impl VolumeTopology {
+    /// Get the volume.
+    pub(crate) fn volume(&self) -> &Volume {
+        &self.volume
+    }
+
+    /// Get the target of the volume, if it's published.
+    pub(crate) fn target(&self) -> Option<&Nexus> {
+        self.target.as_ref()
+    }
+
+    /// Get the topologies of the replicas of the volume.
+    pub(crate) fn replicas_topology(&self) -> &[ReplicaTopology] {
+        &self.replicas_topology
+    }
+
+    /// Build the dependency graph of the volume from its topology.
+    fn dependency_graph(&self) -> DependencyGraph {
+        let mut graph = DependencyGraph::default();
+        let volume = ("volume", self.volume.spec.uuid.to_string());
+        if let Some(target) = &self.target {
+            let nexus = ("nexus", target.uuid.to_string());
+            graph.add(volume.clone(), "targets", nexus.clone());
+            graph.add(nexus, "hosted_on", ("node", target.node.clone()));
+        }
+        for topology in &self.replicas_topology {
+            let replica = topology.replica();
+            let replica_vertex = ("replica", replica.uuid.to_string());
+            graph.add(volume.clone(), "replicated_to", replica_vertex.clone());
+            if let Some(target) = &self.target {
+                let is_child = target
+                    .children
+                    .iter()
+                    .any(|child| child.uri.contains(&replica.uuid.to_string()));
+                if is_child {
+                    graph.add(
+                        ("nexus", target.uuid.to_string()),
+                        "child",
+                        replica_vertex.clone(),
+                    );
+                }
+            }
+            let pool = ("pool", replica.pool.clone());
+            graph.add(replica_vertex, "stored_on", pool.clone());
+            graph.add(pool, "hosted_on", ("node", replica.node.clone()));
+        }
+        graph
+    }
+
+    /// Get the child fault timeline of the volume target, sorted by time.
+    fn child_fault_timeline(&self) -> Vec<ChildFaultEvent> {
+        let mut timeline = self
+            .rebuild_history
+            .iter()
+            .flat_map(|history| history.records.iter())
+            .filter(|record| {
+                match (
+                    self.timeline_cutoff,
+                    record.end_time.parse::<DateTime<Utc>>(),
+                ) {
+                    (Some(cutoff), Ok(end_time)) => end_time >= cutoff,
+                    // Keep the records whose time can't be compared.
+                    _ => true,
+                }
+            })
+            .map(|record| ChildFaultEvent {
+                child_uri: record.child_uri.clone(),
+                src_uri: record.src_uri.clone(),
+                start_time: record.start_time.clone(),
+                end_time: record.end_time.clone(),
+                rebuild_state: record.rebuild_job_state.clone(),
+                is_partial: record.is_partial,
+            })
+            .collect::<Vec<ChildFaultEvent>>();
+        timeline.sort_by(|a, b| a.start_time.cmp(&b.start_time));
+        timeline
+    }
 }
 
 /// Implements functionality to inspect topological information of volume resource
@@ -46,10 +201,24 @@ impl Topologer for VolumeTopology {
         create_directory_if_not_exist(PathBuf::from(dir_path.clone()))?;
         let file_path =
             Path::new(&dir_path).join(format!("volume-{}-topology.json", self.volume.spec.uuid));
-        let mut topo_file = File::create(file_path)?;
-        let topology_as_pretty = serde_json::to_string_pretty(self)?;
-        topo_file.write_all(topology_as_pretty.as_bytes())?;
-        topo_file.flush()?;
+        write_json_file::<ResourceError>(file_path, self)?;
+
+        let timeline = self.child_fault_timeline();
+        if !timeline.is_empty() {
+            let file_path = Path::new(&dir_path).join(format!(
+                "volume-{}-child-fault-timeline.json",
+                self.volume.spec.uuid
+            ));
+            write_json_file::<ResourceError>(file_path, &timeline)?;
+        }
+
+        if self.dependency_graph {
+            let file_path = Path::new(&dir_path).join(format!(
+                "volume-{}-dependency-graph.json",
+                self.volume.spec.uuid
+            ));
+            write_json_file::<ResourceError>(file_path, &self.dependency_graph())?;
+        }
         Ok(())
     }
 
@@ -80,6 +249,7 @@ impl Topologer for VolumeTopology {
             resource_info.set_container_name(RESOURCE_TO_CONTAINER_NAME["nexus"].to_string());
             resource_info.set_host_name(nexus.node.clone());
             resource_info.set_label_selector([MAYASTOR_DAEMONSET_LABEL.to_string()].to_vec());
+            resources.insert(resource_info);
         }
         resources
     }
@@ -98,6 +268,8 @@ impl Topologer for VolumeTopology {
 pub(crate) struct VolumeClientWrapper {
     rest_client: RestClient,
     replica_client: ReplicaClientWrapper,
+    since: Option<Duration>,
+    concurrency: usize,
 }
 
 impl VolumeClientWrapper {
@@ -106,20 +278,35 @@ impl VolumeClientWrapper {
         VolumeClientWrapper {
             rest_client: client.clone(),
             replica_client: ReplicaClientWrapper::new(client),
+            since: None,
+            concurrency: 1,
         }
     }
 
+    /// Only include the child faults of the last `since` duration in the child fault timelines
+    pub(crate) fn with_since(mut self, since: Duration) -> Self {
+        self.since = Some(since);
+        self
+    }
+
+    /// Collect the topologies of up to `concurrency` volumes concurrently
+    pub(crate) fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
     async fn list_volumes(&self) -> Result<Vec<Volume>, ResourceError> {
         let mut volumes: Vec<Volume> = Vec::new();
         let mut next_token: Option<isize> = Some(0);
         let max_entries: isize = utils::MAX_RESOURCE_ENTRIES;
         loop {
-            let volumes_api_resp = self
-                .rest_client
-                .volumes_api()
-                .get_volumes(max_entries, None, next_token)
-                .await?
-                .into_body();
+            let volumes_api_resp = with_retries(|| {
+                self.rest_client
+                    .volumes_api()
+                    .get_volumes(max_entries, None, next_token)
+            })
+            .await?
+            .into_body();
             volumes.extend(volumes_api_resp.entries);
             if volumes_api_resp.next_token.is_none() {
                 break;
@@ -130,10 +317,7 @@ impl VolumeClientWrapper {
     }
 
     async fn get_volume(&self, id: openapi::apis::Uuid) -> Result<Volume, ResourceError> {
-        let volume = self
-            .rest_client
-            .volumes_api()
-            .get_volume(&id)
+        let volume = with_retries(|| self.rest_client.volumes_api().get_volume(&id))
             .await?
             .into_body();
         Ok(volume)
@@ -147,16 +331,73 @@ impl VolumeClientWrapper {
         Ok(topology)
     }
 
+    /// Build the topology of the given volume, i.e. its replicas and rebuild history.
+    async fn volume_topology(&self, volume: Volume) -> Result<VolumeTopology, ResourceError> {
+        let mut replicas_topology = Vec::new();
+        for (replica_id_str, _value) in volume.state.replica_topology.clone() {
+            let replica_uuid = openapi::apis::Uuid::parse_str(replica_id_str.as_str())?;
+            let replica_topology = match self.get_replica_topology(replica_uuid).await {
+                Ok(val) => Some(val),
+                Err(err) => {
+                    // TODO: As of now when mayastor daemon is in not Running state then
+                    // fetching replica information from REST service will result
+                    // error since REST service exposes only runtime spec of replica.
+                    // So if it is not_found we are ignoring error
+                    let _is_not_found_err = err.not_found_rest_json_error()?;
+                    None
+                }
+            };
+            if let Some(topology) = replica_topology {
+                replicas_topology.push(topology);
+            }
+        }
+        let rebuild_history = match self.get_rebuild_history(volume.spec.uuid).await {
+            Ok(rebuild_history) => Some(rebuild_history),
+            Err(error) => {
+                warn!(
+                    "Could not fetch rebuild history for {}, error: {error:?}",
+                    volume.spec.uuid
+                );
+                None
+            }
+        };
+
+        Ok(VolumeTopology {
+            target: volume.state.target.clone(),
+            volume,
+            replicas_topology,
+            rebuild_history,
+            target_node: None,
+            timeline_cutoff: self.since.map(since_cutoff),
+            dependency_graph: false,
+        })
+    }
+
+    /// Resolve the dependencies of the volume which aren't part of its topology, i.e. the node
+    /// hosting its target, and dump its dependency graph along with its topology.
+    async fn with_dependencies(
+        &self,
+        mut topology: VolumeTopology,
+    ) -> Result<VolumeTopology, ResourceError> {
+        if let Some(target) = &topology.target {
+            topology.target_node = Some(
+                with_retries(|| self.rest_client.nodes_api().get_node(&target.node))
+                    .await?
+                    .into_body(),
+            );
+        }
+        topology.dependency_graph = true;
+        Ok(topology)
+    }
+
     async fn get_rebuild_history(
         &self,
         id: openapi::apis::Uuid,
     ) -> Result<RebuildHistory, ResourceError> {
-        let rebuild_history = self
-            .rest_client
-            .volumes_api()
-            .get_rebuild_history(&id)
-            .await?
-            .into_body();
+        let rebuild_history =
+            with_retries(|| self.rest_client.volumes_api().get_rebuild_history(&id))
+                .await?
+                .into_body();
         Ok(rebuild_history)
     }
 }
@@ -169,86 +410,22 @@ impl Resourcer for VolumeClientWrapper {
         &self,
         id: Option<Self::ID>,
     ) -> Result<Box<dyn Topologer>, ResourceError> {
-        // When ID is provided then caller needs topology information for given volume ID
+        // When ID is provided then caller needs topology information for given volume ID,
+        // along with all the resources it depends on
         if let Some(volume_id) = id {
             let volume = self.get_volume(volume_id).await?;
-            let mut replicas_topology = Vec::new();
-            for (replica_id_str, _value) in volume.state.replica_topology.clone() {
-                let replica_uuid = openapi::apis::Uuid::parse_str(replica_id_str.as_str())?;
-                let replica_topology = match self.get_replica_topology(replica_uuid).await {
-                    Ok(val) => Some(val),
-                    Err(err) => {
-                        // TODO: As of now when mayastor daemon is in not Running state then
-                        // fetching replica information from REST service will result
-                        // error since REST service exposes only runtime spec of replica.
-                        // So if it is not_found we are ignoring error
-                        let _is_not_found_err = err.not_found_rest_json_error()?;
-                        None
-                    }
-                };
-                if let Some(topology) = replica_topology {
-                    replicas_topology.push(topology);
-                }
-            }
-            let rebuild_history = match self.get_rebuild_history(volume_id).await {
-                Ok(rebuild_history) => Some(rebuild_history),
-                Err(error) => {
-                    log(format!(
-                        "Could not fetch rebuild history for {volume_id}, error: {error:?}"
-                    ));
-                    None
-                }
-            };
-
-            return Ok(Box::new(VolumeTopology {
-                volume: volume.clone(),
-                target: volume.state.target,
-                replicas_topology,
-                rebuild_history,
-            }));
+            let topology = self.volume_topology(volume).await?;
+            return Ok(Box::new(self.with_dependencies(topology).await?));
         }
 
         // When ID is not provided then caller needs topology information for all volumes in the
-        // cluster
-        // available pools
-        let mut volumes_topology = Vec::new();
+        // cluster, which are collected concurrently
         let volumes = self.list_volumes().await?;
-        for volume in volumes.iter() {
-            let mut replicas_topology = Vec::new();
-            for (replica_id_str, _value) in volume.state.replica_topology.clone() {
-                let replica_uuid = openapi::apis::Uuid::parse_str(replica_id_str.as_str()).unwrap();
-                let replica_topology = match self.get_replica_topology(replica_uuid).await {
-                    Ok(val) => Some(val),
-                    // TODO: As of now when mayastor daemon is in not Running state then
-                    // fetching replica information from REST service will result
-                    // error since REST service exposes only runtime spec of replica.
-                    // So if it is not_found we are ignoring error
-                    Err(err) => {
-                        let _is_not_found_err = err.not_found_rest_json_error()?;
-                        None
-                    }
-                };
-                if let Some(topology) = replica_topology {
-                    replicas_topology.push(topology);
-                }
-            }
-            let rebuild_history = match self.get_rebuild_history(volume.spec.uuid).await {
-                Ok(rebuild_history) => Some(rebuild_history),
-                Err(error) => {
-                    log(format!(
-                        "Could not fetch rebuild history for {}, error: {error:?}",
-                        volume.spec.uuid
-                    ));
-                    None
-                }
-            };
-            volumes_topology.push(VolumeTopology {
-                volume: volume.clone(),
-                target: volume.state.target.clone(),
-                replicas_topology,
-                rebuild_history,
-            })
-        }
+        let volumes_topology = stream::iter(volumes)
+            .map(|volume| self.volume_topology(volume))
+            .buffered(self.concurrency)
+            .try_collect::<Vec<VolumeTopology>>()
+            .await?;
         Ok(Box::new(volumes_topology))
     }
 }
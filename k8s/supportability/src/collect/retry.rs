@@ -0,0 +1,118 @@
+use once_cell::sync::OnceCell;
+use std::{fmt::Debug, future::Future, time::Duration};
+use tracing::debug;
+
+/// Delay before the first retry of a request, doubled after every retry.
+const RETRY_BACKOFF: Duration = Duration::from_millis(500);
+
+/// REQUEST_POLICY bounds and retries the requests of the collection, once initialised.
+static REQUEST_POLICY: OnceCell<RequestPolicy> = OnceCell::new();
+
+/// Timeout and retries of the requests to the control-plane REST api and the kube-apiserver.
+#[derive(Debug, Default, Clone, Copy)]
+struct RequestPolicy {
+    /// Time after which an attempt of a request is abandoned, if any.
+    timeout: Option<Duration>,
+    /// Number of times a failed or abandoned request is retried.
+    retries: u32,
+}
+
+/// Initialise the timeout and the retries of the requests of the collection. Until then, the
+/// requests are neither bounded nor retried.
+pub(crate) fn init_request_policy(timeout: Duration, retries: u32) {
+    let _ = REQUEST_POLICY.set(RequestPolicy {
+        timeout: Some(timeout),
+        retries,
+    });
+}
+
+/// Error of a request which may be retried.
+pub(crate) trait Retryable {
+    /// Whether the request may succeed when retried, i.e. the error is not caused by the request
+    /// itself, eg: it was rejected or the requested resource doesn't exist.
+    fn is_retryable(&self) -> bool;
+}
+
+impl Retryable for kube::Error {
+    fn is_retryable(&self) -> bool {
+        match self {
+            kube::Error::Api(response) => response.code == 429 || response.code >= 500,
+            _ => true,
+        }
+    }
+}
+
+impl Retryable for openapi::clients::tower::Error<openapi::models::RestJsonError> {
+    fn is_retryable(&self) -> bool {
+        match self {
+            openapi::clients::tower::Error::Response(response) => {
+                let status = response.status();
+                status.as_u16() == 429 || status.is_server_error()
+            }
+            _ => true,
+        }
+    }
+}
+
+/// Error of the last attempt of a request.
+#[derive(Debug)]
+pub(crate) enum RequestError<E> {
+    /// The request failed.
+    Failed(E),
+    /// The request did not complete within the request timeout.
+    TimedOut(Duration),
+}
+
+impl<E: std::fmt::Display> std::fmt::Display for RequestError<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RequestError::Failed(error) => error.fmt(f),
+            RequestError::TimedOut(timeout) => write!(
+                f,
+                "The request did not complete within {}",
+                humantime::format_duration(*timeout)
+            ),
+        }
+    }
+}
+
+/// Send the request, bounding every attempt by the request timeout and retrying the failed
+/// and timed out attempts with an exponential backoff, so that a hung or flaky endpoint only
+/// fails the artifacts which depend on it.
+pub(crate) async fn with_retries<T, E, F, Fut>(mut request: F) -> Result<T, RequestError<E>>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    E: Retryable + Debug,
+{
+    let policy = REQUEST_POLICY.get().copied().unwrap_or_default();
+    let mut backoff = RETRY_BACKOFF;
+    let mut attempt = 0;
+    loop {
+        let result = match policy.timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, request()).await {
+                Ok(result) => result.map_err(RequestError::Failed),
+                Err(_) => Err(RequestError::TimedOut(timeout)),
+            },
+            None => request().await.map_err(RequestError::Failed),
+        };
+        let error = match result {
+            Ok(value) => return Ok(value),
+            Err(error) => error,
+        };
+        let retryable = match &error {
+            RequestError::Failed(error) => error.is_retryable(),
+            RequestError::TimedOut(_) => true,
+        };
+        if !retryable || attempt >= policy.retries {
+            return Err(error);
+        }
+        attempt += 1;
+        debug!(
+            "Retrying the request in {backoff:?}, attempt {attempt} of {}, error: {error:?}",
+            policy.retries
+        );
+        tokio::time::sleep(backoff).await;
+        backoff *= 2;
+    }
+}
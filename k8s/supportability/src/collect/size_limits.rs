@@ -0,0 +1,128 @@
+use serde::Serialize;
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+/// Name of the log file of the support tool itself, which is never truncated.
+const TOOL_LOG_FILE: &str = "support_tool_logs.log";
+
+/// Limits of the size of the content collected into the archive.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct SizeLimits {
+    /// Maximum size of all the collected content, before compression
+    pub(crate) max_bundle_size: Option<u64>,
+    /// Maximum size of each log file collected from a pod
+    pub(crate) max_log_size_per_pod: Option<u64>,
+}
+
+/// Record of a log file which was truncated to fit the size limits.
+#[derive(Serialize, Debug)]
+pub(crate) struct Truncation {
+    /// Path of the log file, relative to the root of the archive.
+    path: String,
+    /// Size of the log file as collected, in bytes.
+    original_bytes: u64,
+    /// Size of the newest log lines which were kept, in bytes.
+    kept_bytes: u64,
+}
+
+impl SizeLimits {
+    /// Truncate the log files of the given directory so that none of them exceeds the per pod
+    /// limit, and that the whole content fits in the bundle size. Only the newest log lines are
+    /// kept. The log files share the bundle size left by the other artifacts, which are never
+    /// truncated.
+    pub(crate) fn enforce(&self, dir_path: &str) -> Result<Vec<Truncation>, std::io::Error> {
+        let root = Path::new(dir_path);
+        let mut files = Vec::new();
+        list_files(root, &mut files)?;
+        let (logs, others): (Vec<_>, Vec<_>) = files
+            .into_iter()
+            .partition(|(path, _)| is_pod_log_file(root, path));
+
+        let mut limit = self.max_log_size_per_pod;
+        if let Some(max_bundle_size) = self.max_bundle_size {
+            let budget =
+                max_bundle_size.saturating_sub(others.iter().map(|(_, size)| size).sum::<u64>());
+            let sizes = logs
+                .iter()
+                .map(|(_, size)| limit.map_or(*size, |limit| limit.min(*size)))
+                .collect::<Vec<_>>();
+            if sizes.iter().sum::<u64>() > budget {
+                let share = fair_share(sizes, budget);
+                limit = Some(limit.map_or(share, |limit| limit.min(share)));
+            }
+        }
+        let Some(limit) = limit else {
+            return Ok(Vec::new());
+        };
+
+        let mut truncations = Vec::new();
+        for (path, size) in logs.into_iter().filter(|(_, size)| *size > limit) {
+            let kept_bytes = keep_newest(&path, size, limit)?;
+            truncations.push(Truncation {
+                path: path
+                    .strip_prefix(root)
+                    .unwrap_or(&path)
+                    .display()
+                    .to_string(),
+                original_bytes: size,
+                kept_bytes,
+            });
+        }
+        Ok(truncations)
+    }
+}
+
+/// Recursively list the files of the directory along with their size.
+fn list_files(dir: &Path, files: &mut Vec<(PathBuf, u64)>) -> Result<(), std::io::Error> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            list_files(&entry.path(), files)?;
+        } else {
+            files.push((entry.path(), metadata.len()));
+        }
+    }
+    Ok(())
+}
+
+/// Whether the file holds the logs of a pod, as opposed to the log file of the tool itself or
+/// any other artifact.
+fn is_pod_log_file(root: &Path, path: &Path) -> bool {
+    path.extension()
+        .map_or(false, |extension| extension == "log")
+        && path != root.join(TOOL_LOG_FILE)
+}
+
+/// Largest size to which the files can be capped so that their total size fits in the budget.
+fn fair_share(mut sizes: Vec<u64>, budget: u64) -> u64 {
+    sizes.sort_unstable();
+    let mut remaining = budget;
+    let mut count = sizes.len() as u64;
+    for size in sizes {
+        let share = remaining / count;
+        if size > share {
+            return share;
+        }
+        remaining -= size;
+        count -= 1;
+    }
+    u64::MAX
+}
+
+/// Truncate the file to its newest `limit` bytes, dropping the partial line at the cut, and
+/// return the number of bytes kept.
+fn keep_newest(path: &Path, size: u64, limit: u64) -> Result<u64, std::io::Error> {
+    let mut reader = BufReader::new(File::open(path)?);
+    // Start one byte early, so that a cut right after a newline does not drop a whole line.
+    reader.seek(SeekFrom::Start(size - limit - 1))?;
+    reader.read_until(b'\n', &mut Vec::new())?;
+
+    let tmp_path = path.with_extension("log.tmp");
+    let kept_bytes = std::io::copy(&mut reader, &mut File::create(&tmp_path)?)?;
+    std::fs::rename(tmp_path, path)?;
+    Ok(kept_bytes)
+}
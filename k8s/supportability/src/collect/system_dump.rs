@@ -1,24 +1,36 @@
-use crate::{
-    collect::{
-        archive, common,
-        common::{DumpConfig, Stringer},
-        constants::MAYASTOR_SERVICE,
-        error::Error,
-        k8s_resources::k8s_resource_dump::K8sResourceDumperClient,
-        logs::{LogCollection, LogError, LogResource, Logger},
-        persistent_store::etcd::EtcdStore,
-        resources::{
-            node::NodeClientWrapper, pool::PoolClientWrapper,
-            snapshot::VolumeSnapshotClientWrapper, traits::Topologer, volume::VolumeClientWrapper,
-            Resourcer,
-        },
-        rest_wrapper::RestClient,
-        utils::{flush_tool_log_file, init_tool_log_file, write_to_log_file},
+use crate::collect::{
+    archive,
+    checkpoint::{find_resumable_directory, Checkpoint},
+    collectors::{Collector, CollectorFilter},
+    common,
+    common::{DumpConfig, Stringer},
+    constants::MAYASTOR_SERVICE,
+    error::Error,
+    k8s_resources::{
+        core_dumps::CoreDumpConfig, k8s_resource_dump::K8sResourceDumperClient,
+        packet_capture::PacketCaptureConfig,
     },
-    log,
+    logs::{LogCollection, LogError, LogResource, Logger},
+    persistent_store::etcd::EtcdStore,
+    progress,
+    report::{ArtifactReport, ReportStatus},
+    resources::{
+        node::NodeClientWrapper,
+        placement::PlacementReport,
+        pool::PoolClientWrapper,
+        snapshot::{VolumeSnapshotClientWrapper, VolumeSnapshotTopology},
+        traits::Topologer,
+        volume::{VolumeClientWrapper, VolumeTopology},
+        Resourcer,
+    },
+    rest_wrapper::RestClient,
+    size_limits::SizeLimits,
+    utils::{flush_tool_log_file, init_tool_log_file},
 };
+use chrono::{DateTime, Utc};
 use futures::future;
-use std::{path::PathBuf, process};
+use std::{path::PathBuf, process, time::Duration};
+use tracing::{debug, error, info, warn};
 
 /// SystemDumper interacts with various services to collect information like mayastor resource(s),
 /// logs of mayastor service and state of mayastor artifacts in etcd
@@ -30,6 +42,17 @@ pub(crate) struct SystemDumper {
     k8s_resource_dumper: K8sResourceDumperClient,
     etcd_dumper: Option<EtcdStore>,
     disable_log_collection: bool,
+    include_app_pods: bool,
+    since: humantime::Duration,
+    concurrency: usize,
+    size_limits: SizeLimits,
+    node_diagnostics_image: Option<String>,
+    core_dumps: Option<CoreDumpConfig>,
+    packet_capture: Option<PacketCaptureConfig>,
+    collectors: CollectorFilter,
+    report: ArtifactReport,
+    checkpoint: Checkpoint,
+    incomplete: bool,
 }
 
 impl SystemDumper {
@@ -37,30 +60,67 @@ impl SystemDumper {
     /// 1.1 Create new archive in given directory and create temporary directory
     /// in given directory to generate dump files
     /// 1.2 Instantiate all required objects to interact with various other modules
+    /// When resuming, the temporary directory of the last interrupted collection is reused
+    /// instead, along with its checkpoint.
     pub(crate) async fn get_or_panic_system_dumper(
         config: DumpConfig,
         disable_log_collection: bool,
+        include_app_pods: bool,
+        resume: bool,
     ) -> Self {
+        let resumable_dir = match resume {
+            true => find_resumable_directory(&common::staging_directory(&config.output_directory)),
+            false => None,
+        };
+        let resumed = resumable_dir.is_some();
+        if resume && !resumed {
+            common::print_message(
+                "No interrupted collection found to resume, starting a new collection",
+            );
+        }
         // Creates a temporary directory inside user provided directory, to store
         // artifacts. If creation is failed then we can't continue the process.
-        let new_dir = match common::create_and_get_tmp_directory(config.output_directory.clone()) {
-            Ok(val) => val,
-            Err(e) => {
-                println!("Failed to create temporary directory to dump information, error: {e:?}");
-                process::exit(1);
-            }
+        let new_dir = match resumable_dir {
+            Some(dir) => dir,
+            None => match common::create_and_get_tmp_directory(config.output_directory.clone()) {
+                Ok(val) => val,
+                Err(e) => {
+                    eprintln!(
+                        "Failed to create temporary directory to dump information, error: {e:?}"
+                    );
+                    process::exit(1);
+                }
+            },
         };
 
         // Create and initialise the support tool log file
         init_tool_log_file(PathBuf::from(format!("{new_dir}/support_tool_logs.log")))
             .expect("Support Tool Log file should be created");
 
+        let checkpoint = match Checkpoint::load_or_new(&new_dir) {
+            Ok(checkpoint) => checkpoint,
+            Err(err) => {
+                error!("Failed to load checkpoint, error: {err:?}");
+                process::exit(1);
+            }
+        };
+        if resumed {
+            info!("Resuming the interrupted collection in {new_dir}");
+        }
+
         // Creates an arcive file to dump mayastor resource information. If creation
         // of archive is failed then we can't continue process
-        let archive = match archive::Archive::new(Some(config.output_directory)) {
-            Ok(val) => val,
+        let archive = match archive::Archive::new(
+            Some(config.output_directory),
+            &config.encrypt_recipients,
+            config.archive_format,
+        ) {
+            Ok(val) => val
+                .with_redactor(config.redactor)
+                .with_anonymizer(config.anonymizer)
+                .with_sign_key(config.sign_key),
             Err(err) => {
-                log(format!("Failed to create archive archive, error: {err:?}"));
+                error!("Failed to create archive archive, error: {err:?}");
                 process::exit(1);
             }
         };
@@ -70,15 +130,17 @@ impl SystemDumper {
             config.namespace.clone(),
             config.loki_uri,
             config.since,
+            config.log_start,
+            config.log_end,
             config.timeout,
+            config.concurrency,
+            config.proxy.clone(),
         )
         .await
         {
             Ok(val) => val,
             Err(err) => {
-                log(format!(
-                    "Failed to initialize logging service, error: {err:?}"
-                ));
+                error!("Failed to initialize logging service, error: {err:?}");
                 process::exit(1);
             }
         };
@@ -86,30 +148,34 @@ impl SystemDumper {
         let k8s_resource_dumper = match K8sResourceDumperClient::new(
             config.kube_config_path.clone(),
             config.namespace.clone(),
+            *config.since,
         )
         .await
         {
             Ok(val) => val,
             Err(err) => {
-                log(format!(
-                    "Failed to instantiate K8s resource dumper, error: {err:?}"
-                ));
+                error!("Failed to instantiate K8s resource dumper, error: {err:?}");
                 process::exit(1);
             }
         };
 
-        let etcd_dumper = match EtcdStore::new(
-            config.kube_config_path,
-            config.etcd_uri,
-            config.namespace,
-        )
-        .await
-        {
-            Ok(val) => Some(val),
-            Err(err) => {
-                log(format!("Failed to initialize etcd client, error: {err:?}"));
-                None
-            }
+        // The etcd client isn't needed when the etcd dump is excluded.
+        let etcd_dumper = match config.collectors.is_enabled(Collector::EtcdDump) {
+            true => match EtcdStore::new(
+                config.kube_config_path,
+                config.etcd_uri,
+                config.etcd_page_size,
+                config.namespace,
+            )
+            .await
+            {
+                Ok(val) => Some(val),
+                Err(err) => {
+                    warn!("Failed to initialize etcd client, error: {err:?}");
+                    None
+                }
+            },
+            false => None,
         };
 
         SystemDumper {
@@ -120,6 +186,17 @@ impl SystemDumper {
             k8s_resource_dumper,
             etcd_dumper,
             disable_log_collection,
+            include_app_pods,
+            since: config.since,
+            concurrency: config.concurrency,
+            size_limits: config.size_limits,
+            node_diagnostics_image: config.node_diagnostics_image,
+            core_dumps: config.core_dumps,
+            packet_capture: config.packet_capture,
+            collectors: config.collectors,
+            report: ArtifactReport::default(),
+            checkpoint,
+            incomplete: false,
         }
     }
 
@@ -127,6 +204,17 @@ impl SystemDumper {
     pub(crate) async fn collect_and_dump_loki_logs(
         &mut self,
         node_topologer: Option<Box<dyn Topologer>>,
+    ) -> Result<(), LogError> {
+        progress::start(progress::LOGS);
+        let result = self.dump_loki_logs(node_topologer).await;
+        progress::finish(progress::LOGS, result.is_ok());
+        result
+    }
+
+    /// Fetch the logs of all the logging services and dump them.
+    async fn dump_loki_logs(
+        &mut self,
+        node_topologer: Option<Box<dyn Topologer>>,
     ) -> Result<(), LogError> {
         // Fetch required logging resources
         let mut resources = self.logger.get_control_plane_logging_services().await?;
@@ -150,156 +238,417 @@ impl SystemDumper {
                 });
         }
 
-        let _ = write_to_log_file(format!(
-            "Collecting logs of following services: \n {resources:#?}"
-        ));
+        debug!("Collecting logs of following services: \n {resources:#?}");
 
-        log("Collecting logs...".to_string());
+        info!("Collecting logs...");
         self.logger
             .fetch_and_dump_logs(resources, self.dir_path.clone())
             .await?;
-        log("Completed collection of logs".to_string());
+        info!("Completed collection of logs");
         Ok(())
     }
 
-    /// Dumps the state of the system
-    pub(crate) async fn dump_system(&mut self) -> Result<(), Error> {
+    /// Dumps the state of the system. If the collection does not complete within the overall
+    /// timeout, it is aborted and only the artifacts collected until then are kept, so that
+    /// unattended dumps never hang.
+    pub(crate) async fn dump_system(
+        &mut self,
+        overall_timeout: Option<Duration>,
+    ) -> Result<(), Error> {
+        let result = match overall_timeout {
+            Some(overall_timeout) => {
+                match tokio::time::timeout(overall_timeout, self.collect_system()).await {
+                    Ok(result) => result,
+                    Err(_) => {
+                        info!(
+                            "Collection did not complete within {}, finalizing the archive with the collected information",
+                            humantime::format_duration(overall_timeout)
+                        );
+                        self.report.set_status(ReportStatus::DeadlineExceeded);
+                        self.incomplete = true;
+                        Err(Error::DeadlineExceeded(overall_timeout))
+                    }
+                }
+            }
+            None => self.collect_system().await,
+        };
+
+        match self.size_limits.enforce(&self.dir_path) {
+            Ok(truncations) if !truncations.is_empty() => {
+                info!(
+                    "Truncated {} log files to fit the size limits, only their newest lines are kept",
+                    truncations.len()
+                );
+                self.report.record_truncations(truncations);
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Failed to enforce the size limits, error: {e:?}"),
+        }
+
+        if let Err(e) = self.report.dump(&self.dir_path) {
+            warn!("Failed to write artifacts report, error: {e:?}");
+        }
+
+        result
+    }
+
+    /// Collects the state of the system, skipping the collectors which are not selected or which
+    /// already completed before the collection was resumed.
+    async fn collect_system(&mut self) -> Result<(), Error> {
         let mut errors: Vec<Error> = Vec::new();
 
-        log("Collecting topology information...".to_string());
-        // Dump information of all volume topologies exist in the system
-        match VolumeClientWrapper::new(self.rest_client.clone())
-            .get_topologer(None)
-            .await
-        {
-            Ok(topologer) => {
-                log("\t Collecting volume topology information".to_string());
-                let _ = topologer
-                    .dump_topology_info(format!("{}/topology/volume", self.dir_path.clone()))
-                    .map_err(|e| {
-                        errors.push(Error::ResourceError(e));
-                        log("\t Failed to dump volume topology information".to_string());
-                    });
+        let mut node_topologer = None;
+        if !self.is_selected(Collector::Topology) || self.is_collected("topology") {
+            if !self.disable_log_collection
+                && self.collectors.is_enabled(Collector::Logs)
+                && !self.checkpoint.is_completed("logs")
+            {
+                // The node topology is still needed to collect the io-engine logs.
+                node_topologer = NodeClientWrapper::new(self.rest_client.clone())
+                    .get_topologer(None)
+                    .await
+                    .ok();
             }
-            Err(e) => errors.push(Error::ResourceError(e)),
-        };
+        } else {
+            let errors_count = errors.len();
+            node_topologer = self.collect_topology(&mut errors).await;
+            self.set_collected("topology", errors.len() == errors_count);
+        }
 
-        match VolumeSnapshotClientWrapper::new(self.rest_client.clone())
-            .get_topologer(None)
-            .await
+        if !self.disable_log_collection
+            && self.is_selected(Collector::Logs)
+            && !self.is_collected("logs")
         {
-            Ok(topologer) => {
-                log("\t Collecting snapshot topology information".to_string());
-                let _ = topologer
-                    .dump_topology_info(format!("{}/topology/snapshot", self.dir_path.clone()))
-                    .map_err(|e| {
-                        errors.push(Error::ResourceError(e));
-                        log("\t Failed to dump snapshot topology information".to_string());
-                    });
+            let start_time = Utc::now();
+            let result = self.collect_and_dump_loki_logs(node_topologer).await;
+            let error = result.as_ref().err().map(|error| format!("{error:?}"));
+            self.report.record("logs", start_time, error);
+            self.set_collected("logs", result.is_ok());
+            if let Err(error) = result {
+                warn!("Error occurred while collecting logs");
+                errors.push(Error::LogCollectionError(error));
             }
-            Err(e) => errors.push(Error::ResourceError(e)),
-        };
+        }
 
-        // Dump information of all pools topologies exist in the system
-        match PoolClientWrapper::new(self.rest_client.clone())
-            .get_topologer(None)
-            .await
+        if self.is_selected(Collector::K8sResources) && !self.is_collected("k8s_resources") {
+            info!("Collecting Kubernetes resources specific to mayastor service");
+            let (start_time, errors_count) = (Utc::now(), errors.len());
+            progress::start(progress::K8S_RESOURCES);
+            let _ = self
+                .k8s_resource_dumper
+                .dump_k8s_resources(self.dir_path.clone(), None)
+                .await
+                .map_err(|e| {
+                    errors.push(Error::K8sResourceDumperError(e));
+                    warn!("Error occured while collecting logs");
+                });
+            let error = describe_errors(&errors[errors_count..]);
+            progress::finish(progress::K8S_RESOURCES, error.is_none());
+            self.set_collected("k8s_resources", error.is_none());
+            self.report.record("k8s_resources", start_time, error);
+            info!("Completed collection of Kubernetes resource specific information");
+        }
+
+        if self.include_app_pods
+            && self.is_selected(Collector::AppPods)
+            && !self.is_collected("app_pods")
         {
-            Ok(topologer) => {
-                log("\t Collecting pool topology information".to_string());
-                let _ = topologer
-                    .dump_topology_info(format!("{}/topology/pool", self.dir_path.clone()))
+            let (start_time, errors_count) = (Utc::now(), errors.len());
+            progress::start(progress::APP_PODS);
+            let _ = self
+                .k8s_resource_dumper
+                .dump_app_pods(self.dir_path.clone())
+                .await
+                .map_err(|e| {
+                    errors.push(Error::K8sResourceDumperError(e));
+                    warn!("Error occurred while collecting application pods");
+                });
+            let error = describe_errors(&errors[errors_count..]);
+            progress::finish(progress::APP_PODS, error.is_none());
+            self.set_collected("app_pods", error.is_none());
+            self.report.record("app_pods", start_time, error);
+        }
+
+        if self.is_selected(Collector::IoEngineState) && !self.is_collected("io_engine_state") {
+            let (start_time, errors_count) = (Utc::now(), errors.len());
+            progress::start(progress::IO_ENGINE_STATE);
+            let _ = self
+                .k8s_resource_dumper
+                .dump_io_engine_state(self.dir_path.clone())
+                .await
+                .map_err(|e| {
+                    errors.push(Error::K8sResourceDumperError(e));
+                    warn!("Error occurred while collecting the state of the io-engines");
+                });
+            let error = describe_errors(&errors[errors_count..]);
+            progress::finish(progress::IO_ENGINE_STATE, error.is_none());
+            self.set_collected("io_engine_state", error.is_none());
+            self.report.record("io_engine_state", start_time, error);
+        }
+
+        if self.is_selected(Collector::Observability) && !self.is_collected("observability") {
+            let (start_time, errors_count) = (Utc::now(), errors.len());
+            progress::start(progress::OBSERVABILITY);
+            let _ = self
+                .k8s_resource_dumper
+                .dump_observability_state(self.dir_path.clone())
+                .await
+                .map_err(|e| {
+                    errors.push(Error::K8sResourceDumperError(e));
+                    warn!(
+                        "Error occurred while collecting the state of the observability components"
+                    );
+                });
+            let error = describe_errors(&errors[errors_count..]);
+            progress::finish(progress::OBSERVABILITY, error.is_none());
+            self.set_collected("observability", error.is_none());
+            self.report.record("observability", start_time, error);
+        }
+
+        if let Some(image) = self.node_diagnostics_image.clone() {
+            if self.is_selected(Collector::NodeDiagnostics)
+                && !self.is_collected("node_diagnostics")
+            {
+                let (start_time, errors_count) = (Utc::now(), errors.len());
+                progress::start(progress::NODE_DIAGNOSTICS);
+                let _ = self
+                    .k8s_resource_dumper
+                    .dump_node_diagnostics(self.dir_path.clone(), &image)
+                    .await
                     .map_err(|e| {
-                        log("\t Failed to dump pool topology information".to_string());
-                        errors.push(Error::ResourceError(e));
+                        errors.push(Error::K8sResourceDumperError(e));
+                        warn!("Error occurred while collecting node diagnostics");
                     });
+                let error = describe_errors(&errors[errors_count..]);
+                progress::finish(progress::NODE_DIAGNOSTICS, error.is_none());
+                self.set_collected("node_diagnostics", error.is_none());
+                self.report.record("node_diagnostics", start_time, error);
             }
-            Err(e) => errors.push(Error::ResourceError(e)),
-        };
+        }
 
-        let node_topologer = match NodeClientWrapper::new(self.rest_client.clone())
-            .get_topologer(None)
-            .await
-        {
-            Ok(topologer) => {
-                log("\t Collecting node topology information".to_string());
-                let _ = topologer
-                    .dump_topology_info(format!("{}/topology/node", self.dir_path.clone()))
+        if let Some(core_dumps) = self.core_dumps.clone() {
+            if self.is_selected(Collector::CoreDumps) && !self.is_collected("core_dumps") {
+                let (start_time, errors_count) = (Utc::now(), errors.len());
+                progress::start(progress::CORE_DUMPS);
+                let _ = self
+                    .k8s_resource_dumper
+                    .dump_core_dumps(self.dir_path.clone(), &core_dumps)
+                    .await
                     .map_err(|e| {
-                        log("\t Failed to dump node topology information".to_string());
-                        errors.push(Error::ResourceError(e));
+                        errors.push(Error::K8sResourceDumperError(e));
+                        warn!("Error occurred while collecting core dumps");
                     });
-                Some(topologer)
-            }
-            Err(e) => {
-                errors.push(Error::ResourceError(e));
-                None
+                let error = describe_errors(&errors[errors_count..]);
+                progress::finish(progress::CORE_DUMPS, error.is_none());
+                self.set_collected("core_dumps", error.is_none());
+                self.report.record("core_dumps", start_time, error);
             }
-        };
-        log("Completed collection of topology information".to_string());
+        }
 
-        if !self.disable_log_collection {
-            if let Err(error) = self.collect_and_dump_loki_logs(node_topologer).await {
-                log("Error occurred while collecting logs".to_string());
-                errors.push(Error::LogCollectionError(error));
+        if let Some(packet_capture) = self.packet_capture.clone() {
+            if self.is_selected(Collector::PacketCapture) && !self.is_collected("packet_capture") {
+                let (start_time, errors_count) = (Utc::now(), errors.len());
+                progress::start(progress::PACKET_CAPTURE);
+                let _ = self
+                    .k8s_resource_dumper
+                    .dump_packet_captures(self.dir_path.clone(), &packet_capture)
+                    .await
+                    .map_err(|e| {
+                        errors.push(Error::K8sResourceDumperError(e));
+                        warn!("Error occurred while capturing the NVMe-oF packets");
+                    });
+                let error = describe_errors(&errors[errors_count..]);
+                progress::finish(progress::PACKET_CAPTURE, error.is_none());
+                self.set_collected("packet_capture", error.is_none());
+                self.report.record("packet_capture", start_time, error);
             }
         }
 
-        log("Collecting Kubernetes resources specific to mayastor service".to_string());
-        let _ = self
-            .k8s_resource_dumper
-            .dump_k8s_resources(self.dir_path.clone(), None)
+        if self.is_selected(Collector::EtcdDump)
+            && self.etcd_dumper.is_some()
+            && !self.is_collected("etcd_dump")
+        {
+            let mut path: PathBuf = std::path::PathBuf::new();
+            path.push(&self.dir_path.clone());
+
+            let (start_time, errors_count) = (Utc::now(), errors.len());
+            let _ = future::try_join_all(self.etcd_dumper.as_mut().map(|etcd_store| {
+                info!("Collecting mayastor specific information from Etcd...");
+                progress::start(progress::ETCD);
+                etcd_store.dump(path, false)
+            }))
             .await
             .map_err(|e| {
-                errors.push(Error::K8sResourceDumperError(e));
-                log("Error occured while collecting logs".to_string());
+                warn!("Failed to collect etcd dump information, error: {e:?}");
+                errors.push(Error::EtcdDumpError(e));
             });
-        log("Completed collection of Kubernetes resource specific information".to_string());
+            let error = describe_errors(&errors[errors_count..]);
+            progress::finish(progress::ETCD, error.is_none());
+            self.set_collected("etcd_dump", error.is_none());
+            self.report.record("etcd_dump", start_time, error);
+        }
 
-        let mut path: PathBuf = std::path::PathBuf::new();
-        path.push(&self.dir_path.clone());
+        self.incomplete = !errors.is_empty();
+        Ok(())
+    }
 
-        let _ = future::try_join_all(self.etcd_dumper.as_mut().map(|etcd_store| {
-            log("Collecting mayastor specific information from Etcd...".to_string());
-            etcd_store.dump(path, false)
-        }))
-        .await
-        .map_err(|e| {
-            log(format!(
-                "Failed to collect etcd dump information, error: {e:?}"
-            ));
-            errors.push(Error::EtcdDumpError(e));
-        });
+    /// Collects the topology information of all the resources, returning the node topologer.
+    async fn collect_topology(&mut self, errors: &mut Vec<Error>) -> Option<Box<dyn Topologer>> {
+        info!("Collecting topology information...");
+        // Dump information of all volume, snapshot, pool and node topologies exist in the system
+        // concurrently, the outcome is logged in order once all of them are collected.
+        let dir_path = self.dir_path.clone();
+        progress::start(progress::TOPOLOGY);
+        progress::set_total(progress::TOPOLOGY, 4);
+        let (volume, snapshot, pool, node) = future::join4(
+            dump_topology(
+                VolumeClientWrapper::new(self.rest_client.clone())
+                    .with_since(*self.since)
+                    .with_concurrency(self.concurrency),
+                &dir_path,
+                "volume",
+            ),
+            dump_topology(
+                VolumeSnapshotClientWrapper::new(self.rest_client.clone()),
+                &dir_path,
+                "snapshot",
+            ),
+            dump_topology(
+                PoolClientWrapper::new(self.rest_client.clone()),
+                &dir_path,
+                "pool",
+            ),
+            dump_topology(
+                NodeClientWrapper::new(self.rest_client.clone()),
+                &dir_path,
+                "node",
+            ),
+        )
+        .await;
+        let mut node_topologer = None;
+        let (mut volume_topologer, mut snapshot_topologer) = (None, None);
+        let errors_count = errors.len();
+        for (name, mut dump) in [
+            ("volume", volume),
+            ("snapshot", snapshot),
+            ("pool", pool),
+            ("node", node),
+        ] {
+            match dump.errors.is_empty() {
+                true => info!("\t Collected {name} topology information"),
+                false => warn!("\t Failed to dump {name} topology information"),
+            }
+            self.report.record_span(
+                &format!("topology/{name}"),
+                dump.start_time,
+                dump.end_time,
+                describe_errors(&dump.errors),
+            );
+            errors.append(&mut dump.errors);
+            match name {
+                "volume" => volume_topologer = dump.topologer,
+                "snapshot" => snapshot_topologer = dump.topologer,
+                "node" => node_topologer = dump.topologer,
+                _ => {}
+            }
+        }
 
-        Ok(())
+        // The placement report is built from the volume topologies, along with the snapshot ones
+        // when they could be collected.
+        if let Some(volumes) = volume_topologer
+            .as_ref()
+            .and_then(|topologer| topologer.downcast_ref::<Vec<VolumeTopology>>())
+        {
+            let start_time = Utc::now();
+            let snapshots = snapshot_topologer
+                .as_ref()
+                .and_then(|topologer| topologer.downcast_ref::<Vec<VolumeSnapshotTopology>>())
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            let error = PlacementReport::new(volumes, snapshots)
+                .dump(&format!("{dir_path}/topology"))
+                .err()
+                .map(Error::ResourceError);
+            match &error {
+                None => info!("\t Collected the placement report"),
+                Some(e) => warn!("\t Failed to write the placement report, error: {e:?}"),
+            }
+            let description = error.as_ref().map(|e| format!("{e:?}"));
+            self.report
+                .record("topology/placement", start_time, description);
+            errors.extend(error);
+        }
+        progress::finish(progress::TOPOLOGY, errors.len() == errors_count);
+        info!("Completed collection of topology information");
+        node_topologer
+    }
+
+    /// Whether the collector is selected through `--only` and `--exclude`.
+    fn is_selected(&self, collector: Collector) -> bool {
+        let selected = self.collectors.is_enabled(collector);
+        if !selected {
+            info!("Skipping {}, it is not selected", collector.name());
+        }
+        selected
+    }
+
+    /// Whether the collector already completed before the collection was resumed.
+    fn is_collected(&self, collector: &str) -> bool {
+        let collected = self.checkpoint.is_completed(collector);
+        if collected {
+            info!("Skipping {collector}, it was already collected");
+        }
+        collected
     }
 
-    /// Copies the temporary directory into archive and delete temporary directory
+    /// Record the completion of the collector in the checkpoint, if it succeeded.
+    fn set_collected(&mut self, collector: &str, succeeded: bool) {
+        if !succeeded {
+            return;
+        }
+        if let Err(e) = self.checkpoint.complete(collector) {
+            warn!("Failed to write checkpoint, error: {e:?}");
+        }
+    }
+
+    /// Copies the temporary directory into archive and delete temporary directory. The temporary
+    /// directory is kept if some collectors did not complete, so that the collection can be
+    /// resumed.
     pub fn fill_archive_and_delete_tmp(&mut self) -> Result<(), Error> {
         // Log which is visible in archive system log file
-        let _ = write_to_log_file("Will copy temporary directory content to archive".to_string());
+        debug!("Will copy temporary directory content to archive");
         // Flush log file before copying contents
         flush_tool_log_file()?;
 
-        // Copy folder into archive
+        // Copy folder into archive, with the failed artifacts indexed in its manifest
+        self.archive.set_failures(self.report.failures());
         self.archive
             .copy_to_archive(self.dir_path.clone(), ".".to_string())
             .map_err(|e| {
-                log(format!(
-                    "Failed to move content into archive file, error: {e}"
-                ));
+                warn!("Failed to move content into archive file, error: {e}");
                 e
             })?;
 
+        if self.incomplete {
+            info!(
+                "Kept the temporary directory {}, re-run with --resume to collect the missing information",
+                self.dir_path
+            );
+            return Ok(());
+        }
+
         self.delete_temporary_directory().map_err(|e| {
-            log(format!(
-                "Failed to delete temporary directory, error: {e:?}"
-            ));
+            warn!("Failed to delete temporary directory, error: {e:?}");
             e
         })?;
         Ok(())
     }
 
+    /// Path of the archive file, once its content has been successfully written
+    pub(crate) fn archive_path(&self) -> Option<PathBuf> {
+        self.archive.file_path().map(Into::into)
+    }
+
     /// Get the rest client clone.
     pub(crate) fn rest_client(&self) -> RestClient {
         self.rest_client.clone()
@@ -310,3 +659,46 @@ impl SystemDumper {
         Ok(())
     }
 }
+
+/// Outcome of the collection of the topology information of a kind of resource.
+struct TopologyDump {
+    start_time: DateTime<Utc>,
+    end_time: DateTime<Utc>,
+    topologer: Option<Box<dyn Topologer>>,
+    errors: Vec<Error>,
+}
+
+/// Collect the topology information of all the resources of the resourcer, and dump it into the
+/// topology directory of the given kind of resource.
+async fn dump_topology<R: Resourcer>(resourcer: R, dir_path: &str, name: &str) -> TopologyDump {
+    let start_time = Utc::now();
+    let mut errors = Vec::new();
+    let topologer = match resourcer.get_topologer(None).await {
+        Ok(topologer) => {
+            if let Err(e) = topologer.dump_topology_info(format!("{dir_path}/topology/{name}")) {
+                errors.push(Error::ResourceError(e));
+            }
+            Some(topologer)
+        }
+        Err(e) => {
+            errors.push(Error::ResourceError(e));
+            None
+        }
+    };
+    progress::inc(progress::TOPOLOGY);
+    TopologyDump {
+        start_time,
+        end_time: Utc::now(),
+        topologer,
+        errors,
+    }
+}
+
+/// Describe the errors which occurred while collecting an artifact, if any.
+fn describe_errors(errors: &[Error]) -> Option<String> {
+    match errors {
+        [] => None,
+        [error] => Some(format!("{error:?}")),
+        errors => Some(format!("{errors:?}")),
+    }
+}
@@ -0,0 +1,119 @@
+use crate::collect::{common, progress, utils::ToolLogFileWriter};
+use std::io::Write;
+use tracing::{level_filters::LevelFilter, Dispatch};
+use tracing_subscriber::{
+    filter::Targets,
+    fmt::{self, MakeWriter},
+    layer::SubscriberExt,
+    Layer, Registry,
+};
+
+/// Target of the logs of the tool itself, as opposed to the ones of its libraries.
+const TOOL_TARGET: &str = "supportability";
+
+/// Format of the logs printed on the console.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum LogFormat {
+    /// Plain log lines.
+    #[default]
+    Text,
+    /// A json object per log line, with its level, timestamp and fields.
+    Json,
+}
+
+/// Verbosity and format of the logs of the tool. A copy of the logs is always written into the
+/// support_tool_logs.log file of the dump, at the debug level, regardless of these.
+#[derive(Debug, Clone, clap::Args)]
+pub struct LogArgs {
+    /// Print more detailed logs on the console, -v for the debug logs and -vv for the trace logs
+    /// of the tool along with the debug logs of its libraries
+    #[clap(global = true, long, short, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// Only print the warnings and errors on the console, eg: when running from a script
+    #[clap(global = true, long, short, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Format of the logs printed on the console
+    #[clap(global = true, long, value_enum, default_value_t)]
+    log_format: LogFormat,
+}
+
+impl LogArgs {
+    /// Levels of the logs printed on the console, for the tool and for its libraries.
+    fn console_filter(&self) -> Targets {
+        let (tool, libraries) = match (self.quiet, self.verbose) {
+            (true, _) => (LevelFilter::WARN, LevelFilter::ERROR),
+            (false, 0) => (LevelFilter::INFO, LevelFilter::ERROR),
+            (false, 1) => (LevelFilter::DEBUG, LevelFilter::WARN),
+            (false, _) => (LevelFilter::TRACE, LevelFilter::DEBUG),
+        };
+        Targets::new()
+            .with_target(TOOL_TARGET, tool)
+            .with_default(libraries)
+    }
+
+    /// Subscriber of the logs of the tool, which prints them on the console and tees them into
+    /// the tool log file of the dump, once it is initialised.
+    /// It is scoped to the tool's operations, as the plugin may have set a global subscriber.
+    pub(crate) fn dispatch(&self) -> Dispatch {
+        let console = match self.log_format {
+            LogFormat::Text => fmt::layer()
+                .with_writer(ConsoleWriter)
+                .with_ansi(false)
+                .without_time()
+                .with_target(false)
+                .with_level(self.verbose > 0)
+                .boxed(),
+            LogFormat::Json => fmt::layer().with_writer(ConsoleWriter).json().boxed(),
+        };
+        let bundle = fmt::layer()
+            .with_writer(ToolLogFileWriter)
+            .with_ansi(false)
+            .with_filter(
+                Targets::new()
+                    .with_target(TOOL_TARGET, LevelFilter::DEBUG)
+                    .with_default(LevelFilter::WARN),
+            );
+        Dispatch::new(
+            Registry::default()
+                .with(console.with_filter(self.console_filter()))
+                .with(bundle),
+        )
+    }
+}
+
+/// Prints the log lines above the progress bars, if any, otherwise on the console.
+struct ConsoleWriter;
+
+impl<'a> MakeWriter<'a> for ConsoleWriter {
+    type Writer = ConsoleLine;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        ConsoleLine(Vec::new())
+    }
+}
+
+/// A log line, printed once it is fully formatted.
+struct ConsoleLine(Vec<u8>);
+
+impl Write for ConsoleLine {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for ConsoleLine {
+    fn drop(&mut self) {
+        let line = String::from_utf8_lossy(&self.0);
+        let line = line.trim_end_matches('\n');
+        if !progress::println(line) {
+            common::print_message(line);
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use object_store::{
+    aws::AmazonS3Builder, azure::MicrosoftAzureBuilder, gcp::GoogleCloudStorageBuilder,
+    path::Path as ObjectPath, ObjectStore,
+};
+use std::{path::Path, str::FromStr};
+use tokio::io::AsyncWriteExt;
+
+/// UploadError holds the errors that can occur while uploading the archive file
+#[derive(Debug)]
+pub(crate) enum UploadError {
+    ObjectStore(object_store::Error),
+    IOError(std::io::Error),
+    InvalidArchivePath(std::path::PathBuf),
+}
+
+impl From<object_store::Error> for UploadError {
+    fn from(e: object_store::Error) -> Self {
+        UploadError::ObjectStore(e)
+    }
+}
+
+impl From<std::io::Error> for UploadError {
+    fn from(e: std::io::Error) -> Self {
+        UploadError::IOError(e)
+    }
+}
+
+/// Object storage service the archive file is uploaded to
+#[derive(Debug, Clone, Copy)]
+enum StorageService {
+    S3,
+    Gcs,
+    Azure,
+}
+
+/// Object storage location the archive file is uploaded to, given as
+/// s3://<bucket>/<prefix>, gs://<bucket>/<prefix> or az://<container>/<prefix>
+#[derive(Debug, Clone)]
+pub(crate) struct UploadDestination {
+    service: StorageService,
+    url: url::Url,
+}
+
+impl FromStr for UploadDestination {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let url = url::Url::parse(s).map_err(|e| format!("Invalid upload url '{s}': {e}"))?;
+        let service = match url.scheme() {
+            "s3" => StorageService::S3,
+            "gs" => StorageService::Gcs,
+            "az" | "azure" => StorageService::Azure,
+            scheme => {
+                return Err(format!(
+                    "Unsupported upload url scheme '{scheme}', expected one of s3, gs or az"
+                ))
+            }
+        };
+        if url.host_str().map_or(true, str::is_empty) {
+            return Err(format!("The upload url '{s}' has no bucket or container"));
+        }
+        Ok(Self { service, url })
+    }
+}
+
+impl UploadDestination {
+    /// Builds the client of the object storage, the credentials are read from the
+    /// standard environment variables of each service
+    fn store(&self) -> Result<Box<dyn ObjectStore>, UploadError> {
+        Ok(match self.service {
+            StorageService::S3 => Box::new(
+                AmazonS3Builder::from_env()
+                    .with_url(self.url.as_str())
+                    .build()?,
+            ),
+            StorageService::Gcs => Box::new(
+                GoogleCloudStorageBuilder::from_env()
+                    .with_url(self.url.as_str())
+                    .build()?,
+            ),
+            StorageService::Azure => Box::new(
+                MicrosoftAzureBuilder::from_env()
+                    .with_url(self.url.as_str())
+                    .build()?,
+            ),
+        })
+    }
+
+    /// Streams the archive file into an object under the prefix of the destination and
+    /// returns the url of the uploaded object
+    pub(crate) async fn upload(&self, archive_path: &Path) -> Result<String, UploadError> {
+        let file_name = archive_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| UploadError::InvalidArchivePath(archive_path.to_path_buf()))?;
+        let location =
+            ObjectPath::from(self.url.path().trim_matches('/')).child(file_name.to_string());
+
+        let store = self.store()?;
+        let mut archive = tokio::fs::File::open(archive_path).await?;
+        let (multipart_id, mut writer) = store.put_multipart(&location).await?;
+        let copied = async {
+            tokio::io::copy(&mut archive, &mut writer).await?;
+            writer.shutdown().await
+        }
+        .await;
+        if let Err(error) = copied {
+            let _ = store.abort_multipart(&location, &multipart_id).await;
+            return Err(error.into());
+        }
+
+        Ok(format!(
+            "{}://{}/{}",
+            self.url.scheme(),
+            self.url.host_str().unwrap_or_default(),
+            location
+        ))
+    }
+}
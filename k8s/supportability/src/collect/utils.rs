@@ -1,38 +1,54 @@
 use once_cell::sync::OnceCell;
-use std::{fs::File, io::Write, path::PathBuf};
+use std::{
+    fs::File,
+    io::{BufWriter, IsTerminal, Write},
+    path::{Path, PathBuf},
+};
 
 /// TOOL LOG FILE is the file that stores the logs of the support tool.
 static TOOL_LOG_FILE: OnceCell<Option<File>> = OnceCell::new();
 
-/// Method to be only used to print tool logs to console and write in file.
-pub fn log(content: String) {
-    println!("{content}");
-    // NOTE: If we failed to write to log file can't do anything, just write
-    // to stdout and return
-    let _ = write_to_log_file(format!("{content}\n"))
-        .map_err(|e| println!("Not be able to write to log file, error: {e}"));
+/// Capacity of the buffers through which the artifacts are streamed into their files, which
+/// bounds the memory used per file regardless of the size of the artifact.
+pub(crate) const WRITE_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Writer of the tool logs into the TOOL_LOG_FILE, the logs are discarded until it is
+/// initialised or when there is no log file.
+pub(crate) struct ToolLogFileWriter;
+
+impl<'a> tracing_subscriber::fmt::MakeWriter<'a> for ToolLogFileWriter {
+    type Writer = ToolLogFile;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        ToolLogFile(TOOL_LOG_FILE.get().and_then(Option::as_ref))
+    }
 }
 
-/// Method to be only used to write in file.
-pub(crate) fn write_to_log_file(content: String) -> Result<(), std::io::Error> {
-    if let Some(mut file) = TOOL_LOG_FILE
-        .get()
-        .ok_or(std::io::Error::new(
-            std::io::ErrorKind::NotFound,
-            "LogFile not initialised!",
-        ))?
-        .as_ref()
-    {
-        file.write_all(content.as_bytes())?;
+/// The TOOL_LOG_FILE, if initialised with a file.
+pub(crate) struct ToolLogFile(Option<&'static File>);
+
+impl Write for ToolLogFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match &mut self.0 {
+            Some(file) => file.write(buf),
+            None => Ok(buf.len()),
+        }
     }
 
-    Ok(())
+    fn flush(&mut self) -> std::io::Result<()> {
+        match &mut self.0 {
+            Some(file) => file.flush(),
+            None => Ok(()),
+        }
+    }
 }
 
 /// Method to initialise the TOOL_LOG_FILE once cell with a File.
+/// The file is appended to, so that the logs of a resumed collection are kept.
 pub(crate) fn init_tool_log_file(file_path: PathBuf) -> Result<(), std::io::Error> {
+    let file = File::options().create(true).append(true).open(file_path)?;
     TOOL_LOG_FILE
-        .set(Some(File::create(file_path)?))
+        .set(Some(file))
         .expect("Expect to be initialised only once");
     Ok(())
 }
@@ -57,3 +73,67 @@ pub fn flush_tool_log_file() -> Result<(), std::io::Error> {
     }
     Ok(())
 }
+
+/// Ask the user to confirm the given question on the console. When not running interactively,
+/// the user already opted in through the command line, so the confirmation is implied.
+pub(crate) fn confirm(question: &str) -> bool {
+    if !std::io::stdin().is_terminal() {
+        return true;
+    }
+    // Stdout may carry the archive.
+    eprint!("{question} [y/N]: ");
+    let _ = std::io::stderr().flush();
+    let mut answer = String::new();
+    match std::io::stdin().read_line(&mut answer) {
+        Ok(_) => matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"),
+        Err(_) => false,
+    }
+}
+
+/// Get the point in time from which the artifacts of the last `since` duration are collected.
+pub(crate) fn since_cutoff(since: std::time::Duration) -> chrono::DateTime<chrono::Utc> {
+    chrono::Duration::from_std(since)
+        .ok()
+        .and_then(|since| chrono::Utc::now().checked_sub_signed(since))
+        .unwrap_or(chrono::DateTime::<chrono::Utc>::MIN_UTC)
+}
+
+/// Create the file, the writes to it going through a buffer of bounded capacity.
+pub(crate) fn create_buffered_file(
+    path: impl AsRef<Path>,
+) -> Result<BufWriter<File>, std::io::Error> {
+    Ok(BufWriter::with_capacity(
+        WRITE_BUFFER_SIZE,
+        File::create(path)?,
+    ))
+}
+
+/// Serialize the value as pretty json straight into the file, instead of buffering the whole
+/// json in memory first.
+pub(crate) fn write_json_file<E>(
+    path: impl AsRef<Path>,
+    value: &impl serde::Serialize,
+) -> Result<(), E>
+where
+    E: From<std::io::Error> + From<serde_json::Error>,
+{
+    let mut file = create_buffered_file(path)?;
+    serde_json::to_writer_pretty(&mut file, value)?;
+    file.flush()?;
+    Ok(())
+}
+
+/// Serialize the value as yaml straight into the file, instead of buffering the whole yaml in
+/// memory first.
+pub(crate) fn write_yaml_file<E>(
+    path: impl AsRef<Path>,
+    value: &impl serde::Serialize,
+) -> Result<(), E>
+where
+    E: From<std::io::Error> + From<serde_yaml::Error>,
+{
+    let mut file = create_buffered_file(path)?;
+    serde_yaml::to_writer(&mut file, value)?;
+    file.flush()?;
+    Ok(())
+}
@@ -0,0 +1,459 @@
+use crate::analyze::{walk_bundle, PoolTopologyFile, VolumeTopologyFile};
+use anyhow::Context;
+use k8s_openapi::api::core::v1::Pod;
+use openapi::models::{Node, Pool, Volume};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+const VOLUME_SECTION: &str = "volume";
+const POOL_SECTION: &str = "pool";
+const NODE_SECTION: &str = "node";
+const CUSTOM_RESOURCE_SECTION: &str = "custom resource";
+const VERSION_SECTION: &str = "version";
+
+/// Fields of the helm release summaries which are compared.
+const RELEASE_FIELDS: [&str; 4] = ["chart_version", "app_version", "revision", "status"];
+
+/// Compares two support bundles, eg: collected before and after an incident or an upgrade, and
+/// reports the changes of the topology, of the spec and status of the custom resources, of the
+/// versions of the components and of the capacity of the pools.
+#[derive(Debug, Clone, clap::Args)]
+pub(crate) struct DiffArgs {
+    /// Path of the earlier support bundle, either an archive written by the dump or its
+    /// extracted directory
+    before: PathBuf,
+
+    /// Path of the later support bundle, either an archive written by the dump or its extracted
+    /// directory
+    after: PathBuf,
+
+    /// Print the changes as json, eg: to be processed further
+    #[clap(long)]
+    json: bool,
+}
+
+/// Kind of a change between the bundles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum ChangeKind {
+    Removed,
+    Added,
+    Modified,
+}
+
+/// A change of a resource between the bundles.
+#[derive(Serialize)]
+struct Change {
+    /// Directory of the cluster in multi-cluster bundles, empty otherwise.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    cluster: String,
+    section: &'static str,
+    resource: String,
+    kind: ChangeKind,
+    /// The changed field of a modified resource.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    field: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    before: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    after: Option<String>,
+}
+
+/// The compared fields of the resources of a section, keyed by the resource.
+type Resources = BTreeMap<String, BTreeMap<String, String>>;
+
+/// State of a cluster which is compared, keyed by the section.
+type ClusterState = BTreeMap<&'static str, Resources>;
+
+/// Node topology file of a bundle, of which only the node is compared.
+#[derive(Deserialize)]
+struct NodeTopologyFile {
+    node: Node,
+}
+
+/// Files of the bundles which are compared.
+enum BundleFile {
+    Volume,
+    Pool,
+    Node,
+    CustomResource(String),
+    Pods,
+    Release(String),
+}
+
+/// Classify the file at the given path of the bundle, returning the directory of its cluster,
+/// which is empty unless the bundle holds several clusters.
+fn classify(path: &str) -> Option<(String, BundleFile)> {
+    let parts = path
+        .split('/')
+        .filter(|part| !part.is_empty() && *part != ".")
+        .collect::<Vec<_>>();
+    let root = parts
+        .iter()
+        .position(|part| ["topology", "k8s_resources", "helm"].contains(part))?;
+    let file = match &parts[root..] {
+        ["topology", "volume", name] if name.ends_with(".json") => BundleFile::Volume,
+        ["topology", "pool", name] if name.ends_with(".json") => BundleFile::Pool,
+        ["topology", "node", name] if name.ends_with(".json") => BundleFile::Node,
+        ["k8s_resources", "crs", crd, name] if *crd != "definitions" => {
+            let name = name.strip_suffix(".yaml")?;
+            BundleFile::CustomResource(format!("{crd}/{name}"))
+        }
+        ["k8s_resources", "pods.yaml"] => BundleFile::Pods,
+        ["helm", release, "release.yaml"] => BundleFile::Release(release.to_string()),
+        _ => return None,
+    };
+    Some((parts[..root].join("/"), file))
+}
+
+impl DiffArgs {
+    /// Read both bundles and print the changes from the earlier to the later one.
+    pub(crate) fn diff(&self) -> anyhow::Result<()> {
+        let before = read_bundle(&self.before)?;
+        let after = read_bundle(&self.after)?;
+
+        let clusters = before.keys().chain(after.keys()).collect::<BTreeSet<_>>();
+        let mut changes = Vec::new();
+        for cluster in clusters {
+            let empty = ClusterState::new();
+            let before = before.get(cluster).unwrap_or(&empty);
+            let after = after.get(cluster).unwrap_or(&empty);
+            let sections = before.keys().chain(after.keys()).collect::<BTreeSet<_>>();
+            for section in sections {
+                let empty = Resources::new();
+                diff_resources(
+                    cluster,
+                    *section,
+                    before.get(section).unwrap_or(&empty),
+                    after.get(section).unwrap_or(&empty),
+                    &mut changes,
+                );
+            }
+        }
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&changes)?);
+        } else {
+            print_changes(&changes);
+        }
+        Ok(())
+    }
+}
+
+/// Record the resources of the section which were removed, added or whose fields changed.
+fn diff_resources(
+    cluster: &str,
+    section: &'static str,
+    before: &Resources,
+    after: &Resources,
+    changes: &mut Vec<Change>,
+) {
+    let change = |resource: &str, kind, field, before, after| Change {
+        cluster: cluster.to_string(),
+        section,
+        resource: resource.to_string(),
+        kind,
+        field,
+        before,
+        after,
+    };
+    let resources = before.keys().chain(after.keys()).collect::<BTreeSet<_>>();
+    for resource in resources {
+        match (before.get(resource), after.get(resource)) {
+            (Some(_), None) => {
+                changes.push(change(resource, ChangeKind::Removed, None, None, None))
+            }
+            (None, Some(_)) => changes.push(change(resource, ChangeKind::Added, None, None, None)),
+            (Some(before), Some(after)) => {
+                let fields = before.keys().chain(after.keys()).collect::<BTreeSet<_>>();
+                for field in fields {
+                    let (old, new) = (before.get(field), after.get(field));
+                    if old != new {
+                        changes.push(change(
+                            resource,
+                            ChangeKind::Modified,
+                            Some(field.clone()),
+                            old.cloned(),
+                            new.cloned(),
+                        ));
+                    }
+                }
+            }
+            (None, None) => {}
+        }
+    }
+}
+
+/// Read the compared state of the clusters of the bundle, keyed by the directory of the cluster.
+fn read_bundle(bundle: &Path) -> anyhow::Result<BTreeMap<String, ClusterState>> {
+    let mut clusters = BTreeMap::<String, ClusterState>::new();
+    let mut add_file = |path: &str, reader: &mut dyn Read| -> anyhow::Result<()> {
+        let Some((cluster, file)) = classify(path) else {
+            return Ok(());
+        };
+        let mut content = Vec::new();
+        reader.read_to_end(&mut content)?;
+        let state = clusters.entry(cluster).or_default();
+        let parse_error = || format!("Failed to parse {path}");
+        match file {
+            BundleFile::Volume => {
+                let volume = serde_json::from_slice::<VolumeTopologyFile>(&content)
+                    .with_context(parse_error)?
+                    .volume;
+                state
+                    .entry(VOLUME_SECTION)
+                    .or_default()
+                    .insert(volume.spec.uuid.to_string(), volume_fields(&volume));
+            }
+            BundleFile::Pool => {
+                let pool = serde_json::from_slice::<PoolTopologyFile>(&content)
+                    .with_context(parse_error)?
+                    .pool;
+                state
+                    .entry(POOL_SECTION)
+                    .or_default()
+                    .insert(pool.id.clone(), pool_fields(&pool));
+            }
+            BundleFile::Node => {
+                let node = serde_json::from_slice::<NodeTopologyFile>(&content)
+                    .with_context(parse_error)?
+                    .node;
+                state
+                    .entry(NODE_SECTION)
+                    .or_default()
+                    .insert(node.id.clone(), node_fields(&node));
+            }
+            BundleFile::CustomResource(resource) => {
+                let value = serde_yaml::from_slice::<serde_json::Value>(&content)
+                    .with_context(parse_error)?;
+                let mut fields = BTreeMap::new();
+                for key in ["spec", "status"] {
+                    if let Some(value) = value.get(key) {
+                        flatten(key, value, &mut fields);
+                    }
+                }
+                state
+                    .entry(CUSTOM_RESOURCE_SECTION)
+                    .or_default()
+                    .insert(resource, fields);
+            }
+            BundleFile::Pods => {
+                let pods =
+                    serde_yaml::from_slice::<Vec<Pod>>(&content).with_context(parse_error)?;
+                state
+                    .entry(VERSION_SECTION)
+                    .or_default()
+                    .extend(image_versions(&pods));
+            }
+            BundleFile::Release(release) => {
+                let summary = serde_yaml::from_slice::<serde_json::Value>(&content)
+                    .with_context(parse_error)?;
+                let mut fields = BTreeMap::new();
+                for key in RELEASE_FIELDS {
+                    if let Some(value) = summary.get(key) {
+                        flatten(key, value, &mut fields);
+                    }
+                }
+                state
+                    .entry(VERSION_SECTION)
+                    .or_default()
+                    .insert(format!("helm/{release}"), fields);
+            }
+        }
+        Ok(())
+    };
+    walk_bundle(bundle, &|path| classify(path).is_some(), &mut add_file)?;
+
+    if clusters.is_empty() {
+        anyhow::bail!(
+            "No topology information nor kubernetes resources were found in the bundle {}",
+            bundle.display()
+        );
+    }
+    Ok(clusters)
+}
+
+/// The compared fields of the volume.
+fn volume_fields(volume: &Volume) -> BTreeMap<String, String> {
+    let mut fields = BTreeMap::from([
+        ("status".to_string(), format!("{:?}", volume.state.status)),
+        ("size".to_string(), size(volume.spec.size)),
+        ("replicas".to_string(), volume.spec.num_replicas.to_string()),
+        ("thin".to_string(), volume.spec.thin.to_string()),
+    ]);
+    if let Some(target) = &volume.state.target {
+        fields.insert("target-node".to_string(), target.node.clone());
+        fields.insert("protocol".to_string(), format!("{:?}", target.protocol));
+    }
+    let replica_nodes = volume
+        .state
+        .replica_topology
+        .values()
+        .map(|topology| topology.node.as_deref().unwrap_or("<none>"))
+        .collect::<BTreeSet<_>>();
+    fields.insert(
+        "replica-nodes".to_string(),
+        replica_nodes.into_iter().collect::<Vec<_>>().join(", "),
+    );
+    fields
+}
+
+/// The compared fields of the pool, its capacity along with its usage.
+fn pool_fields(pool: &Pool) -> BTreeMap<String, String> {
+    let mut fields = BTreeMap::new();
+    if let Some(spec) = &pool.spec {
+        fields.insert("node".to_string(), spec.node.clone());
+        fields.insert("disks".to_string(), spec.disks.join(", "));
+    }
+    if let Some(state) = &pool.state {
+        fields.insert("node".to_string(), state.node.clone());
+        fields.insert("status".to_string(), format!("{:?}", state.status));
+        fields.insert("capacity".to_string(), size(state.capacity));
+        fields.insert("used".to_string(), size(state.used));
+        fields.insert(
+            "available".to_string(),
+            size(state.capacity.saturating_sub(state.used)),
+        );
+    }
+    fields
+}
+
+/// The compared fields of the node.
+fn node_fields(node: &Node) -> BTreeMap<String, String> {
+    let mut fields = BTreeMap::new();
+    if let Some(state) = &node.state {
+        fields.insert("status".to_string(), format!("{:?}", state.status));
+        fields.insert("grpc-endpoint".to_string(), state.grpc_endpoint.clone());
+    }
+    fields
+}
+
+/// The images of the containers of the pods, keyed by the `app` label of the pods, or their
+/// name without it, and the name of the container. The pods of a daemonset running different
+/// images, eg: during an upgrade, have all of them listed.
+fn image_versions(pods: &[Pod]) -> Resources {
+    let mut images = BTreeMap::<String, BTreeSet<String>>::new();
+    for pod in pods {
+        let Some(spec) = &pod.spec else {
+            continue;
+        };
+        let app = pod
+            .metadata
+            .labels
+            .as_ref()
+            .and_then(|labels| labels.get("app"))
+            .or(pod.metadata.name.as_ref())
+            .cloned()
+            .unwrap_or_default();
+        for container in &spec.containers {
+            if let Some(image) = &container.image {
+                images
+                    .entry(format!("{app}/{}", container.name))
+                    .or_default()
+                    .insert(image.clone());
+            }
+        }
+    }
+    images
+        .into_iter()
+        .map(|(component, images)| {
+            let images = images.into_iter().collect::<Vec<_>>().join(", ");
+            (component, BTreeMap::from([("image".to_string(), images)]))
+        })
+        .collect()
+}
+
+/// Flatten the value into its leaves, named by their path from the given key, eg: `spec.disks[0]`.
+fn flatten(key: &str, value: &serde_json::Value, fields: &mut BTreeMap<String, String>) {
+    match value {
+        serde_json::Value::Object(object) => {
+            for (name, value) in object {
+                flatten(&format!("{key}.{name}"), value, fields);
+            }
+        }
+        serde_json::Value::Array(array) => {
+            for (index, value) in array.iter().enumerate() {
+                flatten(&format!("{key}[{index}]"), value, fields);
+            }
+        }
+        serde_json::Value::Null => {}
+        serde_json::Value::String(value) => {
+            fields.insert(key.to_string(), value.clone());
+        }
+        value => {
+            fields.insert(key.to_string(), value.to_string());
+        }
+    }
+}
+
+/// A size in bytes, along with its human readable form.
+fn size(bytes: u64) -> String {
+    format!("{bytes} ({})", bytesize::ByteSize::b(bytes))
+}
+
+/// Print the changes as a table, followed by the number of changes of each kind.
+fn print_changes(changes: &[Change]) {
+    if changes.is_empty() {
+        println!("No changes found");
+        return;
+    }
+    let multi_cluster = changes.iter().any(|c| !c.cluster.is_empty());
+    let width = |header: &str, cell: fn(&Change) -> &str| {
+        changes
+            .iter()
+            .map(|c| cell(c).len())
+            .chain(std::iter::once(header.len()))
+            .max()
+            .unwrap_or_default()
+    };
+    let cluster_width = width("CLUSTER", |c| &c.cluster);
+    let section_width = width("SECTION", |c| c.section);
+    let resource_width = width("RESOURCE", |c| &c.resource);
+
+    let cluster_column = |cluster: &str| match multi_cluster {
+        true => format!("{cluster:cluster_width$}  "),
+        false => String::new(),
+    };
+    println!(
+        "{}{:section_width$}  {:resource_width$}  {:8}  CHANGE",
+        cluster_column("CLUSTER"),
+        "SECTION",
+        "RESOURCE",
+        "KIND"
+    );
+    for change in changes {
+        let (kind, details) = match change.kind {
+            ChangeKind::Removed => ("REMOVED", String::new()),
+            ChangeKind::Added => ("ADDED", String::new()),
+            ChangeKind::Modified => (
+                "MODIFIED",
+                format!(
+                    "{}: {} -> {}",
+                    change.field.as_deref().unwrap_or_default(),
+                    change.before.as_deref().unwrap_or("<none>"),
+                    change.after.as_deref().unwrap_or("<none>")
+                ),
+            ),
+        };
+        let line = format!(
+            "{}{:section_width$}  {:resource_width$}  {kind:8}  {details}",
+            cluster_column(&change.cluster),
+            change.section,
+            change.resource,
+        );
+        println!("{}", line.trim_end());
+    }
+
+    let count = |kind| changes.iter().filter(|c| c.kind == kind).count();
+    println!(
+        "\n{} changes: {} added, {} removed, {} modified",
+        changes.len(),
+        count(ChangeKind::Added),
+        count(ChangeKind::Removed),
+        count(ChangeKind::Modified)
+    );
+}
@@ -0,0 +1,314 @@
+use crate::collect::{
+    constants::{CSI_NODE_SERVICE, ETCD_PAGED_LIMIT, REST_SERVICE},
+    k8s_resources::client::ClientSet,
+    persistent_store::etcd::EtcdStore,
+    rest_wrapper::RestClient,
+};
+use k8s_openapi::api::core::v1::{Node as K8sNode, Pod};
+use openapi::models::{Node, NodeStatus};
+use std::{collections::HashMap, path::PathBuf};
+
+/// Name of the hugepages resource required by the io-engine.
+const HUGEPAGES_RESOURCE: &str = "hugepages-2Mi";
+
+const REST_CHECK: &str = "REST connectivity";
+const ETCD_CHECK: &str = "etcd health";
+const IO_ENGINE_CHECK: &str = "io-engine gRPC";
+const HUGEPAGES_CHECK: &str = "hugepages";
+const NVME_TCP_CHECK: &str = "nvme-tcp module";
+
+const REST_FIX: &str = "Check that the api-rest pod is running and that the kubeconfig points to \
+the cluster";
+const ETCD_FIX: &str = "Check that the etcd pods are running and healthy, or pass the etcd \
+endpoint with --etcd-endpoint";
+const IO_ENGINE_FIX: &str = "Check that the io-engine pod of the node is running and that its \
+gRPC port is reachable from the agent-core pod";
+const HUGEPAGES_FIX: &str = "Reserve 2MiB hugepages on the node, eg: `echo 1024 | sudo tee \
+/sys/kernel/mm/hugepages/hugepages-2048kB/nr_hugepages`, and restart the kubelet";
+const NVME_TCP_FIX: &str = "Load the nvme-tcp kernel module on the node, eg: `sudo modprobe \
+nvme_tcp`, the csi-node pod does not start without it";
+
+/// Runs connectivity and configuration checks of the deployment, and prints a pass/fail table
+/// with suggested fixes.
+#[derive(Debug, Clone, clap::Args)]
+pub struct DoctorArgs {
+    /// Endpoint of ETCD service, if left empty then will be parsed from the internal service name
+    #[clap(short, long)]
+    etcd_endpoint: Option<String>,
+}
+
+/// Outcome of a single check.
+pub(crate) struct CheckResult {
+    /// Name of the check.
+    check: &'static str,
+    /// Component or node which was checked.
+    target: String,
+    /// Reason of the failure, if the check failed.
+    pub(crate) failure: Option<String>,
+    /// Suggested fix, in case the check failed.
+    fix: &'static str,
+}
+
+impl CheckResult {
+    pub(crate) fn new(
+        check: &'static str,
+        target: &str,
+        failure: Option<String>,
+        fix: &'static str,
+    ) -> Self {
+        Self {
+            check,
+            target: target.to_string(),
+            failure,
+            fix,
+        }
+    }
+}
+
+impl DoctorArgs {
+    /// Run all the checks and print their outcome, erroring out if any of them failed.
+    pub async fn diagnose(
+        self,
+        kube_config_path: Option<PathBuf>,
+        namespace: &str,
+        timeout: humantime::Duration,
+    ) -> anyhow::Result<()> {
+        let mut results = Vec::new();
+
+        let nodes = match list_rest_nodes(kube_config_path.clone(), namespace, timeout).await {
+            Ok(nodes) => {
+                results.push(CheckResult::new(REST_CHECK, REST_SERVICE, None, REST_FIX));
+                Some(nodes)
+            }
+            Err(error) => {
+                results.push(CheckResult::new(
+                    REST_CHECK,
+                    REST_SERVICE,
+                    Some(error.to_string()),
+                    REST_FIX,
+                ));
+                None
+            }
+        };
+
+        let etcd_failure = match EtcdStore::new(
+            kube_config_path.clone(),
+            self.etcd_endpoint,
+            ETCD_PAGED_LIMIT,
+            namespace.to_string(),
+        )
+        .await
+        {
+            Ok(mut etcd) => etcd.check_health().await.err(),
+            Err(error) => Some(error),
+        };
+        results.push(CheckResult::new(
+            ETCD_CHECK,
+            "etcd",
+            etcd_failure.map(|error| format!("{error:?}")),
+            ETCD_FIX,
+        ));
+
+        if let Some(nodes) = &nodes {
+            results.extend(nodes.iter().map(io_engine_check));
+        }
+
+        match ClientSet::new(kube_config_path, namespace.to_string()).await {
+            Ok(client) => {
+                if let Some(nodes) = &nodes {
+                    results.extend(hugepages_checks(&client, nodes).await);
+                }
+                results.extend(nvme_tcp_checks(&client).await);
+            }
+            Err(error) => {
+                let failure = format!("Failed to create the kubernetes client: {error:?}");
+                results.push(CheckResult::new(
+                    HUGEPAGES_CHECK,
+                    "-",
+                    Some(failure.clone()),
+                    HUGEPAGES_FIX,
+                ));
+                results.push(CheckResult::new(
+                    NVME_TCP_CHECK,
+                    "-",
+                    Some(failure),
+                    NVME_TCP_FIX,
+                ));
+            }
+        }
+
+        print_results(&results);
+
+        let failed = results.iter().filter(|r| r.failure.is_some()).count();
+        if failed > 0 {
+            return Err(anyhow::anyhow!(
+                "{failed} of {} checks failed",
+                results.len()
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// List the io-engine nodes through the REST api.
+async fn list_rest_nodes(
+    kube_config_path: Option<PathBuf>,
+    namespace: &str,
+    timeout: humantime::Duration,
+) -> anyhow::Result<Vec<Node>> {
+    let config = kube_proxy::ConfigBuilder::default_api_rest()
+        .with_kube_config(kube_config_path)
+        .with_timeout(*timeout)
+        .with_target_mod(|t| t.with_namespace(namespace))
+        .build()
+        .await?;
+    let rest_client = RestClient::new_with_config(config);
+    let nodes = rest_client
+        .nodes_api()
+        .get_nodes(None)
+        .await
+        .map_err(|error| anyhow::anyhow!("{error:?}"))?
+        .into_body();
+    Ok(nodes)
+}
+
+/// A node is only online once the control plane reaches its io-engine over gRPC.
+fn io_engine_check(node: &Node) -> CheckResult {
+    let failure = match node.state.as_ref().map(|state| &state.status) {
+        Some(NodeStatus::Online) => None,
+        Some(status) => Some(format!("The node is {status:?}")),
+        None => Some("The node has no state".to_string()),
+    };
+    CheckResult::new(IO_ENGINE_CHECK, &node.id, failure, IO_ENGINE_FIX)
+}
+
+/// Check that hugepages are allocatable on the kubernetes nodes of the io-engine nodes.
+async fn hugepages_checks(client: &ClientSet, nodes: &[Node]) -> Vec<CheckResult> {
+    let k8s_nodes = match client.get_nodes_map().await {
+        Ok(k8s_nodes) => k8s_nodes,
+        Err(error) => {
+            return vec![CheckResult::new(
+                HUGEPAGES_CHECK,
+                "-",
+                Some(format!("Failed to list the kubernetes nodes: {error:?}")),
+                HUGEPAGES_FIX,
+            )]
+        }
+    };
+    nodes
+        .iter()
+        .map(|node| {
+            let failure = match k8s_nodes.get(&node.id).map(allocatable_hugepages) {
+                Some(Some(quantity)) if quantity != "0" => None,
+                Some(_) => Some(format!("No {HUGEPAGES_RESOURCE} are allocatable")),
+                None => Some("The kubernetes node was not found".to_string()),
+            };
+            CheckResult::new(HUGEPAGES_CHECK, &node.id, failure, HUGEPAGES_FIX)
+        })
+        .collect()
+}
+
+/// Get the quantity of allocatable 2MiB hugepages of the node.
+fn allocatable_hugepages(node: &K8sNode) -> Option<String> {
+    node.status
+        .as_ref()
+        .and_then(|status| status.allocatable.as_ref())
+        .and_then(|allocatable| allocatable.get(HUGEPAGES_RESOURCE))
+        .map(|quantity| quantity.0.clone())
+}
+
+/// The kernel modules of the nodes are not visible through the kubernetes api, but the csi-node
+/// refuses to start without the nvme-tcp module, so its pods are checked to be ready instead.
+async fn nvme_tcp_checks(client: &ClientSet) -> Vec<CheckResult> {
+    let pods = match client
+        .get_pods(&format!("app={CSI_NODE_SERVICE}"), "")
+        .await
+    {
+        Ok(pods) => pods,
+        Err(error) => {
+            return vec![CheckResult::new(
+                NVME_TCP_CHECK,
+                "-",
+                Some(format!("Failed to list the csi-node pods: {error:?}")),
+                NVME_TCP_FIX,
+            )]
+        }
+    };
+    let mut nodes = HashMap::new();
+    for pod in &pods {
+        let node = pod
+            .spec
+            .as_ref()
+            .and_then(|spec| spec.node_name.clone())
+            .unwrap_or_default();
+        let ready = is_pod_ready(pod);
+        *nodes.entry(node).or_insert(ready) &= ready;
+    }
+    let mut nodes = nodes.into_iter().collect::<Vec<_>>();
+    nodes.sort();
+    nodes
+        .into_iter()
+        .map(|(node, ready)| {
+            let failure = (!ready).then(|| "The csi-node pod is not ready".to_string());
+            CheckResult::new(NVME_TCP_CHECK, &node, failure, NVME_TCP_FIX)
+        })
+        .collect()
+}
+
+/// Whether the pod has the Ready condition.
+fn is_pod_ready(pod: &Pod) -> bool {
+    pod.status
+        .as_ref()
+        .and_then(|status| status.conditions.as_ref())
+        .map_or(false, |conditions| {
+            conditions
+                .iter()
+                .any(|condition| condition.type_ == "Ready" && condition.status == "True")
+        })
+}
+
+/// Print the outcome of the checks as a table, followed by the suggested fixes of the failed ones.
+pub(crate) fn print_results(results: &[CheckResult]) {
+    let check_width = results
+        .iter()
+        .map(|r| r.check.len())
+        .chain(std::iter::once("CHECK".len()))
+        .max()
+        .unwrap_or_default();
+    let target_width = results
+        .iter()
+        .map(|r| r.target.len())
+        .chain(std::iter::once("TARGET".len()))
+        .max()
+        .unwrap_or_default();
+
+    println!(
+        "{:check_width$}  {:target_width$}  RESULT",
+        "CHECK", "TARGET"
+    );
+    for result in results {
+        let outcome = match result.failure {
+            None => "PASS",
+            Some(_) => "FAIL",
+        };
+        println!(
+            "{:check_width$}  {:target_width$}  {outcome}",
+            result.check, result.target
+        );
+    }
+
+    let failed = results
+        .iter()
+        .filter_map(|r| r.failure.as_ref().map(|failure| (r, failure)))
+        .collect::<Vec<_>>();
+    if failed.is_empty() {
+        return;
+    }
+    println!("\nSuggested fixes:");
+    for (result, failure) in failed {
+        println!(
+            "- {} ({}): {failure}\n  {}",
+            result.check, result.target, result.fix
+        );
+    }
+}
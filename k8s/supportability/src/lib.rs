@@ -1,5 +1,10 @@
+pub mod analyze;
 pub mod collect;
+pub mod diff;
+pub mod doctor;
 pub mod operations;
+pub mod query;
+pub mod self_test;
 
 use collect::{
     common::DumpConfig,
@@ -13,8 +18,27 @@ use operations::{Operations, Resource};
 #[cfg(debug_assertions)]
 use collect::resources::{pool::PoolClientWrapper, traits::Topologer, volume::VolumeClientWrapper};
 
-use crate::collect::{common::OutputFormat, utils::log};
-use std::path::PathBuf;
+use crate::collect::{
+    anonymize::Anonymizer,
+    archive::{ArchiveFormat, ArchiveRecipient},
+    collectors::{print_collectors, CollectorFilter},
+    common::{stream_archive_to_stdout, streams_archive, OutputFormat, STDOUT_OUTPUT_PATH},
+    constants::DEFAULT_NAMESPACE,
+    k8s_resources::{core_dumps::CoreDumpConfig, packet_capture::PacketCaptureConfig},
+    logs::LogTime,
+    multi_cluster::{ClusterDelegation, ContextKubeConfig},
+    namespace::detect_namespaces,
+    progress::{init_progress, ProgressFormat},
+    redact::Redactor,
+    retry::init_request_policy,
+    size_limits::SizeLimits,
+    tool_log::LogArgs,
+    upload::UploadDestination,
+    utils::confirm,
+};
+use kube_proxy::EgressProxy;
+use std::path::{Path, PathBuf};
+use tracing::{info, instrument::WithSubscriber, warn};
 
 /// Collects state & log information of mayastor services running in the system and dump them.
 #[derive(Debug, Clone, clap::Args)]
@@ -23,10 +47,32 @@ pub struct SupportArgs {
     #[clap(global = true, long, short, default_value = "10s")]
     timeout: humantime::Duration,
 
-    /// Period states to collect all logs from last specified duration
+    /// Timeout of each request to the control-plane REST api and the kube-apiserver during the
+    /// collection, a request which times out fails only the artifacts which depend on it
+    /// [default: the value of --timeout]
+    #[clap(global = true, long)]
+    request_timeout: Option<humantime::Duration>,
+
+    /// Number of times a failed or timed out request to the control-plane REST api or the
+    /// kube-apiserver is retried, with an exponential backoff
+    #[clap(global = true, long, default_value_t = 2)]
+    retries: u32,
+
+    /// Period states to collect all logs, events and child fault timelines from last specified
+    /// duration
     #[clap(global = true, long, short, default_value = "24h")]
     since: humantime::Duration,
 
+    /// Start of the time range of the logs collected from LOKI, either in RFC3339 format
+    /// (eg: 2023-10-01T12:00:00Z) or relative to now (eg: -2h). Overrides since for LOKI logs
+    #[clap(global = true, long, allow_hyphen_values = true)]
+    start: Option<LogTime>,
+
+    /// End of the time range of the logs collected from LOKI, either in RFC3339 format
+    /// (eg: 2023-10-01T14:00:00Z) or relative to now (eg: -1h) [default: now]
+    #[clap(global = true, long, allow_hyphen_values = true)]
+    end: Option<LogTime>,
+
     /// Endpoint of LOKI service, if left empty then it will try to parse endpoint
     /// from Loki service(K8s service resource), if the tool is unable to parse
     /// from service then logs will be collected using Kube-apiserver
@@ -37,13 +83,108 @@ pub struct SupportArgs {
     #[clap(global = true, short, long)]
     etcd_endpoint: Option<String>,
 
-    /// Output directory path to store archive file
+    /// Number of keys fetched from ETCD per page
+    #[clap(
+        global = true,
+        long,
+        default_value_t = collect::constants::ETCD_PAGED_LIMIT,
+        value_parser = clap::value_parser!(i64).range(1..)
+    )]
+    etcd_page_size: i64,
+
+    /// Maximum number of resources, eg: volumes or log sources, collected concurrently
+    #[clap(
+        global = true,
+        long,
+        default_value_t = collect::constants::DEFAULT_CONCURRENCY,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    concurrency: usize,
+
+    /// Output directory path to store archive file, or `-` to stream the archive to stdout, eg:
+    /// to pipe it to another host, all the messages are then printed on stderr
     #[clap(global = true, long, short = 'd', default_value = "./")]
     output_directory_path: String,
 
-    /// Kubernetes namespace of mayastor service
-    #[clap(global = true, long, short = 'n', default_value = "mayastor")]
-    namespace: String,
+    /// Format of the archive file, zip archives can't be encrypted
+    #[clap(global = true, long = "format", value_enum, default_value_t)]
+    archive_format: ArchiveFormat,
+
+    /// Public key of a recipient to encrypt the archive file to, either an age public key
+    /// (eg: age1...) or an SSH public key (eg: ssh-ed25519 AAAA...). Can be repeated to
+    /// encrypt to several recipients, any of which can then decrypt it
+    #[clap(global = true, long = "encrypt-recipient", value_name = "PUBLIC_KEY")]
+    encrypt_recipients: Vec<ArchiveRecipient>,
+
+    /// SSH private key to sign the archive file with, eg: ~/.ssh/id_ed25519. The detached
+    /// signature is written next to the archive file, as <archive>.sig
+    #[clap(global = true, long, value_name = "PATH")]
+    sign_key: Option<PathBuf>,
+
+    /// Object storage location to upload the archive file to, eg: s3://bucket/prefix,
+    /// gs://bucket/prefix or az://container/prefix. The credentials are read from the
+    /// standard environment variables of the storage service
+    #[clap(global = true, long, value_name = "URL")]
+    upload: Option<UploadDestination>,
+
+    /// Redact the data of kubernetes Secrets, bearer tokens, credentials and private keys from
+    /// the collected content before it is archived
+    #[clap(global = true, long)]
+    redact: bool,
+
+    /// Additional regular expression whose matches are redacted, can be repeated. If it has a
+    /// capture group named `value`, only the captured value is redacted
+    #[clap(
+        global = true,
+        long = "redact-pattern",
+        value_name = "REGEX",
+        requires = "redact"
+    )]
+    redact_patterns: Vec<regex::Regex>,
+
+    /// Replace the node names, IP addresses, volume and pool UUIDs, pool names and PVC names of
+    /// the collected content with pseudonyms, which are consistent across all the files of the
+    /// archive
+    #[clap(global = true, long)]
+    anonymize: bool,
+
+    /// Local file to write the mapping of the anonymized identifiers to their pseudonyms to, it
+    /// is not included in the archive
+    #[clap(global = true, long, value_name = "PATH", requires = "anonymize")]
+    anonymize_mapping: Option<PathBuf>,
+
+    /// Format of the progress of the collection, either progress bars per collector, a stream
+    /// of json events on stdout or plain log lines [default: bars when running interactively,
+    /// otherwise plain]
+    #[clap(global = true, long, value_enum)]
+    progress: Option<ProgressFormat>,
+
+    /// Kubernetes namespace of mayastor service [default: the namespaces the product is installed
+    /// in, detected from the core agent deployments or the helm releases, each collected into its
+    /// own archive, otherwise mayastor]
+    #[clap(global = true, long, short = 'n')]
+    namespace: Option<String>,
+
+    /// Path to the kubeconfig file of the cluster to collect from, used by all the kubernetes
+    /// and REST clients of the collection. Overrides the kubeconfig of the plugin [default: the
+    /// KUBECONFIG env variable, or ~/.kube/config]
+    #[clap(global = true, long = "kubeconfig", value_name = "PATH")]
+    kube_config: Option<PathBuf>,
+
+    /// HTTP proxy through which the control-plane REST API and Loki are reached, eg:
+    /// http://proxy.example.com:3128 [default: the HTTPS_PROXY env variable]. The hosts listed in
+    /// the NO_PROXY env variable are reached directly
+    #[clap(global = true, long, value_name = "URL")]
+    proxy: Option<EgressProxy>,
+
+    /// Kubeconfig context of the cluster to collect from [default: the current context]. Can be
+    /// repeated to collect from several clusters in parallel into a combined archive, with a
+    /// directory per cluster and a merged summary
+    #[clap(global = true, long = "context", value_name = "CONTEXT")]
+    contexts: Vec<String>,
+
+    #[clap(flatten)]
+    log: LogArgs,
 }
 
 /// Supportability - collects state & log information of services and dumps it to a tar file.
@@ -61,24 +202,273 @@ pub struct DumpArgs {
 impl DumpArgs {
     /// Execute the dump of the specified resources.
     pub async fn dump(self, kube_config: Option<PathBuf>) -> anyhow::Result<()> {
+        let dispatch = self.args.log.dispatch();
         self.args
             .execute(kube_config, Operations::Dump(self.resource))
+            .with_subscriber(dispatch)
             .await
     }
+
+    /// Detect the namespace of the installation rather than using the given one, e.g. the default
+    /// namespace of the plugin, which isn't given explicitly.
+    pub fn detect_namespace(&mut self) {
+        self.args.namespace = None;
+    }
+
+    /// Whether the archive is streamed to stdout, which nothing else may then be printed to.
+    pub fn streams_to_stdout(&self) -> bool {
+        self.args.output_directory_path == STDOUT_OUTPUT_PATH && !self.is_analysis()
+    }
+
+    /// Whether a bundle, or the live cluster, is analyzed, a bundle is queried or two are
+    /// compared, the prerequisites of the tool are checked or the collectors are listed, instead
+    /// of collecting a dump.
+    pub fn is_analysis(&self) -> bool {
+        matches!(
+            self.resource,
+            Resource::Analyze(_)
+                | Resource::Query(_)
+                | Resource::Diff(_)
+                | Resource::SelfTest(_)
+                | Resource::ListCollectors
+        )
+    }
+}
+
+/// The quick dump collected before an upgrade, with the defaults of the dump arguments.
+#[derive(clap::Parser)]
+struct PreUpgradeDump {
+    #[clap(flatten)]
+    args: SupportArgs,
+}
+
+/// Collect a quick dump of the cluster before it's upgraded, i.e. the versions of the
+/// components, the state of the disk pools, the nodes and the volumes, and the logs of the
+/// unhealthy pods, into the given directory. A failed upgrade can then be diagnosed from the
+/// state of the cluster before the change.
+pub async fn dump_before_upgrade(
+    kube_config_path: Option<PathBuf>,
+    namespace: &str,
+    output_directory: &str,
+    timeout: humantime::Duration,
+) -> anyhow::Result<()> {
+    std::fs::create_dir_all(output_directory)?;
+    let timeout = timeout.to_string();
+    let dump = <PreUpgradeDump as clap::Parser>::try_parse_from([
+        "dump",
+        "--namespace",
+        namespace,
+        "--output-directory-path",
+        output_directory,
+        "--timeout",
+        timeout.as_str(),
+        "--progress",
+        "plain",
+        "--quiet",
+    ])?;
+    let dispatch = dump.args.log.dispatch();
+    dump.args
+        .execute(kube_config_path, Operations::Dump(Resource::Quick))
+        .with_subscriber(dispatch)
+        .await
 }
 
 impl SupportArgs {
     /// Execute the specified operation.
     pub(crate) async fn execute(
+        mut self,
+        mut kube_config_path: Option<PathBuf>,
+        operation: Operations,
+    ) -> anyhow::Result<()> {
+        if let Operations::Dump(Resource::ListCollectors) = &operation {
+            print_collectors();
+            return Ok(());
+        }
+        // The query and the diff only read the bundles.
+        match &operation {
+            Operations::Dump(Resource::Query(args)) => return args.query(),
+            Operations::Dump(Resource::Diff(args)) => return args.diff(),
+            _ => {}
+        }
+        if let Some(kube_config) = self.kube_config.take() {
+            kube_config_path = Some(kube_config);
+        }
+        // The collection of a single cluster, on behalf of a multi-cluster collection.
+        if let Some(delegation) = ClusterDelegation::from_env() {
+            delegation.apply(&mut self);
+            kube_config_path = Some(delegation.kube_config_path());
+        }
+        // The analysis and the self-test don't write any archive.
+        let archives = !matches!(
+            operation,
+            Operations::Dump(Resource::Analyze(_) | Resource::SelfTest(_))
+        );
+        if archives && self.output_directory_path == STDOUT_OUTPUT_PATH {
+            self.check_stdout_output(&operation)?;
+            stream_archive_to_stdout();
+        }
+        // Held until the collection completes, as all the clients read the kubeconfig file.
+        let mut context_kube_config = None;
+        match self.contexts.as_slice() {
+            [] => {}
+            [context] => {
+                let kube_config = ContextKubeConfig::write(kube_config_path.as_deref(), context)
+                    .map_err(|e| anyhow::anyhow!("{:?}", e))?;
+                kube_config_path = Some(kube_config.path().to_path_buf());
+                context_kube_config = Some(kube_config);
+            }
+            _ if matches!(operation, Operations::Dump(Resource::Analyze(_))) => {
+                return Err(anyhow::anyhow!(
+                    "Only a single cluster can be analyzed at once"
+                ));
+            }
+            _ if matches!(operation, Operations::Dump(Resource::SelfTest(_))) => {
+                return Err(anyhow::anyhow!(
+                    "Only a single cluster can be self-tested at once"
+                ));
+            }
+            _ => {
+                return collect::multi_cluster::dump_clusters(self, kube_config_path, operation)
+                    .await
+                    .map_err(|e| anyhow::anyhow!("{:?}", e))
+            }
+        }
+
+        if let (Some(start), Some(end)) = (self.start, self.end) {
+            if start >= end {
+                return Err(anyhow::anyhow!(
+                    "The log start time must be before the end time"
+                ));
+            }
+        }
+
+        self.proxy = EgressProxy::resolve(self.proxy.take()).map_err(|e| anyhow::anyhow!(e))?;
+
+        if self.namespace.is_none() {
+            match detect_namespaces(kube_config_path.clone()).await.as_slice() {
+                [namespace] => self.namespace = Some(namespace.clone()),
+                namespaces
+                    if matches!(
+                        operation,
+                        Operations::Dump(
+                            Resource::Analyze(_)
+                                | Resource::SelfTest(_)
+                                | Resource::Etcd { stdout: true }
+                        )
+                    ) || streams_archive() =>
+                {
+                    return Err(anyhow::anyhow!(
+                        "The product is installed in several namespaces: {}, select one with \
+                        --namespace",
+                        namespaces.join(", ")
+                    ));
+                }
+                namespaces => {
+                    let result = self
+                        .execute_namespaces(kube_config_path, namespaces, operation)
+                        .await;
+                    drop(context_kube_config);
+                    return result;
+                }
+            }
+        }
+
+        let result = self.execute_namespace(kube_config_path, operation).await;
+        drop(context_kube_config);
+        result
+    }
+
+    /// Check that the archive can be streamed to stdout along with the other arguments, as
+    /// nothing else may be written to stdout and there's no archive file.
+    fn check_stdout_output(&self, operation: &Operations) -> anyhow::Result<()> {
+        let conflict = if let Operations::Dump(Resource::Etcd { stdout: true }) = operation {
+            Some("--stdout")
+        } else if self.upload.is_some() {
+            Some("--upload")
+        } else if self.sign_key.is_some() {
+            Some("--sign-key")
+        } else if self.progress == Some(ProgressFormat::Json) {
+            Some("--progress json")
+        } else if self.archive_format == ArchiveFormat::Zip {
+            Some("--format zip")
+        } else {
+            None
+        };
+        match conflict {
+            Some(conflict) => Err(anyhow::anyhow!(
+                "The archive can't be streamed to stdout with {conflict}"
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// The namespace of the installation to collect from.
+    pub(crate) fn namespace(&self) -> &str {
+        self.namespace.as_deref().unwrap_or(DEFAULT_NAMESPACE)
+    }
+
+    /// Execute the operation for each of the installations of the product, one after the other,
+    /// each collected into its own archive, in a directory named after its namespace.
+    async fn execute_namespaces(
+        self,
+        kube_config_path: Option<PathBuf>,
+        namespaces: &[String],
+        operation: Operations,
+    ) -> anyhow::Result<()> {
+        let mut errors = Vec::new();
+        for namespace in namespaces {
+            info!("Collecting the installation in namespace {namespace}");
+            let mut args = self.clone();
+            args.namespace = Some(namespace.clone());
+            let output_directory = Path::new(&self.output_directory_path).join(namespace);
+            args.output_directory_path = output_directory.to_string_lossy().into_owned();
+            let result = match std::fs::create_dir_all(&output_directory) {
+                Ok(()) => {
+                    args.execute_namespace(kube_config_path.clone(), operation.clone())
+                        .await
+                }
+                Err(error) => Err(error.into()),
+            };
+            if let Err(error) = result {
+                warn!("Failed to collect the installation in namespace {namespace}: {error}");
+                errors.push(format!("namespace {namespace}: {error}"));
+            }
+        }
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(anyhow::anyhow!("{}", errors.join("\n"))),
+        }
+    }
+
+    /// Execute the operation for the installation in the namespace.
+    async fn execute_namespace(
         self,
         kube_config_path: Option<PathBuf>,
         operation: Operations,
     ) -> anyhow::Result<()> {
+        // The analysis reads the bundle, or the live cluster through its own clients.
+        if let Operations::Dump(Resource::Analyze(args)) = &operation {
+            return args
+                .analyze(
+                    kube_config_path,
+                    self.namespace(),
+                    self.timeout,
+                    self.proxy.clone(),
+                )
+                .await;
+        }
+
+        // The self-test checks the prerequisites of the collection through its own clients.
+        if let Operations::Dump(Resource::SelfTest(args)) = &operation {
+            return args.run(kube_config_path, &self).await;
+        }
+
         // Initialise the REST client.
         let config = kube_proxy::ConfigBuilder::default_api_rest()
             .with_kube_config(kube_config_path.clone())
-            .with_timeout(*self.timeout)
-            .with_target_mod(|t| t.with_namespace(&self.namespace))
+            .with_proxy(self.proxy.clone())
+            .with_timeout(*self.request_timeout.unwrap_or(self.timeout))
+            .with_target_mod(|t| t.with_namespace(self.namespace()))
             .build()
             .await?;
 
@@ -101,89 +491,158 @@ impl SupportArgs {
         resource: Resource,
     ) -> Result<(), Error> {
         let cli_args = self;
+        init_progress(cli_args.progress.unwrap_or_else(ProgressFormat::detect));
+        init_request_policy(
+            *cli_args.request_timeout.unwrap_or(cli_args.timeout),
+            cli_args.retries,
+        );
         #[cfg(debug_assertions)]
         let topologer: Box<dyn Topologer>;
         let mut config = DumpConfig {
             rest_client: rest_client.clone(),
             output_directory: cli_args.output_directory_path,
-            namespace: cli_args.namespace,
+            encrypt_recipients: cli_args.encrypt_recipients,
+            archive_format: cli_args.archive_format,
+            sign_key: cli_args.sign_key,
+            redactor: match cli_args.redact {
+                true => Some(Redactor::new(cli_args.redact_patterns)),
+                false => None,
+            },
+            anonymizer: cli_args
+                .anonymize
+                .then(|| Anonymizer::new(cli_args.anonymize_mapping)),
+            namespace: cli_args
+                .namespace
+                .unwrap_or_else(|| DEFAULT_NAMESPACE.to_string()),
             loki_uri: cli_args.loki_endpoint,
             etcd_uri: cli_args.etcd_endpoint,
+            etcd_page_size: cli_args.etcd_page_size,
             since: cli_args.since,
+            log_start: cli_args.start,
+            log_end: cli_args.end,
             kube_config_path,
+            proxy: cli_args.proxy,
             timeout: cli_args.timeout,
+            concurrency: cli_args.concurrency,
+            size_limits: SizeLimits::default(),
+            node_diagnostics_image: None,
+            core_dumps: None,
+            packet_capture: None,
+            collectors: CollectorFilter::default(),
             #[cfg(debug_assertions)]
             topologer: None,
             output_format: OutputFormat::Tar,
         };
         let mut errors = Vec::new();
-        match resource {
+        let archive_path = match resource {
             Resource::Loki => {
                 let mut system_dumper =
-                    collect::system_dump::SystemDumper::get_or_panic_system_dumper(config, true)
-                        .await;
+                    collect::system_dump::SystemDumper::get_or_panic_system_dumper(
+                        config, true, false, false,
+                    )
+                    .await;
                 let node_topologer = NodeClientWrapper::new(system_dumper.rest_client())
                     .get_topologer(None)
                     .await
                     .ok();
-                log("Completed collection of topology information".to_string());
+                info!("Completed collection of topology information");
 
                 system_dumper
                     .collect_and_dump_loki_logs(node_topologer)
                     .await?;
                 if let Err(e) = system_dumper.fill_archive_and_delete_tmp() {
-                    log(format!("Failed to copy content to archive, error: {e:?}"));
+                    warn!("Failed to copy content to archive, error: {e:?}");
                     errors.push(e);
                 }
+                system_dumper.archive_path()
             }
             Resource::System(args) => {
+                let include_app_pods = args.include_app_pods
+                    && confirm(
+                        "Application pod logs may contain sensitive data, include them in the dump?",
+                    );
+                config.size_limits = SizeLimits {
+                    max_bundle_size: args.max_bundle_size.map(|size| size.as_u64()),
+                    max_log_size_per_pod: args.max_log_size_per_pod.map(|size| size.as_u64()),
+                };
+                config.node_diagnostics_image = args
+                    .node_diagnostics
+                    .then(|| args.node_diagnostics_image.clone());
+                config.core_dumps = args.core_dumps.then(|| CoreDumpConfig {
+                    paths: args.core_dump_path.clone(),
+                    max_count: args.max_core_dumps,
+                    max_size: args.max_core_dumps_size.as_u64(),
+                    image: args.node_diagnostics_image.clone(),
+                });
+                let packet_capture = !args.packet_capture_node.is_empty()
+                    && confirm(
+                        "Captured packets may contain volume data, capture the NVMe-oF packets?",
+                    );
+                config.packet_capture = packet_capture.then(|| PacketCaptureConfig {
+                    nodes: args.packet_capture_node.clone(),
+                    ports: args.packet_capture_port.clone(),
+                    duration: args.packet_capture_duration.into(),
+                    max_size: args.packet_capture_max_size.as_u64(),
+                    snaplen: args.packet_capture_snaplen,
+                    image: args.packet_capture_image.clone(),
+                });
+                config.collectors = CollectorFilter::new(args.only.clone(), args.exclude.clone());
                 let mut system_dumper =
                     collect::system_dump::SystemDumper::get_or_panic_system_dumper(
                         config,
                         args.disable_log_collection,
+                        include_app_pods,
+                        args.resume,
                     )
                     .await;
-                if let Err(e) = system_dumper.dump_system().await {
+                if let Err(e) = system_dumper
+                    .dump_system(args.overall_timeout.map(Into::into))
+                    .await
+                {
                     // NOTE: We also need to log error content into Supportability log file
-                    log(format!("Failed to dump system state, error: {e:?}"));
+                    warn!("Failed to dump system state, error: {e:?}");
                     errors.push(e);
                 }
                 if let Err(e) = system_dumper.fill_archive_and_delete_tmp() {
-                    log(format!("Failed to copy content to archive, error: {e:?}"));
+                    warn!("Failed to copy content to archive, error: {e:?}");
                     errors.push(e);
                 }
+                system_dumper.archive_path()
             }
             #[cfg(debug_assertions)]
             Resource::Volumes => {
-                let volume_client = VolumeClientWrapper::new(rest_client);
+                let volume_client =
+                    VolumeClientWrapper::new(rest_client).with_concurrency(config.concurrency);
                 topologer = volume_client.get_topologer(None).await?;
                 config.topologer = Some(topologer);
                 let mut dumper = ResourceDumper::get_or_panic_resource_dumper(config).await;
                 if let Err(e) = dumper.dump_info("topology/volume".to_string()).await {
-                    log(format!("Failed to dump volumes information, Error: {e:?}"));
+                    warn!("Failed to dump volumes information, Error: {e:?}");
                     errors.push(e);
                 }
                 if let Err(e) = dumper.fill_archive_and_delete_tmp() {
-                    log(format!("Failed to copy content to archive, error: {e:?}"));
+                    warn!("Failed to copy content to archive, error: {e:?}");
                     errors.push(e);
                 }
+                dumper.archive_path()
             }
             #[cfg(debug_assertions)]
             Resource::Volume { id } => {
                 let volume_client = VolumeClientWrapper::new(rest_client);
                 topologer = volume_client.get_topologer(Some(id)).await?;
                 config.topologer = Some(topologer);
-                let mut dumper = ResourceDumper::get_or_panic_resource_dumper(config).await;
+                let mut dumper = ResourceDumper::get_or_panic_resource_dumper(config)
+                    .await
+                    .with_dependency_logs();
                 if let Err(e) = dumper.dump_info("topology/volume".to_string()).await {
-                    log(format!(
-                        "Failed to dump volume {id} information, Error: {e:?}"
-                    ));
+                    warn!("Failed to dump volume {id} information, Error: {e:?}");
                     errors.push(e);
                 }
                 if let Err(e) = dumper.fill_archive_and_delete_tmp() {
-                    log(format!("Failed to copy content to archive, error: {e:?}"));
+                    warn!("Failed to copy content to archive, error: {e:?}");
                     errors.push(e);
                 }
+                dumper.archive_path()
             }
             #[cfg(debug_assertions)]
             Resource::Pools => {
@@ -192,13 +651,14 @@ impl SupportArgs {
                 config.topologer = Some(topologer);
                 let mut dumper = ResourceDumper::get_or_panic_resource_dumper(config).await;
                 if let Err(e) = dumper.dump_info("topology/pool".to_string()).await {
-                    log(format!("Failed to dump pools information, Error: {e:?}"));
+                    warn!("Failed to dump pools information, Error: {e:?}");
                     errors.push(e);
                 }
                 if let Err(e) = dumper.fill_archive_and_delete_tmp() {
-                    log(format!("Failed to copy content to archive, error: {e:?}"));
+                    warn!("Failed to copy content to archive, error: {e:?}");
                     errors.push(e);
                 }
+                dumper.archive_path()
             }
             #[cfg(debug_assertions)]
             Resource::Pool { id } => {
@@ -207,15 +667,14 @@ impl SupportArgs {
                 config.topologer = Some(topologer);
                 let mut dumper = ResourceDumper::get_or_panic_resource_dumper(config).await;
                 if let Err(e) = dumper.dump_info("topology/pool".to_string()).await {
-                    log(format!(
-                        "Failed to dump pool {id} information, Error: {e:?}"
-                    ));
+                    warn!("Failed to dump pool {id} information, Error: {e:?}");
                     errors.push(e);
                 }
                 if let Err(e) = dumper.fill_archive_and_delete_tmp() {
-                    log(format!("Failed to copy content to archive, error: {e:?}"));
+                    warn!("Failed to copy content to archive, error: {e:?}");
                     errors.push(e);
                 }
+                dumper.archive_path()
             }
             #[cfg(debug_assertions)]
             Resource::Nodes => {
@@ -224,13 +683,14 @@ impl SupportArgs {
                 config.topologer = Some(topologer);
                 let mut dumper = ResourceDumper::get_or_panic_resource_dumper(config).await;
                 if let Err(e) = dumper.dump_info("topology/node".to_string()).await {
-                    log(format!("Failed to dump nodes information, Error: {e:?}"));
+                    warn!("Failed to dump nodes information, Error: {e:?}");
                     errors.push(e);
                 }
                 if let Err(e) = dumper.fill_archive_and_delete_tmp() {
-                    log(format!("Failed to copy content to archive, error: {e:?}"));
+                    warn!("Failed to copy content to archive, error: {e:?}");
                     errors.push(e);
                 }
+                dumper.archive_path()
             }
             #[cfg(debug_assertions)]
             Resource::Node { id } => {
@@ -239,16 +699,35 @@ impl SupportArgs {
                 config.topologer = Some(topologer);
                 let mut dumper = ResourceDumper::get_or_panic_resource_dumper(config).await;
                 if let Err(e) = dumper.dump_info("topology/node".to_string()).await {
-                    log(format!(
-                        "Failed to dump node {id} information, Error: {e:?}"
-                    ));
+                    warn!("Failed to dump node {id} information, Error: {e:?}");
+                    errors.push(e);
+                }
+                if let Err(e) = dumper.fill_archive_and_delete_tmp() {
+                    warn!("Failed to copy content to archive, error: {e:?}");
+                    errors.push(e);
+                }
+                dumper.archive_path()
+            }
+            Resource::Quick => {
+                let mut dumper =
+                    collect::quick_dump::QuickDumper::get_or_panic_quick_dumper(config).await;
+                if let Err(e) = dumper.dump_quick().await {
+                    warn!("Failed to dump triage information, error: {e:?}");
                     errors.push(e);
                 }
                 if let Err(e) = dumper.fill_archive_and_delete_tmp() {
-                    log(format!("Failed to copy content to archive, error: {e:?}"));
+                    warn!("Failed to copy content to archive, error: {e:?}");
                     errors.push(e);
                 }
+                dumper.archive_path()
             }
+            // The analysis, the query, the diff, the self-test and the listing of the collectors
+            // don't write any archive, they are run before the clients are created.
+            Resource::Analyze(_)
+            | Resource::Query(_)
+            | Resource::Diff(_)
+            | Resource::SelfTest(_)
+            | Resource::ListCollectors => None,
             Resource::Etcd { stdout } => {
                 config.output_format = if stdout {
                     OutputFormat::Stdout
@@ -257,9 +736,22 @@ impl SupportArgs {
                 };
                 let mut dumper = ResourceDumper::get_or_panic_resource_dumper(config).await;
                 if let Err(e) = dumper.dump_etcd().await {
-                    log(format!("Failed to dump etcd information, Error: {e:?}"));
+                    warn!("Failed to dump etcd information, Error: {e:?}");
                     errors.push(e);
                 }
+                dumper.archive_path()
+            }
+        };
+        if let (Some(upload), Some(archive_path)) = (cli_args.upload, archive_path) {
+            match upload.upload(&archive_path).await {
+                Ok(url) => println!("Uploaded the archive file to {url}"),
+                Err(e) => {
+                    warn!(
+                        "Failed to upload the archive file {}, error: {e:?}",
+                        archive_path.display()
+                    );
+                    errors.push(e.into());
+                }
             }
         }
         if !errors.is_empty() {
@@ -1,3 +1,5 @@
+use crate::collect::collectors::Collector;
+
 #[cfg(debug_assertions)]
 /// Represents type of VolumeID
 pub(crate) type VolumeID = openapi::apis::Uuid;
@@ -23,6 +25,163 @@ pub(crate) struct SystemDumpArgs {
     /// Set this to disable log collection
     #[clap(global = true, long)]
     pub(crate) disable_log_collection: bool,
+
+    /// Run only the given collectors, comma separated, eg: `--only topology,k8s`. The collectors
+    /// which aren't run by default still require their own flag. See `list-collectors`
+    #[clap(
+        global = true,
+        long,
+        value_enum,
+        value_delimiter = ',',
+        value_name = "COLLECTORS",
+        conflicts_with = "exclude"
+    )]
+    pub(crate) only: Vec<Collector>,
+
+    /// Skip the given collectors, comma separated, eg: `--exclude logs,etcd`, to leave out slow
+    /// or sensitive information. See `list-collectors`
+    #[clap(
+        global = true,
+        long,
+        value_enum,
+        value_delimiter = ',',
+        value_name = "COLLECTORS"
+    )]
+    pub(crate) exclude: Vec<Collector>,
+
+    /// Set this to also collect the describe output and recent logs of the application pods
+    /// mounting mayastor volumes. As application logs may contain sensitive data, confirmation
+    /// is asked for when running interactively
+    #[clap(global = true, long)]
+    pub(crate) include_app_pods: bool,
+
+    /// Deadline for the whole collection, eg: 30m. When it is exceeded, the archive is finalized
+    /// with whatever has been collected so far
+    #[clap(global = true, long)]
+    pub(crate) overall_timeout: Option<humantime::Duration>,
+
+    /// Resume the last interrupted collection in the output directory, skipping the information
+    /// which was already collected
+    #[clap(global = true, long)]
+    pub(crate) resume: bool,
+
+    /// Maximum size of the collected content, before compression, eg: 500MiB. When it is
+    /// exceeded, the log files are truncated to their newest lines so that the content fits
+    #[clap(global = true, long, value_name = "SIZE")]
+    pub(crate) max_bundle_size: Option<bytesize::ByteSize>,
+
+    /// Maximum size of each log file collected from a pod, eg: 20MiB. Larger log files are
+    /// truncated to their newest lines
+    #[clap(global = true, long, value_name = "SIZE")]
+    pub(crate) max_log_size_per_pod: Option<bytesize::ByteSize>,
+
+    /// Set this to also collect the storage diagnostics of the io-engine nodes, i.e. the output
+    /// of lsblk, nvme list, dmesg, the hugepages and the multipath state. They are collected by
+    /// a privileged pod which is run on each node, and deleted once done
+    #[clap(global = true, long)]
+    pub(crate) node_diagnostics: bool,
+
+    /// Image of the pods collecting the storage diagnostics or the core dumps of the nodes, which
+    /// needs a shell along with nsenter, find, stat and head
+    #[clap(
+        global = true,
+        long,
+        default_value = crate::collect::constants::NODE_DIAGNOSTICS_IMAGE
+    )]
+    pub(crate) node_diagnostics_image: String,
+
+    /// Set this to also collect the most recent core dumps of the io-engine nodes, along with the
+    /// build-id of their io-engine binary. They are copied out of a privileged pod which is run
+    /// on each node, and deleted once done
+    #[clap(global = true, long)]
+    pub(crate) core_dumps: bool,
+
+    /// Directory of the nodes holding the core dumps or the crash directories, can be repeated
+    #[clap(
+        global = true,
+        long,
+        value_name = "PATH",
+        default_values = crate::collect::constants::CORE_DUMP_PATHS,
+        requires = "core_dumps"
+    )]
+    pub(crate) core_dump_path: Vec<String>,
+
+    /// Maximum number of core dumps collected per node, the most recent ones are collected
+    #[clap(
+        global = true,
+        long,
+        default_value = crate::collect::constants::MAX_CORE_DUMPS,
+        requires = "core_dumps"
+    )]
+    pub(crate) max_core_dumps: usize,
+
+    /// Maximum total size of the core dumps collected per node, eg: 2GiB. The core dumps which
+    /// would exceed it are skipped
+    #[clap(
+        global = true,
+        long,
+        value_name = "SIZE",
+        default_value = crate::collect::constants::MAX_CORE_DUMPS_SIZE,
+        requires = "core_dumps"
+    )]
+    pub(crate) max_core_dumps_size: bytesize::ByteSize,
+
+    /// Node on which to capture a short packet trace of the NVMe-oF ports, can be repeated. The
+    /// packets are captured by a privileged pod in the host's network namespace, and deleted once
+    /// done. As the packets may carry volume data, confirmation is asked for when running
+    /// interactively
+    #[clap(global = true, long, value_name = "NODE")]
+    pub(crate) packet_capture_node: Vec<String>,
+
+    /// TCP port of the NVMe-oF targets whose packets are captured, can be repeated
+    #[clap(
+        global = true,
+        long,
+        value_name = "PORT",
+        default_values = crate::collect::constants::PACKET_CAPTURE_PORTS,
+        requires = "packet_capture_node"
+    )]
+    pub(crate) packet_capture_port: Vec<u16>,
+
+    /// Duration of the packet capture on each node, eg: 30s
+    #[clap(
+        global = true,
+        long,
+        default_value = crate::collect::constants::PACKET_CAPTURE_DURATION,
+        requires = "packet_capture_node"
+    )]
+    pub(crate) packet_capture_duration: humantime::Duration,
+
+    /// Maximum size of the packet trace of each node, eg: 100MiB. The trace is cut beyond it
+    #[clap(
+        global = true,
+        long,
+        value_name = "SIZE",
+        default_value = crate::collect::constants::PACKET_CAPTURE_MAX_SIZE,
+        requires = "packet_capture_node"
+    )]
+    pub(crate) packet_capture_max_size: bytesize::ByteSize,
+
+    /// Number of bytes captured of each packet, the default keeps the protocol headers without
+    /// the bulk of the data
+    #[clap(
+        global = true,
+        long,
+        value_name = "BYTES",
+        default_value = crate::collect::constants::PACKET_CAPTURE_SNAPLEN,
+        requires = "packet_capture_node"
+    )]
+    pub(crate) packet_capture_snaplen: u32,
+
+    /// Image of the pods capturing the packets, which needs a shell along with tcpdump, timeout
+    /// and head
+    #[clap(
+        global = true,
+        long,
+        default_value = crate::collect::constants::PACKET_CAPTURE_IMAGE,
+        requires = "packet_capture_node"
+    )]
+    pub(crate) packet_capture_image: String,
 }
 
 /// Resources on which operation can be performed
@@ -38,7 +197,7 @@ pub(crate) enum Resource {
 
     #[cfg(debug_assertions)]
     /// Collects information about particular volume and its descendants matching
-    /// to given volume ID
+    /// to given volume ID, along with the io-engine logs of the nodes hosting them
     #[clap(name = "volume")]
     Volume { id: VolumeID },
 
@@ -70,6 +229,35 @@ pub(crate) enum Resource {
         stdout: bool,
     },
 
+    /// Collects the component versions, a summary of the resources, the logs of the last 10
+    /// minutes of the unhealthy pods and the etcd keys of the unhealthy volumes, for a
+    /// first-response triage. The collection is capped at one minute and ~50MB
+    Quick,
+
+    /// Analyzes a support bundle, or the live cluster, and reports the common problems: degraded
+    /// volumes, pools near capacity, crash-looping pods, mismatched io-engine versions and stuck
+    /// rebuilds
+    Analyze(crate::analyze::AnalyzeArgs),
+
+    /// Answers common questions about a support bundle by indexing its logs and resources, eg:
+    /// `--volumes-degraded` or `--errors-matching <regex>`, without extracting it
+    Query(crate::query::QueryArgs),
+
+    /// Compares two support bundles, eg: collected before and after an incident or an upgrade,
+    /// and reports the changes of the topology, of the spec and status of the custom resources,
+    /// of the component versions and of the pool capacities
+    Diff(crate::diff::DiffArgs),
+
+    /// Verifies the prerequisites of the collection without collecting anything: the access to
+    /// the cluster, the RBAC permissions, the reachability of the REST API, Loki and etcd, and the
+    /// free space and writability of the output directory. Useful before scheduled unattended
+    /// collections
+    SelfTest(crate::self_test::SelfTestArgs),
+
+    /// Lists the collectors of the system dump, which can be selected with `--only` and
+    /// `--exclude`
+    ListCollectors,
+
     /// Collects the Loki logs from the product's components
     #[clap(hide = true)]
     Loki,
@@ -0,0 +1,229 @@
+use crate::analyze::{walk_bundle, VolumeTopologyFile};
+use anyhow::Context;
+use openapi::models::{Volume, VolumeStatus};
+use std::{
+    io::{BufRead, BufReader, Read},
+    path::PathBuf,
+};
+
+/// Answers the common questions about a support bundle, eg: which volumes are degraded or which
+/// log lines match an error, by indexing its logs and resources in a single pass instead of
+/// extracting and grepping the archive manually.
+#[derive(Debug, Clone, clap::Args)]
+#[clap(group(clap::ArgGroup::new("queries").required(true).multiple(true)))]
+pub(crate) struct QueryArgs {
+    /// Path of the support bundle to query, either an archive written by the dump or its
+    /// extracted directory
+    bundle: PathBuf,
+
+    /// List the volumes which are not online, along with the node of their target and the
+    /// state of their replicas
+    #[clap(long, group = "queries")]
+    volumes_degraded: bool,
+
+    /// List the lines of the logs and of the kubernetes resources of the bundle which match the
+    /// regular expression, eg: 'error|failed'
+    #[clap(long, value_name = "REGEX", group = "queries")]
+    errors_matching: Option<regex::Regex>,
+
+    /// Maximum number of matching lines printed, the remaining ones are only counted
+    #[clap(long, default_value_t = 1000)]
+    max_matches: usize,
+}
+
+/// Files of the bundles which are queried.
+enum BundleFile {
+    Volume,
+    Log,
+    Resource,
+}
+
+/// Classify the file at the given path of the bundle, returning the directory of its cluster,
+/// which is empty unless the bundle holds several clusters.
+fn classify(path: &str) -> Option<(String, BundleFile)> {
+    let parts = path
+        .split('/')
+        .filter(|part| !part.is_empty() && *part != ".")
+        .collect::<Vec<_>>();
+    let root = parts
+        .iter()
+        .position(|part| ["topology", "logs", "k8s_resources"].contains(part))?;
+    let file = match &parts[root..] {
+        ["topology", "volume", name] if name.ends_with(".json") => BundleFile::Volume,
+        ["logs", .., name] if name.ends_with(".log") => BundleFile::Log,
+        ["k8s_resources", .., name] if name.ends_with(".yaml") => BundleFile::Resource,
+        _ => return None,
+    };
+    Some((parts[..root].join("/"), file))
+}
+
+/// Index of the answers to the queries, built while reading the bundle.
+#[derive(Default)]
+struct QueryIndex {
+    /// Degraded volumes, along with the directory of their cluster.
+    degraded_volumes: Vec<(String, Volume)>,
+    /// Printed matching lines, as `<path>:<line number>: <line>`.
+    matches: Vec<String>,
+    /// Number of all the matching lines, printed or not.
+    match_count: usize,
+    /// Number of the files with matching lines.
+    matching_files: usize,
+}
+
+impl QueryArgs {
+    /// Read the bundle and print the answers to the queries.
+    pub(crate) fn query(&self) -> anyhow::Result<()> {
+        let mut index = QueryIndex::default();
+        let wanted = |path: &str| match classify(path) {
+            Some((_, BundleFile::Volume)) => self.volumes_degraded,
+            Some((_, BundleFile::Log | BundleFile::Resource)) => self.errors_matching.is_some(),
+            None => false,
+        };
+        walk_bundle(&self.bundle, &wanted, &mut |path, reader| {
+            match classify(path) {
+                Some((cluster, BundleFile::Volume)) => {
+                    let volume = serde_json::from_reader::<_, VolumeTopologyFile>(reader)
+                        .with_context(|| format!("Failed to parse {path}"))?
+                        .volume;
+                    if !matches!(volume.state.status, VolumeStatus::Online) {
+                        index.degraded_volumes.push((cluster, volume));
+                    }
+                }
+                Some(_) => {
+                    if let Some(regex) = &self.errors_matching {
+                        self.match_lines(regex, path, reader, &mut index)?;
+                    }
+                }
+                None => {}
+            }
+            Ok(())
+        })?;
+
+        if self.volumes_degraded {
+            print_degraded_volumes(&mut index.degraded_volumes);
+        }
+        if self.errors_matching.is_some() {
+            if self.volumes_degraded {
+                println!();
+            }
+            for line in &index.matches {
+                println!("{line}");
+            }
+            if index.match_count > index.matches.len() {
+                println!(
+                    "... {} more matching lines, raise --max-matches to print them",
+                    index.match_count - index.matches.len()
+                );
+            }
+            println!(
+                "{} matching lines in {} files",
+                index.match_count, index.matching_files
+            );
+        }
+        Ok(())
+    }
+
+    /// Record the lines of the file which match the regular expression. The lines which aren't
+    /// valid UTF-8 are matched lossily, so that a binary chunk doesn't end the search.
+    fn match_lines(
+        &self,
+        regex: &regex::Regex,
+        path: &str,
+        reader: &mut dyn Read,
+        index: &mut QueryIndex,
+    ) -> anyhow::Result<()> {
+        let mut reader = BufReader::new(reader);
+        let mut buffer = Vec::new();
+        let mut line_number = 0;
+        let mut matched = false;
+        loop {
+            buffer.clear();
+            if reader.read_until(b'\n', &mut buffer)? == 0 {
+                break;
+            }
+            line_number += 1;
+            let line = String::from_utf8_lossy(&buffer);
+            let line = line.trim_end();
+            if !regex.is_match(line) {
+                continue;
+            }
+            matched = true;
+            index.match_count += 1;
+            if index.matches.len() < self.max_matches {
+                index.matches.push(format!("{path}:{line_number}: {line}"));
+            }
+        }
+        if matched {
+            index.matching_files += 1;
+        }
+        Ok(())
+    }
+}
+
+/// Print the degraded volumes as a table, with the state of the replicas of their target.
+fn print_degraded_volumes(volumes: &mut [(String, Volume)]) {
+    if volumes.is_empty() {
+        println!("No degraded volumes found");
+        return;
+    }
+    volumes.sort_by(|(a_cluster, a), (b_cluster, b)| {
+        (a_cluster, a.spec.uuid.to_string()).cmp(&(b_cluster, b.spec.uuid.to_string()))
+    });
+    let rows = volumes
+        .iter()
+        .map(|(cluster, volume)| {
+            let name = match cluster.is_empty() {
+                true => volume.spec.uuid.to_string(),
+                false => format!("{cluster}/{}", volume.spec.uuid),
+            };
+            let (node, replicas) = match &volume.state.target {
+                Some(target) => (
+                    target.node.to_string(),
+                    target
+                        .children
+                        .iter()
+                        .map(|child| match child.rebuild_progress {
+                            Some(progress) => {
+                                format!("{} {:?} {progress}%", child.uri, child.state)
+                            }
+                            None => format!("{} {:?}", child.uri, child.state),
+                        })
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                ),
+                None => ("<none>".to_string(), "<unpublished>".to_string()),
+            };
+            [
+                name,
+                format!("{:?}", volume.state.status),
+                volume.spec.num_replicas.to_string(),
+                node,
+                replicas,
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    let headers = ["VOLUME", "STATUS", "REPLICAS", "TARGET NODE", "CHILDREN"];
+    let widths = (0..headers.len())
+        .map(|column| {
+            rows.iter()
+                .map(|row| row[column].len())
+                .chain(std::iter::once(headers[column].len()))
+                .max()
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>();
+    let print_row = |cells: &[&str]| {
+        let line = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, &width)| format!("{cell:width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        println!("{}", line.trim_end());
+    };
+    print_row(&headers);
+    for row in &rows {
+        print_row(&row.iter().map(String::as_str).collect::<Vec<_>>());
+    }
+}
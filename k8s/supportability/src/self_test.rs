@@ -0,0 +1,264 @@
+use crate::{
+    collect::{
+        common::staging_directory, k8s_resources::client::ClientSet, logs::loki::LokiClient,
+        persistent_store::etcd::EtcdStore, rest_wrapper::RestClient,
+    },
+    doctor::{print_results, CheckResult},
+    SupportArgs,
+};
+use k8s_openapi::api::authorization::v1::{
+    ResourceAttributes, SelfSubjectAccessReview, SelfSubjectAccessReviewSpec,
+};
+use kube::api::{Api, PostParams};
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+const KUBECONFIG_CHECK: &str = "kubeconfig access";
+const RBAC_CHECK: &str = "RBAC";
+const REST_CHECK: &str = "REST connectivity";
+const LOKI_CHECK: &str = "Loki reachability";
+const ETCD_CHECK: &str = "etcd reachability";
+const DISK_SPACE_CHECK: &str = "disk space";
+const ARCHIVE_CHECK: &str = "archive writability";
+
+const KUBECONFIG_FIX: &str = "Check that the kubeconfig, given with --kubeconfig or through the \
+KUBECONFIG env variable, points to the cluster and that its credentials are valid";
+const RBAC_FIX: &str = "Grant the user of the kubeconfig the missing permission, eg: with a \
+ClusterRole bound to it, or collect with a kubeconfig of a user which has it";
+const REST_FIX: &str = "Check that the api-rest pod is running in the namespace of the \
+installation, or give its namespace with --namespace";
+const LOKI_FIX: &str = "Check that the loki pods are running, or pass the Loki endpoint with \
+--loki-endpoint. Without Loki, only the logs of the running pods are collected, from the \
+kube-apiserver";
+const ETCD_FIX: &str = "Check that the etcd pods are running and healthy, or pass the etcd \
+endpoint with --etcd-endpoint";
+const DISK_SPACE_FIX: &str = "Free up space in the output directory, or choose another one with \
+--output-directory-path";
+const ARCHIVE_FIX: &str = "Check that the output directory, given with --output-directory-path, \
+exists and is writable by the user running the tool";
+
+/// Permissions the collection requires, as (verb, api group, resource, whether namespaced).
+const REQUIRED_PERMISSIONS: [(&str, &str, &str, bool); 14] = [
+    ("list", "", "pods", true),
+    ("get", "", "pods/log", true),
+    ("create", "", "pods/portforward", true),
+    ("list", "", "events", true),
+    ("list", "", "services", true),
+    ("list", "", "secrets", true),
+    ("list", "", "nodes", false),
+    ("list", "", "persistentvolumes", false),
+    ("list", "apps", "deployments", true),
+    ("list", "apps", "daemonsets", true),
+    ("list", "apps", "statefulsets", true),
+    ("list", "openebs.io", "diskpools", true),
+    ("list", "storage.k8s.io", "storageclasses", false),
+    (
+        "list",
+        "apiextensions.k8s.io",
+        "customresourcedefinitions",
+        false,
+    ),
+];
+
+/// Verifies the prerequisites of the collection without collecting anything, i.e. the access to
+/// the cluster, the RBAC permissions, the reachability of Loki and etcd, and the free space and
+/// the writability of the output directory.
+#[derive(Debug, Clone, clap::Args)]
+pub(crate) struct SelfTestArgs {
+    /// Minimum free space of the output directory
+    #[clap(long, value_name = "SIZE", default_value = "1GiB")]
+    min_free_space: bytesize::ByteSize,
+}
+
+impl SelfTestArgs {
+    /// Run all the checks and print their outcome, erroring out if any of them failed.
+    pub(crate) async fn run(
+        &self,
+        kube_config_path: Option<PathBuf>,
+        args: &SupportArgs,
+    ) -> anyhow::Result<()> {
+        let mut results = Vec::new();
+
+        match ClientSet::new(kube_config_path.clone(), args.namespace().to_string()).await {
+            Ok(client) => match client.kube_client().apiserver_version().await {
+                Ok(_) => {
+                    results.push(CheckResult::new(
+                        KUBECONFIG_CHECK,
+                        "kube-apiserver",
+                        None,
+                        KUBECONFIG_FIX,
+                    ));
+                    results.extend(rbac_checks(&client).await);
+                }
+                Err(error) => results.push(CheckResult::new(
+                    KUBECONFIG_CHECK,
+                    "kube-apiserver",
+                    Some(error.to_string()),
+                    KUBECONFIG_FIX,
+                )),
+            },
+            Err(error) => results.push(CheckResult::new(
+                KUBECONFIG_CHECK,
+                "kube-apiserver",
+                Some(format!("Failed to create the kubernetes client: {error:?}")),
+                KUBECONFIG_FIX,
+            )),
+        }
+
+        results.push(CheckResult::new(
+            REST_CHECK,
+            "api-rest",
+            rest_check(kube_config_path.clone(), args).await.err(),
+            REST_FIX,
+        ));
+
+        let loki_failure = match LokiClient::new(
+            args.loki_endpoint.clone(),
+            kube_config_path.clone(),
+            args.namespace().to_string(),
+            args.since,
+            None,
+            None,
+            args.timeout,
+            args.proxy.clone(),
+        )
+        .await
+        {
+            Some(loki) => loki.check_ready().await.err().map(|e| format!("{e:?}")),
+            None => Some("Failed to create the Loki client".to_string()),
+        };
+        results.push(CheckResult::new(LOKI_CHECK, "loki", loki_failure, LOKI_FIX));
+
+        let etcd_failure = match EtcdStore::new(
+            kube_config_path,
+            args.etcd_endpoint.clone(),
+            args.etcd_page_size,
+            args.namespace().to_string(),
+        )
+        .await
+        {
+            Ok(mut etcd) => etcd.check_health().await.err(),
+            Err(error) => Some(error),
+        };
+        results.push(CheckResult::new(
+            ETCD_CHECK,
+            "etcd",
+            etcd_failure.map(|error| format!("{error:?}")),
+            ETCD_FIX,
+        ));
+
+        let output_dir = staging_directory(&args.output_directory_path);
+        let target = output_dir.display().to_string();
+        results.push(CheckResult::new(
+            DISK_SPACE_CHECK,
+            &target,
+            self.disk_space_check(&output_dir).err(),
+            DISK_SPACE_FIX,
+        ));
+        results.push(CheckResult::new(
+            ARCHIVE_CHECK,
+            &target,
+            archive_check(&output_dir).err().map(|e| e.to_string()),
+            ARCHIVE_FIX,
+        ));
+
+        print_results(&results);
+
+        let failed = results.iter().filter(|r| r.failure.is_some()).count();
+        if failed > 0 {
+            return Err(anyhow::anyhow!(
+                "{failed} of {} checks failed",
+                results.len()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Check that the output directory has the minimum free space.
+    fn disk_space_check(&self, output_dir: &Path) -> Result<(), String> {
+        let available = fs2::available_space(output_dir)
+            .map_err(|error| format!("Failed to get the free space: {error}"))?;
+        if available < self.min_free_space.as_u64() {
+            return Err(format!(
+                "Only {} are free, {} are required",
+                bytesize::ByteSize::b(available),
+                self.min_free_space
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Check that the user of the kubeconfig has each of the permissions the collection requires.
+async fn rbac_checks(client: &ClientSet) -> Vec<CheckResult> {
+    let api: Api<SelfSubjectAccessReview> = Api::all(client.kube_client());
+    let mut results = Vec::new();
+    for (verb, group, resource, namespaced) in REQUIRED_PERMISSIONS {
+        let (resource, subresource) = match resource.split_once('/') {
+            Some((resource, subresource)) => (resource, Some(subresource.to_string())),
+            None => (resource, None),
+        };
+        let review = SelfSubjectAccessReview {
+            spec: SelfSubjectAccessReviewSpec {
+                resource_attributes: Some(ResourceAttributes {
+                    verb: Some(verb.to_string()),
+                    group: Some(group.to_string()),
+                    resource: Some(resource.to_string()),
+                    subresource: subresource.clone(),
+                    namespace: namespaced.then(|| client.namespace().to_string()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let failure = match api.create(&PostParams::default(), &review).await {
+            Ok(review) => match review.status {
+                Some(status) if status.allowed => None,
+                Some(status) => Some(match status.reason {
+                    Some(reason) if !reason.is_empty() => format!("Not allowed: {reason}"),
+                    _ => "Not allowed".to_string(),
+                }),
+                None => Some("The access review has no status".to_string()),
+            },
+            Err(error) => Some(format!("Failed to review the access: {error}")),
+        };
+        let target = match subresource {
+            Some(subresource) => format!("{verb} {resource}/{subresource}"),
+            None => format!("{verb} {resource}"),
+        };
+        results.push(CheckResult::new(RBAC_CHECK, &target, failure, RBAC_FIX));
+    }
+    results
+}
+
+/// Check that the REST api answers, through the proxy if any.
+async fn rest_check(kube_config_path: Option<PathBuf>, args: &SupportArgs) -> Result<(), String> {
+    let config = kube_proxy::ConfigBuilder::default_api_rest()
+        .with_kube_config(kube_config_path)
+        .with_proxy(args.proxy.clone())
+        .with_timeout(*args.timeout)
+        .with_target_mod(|t| t.with_namespace(args.namespace()))
+        .build()
+        .await
+        .map_err(|error| format!("{error:?}"))?;
+    RestClient::new_with_config(config)
+        .nodes_api()
+        .get_nodes(None)
+        .await
+        .map_err(|error| format!("{error:?}"))?;
+    Ok(())
+}
+
+/// Check that a file can be written into the output directory, as the archive is.
+fn archive_check(output_dir: &Path) -> Result<(), std::io::Error> {
+    let file_path = output_dir.join(format!(".self-test-{}", uuid::Uuid::new_v4()));
+    let result = std::fs::File::create(&file_path).and_then(|mut file| {
+        file.write_all(b"self-test")?;
+        file.sync_all()
+    });
+    let _ = std::fs::remove_file(&file_path);
+    result
+}
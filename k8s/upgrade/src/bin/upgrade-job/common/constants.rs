@@ -8,15 +8,40 @@ pub(crate) const UMBRELLA_CHART_NAME: &str = "openebs";
 /// This is the name of the Helm chart of this project.
 pub(crate) const CORE_CHART_NAME: &str = "mayastor";
 
+/// This is the environment variable which selects the storage driver of the helm commands.
+pub(crate) const HELM_DRIVER_ENV: &str = "HELM_DRIVER";
+
+/// This is the shared label of the secrets or configmaps which hold the helm release records.
+pub(crate) const HELM_RELEASE_RECORD_LABEL: &str = "owner=helm";
+
+/// This is the suffix of the <helm-release>-upgrade-rollback ConfigMap, which records the source
+/// of the last upgrade of the release.
+pub(crate) const ROLLBACK_POINT_NAME_SUFFIX: &str = "upgrade-rollback";
+
+/// This is the suffix of the <helm-release>-upgrade-status ConfigMap, which records the progress
+/// of the upgrade.
+pub(crate) const UPGRADE_STATUS_NAME_SUFFIX: &str = "upgrade-status";
+
+/// This is the suffix of the <helm-release>-upgrade-state ConfigMap, which records the components
+/// of the upgrade which are completed, for the upgrade to be resumed.
+pub(crate) const UPGRADE_STATE_NAME_SUFFIX: &str = "upgrade-state";
+
 /// This is the shared Pod label of the <helm-release>-io-engine DaemonSet.
 pub(crate) const IO_ENGINE_LABEL: &str = "app=io-engine";
 
+/// This is the port of the gRPC server of the io-engine.
+pub(crate) const IO_ENGINE_GRPC_PORT: u16 = 10124;
+
 /// This is the shared Pod label of the <helm-release>-agent-core Deployment.
 pub(crate) const AGENT_CORE_LABEL: &str = "app=agent-core";
 
 /// This is the shared label across the helm chart components which carries the chart version.
 pub(crate) const CHART_VERSION_LABEL_KEY: &str = "openebs.io/version";
 
+/// This is the annotation of the upgrade Job which pauses the data-plane upgrade, when set to
+/// "true".
+pub(crate) const UPGRADE_PAUSED_ANNOTATION: &str = "openebs.io/upgrade-paused";
+
 /// This is the label set on a storage API Node resource when a 'Node Drain' is issued.
 pub(crate) const DRAIN_FOR_UPGRADE: &str = "mayastor-upgrade";
 
@@ -140,6 +140,28 @@ pub(crate) enum Error {
     #[snafu(display("Failed to parse unsupported versions yaml: {}", source))]
     YamlParseBufferForUnsupportedVersion { source: serde_yaml::Error },
 
+    /// Error for when the data formats yaml could not be parsed from bytes.
+    #[snafu(display("Failed to parse data formats yaml: {}", source))]
+    YamlParseBufferForDataFormats { source: serde_yaml::Error },
+
+    /// Error for when the Kubernetes API request for listing CustomResourceDefinitions fails.
+    #[snafu(display("Failed to list CustomResourceDefinitions: {}", source))]
+    ListCrds { source: kube::Error },
+
+    /// Error for when the data written by the installed version can't be read by the version it's
+    /// downgraded to.
+    #[snafu(display(
+        "Failed to downgrade from {} to {}, as the data formats are incompatible: {}",
+        from_version,
+        to_version,
+        incompatibilities
+    ))]
+    IncompatibleDataFormats {
+        from_version: String,
+        to_version: String,
+        incompatibilities: String,
+    },
+
     /// Error for when the Helm chart installed in the cluster is not of the umbrella or core
     /// variant.
     #[snafu(display(
@@ -200,6 +222,18 @@ pub(crate) enum Error {
         pod_namespace: String,
     },
 
+    /// Error for when a Kubernetes API request for GET-ing the upgrade Job fails.
+    #[snafu(display("Failed to GET Kubernetes Job {}: {}", name, source))]
+    GetJob { source: kube::Error, name: String },
+
+    /// Error for when a Kubernetes API request for patching the upgrade Job fails.
+    #[snafu(display("Failed to patch Kubernetes Job {}: {}", name, source))]
+    PatchJob { source: kube::Error, name: String },
+
+    /// Error for when a Kubernetes API request for GET-ing a list of Nodes fails.
+    #[snafu(display("Failed to list Kubernetes Nodes: {}", source))]
+    ListK8sNodes { source: kube::Error },
+
     /// Error for when a Kubernetes API request for GET-ing a list of Pods filtered by label(s)
     /// fails.
     #[snafu(display(
@@ -282,6 +316,12 @@ pub(crate) enum Error {
     #[snafu(display("Failed to get {} Node {}", PRODUCT, node_id))]
     EmptyStorageNodeSpec { node_id: String },
 
+    /// Error for when a GET request for a list of storage pools fails.
+    #[snafu(display("Failed to list {} Pools: {}", PRODUCT, source))]
+    ListStoragePools {
+        source: openapi::tower::client::Error<openapi::models::RestJsonError>,
+    },
+
     /// Error for when a GET request for a list of storage volumes fails.
     #[snafu(display("Failed to list {} Volumes: {}", PRODUCT, source))]
     ListStorageVolumes {
@@ -346,6 +386,19 @@ pub(crate) enum Error {
         std_err: String,
     },
 
+    /// Error for when a Helm rollback command execution succeeds, but with an error.
+    #[snafu(display(
+        "`helm rollback` command return an error,\ncommand: {},\nargs: {:?},\nstd_err: {}",
+        command,
+        args,
+        std_err,
+    ))]
+    HelmRollbackCommand {
+        command: String,
+        args: Vec<String>,
+        std_err: String,
+    },
+
     /// Error for when a Helm get values command execution succeeds, but with an error.
     #[snafu(display(
         "`helm get values` command return an error,\ncommand: {},\nargs: {:?},\nstd_err: {}",
@@ -397,6 +450,15 @@ pub(crate) enum Error {
         version_string: String,
     },
 
+    /// Error for when the io-engines of some nodes don't report the target version once the
+    /// data-plane upgrade is complete.
+    #[snafu(display(
+        "The data-plane did not converge to version {}, nodes stuck on an older version: {}",
+        version,
+        nodes.join(", ")
+    ))]
+    DataPlaneNotConverged { version: String, nodes: Vec<String> },
+
     /// Error for when the detected upgrade path for PRODUCT is not supported.
     #[snafu(display("The upgrade path is invalid"))]
     InvalidUpgradePath,
@@ -503,7 +565,8 @@ pub(crate) enum Error {
 
     /// Error for when the helm upgrade's target version is lower the source version.
     #[snafu(display(
-        "Failed to upgrade from {} to {}: upgrade to an earlier-released version is forbidden",
+        "Failed to upgrade from {} to {}: upgrade to an earlier-released version is forbidden, \
+        unless '--allow-downgrade' is set",
         from_version,
         to_version
     ))]
@@ -512,6 +575,40 @@ pub(crate) enum Error {
         to_version: String,
     },
 
+    /// Error for when the image of a component which can't be overridden is overridden.
+    #[snafu(display(
+        "Cannot override the image of the unknown component '{}', expected one of: {}",
+        component,
+        components
+    ))]
+    UnknownImageOverrideComponent {
+        component: String,
+        components: String,
+    },
+
+    /// Error for when the image overriding the one of a component is not tagged with a version.
+    #[snafu(display(
+        "The image '{}' overriding the one of the component '{}' is not tagged with a version",
+        image,
+        component
+    ))]
+    ImageOverrideNotTagged { component: String, image: String },
+
+    /// Error for when the image overriding the one of a component is outside of the
+    /// compatibility range of the target version.
+    #[snafu(display(
+        "The image '{}' overriding the one of the component '{}' is not compatible with the \
+        target version {}, its tag must be a version of the same minor release which isn't older",
+        image,
+        component,
+        to_version
+    ))]
+    IncompatibleImageOverride {
+        component: String,
+        image: String,
+        to_version: String,
+    },
+
     /// Error for when yq command execution fails.
     #[snafu(display(
         "Failed to run yq command,\ncommand: {},\nargs: {:?},\ncommand_error: {}",
@@ -609,6 +706,75 @@ pub(crate) enum Error {
         source: std::io::Error,
         path: PathBuf,
     },
+
+    /// Error for when a Kubernetes API request for listing the records of a helm release fails.
+    #[snafu(display(
+        "Failed to list the helm release records of kind {} with label '{}': {}",
+        kind,
+        label,
+        source
+    ))]
+    ListHelmReleaseRecords {
+        source: kube::Error,
+        kind: String,
+        label: String,
+    },
+
+    /// Error for when a Kubernetes API request for deleting a record of a helm release fails.
+    #[snafu(display(
+        "Failed to delete the helm release record {} '{}': {}",
+        kind,
+        name,
+        source
+    ))]
+    DeleteHelmReleaseRecord {
+        source: kube::Error,
+        kind: String,
+        name: String,
+    },
+
+    /// Error for when the source of the upgrade can't be recorded in a ConfigMap.
+    #[snafu(display(
+        "Failed to record the rollback point in ConfigMap '{}': {}",
+        name,
+        source
+    ))]
+    RecordRollbackPoint { source: kube::Error, name: String },
+
+    /// Error for when the progress of the upgrade can't be recorded in its status ConfigMap.
+    #[snafu(display("Failed to record the upgrade status ConfigMap '{}': {}", name, source))]
+    RecordUpgradeStatus { source: kube::Error, name: String },
+
+    /// Error for when the ConfigMap of the rollback point can't be fetched.
+    #[snafu(display("Failed to GET the rollback point ConfigMap '{}': {}", name, source))]
+    GetRollbackPoint { source: kube::Error, name: String },
+
+    /// Error for when no rollback point has been recorded, i.e. no upgrade which changed the
+    /// helm release has been run.
+    #[snafu(display(
+        "No rollback point found in ConfigMap '{}' in namespace '{}', the release can only be \
+        rolled back after an upgrade",
+        name,
+        namespace
+    ))]
+    RollbackPointNotFound { name: String, namespace: String },
+
+    /// Error for when the ConfigMap of the rollback point lacks one of its keys.
+    #[snafu(display("Rollback point ConfigMap '{}' has no '{}' key", name, key))]
+    RollbackPointKeyMissing { name: String, key: String },
+
+    /// Error for when the ConfigMap of the upgrade state can't be fetched.
+    #[snafu(display("Failed to GET the upgrade state ConfigMap '{}': {}", name, source))]
+    GetUpgradeState { source: kube::Error, name: String },
+
+    /// Error for when the completed components of the upgrade can't be recorded in its state
+    /// ConfigMap.
+    #[snafu(display("Failed to record the upgrade state ConfigMap '{}': {}", name, source))]
+    RecordUpgradeState { source: kube::Error, name: String },
+
+    /// Error for when the ConfigMap of the upgrade state can't be deleted.
+    #[snafu(display("Failed to DELETE the upgrade state ConfigMap '{}': {}", name, source))]
+    ClearUpgradeState { source: kube::Error, name: String },
 }
 
 /// A wrapper type to remove repeated Result<T, Error> returns.
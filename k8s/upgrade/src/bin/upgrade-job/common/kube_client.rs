@@ -2,7 +2,8 @@ use crate::common::error::{K8sClientGeneration, KubeClientSetBuilderNs, Result};
 use k8s_openapi::{
     api::{
         apps::v1::Deployment,
-        core::v1::{Namespace, Pod},
+        batch::v1::Job,
+        core::v1::{ConfigMap, Namespace, Node, Pod},
     },
     apiextensions_apiserver::pkg::apis::apiextensions::v1::CustomResourceDefinition,
 };
@@ -39,6 +40,9 @@ impl KubeClientSetBuilder {
             pods_api: Api::namespaced(client.clone(), namespace.as_str()),
             namespaces_api: Api::all(client.clone()),
             deployments_api: Api::namespaced(client.clone(), namespace.as_str()),
+            config_maps_api: Api::namespaced(client.clone(), namespace.as_str()),
+            jobs_api: Api::namespaced(client.clone(), namespace.as_str()),
+            nodes_api: Api::all(client.clone()),
             crd_api: Api::all(client),
         });
     }
@@ -50,6 +54,9 @@ pub(crate) struct KubeClientSet {
     pods_api: Api<Pod>,
     namespaces_api: Api<Namespace>,
     deployments_api: Api<Deployment>,
+    config_maps_api: Api<ConfigMap>,
+    jobs_api: Api<Job>,
+    nodes_api: Api<Node>,
     crd_api: Api<CustomResourceDefinition>,
 }
 
@@ -73,6 +80,21 @@ impl KubeClientSet {
         &self.deployments_api
     }
 
+    /// Generate the ConfigMap api client.
+    pub(crate) fn config_maps_api(&self) -> &Api<ConfigMap> {
+        &self.config_maps_api
+    }
+
+    /// Generate the Job api client.
+    pub(crate) fn jobs_api(&self) -> &Api<Job> {
+        &self.jobs_api
+    }
+
+    /// Generate the Node api client.
+    pub(crate) fn nodes_api(&self) -> &Api<Node> {
+        &self.nodes_api
+    }
+
     /// Generate the CustomResourceDefinition api client.
     pub(crate) fn crd_api(&self) -> &Api<CustomResourceDefinition> {
         &self.crd_api
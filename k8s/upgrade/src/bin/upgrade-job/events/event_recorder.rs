@@ -1,16 +1,32 @@
-use crate::common::{
-    constants::PRODUCT,
-    error::{
-        EventChannelSend, EventPublish, EventRecorderOptionsAbsent, GetPod, JobPodHasTooManyOwners,
-        JobPodOwnerIsNotJob, JobPodOwnerNotFound, Result, SerializeEventNote,
+use crate::{
+    common::{
+        constants::PRODUCT,
+        error::{
+            EventChannelSend, EventPublish, EventRecorderOptionsAbsent, GetPod,
+            JobPodHasTooManyOwners, JobPodOwnerIsNotJob, JobPodOwnerNotFound, Result,
+            SerializeEventNote,
+        },
+        kube_client::KubeClientSet,
     },
-    kube_client::KubeClientSet,
+    helm::images::ImageOverride,
+    upgrade::{
+        convergence::ConvergenceStatus,
+        health::HealthSnapshot,
+        progress::{record_progress, upgrade_status_name, UpgradeProgress},
+        rollback::RollbackStatus,
+    },
+};
+use k8s_openapi::{
+    api::core::v1::{ConfigMap, ObjectReference},
+    serde_json,
+};
+use kube::{
+    runtime::events::{Event, EventType, Recorder},
+    Api,
 };
-use k8s_openapi::{api::core::v1::ObjectReference, serde_json};
-use kube::runtime::events::{Event, EventType, Recorder};
 use serde::Serialize;
 use snafu::{ensure, ResultExt};
-use std::{fmt::Display, time::Duration};
+use std::{fmt::Display, sync::Mutex, time::Duration};
 use tokio::{select, sync::mpsc, time::sleep};
 use tracing::error;
 
@@ -20,6 +36,17 @@ pub(crate) struct EventNote {
     from_version: String,
     to_version: String,
     message: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    image_overrides: Vec<ImageOverride>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    health_before: Option<HealthSnapshot>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    health_after: Option<HealthSnapshot>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    convergence: Option<ConvergenceStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rollback: Option<RollbackStatus>,
+    progress: UpgradeProgress,
 }
 
 impl From<&EventRecorder> for EventNote {
@@ -28,6 +55,12 @@ impl From<&EventRecorder> for EventNote {
             from_version: er.from_version.clone(),
             to_version: er.to_version.clone(),
             message: Default::default(),
+            image_overrides: er.image_overrides.clone(),
+            health_before: er.health_before.clone(),
+            health_after: er.health_after.clone(),
+            convergence: er.convergence.clone(),
+            rollback: er.rollback.clone(),
+            progress: er.progress(),
         }
     }
 }
@@ -44,6 +77,7 @@ impl EventNote {
 pub(crate) struct EventRecorderBuilder {
     pod_name: Option<String>,
     namespace: Option<String>,
+    release_name: Option<String>,
     from_version: Option<String>,
     to_version: Option<String>,
 }
@@ -60,6 +94,17 @@ impl EventRecorderBuilder {
         self
     }
 
+    /// This is a builder option to add the name of the helm release. The progress of the upgrade
+    /// is recorded in the upgrade status ConfigMap of the release, when it's set.
+    #[must_use]
+    pub(crate) fn with_release_name<T>(mut self, release_name: T) -> Self
+    where
+        T: ToString,
+    {
+        self.release_name = Some(release_name.to_string());
+        self
+    }
+
     /// This is a builder option to add the name of this Pod. The owner Job of this Pod
     /// will be the object whose events the publisher will create.
     #[must_use]
@@ -131,6 +176,7 @@ impl EventRecorderBuilder {
             .fail(),
         }?;
 
+        let job_name = pod_owner.name.clone();
         let job_obj_ref = ObjectReference {
             api_version: Some(pod_owner.api_version),
             kind: Some(pod_owner.kind),
@@ -141,6 +187,9 @@ impl EventRecorderBuilder {
             resource_version: None,
         };
 
+        let config_maps_api = k8s_client.config_maps_api().clone();
+        let status_name = self.release_name.as_deref().map(upgrade_status_name);
+
         let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
 
         let event_loop_handle = tokio::spawn(async move {
@@ -173,8 +222,17 @@ impl EventRecorderBuilder {
         Ok(EventRecorder {
             event_sender: Some(tx),
             event_loop_handle,
+            job_name,
             from_version,
             to_version,
+            image_overrides: Vec::new(),
+            health_before: None,
+            health_after: None,
+            convergence: None,
+            rollback: None,
+            progress: Mutex::new(UpgradeProgress::default()),
+            config_maps_api,
+            status_name,
         })
     }
 }
@@ -183,8 +241,17 @@ impl EventRecorderBuilder {
 pub(crate) struct EventRecorder {
     event_sender: Option<mpsc::UnboundedSender<Event>>,
     event_loop_handle: tokio::task::JoinHandle<()>,
+    job_name: String,
     from_version: String,
     to_version: String,
+    image_overrides: Vec<ImageOverride>,
+    health_before: Option<HealthSnapshot>,
+    health_after: Option<HealthSnapshot>,
+    convergence: Option<ConvergenceStatus>,
+    rollback: Option<RollbackStatus>,
+    progress: Mutex<UpgradeProgress>,
+    config_maps_api: Api<ConfigMap>,
+    status_name: Option<String>,
 }
 
 impl EventRecorder {
@@ -209,6 +276,7 @@ impl EventRecorder {
         J: ToString,
         K: ToString,
     {
+        self.update_progress(|progress| progress.set_phase(action.to_string()));
         let note = EventNote::from(self).with_message(note.to_string());
         let note_s = serde_json::to_string(&note).context(SerializeEventNote { note })?;
         self.publish(Event {
@@ -218,7 +286,9 @@ impl EventRecorder {
             action: action.to_string(),
             secondary: None,
         })
-        .await
+        .await?;
+        self.record_progress().await;
+        Ok(())
     }
 
     /// This is a helper method with calls the publish method above and fills out the boilerplate
@@ -228,6 +298,10 @@ impl EventRecorder {
         J: ToString,
         K: ToString,
     {
+        self.update_progress(|progress| {
+            progress.set_phase(action.to_string());
+            progress.set_last_error(note.to_string());
+        });
         let note = EventNote::from(self).with_message(note.to_string());
         let note_s = serde_json::to_string(&note).context(SerializeEventNote { note })?;
         self.publish(Event {
@@ -237,7 +311,9 @@ impl EventRecorder {
             action: action.to_string(),
             secondary: None,
         })
-        .await
+        .await?;
+        self.record_progress().await;
+        Ok(())
     }
 
     /// This method is intended for use when upgrade fails.
@@ -256,6 +332,22 @@ impl EventRecorder {
             .map_err(|error| error!(%error, "Failed to upgrade {PRODUCT}"));
     }
 
+    /// This method is intended for use when the rollback of the release fails.
+    pub(crate) async fn publish_rollback_failure<Error>(&self, err: &Error, validation_error: bool)
+    where
+        Error: Display,
+    {
+        let action = if validation_error {
+            EventAction::ValidationFailed
+        } else {
+            EventAction::RollbackFailed
+        };
+        let _ = self
+            .publish_warning(format!("Failed to roll back: {err}"), action)
+            .await
+            .map_err(|error| error!(%error, "Failed to roll back {PRODUCT}"));
+    }
+
     /// Shuts down the event channel which makes the event loop worker exit its loop and return.
     pub(crate) async fn shutdown_worker(mut self) {
         // Dropping the sender, to signify no more channel messages.
@@ -265,6 +357,11 @@ impl EventRecorder {
         let _ = self.event_loop_handle.await;
     }
 
+    /// This returns the name of the Job which owns this Pod, i.e. the upgrade Job.
+    pub(crate) fn job_name(&self) -> &str {
+        self.job_name.as_str()
+    }
+
     /// Updates the EventRecorder's from_version memeber with a new value.
     pub(crate) fn set_from_version(&mut self, version: String) {
         self.from_version = version
@@ -274,6 +371,75 @@ impl EventRecorder {
     pub(crate) fn set_to_version(&mut self, version: String) {
         self.to_version = version
     }
+
+    /// Updates the EventRecorder's images overriding the ones of the chart's components.
+    pub(crate) fn set_image_overrides(&mut self, image_overrides: Vec<ImageOverride>) {
+        self.image_overrides = image_overrides
+    }
+
+    /// Updates the EventRecorder's health snapshot from before the upgrade.
+    pub(crate) fn set_health_before(&mut self, snapshot: Option<HealthSnapshot>) {
+        self.health_before = snapshot
+    }
+
+    /// Updates the EventRecorder's health snapshot from after the upgrade.
+    pub(crate) fn set_health_after(&mut self, snapshot: Option<HealthSnapshot>) {
+        self.health_after = snapshot
+    }
+
+    /// Updates the EventRecorder's version convergence status of the data-plane.
+    pub(crate) fn set_convergence(&mut self, status: Option<ConvergenceStatus>) {
+        self.convergence = status
+    }
+
+    /// Updates the EventRecorder's status of the rollback of the release.
+    pub(crate) fn set_rollback(&mut self, status: Option<RollbackStatus>) {
+        self.rollback = status
+    }
+
+    /// Updates the EventRecorder's component which is being upgraded. It's recorded along with
+    /// the next event.
+    pub(crate) fn set_component<T>(&self, component: Option<T>)
+    where
+        T: ToString,
+    {
+        self.update_progress(|progress| {
+            progress.set_component(component.map(|component| component.to_string()))
+        });
+    }
+
+    /// Updates the EventRecorder's number of upgraded io-engine nodes, out of the total. It's
+    /// recorded along with the next event.
+    pub(crate) fn set_nodes_progress(&self, nodes_upgraded: usize, nodes_total: usize) {
+        self.update_progress(|progress| progress.set_nodes(nodes_upgraded, nodes_total));
+    }
+
+    /// This returns the progress of the upgrade.
+    fn progress(&self) -> UpgradeProgress {
+        self.progress
+            .lock()
+            .map(|progress| progress.clone())
+            .unwrap_or_default()
+    }
+
+    /// Updates the progress of the upgrade. The progress is shared with the tasks of the upgrade
+    /// which only borrow the EventRecorder.
+    fn update_progress<F>(&self, update: F)
+    where
+        F: FnOnce(&mut UpgradeProgress),
+    {
+        if let Ok(mut progress) = self.progress.lock() {
+            update(&mut progress);
+        }
+    }
+
+    /// Record the progress of the upgrade in the upgrade status ConfigMap, if the helm release is
+    /// known.
+    async fn record_progress(&self) {
+        if let Some(name) = &self.status_name {
+            record_progress(&self.config_maps_api, name.as_str(), &self.progress()).await;
+        }
+    }
 }
 
 /// current volume status
@@ -291,8 +457,20 @@ pub enum EventAction {
     UpgradingDP,
     #[serde(rename = "Upgraded data-plane")]
     UpgradedDP,
+    #[serde(rename = "Verifying data-plane")]
+    VerifyingDP,
+    #[serde(rename = "Paused data-plane")]
+    PausedDP,
+    #[serde(rename = "Resumed data-plane")]
+    ResumedDP,
     #[serde(rename = "Successful")]
     Successful,
+    #[serde(rename = "Rolling back")]
+    RollingBack,
+    #[serde(rename = "Rolled back")]
+    RolledBack,
+    #[serde(rename = "Rollback Failed")]
+    RollbackFailed,
 }
 
 impl ToString for EventAction {
@@ -304,7 +482,13 @@ impl ToString for EventAction {
             Self::UpgradedCP => String::from("Upgraded control-plane"),
             Self::UpgradingDP => String::from("Upgrading data-plane"),
             Self::UpgradedDP => String::from("Upgraded data-plane"),
+            Self::VerifyingDP => String::from("Verifying data-plane"),
+            Self::PausedDP => String::from("Paused data-plane"),
+            Self::ResumedDP => String::from("Resumed data-plane"),
             Self::Successful => String::from("Successful"),
+            Self::RollingBack => String::from("Rolling back"),
+            Self::RolledBack => String::from("Rolled back"),
+            Self::RollbackFailed => String::from("Rollback Failed"),
         }
     }
 }
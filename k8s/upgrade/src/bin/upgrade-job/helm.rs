@@ -1,6 +1,12 @@
 /// Contains the HelmReleaseClient. Used for interacting with installed helm chart releases.
 pub(crate) mod client;
 
+/// Contains the detection of the helm storage driver and the pruning of the release history.
+pub(crate) mod history;
+
+/// Contains the overrides of the container images of the helm chart's components.
+pub(crate) mod images;
+
 /// Contains helm chart upgrade logic.
 pub(crate) mod upgrade;
 
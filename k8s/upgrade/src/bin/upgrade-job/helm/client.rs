@@ -2,9 +2,9 @@ use crate::{
     common::{
         error::{
             CollectDirEntries, CreateCrd, HelmClientNs, HelmCommand, HelmGetValuesCommand,
-            HelmListCommand, HelmRelease, HelmUpgradeCommand, InvalidHelmChartCrdDir,
-            ReadingDirectoryContents, ReadingFile, Result, U8VectorToString, YamlParseFromFile,
-            YamlParseFromSlice,
+            HelmListCommand, HelmRelease, HelmRollbackCommand, HelmUpgradeCommand,
+            InvalidHelmChartCrdDir, ReadingDirectoryContents, ReadingFile, Result,
+            U8VectorToString, YamlParseFromFile, YamlParseFromSlice,
         },
         kube_client::KubeClientSet,
     },
@@ -22,14 +22,19 @@ use std::{
     path::{Path, PathBuf},
     process::Command,
     str,
+    time::Duration,
 };
 use tracing::{debug, info};
 
+/// The time to wait for the helm upgrade and the helm rollback to complete, by default.
+const DEFAULT_HELM_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
 /// This struct is used to deserialize the output of `helm list -n <namespace> --deployed -o yaml`.
 #[derive(Clone, Deserialize)]
 pub(crate) struct HelmReleaseElement {
     name: String,
     chart: String,
+    revision: String,
 }
 
 impl HelmReleaseElement {
@@ -42,12 +47,17 @@ impl HelmReleaseElement {
     pub(crate) fn chart(&self) -> String {
         self.chart.clone()
     }
+    /// This is a getter function for the deployed revision of the release.
+    pub(crate) fn revision(&self) -> String {
+        self.revision.clone()
+    }
 }
 
 /// This is a builder for HelmReleaseClient.
 #[derive(Default)]
 pub(crate) struct HelmReleaseClientBuilder {
     namespace: Option<String>,
+    timeout: Option<Duration>,
 }
 
 impl HelmReleaseClientBuilder {
@@ -62,10 +72,21 @@ impl HelmReleaseClientBuilder {
         self
     }
 
+    /// This is a builder option to set the time to wait for the helm upgrade and the helm
+    /// rollback to complete. This is 15 minutes by default.
+    #[must_use]
+    pub(crate) fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
     /// Build the HelmReleaseClient.
     pub(crate) fn build(self) -> Result<HelmReleaseClient> {
         let ns = self.namespace.ok_or(HelmClientNs.build())?;
-        Ok(HelmReleaseClient { namespace: ns })
+        Ok(HelmReleaseClient {
+            namespace: ns,
+            timeout: self.timeout.unwrap_or(DEFAULT_HELM_TIMEOUT),
+        })
     }
 }
 
@@ -74,6 +95,7 @@ impl HelmReleaseClientBuilder {
 #[derive(Clone)]
 pub(crate) struct HelmReleaseClient {
     pub(crate) namespace: String,
+    timeout: Duration,
 }
 
 impl HelmReleaseClient {
@@ -82,6 +104,12 @@ impl HelmReleaseClient {
         HelmReleaseClientBuilder::default()
     }
 
+    /// The value of the `--timeout` argument of the helm commands, in seconds, as helm parses
+    /// it as a Go duration.
+    fn timeout_arg(&self) -> String {
+        format!("{}s", self.timeout.as_secs())
+    }
+
     /// Runs command `helm get values -n <namespace> <release_name> --all -o yaml`.
     pub(crate) fn get_values_as_yaml<A, B>(
         &self,
@@ -221,7 +249,7 @@ impl HelmReleaseClient {
             "-n",
             self.namespace.as_str(),
             "--timeout",
-            "15m"
+            self.timeout_arg()
         ];
 
         // Extra args
@@ -257,6 +285,49 @@ impl HelmReleaseClient {
         Ok(())
     }
 
+    /// Runs command `helm rollback -n <namespace> <release_name> <revision> --wait`.
+    pub(crate) fn rollback<A, B>(&self, release_name: A, revision: B) -> Result<()>
+    where
+        A: ToString,
+        B: ToString,
+    {
+        let command: &str = "helm";
+        let args: Vec<String> = vec_to_strings![
+            "rollback",
+            release_name,
+            revision,
+            "-n",
+            self.namespace.as_str(),
+            "--wait",
+            "--timeout",
+            self.timeout_arg()
+        ];
+
+        debug!(%command, ?args, "Helm rollback command");
+        let output = Command::new(command)
+            .args(args.clone())
+            .output()
+            .context(HelmCommand {
+                command: command.to_string(),
+                args: args.clone(),
+            })?;
+
+        let stdout_str = str::from_utf8(output.stdout.as_slice()).context(U8VectorToString)?;
+        debug!(stdout=%stdout_str, "Helm rollback command standard output");
+        ensure!(
+            output.status.success(),
+            HelmRollbackCommand {
+                command: command.to_string(),
+                args,
+                std_err: str::from_utf8(output.stderr.as_slice())
+                    .context(U8VectorToString)?
+                    .to_string()
+            }
+        );
+
+        Ok(())
+    }
+
     /// Fetches info about a Helm release in the Namespace, if it exists.
     pub(crate) fn release_info<A>(&self, release_name: A) -> Result<HelmReleaseElement>
     where
@@ -0,0 +1,197 @@
+use crate::common::{
+    constants::{HELM_DRIVER_ENV, HELM_RELEASE_RECORD_LABEL},
+    error::{DeleteHelmReleaseRecord, ListHelmReleaseRecords, Result},
+    kube_client::KubeClientSet,
+};
+use k8s_openapi::{
+    api::core::v1::{ConfigMap, Secret},
+    NamespaceResourceScope,
+};
+use kube::{
+    api::{DeleteParams, ListParams},
+    Api, Resource, ResourceExt,
+};
+use serde::de::DeserializeOwned;
+use snafu::ResultExt;
+use std::fmt::Debug;
+use tracing::{info, warn};
+
+/// This is the storage backend in which helm keeps the records of the revisions of a release.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum HelmStorageDriver {
+    #[default]
+    Secrets,
+    ConfigMaps,
+}
+
+impl HelmStorageDriver {
+    /// This is the value of the HELM_DRIVER environment variable which selects the driver.
+    pub(crate) fn env_value(&self) -> &'static str {
+        match self {
+            Self::Secrets => "secret",
+            Self::ConfigMaps => "configmap",
+        }
+    }
+
+    /// This is the storage driver selected by the HELM_DRIVER environment variable, if it is
+    /// one whose records are Kubernetes objects.
+    fn from_env() -> Option<Self> {
+        match std::env::var(HELM_DRIVER_ENV).as_deref() {
+            Err(_) | Ok("") | Ok("secret") | Ok("secrets") => Some(Self::Secrets),
+            Ok("configmap") | Ok("configmaps") => Some(Self::ConfigMaps),
+            Ok(_) => None,
+        }
+    }
+}
+
+/// This is the record of a revision of a helm release.
+struct ReleaseRecord {
+    name: String,
+    revision: u32,
+    status: String,
+}
+
+impl ReleaseRecord {
+    /// The deployed revision and the ones with an operation in progress must never be pruned.
+    fn is_prunable(&self) -> bool {
+        self.status != "deployed" && !self.status.starts_with("pending")
+    }
+}
+
+/// Detect the storage backend which holds the records of the helm release. Helm defaults to
+/// secrets, configmaps are used by releases installed with HELM_DRIVER=configmap.
+pub(crate) async fn detect_storage_driver(
+    namespace: &str,
+    release_name: &str,
+) -> Result<HelmStorageDriver> {
+    let k8s_client = KubeClientSet::builder()
+        .with_namespace(namespace)
+        .build()
+        .await?;
+    let client = k8s_client.client();
+
+    if !list_records::<Secret>(Api::namespaced(client.clone(), namespace), release_name)
+        .await?
+        .is_empty()
+    {
+        return Ok(HelmStorageDriver::Secrets);
+    }
+    if !list_records::<ConfigMap>(Api::namespaced(client, namespace), release_name)
+        .await?
+        .is_empty()
+    {
+        return Ok(HelmStorageDriver::ConfigMaps);
+    }
+    warn!(
+        release_name,
+        "No helm release records found, assuming the default storage driver"
+    );
+    Ok(HelmStorageDriver::default())
+}
+
+/// Select the storage driver for the helm commands run by this process, unless the
+/// HELM_DRIVER environment variable has been set explicitly.
+pub(crate) fn use_storage_driver(driver: HelmStorageDriver) {
+    if std::env::var_os(HELM_DRIVER_ENV).is_none() {
+        std::env::set_var(HELM_DRIVER_ENV, driver.env_value());
+    }
+    info!(driver = ?driver, "Using helm storage driver");
+}
+
+/// Delete the records of the oldest revisions of the helm release from the storage driver in
+/// use, so that at most `history_max` revisions are kept. The deployed revision and the ones
+/// with an operation in progress are always kept. This returns the number of deleted records.
+pub(crate) async fn prune_release_history(
+    namespace: &str,
+    release_name: &str,
+    history_max: u32,
+) -> Result<usize> {
+    let Some(driver) = HelmStorageDriver::from_env() else {
+        warn!("Skipping the pruning of the helm release history, unsupported storage driver");
+        return Ok(0);
+    };
+    let k8s_client = KubeClientSet::builder()
+        .with_namespace(namespace)
+        .build()
+        .await?;
+    let client = k8s_client.client();
+    match driver {
+        HelmStorageDriver::Secrets => {
+            prune_records::<Secret>(
+                Api::namespaced(client, namespace),
+                release_name,
+                history_max,
+            )
+            .await
+        }
+        HelmStorageDriver::ConfigMaps => {
+            prune_records::<ConfigMap>(
+                Api::namespaced(client, namespace),
+                release_name,
+                history_max,
+            )
+            .await
+        }
+    }
+}
+
+/// Delete the prunable records beyond the `history_max` newest revisions.
+async fn prune_records<K>(api: Api<K>, release_name: &str, history_max: u32) -> Result<usize>
+where
+    K: Resource<Scope = NamespaceResourceScope> + Clone + DeserializeOwned + Debug,
+    <K as Resource>::DynamicType: Default,
+{
+    let mut records = list_records(api.clone(), release_name).await?;
+    // Newest revisions first.
+    records.sort_unstable_by(|a, b| b.revision.cmp(&a.revision));
+
+    let mut pruned = 0;
+    for record in records
+        .iter()
+        .skip(history_max as usize)
+        .filter(|record| record.is_prunable())
+    {
+        api.delete(&record.name, &DeleteParams::default())
+            .await
+            .context(DeleteHelmReleaseRecord {
+                name: record.name.clone(),
+                kind: K::kind(&Default::default()).to_string(),
+            })?;
+        info!(
+            record = record.name,
+            revision = record.revision,
+            status = record.status,
+            "Pruned helm release revision"
+        );
+        pruned += 1;
+    }
+    Ok(pruned)
+}
+
+/// List the records of the revisions of the helm release.
+async fn list_records<K>(api: Api<K>, release_name: &str) -> Result<Vec<ReleaseRecord>>
+where
+    K: Resource<Scope = NamespaceResourceScope> + Clone + DeserializeOwned + Debug,
+    <K as Resource>::DynamicType: Default,
+{
+    let label = format!("{HELM_RELEASE_RECORD_LABEL},name={release_name}");
+    let objects = api
+        .list(&ListParams::default().labels(label.as_str()))
+        .await
+        .context(ListHelmReleaseRecords {
+            label: label.clone(),
+            kind: K::kind(&Default::default()).to_string(),
+        })?;
+    Ok(objects
+        .items
+        .iter()
+        .filter_map(|object| {
+            let labels = object.labels();
+            Some(ReleaseRecord {
+                name: object.name_any(),
+                revision: labels.get("version")?.parse().ok()?,
+                status: labels.get("status").cloned().unwrap_or_default(),
+            })
+        })
+        .collect())
+}
@@ -0,0 +1,106 @@
+use crate::common::error::{
+    ImageOverrideNotTagged, IncompatibleImageOverride, Result, UnknownImageOverrideComponent,
+};
+use semver::Version;
+use serde::Serialize;
+use snafu::ensure;
+use std::str::FromStr;
+
+/// These are the components of the Core chart whose container image can be overridden, i.e. the
+/// keys of the chart's image.overrides map.
+pub(crate) const OVERRIDABLE_COMPONENTS: [&str; 11] = [
+    "agent-core",
+    "agent-ha-cluster",
+    "agent-ha-node",
+    "api-rest",
+    "csi-controller",
+    "csi-node",
+    "io-engine",
+    "metrics-exporter-io-engine",
+    "obs-callhome",
+    "obs-callhome-stats",
+    "operator-diskpool",
+];
+
+/// This is a container image which replaces the one of a component of the Core chart on
+/// upgrade, eg: a hotfixed image, instead of requiring a custom chart build.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub(crate) struct ImageOverride {
+    /// The component whose image is overridden, eg: agent-core.
+    component: String,
+    /// The image reference, eg: docker.io/openebs/mayastor-agent-core:v2.5.1-hotfix.1.
+    image: String,
+}
+
+impl FromStr for ImageOverride {
+    type Err = String;
+
+    /// Parses an override of the form <component>=<image>.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('=') {
+            Some((component, image)) if !component.is_empty() && !image.is_empty() => Ok(Self {
+                component: component.to_string(),
+                image: image.to_string(),
+            }),
+            _ => Err(format!(
+                "invalid image override '{s}', expected <component>=<image>"
+            )),
+        }
+    }
+}
+
+impl ImageOverride {
+    /// Validates that the component's image can be overridden, and that the image is within the
+    /// compatibility range of the target version, i.e. its tag is a version of the same minor
+    /// release, which isn't older than the target version's patch release.
+    /// Pre-release tags, eg: v2.5.1-hotfix.1, are accepted.
+    pub(crate) fn validate(&self, to_version: &Version) -> Result<()> {
+        ensure!(
+            OVERRIDABLE_COMPONENTS.contains(&self.component.as_str()),
+            UnknownImageOverrideComponent {
+                component: self.component.clone(),
+                components: OVERRIDABLE_COMPONENTS.join(", "),
+            }
+        );
+
+        let version = self.tag_version().ok_or(
+            ImageOverrideNotTagged {
+                component: self.component.clone(),
+                image: self.image.clone(),
+            }
+            .build(),
+        )?;
+
+        ensure!(
+            version.major == to_version.major
+                && version.minor == to_version.minor
+                && version.patch >= to_version.patch,
+            IncompatibleImageOverride {
+                component: self.component.clone(),
+                image: self.image.clone(),
+                to_version: to_version.to_string(),
+            }
+        );
+
+        Ok(())
+    }
+
+    /// This returns the `helm upgrade --set` value which overrides the component's image.
+    pub(crate) fn helm_set_value(&self) -> String {
+        format!("image.overrides.{}={}", self.component, self.image)
+    }
+
+    /// This returns the version of the image's tag, eg: 2.5.1-hotfix.1 for
+    /// docker.io/openebs/mayastor-agent-core:v2.5.1-hotfix.1, if it is tagged with one.
+    fn tag_version(&self) -> Option<Version> {
+        // The digest, if any, follows the tag.
+        let reference = self.image.split('@').next().unwrap_or_default();
+        // A ':' before the last '/' is the port of the registry.
+        let (_, tag) = reference.rsplit_once(':')?;
+        if tag.contains('/') {
+            return None;
+        }
+        Version::parse(tag.strip_prefix('v').unwrap_or(tag)).ok()
+    }
+}
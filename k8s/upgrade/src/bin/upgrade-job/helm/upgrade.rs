@@ -7,13 +7,17 @@ use crate::{
             RollbackForbidden, UmbrellaChartNotUpgraded,
         },
     },
-    helm::{client::HelmReleaseClient, values::generate_values_yaml_file},
+    helm::{
+        client::HelmReleaseClient,
+        images::ImageOverride,
+        values::{generate_values_yaml_file, values_diff},
+    },
     upgrade, vec_to_strings,
 };
 use regex::Regex;
 use semver::Version;
 use snafu::{ensure, ResultExt};
-use std::{future::Future, path::PathBuf, pin::Pin};
+use std::{future::Future, path::PathBuf, pin::Pin, time::Duration};
 use tempfile::NamedTempFile as TempFile;
 use tracing::info;
 
@@ -26,6 +30,44 @@ pub(crate) enum HelmChart {
     Core,
 }
 
+/// This is how the helm upgrade waits for the resources of the release.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct HelmWait {
+    /// The upgrade is rolled back by helm if it fails or times out. This implies waiting for the
+    /// resources to be ready.
+    pub(crate) atomic: bool,
+    /// The upgrade waits for the resources to be ready.
+    pub(crate) wait: bool,
+    /// The upgrade waits for the Jobs to complete.
+    pub(crate) wait_for_jobs: bool,
+}
+
+impl Default for HelmWait {
+    fn default() -> Self {
+        Self {
+            atomic: true,
+            wait: false,
+            wait_for_jobs: false,
+        }
+    }
+}
+
+impl HelmWait {
+    /// The helm upgrade arguments for the wait behaviour.
+    fn args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if self.atomic {
+            args.push("--atomic".to_string());
+        } else if self.wait {
+            args.push("--wait".to_string());
+        }
+        if self.wait_for_jobs {
+            args.push("--wait-for-jobs".to_string());
+        }
+        args
+    }
+}
+
 /// This is a builder for the Helm chart upgrade.
 #[derive(Default)]
 pub(crate) struct HelmUpgradeBuilder {
@@ -33,8 +75,14 @@ pub(crate) struct HelmUpgradeBuilder {
     namespace: Option<String>,
     core_chart_dir: Option<PathBuf>,
     skip_upgrade_path_validation: bool,
+    allow_downgrade: bool,
     helm_args_set: Option<String>,
     helm_args_set_file: Option<String>,
+    helm_args_values: Vec<PathBuf>,
+    helm_history_max: Option<u32>,
+    helm_timeout: Option<Duration>,
+    helm_wait: HelmWait,
+    image_overrides: Vec<ImageOverride>,
 }
 
 impl HelmUpgradeBuilder {
@@ -75,6 +123,13 @@ impl HelmUpgradeBuilder {
         self
     }
 
+    /// This sets the flag to allow the upgrade to an earlier version.
+    #[must_use]
+    pub(crate) fn with_allow_downgrade(mut self, allow_downgrade: bool) -> Self {
+        self.allow_downgrade = allow_downgrade;
+        self
+    }
+
     /// This is a builder option to add set flags set during upgrade.
     #[must_use]
     pub(crate) fn with_helm_args_set<J>(mut self, helm_args_set: J) -> Self
@@ -95,6 +150,43 @@ impl HelmUpgradeBuilder {
         self
     }
 
+    /// This is a builder option to add the values files set during upgrade.
+    #[must_use]
+    pub(crate) fn with_helm_args_values(mut self, helm_args_values: Vec<PathBuf>) -> Self {
+        self.helm_args_values = helm_args_values;
+        self
+    }
+
+    /// This is a builder option to set the maximum number of revisions of the helm release which
+    /// helm keeps on upgrade.
+    #[must_use]
+    pub(crate) fn with_helm_history_max(mut self, helm_history_max: Option<u32>) -> Self {
+        self.helm_history_max = helm_history_max;
+        self
+    }
+
+    /// This is a builder option to set the time to wait for the helm upgrade to complete.
+    #[must_use]
+    pub(crate) fn with_helm_timeout(mut self, helm_timeout: Duration) -> Self {
+        self.helm_timeout = Some(helm_timeout);
+        self
+    }
+
+    /// This is a builder option to set how the helm upgrade waits for the resources of the
+    /// release.
+    #[must_use]
+    pub(crate) fn with_helm_wait(mut self, helm_wait: HelmWait) -> Self {
+        self.helm_wait = helm_wait;
+        self
+    }
+
+    /// This is a builder option to add the images overriding the ones of the chart's components.
+    #[must_use]
+    pub(crate) fn with_image_overrides(mut self, image_overrides: Vec<ImageOverride>) -> Self {
+        self.image_overrides = image_overrides;
+        self
+    }
+
     /// This builds the HelmUpgrade object.
     pub(crate) async fn build(self) -> Result<HelmUpgrade> {
         ensure!(
@@ -107,9 +199,11 @@ impl HelmUpgradeBuilder {
         let helm_args_set_file = self.helm_args_set_file.clone().unwrap_or_default();
 
         // Generate HelmReleaseClient.
-        let client = HelmReleaseClient::builder()
-            .with_namespace(namespace.clone())
-            .build()?;
+        let mut client_builder = HelmReleaseClient::builder().with_namespace(namespace.clone());
+        if let Some(helm_timeout) = self.helm_timeout {
+            client_builder = client_builder.with_timeout(helm_timeout);
+        }
+        let client = client_builder.build()?;
 
         // Get HelmReleaseElement object for the release specified in CLI options.
         let chart = client.release_info(release_name.clone())?.chart();
@@ -176,22 +270,37 @@ impl HelmUpgradeBuilder {
         {
             chart_variant = HelmChart::Core;
 
-            // Skip upgrade-path validation and allow all upgrades for the Core helm chart, if the
-            // flag is set.
-            if !self.skip_upgrade_path_validation {
-                // Rollbacks not supported.
+            // Downgrades are forbidden unless allowed, as the earlier version may not read the
+            // data written by the installed one. When allowed, the data formats are validated.
+            if to_version.lt(&from_version) {
                 ensure!(
-                    to_version.ge(&from_version),
+                    self.allow_downgrade,
                     RollbackForbidden {
                         from_version: from_version.to_string(),
                         to_version: to_version.to_string()
                     }
                 );
+                upgrade::downgrade::validate_data_formats(
+                    namespace.as_str(),
+                    &from_version,
+                    &to_version,
+                )
+                .await?;
+            }
 
+            // Skip upgrade-path validation and allow all upgrades for the Core helm chart, if the
+            // flag is set.
+            if !self.skip_upgrade_path_validation {
                 let upgrade_path_is_valid = upgrade::path::is_valid_for_core_chart(&from_version)?;
                 ensure!(upgrade_path_is_valid, InvalidUpgradePath);
             }
 
+            // The image overrides are validated against the target version regardless, as an
+            // incompatible image may break the cluster.
+            for image_override in &self.image_overrides {
+                image_override.validate(&to_version)?;
+            }
+
             // Generate values yaml file for upgrade
             let _upgrade_values_file = generate_values_yaml_file(
                 &from_version,
@@ -201,18 +310,43 @@ impl HelmUpgradeBuilder {
                 release_name.clone(),
             )?;
 
+            // The values of the deployed release are compared with the ones the upgrade applies,
+            // so that the customizations which the upgrade changes can be reviewed.
+            let changes = values_diff(
+                &client,
+                release_name.clone(),
+                _upgrade_values_file.path(),
+                self.helm_args_values.as_slice(),
+            )?;
+            for change in &changes {
+                info!(%change, "Helm value changed by the upgrade");
+            }
+
             core_chart_dir = Some(chart_dir);
 
-            // helm upgrade .. -f <values-yaml> --atomic
-            core_chart_extra_args = Some(vec_to_strings![
-                "-f",
-                _upgrade_values_file.path().to_string_lossy(),
+            // helm upgrade .. -f <values-yaml> -f <user-values-yaml>.. [--atomic|--wait]
+            let mut extra_args =
+                vec_to_strings!["-f", _upgrade_values_file.path().to_string_lossy()];
+            // The values files of the user override the values of the release, and the set
+            // values override them in turn.
+            for values_file in &self.helm_args_values {
+                extra_args.extend(vec_to_strings!["-f", values_file.to_string_lossy()]);
+            }
+            extra_args.extend(vec_to_strings![
                 "--set",
                 helm_args_set,
                 "--set-file",
-                helm_args_set_file,
-                "--atomic"
+                helm_args_set_file
             ]);
+            extra_args.extend(self.helm_wait.args());
+            for image_override in &self.image_overrides {
+                info!(?image_override, "Overriding the image of a component");
+                extra_args.extend(vec_to_strings!["--set", image_override.helm_set_value()]);
+            }
+            if let Some(history_max) = self.helm_history_max {
+                extra_args.extend(vec_to_strings!["--history-max", history_max]);
+            }
+            core_chart_extra_args = Some(extra_args);
             upgrade_values_file = Some(_upgrade_values_file)
         } else {
             // Case: Helm chart release is not a known helm chart installation.
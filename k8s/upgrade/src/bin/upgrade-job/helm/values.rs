@@ -13,10 +13,41 @@ use crate::{
     },
 };
 use semver::Version;
+use serde_yaml::Value;
 use snafu::ResultExt;
-use std::{fs, io::Write, path::Path, str};
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter},
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    str,
+};
 use tempfile::NamedTempFile as TempFile;
 
+/// Value shown for the values which aren't set.
+const UNSET_VALUE: &str = "<unset>";
+
+/// A change to a value of the helm release made by the upgrade, the key being in the dotted
+/// notation of the set values.
+pub(crate) struct ValueChange {
+    key: String,
+    from: Option<String>,
+    to: Option<String>,
+}
+
+impl Display for ValueChange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {} -> {}",
+            self.key,
+            self.from.as_deref().unwrap_or(UNSET_VALUE),
+            self.to.as_deref().unwrap_or(UNSET_VALUE)
+        )
+    }
+}
+
 /// This compiles all of the helm values options to be passed during the helm chart upgrade.
 pub(crate) fn generate_values_yaml_file(
     from_version: &Version,
@@ -140,6 +171,14 @@ pub(crate) fn generate_values_yaml_file(
         upgrade_values_file.path(),
     )?;
 
+    // The image overrides of a previous upgrade are specific to its version, so they're dropped.
+    // The ones of this upgrade, if any, are set with the '--set' helm upgrade option.
+    yq.set_value(
+        YamlKey::try_from(".image.overrides")?,
+        "null",
+        upgrade_values_file.path(),
+    )?;
+
     // The CSI sidecar images need to always be the versions set on the chart by default.
     yq.set_value(
         YamlKey::try_from(".csi.image.provisionerTag")?,
@@ -172,3 +211,104 @@ pub(crate) fn generate_values_yaml_file(
 
     Ok(upgrade_values_file)
 }
+
+/// This compares the values of the deployed release with the ones the upgrade applies, i.e. the
+/// generated upgrade values overridden by the values files of the user, key by key. The set
+/// values aren't part of the comparison, they are shown by the upgrade plan of the plugin.
+pub(crate) fn values_diff(
+    client: &HelmReleaseClient,
+    release_name: String,
+    upgrade_values_file: &Path,
+    user_values_files: &[PathBuf],
+) -> Result<Vec<ValueChange>> {
+    let from_values_yaml = client.get_values_as_yaml::<String, String>(release_name, None)?;
+
+    let mut to_values_yaml = fs::read(upgrade_values_file).context(ReadingFile {
+        filepath: upgrade_values_file.to_path_buf(),
+    })?;
+    if !user_values_files.is_empty() {
+        let yq = YqV4::new()?;
+        for user_values_file in user_values_files {
+            let mut merged_file = TempFile::new().context(TempFileCreation)?;
+            merged_file
+                .write(to_values_yaml.as_slice())
+                .context(WriteToTempFile {
+                    filepath: merged_file.path().to_path_buf(),
+                })?;
+            to_values_yaml = yq.merge_files(user_values_file.as_path(), merged_file.path())?;
+        }
+    }
+
+    let from_values = flatten_values_yaml(from_values_yaml.as_slice())?;
+    let to_values = flatten_values_yaml(to_values_yaml.as_slice())?;
+
+    let mut changes = Vec::new();
+    for (key, from) in &from_values {
+        let to = to_values.get(key);
+        if to != Some(from) {
+            changes.push(ValueChange {
+                key: key.clone(),
+                from: Some(from.clone()),
+                to: to.cloned(),
+            });
+        }
+    }
+    for (key, to) in to_values {
+        if !from_values.contains_key(&key) {
+            changes.push(ValueChange {
+                key,
+                from: None,
+                to: Some(to),
+            });
+        }
+    }
+    changes.sort_by(|a, b| a.key.cmp(&b.key));
+    Ok(changes)
+}
+
+/// Parse the values yaml and flatten it into the keys of its values, in the dotted notation of
+/// the set values, and their values.
+fn flatten_values_yaml(values_yaml: &[u8]) -> Result<BTreeMap<String, String>> {
+    let values: Value = serde_yaml::from_slice(values_yaml).context(YamlParseFromSlice {
+        input_yaml: str::from_utf8(values_yaml)
+            .context(U8VectorToString)?
+            .to_string(),
+    })?;
+    let mut flat = BTreeMap::new();
+    // No values are set if the yaml is empty.
+    if values
+        .as_mapping()
+        .is_some_and(|mapping| !mapping.is_empty())
+    {
+        flatten_value(String::new(), &values, &mut flat);
+    }
+    Ok(flat)
+}
+
+/// Flatten the value into the map, the mappings being flattened into their keys.
+fn flatten_value(prefix: String, value: &Value, flat: &mut BTreeMap<String, String>) {
+    match value {
+        Value::Mapping(mapping) if !mapping.is_empty() => {
+            for (key, value) in mapping {
+                let key = match key {
+                    Value::String(key) => key.clone(),
+                    key => serde_yaml::to_string(key)
+                        .unwrap_or_default()
+                        .trim_end()
+                        .to_string(),
+                };
+                let key = match prefix.is_empty() {
+                    true => key,
+                    false => format!("{prefix}.{key}"),
+                };
+                flatten_value(key, value, flat);
+            }
+        }
+        Value::String(value) => {
+            flat.insert(prefix, value.clone());
+        }
+        value => {
+            flat.insert(prefix, serde_json::to_string(value).unwrap_or_default());
+        }
+    }
+}
@@ -1,10 +1,11 @@
 use crate::{
     common::{constants::PRODUCT, error::Result},
+    helm::history::{detect_storage_driver, use_storage_driver},
     opts::validators::{
         validate_helm_chart_dir, validate_helm_release, validate_helmv3_in_path,
         validate_namespace, validate_rest_endpoint,
     },
-    upgrade::upgrade,
+    upgrade::{rollback, upgrade},
 };
 use clap::Parser;
 use opts::CliArgs;
@@ -30,6 +31,14 @@ async fn main() -> Result<()> {
         error
     })?;
 
+    if opts.rollback() {
+        return rollback(&opts).await.map_err(|error| {
+            error!(%error, "Failed to roll back {PRODUCT}");
+            flush_traces();
+            error
+        });
+    }
+
     upgrade(&opts).await.map_err(|error| {
         error!(%error, "Failed to upgrade {PRODUCT}");
         flush_traces();
@@ -53,6 +62,10 @@ pub(crate) async fn parse_cli_args() -> Result<CliArgs> {
     validate_rest_endpoint(opts.rest_endpoint()).await?;
 
     validate_helmv3_in_path()?;
+    // The helm commands must use the storage driver of the release, for it to be found.
+    let storage_driver =
+        detect_storage_driver(opts.namespace().as_str(), opts.release_name().as_str()).await?;
+    use_storage_driver(storage_driver);
     validate_helm_release(opts.release_name(), opts.namespace())?;
     validate_helm_chart_dir(opts.core_chart_dir())?;
 
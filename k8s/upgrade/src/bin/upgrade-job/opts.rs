@@ -1,4 +1,8 @@
-use crate::common::constants::PRODUCT;
+use crate::{
+    common::constants::PRODUCT,
+    helm::{images::ImageOverride, upgrade::HelmWait},
+    upgrade::data_plane::DataPlanePacing,
+};
 use clap::Parser;
 use std::path::PathBuf;
 use utils::{package_description, version_info_str};
@@ -35,6 +39,17 @@ pub(crate) struct CliArgs {
     #[arg(long, default_value_t = false)]
     skip_upgrade_path_validation: bool,
 
+    /// If set then the upgrade to an earlier version is allowed, once the data formats are
+    /// validated to be compatible with it.
+    #[arg(long, default_value_t = false)]
+    allow_downgrade: bool,
+
+    /// The nodes whose io-engine Pods are not restarted, as they don't meet the node-level
+    /// requirements of the target version
+    /// (can specify multiple or separate values with commas: node1,node2).
+    #[arg(long, value_delimiter = ',')]
+    held_nodes: Vec<String>,
+
     /// The name of the Kubernetes Job Pod. The Job object will be used to post upgrade event.
     #[arg(env = "POD_NAME")]
     pod_name: String,
@@ -48,6 +63,74 @@ pub(crate) struct CliArgs {
     /// (can specify multiple or separate values with commas: key1=path1,key2=path2).
     #[arg(long)]
     helm_args_set_file: String,
+
+    /// The values files specified by the user for upgrade, whose values override the ones of the
+    /// release (can specify multiple or separate values with commas: path1,path2).
+    #[arg(long, value_delimiter = ',')]
+    helm_args_values: Vec<PathBuf>,
+
+    /// The images overriding the ones of the components of the helm chart, eg: hotfixed images
+    /// (can specify multiple or separate values with commas: component1=image1,component2=image2).
+    #[arg(long, value_delimiter = ',')]
+    image_overrides: Vec<ImageOverride>,
+
+    /// The maximum number of revisions of the helm release which are kept. The oldest revisions
+    /// are pruned before the upgrade, and helm prunes them on upgrade beyond it.
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    helm_history_max: Option<u32>,
+
+    /// The time to wait for the helm upgrade and the helm rollback to complete, eg: on clusters
+    /// with slow image pulls or a large number of nodes.
+    #[arg(long, default_value = "15m")]
+    helm_timeout: humantime::Duration,
+
+    /// If set then the helm upgrade is rolled back by helm if it fails or times out. This is the
+    /// default.
+    #[arg(long, overrides_with = "no_atomic")]
+    atomic: bool,
+
+    /// If set then the helm upgrade isn't rolled back by helm if it fails or times out.
+    #[arg(long, overrides_with = "atomic")]
+    no_atomic: bool,
+
+    /// If set then the helm upgrade waits for the resources of the release to be ready, when it
+    /// isn't atomic. The atomic helm upgrade always waits for them.
+    #[arg(long, default_value_t = false)]
+    helm_wait: bool,
+
+    /// If set then the helm upgrade also waits for the Jobs of the release to complete.
+    #[arg(long, default_value_t = false)]
+    helm_wait_for_jobs: bool,
+
+    /// The time to wait, after the data-plane upgrade, for the io-engine of every node to report
+    /// the target version over gRPC.
+    #[arg(long, default_value = "15m")]
+    data_plane_convergence_timeout: humantime::Duration,
+
+    /// The Kubernetes Node label whose values group the nodes whose io-engine Pods are restarted
+    /// together, eg: a zone label. Every node is upgraded on its own if this isn't set.
+    #[arg(long)]
+    data_plane_group_label: Option<String>,
+
+    /// If set then the data-plane upgrade is paused once the first node, or group of nodes, is
+    /// upgraded, until it's resumed.
+    #[arg(long, default_value_t = false)]
+    canary: bool,
+
+    /// The time to wait for the volumes of the upgraded nodes to be healthy again, before the
+    /// data-plane upgrade is paused.
+    #[arg(long, default_value = "30m")]
+    volume_health_timeout: humantime::Duration,
+
+    /// If set then the release is rolled back to the chart version and the values it had before
+    /// the upgrade, when the data-plane upgrade fails.
+    #[arg(long, default_value_t = false)]
+    rollback_on_failure: bool,
+
+    /// If set then the release is rolled back to the chart version and the values recorded
+    /// before its last upgrade, instead of being upgraded.
+    #[arg(long, default_value_t = false)]
+    rollback: bool,
 }
 
 impl CliArgs {
@@ -82,6 +165,16 @@ impl CliArgs {
         self.skip_upgrade_path_validation
     }
 
+    /// This is a predicate to decide if the upgrade to an earlier version is allowed.
+    pub(crate) fn allow_downgrade(&self) -> bool {
+        self.allow_downgrade
+    }
+
+    /// This returns the nodes whose io-engine Pods are held back from the data-plane upgrade.
+    pub(crate) fn held_nodes(&self) -> Vec<String> {
+        self.held_nodes.clone()
+    }
+
     /// This returns the name of the Kubernetes Pod where this binary will be running.
     pub(crate) fn pod_name(&self) -> String {
         self.pod_name.clone()
@@ -96,4 +189,57 @@ impl CliArgs {
     pub(crate) fn helm_args_set_file(&self) -> String {
         self.helm_args_set_file.clone()
     }
+
+    /// This returns the values files passed during upgrade.
+    pub(crate) fn helm_args_values(&self) -> Vec<PathBuf> {
+        self.helm_args_values.clone()
+    }
+
+    /// This returns the images overriding the ones of the components of the helm chart.
+    pub(crate) fn image_overrides(&self) -> Vec<ImageOverride> {
+        self.image_overrides.clone()
+    }
+
+    /// This returns the maximum number of revisions of the helm release which are kept.
+    pub(crate) fn helm_history_max(&self) -> Option<u32> {
+        self.helm_history_max
+    }
+
+    /// This returns the time to wait for the helm upgrade and the helm rollback to complete.
+    pub(crate) fn helm_timeout(&self) -> std::time::Duration {
+        self.helm_timeout.into()
+    }
+
+    /// This returns how the helm upgrade waits for the resources of the release.
+    pub(crate) fn helm_wait(&self) -> HelmWait {
+        HelmWait {
+            atomic: self.atomic || !self.no_atomic,
+            wait: self.helm_wait,
+            wait_for_jobs: self.helm_wait_for_jobs,
+        }
+    }
+
+    /// This returns the time to wait for the data-plane to converge to the target version.
+    pub(crate) fn data_plane_convergence_timeout(&self) -> std::time::Duration {
+        self.data_plane_convergence_timeout.into()
+    }
+
+    /// This returns the pacing of the data-plane upgrade.
+    pub(crate) fn data_plane_pacing(&self) -> DataPlanePacing {
+        DataPlanePacing {
+            group_label: self.data_plane_group_label.clone(),
+            canary: self.canary,
+            volume_health_timeout: self.volume_health_timeout.into(),
+        }
+    }
+
+    /// This is a predicate to decide if the release is rolled back when the upgrade fails.
+    pub(crate) fn rollback_on_failure(&self) -> bool {
+        self.rollback_on_failure
+    }
+
+    /// This is a predicate to decide if the release is rolled back instead of being upgraded.
+    pub(crate) fn rollback(&self) -> bool {
+        self.rollback
+    }
 }
@@ -1,10 +1,22 @@
 use crate::{
-    common::{constants::PRODUCT, error::Result},
+    common::{
+        constants::PRODUCT,
+        error::{Error, Result},
+        rest_client::RestClientSet,
+    },
     events::event_recorder::{EventAction, EventRecorder},
-    helm::upgrade::{HelmUpgrade, HelmUpgradeRunner},
+    helm::{
+        history::prune_release_history,
+        upgrade::{HelmUpgrade, HelmUpgradeRunner},
+    },
     opts::CliArgs,
 };
+use convergence::{monitor_convergence, record_pending_restarts};
 use data_plane::upgrade_data_plane;
+use health::HealthSnapshot;
+use resume::{UpgradeComponent, UpgradeState};
+use rollback::{record_rollback_point, rollback_release};
+use tracing::{error, info, warn};
 
 /// Contains the data-plane upgrade logic.
 pub(crate) mod data_plane;
@@ -15,11 +27,33 @@ pub(crate) mod utils;
 /// Tools to validate upgrade path.
 pub(crate) mod path;
 
+/// Contains the data format validation of the downgrades.
+pub(crate) mod downgrade;
+
+/// Contains the cluster health snapshots.
+pub(crate) mod health;
+
+/// Contains the monitor of the version the io-engines report after the data-plane upgrade.
+pub(crate) mod convergence;
+
+/// Contains the pause and resume controls of the data-plane upgrade.
+pub(crate) mod pause;
+
+/// Contains the recording of the source of the upgrade, and the rollback of the release to it.
+pub(crate) mod rollback;
+
+/// Contains the progress of the upgrade, and its recording in the upgrade status ConfigMap.
+pub(crate) mod progress;
+
+/// Contains the completed components of the upgrade, for a failed upgrade to be resumed.
+pub(crate) mod resume;
+
 /// This function starts and sees upgrade through to the end.
 pub(crate) async fn upgrade(opts: &CliArgs) -> Result<()> {
     let mut event = EventRecorder::builder()
         .with_pod_name(&opts.pod_name())
         .with_namespace(&opts.namespace())
+        .with_release_name(&opts.release_name())
         .build()
         .await?;
 
@@ -32,6 +66,24 @@ pub(crate) async fn upgrade(opts: &CliArgs) -> Result<()> {
     result
 }
 
+/// This rolls the release back to the source of its last upgrade, instead of upgrading it.
+pub(crate) async fn rollback(opts: &CliArgs) -> Result<()> {
+    let mut event = EventRecorder::builder()
+        .with_pod_name(&opts.pod_name())
+        .with_namespace(&opts.namespace())
+        .with_release_name(&opts.release_name())
+        .build()
+        .await?;
+
+    let result = rollback_release(opts, &mut event).await;
+
+    // This makes sure that the event worker attempts to publish
+    // all of its events. It waits for the event worker to exit.
+    event.shutdown_worker().await;
+
+    result
+}
+
 /// This carries out the helm upgrade validation, actual helm upgrade, and the io-engine Pod
 /// restarts.
 async fn upgrade_product(opts: &CliArgs, event: &mut EventRecorder) -> Result<()> {
@@ -40,8 +92,14 @@ async fn upgrade_product(opts: &CliArgs, event: &mut EventRecorder) -> Result<()
         .with_release_name(opts.release_name())
         .with_core_chart_dir(opts.core_chart_dir())
         .with_skip_upgrade_path_validation(opts.skip_upgrade_path_validation())
+        .with_allow_downgrade(opts.allow_downgrade())
         .with_helm_args_set(opts.helm_args_set())
         .with_helm_args_set_file(opts.helm_args_set_file())
+        .with_helm_args_values(opts.helm_args_values())
+        .with_helm_history_max(opts.helm_history_max())
+        .with_helm_timeout(opts.helm_timeout())
+        .with_helm_wait(opts.helm_wait())
+        .with_image_overrides(opts.image_overrides())
         .build()
         .await?;
 
@@ -53,6 +111,146 @@ async fn upgrade_product(opts: &CliArgs, event: &mut EventRecorder) -> Result<()
     // shared with any other tokio task.
     event.set_from_version(from_version.clone());
     event.set_to_version(to_version.clone());
+    // The image overrides are recorded with every upgrade event, for the audit of the upgrade.
+    event.set_image_overrides(opts.image_overrides());
+
+    // The health snapshots are informational, failing to capture them does not fail the upgrade.
+    let rest_client = RestClientSet::new_with_url(opts.rest_endpoint())?;
+    event.set_health_before(capture_health(&rest_client).await);
+
+    // The components completed by a previous attempt of the upgrade to this version are skipped,
+    // so that a re-run upgrade continues from the component which failed.
+    let mut state = match UpgradeState::load(
+        opts.namespace().as_str(),
+        opts.release_name().as_str(),
+        to_version.as_str(),
+    )
+    .await
+    {
+        Ok(state) => state,
+        Err(error) => {
+            event.publish_unrecoverable(&error, true).await;
+            return Err(error);
+        }
+    };
+
+    // The control-plane is only skipped if the release is still at the target version.
+    if state.is_completed(UpgradeComponent::ControlPlane) && from_version == to_version {
+        event
+            .publish_normal(
+                format!("Resuming {PRODUCT} upgrade, the control-plane is upgraded already"),
+                EventAction::UpgradedCP,
+            )
+            .await?;
+    } else {
+        upgrade_control_plane(opts, event, helm_upgrade, &from_version, &to_version).await?;
+        state.complete(UpgradeComponent::ControlPlane).await;
+    }
+
+    // Data plane containers are updated in this step.
+    let mut pending_nodes = Vec::new();
+    if !opts.skip_data_plane_restart() {
+        if !state.is_completed(UpgradeComponent::DataPlane) {
+            event.set_component(Some("io-engine"));
+            event
+                .publish_normal(
+                    format!("Upgrading {PRODUCT} data-plane"),
+                    EventAction::UpgradingDP,
+                )
+                .await?;
+
+            if let Err(error) = upgrade_data_plane(
+                opts.namespace(),
+                opts.rest_endpoint(),
+                to_version.clone(),
+                opts.held_nodes(),
+                opts.data_plane_pacing(),
+                event,
+            )
+            .await
+            {
+                return handle_data_plane_failure(opts, event, error).await;
+            }
+
+            event
+                .publish_normal(
+                    format!("Upgraded {PRODUCT} data-plane"),
+                    EventAction::UpgradedDP,
+                )
+                .await?;
+            state.complete(UpgradeComponent::DataPlane).await;
+        }
+
+        // The io-engine Pods may run the new image, yet fail to restart, so the upgrade is only
+        // complete once every io-engine reports the target version.
+        if !state.is_completed(UpgradeComponent::Convergence) {
+            if let Err(error) = monitor_convergence(
+                opts.namespace(),
+                to_version,
+                opts.held_nodes(),
+                opts.data_plane_convergence_timeout(),
+                event,
+            )
+            .await
+            {
+                return handle_data_plane_failure(opts, event, error).await;
+            }
+            state.complete(UpgradeComponent::Convergence).await;
+        }
+    } else {
+        // The io-engine Pods are left for the user to restart, the nodes which still run the
+        // older version are recorded so that they can be tracked down.
+        match record_pending_restarts(opts.namespace(), to_version, event).await {
+            Ok(nodes) => pending_nodes = nodes,
+            Err(error) => {
+                warn!(%error, "Failed to record the nodes pending the data-plane restart")
+            }
+        }
+    }
+
+    event.set_health_after(capture_health(&rest_client).await);
+    event.set_component(None::<String>);
+
+    let message = match pending_nodes.len() {
+        0 => format!("Successfully upgraded {PRODUCT}"),
+        count => format!(
+            "Successfully upgraded {PRODUCT} control-plane, the io-engine Pods of {count} node(s) \
+            need to be restarted to complete the data-plane upgrade"
+        ),
+    };
+    event
+        .publish_normal(message, EventAction::Successful)
+        .await?;
+
+    Ok(())
+}
+
+/// This prunes the helm release history, validates the helm upgrade with a dry-run, records the
+/// rollback point and runs the helm upgrade, i.e. upgrades the control-plane.
+async fn upgrade_control_plane(
+    opts: &CliArgs,
+    event: &mut EventRecorder,
+    helm_upgrade: HelmUpgrade,
+    from_version: &str,
+    to_version: &str,
+) -> Result<()> {
+    // Prune the oldest revisions of the helm release, as the records of a release with a long
+    // history may hit the size or the quota limits of the cluster mid-upgrade.
+    if let Some(history_max) = opts.helm_history_max() {
+        match prune_release_history(
+            opts.namespace().as_str(),
+            opts.release_name().as_str(),
+            history_max,
+        )
+        .await
+        {
+            Ok(pruned) => info!(pruned, history_max, "Pruned the helm release history"),
+            Err(error) => {
+                event.publish_unrecoverable(&error, true).await;
+                return Err(error);
+            }
+        }
+    }
 
     // Dry-run helm upgrade.
     let dry_run_result: Result<HelmUpgradeRunner> = helm_upgrade.dry_run().await;
@@ -64,6 +262,23 @@ async fn upgrade_product(opts: &CliArgs, event: &mut EventRecorder) -> Result<()
         Ok(run_helm_upgrade) => Ok(run_helm_upgrade),
     }?;
 
+    // The source of the upgrade is recorded before the release is changed, so that it can be
+    // rolled back to. A retried upgrade job finds the release upgraded already, and keeps the
+    // rollback point of its first attempt.
+    if from_version != to_version {
+        if let Err(error) = record_rollback_point(
+            opts.namespace(),
+            opts.release_name(),
+            from_version.to_string(),
+            to_version.to_string(),
+        )
+        .await
+        {
+            event.publish_unrecoverable(&error, true).await;
+            return Err(error);
+        }
+    }
+
     event
         .publish_normal(
             format!("Starting {PRODUCT} upgrade..."),
@@ -71,6 +286,7 @@ async fn upgrade_product(opts: &CliArgs, event: &mut EventRecorder) -> Result<()
         )
         .await?;
 
+    event.set_component(Some(format!("helm release {}", opts.release_name())));
     event
         .publish_normal(
             format!("Upgrading {PRODUCT} control-plane"),
@@ -91,36 +307,35 @@ async fn upgrade_product(opts: &CliArgs, event: &mut EventRecorder) -> Result<()
         )
         .await?;
 
-    // Data plane containers are updated in this step.
-    if !opts.skip_data_plane_restart() {
-        event
-            .publish_normal(
-                format!("Upgrading {PRODUCT} data-plane"),
-                EventAction::UpgradingDP,
-            )
-            .await?;
-
-        if let Err(error) =
-            upgrade_data_plane(opts.namespace(), opts.rest_endpoint(), to_version).await
-        {
-            event.publish_unrecoverable(&error, false).await;
-            return Err(error);
-        }
+    Ok(())
+}
 
-        event
-            .publish_normal(
-                format!("Upgraded {PRODUCT} data-plane"),
-                EventAction::UpgradedDP,
-            )
-            .await?;
+/// Publish the failure of the data-plane upgrade, and roll the release back if that is enabled.
+/// The failure of the helm upgrade itself needs no rollback, as 'helm upgrade --atomic' reverts
+/// it. The upgrade job completes once the rollback succeeds, so that the upgrade isn't retried.
+async fn handle_data_plane_failure(
+    opts: &CliArgs,
+    event: &mut EventRecorder,
+    error: Error,
+) -> Result<()> {
+    event.publish_unrecoverable(&error, false).await;
+    if !opts.rollback_on_failure() {
+        return Err(error);
     }
 
-    event
-        .publish_normal(
-            format!("Successfully upgraded {PRODUCT}"),
-            EventAction::Successful,
-        )
-        .await?;
+    warn!(%error, "Rolling back {PRODUCT}, as the data-plane upgrade failed");
+    rollback_release(opts, event)
+        .await
+        .map_err(|rollback_error| {
+            error!(error = %rollback_error, "Failed to roll back {PRODUCT}");
+            error
+        })
+}
 
-    Ok(())
+/// Capture a health snapshot of the cluster, logging a warning on failure.
+async fn capture_health(rest_client: &RestClientSet) -> Option<HealthSnapshot> {
+    HealthSnapshot::capture(rest_client)
+        .await
+        .map_err(|error| warn!(%error, "Failed to capture {PRODUCT} health snapshot"))
+        .ok()
 }
@@ -0,0 +1,293 @@
+use crate::{
+    common::{
+        constants::{CHART_VERSION_LABEL_KEY, IO_ENGINE_GRPC_PORT, IO_ENGINE_LABEL, PRODUCT},
+        error::{DataPlaneNotConverged, ListPodsWithLabel, Result, SemverParse},
+        kube_client::KubeClientSet,
+    },
+    events::event_recorder::{EventAction, EventRecorder},
+};
+use k8s_openapi::api::core::v1::Pod;
+use kube::{api::ListParams, ResourceExt};
+use rpc::v1::host::host_rpc_client::HostRpcClient;
+use semver::Version;
+use serde::Serialize;
+use snafu::ResultExt;
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
+use tracing::{info, warn};
+
+/// Interval between the checks of the versions reported by the io-engines.
+const CONVERGENCE_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Timeout of the gRPC requests to the io-engines.
+const IO_ENGINE_GRPC_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The version convergence of the data-plane, i.e. whether every io-engine reports the target
+/// version over gRPC, as opposed to only running a Pod of the target chart version. It's
+/// published with the upgrade events.
+#[derive(Serialize, Clone, Debug, Default)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub(crate) struct ConvergenceStatus {
+    target_version: String,
+    converged: bool,
+    /// Whether the data-plane restart was skipped, the nodes which don't report the target
+    /// version awaiting the manual restart of their io-engine Pods.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    restart_pending: bool,
+    nodes: BTreeMap<String, NodeConvergence>,
+}
+
+/// The version reported by the io-engine of a node.
+#[derive(Serialize, Clone, Debug, Default)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub(crate) struct NodeConvergence {
+    pod: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<String>,
+    converged: bool,
+    restarts: i32,
+    /// Why the node is stuck on an older version, if it is.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<String>,
+}
+
+impl ConvergenceStatus {
+    /// The nodes whose io-engine doesn't report the target version.
+    fn stuck_nodes(&self) -> Vec<String> {
+        self.nodes
+            .iter()
+            .filter(|(_, node)| !node.converged)
+            .map(|(name, node)| match &node.reason {
+                Some(reason) => format!("{name} ({reason})"),
+                None => name.clone(),
+            })
+            .collect()
+    }
+
+    /// The number of nodes whose io-engine reports the target version.
+    fn converged_nodes(&self) -> usize {
+        self.nodes.values().filter(|node| node.converged).count()
+    }
+}
+
+/// Wait until the io-engine of every node, except for the held ones, reports the target version
+/// over gRPC. The status is checked every few seconds and recorded with the upgrade events, and
+/// the nodes which are still stuck on an older version once the timeout expires are flagged.
+pub(crate) async fn monitor_convergence(
+    namespace: String,
+    upgrade_to_version: String,
+    held_nodes: Vec<String>,
+    timeout: Duration,
+    event: &mut EventRecorder,
+) -> Result<ConvergenceStatus> {
+    let k8s_client = KubeClientSet::builder()
+        .with_namespace(namespace.clone())
+        .build()
+        .await?;
+    let target_version = Version::parse(upgrade_to_version.as_str()).context(SemverParse {
+        version_string: upgrade_to_version.clone(),
+    })?;
+
+    event
+        .publish_normal(
+            format!("Waiting for the {PRODUCT} data-plane to report version {target_version}"),
+            EventAction::VerifyingDP,
+        )
+        .await?;
+
+    let deadline = Instant::now() + timeout;
+    loop {
+        let status =
+            check_convergence(&k8s_client, &namespace, &target_version, &held_nodes).await?;
+        event.set_convergence(Some(status.clone()));
+        if status.converged {
+            info!(version = %target_version, "The data-plane converged to the target version");
+            return Ok(status);
+        }
+
+        let stuck_nodes = status.stuck_nodes();
+        if Instant::now() >= deadline {
+            warn!(
+                ?stuck_nodes,
+                "The data-plane did not converge to the target version"
+            );
+            return DataPlaneNotConverged {
+                version: target_version.to_string(),
+                nodes: stuck_nodes,
+            }
+            .fail();
+        }
+        info!(
+            ?stuck_nodes,
+            "Waiting for the data-plane to converge to the target version"
+        );
+        tokio::time::sleep(CONVERGENCE_POLL_INTERVAL).await;
+    }
+}
+
+/// Record the nodes whose io-engine still runs an older version, as the data-plane restart is
+/// skipped, so that their io-engine Pods can be restarted during a maintenance window. The held
+/// nodes are recorded as well, as they are left on the older version too. Returns the nodes which
+/// still run an older version.
+pub(crate) async fn record_pending_restarts(
+    namespace: String,
+    upgrade_to_version: String,
+    event: &mut EventRecorder,
+) -> Result<Vec<String>> {
+    let k8s_client = KubeClientSet::builder()
+        .with_namespace(namespace.clone())
+        .build()
+        .await?;
+    let target_version = Version::parse(upgrade_to_version.as_str()).context(SemverParse {
+        version_string: upgrade_to_version.clone(),
+    })?;
+
+    let mut status = check_convergence(&k8s_client, &namespace, &target_version, &[]).await?;
+    status.restart_pending = !status.converged;
+    let pending_nodes = status.stuck_nodes();
+    event.set_nodes_progress(status.converged_nodes(), status.nodes.len());
+    event.set_convergence(Some(status));
+
+    if !pending_nodes.is_empty() {
+        info!(
+            ?pending_nodes,
+            "The io-engine Pods of these nodes need to be restarted to run version {target_version}"
+        );
+    }
+    Ok(pending_nodes)
+}
+
+/// Check the version reported by the io-engine of every node.
+async fn check_convergence(
+    k8s_client: &KubeClientSet,
+    namespace: &str,
+    target_version: &Version,
+    held_nodes: &[String],
+) -> Result<ConvergenceStatus> {
+    let io_engine_pods = k8s_client
+        .pods_api()
+        .list(&ListParams::default().labels(IO_ENGINE_LABEL))
+        .await
+        .context(ListPodsWithLabel {
+            label: IO_ENGINE_LABEL.to_string(),
+            namespace: namespace.to_string(),
+        })?;
+
+    let mut nodes = BTreeMap::new();
+    for pod in io_engine_pods.iter() {
+        let Some(node_name) = pod.spec.as_ref().and_then(|spec| spec.node_name.clone()) else {
+            continue;
+        };
+        if held_nodes.contains(&node_name) {
+            continue;
+        }
+        nodes.insert(node_name, node_convergence(pod, target_version).await);
+    }
+
+    Ok(ConvergenceStatus {
+        target_version: target_version.to_string(),
+        converged: nodes.values().all(|node| node.converged),
+        restart_pending: false,
+        nodes,
+    })
+}
+
+/// Get the version reported by the io-engine of the Pod, along with why it's stuck on an older
+/// version, if it is.
+async fn node_convergence(pod: &Pod, target_version: &Version) -> NodeConvergence {
+    let mut node = NodeConvergence {
+        pod: pod.name_any(),
+        restarts: io_engine_restarts(pod),
+        ..Default::default()
+    };
+
+    let chart_version = pod.labels().get(CHART_VERSION_LABEL_KEY);
+    if chart_version.map(String::as_str) != Some(target_version.to_string().as_str()) {
+        node.reason = Some(format!(
+            "the Pod of chart version {} was not restarted",
+            chart_version.map(String::as_str).unwrap_or("unknown")
+        ));
+        return node;
+    }
+
+    match io_engine_version(pod).await {
+        Ok(version) => {
+            node.converged = reports_version(&version, target_version);
+            if !node.converged {
+                node.reason = Some(format!("reports version {version}"));
+            }
+            node.version = Some(version);
+        }
+        Err(error) if node.restarts > 0 => {
+            node.reason = Some(format!(
+                "the io-engine restarted {} time(s) and is unreachable: {error}",
+                node.restarts
+            ));
+        }
+        Err(error) => node.reason = Some(format!("the io-engine is unreachable: {error}")),
+    }
+    node
+}
+
+/// The number of restarts of the io-engine container of the Pod.
+fn io_engine_restarts(pod: &Pod) -> i32 {
+    pod.status
+        .as_ref()
+        .and_then(|status| status.container_statuses.as_ref())
+        .and_then(|statuses| statuses.iter().find(|status| status.name == "io-engine"))
+        .map_or(0, |status| status.restart_count)
+}
+
+/// Get the version the io-engine of the Pod reports over gRPC.
+async fn io_engine_version(pod: &Pod) -> std::result::Result<String, String> {
+    let pod_ip = pod
+        .status
+        .as_ref()
+        .and_then(|status| status.pod_ip.as_ref())
+        .ok_or_else(|| "the Pod has no IP".to_string())?;
+    let endpoint = tonic::transport::Endpoint::from_shared(format!(
+        "http://{}",
+        std::net::SocketAddr::new(
+            pod_ip
+                .parse()
+                .map_err(|_| format!("invalid Pod IP {pod_ip}"))?,
+            IO_ENGINE_GRPC_PORT
+        )
+    ))
+    .map_err(|error| error.to_string())?
+    .connect_timeout(IO_ENGINE_GRPC_TIMEOUT)
+    .timeout(IO_ENGINE_GRPC_TIMEOUT);
+    let mut client = HostRpcClient::connect(endpoint)
+        .await
+        .map_err(|error| error.to_string())?;
+    let info = client
+        .get_mayastor_info(())
+        .await
+        .map_err(|status| status.message().to_string())?
+        .into_inner();
+    Ok(info.version)
+}
+
+/// Whether the version reported by the io-engine, eg: `v2.5.0` or `v2.5.0 (abcdef0)`, is the
+/// target version, regardless of its build metadata.
+fn reports_version(reported: &str, target_version: &Version) -> bool {
+    let version = reported
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .trim_start_matches('v');
+    match Version::parse(version) {
+        Ok(version) => {
+            (&version.major, &version.minor, &version.patch, &version.pre)
+                == (
+                    &target_version.major,
+                    &target_version.minor,
+                    &target_version.patch,
+                    &target_version.pre,
+                )
+        }
+        Err(_) => reported.contains(target_version.to_string().as_str()),
+    }
+}
@@ -5,30 +5,60 @@ use crate::{
         },
         error::{
             DrainStorageNode, EmptyPodNodeName, EmptyPodSpec, EmptyStorageNodeSpec, GetStorageNode,
-            ListPodsWithLabel, ListPodsWithLabelAndField, ListStorageNodes, PodDelete, Result,
-            StorageNodeUncordon, TooManyIoEnginePods,
+            ListK8sNodes, ListPodsWithLabel, ListPodsWithLabelAndField, ListStorageNodes,
+            PodDelete, Result, StorageNodeUncordon, TooManyIoEnginePods,
         },
         kube_client::KubeClientSet,
         rest_client::RestClientSet,
     },
-    upgrade::utils::{all_pods_are_ready, data_plane_is_upgraded, rebuild_result, RebuildResult},
+    events::event_recorder::{EventAction, EventRecorder},
+    upgrade::{
+        pause::PauseControl,
+        utils::{
+            all_pods_are_ready, data_plane_is_upgraded, list_unhealthy_volumes, rebuild_result,
+            RebuildResult,
+        },
+    },
 };
 use k8s_openapi::api::core::v1::Pod;
 use kube::{
     api::{DeleteParams, ListParams, ObjectList},
     ResourceExt,
 };
-use openapi::models::CordonDrainState;
+use openapi::models::{CordonDrainState, Volume};
 use snafu::ResultExt;
-use std::time::Duration;
-use tracing::info;
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::{Duration, Instant},
+};
+use tracing::{info, warn};
 use utils::{API_REST_LABEL, ETCD_LABEL};
 
-/// Upgrade data plane by controlled restart of io-engine pods
+/// The pacing of the data-plane upgrade, i.e. which nodes are upgraded together, and when the
+/// upgrade pauses.
+#[derive(Clone, Debug)]
+pub(crate) struct DataPlanePacing {
+    /// The Kubernetes Node label whose values group the nodes which are upgraded together. Every
+    /// node is upgraded on its own when this isn't set.
+    pub(crate) group_label: Option<String>,
+    /// Pause the upgrade once the first node, or group of nodes, is upgraded.
+    pub(crate) canary: bool,
+    /// The time to wait for the volumes of the upgraded nodes to be healthy again, before the
+    /// upgrade is paused.
+    pub(crate) volume_health_timeout: Duration,
+}
+
+/// Upgrade data plane by controlled restart of io-engine pods, except for the ones on the held
+/// nodes. The nodes are upgraded one at a time, or one group of nodes at a time, and the upgrade
+/// waits for their volumes to be healthy again before proceeding. It can be paused before each
+/// node or group.
 pub(crate) async fn upgrade_data_plane(
     namespace: String,
     rest_endpoint: String,
     upgrade_to_version: String,
+    held_nodes: Vec<String>,
+    pacing: DataPlanePacing,
+    event: &EventRecorder,
 ) -> Result<()> {
     // Generate k8s clients.
     let k8s_client = KubeClientSet::builder()
@@ -54,6 +84,22 @@ pub(crate) async fn upgrade_data_plane(
 
     // If here, then there is a need to proceed to data-plane upgrade.
 
+    // The progress of the upgrade counts the nodes, except for the held ones.
+    let mut nodes_upgraded = io_engine_pod_list
+        .iter()
+        .filter(|pod| {
+            pod.spec
+                .as_ref()
+                .and_then(|spec| spec.node_name.as_ref())
+                .map_or(true, |node_name| !held_nodes.contains(node_name))
+        })
+        .filter(|pod| {
+            pod.labels()
+                .get(CHART_VERSION_LABEL_KEY)
+                .is_some_and(|version| *version == upgrade_to_version)
+        })
+        .count();
+
     let yet_to_upgrade_io_engine_label_selector =
         format!("{IO_ENGINE_LABEL},{CHART_VERSION_LABEL_KEY}!={upgrade_to_version}");
     let io_engine_listparams =
@@ -62,6 +108,7 @@ pub(crate) async fn upgrade_data_plane(
 
     // Generate storage REST API client.
     let rest_client = RestClientSet::new_with_url(rest_endpoint)?;
+    let pause_control = PauseControl::new(&k8s_client, event.job_name());
 
     info!("Starting data-plane upgrade...");
 
@@ -80,6 +127,7 @@ pub(crate) async fn upgrade_data_plane(
         uncordon_node(storage_node.id.as_str(), &rest_client).await?;
     }
 
+    let mut first_group_upgraded = false;
     loop {
         let initial_io_engine_pod_list: ObjectList<Pod> = k8s_client
             .pods_api()
@@ -90,74 +138,267 @@ pub(crate) async fn upgrade_data_plane(
                 namespace: namespace.clone(),
             })?;
 
+        let io_engine_pods = initial_io_engine_pod_list
+            .iter()
+            .filter(|pod| {
+                pod.spec
+                    .as_ref()
+                    .and_then(|spec| spec.node_name.as_ref())
+                    .map_or(true, |node_name| !held_nodes.contains(node_name))
+            })
+            .collect::<Vec<_>>();
+
         // Infinite loop exit.
-        if initial_io_engine_pod_list.items.is_empty() {
+        if io_engine_pods.is_empty() {
             break;
         }
+        let nodes_total = nodes_upgraded + io_engine_pods.len();
+
+        let groups = group_pods(
+            io_engine_pods,
+            pacing.group_label.as_deref(),
+            namespace.as_str(),
+            &k8s_client,
+        )
+        .await?;
+
+        for (group, pods) in groups {
+            pause_control.wait_while_paused(event).await?;
 
-        for pod in initial_io_engine_pod_list.iter() {
             // Validate the control plane pod is up and running before we start.
             verify_control_plane_is_running(namespace.clone(), &k8s_client, &upgrade_to_version)
                 .await?;
 
-            // Fetch the node name on which the io-engine pod is running
-            let node_name = pod
-                .spec
-                .as_ref()
-                .ok_or(
-                    EmptyPodSpec {
-                        name: pod.name_any(),
-                        namespace: namespace.clone(),
-                    }
-                    .build(),
-                )?
-                .node_name
-                .as_ref()
-                .ok_or(
-                    EmptyPodNodeName {
-                        name: pod.name_any(),
-                        namespace: namespace.clone(),
-                    }
-                    .build(),
-                )?
-                .as_str();
-
+            let node_names = pods.iter().map(|(node, _)| *node).collect::<Vec<_>>();
             info!(
-                pod.name = %pod.name_any(),
-                node.name = %node_name,
-                "Starting upgrade for the data-plane pod"
+                group = %group,
+                nodes = ?node_names,
+                "Starting upgrade for the data-plane pods of the group"
             );
+            event.set_component(Some(format!("io-engine on {}", node_names.join(", "))));
+            event.set_nodes_progress(nodes_upgraded, nodes_total);
+            event
+                .publish_normal(
+                    format!(
+                        "Upgrading {PRODUCT} data-plane on {}",
+                        node_names.join(", ")
+                    ),
+                    EventAction::UpgradingDP,
+                )
+                .await?;
 
-            // Wait for any rebuild to complete
-            wait_for_rebuild(node_name, &rest_client).await?;
+            for (node_name, pod) in &pods {
+                info!(
+                    pod.name = %pod.name_any(),
+                    node.name = %node_name,
+                    "Starting upgrade for the data-plane pod"
+                );
+
+                // Wait for any rebuild to complete
+                wait_for_rebuild(node_name, &rest_client).await?;
+            }
+
+            // The volumes which are unhealthy already aren't waited for after the restarts.
+            let unhealthy_volumes = list_unhealthy_volumes(&rest_client, &[]).await?;
 
-            // Issue node drain command
-            drain_storage_node(node_name, &rest_client).await?;
+            for (node_name, pod) in &pods {
+                // Issue node drain command
+                drain_storage_node(node_name, &rest_client).await?;
 
-            // restart the data plane pod
-            delete_data_plane_pod(node_name, pod, &k8s_client).await?;
+                // restart the data plane pod
+                delete_data_plane_pod(node_name, pod, &k8s_client).await?;
+            }
+
+            for (node_name, _) in &pods {
+                // validate the new pod is up and running
+                verify_data_plane_pod_is_running(
+                    node_name,
+                    namespace.clone(),
+                    &upgrade_to_version,
+                    &k8s_client,
+                )
+                .await?;
 
-            // validate the new pod is up and running
-            verify_data_plane_pod_is_running(
-                node_name,
-                namespace.clone(),
-                &upgrade_to_version,
-                &k8s_client,
+                // Uncordon the drained node
+                uncordon_node(node_name, &rest_client).await?;
+            }
+
+            nodes_upgraded += pods.len();
+            event.set_nodes_progress(nodes_upgraded, nodes_total);
+            event
+                .publish_normal(
+                    format!(
+                        "Upgraded {PRODUCT} data-plane on {}, {nodes_upgraded}/{nodes_total} \
+                        nodes upgraded",
+                        node_names.join(", ")
+                    ),
+                    EventAction::UpgradingDP,
+                )
+                .await?;
+
+            let healthy = wait_for_volumes_healthy(
+                &node_names,
+                &unhealthy_volumes,
+                pacing.volume_health_timeout,
+                &rest_client,
             )
             .await?;
-
-            // Uncordon the drained node
-            uncordon_node(node_name, &rest_client).await?;
+            if !healthy {
+                warn!(
+                    nodes = ?node_names,
+                    "Pausing the data-plane upgrade, as the volumes of the upgraded nodes are \
+                    not healthy yet"
+                );
+                pause_control.pause().await?;
+            } else if pacing.canary && !first_group_upgraded {
+                info!(
+                    nodes = ?node_names,
+                    "Pausing the data-plane upgrade after the canary upgrade"
+                );
+                pause_control.pause().await?;
+            }
+            first_group_upgraded = true;
         }
 
         info!("Checking to see if new {PRODUCT} Nodes have been added to the cluster, which require upgrade");
     }
 
+    if !held_nodes.is_empty() {
+        info!(
+            nodes = ?held_nodes,
+            "Held back the data-plane upgrade of the Nodes which don't meet the requirements of \
+            the target version, their data-plane Pods need to be restarted once they do"
+        );
+    }
+
     info!("Successfully upgraded data-plane!");
 
     Ok(())
 }
 
+/// Group the io-engine Pods by the value of the group label of their node, along with the name
+/// of their node. The groups are ordered by the label value, and the Pods whose node lacks the
+/// label come last, one at a time. Without a group label, each Pod is a group of its own.
+async fn group_pods<'a>(
+    pods: Vec<&'a Pod>,
+    group_label: Option<&str>,
+    namespace: &str,
+    k8s_client: &KubeClientSet,
+) -> Result<Vec<(String, Vec<(&'a str, &'a Pod)>)>> {
+    let mut pods_with_nodes = Vec::with_capacity(pods.len());
+    for pod in pods {
+        pods_with_nodes.push((pod_node_name(pod, namespace)?, pod));
+    }
+
+    let node_groups: HashMap<String, String> = match group_label {
+        Some(label) => k8s_client
+            .nodes_api()
+            .list(&ListParams::default().labels(label))
+            .await
+            .context(ListK8sNodes)?
+            .into_iter()
+            .filter_map(|node| {
+                let group = node.labels().get(label)?.clone();
+                Some((node.name_any(), group))
+            })
+            .collect(),
+        None => HashMap::new(),
+    };
+
+    let mut groups: BTreeMap<String, Vec<(&str, &Pod)>> = BTreeMap::new();
+    let mut ungrouped = Vec::new();
+    for (node_name, pod) in pods_with_nodes {
+        match node_groups.get(node_name) {
+            Some(group) => groups
+                .entry(format!("{}={group}", group_label.unwrap_or_default()))
+                .or_default()
+                .push((node_name, pod)),
+            None => ungrouped.push((node_name.to_string(), vec![(node_name, pod)])),
+        }
+    }
+    Ok(groups.into_iter().chain(ungrouped).collect())
+}
+
+/// This returns the name of the node of the io-engine Pod.
+fn pod_node_name<'a>(pod: &'a Pod, namespace: &str) -> Result<&'a str> {
+    Ok(pod
+        .spec
+        .as_ref()
+        .ok_or(
+            EmptyPodSpec {
+                name: pod.name_any(),
+                namespace: namespace.to_string(),
+            }
+            .build(),
+        )?
+        .node_name
+        .as_ref()
+        .ok_or(
+            EmptyPodNodeName {
+                name: pod.name_any(),
+                namespace: namespace.to_string(),
+            }
+            .build(),
+        )?
+        .as_str())
+}
+
+/// Wait for the volumes with a target or a replica on the upgraded nodes to be healthy again,
+/// except for the ones which were unhealthy before the upgrade of the nodes. This returns false
+/// if they aren't healthy by the timeout.
+async fn wait_for_volumes_healthy(
+    node_names: &[&str],
+    unhealthy_volumes: &[Volume],
+    timeout: Duration,
+    rest_client: &RestClientSet,
+) -> Result<bool> {
+    let start = Instant::now();
+    loop {
+        let volumes = list_unhealthy_volumes(rest_client, unhealthy_volumes)
+            .await?
+            .into_iter()
+            .filter(|volume| volume_is_on_nodes(volume, node_names))
+            .map(|volume| volume.spec.uuid.to_string())
+            .collect::<Vec<_>>();
+        if volumes.is_empty() {
+            info!(nodes = ?node_names, "The volumes of the upgraded nodes are healthy");
+            return Ok(true);
+        }
+        if start.elapsed() >= timeout {
+            warn!(
+                nodes = ?node_names,
+                ?volumes,
+                "The volumes of the upgraded nodes are not healthy by the timeout"
+            );
+            return Ok(false);
+        }
+        info!(
+            nodes = ?node_names,
+            ?volumes,
+            "Waiting for the volumes of the upgraded nodes to be healthy"
+        );
+        tokio::time::sleep(Duration::from_secs(10_u64)).await;
+    }
+}
+
+/// This is a predicate to decide if the volume has its target or a replica on one of the nodes.
+fn volume_is_on_nodes(volume: &Volume, node_names: &[&str]) -> bool {
+    let target_node = volume
+        .state
+        .target
+        .as_ref()
+        .map(|target| target.node.as_str());
+    let replica_nodes = volume
+        .state
+        .replica_topology
+        .values()
+        .filter_map(|topology| topology.node.as_deref());
+    target_node
+        .into_iter()
+        .chain(replica_nodes)
+        .any(|node| node_names.contains(&node))
+}
+
 /// Uncordon storage Node.
 async fn uncordon_node(node_id: &str, rest_client: &RestClientSet) -> Result<()> {
     let drain_label_for_upgrade: String = DRAIN_FOR_UPGRADE.to_string();
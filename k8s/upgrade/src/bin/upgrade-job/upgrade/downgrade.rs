@@ -0,0 +1,147 @@
+use crate::common::{
+    error::{IncompatibleDataFormats, ListCrds, Result, YamlParseBufferForDataFormats},
+    kube_client::KubeClientSet,
+};
+use kube::api::ListParams;
+use semver::Version;
+use serde::Deserialize;
+use snafu::{ensure, ResultExt};
+use std::collections::BTreeMap;
+use tracing::{info, warn};
+
+/// The api group of the custom resources of the product, and the suffix of its sub-groups.
+const PRODUCT_CRD_GROUP: &str = "openebs.io";
+
+/// Struct to deserialize the data formats yaml.
+#[derive(Deserialize)]
+struct DataFormatsList {
+    data_formats: Vec<VersionDataFormats>,
+}
+
+/// The data formats written from the given version onwards.
+#[derive(Deserialize)]
+struct VersionDataFormats {
+    /// The first version which writes these formats.
+    version: Version,
+    /// The version of the specs stored in etcd.
+    #[serde(default)]
+    etcd_spec_version: Option<String>,
+    /// The versions served by the custom resource definitions, by the name of the definition.
+    #[serde(default)]
+    crd_versions: BTreeMap<String, Vec<String>>,
+}
+
+impl TryFrom<&[u8]> for DataFormatsList {
+    type Error = serde_yaml::Error;
+
+    /// Returns a DataFormatsList object.
+    fn try_from(bytes: &[u8]) -> std::result::Result<Self, Self::Error> {
+        serde_yaml::from_reader(bytes)
+    }
+}
+
+/// The data formats of a version.
+#[derive(Default)]
+struct DataFormats {
+    etcd_spec_version: Option<String>,
+    crd_versions: BTreeMap<String, Vec<String>>,
+}
+
+impl DataFormats {
+    /// Combine the data formats of all the versions up to the given one, the formats of a later
+    /// version replacing those of the earlier ones.
+    fn for_version(list: &DataFormatsList, version: &Version) -> Self {
+        let mut formats = Self::default();
+        for version_formats in &list.data_formats {
+            if version_formats.version > *version {
+                continue;
+            }
+            if let Some(etcd_spec_version) = &version_formats.etcd_spec_version {
+                formats.etcd_spec_version = Some(etcd_spec_version.clone());
+            }
+            formats
+                .crd_versions
+                .extend(version_formats.crd_versions.clone());
+        }
+        formats
+    }
+}
+
+/// Validate that the data written by the installed version can be read by the version it's
+/// downgraded to, i.e. that the specs in etcd are stored in a version the target version reads,
+/// and that the custom resources are stored in versions its custom resource definitions serve.
+/// The downgrade fails with the list of incompatibilities otherwise, as they'd corrupt the specs.
+pub(crate) async fn validate_data_formats(
+    namespace: &str,
+    from_version: &Version,
+    to_version: &Version,
+) -> Result<()> {
+    let buf = &include_bytes!("../../../../../upgrade/config/data_formats.yaml")[..];
+    let list = DataFormatsList::try_from(buf).context(YamlParseBufferForDataFormats)?;
+    let source = DataFormats::for_version(&list, from_version);
+    let target = DataFormats::for_version(&list, to_version);
+
+    let mut incompatibilities = Vec::new();
+
+    if source.etcd_spec_version != target.etcd_spec_version {
+        incompatibilities.push(format!(
+            "the specs in etcd are stored as version {}, which version {to_version} can't read, \
+            as it reads version {}",
+            source.etcd_spec_version.as_deref().unwrap_or("unknown"),
+            target.etcd_spec_version.as_deref().unwrap_or("unknown")
+        ));
+    }
+
+    let k8s_client = KubeClientSet::builder()
+        .with_namespace(namespace)
+        .build()
+        .await?;
+    let crds = k8s_client
+        .crd_api()
+        .list(&ListParams::default())
+        .await
+        .context(ListCrds)?;
+    for crd in crds.items {
+        let group = crd.spec.group.as_str();
+        if group != PRODUCT_CRD_GROUP && !group.ends_with(&format!(".{PRODUCT_CRD_GROUP}")) {
+            continue;
+        }
+        let name = crd.metadata.name.unwrap_or_default();
+        let Some(served_versions) = target.crd_versions.get(&name) else {
+            warn!(
+                crd = name,
+                "The versions of the CustomResourceDefinition which version {to_version} serves \
+                are unknown, skipping its data format validation"
+            );
+            continue;
+        };
+        let stored_versions = crd
+            .status
+            .and_then(|status| status.stored_versions)
+            .unwrap_or_default();
+        for stored_version in stored_versions {
+            if !served_versions.contains(&stored_version) {
+                incompatibilities.push(format!(
+                    "the {name} custom resources are stored as version {stored_version}, which \
+                    version {to_version} doesn't serve"
+                ));
+            }
+        }
+    }
+
+    ensure!(
+        incompatibilities.is_empty(),
+        IncompatibleDataFormats {
+            from_version: from_version.to_string(),
+            to_version: to_version.to_string(),
+            incompatibilities: incompatibilities.join("; "),
+        }
+    );
+
+    info!(
+        from_version = from_version.to_string(),
+        to_version = to_version.to_string(),
+        "Validated that the data formats are compatible with the downgrade"
+    );
+    Ok(())
+}
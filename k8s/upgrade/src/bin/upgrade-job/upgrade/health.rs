@@ -0,0 +1,78 @@
+use crate::common::{
+    error::{ListStorageNodes, ListStoragePools, ListStorageVolumes, Result},
+    rest_client::RestClientSet,
+};
+use serde::Serialize;
+use snafu::ResultExt;
+use std::collections::BTreeMap;
+
+/// A compact snapshot of the health of the cluster, i.e. the number of volumes, pools and
+/// storage nodes in each state. Snapshots taken before and after the upgrade are published with
+/// the upgrade events, so that regressions can be attributed to the upgrade.
+#[derive(Serialize, Clone, Debug, Default)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub(crate) struct HealthSnapshot {
+    volumes: BTreeMap<String, usize>,
+    pools: BTreeMap<String, usize>,
+    nodes: BTreeMap<String, usize>,
+}
+
+impl HealthSnapshot {
+    /// Capture the current health of the cluster using the storage REST API.
+    pub(crate) async fn capture(rest_client: &RestClientSet) -> Result<Self> {
+        let mut snapshot = Self::default();
+
+        // The number of volumes to get per request.
+        let max_entries = 200;
+        let mut starting_token = Some(0_isize);
+
+        // The last paginated request will set the `starting_token` to `None`.
+        while starting_token.is_some() {
+            let volumes = rest_client
+                .volumes_api()
+                .get_volumes(max_entries, None, starting_token)
+                .await
+                .context(ListStorageVolumes)?
+                .into_body();
+            starting_token = volumes.next_token;
+            for volume in volumes.entries {
+                count(&mut snapshot.volumes, format!("{:?}", volume.state.status));
+            }
+        }
+
+        let pools = rest_client
+            .pools_api()
+            .get_pools()
+            .await
+            .context(ListStoragePools)?
+            .into_body();
+        for pool in pools {
+            let status = pool
+                .state
+                .map(|state| format!("{:?}", state.status))
+                .unwrap_or_else(|| "Unknown".to_string());
+            count(&mut snapshot.pools, status);
+        }
+
+        let nodes = rest_client
+            .nodes_api()
+            .get_nodes(None)
+            .await
+            .context(ListStorageNodes)?
+            .into_body();
+        for node in nodes {
+            let status = node
+                .state
+                .map(|state| format!("{:?}", state.status))
+                .unwrap_or_else(|| "Unknown".to_string());
+            count(&mut snapshot.nodes, status);
+        }
+
+        Ok(snapshot)
+    }
+}
+
+/// Increment the count of resources in the given state.
+fn count(counts: &mut BTreeMap<String, usize>, state: String) {
+    *counts.entry(state).or_default() += 1;
+}
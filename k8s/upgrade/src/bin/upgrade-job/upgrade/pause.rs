@@ -0,0 +1,105 @@
+use crate::{
+    common::{
+        constants::{PRODUCT, UPGRADE_PAUSED_ANNOTATION},
+        error::{GetJob, PatchJob, Result},
+        kube_client::KubeClientSet,
+    },
+    events::event_recorder::{EventAction, EventRecorder},
+};
+use k8s_openapi::{api::batch::v1::Job, serde_json::json};
+use kube::{
+    api::{Patch, PatchParams},
+    Api, ResourceExt,
+};
+use snafu::ResultExt;
+use std::time::Duration;
+use tracing::info;
+
+/// Interval between the checks of the pause annotation of a paused upgrade.
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The pause and resume controls of the data-plane upgrade, i.e. the pause annotation of the
+/// upgrade Job, which is set by the 'upgrade pause' and removed by the 'upgrade resume' commands
+/// of the plugin. The data-plane upgrade checks it before each node, or group of nodes.
+pub(crate) struct PauseControl {
+    jobs_api: Api<Job>,
+    job_name: String,
+}
+
+impl PauseControl {
+    /// Create the pause controls of the upgrade Job.
+    pub(crate) fn new(k8s_client: &KubeClientSet, job_name: &str) -> Self {
+        Self {
+            jobs_api: k8s_client.jobs_api().clone(),
+            job_name: job_name.to_string(),
+        }
+    }
+
+    /// This is a predicate to decide if the upgrade is paused.
+    async fn is_paused(&self) -> Result<bool> {
+        let job = self
+            .jobs_api
+            .get(self.job_name.as_str())
+            .await
+            .context(GetJob {
+                name: self.job_name.clone(),
+            })?;
+        Ok(job
+            .annotations()
+            .get(UPGRADE_PAUSED_ANNOTATION)
+            .is_some_and(|value| value == "true"))
+    }
+
+    /// Pause the upgrade, eg: after the canary node has been upgraded.
+    pub(crate) async fn pause(&self) -> Result<()> {
+        let patch = json!({
+            "metadata": {
+                "annotations": {
+                    UPGRADE_PAUSED_ANNOTATION: "true"
+                }
+            }
+        });
+        self.jobs_api
+            .patch(
+                self.job_name.as_str(),
+                &PatchParams::default(),
+                &Patch::Merge(&patch),
+            )
+            .await
+            .context(PatchJob {
+                name: self.job_name.clone(),
+            })?;
+        Ok(())
+    }
+
+    /// Wait until the upgrade is resumed, if it's paused. The pause and the resume are published
+    /// as upgrade events.
+    pub(crate) async fn wait_while_paused(&self, event: &EventRecorder) -> Result<()> {
+        if !self.is_paused().await? {
+            return Ok(());
+        }
+
+        info!("{PRODUCT} data-plane upgrade is paused, waiting for it to be resumed");
+        event
+            .publish_normal(
+                format!(
+                    "Paused {PRODUCT} data-plane upgrade, it continues once resumed with the \
+                    'upgrade resume' command"
+                ),
+                EventAction::PausedDP,
+            )
+            .await?;
+
+        while self.is_paused().await? {
+            tokio::time::sleep(PAUSE_POLL_INTERVAL).await;
+        }
+
+        info!("{PRODUCT} data-plane upgrade is resumed");
+        event
+            .publish_normal(
+                format!("Resumed {PRODUCT} data-plane upgrade"),
+                EventAction::ResumedDP,
+            )
+            .await
+    }
+}
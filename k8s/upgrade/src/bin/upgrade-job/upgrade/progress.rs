@@ -0,0 +1,113 @@
+use crate::common::{constants::UPGRADE_STATUS_NAME_SUFFIX, error::RecordUpgradeStatus};
+use k8s_openapi::api::core::v1::ConfigMap;
+use kube::{
+    api::{ObjectMeta, Patch, PatchParams},
+    Api,
+};
+use serde::Serialize;
+use snafu::ResultExt;
+use std::collections::BTreeMap;
+use tracing::warn;
+
+/// Field manager of the server-side apply of the upgrade status.
+const FIELD_MANAGER: &str = "upgrade-job";
+
+/// The keys of the upgrade status ConfigMap.
+const PHASE_KEY: &str = "phase";
+const COMPONENT_KEY: &str = "component";
+const NODES_UPGRADED_KEY: &str = "nodesUpgraded";
+const NODES_TOTAL_KEY: &str = "nodesTotal";
+const LAST_ERROR_KEY: &str = "lastError";
+
+/// The progress of the upgrade. It's published with the upgrade events, and recorded in the
+/// upgrade status ConfigMap, for the dashboards of the cluster.
+#[derive(Serialize, Clone, Debug, Default)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub(crate) struct UpgradeProgress {
+    /// The phase of the upgrade, i.e. the action of its last event.
+    phase: String,
+    /// The component which is being upgraded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    component: Option<String>,
+    /// The number of io-engine nodes which are upgraded.
+    nodes_upgraded: usize,
+    /// The number of io-engine nodes to upgrade, except for the held ones.
+    nodes_total: usize,
+    /// The last error of the upgrade, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_error: Option<String>,
+}
+
+impl UpgradeProgress {
+    /// Updates the phase of the upgrade.
+    pub(crate) fn set_phase(&mut self, phase: String) {
+        self.phase = phase
+    }
+
+    /// Updates the component which is being upgraded.
+    pub(crate) fn set_component(&mut self, component: Option<String>) {
+        self.component = component
+    }
+
+    /// Updates the number of io-engine nodes which are upgraded, out of the total.
+    pub(crate) fn set_nodes(&mut self, nodes_upgraded: usize, nodes_total: usize) {
+        self.nodes_upgraded = nodes_upgraded;
+        self.nodes_total = nodes_total;
+    }
+
+    /// Updates the last error of the upgrade.
+    pub(crate) fn set_last_error(&mut self, error: String) {
+        self.last_error = Some(error)
+    }
+}
+
+/// This is the name of the upgrade status ConfigMap of the release.
+pub(crate) fn upgrade_status_name(release_name: &str) -> String {
+    format!("{release_name}-{UPGRADE_STATUS_NAME_SUFFIX}")
+}
+
+/// Record the progress of the upgrade in the upgrade status ConfigMap. This is informational, the
+/// upgrade doesn't fail if it can't be recorded.
+pub(crate) async fn record_progress(
+    config_maps_api: &Api<ConfigMap>,
+    name: &str,
+    progress: &UpgradeProgress,
+) {
+    let config_map = ConfigMap {
+        metadata: ObjectMeta {
+            name: Some(name.to_string()),
+            ..Default::default()
+        },
+        data: Some(BTreeMap::from([
+            (PHASE_KEY.to_string(), progress.phase.clone()),
+            (
+                COMPONENT_KEY.to_string(),
+                progress.component.clone().unwrap_or_default(),
+            ),
+            (
+                NODES_UPGRADED_KEY.to_string(),
+                progress.nodes_upgraded.to_string(),
+            ),
+            (
+                NODES_TOTAL_KEY.to_string(),
+                progress.nodes_total.to_string(),
+            ),
+            (
+                LAST_ERROR_KEY.to_string(),
+                progress.last_error.clone().unwrap_or_default(),
+            ),
+        ])),
+        ..Default::default()
+    };
+    if let Err(error) = config_maps_api
+        .patch(
+            name,
+            &PatchParams::apply(FIELD_MANAGER).force(),
+            &Patch::Apply(&config_map),
+        )
+        .await
+        .context(RecordUpgradeStatus { name })
+    {
+        warn!(%error, "Failed to record the progress of the upgrade");
+    }
+}
@@ -0,0 +1,176 @@
+use crate::common::{
+    constants::UPGRADE_STATE_NAME_SUFFIX,
+    error::{ClearUpgradeState, GetUpgradeState, RecordUpgradeState, Result},
+    kube_client::KubeClientSet,
+};
+use k8s_openapi::api::core::v1::ConfigMap;
+use kube::{
+    api::{DeleteParams, ObjectMeta, Patch, PatchParams},
+    Api,
+};
+use snafu::ResultExt;
+use std::collections::{BTreeMap, BTreeSet};
+use tracing::{info, warn};
+
+/// Field manager of the server-side apply of the upgrade state.
+const FIELD_MANAGER: &str = "upgrade-job";
+
+/// The key of the target version of the upgrade state ConfigMap. The other keys are the ones of
+/// the completed components.
+const TARGET_VERSION_KEY: &str = "targetVersion";
+
+/// The value of the keys of the completed components of the upgrade state ConfigMap.
+const COMPLETED: &str = "Completed";
+
+/// The components of the upgrade, in the order they are upgraded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum UpgradeComponent {
+    /// The helm release, i.e. the control-plane.
+    ControlPlane,
+    /// The restarts of the io-engine Pods.
+    DataPlane,
+    /// The io-engine of every node reporting the target version.
+    Convergence,
+}
+
+impl UpgradeComponent {
+    /// The key of the component in the upgrade state ConfigMap.
+    fn key(&self) -> &'static str {
+        match self {
+            Self::ControlPlane => "controlPlane",
+            Self::DataPlane => "dataPlane",
+            Self::Convergence => "convergence",
+        }
+    }
+
+    /// All of the components, in the order they are upgraded.
+    fn all() -> [Self; 3] {
+        [Self::ControlPlane, Self::DataPlane, Self::Convergence]
+    }
+}
+
+/// The components of the upgrade to a version which are completed. It's recorded in the upgrade
+/// state ConfigMap, so that an upgrade which is re-run after a transient failure skips them and
+/// continues from the component which failed.
+pub(crate) struct UpgradeState {
+    name: String,
+    target_version: String,
+    completed: BTreeSet<UpgradeComponent>,
+    config_maps_api: Api<ConfigMap>,
+}
+
+impl UpgradeState {
+    /// Load the upgrade state of the release. The state of an upgrade to another version is
+    /// discarded, as none of its components apply to this one.
+    pub(crate) async fn load(
+        namespace: &str,
+        release_name: &str,
+        target_version: &str,
+    ) -> Result<Self> {
+        let name = upgrade_state_name(release_name);
+        let config_maps_api = config_maps_api(namespace).await?;
+        let data = config_maps_api
+            .get_opt(name.as_str())
+            .await
+            .context(GetUpgradeState { name: name.clone() })?
+            .and_then(|config_map| config_map.data)
+            .unwrap_or_default();
+
+        let completed = match data.get(TARGET_VERSION_KEY) {
+            Some(version) if version == target_version => UpgradeComponent::all()
+                .into_iter()
+                .filter(|component| {
+                    data.get(component.key())
+                        .is_some_and(|value| value == COMPLETED)
+                })
+                .collect(),
+            _ => BTreeSet::new(),
+        };
+        if !completed.is_empty() {
+            info!(
+                ?completed,
+                version = target_version,
+                "Resuming the upgrade, skipping the completed components"
+            );
+        }
+
+        Ok(Self {
+            name,
+            target_version: target_version.to_string(),
+            completed,
+            config_maps_api,
+        })
+    }
+
+    /// Whether the component is completed for the target version.
+    pub(crate) fn is_completed(&self, component: UpgradeComponent) -> bool {
+        self.completed.contains(&component)
+    }
+
+    /// Record that the component is completed for the target version. The upgrade doesn't fail
+    /// if it can't be recorded, a re-run upgrade redoes the component instead.
+    pub(crate) async fn complete(&mut self, component: UpgradeComponent) {
+        self.completed.insert(component);
+
+        let mut data =
+            BTreeMap::from([(TARGET_VERSION_KEY.to_string(), self.target_version.clone())]);
+        for recorded in UpgradeComponent::all() {
+            let value = match self.is_completed(recorded) {
+                true => COMPLETED,
+                false => "",
+            };
+            data.insert(recorded.key().to_string(), value.to_string());
+        }
+        let config_map = ConfigMap {
+            metadata: ObjectMeta {
+                name: Some(self.name.clone()),
+                ..Default::default()
+            },
+            data: Some(data),
+            ..Default::default()
+        };
+        if let Err(error) = self
+            .config_maps_api
+            .patch(
+                self.name.as_str(),
+                &PatchParams::apply(FIELD_MANAGER).force(),
+                &Patch::Apply(&config_map),
+            )
+            .await
+            .context(RecordUpgradeState {
+                name: self.name.clone(),
+            })
+        {
+            warn!(%error, ?component, "Failed to record the completed upgrade component");
+        }
+    }
+}
+
+/// Clear the upgrade state of the release, eg: once it's rolled back, so that the next upgrade
+/// redoes all of its components.
+pub(crate) async fn clear_upgrade_state(namespace: &str, release_name: &str) -> Result<()> {
+    let name = upgrade_state_name(release_name);
+    match config_maps_api(namespace)
+        .await?
+        .delete(name.as_str(), &DeleteParams::default())
+        .await
+    {
+        Ok(_) => Ok(()),
+        Err(kube::Error::Api(error)) if error.code == 404 => Ok(()),
+        Err(error) => Err(error).context(ClearUpgradeState { name }),
+    }
+}
+
+/// This is the name of the upgrade state ConfigMap of the release.
+fn upgrade_state_name(release_name: &str) -> String {
+    format!("{release_name}-{UPGRADE_STATE_NAME_SUFFIX}")
+}
+
+/// Generate the ConfigMap api client of the namespace.
+async fn config_maps_api(namespace: &str) -> Result<Api<ConfigMap>> {
+    let k8s_client = KubeClientSet::builder()
+        .with_namespace(namespace)
+        .build()
+        .await?;
+    Ok(k8s_client.config_maps_api().clone())
+}
@@ -0,0 +1,307 @@
+use crate::{
+    common::{
+        constants::{PRODUCT, ROLLBACK_POINT_NAME_SUFFIX},
+        error::{
+            GetRollbackPoint, RecordRollbackPoint, Result, RollbackPointKeyMissing,
+            RollbackPointNotFound, U8VectorToString,
+        },
+        kube_client::KubeClientSet,
+    },
+    events::event_recorder::{EventAction, EventRecorder},
+    helm::client::HelmReleaseClient,
+    opts::CliArgs,
+    upgrade::{
+        data_plane::{upgrade_data_plane, DataPlanePacing},
+        resume::clear_upgrade_state,
+    },
+};
+use k8s_openapi::{api::core::v1::ConfigMap, serde_json::json};
+use kube::{
+    api::{ObjectMeta, Patch, PatchParams},
+    Api,
+};
+use serde::Serialize;
+use snafu::ResultExt;
+use std::{collections::BTreeMap, str};
+use tracing::{info, warn};
+
+/// Field manager of the server-side apply of the rollback point.
+const FIELD_MANAGER: &str = "upgrade-job";
+
+/// The keys of the rollback point ConfigMap.
+const REVISION_KEY: &str = "revision";
+const SOURCE_VERSION_KEY: &str = "sourceVersion";
+const TARGET_VERSION_KEY: &str = "targetVersion";
+const VALUES_KEY: &str = "values.yaml";
+const STATE_KEY: &str = "rollbackState";
+const MESSAGE_KEY: &str = "rollbackMessage";
+
+/// The state of the rollback of the release.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum RollbackState {
+    /// The source of the upgrade is recorded, no rollback has been run.
+    #[serde(rename = "Not Started")]
+    NotStarted,
+    #[serde(rename = "In Progress")]
+    InProgress,
+    #[serde(rename = "Succeeded")]
+    Succeeded,
+    #[serde(rename = "Failed")]
+    Failed,
+}
+
+impl ToString for RollbackState {
+    fn to_string(&self) -> String {
+        match self {
+            Self::NotStarted => String::from("Not Started"),
+            Self::InProgress => String::from("In Progress"),
+            Self::Succeeded => String::from("Succeeded"),
+            Self::Failed => String::from("Failed"),
+        }
+    }
+}
+
+/// The status of the rollback of the release to the source of its last upgrade. It's published
+/// with the upgrade events, and recorded in the rollback point ConfigMap.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub(crate) struct RollbackStatus {
+    /// The chart version the release is rolled back to.
+    version: String,
+    /// The helm revision the release is rolled back to.
+    revision: String,
+    state: RollbackState,
+    /// Why the rollback failed, if it did.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+}
+
+/// The recorded source of an upgrade, which the release is rolled back to. Rolling back to the
+/// helm revision restores both the chart version and the values of the release.
+struct RollbackPoint {
+    revision: String,
+    source_version: String,
+}
+
+/// This is the name of the rollback point ConfigMap of the release.
+fn rollback_point_name(release_name: &str) -> String {
+    format!("{release_name}-{ROLLBACK_POINT_NAME_SUFFIX}")
+}
+
+/// Record the deployed revision of the release, its chart version and its values, before it's
+/// upgraded. This replaces the rollback point of any previous upgrade.
+pub(crate) async fn record_rollback_point(
+    namespace: String,
+    release_name: String,
+    from_version: String,
+    to_version: String,
+) -> Result<()> {
+    let client = HelmReleaseClient::builder()
+        .with_namespace(namespace.as_str())
+        .build()?;
+    let release = client.release_info(release_name.as_str())?;
+    let values = client.get_values_as_yaml::<_, String>(release_name.as_str(), None)?;
+    let values = str::from_utf8(values.as_slice())
+        .context(U8VectorToString)?
+        .to_string();
+
+    let name = rollback_point_name(release_name.as_str());
+    let config_map = ConfigMap {
+        metadata: ObjectMeta {
+            name: Some(name.clone()),
+            namespace: Some(namespace.clone()),
+            ..Default::default()
+        },
+        data: Some(BTreeMap::from([
+            (REVISION_KEY.to_string(), release.revision()),
+            (SOURCE_VERSION_KEY.to_string(), from_version.clone()),
+            (TARGET_VERSION_KEY.to_string(), to_version),
+            (VALUES_KEY.to_string(), values),
+            (STATE_KEY.to_string(), RollbackState::NotStarted.to_string()),
+            (MESSAGE_KEY.to_string(), String::new()),
+        ])),
+        ..Default::default()
+    };
+    config_maps_api(namespace.as_str())
+        .await?
+        .patch(
+            name.as_str(),
+            &PatchParams::apply(FIELD_MANAGER).force(),
+            &Patch::Apply(&config_map),
+        )
+        .await
+        .context(RecordRollbackPoint { name })?;
+
+    info!(
+        revision = release.revision(),
+        version = from_version,
+        "Recorded the rollback point of the helm release"
+    );
+    Ok(())
+}
+
+/// Roll the release back to its recorded rollback point: the helm release is rolled back to the
+/// recorded revision, and then the io-engine Pods are restarted one node at a time, the same way
+/// as for the data-plane upgrade. The status of the rollback is published with the upgrade events
+/// and recorded in the rollback point ConfigMap.
+pub(crate) async fn rollback_release(opts: &CliArgs, event: &mut EventRecorder) -> Result<()> {
+    let namespace = opts.namespace();
+    let release_name = opts.release_name();
+    let point = match load_rollback_point(namespace.as_str(), release_name.as_str()).await {
+        Ok(point) => point,
+        Err(error) => {
+            event.publish_rollback_failure(&error, true).await;
+            return Err(error);
+        }
+    };
+
+    let mut status = RollbackStatus {
+        version: point.source_version.clone(),
+        revision: point.revision.clone(),
+        state: RollbackState::InProgress,
+        message: None,
+    };
+    record_rollback_status(namespace.as_str(), release_name.as_str(), &status).await;
+    event.set_rollback(Some(status.clone()));
+    event
+        .publish_normal(
+            format!(
+                "Rolling back {PRODUCT} to version {} (helm revision {})",
+                point.source_version, point.revision
+            ),
+            EventAction::RollingBack,
+        )
+        .await?;
+
+    let result = rollback_to_point(opts, &point, event).await;
+
+    status.state = match &result {
+        Ok(()) => RollbackState::Succeeded,
+        Err(error) => {
+            status.message = Some(error.to_string());
+            RollbackState::Failed
+        }
+    };
+    record_rollback_status(namespace.as_str(), release_name.as_str(), &status).await;
+    event.set_rollback(Some(status));
+
+    match result {
+        Ok(()) => {
+            // The components of the upgrade are redone by the next upgrade.
+            if let Err(error) = clear_upgrade_state(namespace.as_str(), release_name.as_str()).await
+            {
+                warn!(%error, "Failed to clear the state of the rolled back upgrade");
+            }
+            event
+                .publish_normal(
+                    format!("Rolled back {PRODUCT} to version {}", point.source_version),
+                    EventAction::RolledBack,
+                )
+                .await
+        }
+        Err(error) => {
+            event.publish_rollback_failure(&error, false).await;
+            Err(error)
+        }
+    }
+}
+
+/// Run the helm rollback, and restart the io-engine Pods which don't run the chart version of the
+/// rollback point.
+async fn rollback_to_point(
+    opts: &CliArgs,
+    point: &RollbackPoint,
+    event: &EventRecorder,
+) -> Result<()> {
+    let client = HelmReleaseClient::builder()
+        .with_namespace(opts.namespace())
+        .with_timeout(opts.helm_timeout())
+        .build()?;
+    info!(revision = point.revision, "Starting helm rollback...");
+    client.rollback(opts.release_name(), point.revision.as_str())?;
+    info!("Helm rollback successful!");
+
+    if opts.skip_data_plane_restart() {
+        return Ok(());
+    }
+    upgrade_data_plane(
+        opts.namespace(),
+        opts.rest_endpoint(),
+        point.source_version.clone(),
+        Vec::new(),
+        DataPlanePacing {
+            canary: false,
+            ..opts.data_plane_pacing()
+        },
+        event,
+    )
+    .await
+}
+
+/// Load the rollback point of the release.
+async fn load_rollback_point(namespace: &str, release_name: &str) -> Result<RollbackPoint> {
+    let name = rollback_point_name(release_name);
+    let config_map = config_maps_api(namespace)
+        .await?
+        .get_opt(name.as_str())
+        .await
+        .context(GetRollbackPoint { name: name.clone() })?
+        .ok_or(
+            RollbackPointNotFound {
+                name: name.clone(),
+                namespace: namespace.to_string(),
+            }
+            .build(),
+        )?;
+
+    let data = config_map.data.unwrap_or_default();
+    let value = |key: &str| {
+        data.get(key).cloned().ok_or(
+            RollbackPointKeyMissing {
+                name: name.clone(),
+                key: key.to_string(),
+            }
+            .build(),
+        )
+    };
+    Ok(RollbackPoint {
+        revision: value(REVISION_KEY)?,
+        source_version: value(SOURCE_VERSION_KEY)?,
+    })
+}
+
+/// Record the status of the rollback in the rollback point ConfigMap. This is informational, the
+/// rollback doesn't fail if it can't be recorded.
+async fn record_rollback_status(namespace: &str, release_name: &str, status: &RollbackStatus) {
+    let name = rollback_point_name(release_name);
+    let patch = json!({
+        "data": {
+            STATE_KEY: status.state.to_string(),
+            MESSAGE_KEY: status.message.clone().unwrap_or_default(),
+        }
+    });
+    let result = match config_maps_api(namespace).await {
+        Ok(api) => api
+            .patch(
+                name.as_str(),
+                &PatchParams::default(),
+                &Patch::Merge(&patch),
+            )
+            .await
+            .context(RecordRollbackPoint { name })
+            .map(|_| ()),
+        Err(error) => Err(error),
+    };
+    if let Err(error) = result {
+        warn!(%error, "Failed to record the status of the rollback");
+    }
+}
+
+/// Generate the ConfigMap api client of the namespace.
+async fn config_maps_api(namespace: &str) -> Result<Api<ConfigMap>> {
+    let k8s_client = KubeClientSet::builder()
+        .with_namespace(namespace)
+        .build()
+        .await?;
+    Ok(k8s_client.config_maps_api().clone())
+}
@@ -65,6 +65,12 @@ pub(crate) fn upgrade_event_selector(release_name: &str, component_name: &str) -
 
 pub(crate) const HELM_RELEASE_NAME_LABEL: &str = "openebs.io/release";
 
+/// Label selector of the secret in which helm stores the deployed revision of a release.
+pub(crate) const HELM_DEPLOYED_RELEASE_SELECTOR: &str = "owner=helm,status=deployed";
+
+/// Key of the helm release in the data of its secret.
+pub(crate) const HELM_RELEASE_KEY: &str = "release";
+
 pub(crate) const DEFAULT_IMAGE_REGISTRY: &str = "docker.io";
 
 /// The upgrade job will use the UPGRADE_JOB_IMAGE_NAME image (below) with this tag.
@@ -79,6 +85,20 @@ pub(crate) const UPGRADE_JOB_IMAGE_NAME: &str = "mayastor-upgrade-job";
 /// Upgrade job name suffix.
 pub(crate) const UPGRADE_JOB_NAME_SUFFIX: &str = "upgrade";
 
+/// Actions of the upgrade events with which the upgrade job ends without upgrading the release.
+pub(crate) const UPGRADE_EVENT_FAILED_ACTIONS: [&str; 4] = [
+    "Failed",
+    "Validation Failed",
+    "Rolled back",
+    "Rollback Failed",
+];
+
+/// Action of the upgrade event with which the upgrade job ends after upgrading the release.
+pub(crate) const UPGRADE_EVENT_SUCCESSFUL_ACTION: &str = "Successful";
+
+/// Annotation of the upgrade job which pauses the data plane upgrade, when set to "true".
+pub(crate) const UPGRADE_PAUSED_ANNOTATION: &str = "openebs.io/upgrade-paused";
+
 /// ConfigMap name for upgrade.
 pub(crate) const UPGRADE_CONFIG_MAP: &str = "upgrade-config-map";
 
@@ -115,12 +135,22 @@ pub(crate) const SINGLE_REPLICA_VOLUME: u8 = 1;
 /// IO_ENGINE_POD_LABEL is the Kubernetes Pod label set on mayastor-io-engine Pods.
 pub(crate) const IO_ENGINE_POD_LABEL: &str = "app=io-engine";
 
+/// CSI_NODE_POD_LABEL is the Kubernetes Pod label set on mayastor-csi-node Pods.
+pub(crate) const CSI_NODE_POD_LABEL: &str = "app=csi-node";
+
 /// AGENT_CORE_POD_LABEL is the Kubernetes Pod label set on mayastor-agent-core Pods.
 pub(crate) const AGENT_CORE_POD_LABEL: &str = "app=agent-core";
 
 /// API_REST_POD_LABEL is the Kubernetes Pod label set on mayastor-api-rest Pods.
 pub(crate) const API_REST_POD_LABEL: &str = "app=api-rest";
 
+/// ETCD_POD_LABEL is the Kubernetes Pod label set on the etcd Pods of the release.
+pub(crate) const ETCD_POD_LABEL: &str = "app=etcd";
+
+/// The labels of the control plane Pods which must be ready before upgrade.
+pub(crate) const CONTROL_PLANE_POD_LABELS: [&str; 3] =
+    [ETCD_POD_LABEL, AGENT_CORE_POD_LABEL, API_REST_POD_LABEL];
+
 /// UPGRADE_EVENT_REASON is the reason field in upgrade job.
 pub(crate) const UPGRADE_EVENT_REASON: &str = "MayastorUpgrade";
 
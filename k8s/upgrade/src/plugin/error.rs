@@ -259,6 +259,122 @@ pub enum Error {
     /// Error for key not present in Map.
     #[snafu(display("Specified key not present."))]
     SpecifiedKeyNotPresent,
+
+    /// Error for when json could not be parsed from a file.
+    #[snafu(display("Failed to parse JSON at {}: {}", filepath.display(), source))]
+    JsonParseFromFile {
+        source: serde_json::Error,
+        filepath: PathBuf,
+    },
+
+    /// Error when extracting the support bundle archive.
+    #[snafu(display("Failed to extract support bundle {}: {}", filepath.display(), source))]
+    ExtractSupportBundle {
+        source: std::io::Error,
+        filepath: PathBuf,
+    },
+
+    /// Error for when the support bundle doesn't contain the recorded cluster state.
+    #[snafu(display("No recorded cluster state found in support bundle {}", filepath.display()))]
+    InvalidSupportBundle { filepath: PathBuf },
+
+    /// Error for when the node requirements yaml could not be parsed from bytes.
+    #[snafu(display("Failed to parse node requirements yaml: {}", source))]
+    YamlParseBufferForNodeRequirements { source: serde_yaml::Error },
+
+    /// Error for when a quantity of the node requirements is not valid.
+    #[snafu(display("Failed to parse {} as a valid quantity", quantity))]
+    InvalidQuantity { quantity: String },
+
+    /// Error for when a Kubernetes API request for GET-ing a Node fails.
+    #[snafu(display("Failed to get Kubernetes Node {}: {}", node, source))]
+    GetK8sNode { source: kube::Error, node: String },
+
+    /// Some io-engine nodes don't meet the node-level requirements of the target version.
+    #[snafu(display("Nodes don't meet the requirements of the target version."))]
+    NodeRequirementsNotMet,
+
+    /// Error for when a Kubernetes API request for GET-ing a list of resources filtered by
+    /// label(s) fails.
+    #[snafu(display(
+        "Failed to list {} with label {} in namespace {}: {}",
+        kind,
+        label,
+        namespace,
+        source
+    ))]
+    ListResourcesWithLabel {
+        source: kube::Error,
+        kind: String,
+        label: String,
+        namespace: String,
+    },
+
+    /// Error listing the pools.
+    #[snafu(display("Failed to list pools : {}", source))]
+    ListPools {
+        source: openapi::tower::client::Error<openapi::models::RestJsonError>,
+    },
+
+    /// Some pools are not online.
+    #[snafu(display("Pools are not online."))]
+    PoolsNotOnline,
+
+    /// The control plane is not healthy.
+    #[snafu(display("Control plane is not healthy."))]
+    ControlPlaneNotHealthy,
+
+    /// Error for when the helm release stored in a secret could not be decoded.
+    #[snafu(display("Failed to decode the helm release in secret {}: {}", name, source))]
+    HelmReleaseDecode {
+        source: std::io::Error,
+        name: String,
+    },
+
+    /// Error for when the upgrade job is still running.
+    #[snafu(display("Upgrade Job: {} in namespace {} is still running.", name, namespace))]
+    UpgradeJobInProgress { name: String, namespace: String },
+
+    /// Error for when the upgrade job is not running.
+    #[snafu(display("Upgrade Job: {} in namespace {} is not running.", name, namespace))]
+    UpgradeJobNotRunning { name: String, namespace: String },
+
+    /// Failed in patching upgrade job.
+    #[snafu(display("Upgrade Job: {} patch failed Error: {}", name, source))]
+    UpgradeJobPatch { name: String, source: kube::Error },
+
+    /// Error for when yaml could not be parsed from bytes.
+    #[snafu(display("Failed to parse upgrade path yaml: {}", source))]
+    YamlParseBufferForUpgradePath { source: serde_yaml::Error },
+
+    /// Error for when the installation is too old to be upgraded to the target version directly.
+    #[snafu(display(
+        "Upgrade from version {} to version {} requires upgrading through versions {} first",
+        source_version,
+        target_version,
+        intermediate_versions
+    ))]
+    MultiHopUpgradeRequired {
+        source_version: String,
+        target_version: String,
+        intermediate_versions: String,
+    },
+
+    /// Error for when the upgrade to an intermediate version failed.
+    #[snafu(display("Upgrade to the intermediate version {} failed", version))]
+    IntermediateUpgradeFailed { version: String },
+
+    /// Error for when the upgrade status can't be serialized to json.
+    #[snafu(display("Failed to serialize the upgrade status to json: {}", source))]
+    UpgradeStatusJsonSerialization { source: serde_json::Error },
+
+    /// Error for when the upgrade status can't be serialized to yaml.
+    #[snafu(display("Failed to serialize the upgrade status to yaml: {}", source))]
+    UpgradeStatusYamlSerialization { source: serde_yaml::Error },
+
+    /// Error for when the followed upgrade ended without upgrading the release.
+    #[snafu(display("Upgrade ended with phase: {}", phase))]
+    UpgradeFailed { phase: String },
 }
 
 /// A wrapper type to remove repeated Result<T, Error> returns.
@@ -319,6 +435,27 @@ impl From<Error> for i32 {
             Error::UpgradeConfigMapDelete { .. } => 450,
             Error::InvalidSetFileArguments { .. } => 451,
             Error::SpecifiedKeyNotPresent { .. } => 452,
+            Error::JsonParseFromFile { .. } => 453,
+            Error::ExtractSupportBundle { .. } => 454,
+            Error::InvalidSupportBundle { .. } => 455,
+            Error::YamlParseBufferForNodeRequirements { .. } => 456,
+            Error::InvalidQuantity { .. } => 457,
+            Error::GetK8sNode { .. } => 458,
+            Error::NodeRequirementsNotMet { .. } => 459,
+            Error::ListResourcesWithLabel { .. } => 460,
+            Error::HelmReleaseDecode { .. } => 461,
+            Error::ListPools { .. } => 462,
+            Error::PoolsNotOnline { .. } => 463,
+            Error::ControlPlaneNotHealthy { .. } => 464,
+            Error::UpgradeJobInProgress { .. } => 465,
+            Error::UpgradeJobNotRunning { .. } => 466,
+            Error::UpgradeJobPatch { .. } => 467,
+            Error::YamlParseBufferForUpgradePath { .. } => 468,
+            Error::MultiHopUpgradeRequired { .. } => 469,
+            Error::IntermediateUpgradeFailed { .. } => 470,
+            Error::UpgradeStatusJsonSerialization { .. } => 471,
+            Error::UpgradeStatusYamlSerialization { .. } => 472,
+            Error::UpgradeFailed { .. } => 473,
         }
     }
 }
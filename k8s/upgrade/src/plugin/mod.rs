@@ -7,6 +7,18 @@ pub mod upgrade;
 /// Validations before applying upgrade.
 pub mod preflight_validations;
 
+/// Node-level requirements of the target io-engine version.
+pub(crate) mod node_requirements;
+
+/// Plan of the upgrade, printed on dry-run.
+pub(crate) mod plan;
+
+/// Offline upgrade simulation against a support bundle.
+pub mod simulate;
+
+/// Upgrade path through the intermediate versions.
+pub(crate) mod upgrade_path;
+
 /// Module for user messages.
 pub(crate) mod user_prompt;
 
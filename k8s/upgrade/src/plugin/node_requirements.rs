@@ -0,0 +1,200 @@
+use crate::plugin::{
+    constants::{get_image_version_tag, CSI_NODE_POD_LABEL, IO_ENGINE_POD_LABEL},
+    error,
+};
+use k8s_openapi::api::core::v1::{Node, Pod};
+use kube::{
+    api::{Api, ListParams},
+    Client,
+};
+use semver::Version;
+use serde::Deserialize;
+use snafu::ResultExt;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Name of the hugepages resource required by the io-engine.
+const HUGEPAGES_RESOURCE: &str = "hugepages-2Mi";
+
+/// Size of a single 2MiB hugepage.
+const HUGEPAGE_SIZE: u64 = 2 * 1024 * 1024;
+
+/// Struct to deserialize the node requirements yaml.
+#[derive(Deserialize)]
+struct NodeRequirementsList {
+    node_requirements: Vec<VersionRequirements>,
+}
+
+/// Node-level requirements of the io-engine, from the given version onwards.
+#[derive(Deserialize)]
+struct VersionRequirements {
+    /// The first io-engine version which needs these requirements.
+    version: Version,
+    /// Minimum quantity of allocatable 2MiB hugepages, eg: 2Gi.
+    #[serde(default)]
+    hugepages_2mi: Option<String>,
+    /// Kernel modules which must be loaded on the node.
+    #[serde(default)]
+    kernel_modules: Vec<String>,
+}
+
+impl TryFrom<&[u8]> for NodeRequirementsList {
+    type Error = serde_yaml::Error;
+
+    /// Returns a NodeRequirementsList object.
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        serde_yaml::from_reader(bytes)
+    }
+}
+
+/// Node-level requirements of the target io-engine version.
+#[derive(Default)]
+pub(crate) struct NodeRequirements {
+    /// Minimum bytes of allocatable 2MiB hugepages.
+    hugepages_bytes: u64,
+    /// Kernel modules which must be loaded on the node.
+    kernel_modules: BTreeSet<String>,
+}
+
+impl NodeRequirements {
+    /// Combine the requirements of all the versions up to the version of this plugin. The
+    /// requirements of all the versions apply if it isn't a release version.
+    pub(crate) fn for_target_version() -> error::Result<Self> {
+        let buf = &std::include_bytes!("../../config/node_requirements.yaml")[..];
+        let list = NodeRequirementsList::try_from(buf)
+            .context(error::YamlParseBufferForNodeRequirements)?;
+        let target = Version::parse(get_image_version_tag().trim_start_matches('v')).ok();
+
+        let mut requirements = Self::default();
+        for version_requirements in list.node_requirements {
+            if matches!(&target, Some(target) if *target < version_requirements.version) {
+                continue;
+            }
+            if let Some(quantity) = &version_requirements.hugepages_2mi {
+                let bytes = parse_quantity(quantity).ok_or(
+                    error::InvalidQuantity {
+                        quantity: quantity.clone(),
+                    }
+                    .build(),
+                )?;
+                requirements.hugepages_bytes = requirements.hugepages_bytes.max(bytes);
+            }
+            requirements
+                .kernel_modules
+                .extend(version_requirements.kernel_modules);
+        }
+        Ok(requirements)
+    }
+
+    /// Returns the io-engine nodes which don't meet the requirements, with the steps to
+    /// remediate each of them.
+    pub(crate) async fn unprepared_nodes(
+        &self,
+        namespace: &str,
+    ) -> error::Result<BTreeMap<String, Vec<String>>> {
+        let client = Client::try_default().await.context(error::K8sClient)?;
+        let pods: Api<Pod> = Api::namespaced(client.clone(), namespace);
+        let nodes: Api<Node> = Api::all(client);
+
+        let io_engine_nodes = pods_ready_by_node(&pods, IO_ENGINE_POD_LABEL, namespace).await?;
+        // The kernel modules of the nodes are not visible through the kubernetes api, but the
+        // csi-node refuses to start without them, so its pods are checked to be ready instead.
+        let csi_nodes = pods_ready_by_node(&pods, CSI_NODE_POD_LABEL, namespace).await?;
+
+        let mut unprepared_nodes = BTreeMap::new();
+        for node_name in io_engine_nodes.keys() {
+            let node = nodes
+                .get_opt(node_name)
+                .await
+                .context(error::GetK8sNode { node: node_name })?;
+            let mut steps = Vec::new();
+
+            let hugepages_bytes = node
+                .as_ref()
+                .and_then(|node| node.status.as_ref())
+                .and_then(|status| status.allocatable.as_ref())
+                .and_then(|allocatable| allocatable.get(HUGEPAGES_RESOURCE))
+                .and_then(|quantity| parse_quantity(&quantity.0))
+                .unwrap_or_default();
+            if hugepages_bytes < self.hugepages_bytes {
+                let count = (self.hugepages_bytes + HUGEPAGE_SIZE - 1) / HUGEPAGE_SIZE;
+                steps.push(format!(
+                    "Reserve at least {count} 2MiB hugepages, eg: `echo {count} | sudo tee \
+                    /sys/kernel/mm/hugepages/hugepages-2048kB/nr_hugepages`, and restart the \
+                    kubelet"
+                ));
+            }
+
+            if !self.kernel_modules.is_empty() && csi_nodes.get(node_name) != Some(&true) {
+                let modules = self
+                    .kernel_modules
+                    .iter()
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                steps.push(format!(
+                    "Load the kernel modules, eg: `sudo modprobe -a {modules}`, and persist them \
+                    in /etc/modules-load.d"
+                ));
+            }
+
+            if !steps.is_empty() {
+                unprepared_nodes.insert(node_name.clone(), steps);
+            }
+        }
+        Ok(unprepared_nodes)
+    }
+}
+
+/// Returns the nodes of the pods with the given label, and whether all of their pods are ready.
+async fn pods_ready_by_node(
+    pods: &Api<Pod>,
+    label: &str,
+    namespace: &str,
+) -> error::Result<HashMap<String, bool>> {
+    let pod_list = pods
+        .list(&ListParams::default().labels(label))
+        .await
+        .context(error::ListPodsWithLabel {
+            label: label.to_string(),
+            namespace: namespace.to_string(),
+        })?;
+    let mut nodes = HashMap::new();
+    for pod in pod_list.iter() {
+        let Some(node) = pod.spec.as_ref().and_then(|spec| spec.node_name.clone()) else {
+            continue;
+        };
+        let ready = pod
+            .status
+            .as_ref()
+            .and_then(|status| status.conditions.as_ref())
+            .map_or(false, |conditions| {
+                conditions
+                    .iter()
+                    .any(|condition| condition.type_ == "Ready" && condition.status == "True")
+            });
+        *nodes.entry(node).or_insert(ready) &= ready;
+    }
+    Ok(nodes)
+}
+
+/// Parse a kubernetes quantity into bytes, eg: 2Gi or 1024Mi.
+fn parse_quantity(quantity: &str) -> Option<u64> {
+    let quantity = quantity.trim();
+    let split = quantity
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(quantity.len());
+    let (value, suffix) = quantity.split_at(split);
+    let multiplier: u64 = match suffix {
+        "" => 1,
+        "k" => 1000,
+        "M" => 1000u64.pow(2),
+        "G" => 1000u64.pow(3),
+        "T" => 1000u64.pow(4),
+        "Ki" => 1024,
+        "Mi" => 1024u64.pow(2),
+        "Gi" => 1024u64.pow(3),
+        "Ti" => 1024u64.pow(4),
+        _ => return None,
+    };
+    value.parse::<u64>().ok()?.checked_mul(multiplier)
+}
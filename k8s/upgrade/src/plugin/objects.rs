@@ -168,7 +168,7 @@ pub(crate) fn upgrade_job_cluster_role(
             },
             PolicyRule {
                 api_groups: Some(vec!["batch"].into_vec()),
-                resources: Some(vec!["cronjobs"].into_vec()),
+                resources: Some(vec!["cronjobs", "jobs"].into_vec()),
                 verbs: vec!["create", "list", "delete", "get", "patch"].into_vec(),
                 ..Default::default()
             },
@@ -278,6 +278,7 @@ pub(crate) fn upgrade_job(
     release_name: String,
     args: &UpgradeArgs,
     set_file: String,
+    values: String,
     image_pull_secrets: Option<Vec<k8s_openapi::api::core::v1::LocalObjectReference>>,
     image_pull_policy: Option<String>,
 ) -> Job {
@@ -289,12 +290,57 @@ pub(crate) fn upgrade_job(
         format!("--helm-args-set={helm_args_set}"),
         format!("--helm-args-set-file={set_file}"),
     ];
+    if !values.is_empty() {
+        job_args.push(format!("--helm-args-values={values}"));
+    }
     if args.skip_data_plane_restart {
         job_args.push("--skip-data-plane-restart".to_string());
     }
     if args.skip_upgrade_path_validation_for_unsupported_version {
         job_args.push("--skip-upgrade-path-validation".to_string());
     }
+    if args.allow_downgrade {
+        job_args.push("--allow-downgrade".to_string());
+    }
+    if !args.held_nodes.is_empty() {
+        job_args.push(format!("--held-nodes={}", args.held_nodes.join(",")));
+    }
+    if !args.set_image.is_empty() {
+        job_args.push(format!("--image-overrides={}", args.set_image.join(",")));
+    }
+    if let Some(helm_history_max) = args.helm_history_max {
+        job_args.push(format!("--helm-history-max={helm_history_max}"));
+    }
+    if let Some(timeout) = args.helm_timeout {
+        job_args.push(format!("--helm-timeout={timeout}"));
+    }
+    if args.no_atomic {
+        job_args.push("--no-atomic".to_string());
+    }
+    if args.helm_wait {
+        job_args.push("--helm-wait".to_string());
+    }
+    if args.helm_wait_for_jobs {
+        job_args.push("--helm-wait-for-jobs".to_string());
+    }
+    if let Some(timeout) = args.data_plane_convergence_timeout {
+        job_args.push(format!("--data-plane-convergence-timeout={timeout}"));
+    }
+    if let Some(label) = &args.data_plane_group_label {
+        job_args.push(format!("--data-plane-group-label={label}"));
+    }
+    if args.canary {
+        job_args.push("--canary".to_string());
+    }
+    if let Some(timeout) = args.volume_health_timeout {
+        job_args.push(format!("--volume-health-timeout={timeout}"));
+    }
+    if args.rollback_on_failure {
+        job_args.push("--rollback-on-failure".to_string());
+    }
+    if args.rollback {
+        job_args.push("--rollback".to_string());
+    }
 
     Job {
         metadata: ObjectMeta {
@@ -0,0 +1,408 @@
+use crate::plugin::{
+    constants::{
+        get_image_version_tag, HELM_DEPLOYED_RELEASE_SELECTOR, HELM_RELEASE_KEY,
+        HELM_RELEASE_NAME_LABEL, IO_ENGINE_POD_LABEL,
+    },
+    error,
+    upgrade::{get_release_name, get_source_version, UpgradeArgs},
+    user_prompt::{
+        upgrade_dry_run_summary, CONTROL_PLANE_PLAN, DATA_PLANE_PLAN, DATA_PLANE_PLAN_SKIP_RESTART,
+        HELD_BACK_PODS_PLAN, IMAGES_PLAN, UPGRADE_DRY_RUN_SUMMARY, VALUES_PLAN,
+    },
+};
+use base64::Engine;
+use flate2::read::GzDecoder;
+use k8s_openapi::{
+    api::{
+        apps::v1::{DaemonSet, Deployment, StatefulSet},
+        core::v1::{Pod, PodTemplateSpec, Secret},
+    },
+    Resource,
+};
+use kube::{
+    api::{Api, ListParams},
+    Client, ResourceExt,
+};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::Value;
+use snafu::ResultExt;
+use std::{collections::BTreeMap, fs, io::Read};
+
+/// Value shown for the values which aren't set.
+const UNSET_VALUE: &str = "<unset>";
+
+/// Release as stored by helm, of which only the user supplied values are decoded.
+#[derive(Deserialize)]
+struct HelmRelease {
+    #[serde(default)]
+    config: Value,
+}
+
+/// Change of a user supplied value of the helm release.
+struct ValueChange {
+    key: String,
+    from: String,
+    to: String,
+}
+
+/// UpgradePlan is what the upgrade would do, computed from the live cluster without changing
+/// anything: the images of the workloads, the changes to the user supplied values of the helm
+/// release and the order in which the pods restart.
+pub(crate) struct UpgradePlan {
+    release_name: String,
+    from_version: String,
+    to_version: String,
+    /// The images of the workloads of the release, with the ones they are upgraded to.
+    images: BTreeMap<String, String>,
+    values: Vec<ValueChange>,
+    /// The workloads of the release whose pods are restarted by the helm upgrade.
+    control_plane: Vec<String>,
+    /// The io-engine pods which are restarted one node at a time, in this order, as node/pod.
+    data_plane: Vec<String>,
+    /// The io-engine pods whose restart is held back, as node/pod.
+    held: Vec<String>,
+    skip_data_plane_restart: bool,
+}
+
+impl UpgradePlan {
+    /// Compute the plan of the upgrade with the given arguments.
+    pub(crate) async fn compute(namespace: &str, args: &UpgradeArgs) -> error::Result<Self> {
+        let client = Client::try_default().await.context(error::K8sClient)?;
+        let release_name = get_release_name(namespace).await?;
+        let from_version = get_source_version(namespace).await?;
+        let to_version = get_image_version_tag();
+        let release_selector = format!("{HELM_RELEASE_NAME_LABEL}={release_name}");
+
+        // Every pod of the release is restarted by the helm upgrade, as the version label of the
+        // pod templates changes, except for the io-engine pods which are restarted by the
+        // upgrade job one node at a time.
+        let mut templates = Vec::new();
+        templates.extend(
+            list::<Deployment>(&client, namespace, &release_selector)
+                .await?
+                .into_iter()
+                .filter_map(|d| Some((workload_name(&d), d.spec?.template))),
+        );
+        templates.extend(
+            list::<StatefulSet>(&client, namespace, &release_selector)
+                .await?
+                .into_iter()
+                .filter_map(|s| Some((workload_name(&s), s.spec?.template))),
+        );
+        templates.extend(
+            list::<DaemonSet>(&client, namespace, &release_selector)
+                .await?
+                .into_iter()
+                .filter_map(|d| Some((workload_name(&d), d.spec?.template))),
+        );
+
+        let overrides = image_overrides(&args.set_image);
+        let images = templates
+            .iter()
+            .flat_map(|(_, template)| template_images(template))
+            .map(|image| {
+                let target = target_image(&image, &from_version, &to_version, &overrides);
+                (image, target)
+            })
+            .collect();
+
+        let io_engine_app = IO_ENGINE_POD_LABEL.split_once('=').map(|(_, app)| app);
+        let control_plane = templates
+            .iter()
+            .filter(|(_, template)| template_app(template) != io_engine_app)
+            .map(|(name, _)| name.clone())
+            .collect();
+
+        let mut io_engine_pods = list::<Pod>(&client, namespace, IO_ENGINE_POD_LABEL)
+            .await?
+            .into_iter()
+            .map(|pod| {
+                let node = pod
+                    .spec
+                    .as_ref()
+                    .and_then(|spec| spec.node_name.clone())
+                    .unwrap_or_default();
+                (node, pod.name_any())
+            })
+            .collect::<Vec<_>>();
+        io_engine_pods.sort_by(|a, b| a.1.cmp(&b.1));
+        let (held, data_plane): (Vec<_>, Vec<_>) = io_engine_pods
+            .into_iter()
+            .partition(|(node, _)| args.held_nodes.contains(node));
+
+        let config = deployed_release_config(&client, namespace, &release_name).await?;
+
+        Ok(Self {
+            values: value_changes(&config, args)?,
+            release_name,
+            from_version,
+            to_version,
+            images,
+            control_plane,
+            data_plane: data_plane
+                .into_iter()
+                .map(|(node, pod)| format!("{node}/{pod}"))
+                .collect(),
+            held: held
+                .into_iter()
+                .map(|(node, pod)| format!("{node}/{pod}"))
+                .collect(),
+            skip_data_plane_restart: args.skip_data_plane_restart,
+        })
+    }
+
+    /// Print the plan, in the order of the upgrade.
+    pub(crate) fn print(&self) {
+        console_logger::info(
+            &format!(
+                "\nThe helm release {} will be upgraded from version {} to version {}.",
+                self.release_name, self.from_version, self.to_version
+            ),
+            "",
+        );
+
+        let images = self
+            .images
+            .iter()
+            .map(|(image, target)| match image == target {
+                true => format!("{image} (unchanged)"),
+                false => format!("{image} -> {target}"),
+            })
+            .collect::<Vec<_>>();
+        console_logger::info(IMAGES_PLAN, &images.join("\n"));
+
+        let values = match self.values.is_empty() {
+            true => "No changes".to_string(),
+            false => self
+                .values
+                .iter()
+                .map(|change| format!("{}: {} -> {}", change.key, change.from, change.to))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        };
+        console_logger::info(VALUES_PLAN, &values);
+
+        console_logger::info(CONTROL_PLANE_PLAN, &self.control_plane.join("\n"));
+
+        if self.skip_data_plane_restart {
+            console_logger::info(DATA_PLANE_PLAN_SKIP_RESTART, &self.data_plane.join("\n"));
+        } else {
+            console_logger::info(DATA_PLANE_PLAN, &self.data_plane.join("\n"));
+            if !self.held.is_empty() {
+                console_logger::info(HELD_BACK_PODS_PLAN, &self.held.join("\n"));
+            }
+        }
+
+        console_logger::info(
+            upgrade_dry_run_summary(UPGRADE_DRY_RUN_SUMMARY).as_str(),
+            "",
+        );
+    }
+}
+
+/// List the objects of the namespace with the given labels.
+async fn list<K>(client: &Client, namespace: &str, labels: &str) -> error::Result<Vec<K>>
+where
+    K: kube::Resource<Scope = k8s_openapi::NamespaceResourceScope>
+        + Resource
+        + Clone
+        + DeserializeOwned
+        + std::fmt::Debug,
+    <K as kube::Resource>::DynamicType: Default,
+{
+    let api: Api<K> = Api::namespaced(client.clone(), namespace);
+    Ok(api
+        .list(&ListParams::default().labels(labels))
+        .await
+        .context(error::ListResourcesWithLabel {
+            kind: K::KIND.to_string(),
+            label: labels.to_string(),
+            namespace: namespace.to_string(),
+        })?
+        .items)
+}
+
+/// Name of the workload, as <kind>/<name>.
+fn workload_name<K: Resource + kube::Resource>(workload: &K) -> String {
+    format!("{}/{}", K::KIND, workload.name_any())
+}
+
+/// The app label of the pod template, if any.
+fn template_app(template: &PodTemplateSpec) -> Option<&str> {
+    template
+        .metadata
+        .as_ref()?
+        .labels
+        .as_ref()?
+        .get("app")
+        .map(String::as_str)
+}
+
+/// The images of the init and the regular containers of the pod template.
+fn template_images(template: &PodTemplateSpec) -> Vec<String> {
+    let Some(spec) = &template.spec else {
+        return Vec::new();
+    };
+    spec.init_containers
+        .iter()
+        .flatten()
+        .chain(spec.containers.iter())
+        .filter_map(|container| container.image.clone())
+        .collect()
+}
+
+/// Parse the image overrides, eg: agent-core=<image>, sorted from the longest component name so
+/// that eg: metrics-exporter-io-engine is matched before io-engine.
+fn image_overrides(set_image: &[String]) -> Vec<(String, String)> {
+    let mut overrides = set_image
+        .iter()
+        .flat_map(|value| value.split(','))
+        .filter_map(|value| value.split_once('='))
+        .map(|(component, image)| (component.to_string(), image.to_string()))
+        .collect::<Vec<_>>();
+    overrides.sort_by(|a, b| b.0.len().cmp(&a.0.len()));
+    overrides
+}
+
+/// The image the given one is upgraded to: the override of its component if any, otherwise the
+/// same image tagged with the target version if it is tagged with the source version. The images
+/// of the dependencies are set by the target chart, and shown as unchanged.
+fn target_image(
+    image: &str,
+    from_version: &str,
+    to_version: &str,
+    overrides: &[(String, String)],
+) -> String {
+    let Some((repository, tag)) = image.rsplit_once(':') else {
+        return image.to_string();
+    };
+    if let Some((_, image)) = overrides
+        .iter()
+        .find(|(component, _)| repository.ends_with(&format!("-{component}")))
+    {
+        return image.clone();
+    }
+    match tag.trim_start_matches('v') == from_version.trim_start_matches('v') {
+        true => format!("{repository}:{to_version}"),
+        false => image.to_string(),
+    }
+}
+
+/// Get the user supplied values of the deployed helm release, from the secret helm stores it in.
+async fn deployed_release_config(
+    client: &Client,
+    namespace: &str,
+    release_name: &str,
+) -> error::Result<Value> {
+    let selector = format!("{HELM_DEPLOYED_RELEASE_SELECTOR},name={release_name}");
+    let secrets = list::<Secret>(client, namespace, &selector).await?;
+    let Some(secret) = secrets.first() else {
+        return Ok(Value::Null);
+    };
+    let name = secret.name_any();
+    decode_release(secret)
+        .map(|release| release.config)
+        .context(error::HelmReleaseDecode { name })
+}
+
+/// Decode the release of the helm secret, which is a gzipped JSON document, base64 encoded once
+/// more on top of the encoding of the secret data.
+fn decode_release(secret: &Secret) -> std::io::Result<HelmRelease> {
+    let encoded = secret
+        .data
+        .as_ref()
+        .and_then(|data| data.get(HELM_RELEASE_KEY))
+        .ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::NotFound, "no release in the secret")
+        })?;
+    let gzipped = base64::engine::general_purpose::STANDARD
+        .decode(&encoded.0)
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+    let mut json = Vec::new();
+    GzDecoder::new(gzipped.as_slice()).read_to_end(&mut json)?;
+    Ok(serde_json::from_slice(&json)?)
+}
+
+/// The changes to the user supplied values of the release made by the upgrade arguments, the
+/// values files first, and then the set values, which take precedence. The image overrides of a
+/// previous upgrade are dropped, unless they are given again.
+fn value_changes(config: &Value, args: &UpgradeArgs) -> error::Result<Vec<ValueChange>> {
+    let current = |key: &str| {
+        key.split('.')
+            .try_fold(config, |value, segment| value.get(segment))
+            .map(|value| match value {
+                Value::String(value) => value.clone(),
+                value => value.to_string(),
+            })
+            .unwrap_or_else(|| UNSET_VALUE.to_string())
+    };
+
+    let mut values_files = Vec::new();
+    for filepath in args.values.iter().flat_map(|values| values.split(',')) {
+        let content = fs::read_to_string(filepath).context(error::ReadFromFile { filepath })?;
+        let values: Value =
+            serde_yaml::from_str(&content).context(error::YamlParseFromFile { filepath })?;
+        flatten_values(String::new(), &values, &mut values_files);
+    }
+
+    let mut changes: Vec<ValueChange> = Vec::new();
+    let set_values = args
+        .set
+        .iter()
+        .flat_map(|value| value.split(','))
+        .filter_map(|value| value.split_once('='))
+        .map(|(key, value)| (key.to_string(), value.to_string()));
+    let set_files = args
+        .set_file
+        .iter()
+        .flat_map(|value| value.split(','))
+        .filter_map(|value| value.split_once('='))
+        .map(|(key, path)| (key.to_string(), format!("<content of {path}>")));
+    let overrides = image_overrides(&args.set_image)
+        .into_iter()
+        .map(|(component, image)| (format!("image.overrides.{component}"), image));
+    for (key, to) in values_files
+        .into_iter()
+        .chain(set_values)
+        .chain(set_files)
+        .chain(overrides)
+    {
+        // A later value of the same key takes precedence over the earlier ones.
+        changes.retain(|change| change.key != key);
+        let from = current(&key);
+        if from != to {
+            changes.push(ValueChange { key, from, to });
+        }
+    }
+
+    if let Some(Value::Object(previous)) = config.pointer("/image/overrides") {
+        for (component, image) in previous {
+            let key = format!("image.overrides.{component}");
+            if changes.iter().all(|change| change.key != key) {
+                changes.push(ValueChange {
+                    key,
+                    from: image.as_str().unwrap_or_default().to_string(),
+                    to: UNSET_VALUE.to_string(),
+                });
+            }
+        }
+    }
+    Ok(changes)
+}
+
+/// Flatten the values into their keys, in the dotted notation of the set values, and their values.
+fn flatten_values(prefix: String, values: &Value, flat: &mut Vec<(String, String)>) {
+    match values {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, value) in map {
+                let key = match prefix.is_empty() {
+                    true => key.clone(),
+                    false => format!("{prefix}.{key}"),
+                };
+                flatten_values(key, value, flat);
+            }
+        }
+        Value::String(value) => flat.push((prefix, value.clone())),
+        value => flat.push((prefix, value.to_string())),
+    }
+}
@@ -1,15 +1,22 @@
 use crate::{
     plugin::{
-        constants::{get_image_version_tag, SINGLE_REPLICA_VOLUME, UPGRADE_TO_DEVELOP_BRANCH},
+        constants::{
+            get_image_version_tag, CONTROL_PLANE_POD_LABELS, SINGLE_REPLICA_VOLUME,
+            UPGRADE_TO_DEVELOP_BRANCH,
+        },
         error,
+        node_requirements::NodeRequirements,
         upgrade::{get_pvc_from_uuid, get_source_version},
+        upgrade_path::{intermediate_versions, target_version},
         user_prompt,
     },
     upgrade::UpgradeArgs,
 };
+use k8s_openapi::api::core::v1::Pod;
+use kube::{api::ListParams, Api, Client};
 use openapi::{
     clients::tower::{self, Configuration},
-    models::CordonDrainState,
+    models::{CordonDrainState, Node, Pool, PoolStatus, Volume},
 };
 use semver::Version;
 use serde::Deserialize;
@@ -18,13 +25,14 @@ use snafu::ResultExt;
 use std::{collections::HashSet, ops::Deref, path::PathBuf};
 use utils::version_info;
 
-/// Validation to be done before applying upgrade.
+/// Validation to be done before applying upgrade. Returns the nodes whose io-engine pods must be
+/// held back, as they don't meet the node-level requirements of the target version.
 pub async fn preflight_check(
     namespace: &str,
     kube_config_path: Option<PathBuf>,
     timeout: humantime::Duration,
     resources: &UpgradeArgs,
-) -> error::Result<()> {
+) -> error::Result<Vec<String>> {
     console_logger::info(user_prompt::UPGRADE_WARNING, "");
     // Initialise the REST client.
     let config = kube_proxy::ConfigBuilder::default_api_rest()
@@ -37,7 +45,13 @@ pub async fn preflight_check(
     let rest_client = RestClient::new_with_config(config);
 
     if !resources.skip_upgrade_path_validation_for_unsupported_version {
-        upgrade_path_validation(namespace, resources.allow_unstable).await?;
+        upgrade_path_validation(
+            namespace,
+            resources.allow_unstable,
+            resources.allow_downgrade,
+            resources.multi_hop,
+        )
+        .await?;
     }
 
     if !resources.skip_replica_rebuild {
@@ -51,19 +65,64 @@ pub async fn preflight_check(
     if !resources.skip_single_replica_volume_validation {
         single_volume_replica_validation(&rest_client).await?;
     }
-    Ok(())
+
+    if !resources.skip_offline_pools_validation {
+        offline_pools_validation(&rest_client).await?;
+    }
+
+    if !resources.skip_control_plane_validation {
+        control_plane_validation(namespace).await?;
+    }
+
+    if resources.skip_node_requirements_validation || resources.skip_data_plane_restart {
+        return Ok(Vec::new());
+    }
+    node_requirements_validation(namespace, resources.hold_unprepared_nodes).await
+}
+
+/// Prompt to user the remediation steps of the io-engine nodes which don't meet the node-level
+/// requirements of the target version, and error out unless these nodes are to be held back, in
+/// which case they are returned.
+pub(crate) async fn node_requirements_validation(
+    namespace: &str,
+    hold_unprepared_nodes: bool,
+) -> error::Result<Vec<String>> {
+    let unprepared_nodes = NodeRequirements::for_target_version()?
+        .unprepared_nodes(namespace)
+        .await?;
+    if unprepared_nodes.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let data = unprepared_nodes
+        .iter()
+        .map(|(node, steps)| format!("{node}:\n  - {}", steps.join("\n  - ")))
+        .collect::<Vec<_>>()
+        .join("\n");
+    console_logger::error(user_prompt::NODE_REQUIREMENTS_WARNING, &data);
+    if !hold_unprepared_nodes {
+        return error::NodeRequirementsNotMet.fail();
+    }
+
+    let held_nodes = unprepared_nodes.into_keys().collect::<Vec<_>>();
+    console_logger::info(user_prompt::HELD_NODES_LIST, &held_nodes.join("\n"));
+    Ok(held_nodes)
 }
 
 /// Prompt to user and error out if some nodes are already in cordoned state.
 pub(crate) async fn already_cordoned_nodes_validation(client: &RestClient) -> error::Result<()> {
-    let mut cordoned_nodes_list = Vec::new();
     let nodes = client
         .nodes_api()
         .get_nodes(None)
         .await
         .context(error::ListStorageNodes)?;
-    let nodelist = nodes.into_body();
-    for node in nodelist {
+    cordoned_nodes_validation(nodes.into_body())
+}
+
+/// Prompt to user and error out if any of the given nodes is in cordoned state.
+pub(crate) fn cordoned_nodes_validation(nodes: Vec<Node>) -> error::Result<()> {
+    let mut cordoned_nodes_list = Vec::new();
+    for node in nodes {
         let node_spec = node.spec.ok_or(
             error::NodeSpecNotPresent {
                 node: node.id.to_string(),
@@ -88,6 +147,112 @@ pub(crate) async fn already_cordoned_nodes_validation(client: &RestClient) -> er
     Ok(())
 }
 
+/// Prompt to user and error out if some pools are not online.
+pub(crate) async fn offline_pools_validation(client: &RestClient) -> error::Result<()> {
+    let pools = client
+        .pools_api()
+        .get_pools()
+        .await
+        .context(error::ListPools)?;
+    pools_online_validation(&pools.into_body())
+}
+
+/// Prompt to user and error out if any of the given pools is not online.
+pub(crate) fn pools_online_validation(pools: &[Pool]) -> error::Result<()> {
+    let offline_pools = pools
+        .iter()
+        .filter_map(|pool| match &pool.state {
+            Some(state) if matches!(state.status, PoolStatus::Online) => None,
+            Some(state) => Some(format!(
+                "{} on node {}: {:?}",
+                pool.id, state.node, state.status
+            )),
+            None => Some(format!(
+                "{} on node {}: Unknown",
+                pool.id,
+                pool.spec
+                    .as_ref()
+                    .map_or("<unknown>", |spec| spec.node.as_str())
+            )),
+        })
+        .collect::<Vec<_>>();
+    if !offline_pools.is_empty() {
+        console_logger::error(
+            user_prompt::OFFLINE_POOLS_WARNING,
+            &offline_pools.join("\n"),
+        );
+        return error::PoolsNotOnline.fail();
+    }
+    Ok(())
+}
+
+/// Prompt to user and error out if the control plane pods are not all ready.
+pub(crate) async fn control_plane_validation(namespace: &str) -> error::Result<()> {
+    let client = Client::try_default().await.context(error::K8sClient)?;
+    let mut pods = Vec::new();
+    for label in CONTROL_PLANE_POD_LABELS {
+        let list = Api::<Pod>::namespaced(client.clone(), namespace)
+            .list(&ListParams::default().labels(label))
+            .await
+            .context(error::ListPodsWithLabel {
+                label: label.to_string(),
+                namespace: namespace.to_string(),
+            })?;
+        pods.extend(list.items);
+    }
+    control_plane_health_validation(&pods)
+}
+
+/// Prompt to user and error out if, among the given pods, the ones of a control plane component
+/// are missing or not ready.
+pub(crate) fn control_plane_health_validation(pods: &[Pod]) -> error::Result<()> {
+    let mut issues = Vec::new();
+    for label in CONTROL_PLANE_POD_LABELS {
+        let Some((key, value)) = label.split_once('=') else {
+            continue;
+        };
+        let component_pods = pods
+            .iter()
+            .filter(|pod| {
+                pod.metadata
+                    .labels
+                    .as_ref()
+                    .and_then(|labels| labels.get(key))
+                    .map_or(false, |label| label == value)
+            })
+            .collect::<Vec<_>>();
+        if component_pods.is_empty() {
+            issues.push(format!("No pod with label {label}"));
+        }
+        for pod in component_pods.into_iter().filter(|pod| !is_pod_ready(pod)) {
+            issues.push(format!(
+                "{}: not ready",
+                pod.metadata.name.as_deref().unwrap_or_default()
+            ));
+        }
+    }
+    if !issues.is_empty() {
+        console_logger::error(
+            user_prompt::CONTROL_PLANE_UNHEALTHY_WARNING,
+            &issues.join("\n"),
+        );
+        return error::ControlPlaneNotHealthy.fail();
+    }
+    Ok(())
+}
+
+/// Whether the Ready condition of the pod is true.
+fn is_pod_ready(pod: &Pod) -> bool {
+    pod.status
+        .as_ref()
+        .and_then(|status| status.conditions.as_ref())
+        .map_or(false, |conditions| {
+            conditions
+                .iter()
+                .any(|condition| condition.type_ == "Ready" && condition.status == "True")
+        })
+}
+
 /// Prompt to user and error out if the cluster has single replica volume.
 pub(crate) async fn single_volume_replica_validation(client: &RestClient) -> error::Result<()> {
     // let mut single_replica_volumes = Vec::new();
@@ -105,13 +270,7 @@ pub(crate) async fn single_volume_replica_validation(client: &RestClient) -> err
             .context(error::ListVolumes)?;
 
         let v = vols.into_body();
-        let single_rep_vol_ids: Vec<String> = v
-            .entries
-            .into_iter()
-            .filter(|volume| volume.spec.num_replicas == SINGLE_REPLICA_VOLUME)
-            .map(|volume| volume.spec.uuid.to_string())
-            .collect();
-        volumes.extend(single_rep_vol_ids);
+        volumes.extend(single_replica_volumes(&v.entries));
         starting_token = v.next_token;
     }
 
@@ -126,6 +285,17 @@ pub(crate) async fn single_volume_replica_validation(client: &RestClient) -> err
     Ok(())
 }
 
+/// Returns the uuids of the given volumes which have a single replica and are published, as
+/// these are the ones whose io is interrupted while the io-engine of their replica restarts.
+pub(crate) fn single_replica_volumes(volumes: &[Volume]) -> Vec<String> {
+    volumes
+        .iter()
+        .filter(|volume| volume.spec.num_replicas == SINGLE_REPLICA_VOLUME)
+        .filter(|volume| volume.state.target.is_some())
+        .map(|volume| volume.spec.uuid.to_string())
+        .collect()
+}
+
 /// Prompt to user and error out if any rebuild in progress.
 pub(crate) async fn rebuild_in_progress_validation(client: &RestClient) -> error::Result<()> {
     if is_rebuild_in_progress(client).await? {
@@ -150,21 +320,24 @@ pub(crate) async fn is_rebuild_in_progress(client: &RestClient) -> error::Result
             .context(error::ListVolumes)?;
         let volumes = vols.into_body();
         starting_token = volumes.next_token;
-        for volume in volumes.entries {
-            if let Some(target) = &volume.state.target {
-                if target
-                    .children
-                    .iter()
-                    .any(|child| child.rebuild_progress.is_some())
-                {
-                    return Ok(true);
-                }
-            }
+        if volumes.entries.iter().any(is_volume_rebuilding) {
+            return Ok(true);
         }
     }
     Ok(false)
 }
 
+/// Check if any child of the volume's target is being rebuilt.
+pub(crate) fn is_volume_rebuilding(volume: &Volume) -> bool {
+    match &volume.state.target {
+        Some(target) => target
+            .children
+            .iter()
+            .any(|child| child.rebuild_progress.is_some()),
+        None => false,
+    }
+}
+
 /// Struct to deserialize the unsupported version yaml.
 #[derive(Deserialize)]
 struct UnsupportedVersions {
@@ -189,12 +362,24 @@ impl TryFrom<&[u8]> for UnsupportedVersions {
 pub(crate) async fn upgrade_path_validation(
     namespace: &str,
     allow_unstable: bool,
+    allow_downgrade: bool,
+    multi_hop: bool,
+) -> error::Result<()> {
+    let source_version = get_source_version(namespace).await?;
+    source_version_validation(source_version, allow_unstable, allow_downgrade, multi_hop)
+}
+
+/// Validate the upgrade path from the given source version to the version of this plugin.
+pub(crate) fn source_version_validation(
+    source_version: String,
+    allow_unstable: bool,
+    allow_downgrade: bool,
+    multi_hop: bool,
 ) -> error::Result<()> {
     let unsupported_version_buf =
         &std::include_bytes!("../../config/unsupported_versions.yaml")[..];
     let unsupported_versions = UnsupportedVersions::try_from(unsupported_version_buf)
         .context(error::YamlParseBufferForUnsupportedVersion)?;
-    let source_version = get_source_version(namespace).await?;
 
     let source = Version::parse(source_version.as_str()).context(error::SemverParse {
         version_string: source_version.clone(),
@@ -219,6 +404,28 @@ pub(crate) async fn upgrade_path_validation(
         return error::InvalidUpgradePath.fail();
     }
 
+    // The installations older than a version the upgrade can't skip need to be upgraded through
+    // the intermediate versions, which is only done with --multi-hop.
+    if let Some(target) = target_version() {
+        let versions = intermediate_versions(&source, &target)?;
+        if !versions.is_empty() {
+            let versions = versions.iter().map(ToString::to_string).collect::<Vec<_>>();
+            if !multi_hop {
+                console_logger::error(
+                    user_prompt::MULTI_HOP_UPGRADE_REQUIRED,
+                    &versions.join("\n"),
+                );
+                return error::MultiHopUpgradeRequired {
+                    source_version: source.to_string(),
+                    target_version: target.to_string(),
+                    intermediate_versions: versions.join(", "),
+                }
+                .fail();
+            }
+            console_logger::info(user_prompt::MULTI_HOP_UPGRADE_PATH, &versions.join("\n"));
+        }
+    }
+
     // Self version
     let self_version_info = version_info!();
     let mut self_version: Option<Version> = None;
@@ -244,11 +451,15 @@ pub(crate) async fn upgrade_path_validation(
         }
     }
 
-    // Upgrade not allowed to lower semver versions check.
+    // Upgrade not allowed to lower semver versions check, unless the downgrade is allowed, in
+    // which case the upgrade job validates the data formats before the downgrade.
     if let Some(ref version) = self_version {
         if version.lt(&source) {
-            console_logger::error("", user_prompt::HIGHER_TO_LOWER_SEMVER_UPGRADE);
-            return error::InvalidUpgradePath.fail();
+            if !allow_downgrade {
+                console_logger::error("", user_prompt::HIGHER_TO_LOWER_SEMVER_UPGRADE);
+                return error::InvalidUpgradePath.fail();
+            }
+            console_logger::info(user_prompt::DOWNGRADE_WARNING, "");
         }
     }
 
@@ -0,0 +1,332 @@
+use crate::plugin::{
+    constants::{
+        AGENT_CORE_POD_LABEL, API_REST_LABEL_SELECTOR, API_REST_POD_LABEL,
+        HELM_RELEASE_VERSION_LABEL, IO_ENGINE_POD_LABEL,
+    },
+    error,
+    preflight_validations::{
+        control_plane_health_validation, cordoned_nodes_validation, is_volume_rebuilding,
+        pools_online_validation, single_replica_volumes, source_version_validation,
+    },
+    upgrade::{PauseUpgradeArgs, RollbackUpgradeArgs, UpgradeArgs},
+    user_prompt::{
+        upgrade_dry_run_summary, CONTROL_PLANE_PODS_LIST, DATA_PLANE_PODS_LIST,
+        DATA_PLANE_PODS_LIST_SKIP_RESTART, REBUILD_WARNING, SINGLE_REPLICA_VOLUME_WARNING,
+        UPGRADE_DRY_RUN_SUMMARY, UPGRADE_SIMULATION_NOTICE, UPGRADE_WARNING,
+    },
+};
+use flate2::read::GzDecoder;
+use k8s_openapi::api::{apps::v1::Deployment, core::v1::Pod};
+use openapi::models::{Node, Pool, Volume};
+use serde::{de::DeserializeOwned, Deserialize};
+use snafu::ResultExt;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Directory of the kubernetes resources in the support bundle.
+const BUNDLE_K8S_RESOURCES_DIR: &str = "k8s_resources";
+/// Directory of the topology information in the support bundle.
+const BUNDLE_TOPOLOGY_DIR: &str = "topology";
+/// Suffix of the resource topology files in the support bundle.
+const TOPOLOGY_FILE_SUFFIX: &str = "-topology.json";
+
+/// Actions to be performed with the upgrade.
+#[derive(clap::Subcommand, Debug, Clone)]
+pub enum UpgradeCommand {
+    /// Developer mode which runs the upgrade validations and planning offline, against the
+    /// cluster state recorded in a support bundle.
+    #[clap(hide = true)]
+    Simulate(SimulateUpgradeArgs),
+
+    /// Roll the release back to the chart version and the values it had before its last upgrade,
+    /// restarting the data plane pods one node at a time.
+    Rollback(RollbackUpgradeArgs),
+
+    /// Pause the data plane upgrade of the running upgrade job before its next node, or group of
+    /// nodes.
+    Pause(PauseUpgradeArgs),
+
+    /// Resume the paused data plane upgrade of the running upgrade job.
+    Resume(PauseUpgradeArgs),
+}
+
+/// Arguments to be passed for the upgrade simulation.
+#[derive(clap::Args, Debug, Clone)]
+pub struct SimulateUpgradeArgs {
+    /// Path to the support bundle, either the archive or its extracted directory.
+    #[clap(long)]
+    pub from_bundle: PathBuf,
+}
+
+impl SimulateUpgradeArgs {
+    /// Run the preflight validations and the upgrade planning against the recorded cluster
+    /// state, without any access to the cluster. This reproduces the outcome of
+    /// `upgrade --dry-run` at the time the support bundle was collected.
+    pub async fn simulate(&self, upgrade_args: &UpgradeArgs) -> error::Result<()> {
+        let state = RecordedClusterState::from_bundle(&self.from_bundle)?;
+        console_logger::info(
+            UPGRADE_SIMULATION_NOTICE,
+            &self.from_bundle.display().to_string(),
+        );
+        console_logger::info(UPGRADE_WARNING, "");
+
+        if !upgrade_args.skip_upgrade_path_validation_for_unsupported_version {
+            source_version_validation(
+                state.source_version.clone(),
+                upgrade_args.allow_unstable,
+                upgrade_args.allow_downgrade,
+                upgrade_args.multi_hop,
+            )?;
+        }
+
+        if !upgrade_args.skip_replica_rebuild && state.volumes.iter().any(is_volume_rebuilding) {
+            console_logger::error(REBUILD_WARNING, "");
+            return error::VolumeRebuildInProgress.fail();
+        }
+
+        if !upgrade_args.skip_cordoned_node_validation {
+            cordoned_nodes_validation(state.nodes.clone())?;
+        }
+
+        if !upgrade_args.skip_single_replica_volume_validation {
+            let volumes = single_replica_volumes(&state.volumes);
+            if !volumes.is_empty() {
+                // The PVCs are not recorded in the bundle, so list the volumes instead.
+                console_logger::error(SINGLE_REPLICA_VOLUME_WARNING, &volumes.join("\n"));
+                return error::SingleReplicaVolumeErr.fail();
+            }
+        }
+
+        if !upgrade_args.skip_offline_pools_validation {
+            pools_online_validation(&state.pools)?;
+        }
+
+        // The pods are only recorded by the bundles which include the kubernetes resources.
+        if !upgrade_args.skip_control_plane_validation && !state.pods.is_empty() {
+            control_plane_health_validation(&state.pods)?;
+        }
+
+        let mut pods_names = state.pods_with_label(AGENT_CORE_POD_LABEL);
+        pods_names.extend(state.pods_with_label(API_REST_POD_LABEL));
+        console_logger::info(CONTROL_PLANE_PODS_LIST, &pods_names.join("\n"));
+
+        let io_engine_pods_names = state.pods_with_label(IO_ENGINE_POD_LABEL);
+        if upgrade_args.skip_data_plane_restart {
+            console_logger::info(
+                DATA_PLANE_PODS_LIST_SKIP_RESTART,
+                &io_engine_pods_names.join("\n"),
+            );
+        } else {
+            console_logger::info(DATA_PLANE_PODS_LIST, &io_engine_pods_names.join("\n"));
+        }
+        console_logger::info(
+            upgrade_dry_run_summary(UPGRADE_DRY_RUN_SUMMARY).as_str(),
+            "",
+        );
+        Ok(())
+    }
+}
+
+/// Volume topology as recorded in the support bundle.
+#[derive(Deserialize)]
+struct VolumeTopology {
+    volume: Volume,
+}
+
+/// Node topology as recorded in the support bundle.
+#[derive(Deserialize)]
+struct NodeTopology {
+    node: Node,
+}
+
+/// Pool topology as recorded in the support bundle.
+#[derive(Deserialize)]
+struct PoolTopology {
+    pool: Pool,
+}
+
+/// Cluster state recorded in a support bundle.
+struct RecordedClusterState {
+    source_version: String,
+    nodes: Vec<Node>,
+    pools: Vec<Pool>,
+    volumes: Vec<Volume>,
+    pods: Vec<Pod>,
+}
+
+impl RecordedClusterState {
+    /// Load the recorded cluster state from the support bundle archive or its extracted
+    /// directory.
+    fn from_bundle(path: &Path) -> error::Result<Self> {
+        if path.is_dir() {
+            return Self::from_bundle_dir(path, path);
+        }
+        let dir = tempfile::tempdir().context(error::ExtractSupportBundle { filepath: path })?;
+        let file = fs::File::open(path).context(error::OpeningFile { filepath: path })?;
+        extract_bundle(file, path, dir.path())
+            .context(error::ExtractSupportBundle { filepath: path })?;
+        Self::from_bundle_dir(dir.path(), path)
+    }
+
+    /// Load the recorded cluster state from the extracted support bundle.
+    fn from_bundle_dir(dir: &Path, bundle: &Path) -> error::Result<Self> {
+        let root = bundle_root(dir)?.ok_or(
+            error::InvalidSupportBundle {
+                filepath: bundle.to_path_buf(),
+            }
+            .build(),
+        )?;
+
+        let source_version = recorded_source_version(&root)?.ok_or(
+            error::InvalidSupportBundle {
+                filepath: bundle.to_path_buf(),
+            }
+            .build(),
+        )?;
+
+        let topology_dir = root.join(BUNDLE_TOPOLOGY_DIR);
+        let nodes =
+            read_json_files::<NodeTopology>(&topology_dir.join("node"), TOPOLOGY_FILE_SUFFIX)?
+                .into_iter()
+                .map(|topology| topology.node)
+                .collect();
+        let pools =
+            read_json_files::<PoolTopology>(&topology_dir.join("pool"), TOPOLOGY_FILE_SUFFIX)?
+                .into_iter()
+                .map(|topology| topology.pool)
+                .collect();
+        let volumes =
+            read_json_files::<VolumeTopology>(&topology_dir.join("volume"), TOPOLOGY_FILE_SUFFIX)?
+                .into_iter()
+                .map(|topology| topology.volume)
+                .collect();
+
+        let pods_file = root.join(BUNDLE_K8S_RESOURCES_DIR).join("pods.yaml");
+        let pods = match pods_file.exists() {
+            true => read_yaml_file(&pods_file)?,
+            false => Vec::new(),
+        };
+
+        Ok(Self {
+            source_version,
+            nodes,
+            pools,
+            volumes,
+            pods,
+        })
+    }
+
+    /// Names of the recorded pods which have the given `key=value` label.
+    fn pods_with_label(&self, label: &str) -> Vec<String> {
+        let Some((key, value)) = label.split_once('=') else {
+            return Vec::new();
+        };
+        self.pods
+            .iter()
+            .filter(|pod| {
+                pod.metadata
+                    .labels
+                    .as_ref()
+                    .and_then(|labels| labels.get(key))
+                    .map_or(false, |label| label == value)
+            })
+            .filter_map(|pod| pod.metadata.name.clone())
+            .collect()
+    }
+}
+
+/// Extract the support bundle archive into the directory, according to the format given by its
+/// extension: a zstd or gzip compressed tar file, or a zip file.
+fn extract_bundle(file: fs::File, path: &Path, dir: &Path) -> std::io::Result<()> {
+    let name = path.to_string_lossy();
+    if name.ends_with(".zip") {
+        zip::ZipArchive::new(file)?.extract(dir)?;
+        Ok(())
+    } else if name.ends_with(".tar.zst") {
+        tar::Archive::new(zstd::Decoder::new(file)?).unpack(dir)
+    } else {
+        tar::Archive::new(GzDecoder::new(file)).unpack(dir)
+    }
+}
+
+/// Find the root directory of the extracted support bundle, which is either the given directory
+/// or one of its subdirectories, as the archive may wrap the bundle in a directory.
+fn bundle_root(dir: &Path) -> error::Result<Option<PathBuf>> {
+    let is_root = |dir: &Path| {
+        dir.join(BUNDLE_K8S_RESOURCES_DIR).is_dir() || dir.join(BUNDLE_TOPOLOGY_DIR).is_dir()
+    };
+    if is_root(dir) {
+        return Ok(Some(dir.to_path_buf()));
+    }
+    let entries = fs::read_dir(dir).context(error::ReadFromFile { filepath: dir })?;
+    for entry in entries {
+        let path = entry.context(error::ReadFromFile { filepath: dir })?.path();
+        if path.is_dir() && is_root(&path) {
+            return Ok(Some(path));
+        }
+    }
+    Ok(None)
+}
+
+/// Get the installed version from the labels of the recorded REST deployment.
+fn recorded_source_version(root: &Path) -> error::Result<Option<String>> {
+    let configurations_dir = root.join(BUNDLE_K8S_RESOURCES_DIR).join("configurations");
+    let Some((app_key, app_value)) = API_REST_LABEL_SELECTOR.split_once('=') else {
+        return Ok(None);
+    };
+    for path in files_with_suffix(&configurations_dir, ".yaml")? {
+        // The configurations also contain other kinds of objects, which fail to parse.
+        let Ok(deployment) = read_yaml_file::<Deployment>(&path) else {
+            continue;
+        };
+        let Some(labels) = deployment.metadata.labels else {
+            continue;
+        };
+        if labels.get(app_key).map(String::as_str) == Some(app_value) {
+            if let Some(version) = labels.get(HELM_RELEASE_VERSION_LABEL) {
+                return Ok(Some(version.clone()));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Parse all the json files with the given suffix in the directory, if it exists.
+fn read_json_files<T: DeserializeOwned>(dir: &Path, suffix: &str) -> error::Result<Vec<T>> {
+    files_with_suffix(dir, suffix)?
+        .into_iter()
+        .map(|path| {
+            let file = fs::File::open(&path).context(error::OpeningFile {
+                filepath: path.clone(),
+            })?;
+            serde_json::from_reader(file).context(error::JsonParseFromFile { filepath: path })
+        })
+        .collect()
+}
+
+/// Parse the given yaml file.
+fn read_yaml_file<T: DeserializeOwned>(path: &Path) -> error::Result<T> {
+    let file = fs::File::open(path).context(error::OpeningFile { filepath: path })?;
+    serde_yaml::from_reader(file).context(error::YamlParseFromFile { filepath: path })
+}
+
+/// List the files whose name ends with the given suffix in the directory, if it exists.
+fn files_with_suffix(dir: &Path, suffix: &str) -> error::Result<Vec<PathBuf>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir).context(error::ReadFromFile { filepath: dir })? {
+        let path = entry.context(error::ReadFromFile { filepath: dir })?.path();
+        let matches = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map_or(false, |name| name.ends_with(suffix));
+        if matches {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
@@ -1,40 +1,46 @@
 use crate::plugin::{
     constants::{
         get_image_version_tag, upgrade_event_selector, upgrade_image_concat, upgrade_name_concat,
-        AGENT_CORE_POD_LABEL, API_REST_LABEL_SELECTOR, API_REST_POD_LABEL, DEFAULT_IMAGE_REGISTRY,
-        DEFAULT_RELEASE_NAME, HELM_RELEASE_NAME_LABEL, HELM_RELEASE_VERSION_LABEL,
-        IO_ENGINE_POD_LABEL, MAX_RETRY_ATTEMPTS, UPGRADE_CONFIG_MAP_MOUNT_PATH,
-        UPGRADE_CONFIG_MAP_NAME_SUFFIX, UPGRADE_EVENT_REASON,
+        API_REST_LABEL_SELECTOR, DEFAULT_IMAGE_REGISTRY, DEFAULT_RELEASE_NAME,
+        HELM_RELEASE_NAME_LABEL, HELM_RELEASE_VERSION_LABEL, MAX_RETRY_ATTEMPTS,
+        UPGRADE_CONFIG_MAP_MOUNT_PATH, UPGRADE_CONFIG_MAP_NAME_SUFFIX,
+        UPGRADE_EVENT_FAILED_ACTIONS, UPGRADE_EVENT_REASON, UPGRADE_EVENT_SUCCESSFUL_ACTION,
         UPGRADE_JOB_CLUSTERROLEBINDING_NAME_SUFFIX, UPGRADE_JOB_CLUSTERROLE_NAME_SUFFIX,
         UPGRADE_JOB_IMAGE_NAME, UPGRADE_JOB_IMAGE_REPO, UPGRADE_JOB_NAME_SUFFIX,
-        UPGRADE_JOB_SERVICEACCOUNT_NAME_SUFFIX,
+        UPGRADE_JOB_SERVICEACCOUNT_NAME_SUFFIX, UPGRADE_PAUSED_ANNOTATION,
     },
     error, objects,
+    plan::UpgradePlan,
+    simulate::UpgradeCommand,
+    upgrade_path::{intermediate_versions, target_version, upgrade_through},
     user_prompt::{
-        upgrade_dry_run_summary, CONTROL_PLANE_PODS_LIST, DATA_PLANE_PODS_LIST,
-        DATA_PLANE_PODS_LIST_SKIP_RESTART, DELETE_INCOMPLETE_JOB, HELM_UPGRADE_VALIDATION_ERROR,
-        UPGRADE_DRY_RUN_SUMMARY, UPGRADE_JOB_STARTED,
+        DELETE_INCOMPLETE_JOB, HELM_UPGRADE_VALIDATION_ERROR, ROLLBACK_JOB_STARTED,
+        UPGRADE_JOB_STARTED, UPGRADE_PAUSED, UPGRADE_RESUMED,
     },
 };
 use k8s_openapi::api::{
     apps::v1::Deployment,
     batch::v1::Job,
-    core::v1::{ConfigMap, Event, PersistentVolumeClaim, Pod, ServiceAccount},
+    core::v1::{ConfigMap, Event, PersistentVolumeClaim, ServiceAccount},
     rbac::v1::{ClusterRole, ClusterRoleBinding},
 };
 use kube::{
-    api::{Api, DeleteParams, ListParams, PostParams},
-    core::ObjectList,
+    api::{Api, DeleteParams, ListParams, Patch, PatchParams, PostParams},
     Client,
 };
-use serde::Deserialize;
+use semver::Version;
+use serde::{Deserialize, Serialize};
 use snafu::ResultExt;
 use std::{
     collections::{BTreeMap, HashMap, HashSet},
     fs,
-    time::Duration,
+    io::{IsTerminal, Write},
+    time::{Duration, SystemTime},
 };
 
+/// Escape sequence which clears the terminal and moves the cursor to its top left corner.
+const CLEAR_SCREEN: &str = "\x1B[2J\x1B[H";
+
 /// Arguments to be passed for upgrade.
 #[derive(clap::Subcommand, Debug)]
 pub enum DeleteResources {
@@ -74,6 +80,107 @@ impl DeleteUpgradeArgs {
     }
 }
 
+/// Roll back the last upgrade.
+#[derive(clap::Args, Debug, Clone)]
+pub struct RollbackUpgradeArgs {}
+
+impl RollbackUpgradeArgs {
+    /// Roll the release back to the source of its last upgrade, by running the upgrade job in the
+    /// rollback mode. The resources of a finished upgrade job are replaced, whether it succeeded
+    /// or failed, while a running one is never interrupted.
+    pub async fn rollback(&self, namespace: &str, upgrade_args: &UpgradeArgs) -> error::Result<()> {
+        let uo = UpgradeResources::new(namespace).await?;
+        let job_name = upgrade_name_concat(&uo.release_name, UPGRADE_JOB_NAME_SUFFIX);
+        if let Some(job) = uo
+            .job
+            .get_opt(&job_name)
+            .await
+            .context(error::GetUpgradeJob {
+                name: job_name.clone(),
+            })?
+        {
+            let active = job
+                .status
+                .and_then(|status| status.active)
+                .unwrap_or_default();
+            if active > 0 {
+                return error::UpgradeJobInProgress {
+                    name: job_name,
+                    namespace,
+                }
+                .fail();
+            }
+            UpgradeResources::delete_upgrade_resources(namespace).await?;
+        }
+
+        let args = UpgradeArgs {
+            rollback: true,
+            ..upgrade_args.clone()
+        };
+        args.apply(namespace).await
+    }
+}
+
+/// Pause or resume the data plane upgrade.
+#[derive(clap::Args, Debug, Clone)]
+pub struct PauseUpgradeArgs {}
+
+impl PauseUpgradeArgs {
+    /// Pause or resume the data plane upgrade of the running upgrade job, by setting or removing
+    /// its pause annotation. The upgrade job checks it before each node, or group of nodes.
+    pub async fn set_paused(&self, namespace: &str, paused: bool) -> error::Result<()> {
+        let uo = UpgradeResources::new(namespace).await?;
+        let job_name = upgrade_name_concat(&uo.release_name, UPGRADE_JOB_NAME_SUFFIX);
+        let job = uo
+            .job
+            .get_opt(&job_name)
+            .await
+            .context(error::GetUpgradeJob {
+                name: job_name.clone(),
+            })?
+            .ok_or(
+                error::UpgradeJobNotPresent {
+                    name: job_name.clone(),
+                    namespace,
+                }
+                .build(),
+            )?;
+        let active = job
+            .status
+            .and_then(|status| status.active)
+            .unwrap_or_default();
+        if active == 0 {
+            return error::UpgradeJobNotRunning {
+                name: job_name,
+                namespace,
+            }
+            .fail();
+        }
+
+        let value = match paused {
+            true => serde_json::json!("true"),
+            false => serde_json::Value::Null,
+        };
+        let patch = serde_json::json!({
+            "metadata": {
+                "annotations": {
+                    UPGRADE_PAUSED_ANNOTATION: value
+                }
+            }
+        });
+        uo.job
+            .patch(&job_name, &PatchParams::default(), &Patch::Merge(&patch))
+            .await
+            .context(error::UpgradeJobPatch { name: job_name })?;
+
+        match paused {
+            true => console_logger::info(UPGRADE_PAUSED, ""),
+            false => console_logger::info(UPGRADE_RESUMED, ""),
+        }
+        Ok(())
+    }
+}
+
 #[derive(clap::Subcommand, Debug)]
 /// Actions to be performed.
 pub enum Actions {
@@ -91,11 +198,17 @@ pub struct UpgradeArgs {
     #[clap(global = true, long, hide = true)]
     pub allow_unstable: bool,
 
-    /// Display all the validations output but will not execute upgrade.
+    /// Allow upgrade to a lower version than the installed one. The upgrade job validates that
+    /// the data formats of the installed version can be read by the lower version beforehand.
+    #[clap(global = true, long)]
+    pub allow_downgrade: bool,
+
+    /// Display all the validations output and the upgrade plan, but will not execute upgrade.
     #[clap(global = true, long, short)]
     pub dry_run: bool,
 
-    /// If set then upgrade will skip the io-engine pods restart.
+    /// If set then upgrade will skip the io-engine pods restart, the nodes which still run the
+    /// older version are recorded.
     #[clap(global = true, long, default_value_t = false)]
     pub skip_data_plane_restart: bool,
 
@@ -111,6 +224,30 @@ pub struct UpgradeArgs {
     #[clap(global = true, long)]
     pub skip_cordoned_node_validation: bool,
 
+    /// If set then upgrade will skip the validation that all the pools are online.
+    #[clap(global = true, long)]
+    pub skip_offline_pools_validation: bool,
+
+    /// If set then upgrade will skip the validation that the control plane, i.e. etcd, the
+    /// agent-core and the api-rest, is healthy.
+    #[clap(global = true, long)]
+    pub skip_control_plane_validation: bool,
+
+    /// If set then upgrade will skip the validation of the node-level requirements of the target
+    /// io-engine version, eg: hugepages and kernel modules.
+    #[clap(global = true, long)]
+    pub skip_node_requirements_validation: bool,
+
+    /// If set then the nodes which don't meet the node-level requirements of the target version
+    /// don't fail the upgrade, instead the restart of their io-engine pods is held back.
+    #[clap(global = true, long)]
+    pub hold_unprepared_nodes: bool,
+
+    /// The nodes whose io-engine pods are not restarted during upgrade, as they don't meet the
+    /// node-level requirements of the target version.
+    #[clap(skip)]
+    pub held_nodes: Vec<String>,
+
     /// Upgrade to an unsupported version.
     #[clap(global = true, hide = true, long, default_value_t = false)]
     pub skip_upgrade_path_validation_for_unsupported_version: bool,
@@ -124,6 +261,96 @@ pub struct UpgradeArgs {
     /// (can specify multiple or separate values with commas: key1=path1,key2=path2).
     #[clap(global = true, long)]
     pub set_file: Vec<String>,
+
+    /// The values files whose values override the ones of the release, before the set values
+    /// (can specify multiple or separate values with commas: path1,path2).
+    #[clap(global = true, long)]
+    pub values: Vec<String>,
+
+    /// The images overriding the ones of the components of the helm chart, eg: a hotfixed image,
+    /// instead of a custom chart build. The image must be tagged with a version of the same minor
+    /// release as the target version, which isn't older
+    /// (can specify multiple or separate values with commas: component1=image1,component2=image2).
+    #[clap(global = true, long, value_name = "COMPONENT=IMAGE")]
+    pub set_image: Vec<String>,
+
+    /// The maximum number of revisions of the helm release which are kept. The oldest revisions
+    /// are pruned before the upgrade, the deployed one is always kept.
+    #[clap(global = true, long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub helm_history_max: Option<u32>,
+
+    /// The time to wait for the helm upgrade of the release to complete, eg: on clusters with
+    /// slow image pulls or a large number of nodes. This is 15m by default.
+    #[clap(global = true, long)]
+    pub helm_timeout: Option<humantime::Duration>,
+
+    /// If set then the helm upgrade isn't rolled back by helm if it fails or times out, so that
+    /// the failure can be inspected and the upgrade retried.
+    #[clap(global = true, long)]
+    pub no_atomic: bool,
+
+    /// If set then the helm upgrade waits for the resources of the release to be ready, when it
+    /// isn't atomic. The atomic helm upgrade always waits for them.
+    #[clap(global = true, long)]
+    pub helm_wait: bool,
+
+    /// If set then the helm upgrade also waits for the jobs of the release to complete.
+    #[clap(global = true, long)]
+    pub helm_wait_for_jobs: bool,
+
+    /// The time to wait, after the data-plane upgrade, for the io-engine of every node to report
+    /// the target version. The nodes still on an older version by then fail the upgrade.
+    #[clap(global = true, long)]
+    pub data_plane_convergence_timeout: Option<humantime::Duration>,
+
+    /// The node label whose values group the nodes whose io-engine pods are restarted together,
+    /// eg: a zone label. Every node is upgraded on its own if this isn't set.
+    #[clap(global = true, long, value_name = "LABEL")]
+    pub data_plane_group_label: Option<String>,
+
+    /// If set then the data plane upgrade is paused once the first node, or group of nodes, is
+    /// upgraded, until it's resumed with the 'upgrade resume' command.
+    #[clap(global = true, long)]
+    pub canary: bool,
+
+    /// The time to wait for the volumes of the upgraded nodes to be healthy again, before the
+    /// data plane upgrade is paused.
+    #[clap(global = true, long)]
+    pub volume_health_timeout: Option<humantime::Duration>,
+
+    /// If set then the release is rolled back to the chart version and the values it had before
+    /// the upgrade, when the data plane upgrade fails.
+    #[clap(global = true, long)]
+    pub rollback_on_failure: bool,
+
+    /// Run the upgrade job to roll the release back, instead of upgrading it.
+    #[clap(skip)]
+    pub rollback: bool,
+
+    /// If set then an installation which can't be upgraded to the target version directly, as
+    /// it's older than a version the upgrade can't skip, is upgraded through the intermediate
+    /// versions first, one after the other.
+    #[clap(global = true, long)]
+    pub multi_hop: bool,
+
+    /// If set then no quick dump of the cluster is collected before the upgrade. By default, the
+    /// versions of the components and the state of the resources are dumped, so that a failed
+    /// upgrade can be diagnosed from the state before the upgrade.
+    #[clap(global = true, long)]
+    pub skip_preflight_dump: bool,
+
+    /// Directory to write the archive file of the quick dump collected before the upgrade to.
+    #[clap(global = true, long, value_name = "DIR_PATH", default_value = "./")]
+    pub preflight_dump_dir: String,
+
+    /// The image tag of the upgrade job, i.e. the version it upgrades to, if it's not the version
+    /// of this plugin.
+    #[clap(skip)]
+    pub job_version_tag: Option<String>,
+
+    /// The upgrade action to perform instead of upgrading the cluster.
+    #[clap(subcommand)]
+    pub command: Option<UpgradeCommand>,
 }
 
 impl Default for UpgradeArgs {
@@ -137,18 +364,49 @@ impl UpgradeArgs {
     pub fn new() -> Self {
         Self {
             allow_unstable: false,
+            allow_downgrade: false,
             dry_run: false,
             skip_data_plane_restart: false,
             skip_single_replica_volume_validation: false,
             skip_replica_rebuild: false,
             skip_cordoned_node_validation: false,
+            skip_offline_pools_validation: false,
+            skip_control_plane_validation: false,
+            skip_node_requirements_validation: false,
+            hold_unprepared_nodes: false,
+            held_nodes: Default::default(),
             skip_upgrade_path_validation_for_unsupported_version: false,
             set: Default::default(),
             set_file: Default::default(),
+            values: Default::default(),
+            set_image: Default::default(),
+            helm_history_max: None,
+            helm_timeout: None,
+            no_atomic: false,
+            helm_wait: false,
+            helm_wait_for_jobs: false,
+            data_plane_convergence_timeout: None,
+            data_plane_group_label: None,
+            canary: false,
+            volume_health_timeout: None,
+            rollback_on_failure: false,
+            rollback: false,
+            multi_hop: false,
+            skip_preflight_dump: false,
+            preflight_dump_dir: "./".to_string(),
+            job_version_tag: None,
+            command: None,
         }
     }
     ///  Upgrade the resources.
     pub async fn apply(&self, namespace: &str) -> error::Result<()> {
+        // The installation is upgraded through the intermediate versions first, if it's too old
+        // to be upgraded to the target version directly.
+        if self.multi_hop && !self.rollback {
+            let versions = self.intermediate_versions(namespace).await?;
+            upgrade_through(namespace, self, &versions).await?;
+        }
+
         let upgrade_event_client = UpgradeEventClient::new(namespace).await?;
         let release_name = get_release_name(namespace).await?;
 
@@ -175,6 +433,21 @@ impl UpgradeArgs {
         Ok(())
     }
 
+    /// The intermediate versions the installation is upgraded through, before the target version.
+    async fn intermediate_versions(&self, namespace: &str) -> error::Result<Vec<Version>> {
+        if self.skip_upgrade_path_validation_for_unsupported_version {
+            return Ok(Vec::new());
+        }
+        let source_version = get_source_version(namespace).await?;
+        let source = Version::parse(source_version.as_str()).context(error::SemverParse {
+            version_string: source_version.clone(),
+        })?;
+        match target_version() {
+            Some(target) => intermediate_versions(&source, &target),
+            None => Ok(Vec::new()),
+        }
+    }
+
     /// Handle the event and errors out for invalid helm command.
     async fn handle_upgrade_event(
         &self,
@@ -192,6 +465,8 @@ impl UpgradeArgs {
                 } else {
                     return error::MessageInEventNotPresent.fail();
                 }
+            } else if self.rollback {
+                console_logger::info(ROLLBACK_JOB_STARTED, "");
             } else {
                 console_logger::info(UPGRADE_JOB_STARTED, "");
             }
@@ -199,86 +474,210 @@ impl UpgradeArgs {
         Ok(())
     }
 
-    /// Dummy upgrade the resources.
+    /// Dummy upgrade the resources, i.e. print the plan of the upgrade without changing anything.
     pub async fn dummy_apply(&self, namespace: &str) -> error::Result<()> {
-        let mut pods_names: Vec<String> = Vec::new();
-        list_pods(AGENT_CORE_POD_LABEL, namespace, &mut pods_names).await?;
-        list_pods(API_REST_POD_LABEL, namespace, &mut pods_names).await?;
-        console_logger::info(CONTROL_PLANE_PODS_LIST, &pods_names.join("\n"));
-
-        let mut io_engine_pods_names: Vec<String> = Vec::new();
-        list_pods(IO_ENGINE_POD_LABEL, namespace, &mut io_engine_pods_names).await?;
-        if self.skip_data_plane_restart {
-            console_logger::info(
-                DATA_PLANE_PODS_LIST_SKIP_RESTART,
-                &io_engine_pods_names.join("\n"),
-            );
-        } else {
-            console_logger::info(DATA_PLANE_PODS_LIST, &io_engine_pods_names.join("\n"));
-        }
-        console_logger::info(
-            upgrade_dry_run_summary(UPGRADE_DRY_RUN_SUMMARY).as_str(),
-            "",
-        );
+        UpgradePlan::compute(namespace, self).await?.print();
         Ok(())
     }
 }
 
-pub(crate) async fn list_pods(
-    label: &str,
-    namespace: &str,
-    pods_names: &mut Vec<String>,
-) -> error::Result<()> {
-    let client = Client::try_default().await.context(error::K8sClient)?;
-    let pods: Api<Pod> = Api::namespaced(client, namespace);
-    let pod_list: ObjectList<Pod> = pods
-        .list(&ListParams::default().labels(label))
-        .await
-        .context(error::ListPodsWithLabel {
-            label: label.to_string(),
-            namespace: namespace.to_string(),
-        })?;
-
-    for pod in pod_list.iter() {
-        // Fetch the pod name
-        let pod_name = pod
-            .metadata
-            .name
-            .as_ref()
-            .ok_or(error::PodNameNotPresent.build())?
-            .as_str();
-        let _ = &pods_names.push(pod_name.to_string());
-    }
-    Ok(())
-}
-
 /// Arguments to be passed for upgrade.
 #[derive(Debug, Clone, clap::Args)]
-pub struct GetUpgradeArgs {}
+pub struct GetUpgradeArgs {
+    /// Follow the upgrade until it completes or fails, printing its status, i.e. its phase, the
+    /// progress of the data-plane restart of the nodes and its errors, whenever it changes. The
+    /// command exits with an error if the upgrade fails.
+    #[clap(long, short)]
+    follow: bool,
+}
 
 impl GetUpgradeArgs {
-    ///  Upgrade the resources.
-    pub async fn get_upgrade(&self, namespace: &str) {
-        // Create resources for getting upgrade status
-        _ = UpgradeEventClient::create_get_upgrade_resource(namespace)
-            .await
-            .map_err(|error| {
-                std::process::exit(error.into());
-            });
+    ///  Upgrade the resources. The status of the followed upgrade is checked every refresh
+    /// interval.
+    pub async fn get_upgrade(
+        &self,
+        namespace: &str,
+        output: UpgradeStatusOutput,
+        refresh: Duration,
+    ) {
+        let result = if self.follow {
+            UpgradeEventClient::follow_upgrade(namespace, output, refresh).await
+        } else {
+            // Create resources for getting upgrade status
+            UpgradeEventClient::create_get_upgrade_resource(namespace, output).await
+        };
+        _ = result.map_err(|error| {
+            std::process::exit(error.into());
+        });
     }
 }
 
+/// The output format of the upgrade status.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UpgradeStatusOutput {
+    /// Human readable lines.
+    #[default]
+    Table,
+    /// Json, with the field names of the upgrade event.
+    Json,
+    /// Yaml, with the field names of the upgrade event.
+    Yaml,
+}
+
 /// This struct is used to deserialize the output of ugrade events.
-#[derive(Clone, Deserialize)]
-#[serde(rename_all(deserialize = "camelCase"))]
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub(crate) struct UpgradeEvent {
     from_version: String,
     to_version: String,
     message: String,
+    #[serde(default)]
+    health_before: Option<HealthSnapshot>,
+    #[serde(default)]
+    health_after: Option<HealthSnapshot>,
+    #[serde(default)]
+    convergence: Option<ConvergenceStatus>,
+    #[serde(default)]
+    rollback: Option<RollbackStatus>,
+    #[serde(default)]
+    progress: Option<UpgradeProgress>,
+}
+
+/// This struct is used to deserialize the progress of the upgrade, i.e. its phase, the component
+/// which is being upgraded and the number of upgraded io-engine nodes.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct UpgradeProgress {
+    phase: String,
+    #[serde(default)]
+    component: Option<String>,
+    nodes_upgraded: usize,
+    nodes_total: usize,
+    #[serde(default)]
+    last_error: Option<String>,
+}
+
+impl UpgradeProgress {
+    /// Print the phase of the upgrade, the component which is being upgraded, the number of
+    /// upgraded nodes and the last error.
+    fn print(&self) {
+        println!("Upgrade Phase: {}", self.phase);
+        if let Some(component) = &self.component {
+            println!("Current Component: {component}");
+        }
+        if self.nodes_total > 0 {
+            println!(
+                "Data-plane Nodes Upgraded: {}/{}",
+                self.nodes_upgraded, self.nodes_total
+            );
+        }
+        if let Some(error) = &self.last_error {
+            println!("Last Error: {error}");
+        }
+    }
+}
+
+/// This struct is used to deserialize the status of the rollback of the release to the source of
+/// its last upgrade.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RollbackStatus {
+    version: String,
+    revision: String,
+    state: String,
+    #[serde(default)]
+    message: Option<String>,
+}
+
+impl RollbackStatus {
+    /// Print the version the release is rolled back to, and the state of the rollback.
+    fn print(&self) {
+        println!(
+            "Rollback To: {} (helm revision {})",
+            self.version, self.revision
+        );
+        match &self.message {
+            Some(message) => println!("Rollback Status: {}: {message}", self.state),
+            None => println!("Rollback Status: {}", self.state),
+        }
+    }
+}
+
+/// This struct is used to deserialize the version convergence of the data-plane, i.e. the
+/// version the io-engine of each node reports after the data-plane upgrade.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ConvergenceStatus {
+    target_version: String,
+    converged: bool,
+    #[serde(default)]
+    restart_pending: bool,
+    nodes: BTreeMap<String, NodeConvergence>,
+}
+
+/// This struct is used to deserialize the version the io-engine of a node reports.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct NodeConvergence {
+    #[serde(default)]
+    version: Option<String>,
+    converged: bool,
+    restarts: i32,
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+impl ConvergenceStatus {
+    /// Print the version reported by the io-engine of each node, flagging the stuck ones, or the
+    /// ones pending a restart if the data-plane restart was skipped.
+    fn print(&self) {
+        let (status, flag) = match (self.converged, self.restart_pending) {
+            (true, _) => ("converged", "stuck"),
+            (false, true) => ("restart pending", "pending restart"),
+            (false, false) => ("not converged", "stuck"),
+        };
+        println!("Data-plane Version {}: {status}", self.target_version);
+        for (name, node) in &self.nodes {
+            let version = node.version.as_deref().unwrap_or("unknown");
+            match (&node.reason, node.converged) {
+                (Some(reason), false) => println!(
+                    "  {name}: {version}, restarts: {}, {flag}: {reason}",
+                    node.restarts
+                ),
+                _ => println!("  {name}: {version}, restarts: {}", node.restarts),
+            }
+        }
+    }
+}
+
+/// This struct is used to deserialize the cluster health snapshots of the upgrade events, i.e.
+/// the number of volumes, pools and nodes in each state.
+#[derive(Clone, Deserialize, Serialize)]
+pub(crate) struct HealthSnapshot {
+    volumes: BTreeMap<String, usize>,
+    pools: BTreeMap<String, usize>,
+    nodes: BTreeMap<String, usize>,
+}
+
+impl HealthSnapshot {
+    /// Print the health snapshot with the given title.
+    fn print(&self, title: &str) {
+        let format = |counts: &BTreeMap<String, usize>| {
+            counts
+                .iter()
+                .map(|(state, count)| format!("{state}: {count}"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        println!("{title}:");
+        println!("  Volumes: {}", format(&self.volumes));
+        println!("  Pools: {}", format(&self.pools));
+        println!("  Nodes: {}", format(&self.nodes));
+    }
 }
 
 /// Resource to be created to get upgrade status.
-struct UpgradeEventClient {
+pub(crate) struct UpgradeEventClient {
     upgrade_event: Api<Event>,
 }
 
@@ -337,35 +736,127 @@ impl UpgradeEventClient {
     }
 
     /// Create resources for fetching upgrade events.
-    pub async fn create_get_upgrade_resource(ns: &str) -> error::Result<()> {
+    pub async fn create_get_upgrade_resource(
+        ns: &str,
+        output: UpgradeStatusOutput,
+    ) -> error::Result<()> {
         let release_name = get_release_name(ns).await?;
         let upgrade_event_client = UpgradeEventClient::new(ns).await?;
         let latest_event = upgrade_event_client
             .get_latest_upgrade_event(release_name)
             .await?;
-        log_upgrade_result(&latest_event).await?;
-        Ok(())
+        print_upgrade_status(&latest_event, output).await
+    }
+
+    /// Follow the upgrade until its upgrade job ends, printing its status every time it changes.
+    /// On a terminal the table output is redrawn in place, otherwise each status is appended,
+    /// the yaml ones as separate documents. The upgrade events which aren't published yet are
+    /// waited for, so that the upgrade can be followed as soon as it's started.
+    pub async fn follow_upgrade(
+        ns: &str,
+        output: UpgradeStatusOutput,
+        interval: Duration,
+    ) -> error::Result<()> {
+        let release_name = get_release_name(ns).await?;
+        let upgrade_event_client = UpgradeEventClient::new(ns).await?;
+        let terminal = std::io::stdout().is_terminal();
+        let mut last_message = None;
+        loop {
+            let latest_event = match upgrade_event_client
+                .get_latest_upgrade_event(release_name.clone())
+                .await
+            {
+                Ok(event) => event,
+                Err(error::Error::UpgradeEventNotPresent) => {
+                    tokio::time::sleep(interval).await;
+                    continue;
+                }
+                Err(error) => return Err(error),
+            };
+            if latest_event.message != last_message {
+                match output {
+                    UpgradeStatusOutput::Table if terminal => {
+                        print!("{CLEAR_SCREEN}");
+                        println!(
+                            "Every {}: kubectl mayastor get upgrade-status --follow    {}\n",
+                            humantime::format_duration(interval),
+                            humantime::format_rfc3339_seconds(SystemTime::now())
+                        );
+                    }
+                    UpgradeStatusOutput::Table if last_message.is_some() => println!(),
+                    UpgradeStatusOutput::Yaml if last_message.is_some() => println!("---"),
+                    _ => {}
+                }
+                print_upgrade_status(&latest_event, output).await?;
+                let _ = std::io::stdout().flush();
+                last_message = latest_event.message.clone();
+            }
+
+            let action = latest_event.action.as_deref().unwrap_or_default();
+            if UPGRADE_EVENT_FAILED_ACTIONS.contains(&action) {
+                return error::UpgradeFailed { phase: action }.fail();
+            }
+            if action == UPGRADE_EVENT_SUCCESSFUL_ACTION {
+                return Ok(());
+            }
+            tokio::time::sleep(interval).await;
+        }
     }
 }
 
-/// Print the upgrade iutput to console.
-pub async fn log_upgrade_result(event: &Event) -> error::Result<()> {
-    _ = match event.message.clone() {
-        Some(data) => {
-            let e: UpgradeEvent = serde_json::from_str(data.as_str())
-                .context(error::EventSerdeDeserialization { event: data })?;
-            println!("Upgrade From: {}", e.from_version);
-            println!("Upgrade To: {}", e.to_version);
-            println!("Upgrade Status: {}", e.message);
-            Ok(())
+/// Print the upgrade status of the upgrade event in the output format.
+async fn print_upgrade_status(event: &Event, output: UpgradeStatusOutput) -> error::Result<()> {
+    match output {
+        UpgradeStatusOutput::Table => log_upgrade_result(event).await?,
+        UpgradeStatusOutput::Json => {
+            let status = serde_json::to_string_pretty(&upgrade_event(event)?)
+                .context(error::UpgradeStatusJsonSerialization)?;
+            println!("{status}");
+        }
+        UpgradeStatusOutput::Yaml => {
+            let status = serde_yaml::to_string(&upgrade_event(event)?)
+                .context(error::UpgradeStatusYamlSerialization)?;
+            print!("{status}");
         }
+    }
+    Ok(())
+}
+
+/// Deserialize the upgrade status from the message of the upgrade event.
+fn upgrade_event(event: &Event) -> error::Result<UpgradeEvent> {
+    match event.message.clone() {
+        Some(data) => serde_json::from_str(data.as_str())
+            .context(error::EventSerdeDeserialization { event: data }),
         None => error::MessageInEventNotPresent.fail(),
-    };
+    }
+}
+
+/// Print the upgrade iutput to console.
+pub async fn log_upgrade_result(event: &Event) -> error::Result<()> {
+    let e = upgrade_event(event)?;
+    println!("Upgrade From: {}", e.from_version);
+    println!("Upgrade To: {}", e.to_version);
+    println!("Upgrade Status: {}", e.message);
+    if let Some(progress) = &e.progress {
+        progress.print();
+    }
+    if let Some(health) = &e.health_before {
+        health.print("Health Before Upgrade");
+    }
+    if let Some(health) = &e.health_after {
+        health.print("Health After Upgrade");
+    }
+    if let Some(convergence) = &e.convergence {
+        convergence.print();
+    }
+    if let Some(rollback) = &e.rollback {
+        rollback.print();
+    }
     Ok(())
 }
 
 /// K8s resources needed for upgrade operator.
-struct UpgradeResources {
+pub(crate) struct UpgradeResources {
     pub(crate) service_account: Api<ServiceAccount>,
     pub(crate) cluster_role: Api<ClusterRole>,
     pub(crate) cluster_role_binding: Api<ClusterRoleBinding>,
@@ -697,9 +1188,13 @@ impl UpgradeResources {
         } else {
             match action {
                 Actions::Create => {
-                    let upgrade_job_image_tag = get_image_version_tag();
+                    let upgrade_job_image_tag = args
+                        .job_version_tag
+                        .clone()
+                        .unwrap_or_else(get_image_version_tag);
                     let rest_deployment = get_deployment_for_rest(ns).await?;
                     let img = ImageProperties::try_from(rest_deployment)?;
+                    let values = create_helm_values_args(args, set_file_map.as_ref()).await?;
                     let set_file = create_helm_set_file_args(args, set_file_map).await?;
                     let upgrade_deploy = objects::upgrade_job(
                         ns,
@@ -712,6 +1207,7 @@ impl UpgradeResources {
                         self.release_name.clone(),
                         args,
                         set_file.unwrap_or_default(),
+                        values.unwrap_or_default(),
                         img.pull_secrets(),
                         img.pull_policy(),
                     );
@@ -939,7 +1435,7 @@ pub(crate) async fn get_source_version(ns: &str) -> error::Result<String> {
     Ok(value.to_string())
 }
 
-/// Parse set-file and create config map data.
+/// Parse set-file and values, and create config map data.
 pub(crate) async fn create_config_map_data(
     upgrade_args: &UpgradeArgs,
 ) -> error::Result<(BTreeMap<String, String>, HashMap<String, String>)> {
@@ -965,6 +1461,21 @@ pub(crate) async fn create_config_map_data(
         upgrade_map.insert(filepath.to_string(), index.to_string());
         index += 1;
     }
+    // The values files are stored the same way, a file which is also given with set-file is
+    // stored once.
+    for filepath in upgrade_args
+        .values
+        .iter()
+        .flat_map(|values| values.split(','))
+    {
+        if upgrade_map.contains_key(filepath) {
+            continue;
+        }
+        let cm_values = fs::read_to_string(filepath).context(error::ReadFromFile { filepath })?;
+        data_map.insert(index.to_string(), cm_values);
+        upgrade_map.insert(filepath.to_string(), index.to_string());
+        index += 1;
+    }
     Ok((data_map, upgrade_map))
 }
 
@@ -998,3 +1509,26 @@ pub(crate) async fn create_helm_set_file_args(
     }
     Ok(None)
 }
+
+/// Create helm values args, i.e. the paths the values files are mounted at in the upgrade job.
+pub(crate) async fn create_helm_values_args(
+    upgrade_args: &UpgradeArgs,
+    set_file_map: Option<&HashMap<String, String>>,
+) -> error::Result<Option<String>> {
+    if upgrade_args.values.is_empty() {
+        return Ok(None);
+    }
+    let mut helm_args_values = Vec::new();
+    for filepath in upgrade_args
+        .values
+        .iter()
+        .flat_map(|values| values.split(','))
+    {
+        // Example : --values /root/values.yaml gets converted to /upgrade-config-map/2
+        let mapped_file = set_file_map
+            .and_then(|map| map.get(filepath))
+            .ok_or(error::SpecifiedKeyNotPresent.build())?;
+        helm_args_values.push(format!("{UPGRADE_CONFIG_MAP_MOUNT_PATH}/{mapped_file}"));
+    }
+    Ok(Some(helm_args_values.join(",")))
+}
@@ -0,0 +1,117 @@
+use crate::plugin::{
+    constants::{get_image_version_tag, UPGRADE_EVENT_FAILED_ACTIONS},
+    error,
+    upgrade::{
+        get_release_name, is_upgrade_job_completed, log_upgrade_result, UpgradeArgs,
+        UpgradeEventClient, UpgradeResources,
+    },
+    user_prompt,
+};
+use semver::Version;
+use serde::Deserialize;
+use snafu::ResultExt;
+use std::time::Duration;
+
+/// Interval between the checks of the status of the upgrade job of an intermediate version.
+const HOP_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Struct to deserialize the upgrade path yaml.
+#[derive(Deserialize)]
+struct UpgradePath {
+    intermediate_versions: Vec<Version>,
+}
+
+impl TryFrom<&[u8]> for UpgradePath {
+    type Error = serde_yaml::Error;
+
+    /// Returns an UpgradePath object.
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        serde_yaml::from_reader(bytes)
+    }
+}
+
+/// The target version of the upgrade, i.e. the version of this plugin, if it's a release.
+pub(crate) fn target_version() -> Option<Version> {
+    Version::parse(get_image_version_tag().trim_start_matches('v')).ok()
+}
+
+/// The intermediate versions the upgrade from the source version to the target version goes
+/// through, in order. The intermediate versions of the minor release of the target version are
+/// skipped, as the upgrade within a minor release is always supported.
+pub(crate) fn intermediate_versions(
+    source: &Version,
+    target: &Version,
+) -> error::Result<Vec<Version>> {
+    let upgrade_path_buf = &std::include_bytes!("../../config/upgrade_path.yaml")[..];
+    let upgrade_path =
+        UpgradePath::try_from(upgrade_path_buf).context(error::YamlParseBufferForUpgradePath)?;
+
+    let mut versions = upgrade_path
+        .intermediate_versions
+        .into_iter()
+        .filter(|version| version > source && version < target)
+        .filter(|version| (version.major, version.minor) != (target.major, target.minor))
+        .collect::<Vec<_>>();
+    versions.sort();
+    Ok(versions)
+}
+
+/// Upgrade the installation to each of the intermediate versions, one after the other, with the
+/// upgrade job of the version. Each upgrade job must complete before the next one is started, the
+/// upgrade stops at the first one which fails. The intermediate upgrades run with the default
+/// options, the options of the command apply to the upgrade to the target version.
+pub(crate) async fn upgrade_through(
+    namespace: &str,
+    args: &UpgradeArgs,
+    versions: &[Version],
+) -> error::Result<()> {
+    let upgrade_event_client = UpgradeEventClient::new(namespace).await?;
+    let release_name = get_release_name(namespace).await?;
+
+    for version in versions {
+        console_logger::info(
+            user_prompt::INTERMEDIATE_UPGRADE_STARTED,
+            &version.to_string(),
+        );
+        let hop_args = UpgradeArgs {
+            job_version_tag: Some(format!("v{version}")),
+            skip_data_plane_restart: args.skip_data_plane_restart,
+            ..UpgradeArgs::new()
+        };
+
+        upgrade_event_client
+            .delete_upgrade_events(release_name.clone())
+            .await?;
+        UpgradeResources::create_upgrade_resources(namespace, &hop_args).await?;
+
+        loop {
+            tokio::time::sleep(HOP_POLL_INTERVAL).await;
+            if let Ok(event) = upgrade_event_client
+                .get_latest_upgrade_event(release_name.clone())
+                .await
+            {
+                let failed = event
+                    .action
+                    .as_deref()
+                    .is_some_and(|action| UPGRADE_EVENT_FAILED_ACTIONS.contains(&action));
+                if failed {
+                    log_upgrade_result(&event).await?;
+                    return error::IntermediateUpgradeFailed {
+                        version: version.to_string(),
+                    }
+                    .fail();
+                }
+            }
+            if is_upgrade_job_completed(namespace).await? {
+                break;
+            }
+        }
+
+        UpgradeResources::delete_upgrade_resources(namespace).await?;
+        console_logger::info(
+            user_prompt::INTERMEDIATE_UPGRADE_COMPLETED,
+            &version.to_string(),
+        );
+    }
+    Ok(())
+}
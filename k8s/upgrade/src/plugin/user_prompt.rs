@@ -7,11 +7,24 @@ pub const UPGRADE_WARNING: &str =  "\nVolumes which make use of a single volume
 pub const REBUILD_WARNING: &str =  "\nThe cluster is rebuilding replica of some volumes.\nTo skip this validation please run after some time or re-run with '--skip-replica-rebuild` flag.";
 
 /// Warning to users before doing an upgrade
-pub const SINGLE_REPLICA_VOLUME_WARNING: &str =  "\nThe list below shows the single replica volumes in cluster.\nThese single replica volumes may not be accessible during upgrade.\nTo skip this validation, please re-run with '--skip-single-replica-volume-validation` flag.";
+pub const SINGLE_REPLICA_VOLUME_WARNING: &str =  "\nThe list below shows the published single replica volumes in cluster.\nThese single replica volumes may not be accessible during upgrade.\nTo skip this validation, please re-run with '--skip-single-replica-volume-validation` flag.";
 
 /// Warning to users before doing an upgrade.
 pub const CORDONED_NODE_WARNING: &str =  "\nOne or more nodes in this cluster are in a Mayastor cordoned state.\nThis implies that the storage space of DiskPools on these nodes cannot be utilized for volume replica rebuilds.\nPlease ensure remaining storage nodes have enough available DiskPool space to accommodate volume replica rebuilds,\nthat get triggered during the upgrade process.\nTo skip this validation, please re-run with '--skip-cordoned-node-validation` flag.\nBelow is a list of the Mayastor cordoned nodes:";
 
+/// Warning to users before doing an upgrade.
+pub const NODE_REQUIREMENTS_WARNING: &str =  "\nOne or more io-engine nodes in this cluster don't meet the node-level requirements of the target version.\nThe io-engine may fail to start on these nodes after the upgrade, please follow the steps below to prepare them.\nTo only hold back the data plane upgrade of these nodes, please re-run with '--hold-unprepared-nodes` flag.\nTo skip this validation, please re-run with '--skip-node-requirements-validation` flag.";
+
+/// Warning to users before doing an upgrade.
+pub const OFFLINE_POOLS_WARNING: &str =  "\nOne or more pools in this cluster are not online.\nThe replicas on these pools can't be rebuilt from while the io-engine pods restart, which may leave volumes without any healthy replica.\nPlease bring these pools back online, eg: by checking their node and disks with 'kubectl mayastor get pools', before upgrading.\nTo skip this validation, please re-run with '--skip-offline-pools-validation` flag.\nBelow is a list of the pools which are not online:";
+
+/// Warning to users before doing an upgrade.
+pub const CONTROL_PLANE_UNHEALTHY_WARNING: &str =  "\nThe control plane of this cluster is not healthy.\nThe upgrade relies on etcd, the agent-core and the api-rest to track the volumes while the io-engine pods restart.\nPlease check the events and the logs of the pods below, eg: with 'kubectl describe pod', before upgrading.\nTo skip this validation, please re-run with '--skip-control-plane-validation` flag.\nBelow is a list of the control plane issues:";
+
+/// Info about the held back data plane nodes.
+pub const HELD_NODES_LIST: &str =
+    "\nList of nodes whose data plane pods will not be restarted during upgrade, until they meet the requirements of the target version.";
+
 /// Info about the control plane pods.
 pub const CONTROL_PLANE_PODS_LIST: &str =
     "\nList of control plane pods which will be restarted during upgrade.";
@@ -24,6 +37,29 @@ pub const DATA_PLANE_PODS_LIST: &str =
 pub const DATA_PLANE_PODS_LIST_SKIP_RESTART: &str =
     "\nList of data plane pods which need to be manually restarted to reflect upgrade as --skip-data-plane-restart flag is passed during upgrade.";
 
+/// Info about the images of the upgrade plan.
+pub const IMAGES_PLAN: &str =
+    "\nImages of the workloads of the release, and the ones they will be upgraded to.";
+
+/// Info about the helm values of the upgrade plan.
+pub const VALUES_PLAN: &str = "\nChanges to the user supplied helm values of the release.";
+
+/// Info about the control plane restarts of the upgrade plan.
+pub const CONTROL_PLANE_PLAN: &str =
+    "\nFirst, the helm upgrade will roll out the pods of these workloads.";
+
+/// Info about the data plane restarts of the upgrade plan.
+pub const DATA_PLANE_PLAN: &str =
+    "\nThen, the data plane pods will be restarted one node at a time, in this order, as node/pod.\nFor each node, the upgrade waits for the volume rebuilds, drains the node, restarts the pod, waits until it is running and uncordons the node.";
+
+/// Info about the data plane restarts of the upgrade plan.
+pub const DATA_PLANE_PLAN_SKIP_RESTART: &str =
+    "\nThen, the data plane pods need to be manually restarted, as node/pod, as --skip-data-plane-restart flag is passed during upgrade.";
+
+/// Info about the held back data plane pods of the upgrade plan.
+pub const HELD_BACK_PODS_PLAN: &str =
+    "\nThe data plane pods of the held back nodes will not be restarted, as node/pod.";
+
 /// Append the release name to k8s objects.
 pub(crate) fn upgrade_dry_run_summary(message: &str) -> String {
     let tag = release_version();
@@ -31,6 +67,10 @@ pub(crate) fn upgrade_dry_run_summary(message: &str) -> String {
     format!("{message} : {version}")
 }
 
+/// Notice about the upgrade simulation.
+pub const UPGRADE_SIMULATION_NOTICE: &str =
+    "\nSimulating the upgrade offline against the cluster state recorded in the support bundle:";
+
 /// Info about the data plane pods.
 pub const UPGRADE_DRY_RUN_SUMMARY: &str =
     "\nFinally the cluster deployment will be upgraded to version";
@@ -39,6 +79,17 @@ pub const UPGRADE_DRY_RUN_SUMMARY: &str =
 pub const UPGRADE_JOB_STARTED: &str =
     "\nThe upgrade has started. You can see the recent upgrade status using 'get upgrade-status` command.";
 
+/// Information about successful start of rollback process.
+pub const ROLLBACK_JOB_STARTED: &str =
+    "\nThe rollback has started. You can see the recent rollback status using 'get upgrade-status` command.";
+
+/// Information about the pause of the upgrade.
+pub const UPGRADE_PAUSED: &str =
+    "\nThe data plane upgrade will pause before the next node. You can resume it using 'upgrade resume' command.";
+
+/// Information about the resume of the upgrade.
+pub const UPGRADE_RESUMED: &str = "\nThe data plane upgrade is resumed.";
+
 /// Upgrade path not valid.
 pub const UPGRADE_PATH_NOT_VALID: &str =
     "\nThe upgrade path is not valid. The source version is in the list of unsupported versions:";
@@ -61,5 +112,25 @@ pub const STABLE_TO_UNSTABLE_UPGRADE: &str =
 
 /// Failure notice for when upgrading from a higher version to a lower one.
 pub const HIGHER_TO_LOWER_SEMVER_UPGRADE: &str =
-    "Cannot upgrade from a higher version to a lower version. \
-If this is intentional, try again with '--skip-upgrade-path-validation-for-unsupported-version'";
+    "Cannot upgrade from a higher version to a lower version, as the lower version may not read the data written by the higher one. \
+If this is intentional, try again with '--allow-downgrade', the data formats are then validated before the downgrade";
+
+/// Failure notice for when the upgrade needs to go through intermediate versions.
+pub const MULTI_HOP_UPGRADE_REQUIRED: &str =
+    "\nThe installed version is too old to be upgraded to the target version directly. Use the --multi-hop flag to upgrade it through these intermediate versions first, one after the other:";
+
+/// Info about the intermediate versions of the upgrade.
+pub const MULTI_HOP_UPGRADE_PATH: &str =
+    "\nThe installation will be upgraded through these intermediate versions first, one after the other:";
+
+/// Information about the start of the upgrade to an intermediate version.
+pub const INTERMEDIATE_UPGRADE_STARTED: &str =
+    "\nThe upgrade to the intermediate version has started, waiting for it to complete:";
+
+/// Information about the completion of the upgrade to an intermediate version.
+pub const INTERMEDIATE_UPGRADE_COMPLETED: &str =
+    "\nThe upgrade to the intermediate version completed:";
+
+/// Warning about the downgrade.
+pub const DOWNGRADE_WARNING: &str =
+    "\nThe target version is lower than the installed version. The upgrade job validates that the specs in etcd and the custom resources can be read by the target version, and fails otherwise.";
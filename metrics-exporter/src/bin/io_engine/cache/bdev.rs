@@ -0,0 +1,144 @@
+use super::{Cache, InternLabels, LabelInterner, MonotonicCounter, PoolCache, ResourceOps};
+use crate::client::{
+    bdev::{BdevErrorStats, BdevOperations},
+    grpc_client::{GrpcClient, RefreshedResource},
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, ops::DerefMut, sync::Arc};
+use tracing::{debug, error};
+
+/// Error counters sampled from the bdev backing a disk of a pool.
+#[derive(Debug, Clone)]
+pub(crate) struct PoolDeviceErrors {
+    pool: Arc<str>,
+    device: Arc<str>,
+    stats: BdevErrorStats,
+}
+
+impl InternLabels for PoolDeviceErrors {
+    fn intern_labels(&mut self, labels: &mut LabelInterner) {
+        labels.intern_in_place(&mut self.pool);
+        labels.intern_in_place(&mut self.device);
+    }
+}
+
+/// Reset-safe error counters of a device backing a pool.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct PoolDeviceCounters {
+    pool: Arc<str>,
+    device: Arc<str>,
+    pub(crate) io_errors: MonotonicCounter,
+    pub(crate) resets: MonotonicCounter,
+    pub(crate) aborts: MonotonicCounter,
+}
+
+impl PoolDeviceCounters {
+    /// Create the counters of a device which was not tracked yet.
+    fn new(errors: &PoolDeviceErrors) -> Self {
+        Self {
+            pool: errors.pool.clone(),
+            device: errors.device.clone(),
+            io_errors: MonotonicCounter::default(),
+            resets: MonotonicCounter::default(),
+            aborts: MonotonicCounter::default(),
+        }
+    }
+
+    /// Account for new samples of the device error counters.
+    fn update(&mut self, errors: &PoolDeviceErrors) {
+        self.pool = errors.pool.clone();
+        self.io_errors.update(errors.stats.io_errors());
+        self.resets.update(errors.stats.resets());
+        self.aborts.update(errors.stats.aborts());
+    }
+
+    /// Get name of the pool the device backs.
+    pub(crate) fn pool(&self) -> &str {
+        &self.pool
+    }
+
+    /// Get name of the device, i.e. of its bdev.
+    pub(crate) fn device(&self) -> &str {
+        &self.device
+    }
+}
+
+/// Error counters of the pool devices of a node stored in the cache, keyed by the device name.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub(crate) struct PoolDeviceErrorsCache {
+    devices: BTreeMap<Arc<str>, PoolDeviceCounters>,
+}
+
+impl PoolDeviceErrorsCache {
+    /// Get an iterator over the cached device error counters.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &PoolDeviceCounters> {
+        self.devices.values()
+    }
+}
+
+impl ResourceOps for PoolDeviceErrorsCache {
+    type ResourceVec = Vec<PoolDeviceErrors>;
+
+    /// Update the counters of the devices backing the pools, and evict the counters of the
+    /// devices whose pools were deleted since the last refresh.
+    fn set(&mut self, val: Self::ResourceVec) {
+        let mut previous = std::mem::take(&mut self.devices);
+        for errors in val {
+            let mut counters = previous
+                .remove(&errors.device)
+                .unwrap_or_else(|| PoolDeviceCounters::new(&errors));
+            counters.update(&errors);
+            self.devices.insert(errors.device.clone(), counters);
+        }
+    }
+}
+
+/// Match the bdevs with the disks of the pools they back.
+fn pool_devices(pools: &PoolCache, stats: &[BdevErrorStats]) -> Vec<PoolDeviceErrors> {
+    pools
+        .iter()
+        .flat_map(|pool| {
+            pool.disks().split(',').filter_map(move |disk| {
+                stats
+                    .iter()
+                    .find(|bdev| bdev.backs_disk(disk))
+                    .map(|bdev| PoolDeviceErrors {
+                        pool: pool.name().clone(),
+                        device: bdev.name().into(),
+                        stats: bdev.clone(),
+                    })
+            })
+        })
+        .collect()
+}
+
+/// To store the error counters of the devices backing the pools in cache. The pools must have
+/// been stored beforehand, as the devices are labelled with their pool.
+pub(crate) async fn store_pool_device_errors_data(client: GrpcClient) -> Result<(), ()> {
+    let stats = client
+        .with_deadline(
+            RefreshedResource::PoolDeviceErrors,
+            client.list_bdev_error_stats(),
+        )
+        .await;
+    let mut cache = Cache::lock();
+    let errors_cache = cache.deref_mut();
+    let node_name = client.node_name();
+    match stats {
+        Ok(stats) => {
+            debug!(node.name = %node_name, "Updated pool device errors cache with latest counters");
+            let devices = errors_cache
+                .nodes()
+                .get(node_name)
+                .map(|data| pool_devices(data.pools(), &stats))
+                .unwrap_or_default();
+            let devices = errors_cache.intern(devices);
+            errors_cache.pool_device_errors_mut(node_name).set(devices);
+        }
+        Err(error) => {
+            error!(?error, node.name = %node_name, "Error getting bdev error stats");
+            return Err(());
+        }
+    };
+    Ok(())
+}
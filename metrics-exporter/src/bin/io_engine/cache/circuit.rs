@@ -0,0 +1,104 @@
+use once_cell::sync::Lazy;
+use prometheus::{IntGaugeVec, Opts};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Whether the circuit breaker of the refreshes of the io-engine data is open, per node.
+pub(crate) static CIRCUIT_OPEN: Lazy<IntGaugeVec> = Lazy::new(|| {
+    IntGaugeVec::new(
+        Opts::new(
+            "exporter_circuit_open",
+            "Whether the refreshes of the io-engine data are suspended after consecutive failures",
+        ),
+        &["node"],
+    )
+    .expect("Unable to create gauge metric type for exporter_circuit_open")
+});
+
+/// State of the circuit breaker.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    /// The refreshes are made.
+    Closed,
+    /// The refreshes are suspended until the next probe.
+    Open { probe_at: Instant },
+    /// A single refresh is made to probe whether the io-engine recovered.
+    HalfOpen,
+}
+
+/// Circuit breaker of the refreshes of the io-engine data of a node. It opens after consecutive
+/// failed refreshes, suspending them so that retries don't add load to a distressed io-engine,
+/// while the cached data is served as is. Once open, a single refresh is made every probe
+/// interval, which closes it again on success.
+pub(crate) struct CircuitBreaker {
+    node_name: String,
+    state: State,
+    consecutive_failures: u32,
+}
+
+impl CircuitBreaker {
+    /// Create a closed circuit breaker for the given node.
+    pub(crate) fn new(node_name: &str) -> Self {
+        CIRCUIT_OPEN.with_label_values(&[node_name]).set(0);
+        Self {
+            node_name: node_name.to_string(),
+            state: State::Closed,
+            consecutive_failures: 0,
+        }
+    }
+
+    /// Whether a refresh should be made now, the first one after the probe interval elapsed is
+    /// the probe.
+    pub(crate) fn allow(&mut self) -> bool {
+        match self.state {
+            State::Closed | State::HalfOpen => true,
+            State::Open { probe_at } if Instant::now() >= probe_at => {
+                self.state = State::HalfOpen;
+                true
+            }
+            State::Open { .. } => false,
+        }
+    }
+
+    /// Record the outcome of the refresh, opening the circuit once the failures reach the
+    /// threshold, or when the probe failed.
+    pub(crate) fn record(&mut self, success: bool, threshold: u32, probe_interval: Duration) {
+        if success {
+            if self.state != State::Closed {
+                info!(node.name = %self.node_name, "Refreshes succeed again, closing the circuit");
+            }
+            self.state = State::Closed;
+            self.consecutive_failures = 0;
+        } else {
+            self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+            let open = match self.state {
+                State::HalfOpen => true,
+                _ => self.consecutive_failures >= threshold.max(1),
+            };
+            if open {
+                if self.state == State::Closed {
+                    warn!(
+                        node.name = %self.node_name,
+                        failures = self.consecutive_failures,
+                        "Refreshes keep failing, opening the circuit and serving the cached data"
+                    );
+                }
+                self.state = State::Open {
+                    probe_at: Instant::now() + probe_interval,
+                };
+            }
+        }
+        let open = matches!(self.state, State::Open { .. });
+        CIRCUIT_OPEN
+            .with_label_values(&[&self.node_name])
+            .set(open as i64);
+    }
+
+    /// The time until the next probe, if the circuit is open.
+    pub(crate) fn until_probe(&self) -> Option<Duration> {
+        match self.state {
+            State::Open { probe_at } => Some(probe_at.saturating_duration_since(Instant::now())),
+            _ => None,
+        }
+    }
+}
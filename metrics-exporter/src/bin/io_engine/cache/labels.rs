@@ -0,0 +1,42 @@
+use std::{collections::HashSet, sync::Arc};
+
+/// Interns the label values of the cached resources, eg: the node, pool and replica names, so
+/// that a label value is allocated once and shared by every resource and metric which uses it,
+/// rather than cloned per metric per scrape.
+#[derive(Debug, Default)]
+pub(crate) struct LabelInterner {
+    labels: HashSet<Arc<str>>,
+}
+
+impl LabelInterner {
+    /// Get the interned label value equal to the given one, interning it if it is new.
+    pub(crate) fn intern(&mut self, value: &str) -> Arc<str> {
+        if let Some(label) = self.labels.get(value) {
+            return label.clone();
+        }
+        let label = Arc::<str>::from(value);
+        self.labels.insert(label.clone());
+        label
+    }
+
+    /// Replace the label value with its interned one, so that the duplicate is freed.
+    pub(crate) fn intern_in_place(&mut self, value: &mut Arc<str>) {
+        match self.labels.get(value.as_ref()) {
+            Some(label) => *value = label.clone(),
+            None => {
+                self.labels.insert(value.clone());
+            }
+        }
+    }
+
+    /// Drop the label values which are no longer used by any cached resource.
+    pub(crate) fn prune(&mut self) {
+        self.labels.retain(|label| Arc::strong_count(label) > 1);
+    }
+}
+
+/// Implemented by the cached resources whose label values are interned.
+pub(crate) trait InternLabels {
+    /// Replace the label values of the resource with their interned ones.
+    fn intern_labels(&mut self, labels: &mut LabelInterner);
+}
@@ -1,32 +1,70 @@
+mod bdev;
+mod circuit;
+mod labels;
 mod pool;
+mod refresh;
+mod stats;
+mod volume;
+
+pub(crate) use bdev::{PoolDeviceCounters, PoolDeviceErrorsCache};
+pub(crate) use circuit::CIRCUIT_OPEN;
+pub(crate) use labels::{InternLabels, LabelInterner};
+pub(crate) use pool::{PoolCache, POOL_RESOURCES_CACHED};
+pub(crate) use refresh::REFRESH_INTERVAL_SECONDS;
+pub(crate) use stats::{MonotonicCounter, ReplicaCounters, ReplicaStatsCache};
+pub(crate) use volume::{VolumeCache, VOLUME_RESOURCES_CACHED};
 
 use crate::{
-    client::{grpc_client::GrpcClient, pool::Pools},
+    capabilities::is_enabled_for,
+    client::grpc_client::{GrpcClient, GrpcContext},
     ExporterConfig,
 };
 
+use once_cell::sync::Lazy;
 use once_cell::sync::OnceCell;
+use prometheus::IntCounter;
 use serde::{Deserialize, Serialize};
-use std::sync::Mutex;
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
 use tokio::time::sleep;
+use tracing::{error, warn};
 static CACHE: OnceCell<Mutex<Cache>> = OnceCell::new();
 
+/// Counts the cache lock acquisitions which recovered from a poisoned lock.
+pub(crate) static CACHE_POISON_RECOVERIES: Lazy<IntCounter> = Lazy::new(|| {
+    IntCounter::new(
+        "exporter_cache_poison_recoveries_total",
+        "Number of times the cache lock was recovered after a panic while it was held",
+    )
+    .expect("Unable to create counter metric type for exporter_cache_poison_recoveries_total")
+});
+
 /// Trait to be implemented by all Resource structs stored in Cache.
 trait ResourceOps {
     type ResourceVec;
     fn set(&mut self, val: Self::ResourceVec);
-    fn invalidate(&mut self);
 }
 
 /// Cache to store data that has to be exposed though metrics-exporter.
+/// The data is kept per io-engine node, keyed by the node name.
 pub(crate) struct Cache {
-    data: Data,
+    nodes: BTreeMap<Arc<str>, Data>,
+    /// Label values shared by the cached resources of all the nodes.
+    labels: LabelInterner,
 }
 
 impl Cache {
     /// Initialize the cache with default value.
-    pub fn initialize(data: Data) {
-        CACHE.get_or_init(|| Mutex::new(Self { data }));
+    pub fn initialize() {
+        CACHE.get_or_init(|| {
+            Mutex::new(Self {
+                nodes: BTreeMap::new(),
+                labels: LabelInterner::default(),
+            })
+        });
     }
 
     /// Returns cache.
@@ -34,9 +72,86 @@ impl Cache {
         CACHE.get().expect("Cache is not initialized")
     }
 
-    /// Get pool mutably stored in struct.
-    pub fn pool_mut(&mut self) -> &mut Pools {
-        &mut self.data.pools
+    /// Lock the cache. If a panic occurred while the lock was held, the lock is recovered
+    /// rather than failing, as the cache is fully refreshed on every poll anyway.
+    pub fn lock() -> MutexGuard<'static, Cache> {
+        Cache::get_cache().lock().unwrap_or_else(|poisoned| {
+            warn!("Recovering cache from poisoned lock");
+            CACHE_POISON_RECOVERIES.inc();
+            poisoned.into_inner()
+        })
+    }
+
+    /// Get the data of the given node mutably, registering the node if it is new.
+    fn data_mut(&mut self, node_name: &str) -> &mut Data {
+        if !self.nodes.contains_key(node_name) {
+            let node_name = self.labels.intern(node_name);
+            self.nodes.insert(node_name, Data::new());
+        }
+        self.nodes
+            .get_mut(node_name)
+            .expect("The node data was registered above")
+    }
+
+    /// Get pool of the given node mutably stored in struct.
+    pub fn pool_mut(&mut self, node_name: &str) -> &mut PoolCache {
+        &mut self.data_mut(node_name).pools
+    }
+
+    /// Get volume of the given node mutably stored in struct.
+    pub fn volume_mut(&mut self, node_name: &str) -> &mut VolumeCache {
+        &mut self.data_mut(node_name).volumes
+    }
+
+    /// Get replica stats counters of the given node mutably stored in struct.
+    pub fn replica_stats_mut(&mut self, node_name: &str) -> &mut ReplicaStatsCache {
+        &mut self.data_mut(node_name).replica_stats
+    }
+
+    /// Get pool device error counters of the given node mutably stored in struct.
+    pub fn pool_device_errors_mut(&mut self, node_name: &str) -> &mut PoolDeviceErrorsCache {
+        &mut self.data_mut(node_name).pool_device_errors
+    }
+
+    /// Get the cached data of all nodes.
+    pub fn nodes(&self) -> &BTreeMap<Arc<str>, Data> {
+        &self.nodes
+    }
+
+    /// Replace the label values of the resources with the interned ones, before they are stored
+    /// in the cache.
+    pub fn intern<R: InternLabels>(&mut self, mut resources: Vec<R>) -> Vec<R> {
+        for resource in resources.iter_mut() {
+            resource.intern_labels(&mut self.labels);
+        }
+        resources
+    }
+
+    /// Drop the interned label values which are no longer used by any cached resource.
+    pub fn prune_labels(&mut self) {
+        self.labels.prune();
+    }
+
+    /// Register a node whose data is expected to be stored in the cache.
+    fn register_node(&mut self, node_name: &str) {
+        self.data_mut(node_name);
+    }
+
+    /// Mark the data of the given node as refreshed.
+    pub fn set_refreshed(&mut self, node_name: &str) {
+        self.data_mut(node_name).last_refreshed = Some(Instant::now());
+    }
+
+    /// Get the nodes whose data has not been refreshed within the given duration.
+    pub fn stale_nodes(&self, max_age: Duration) -> Vec<&str> {
+        self.nodes
+            .iter()
+            .filter(|(_, data)| {
+                data.last_refreshed
+                    .map_or(true, |refreshed| refreshed.elapsed() > max_age)
+            })
+            .map(|(node_name, _)| node_name.as_ref())
+            .collect()
     }
 }
 
@@ -44,7 +159,16 @@ impl Cache {
 #[derive(Serialize, Deserialize, Debug)]
 pub(crate) struct Data {
     /// Contains Pool Capacity and state data.
-    pools: Pools,
+    pools: PoolCache,
+    /// Contains Volume capacity data.
+    volumes: VolumeCache,
+    /// Contains the reset-safe replica io counters.
+    replica_stats: ReplicaStatsCache,
+    /// Contains the reset-safe error counters of the devices backing the pools.
+    pool_device_errors: PoolDeviceErrorsCache,
+    /// Time at which the data was last refreshed from the io-engine.
+    #[serde(skip)]
+    last_refreshed: Option<Instant>,
 }
 
 impl Default for Data {
@@ -57,22 +181,107 @@ impl Data {
     /// Constructor for Cache data.
     fn new() -> Self {
         Self {
-            pools: Pools { pools: vec![] },
+            pools: PoolCache::default(),
+            volumes: VolumeCache::default(),
+            replica_stats: ReplicaStatsCache::default(),
+            pool_device_errors: PoolDeviceErrorsCache::default(),
+            last_refreshed: None,
         }
     }
+
+    /// Get pools stored in struct.
+    pub fn pools(&self) -> &PoolCache {
+        &self.pools
+    }
+
+    /// Get volumes stored in struct.
+    pub fn volumes(&self) -> &VolumeCache {
+        &self.volumes
+    }
+
+    /// Get replica stats counters stored in struct.
+    pub fn replica_stats(&self) -> &ReplicaStatsCache {
+        &self.replica_stats
+    }
+
+    /// Get pool device error counters stored in struct.
+    pub fn pool_device_errors(&self) -> &PoolDeviceErrorsCache {
+        &self.pool_device_errors
+    }
 }
 
-/// To store data in shared variable i.e cache.
-pub(crate) async fn store_data(client: GrpcClient) {
-    tokio::spawn(async move {
-        store_resource_data(client).await;
-    });
+/// To store data in shared variable i.e cache, one task per io-engine node.
+pub(crate) async fn store_data(contexts: Vec<GrpcContext>) {
+    let mut cache = Cache::lock();
+    contexts
+        .iter()
+        .for_each(|context| cache.register_node(context.node_name()));
+    drop(cache);
+    for context in contexts {
+        tokio::spawn(async move {
+            match GrpcClient::new(context).await {
+                Ok(client) => store_resource_data(client).await,
+                Err(error) => error!(?error, "Failed to initialize grpc client"),
+            }
+        });
+    }
 }
 
-/// To store pools related data in cache.
-async fn store_resource_data(client: GrpcClient) {
+/// To store pools and volumes related data in cache.
+/// The refresh interval adapts to the rate of change of the pools and volumes, failed refreshes
+/// are treated as changes so that the data is refreshed again soon. Once the refreshes keep
+/// failing, they are suspended by the circuit breaker, other than for a periodic probe. After a
+/// failed refresh the health of the channel to the io-engine is checked, as it may have been
+/// restarted, and its api version detected again, as it may have been restarted with another
+/// one.
+async fn store_resource_data(mut client: GrpcClient) {
+    let mut interval = refresh::AdaptiveInterval::new();
+    let mut circuit = circuit::CircuitBreaker::new(client.node_name());
     loop {
-        let _ = pool::store_pool_info_data(client.clone()).await;
-        sleep(ExporterConfig::get_config().polling_time()).await;
+        let config = ExporterConfig::get_config();
+        if !circuit.allow() {
+            sleep(circuit.until_probe().unwrap_or(config.polling_time())).await;
+            continue;
+        }
+        let changed = refresh_resource_data(&client).await;
+        if changed.is_err() {
+            client.check_health().await;
+        }
+        circuit.record(
+            changed.is_ok(),
+            config.circuit_failure_threshold(),
+            config.circuit_probe_interval(),
+        );
+        let next = match circuit.until_probe() {
+            Some(until_probe) => until_probe,
+            None => interval.next(
+                changed.unwrap_or(true),
+                config.polling_time(),
+                config.min_polling_time(),
+                config.max_polling_time(),
+            ),
+        };
+        REFRESH_INTERVAL_SECONDS
+            .with_label_values(&[client.node_name()])
+            .set(next.as_secs_f64());
+        sleep(next).await;
+    }
+}
+
+/// Refresh the cached data of the node. Returns whether the pools or volumes changed, or an
+/// error if the pools could not be refreshed, as the io-engine is then unlikely to respond at
+/// all. Each resource is refreshed within its own request timeout, independently of the others:
+/// a resource which fails to refresh, eg: because it timed out, keeps its cached data and counts
+/// as changed, so that it is refreshed again soon.
+async fn refresh_resource_data(client: &GrpcClient) -> Result<bool, ()> {
+    let pools = pool::store_pool_info_data(client.clone()).await;
+    let volumes = volume::store_volume_info_data(client.clone()).await;
+    if is_enabled_for("replica_stats", client.served_api_versions()) {
+        let _ = stats::store_replica_stats_data(client.clone()).await;
+    }
+    if is_enabled_for("pool_device_errors", client.served_api_versions()) {
+        let _ = bdev::store_pool_device_errors_data(client.clone()).await;
     }
+    Cache::lock().prune_labels();
+    Ok(pools? || volumes.unwrap_or(true))
 }
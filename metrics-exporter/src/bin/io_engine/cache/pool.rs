@@ -1,46 +1,85 @@
 use super::{Cache, ResourceOps};
 use crate::client::{
-    grpc_client::GrpcClient,
-    pool::{PoolInfo, PoolOperations, Pools},
+    grpc_client::{GrpcClient, RefreshedResource},
+    pool::{PoolInfo, PoolOperations},
 };
-use std::ops::DerefMut;
+use once_cell::sync::Lazy;
+use prometheus::IntGauge;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, ops::DerefMut, sync::Arc};
 use tracing::{debug, error};
 
-impl ResourceOps for Pools {
+/// Number of pools currently stored in the cache, across all nodes.
+pub(crate) static POOL_RESOURCES_CACHED: Lazy<IntGauge> = Lazy::new(|| {
+    IntGauge::new(
+        "exporter_pool_resources_cached",
+        "Number of pools currently stored in the cache",
+    )
+    .expect("Unable to create gauge metric type for exporter_pool_resources_cached")
+});
+
+/// Pools of a node stored in the cache, keyed by the pool name.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub(crate) struct PoolCache {
+    pools: BTreeMap<Arc<str>, PoolInfo>,
+}
+
+impl PoolCache {
+    /// Get an iterator over the cached pools.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &PoolInfo> {
+        self.pools.values()
+    }
+
+    /// Get the number of cached pools.
+    pub(crate) fn len(&self) -> usize {
+        self.pools.len()
+    }
+}
+
+impl ResourceOps for PoolCache {
     type ResourceVec = Vec<PoolInfo>;
 
+    /// Replace the cached pools with the ones listed by the io-engine, which evicts the pools
+    /// which were deleted since the last refresh.
     fn set(&mut self, val: Self::ResourceVec) {
         self.pools = val
-    }
-
-    fn invalidate(&mut self) {
-        self.pools = vec![]
+            .into_iter()
+            .map(|pool| (pool.name().clone(), pool))
+            .collect()
     }
 }
 
-/// To store pools state and capacity data in cache.
-pub(crate) async fn store_pool_info_data(client: GrpcClient) -> Result<(), ()> {
-    let pools = client.list_pools().await;
-    let mut cache = match Cache::get_cache().lock() {
-        Ok(cache) => cache,
-        Err(error) => {
-            error!(%error, "Error while getting cache resource");
-            return Err(());
-        }
-    };
+/// To store pools state and capacity data in cache. Returns whether the cached pools changed.
+pub(crate) async fn store_pool_info_data(client: GrpcClient) -> Result<bool, ()> {
+    let pools = client
+        .with_deadline(RefreshedResource::Pools, client.list_pools())
+        .await;
+    let mut cache = Cache::lock();
     let pools_cache = cache.deref_mut();
-    match pools {
+    let node_name = client.node_name();
+    let result = match pools {
         // set pools in the cache
         Ok(pools) => {
-            debug!("Updated pool cache with latest metrics");
-            pools_cache.pool_mut().set(pools.pools)
+            debug!(node.name = %node_name, "Updated pool cache with latest metrics");
+            let pools = pools_cache.intern(pools.pools);
+            let pool_cache = pools_cache.pool_mut(node_name);
+            let previous = std::mem::take(pool_cache);
+            pool_cache.set(pools);
+            let changed = *pool_cache != previous;
+            pools_cache.set_refreshed(node_name);
+            Ok(changed)
         }
-        // invalidate cache in case of error
+        // keep the cached pools in case of error, they are served as stale data
         Err(error) => {
-            error!(?error, "Error getting pools data, invalidating pools cache");
-            pools_cache.pool_mut().invalidate();
-            return Err(());
+            error!(?error, node.name = %node_name, "Error getting pools data, keeping pools cache");
+            Err(())
         }
     };
-    Ok(())
+    let cached = pools_cache
+        .nodes()
+        .values()
+        .map(|data| data.pools().len())
+        .sum::<usize>();
+    POOL_RESOURCES_CACHED.set(cached as i64);
+    result
 }
@@ -0,0 +1,47 @@
+use once_cell::sync::Lazy;
+use prometheus::{GaugeVec, Opts};
+use std::time::Duration;
+
+/// Current interval between the refreshes of the io-engine data, per node.
+pub(crate) static REFRESH_INTERVAL_SECONDS: Lazy<GaugeVec> = Lazy::new(|| {
+    GaugeVec::new(
+        Opts::new(
+            "exporter_refresh_interval_seconds",
+            "Current interval between the refreshes of the io-engine data",
+        ),
+        &["node"],
+    )
+    .expect("Unable to create gauge metric type for exporter_refresh_interval_seconds")
+});
+
+/// Refresh interval which adapts to the rate of change of the io-engine data. It is doubled
+/// every time a refresh returns identical data, and reset to the minimum as soon as the data
+/// changes or the refresh fails, always staying within the min/max bounds.
+pub(crate) struct AdaptiveInterval {
+    current: Option<Duration>,
+}
+
+impl AdaptiveInterval {
+    /// Create an interval which starts from the polling time.
+    pub(crate) fn new() -> Self {
+        Self { current: None }
+    }
+
+    /// Get the interval until the next refresh, given whether the last refresh changed the data.
+    pub(crate) fn next(
+        &mut self,
+        changed: bool,
+        polling_time: Duration,
+        min: Duration,
+        max: Duration,
+    ) -> Duration {
+        let next = match self.current {
+            None => polling_time,
+            Some(_) if changed => min,
+            Some(current) => current.saturating_mul(2),
+        };
+        let next = next.clamp(min, max.max(min));
+        self.current = Some(next);
+        next
+    }
+}
@@ -0,0 +1,128 @@
+use super::{Cache, ResourceOps};
+use crate::client::{
+    grpc_client::{GrpcClient, RefreshedResource},
+    stats::{ReplicaStats, StatsOperations},
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, ops::DerefMut, sync::Arc};
+use tracing::{debug, error};
+
+/// A counter which keeps increasing across resets of the sampled cumulative counter, eg: when
+/// the io-engine restarts.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy)]
+pub(crate) struct MonotonicCounter {
+    /// Last sample of the cumulative counter.
+    last_sample: u64,
+    /// Total accumulated across resets.
+    total: u64,
+}
+
+impl MonotonicCounter {
+    /// Account for a new sample of the cumulative counter. A sample lower than the previous one
+    /// means the counter was reset, in which case the whole sample is accounted as the delta.
+    pub(super) fn update(&mut self, sample: u64) {
+        let delta = sample.checked_sub(self.last_sample).unwrap_or(sample);
+        self.total = self.total.saturating_add(delta);
+        self.last_sample = sample;
+    }
+
+    /// Get the total accumulated across resets.
+    pub(crate) fn total(&self) -> u64 {
+        self.total
+    }
+}
+
+/// Reset-safe io counters of a replica.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct ReplicaCounters {
+    uuid: Arc<str>,
+    pool: Arc<str>,
+    pub(crate) num_read_ops: MonotonicCounter,
+    pub(crate) num_write_ops: MonotonicCounter,
+    pub(crate) bytes_read: MonotonicCounter,
+    pub(crate) bytes_written: MonotonicCounter,
+}
+
+impl ReplicaCounters {
+    /// Create the counters of a replica which was not tracked yet.
+    fn new(stats: &ReplicaStats) -> Self {
+        Self {
+            uuid: stats.uuid().clone(),
+            pool: stats.pool().clone(),
+            num_read_ops: MonotonicCounter::default(),
+            num_write_ops: MonotonicCounter::default(),
+            bytes_read: MonotonicCounter::default(),
+            bytes_written: MonotonicCounter::default(),
+        }
+    }
+
+    /// Account for new samples of the replica stats.
+    fn update(&mut self, stats: &ReplicaStats) {
+        self.num_read_ops.update(stats.num_read_ops());
+        self.num_write_ops.update(stats.num_write_ops());
+        self.bytes_read.update(stats.bytes_read());
+        self.bytes_written.update(stats.bytes_written());
+    }
+
+    /// Get uuid of the replica.
+    pub(crate) fn uuid(&self) -> &str {
+        &self.uuid
+    }
+
+    /// Get name of the pool the replica is on.
+    pub(crate) fn pool(&self) -> &str {
+        &self.pool
+    }
+}
+
+/// Replica counters of a node stored in the cache, keyed by the replica uuid.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub(crate) struct ReplicaStatsCache {
+    replicas: BTreeMap<Arc<str>, ReplicaCounters>,
+}
+
+impl ReplicaStatsCache {
+    /// Get an iterator over the cached replica counters.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &ReplicaCounters> {
+        self.replicas.values()
+    }
+}
+
+impl ResourceOps for ReplicaStatsCache {
+    type ResourceVec = Vec<ReplicaStats>;
+
+    /// Update the counters of the replicas listed by the io-engine, and evict the counters of the
+    /// replicas which were deleted since the last refresh.
+    fn set(&mut self, val: Self::ResourceVec) {
+        let mut previous = std::mem::take(&mut self.replicas);
+        for stats in val {
+            let mut counters = previous
+                .remove(stats.uuid())
+                .unwrap_or_else(|| ReplicaCounters::new(&stats));
+            counters.update(&stats);
+            self.replicas.insert(stats.uuid().clone(), counters);
+        }
+    }
+}
+
+/// To store replica stats counters in cache.
+pub(crate) async fn store_replica_stats_data(client: GrpcClient) -> Result<(), ()> {
+    let stats = client
+        .with_deadline(RefreshedResource::ReplicaStats, client.list_replica_stats())
+        .await;
+    let mut cache = Cache::lock();
+    let stats_cache = cache.deref_mut();
+    let node_name = client.node_name();
+    match stats {
+        Ok(stats) => {
+            debug!(node.name = %node_name, "Updated replica stats cache with latest counters");
+            let replicas = stats_cache.intern(stats.replicas);
+            stats_cache.replica_stats_mut(node_name).set(replicas);
+        }
+        Err(error) => {
+            error!(?error, node.name = %node_name, "Error getting replica stats");
+            return Err(());
+        }
+    };
+    Ok(())
+}
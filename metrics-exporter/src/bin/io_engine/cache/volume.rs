@@ -0,0 +1,85 @@
+use super::{Cache, ResourceOps};
+use crate::client::{
+    grpc_client::{GrpcClient, RefreshedResource},
+    volume::{VolumeInfo, VolumeOperations},
+};
+use once_cell::sync::Lazy;
+use prometheus::IntGauge;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, ops::DerefMut, sync::Arc};
+use tracing::{debug, error};
+
+/// Number of volumes currently stored in the cache, across all nodes.
+pub(crate) static VOLUME_RESOURCES_CACHED: Lazy<IntGauge> = Lazy::new(|| {
+    IntGauge::new(
+        "exporter_volume_resources_cached",
+        "Number of volumes currently stored in the cache",
+    )
+    .expect("Unable to create gauge metric type for exporter_volume_resources_cached")
+});
+
+/// Volumes of a node stored in the cache, keyed by the volume uuid.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub(crate) struct VolumeCache {
+    volumes: BTreeMap<Arc<str>, VolumeInfo>,
+}
+
+impl VolumeCache {
+    /// Get an iterator over the cached volumes.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &VolumeInfo> {
+        self.volumes.values()
+    }
+
+    /// Get the number of cached volumes.
+    pub(crate) fn len(&self) -> usize {
+        self.volumes.len()
+    }
+}
+
+impl ResourceOps for VolumeCache {
+    type ResourceVec = Vec<VolumeInfo>;
+
+    /// Replace the cached volumes with the ones listed by the io-engine, which evicts the volumes
+    /// which were deleted since the last refresh.
+    fn set(&mut self, val: Self::ResourceVec) {
+        self.volumes = val
+            .into_iter()
+            .map(|volume| (volume.uuid().clone(), volume))
+            .collect()
+    }
+}
+
+/// To store volumes capacity data in cache. Returns whether the cached volumes changed.
+pub(crate) async fn store_volume_info_data(client: GrpcClient) -> Result<bool, ()> {
+    let volumes = client
+        .with_deadline(RefreshedResource::Volumes, client.list_volumes())
+        .await;
+    let mut cache = Cache::lock();
+    let volumes_cache = cache.deref_mut();
+    let node_name = client.node_name();
+    let result = match volumes {
+        // set volumes in the cache
+        Ok(volumes) => {
+            debug!(node.name = %node_name, "Updated volume cache with latest metrics");
+            let volumes = volumes_cache.intern(volumes.volumes);
+            let volume_cache = volumes_cache.volume_mut(node_name);
+            let previous = std::mem::take(volume_cache);
+            volume_cache.set(volumes);
+            let changed = *volume_cache != previous;
+            volumes_cache.set_refreshed(node_name);
+            Ok(changed)
+        }
+        // keep the cached volumes in case of error, they are served as stale data
+        Err(error) => {
+            error!(?error, node.name = %node_name, "Error getting volumes data, keeping volumes cache");
+            Err(())
+        }
+    };
+    let cached = volumes_cache
+        .nodes()
+        .values()
+        .map(|data| data.volumes().len())
+        .sum::<usize>();
+    VOLUME_RESOURCES_CACHED.set(cached as i64);
+    result
+}
@@ -0,0 +1,243 @@
+use crate::{client::ApiVersion, config::ExporterConfig};
+use once_cell::sync::Lazy;
+use prometheus::{IntGaugeVec, Opts};
+use serde::Serialize;
+use std::{collections::BTreeMap, sync::RwLock};
+
+/// Requirement of a collector on top of a supported io-engine api version.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Requirement {
+    /// The collector is enabled through `--v0-stats`.
+    V0Stats,
+}
+
+impl Requirement {
+    /// Whether the requirement is met by the exporter configuration.
+    fn is_met(&self) -> bool {
+        match self {
+            Self::V0Stats => ExporterConfig::get_config().v0_stats(),
+        }
+    }
+}
+
+/// The metric families exported by a collector, and the io-engine api versions and rpcs they are
+/// fetched with.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CollectorCapability {
+    /// Name of the collector.
+    collector: &'static str,
+    /// Metric families of the collector, without the metric prefix.
+    families: &'static [&'static str],
+    /// The io-engine rpcs the metrics are fetched with.
+    rpcs: &'static [&'static str],
+    /// The io-engine api versions which serve the rpcs.
+    api_versions: &'static [ApiVersion],
+    /// Requirement of the collector on top of a supported api version, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    requires: Option<Requirement>,
+}
+
+impl CollectorCapability {
+    /// Whether the collector is supported by the api version and its requirement, if any, is met.
+    fn is_enabled(&self, api_version: &ApiVersion) -> bool {
+        self.api_versions.contains(api_version)
+            && self.requires.as_ref().map_or(true, Requirement::is_met)
+    }
+
+    /// Whether the collector is enabled for any of the api versions.
+    fn is_enabled_for_any(&self, api_versions: &[ApiVersion]) -> bool {
+        api_versions
+            .iter()
+            .any(|api_version| self.is_enabled(api_version))
+    }
+}
+
+/// Matrix of the metric families of every collector and the io-engine api versions they require.
+/// The collectors whose api versions don't include the one of the io-engine are not run, and
+/// their data isn't fetched, rather than exporting empty metric families.
+static CAPABILITY_MATRIX: [CollectorCapability; 6] = [
+    CollectorCapability {
+        collector: "pool_capacity",
+        families: &[
+            "disk_pool_total_size_bytes",
+            "disk_pool_used_size_bytes",
+            "disk_pool_committed_size_bytes",
+        ],
+        rpcs: &["list_pools"],
+        api_versions: &[ApiVersion::V0, ApiVersion::V1],
+        requires: None,
+    },
+    CollectorCapability {
+        collector: "pool_status",
+        families: &["disk_pool_status"],
+        rpcs: &["list_pools"],
+        api_versions: &[ApiVersion::V0, ApiVersion::V1],
+        requires: None,
+    },
+    CollectorCapability {
+        collector: "pool_info",
+        families: &["io_engine_pool_info"],
+        rpcs: &["list_pools"],
+        api_versions: &[ApiVersion::V0, ApiVersion::V1],
+        requires: None,
+    },
+    CollectorCapability {
+        collector: "volume_capacity",
+        families: &[
+            "volume_allocated_size_bytes",
+            "volume_capacity_size_bytes",
+            "volume_snapshot_count",
+            "volume_oldest_snapshot_age_seconds",
+        ],
+        rpcs: &["list_replicas", "list_nexus", "list_snapshot"],
+        api_versions: &[ApiVersion::V1],
+        requires: None,
+    },
+    CollectorCapability {
+        collector: "replica_stats",
+        families: &[
+            "replica_num_read_ops_total",
+            "replica_num_write_ops_total",
+            "replica_bytes_read_total",
+            "replica_bytes_written_total",
+        ],
+        rpcs: &["stat_replicas"],
+        api_versions: &[ApiVersion::V0],
+        requires: Some(Requirement::V0Stats),
+    },
+    CollectorCapability {
+        collector: "pool_device_errors",
+        families: &[
+            "disk_pool_device_io_errors_total",
+            "disk_pool_device_resets_total",
+            "disk_pool_device_aborts_total",
+        ],
+        rpcs: &["bdev_get_iostat"],
+        api_versions: &[ApiVersion::V1],
+        requires: None,
+    },
+];
+
+/// The io-engine api versions the exporter scrapes with until they are detected.
+static DEFAULT_API_VERSIONS: RwLock<Vec<ApiVersion>> = RwLock::new(Vec::new());
+
+/// The io-engine api versions served by the io-engine of each node, as last detected. They
+/// differ between the nodes while the data-plane is upgraded.
+static NODE_API_VERSIONS: RwLock<BTreeMap<String, Vec<ApiVersion>>> = RwLock::new(BTreeMap::new());
+
+/// Whether the collector is enabled for the io-engine api version, so that the collectors which
+/// are disabled can be told apart from the ones which fail.
+pub(crate) static EXPORTER_COLLECTOR_ENABLED: Lazy<IntGaugeVec> = Lazy::new(|| {
+    IntGaugeVec::new(
+        Opts::new(
+            "exporter_collector_enabled",
+            "Whether the collector is enabled for the io-engine api version",
+        ),
+        &["collector", "api_version"],
+    )
+    .expect("Unable to create gauge metric type for exporter_collector_enabled")
+});
+
+/// Set the io-engine api version the exporter scrapes with until the api versions of the
+/// io-engines are detected, and the collector enabled gauges.
+pub(crate) fn initialize_capabilities(api_version: ApiVersion) {
+    if let Ok(mut default) = DEFAULT_API_VERSIONS.write() {
+        *default = vec![api_version];
+    }
+    set_collector_enabled();
+}
+
+/// Set the io-engine api versions detected for the node, and the collector enabled gauges.
+pub(crate) fn set_node_api_versions(node_name: &str, api_versions: &[ApiVersion]) {
+    if let Ok(mut nodes) = NODE_API_VERSIONS.write() {
+        nodes.insert(node_name.to_string(), api_versions.to_vec());
+    }
+    set_collector_enabled();
+}
+
+/// Set the collector enabled gauges for the api versions the exporter scrapes with.
+fn set_collector_enabled() {
+    EXPORTER_COLLECTOR_ENABLED.reset();
+    for api_version in api_versions() {
+        for capability in &CAPABILITY_MATRIX {
+            EXPORTER_COLLECTOR_ENABLED
+                .with_label_values(&[capability.collector, api_version.as_ref()])
+                .set(capability.is_enabled(&api_version).into());
+        }
+    }
+}
+
+/// Get the io-engine api versions the exporter scrapes with, from the latest one: those served
+/// by any of the io-engines, or the default ones until they are detected.
+fn api_versions() -> Vec<ApiVersion> {
+    let mut api_versions = NODE_API_VERSIONS
+        .read()
+        .map(|nodes| nodes.values().flatten().cloned().collect::<Vec<_>>())
+        .unwrap_or_default();
+    if api_versions.is_empty() {
+        api_versions = DEFAULT_API_VERSIONS
+            .read()
+            .map(|default| default.clone())
+            .unwrap_or_default();
+    }
+    api_versions.sort_by(|a, b| b.cmp(a));
+    api_versions.dedup();
+    api_versions
+}
+
+/// Whether the collector is enabled for any of the given io-engine api versions. Collectors
+/// which are not part of the matrix are always enabled.
+pub(crate) fn is_enabled_for(collector: &str, api_versions: &[ApiVersion]) -> bool {
+    CAPABILITY_MATRIX
+        .iter()
+        .find(|capability| capability.collector == collector)
+        .map_or(true, |capability| {
+            capability.is_enabled_for_any(api_versions)
+        })
+}
+
+/// Whether the collector is enabled for any of the io-engine api versions the exporter scrapes
+/// with, so that the metrics of the nodes on either api version are served during an upgrade.
+pub(crate) fn is_enabled(collector: &str) -> bool {
+    is_enabled_for(collector, &api_versions())
+}
+
+/// Capability of a collector, as served by the capabilities endpoint.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CollectorStatus {
+    #[serde(flatten)]
+    capability: &'static CollectorCapability,
+    enabled: bool,
+}
+
+/// Capabilities of the exporter, as served by the capabilities endpoint.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Capabilities {
+    /// The latest io-engine api version the exporter scrapes with.
+    api_version: ApiVersion,
+    /// The io-engine api versions the exporter scrapes with.
+    api_versions: Vec<ApiVersion>,
+    collectors: Vec<CollectorStatus>,
+}
+
+impl Capabilities {
+    /// Get the capabilities of the exporter for the io-engine api versions it scrapes with.
+    pub(crate) fn current() -> Self {
+        let api_versions = api_versions();
+        Self {
+            collectors: CAPABILITY_MATRIX
+                .iter()
+                .map(|capability| CollectorStatus {
+                    capability,
+                    enabled: capability.is_enabled_for_any(&api_versions),
+                })
+                .collect(),
+            api_version: api_versions.first().cloned().unwrap_or(ApiVersion::V0),
+            api_versions,
+        }
+    }
+}
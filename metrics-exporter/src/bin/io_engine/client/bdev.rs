@@ -0,0 +1,129 @@
+use crate::{client::grpc_client::GrpcClient, error::ExporterError};
+
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Json-rpc method returning the io stats of the bdevs.
+const BDEV_GET_IOSTAT: &str = "bdev_get_iostat";
+/// Io error status of the aborted I/Os.
+const ABORTED_STATUS: &str = "aborted";
+/// Io error status of the I/Os which were retried as the bdev was out of memory, which are not
+/// errors of the underlying device.
+const NOMEM_STATUS: &str = "nomem";
+
+/// This stores the cumulative error counters of a bdev, as reported by the io-engine.
+/// The counters are reset whenever the io-engine restarts.
+#[derive(Debug, Clone)]
+pub(crate) struct BdevErrorStats {
+    name: String,
+    io_errors: u64,
+    resets: u64,
+    aborts: u64,
+}
+
+impl BdevErrorStats {
+    /// Get name of the bdev.
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the number of failed I/Os.
+    pub(crate) fn io_errors(&self) -> u64 {
+        self.io_errors
+    }
+
+    /// Get the number of resets.
+    pub(crate) fn resets(&self) -> u64 {
+        self.resets
+    }
+
+    /// Get the number of aborted I/Os.
+    pub(crate) fn aborts(&self) -> u64 {
+        self.aborts
+    }
+
+    /// Check whether the bdev backs the given pool disk. The bdev of a disk is named after the
+    /// path of the disk uri, eg: `/dev/sdb` for `aio:///dev/sdb?uuid=..`, and nvme bdevs are
+    /// further suffixed with their namespace, eg: `0000:00:04.0n1` for `pcie:///0000:00:04.0`.
+    pub(crate) fn backs_disk(&self, disk: &str) -> bool {
+        let path = disk.split_once("://").map_or(disk, |(_, path)| path);
+        let path = path.split_once('?').map_or(path, |(path, _)| path);
+        self.name == path
+            || self
+                .name
+                .strip_prefix(path.trim_start_matches('/'))
+                .and_then(|suffix| suffix.strip_prefix('n'))
+                .map_or(false, |nsid| {
+                    !nsid.is_empty() && nsid.chars().all(|c| c.is_ascii_digit())
+                })
+    }
+}
+
+/// Io stats of the bdevs, as returned by `bdev_get_iostat`.
+#[derive(Deserialize, Debug)]
+struct BdevIoStats {
+    bdevs: Vec<BdevIoStat>,
+}
+
+/// Io stats of a bdev. The error counters are only reported by the bdev modules which track
+/// them, hence they default to 0.
+#[derive(Deserialize, Debug)]
+struct BdevIoStat {
+    name: String,
+    #[serde(default)]
+    num_reset_ops: u64,
+    #[serde(default)]
+    io_error: HashMap<String, u64>,
+}
+
+impl From<BdevIoStat> for BdevErrorStats {
+    fn from(value: BdevIoStat) -> Self {
+        Self {
+            aborts: value
+                .io_error
+                .get(ABORTED_STATUS)
+                .copied()
+                .unwrap_or_default(),
+            io_errors: value
+                .io_error
+                .iter()
+                .filter(|(status, _)| status.as_str() != NOMEM_STATUS)
+                .map(|(_, count)| count)
+                .sum(),
+            resets: value.num_reset_ops,
+            name: value.name,
+        }
+    }
+}
+
+/// Trait to be implemented by grpc client to call the bdev io stats json-rpc.
+#[tonic::async_trait]
+pub(crate) trait BdevOperations: Send + Sync + Sized {
+    async fn list_bdev_error_stats(&self) -> Result<Vec<BdevErrorStats>, ExporterError>;
+}
+
+#[tonic::async_trait]
+impl BdevOperations for GrpcClient {
+    async fn list_bdev_error_stats(&self) -> Result<Vec<BdevErrorStats>, ExporterError> {
+        // The bdev error stats are only tracked for the v1 api, which the io-engine may serve
+        // alongside the v0 api.
+        let result = match self
+            .client_v1()?
+            .json
+            .json_rpc_call(rpc::v1::json::JsonRpcRequest {
+                method: BDEV_GET_IOSTAT.to_string(),
+                params: "{}".to_string(),
+            })
+            .await
+        {
+            Ok(response) => {
+                self.record_success(BDEV_GET_IOSTAT);
+                response.into_inner().result
+            }
+            Err(error) => return Err(ExporterError::GrpcResponseError(error.to_string())),
+        };
+        let stats = serde_json::from_str::<BdevIoStats>(&result)
+            .map_err(|error| ExporterError::GrpcResponseError(error.to_string()))?;
+        Ok(stats.bdevs.into_iter().map(BdevErrorStats::from).collect())
+    }
+}
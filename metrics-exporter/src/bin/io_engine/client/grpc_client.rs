@@ -1,173 +1,393 @@
-use crate::{error::ExporterError, get_node_name, get_pod_ip, ApiVersion};
-use rpc::io_engine::IoEngineClientV0;
+use crate::{
+    capabilities::set_node_api_versions, error::ExporterError, get_node_name, get_pod_ip,
+    maintenance::suppresses_transitions, ApiVersion, ExporterConfig,
+};
+pub(crate) use io_engine_client::{GrpcContext, Timeouts};
+use io_engine_client::{MayaClientV0, MayaClientV1};
 
 use actix_web::http::Uri;
-use std::time::Duration;
+use once_cell::sync::Lazy;
+use prometheus::{GaugeVec, IntCounterVec, IntGaugeVec, Opts};
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 use tokio::time::sleep;
-use tonic::transport::Channel;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
-/// Timeout for gRPC.
+/// Unix timestamp of the last successful call of each gRPC method to the io-engine, per node.
+/// Unlike the resource metrics, which are served from the cache even when they are stale, this
+/// tells an io-engine which stopped responding from an exporter which stopped scraping.
+pub(crate) static LAST_SUCCESSFUL_RPC_TIMESTAMP_SECONDS: Lazy<GaugeVec> = Lazy::new(|| {
+    GaugeVec::new(
+        Opts::new(
+            "exporter_last_successful_rpc_timestamp_seconds",
+            "Unix timestamp of the last successful call of the gRPC method to the io-engine",
+        ),
+        &["node", "rpc"],
+    )
+    .expect("Unable to create gauge metric type for exporter_last_successful_rpc_timestamp_seconds")
+});
+
+/// The io-engine api versions detected for each node, set for the versions the node is scraped
+/// with, so that the nodes which are still on another api version, eg: during an upgrade, are
+/// told apart.
+pub(crate) static IO_ENGINE_API_VERSION: Lazy<IntGaugeVec> = Lazy::new(|| {
+    IntGaugeVec::new(
+        Opts::new(
+            "exporter_io_engine_api_version",
+            "Whether the io-engine of the node is scraped with the api version",
+        ),
+        &["node", "api_version"],
+    )
+    .expect("Unable to create gauge metric type for exporter_io_engine_api_version")
+});
+
+/// Counts the re-establishments of the channel to the io-engine of each node, eg: after the
+/// io-engine restarted and the previous channel stopped responding.
+pub(crate) static GRPC_RECONNECTS_TOTAL: Lazy<IntCounterVec> = Lazy::new(|| {
+    IntCounterVec::new(
+        Opts::new(
+            "exporter_grpc_reconnects_total",
+            "Number of times the channel to the io-engine was re-established",
+        ),
+        &["node"],
+    )
+    .expect("Unable to create counter metric type for exporter_grpc_reconnects_total")
+});
+
+/// Io-engine gRPC endpoint of a node, in the form `<node-name>=<host>:<port>`.
 #[derive(Debug, Clone)]
-pub struct Timeouts {
-    connect: Duration,
-    request: Duration,
+pub(crate) struct NodeEndpoint {
+    node_name: String,
+    authority: String,
 }
 
-impl Timeouts {
-    /// Return a new `Self` with the connect and request timeouts.
-    pub fn new(connect: Duration, request: Duration) -> Self {
-        Self { connect, request }
-    }
-    /// Timeout to establish connection to the node.
-    pub fn connect(&self) -> Duration {
-        self.connect
+impl FromStr for NodeEndpoint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('=') {
+            Some((node_name, authority)) if !node_name.is_empty() && !authority.is_empty() => {
+                Ok(Self {
+                    node_name: node_name.to_string(),
+                    authority: authority.to_string(),
+                })
+            }
+            _ => Err(format!(
+                "Invalid io-engine endpoint '{s}', expected <node-name>=<host>:<port>"
+            )),
+        }
     }
-    /// Timeout for the request itself.
-    pub fn request(&self) -> Duration {
-        self.request
+}
+
+/// Resources of the io-engine which are refreshed into the cache, each within its own request
+/// timeout.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    strum_macros::EnumString,
+    strum_macros::AsRefStr,
+)]
+#[strum(serialize_all = "snake_case")]
+pub(crate) enum RefreshedResource {
+    Pools,
+    Volumes,
+    ReplicaStats,
+    PoolDeviceErrors,
+}
+
+/// Request timeout of the refresh of a resource, in the form `<resource>=<duration>`.
+#[derive(Debug, Clone)]
+pub(crate) struct RequestTimeout {
+    resource: RefreshedResource,
+    timeout: Duration,
+}
+
+impl FromStr for RequestTimeout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((resource, timeout)) = s.split_once('=') else {
+            return Err(format!(
+                "Invalid request timeout '{s}', expected <resource>=<duration>"
+            ));
+        };
+        let resource = resource.parse().map_err(|_| {
+            format!(
+                "Invalid resource '{resource}', expected one of pools, volumes, replica_stats or \
+                pool_device_errors"
+            )
+        })?;
+        let timeout = humantime::parse_duration(timeout)
+            .map_err(|error| format!("Invalid request timeout '{timeout}': {error}"))?;
+        Ok(Self { resource, timeout })
     }
 }
 
-/// Context for Grpc client.
+/// Request timeouts of the refreshes of the resources, the default one applying to those which
+/// aren't given their own.
 #[derive(Debug, Clone)]
-pub(crate) struct GrpcContext {
-    endpoint: tonic::transport::Endpoint,
-    timeouts: Timeouts,
-    api_version: ApiVersion,
+pub(crate) struct RequestTimeouts {
+    default: Duration,
+    resources: BTreeMap<RefreshedResource, Duration>,
 }
 
-impl GrpcContext {
-    /// initialize context
-    pub fn new(endpoint: Uri, timeouts: Timeouts, api_version: ApiVersion) -> Self {
-        let endpoint = tonic::transport::Endpoint::from(endpoint)
-            .connect_timeout(timeouts.connect())
-            .timeout(timeouts.request());
+impl RequestTimeouts {
+    /// Return a new `Self` with the default request timeout and those of the resources.
+    pub(crate) fn new(default: Duration, resources: &[RequestTimeout]) -> Self {
         Self {
-            endpoint,
-            timeouts,
-            api_version,
+            default,
+            resources: resources
+                .iter()
+                .map(|timeout| (timeout.resource, timeout.timeout))
+                .collect(),
         }
     }
-}
-/// The V0 Mayastor client.
-type MayaClientV0 = IoEngineClientV0<Channel>;
 
-/// The V1 PoolClient.
-type PoolClient = rpc::v1::pool::pool_rpc_client::PoolRpcClient<Channel>;
+    /// Get the request timeout of the refresh of the resource.
+    pub(crate) fn get(&self, resource: RefreshedResource) -> Duration {
+        self.resources
+            .get(&resource)
+            .copied()
+            .unwrap_or(self.default)
+    }
 
-/// A wrapper for client for the V1 dataplane interface.
-#[derive(Clone, Debug)]
-pub(crate) struct MayaClientV1 {
-    pub(crate) pool: PoolClient,
+    /// Get the longest of the request timeouts.
+    pub(crate) fn max(&self) -> Duration {
+        self.resources
+            .values()
+            .copied()
+            .fold(self.default, Duration::max)
+    }
 }
 
-/// Dataplane grpc client.
+/// Dataplane grpc client, which records the successful calls of the exporter. The context is
+/// kept so that the channel can be re-established.
 #[derive(Debug, Clone)]
 pub(crate) struct GrpcClient {
-    ctx: GrpcContext,
-    v0_client: Option<MayaClientV0>,
-    v1_client: Option<MayaClientV1>,
+    client: io_engine_client::GrpcClient,
+    context: GrpcContext,
 }
 
 impl GrpcClient {
-    /// Initialize gRPC client.
+    /// Initialize gRPC client, retrying until the io-engine is connected and its api version is
+    /// detected.
     pub(crate) async fn new(context: GrpcContext) -> Result<Self, ExporterError> {
         let sleep_duration_sec = 10;
         loop {
-            match context.api_version {
-                ApiVersion::V0 => {
-                    match tokio::time::timeout(
-                        context.timeouts.connect(),
-                        MayaClientV0::connect(context.endpoint.clone()),
-                    )
-                    .await
-                    {
-                        Err(error) => {
-                            error!(error=%error, "Grpc connection timeout, retrying after {}s",sleep_duration_sec);
-                        }
-                        Ok(result) => match result {
-                            Ok(v0_client) => {
-                                return Ok(Self {
-                                    ctx: context.clone(),
-                                    v0_client: Some(v0_client),
-                                    v1_client: None,
-                                })
-                            }
-                            Err(error) => {
-                                error!(error=%error, "Grpc client connection error, retrying after {}s",sleep_duration_sec);
-                            }
-                        },
-                    }
+            match io_engine_client::GrpcClient::connect(&context).await {
+                Ok(client) => {
+                    let client = Self {
+                        client,
+                        context: context.clone(),
+                    };
+                    info!(
+                        node.name=%context.node_name(),
+                        api_versions=?client.served_api_versions(),
+                        "grpc connected successfully"
+                    );
+                    client.record_api_version();
+                    return Ok(client);
                 }
-                ApiVersion::V1 => {
-                    match tokio::time::timeout(
-                        context.timeouts.connect(),
-                        PoolClient::connect(context.endpoint.clone()),
-                    )
-                    .await
-                    {
-                        Err(error) => {
-                            error!(error=%error, "Grpc connection timeout, retrying after {}s",sleep_duration_sec);
-                        }
-                        Ok(result) => match result {
-                            Ok(pool) => {
-                                info!("grpc connected successfully");
-                                return Ok(Self {
-                                    ctx: context.clone(),
-                                    v0_client: None,
-                                    v1_client: Some(MayaClientV1 { pool }),
-                                });
-                            }
-                            Err(error) => {
-                                error!(error=%error, "Grpc client connection error, retrying after {}s",sleep_duration_sec);
-                            }
-                        },
-                    }
+                Err(error) => {
+                    error!(
+                        error=%error,
+                        node.name=%context.node_name(),
+                        "Grpc client connection error, retrying after {sleep_duration_sec}s"
+                    );
                 }
             }
             sleep(Duration::from_secs(sleep_duration_sec)).await;
         }
     }
 
+    /// Check the health of the channel to the io-engine by detecting its api version again,
+    /// eg: after an upgrade of the io-engine changed it. Should the io-engine not respond over
+    /// the channel, eg: after it restarted, the channel is re-established. The current client is
+    /// kept if neither succeeds.
+    pub(crate) async fn check_health(&mut self) {
+        let previous = self.served_api_versions().to_vec();
+        let mut client = self.client.clone();
+        match client.detect_api_version().await {
+            Ok(_) if client.served_api_versions() != previous => {
+                info!(
+                    node.name=%self.node_name(),
+                    previous=?previous,
+                    api_versions=?client.served_api_versions(),
+                    "io-engine api versions changed"
+                );
+                self.client = client;
+                self.record_api_version();
+            }
+            Ok(_) => {}
+            Err(error) => {
+                warn!(
+                    error=%error,
+                    node.name=%self.node_name(),
+                    "io-engine not responding over the grpc channel, reconnecting"
+                );
+                self.reconnect().await;
+            }
+        }
+    }
+
+    /// Re-establish the channel to the io-engine, detecting its api version again.
+    async fn reconnect(&mut self) {
+        match io_engine_client::GrpcClient::connect(&self.context).await {
+            Ok(client) => {
+                self.client = client;
+                if !suppresses_transitions() {
+                    GRPC_RECONNECTS_TOTAL
+                        .with_label_values(&[self.node_name()])
+                        .inc();
+                }
+                info!(
+                    node.name=%self.node_name(),
+                    api_versions=?self.served_api_versions(),
+                    "grpc reconnected successfully"
+                );
+                self.record_api_version();
+            }
+            Err(error) => {
+                warn!(
+                    error=%error,
+                    node.name=%self.node_name(),
+                    "Grpc client reconnection error, retrying after the next failed refresh"
+                );
+            }
+        }
+    }
+
+    /// Record the api versions the io-engine of the node is scraped with.
+    fn record_api_version(&self) {
+        let api_versions = self.served_api_versions();
+        for api_version in [ApiVersion::V0, ApiVersion::V1] {
+            IO_ENGINE_API_VERSION
+                .with_label_values(&[self.node_name(), api_version.as_ref()])
+                .set(api_versions.contains(&api_version).into());
+        }
+        set_node_api_versions(self.node_name(), api_versions);
+    }
+
+    /// Get the shared io-engine client, for its typed calls.
+    pub(crate) fn client(&self) -> &io_engine_client::GrpcClient {
+        &self.client
+    }
+
     /// Get the v0 api client.
     pub(crate) fn client_v0(&self) -> Result<MayaClientV0, ExporterError> {
-        match self.v0_client.clone() {
-            Some(client) => Ok(client),
-            None => Err(ExporterError::GrpcClientError(
-                "Could not get v0 client".to_string(),
-            )),
-        }
+        Ok(self.client.client_v0()?)
     }
 
     /// Get the v1 api client.
     pub(crate) fn client_v1(&self) -> Result<MayaClientV1, ExporterError> {
-        match self.v1_client.clone() {
-            Some(client) => Ok(client),
-            None => Err(ExporterError::GrpcClientError(
-                "Could not get v1 client".to_string(),
-            )),
-        }
+        Ok(self.client.client_v1()?)
     }
 
-    /// Get the api version.
+    /// Get the api version, the latest one which the io-engine serves.
     pub(crate) fn api_version(&self) -> ApiVersion {
-        self.ctx.api_version.clone()
+        self.client.api_version()
+    }
+
+    /// Get the api versions which the io-engine serves.
+    pub(crate) fn served_api_versions(&self) -> &[ApiVersion] {
+        self.client.served_api_versions()
+    }
+
+    /// Get the name of the node this client is connected to.
+    pub(crate) fn node_name(&self) -> &str {
+        self.client.node_name()
+    }
+
+    /// Refresh the resource from the io-engine within its request timeout, so that a slow call
+    /// only fails the refresh of its own resource.
+    pub(crate) async fn with_deadline<T, F>(
+        &self,
+        resource: RefreshedResource,
+        refresh: F,
+    ) -> Result<T, ExporterError>
+    where
+        F: Future<Output = Result<T, ExporterError>>,
+    {
+        let timeout = ExporterConfig::get_config().request_timeout(resource);
+        match tokio::time::timeout(timeout, refresh).await {
+            Ok(result) => result,
+            Err(_) => Err(ExporterError::DeadlineExceeded(format!(
+                "the {} of node {} were not refreshed within {}",
+                resource.as_ref(),
+                self.node_name(),
+                humantime::format_duration(timeout)
+            ))),
+        }
+    }
+
+    /// Record the successful call of the gRPC method to the io-engine of the node.
+    pub(crate) fn record_success(&self, rpc: &str) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        LAST_SUCCESSFUL_RPC_TIMESTAMP_SECONDS
+            .with_label_values(&[self.node_name(), rpc])
+            .set(now.as_secs_f64());
     }
 }
 
-/// Initialize mayastor grpc client.
-pub(crate) async fn init_client(api_version: ApiVersion) -> Result<GrpcClient, ExporterError> {
-    let timeout = Timeouts::new(Duration::from_secs(1), Duration::from_secs(5));
-    let pod_ip = get_pod_ip()?;
-    let _ = get_node_name()?;
-    let endpoint = Uri::builder()
+/// Build the io-engine endpoint uri from the given authority.
+fn io_engine_uri(authority: &str) -> Result<Uri, ExporterError> {
+    Uri::builder()
         .scheme("https")
-        .authority(format!("{pod_ip}:10124"))
+        .authority(authority)
         .path_and_query("")
         .build()
-        .map_err(|error| ExporterError::InvalidURI(error.to_string()))?;
-    let ctx = GrpcContext::new(endpoint, timeout, api_version);
-    let client = GrpcClient::new(ctx).await?;
-    Ok(client)
+        .map_err(|error| ExporterError::InvalidURI(error.to_string()))
+}
+
+/// Initialize the grpc contexts of the io-engines to be scraped.
+/// When no endpoints are given, the exporter only scrapes a single io-engine: the given
+/// `endpoint`, or the io-engine of its own pod on `grpc_port` when running as a sidecar.
+pub(crate) fn init_contexts(
+    api_versions: Vec<ApiVersion>,
+    endpoints: &[NodeEndpoint],
+    node_name: Option<String>,
+    endpoint: Option<String>,
+    grpc_port: u16,
+    timeout: Timeouts,
+) -> Result<Vec<GrpcContext>, ExporterError> {
+    if endpoints.is_empty() {
+        let node_name = match node_name {
+            Some(node_name) => node_name,
+            None => get_node_name()?,
+        };
+        let authority = match endpoint {
+            Some(endpoint) => endpoint,
+            None => format!("{}:{grpc_port}", get_pod_ip()?),
+        };
+        let endpoint = io_engine_uri(&authority)?;
+        return Ok(vec![GrpcContext::new(
+            node_name,
+            endpoint,
+            timeout,
+            api_versions,
+        )]);
+    }
+    endpoints
+        .iter()
+        .map(|node| {
+            let endpoint = io_engine_uri(&node.authority)?;
+            Ok(GrpcContext::new(
+                node.node_name.clone(),
+                endpoint,
+                timeout.clone(),
+                api_versions.clone(),
+            ))
+        })
+        .collect()
 }
@@ -1,13 +1,12 @@
+/// BdevErrorStats module.
+pub mod bdev;
 /// Grpc client module.
 pub mod grpc_client;
 /// PoolInfo module.
 pub mod pool;
+/// ReplicaStats module.
+pub mod stats;
+/// VolumeInfo module.
+pub mod volume;
 
-#[derive(
-    Debug, strum_macros::EnumString, strum_macros::AsRefStr, Clone, Ord, PartialOrd, Eq, PartialEq,
-)]
-#[strum(serialize_all = "lowercase")]
-pub enum ApiVersion {
-    V0,
-    V1,
-}
+pub(crate) use io_engine_client::ApiVersion;
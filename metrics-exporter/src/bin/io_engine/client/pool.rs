@@ -1,11 +1,18 @@
-use crate::{client::grpc_client::GrpcClient, error::ExporterError, ApiVersion};
+use crate::{
+    cache::{InternLabels, LabelInterner},
+    client::grpc_client::GrpcClient,
+    error::ExporterError,
+};
 
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
 /// This stores Capacity and state information of a pool.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub(crate) struct PoolInfo {
-    name: String,
+    name: Arc<str>,
+    uuid: Arc<str>,
+    disks: Arc<str>,
     used: u64,
     capacity: u64,
     state: u64,
@@ -14,10 +21,20 @@ pub(crate) struct PoolInfo {
 
 impl PoolInfo {
     /// Get name of the pool.
-    pub(crate) fn name(&self) -> &String {
+    pub(crate) fn name(&self) -> &Arc<str> {
         &self.name
     }
 
+    /// Get uuid of the pool, empty if not reported by the io-engine.
+    pub(crate) fn uuid(&self) -> &Arc<str> {
+        &self.uuid
+    }
+
+    /// Get the disks the pool is created on, comma separated.
+    pub(crate) fn disks(&self) -> &Arc<str> {
+        &self.disks
+    }
+
     /// Get used capacity of the pool.
     pub(crate) fn used(&self) -> u64 {
         self.used
@@ -39,6 +56,14 @@ impl PoolInfo {
     }
 }
 
+impl InternLabels for PoolInfo {
+    fn intern_labels(&mut self, labels: &mut LabelInterner) {
+        labels.intern_in_place(&mut self.name);
+        labels.intern_in_place(&mut self.uuid);
+        labels.intern_in_place(&mut self.disks);
+    }
+}
+
 /// Array of PoolInfo objects.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub(crate) struct Pools {
@@ -51,25 +76,17 @@ pub(crate) trait PoolOperations: Send + Sync + Sized {
     async fn list_pools(&self) -> Result<Pools, ExporterError>;
 }
 
-impl From<rpc::io_engine::Pool> for PoolInfo {
-    fn from(value: rpc::io_engine::Pool) -> Self {
+impl From<io_engine_client::pool::Pool> for PoolInfo {
+    fn from(value: io_engine_client::pool::Pool) -> Self {
         Self {
-            name: value.name,
+            name: value.name.into(),
+            uuid: value.uuid.unwrap_or_default().into(),
+            disks: value.disks.join(",").into(),
             used: value.used,
             capacity: value.capacity,
             state: value.state as u64,
-            committed: value.used,
-        }
-    }
-}
-impl From<rpc::v1::pool::Pool> for PoolInfo {
-    fn from(value: rpc::v1::pool::Pool) -> Self {
-        Self {
-            name: value.name,
-            used: value.used,
-            capacity: value.capacity,
-            state: value.state as u64,
-            committed: value.committed,
+            // The v0 pools don't report their commitment, which is at least what they use.
+            committed: value.committed.unwrap_or(value.used),
         }
     }
 }
@@ -77,32 +94,10 @@ impl From<rpc::v1::pool::Pool> for PoolInfo {
 #[tonic::async_trait]
 impl PoolOperations for GrpcClient {
     async fn list_pools(&self) -> Result<Pools, ExporterError> {
-        let pools = match self.api_version() {
-            ApiVersion::V0 => match self.client_v0()?.list_pools(rpc::io_engine::Null {}).await {
-                Ok(response) => response
-                    .into_inner()
-                    .pools
-                    .into_iter()
-                    .map(PoolInfo::from)
-                    .collect::<Vec<_>>(),
-                Err(error) => return Err(ExporterError::GrpcResponseError(error.to_string())),
-            },
-            ApiVersion::V1 => match self
-                .client_v1()?
-                .pool
-                .list_pools(rpc::v1::pool::ListPoolOptions::default())
-                .await
-            {
-                Ok(response) => response
-                    .into_inner()
-                    .pools
-                    .into_iter()
-                    .map(PoolInfo::from)
-                    .collect::<Vec<_>>(),
-                Err(error) => return Err(ExporterError::GrpcResponseError(error.to_string())),
-            },
-        };
-
-        Ok(Pools { pools })
+        let pools = self.client().list_pools().await?;
+        self.record_success("list_pools");
+        Ok(Pools {
+            pools: pools.into_iter().map(PoolInfo::from).collect(),
+        })
     }
 }
@@ -0,0 +1,111 @@
+use crate::{
+    cache::{InternLabels, LabelInterner},
+    client::grpc_client::GrpcClient,
+    error::ExporterError,
+};
+
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// This stores the cumulative io stats of a replica, as reported by the io-engine.
+/// The v0 counters are reset whenever the io-engine restarts.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct ReplicaStats {
+    uuid: Arc<str>,
+    pool: Arc<str>,
+    num_read_ops: u64,
+    num_write_ops: u64,
+    bytes_read: u64,
+    bytes_written: u64,
+}
+
+impl ReplicaStats {
+    /// Get uuid of the replica.
+    pub(crate) fn uuid(&self) -> &Arc<str> {
+        &self.uuid
+    }
+
+    /// Get name of the pool the replica is on.
+    pub(crate) fn pool(&self) -> &Arc<str> {
+        &self.pool
+    }
+
+    /// Get the number of read operations.
+    pub(crate) fn num_read_ops(&self) -> u64 {
+        self.num_read_ops
+    }
+
+    /// Get the number of write operations.
+    pub(crate) fn num_write_ops(&self) -> u64 {
+        self.num_write_ops
+    }
+
+    /// Get the number of bytes read.
+    pub(crate) fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+
+    /// Get the number of bytes written.
+    pub(crate) fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+}
+
+impl InternLabels for ReplicaStats {
+    fn intern_labels(&mut self, labels: &mut LabelInterner) {
+        labels.intern_in_place(&mut self.uuid);
+        labels.intern_in_place(&mut self.pool);
+    }
+}
+
+/// Array of ReplicaStats objects.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct ReplicasStats {
+    pub(crate) replicas: Vec<ReplicaStats>,
+}
+
+/// Trait to be implemented by grpc client to call replica stats rpc.
+#[tonic::async_trait]
+pub(crate) trait StatsOperations: Send + Sync + Sized {
+    async fn list_replica_stats(&self) -> Result<ReplicasStats, ExporterError>;
+}
+
+impl From<rpc::io_engine::ReplicaStats> for ReplicaStats {
+    fn from(value: rpc::io_engine::ReplicaStats) -> Self {
+        let stats = value.stats.unwrap_or_default();
+        Self {
+            uuid: value.uuid.into(),
+            pool: value.pool.into(),
+            num_read_ops: stats.num_read_ops,
+            num_write_ops: stats.num_write_ops,
+            bytes_read: stats.bytes_read,
+            bytes_written: stats.bytes_written,
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl StatsOperations for GrpcClient {
+    async fn list_replica_stats(&self) -> Result<ReplicasStats, ExporterError> {
+        // The replica stats are only tracked for the v0 api, which the io-engine may serve
+        // alongside the v1 api.
+        let replicas = match self
+            .client_v0()?
+            .stat_replicas(rpc::io_engine::Null {})
+            .await
+        {
+            Ok(response) => {
+                self.record_success("stat_replicas");
+                response
+                    .into_inner()
+                    .replicas
+                    .into_iter()
+                    .map(ReplicaStats::from)
+                    .collect::<Vec<_>>()
+            }
+            Err(error) => return Err(ExporterError::GrpcResponseError(error.to_string())),
+        };
+
+        Ok(ReplicasStats { replicas })
+    }
+}
@@ -0,0 +1,162 @@
+use crate::{
+    cache::{InternLabels, LabelInterner},
+    client::grpc_client::GrpcClient,
+    error::ExporterError,
+    ApiVersion,
+};
+
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+};
+use tracing::debug;
+
+/// This stores the capacity and snapshot information of a volume, as known to a single
+/// io-engine.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub(crate) struct VolumeInfo {
+    uuid: Arc<str>,
+    capacity: u64,
+    allocated: u64,
+    snapshot_count: u64,
+    oldest_snapshot_timestamp: Option<i64>,
+}
+
+impl VolumeInfo {
+    /// Get uuid of the volume.
+    pub(crate) fn uuid(&self) -> &Arc<str> {
+        &self.uuid
+    }
+
+    /// Get capacity of the volume, i.e. the size of its nexus if published on this node,
+    /// otherwise the capacity of its largest replica on this node.
+    pub(crate) fn capacity(&self) -> u64 {
+        self.capacity
+    }
+
+    /// Get the bytes allocated by the replicas of the volume on this node.
+    pub(crate) fn allocated(&self) -> u64 {
+        self.allocated
+    }
+
+    /// Get the number of snapshots of the volume on this node.
+    pub(crate) fn snapshot_count(&self) -> u64 {
+        self.snapshot_count
+    }
+
+    /// Get the creation time of the oldest snapshot of the volume on this node, in seconds since
+    /// the unix epoch.
+    pub(crate) fn oldest_snapshot_timestamp(&self) -> Option<i64> {
+        self.oldest_snapshot_timestamp
+    }
+
+    /// Create the info of a volume, with no capacity nor snapshots yet.
+    fn new(uuid: String) -> Self {
+        Self {
+            uuid: uuid.into(),
+            capacity: 0,
+            allocated: 0,
+            snapshot_count: 0,
+            oldest_snapshot_timestamp: None,
+        }
+    }
+}
+
+impl InternLabels for VolumeInfo {
+    fn intern_labels(&mut self, labels: &mut LabelInterner) {
+        labels.intern_in_place(&mut self.uuid);
+    }
+}
+
+/// Array of VolumeInfo objects.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct Volumes {
+    pub(crate) volumes: Vec<VolumeInfo>,
+}
+
+/// Trait to be implemented by grpc client to aggregate the volumes from replica and nexus rpc.
+#[tonic::async_trait]
+pub(crate) trait VolumeOperations: Send + Sync + Sized {
+    async fn list_volumes(&self) -> Result<Volumes, ExporterError>;
+}
+
+#[tonic::async_trait]
+impl VolumeOperations for GrpcClient {
+    async fn list_volumes(&self) -> Result<Volumes, ExporterError> {
+        let mut client = match self.api_version() {
+            ApiVersion::V0 => {
+                // The v0 replicas don't carry the uuid of the volume they belong to.
+                debug!("Volume metrics are not supported with the v0 api");
+                return Ok(Volumes { volumes: vec![] });
+            }
+            ApiVersion::V1 => self.client_v1()?,
+        };
+        let replicas = self.client().list_replicas().await?;
+        self.record_success("list_replicas");
+        let nexuses = self.client().list_nexuses().await?;
+        self.record_success("list_nexus");
+        let snapshots = match client
+            .snapshot
+            .list_snapshot(rpc::v1::snapshot::ListSnapshotsRequest::default())
+            .await
+        {
+            Ok(response) => {
+                self.record_success("list_snapshot");
+                response.into_inner().snapshots
+            }
+            // Io-engines which predate snapshots don't implement the snapshot service.
+            Err(status) if status.code() == tonic::Code::Unimplemented => {
+                debug!("Snapshot metrics are not supported by the io-engine");
+                vec![]
+            }
+            Err(error) => return Err(ExporterError::GrpcResponseError(error.to_string())),
+        };
+
+        let mut volumes = BTreeMap::<String, VolumeInfo>::new();
+        for replica in replicas {
+            // Replicas which are not owned by a volume, eg: snapshots, are skipped.
+            let (Some(uuid), Some(usage)) = (replica.entity_id, replica.usage) else {
+                continue;
+            };
+            let volume = volumes
+                .entry(uuid.clone())
+                .or_insert_with(|| VolumeInfo::new(uuid));
+            volume.capacity = volume.capacity.max(usage.capacity_bytes);
+            volume.allocated += usage.allocated_bytes;
+        }
+        // The nexus of a volume is named after the volume uuid, and is sized as the volume.
+        for nexus in nexuses {
+            let volume = volumes
+                .entry(nexus.name.clone())
+                .or_insert_with(|| VolumeInfo::new(nexus.name));
+            volume.capacity = nexus.size;
+        }
+        // A volume snapshot is made of a snapshot of each of its replicas, which share the same
+        // transaction id. The snapshots are also kept after the volume replicas are removed, so
+        // that snapshots which are never pruned remain visible.
+        let mut transactions = BTreeMap::<String, BTreeSet<String>>::new();
+        for snapshot in snapshots {
+            if snapshot.entity_id.is_empty() || snapshot.discarded_snapshot {
+                continue;
+            }
+            let volume = volumes
+                .entry(snapshot.entity_id.clone())
+                .or_insert_with(|| VolumeInfo::new(snapshot.entity_id.clone()));
+            if let Some(timestamp) = snapshot.timestamp {
+                volume.oldest_snapshot_timestamp = Some(
+                    volume
+                        .oldest_snapshot_timestamp
+                        .map_or(timestamp.seconds, |oldest| oldest.min(timestamp.seconds)),
+                );
+            }
+            let volume_transactions = transactions.entry(snapshot.entity_id).or_default();
+            volume_transactions.insert(snapshot.txn_id);
+            volume.snapshot_count = volume_transactions.len() as u64;
+        }
+
+        Ok(Volumes {
+            volumes: volumes.into_values().collect(),
+        })
+    }
+}
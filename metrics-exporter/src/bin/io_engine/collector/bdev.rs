@@ -0,0 +1,86 @@
+use crate::{
+    cache::{Cache, PoolDeviceCounters},
+    collector::prefixed_opts,
+};
+use prometheus::{
+    core::{Collector, Desc},
+    proto::MetricFamily,
+    IntCounterVec, Opts,
+};
+use tracing::error;
+
+/// Reset-safe error counters of the devices backing the pools, so that disk-level failures can
+/// be correlated with the pools in alerts.
+pub(crate) struct PoolDeviceErrorsCollector {
+    counters: Vec<(IntCounterVec, fn(&PoolDeviceCounters) -> u64)>,
+    descs: Vec<Desc>,
+}
+
+impl Default for PoolDeviceErrorsCollector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PoolDeviceErrorsCollector {
+    /// Initialize all the device error counters.
+    pub(crate) fn new() -> Self {
+        let specs: [(&str, &str, fn(&PoolDeviceCounters) -> u64); 3] = [
+            (
+                "io_errors_total",
+                "Number of failed I/Os of the device",
+                |device| device.io_errors.total(),
+            ),
+            ("resets_total", "Number of resets of the device", |device| {
+                device.resets.total()
+            }),
+            (
+                "aborts_total",
+                "Number of aborted I/Os of the device",
+                |device| device.aborts.total(),
+            ),
+        ];
+        let mut descs = Vec::new();
+        let counters = specs
+            .into_iter()
+            .map(|(name, help, value)| {
+                let counter = IntCounterVec::new(
+                    prefixed_opts(Opts::new(name, help).subsystem("disk_pool_device"), false),
+                    &["node", "pool", "device"],
+                )
+                .unwrap_or_else(|_| panic!("Unable to create counter metric type for {name}"));
+                descs.extend(counter.desc().into_iter().cloned());
+                (counter, value)
+            })
+            .collect();
+        Self { counters, descs }
+    }
+}
+
+impl Collector for PoolDeviceErrorsCollector {
+    fn desc(&self) -> Vec<&Desc> {
+        self.descs.iter().collect()
+    }
+
+    fn collect(&self) -> Vec<MetricFamily> {
+        let cache = Cache::lock();
+        let mut metric_family = Vec::new();
+        for (node_name, data) in cache.nodes() {
+            for device in data.pool_device_errors().iter() {
+                let labels = [node_name.as_ref(), device.pool(), device.device()];
+                for (counter, value) in &self.counters {
+                    match counter.get_metric_with_label_values(&labels) {
+                        Ok(metric) => {
+                            metric.inc_by(value(device));
+                            metric_family.extend(metric.collect().pop());
+                        }
+                        Err(error) => {
+                            error!(%error, ?labels, "Error while creating metrics with label values");
+                        }
+                    }
+                }
+            }
+        }
+        metric_family
+    }
+}
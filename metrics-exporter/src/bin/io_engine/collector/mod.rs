@@ -1,2 +1,238 @@
+use crate::{
+    cache::Cache, capabilities::is_enabled, config::ExporterConfig, info::is_identity_fallback,
+    maintenance::suppresses_transitions, memory::is_shed,
+};
+use once_cell::sync::Lazy;
+use prometheus::{
+    core::{Collector, Desc},
+    proto::MetricFamily,
+    GaugeVec, IntCounterVec, Opts,
+};
+use std::marker::PhantomData;
+use tracing::{error, warn};
+
+/// Module for pool device errors collector.
+pub mod bdev;
 /// Module for pools collector.
 pub mod pool;
+/// Module for replica stats collector.
+pub mod stats;
+/// Module for volumes collector.
+pub mod volume;
+
+/// Counts the scrapes in which a collector did not complete within the collector timeout.
+pub(crate) static COLLECTOR_TIMEOUTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    IntCounterVec::new(
+        Opts::new(
+            "exporter_collector_timeout_total",
+            "Number of scrapes in which the collector timed out",
+        ),
+        &["collector"],
+    )
+    .expect("Unable to create counter metric type for exporter_collector_timeout_total")
+});
+
+/// Metric families which were already collected by the wrapped collector.
+struct Collected {
+    collector: Box<dyn Collector>,
+    families: Vec<MetricFamily>,
+}
+
+impl Collector for Collected {
+    fn desc(&self) -> Vec<&Desc> {
+        self.collector.desc()
+    }
+
+    fn collect(&self) -> Vec<MetricFamily> {
+        self.families.clone()
+    }
+}
+
+/// Run the collector bounded by the configured collector timeout, so that a slow collector
+/// cannot delay the whole scrape. Returns `None` if the collector timed out, in which case the
+/// scrape only contains the metrics of the other collectors. Returns `None` as well if the
+/// collector is not enabled for the io-engine api version, or if it is shed as the exporter
+/// exceeds its memory limit.
+pub(crate) async fn collect_with_timeout(
+    name: &'static str,
+    collector: Box<dyn Collector>,
+) -> Option<Box<dyn Collector>> {
+    if !is_enabled(name) || is_shed(name) {
+        return None;
+    }
+    let timeout = ExporterConfig::get_config().collector_timeout();
+    let task = tokio::task::spawn_blocking(move || {
+        let families = collector.collect();
+        Collected {
+            collector,
+            families,
+        }
+    });
+    match tokio::time::timeout(timeout, task).await {
+        Ok(Ok(collected)) => Some(Box::new(collected)),
+        Ok(Err(error)) => {
+            error!(%error, collector = name, "Collector failed");
+            None
+        }
+        Err(_) => {
+            warn!(
+                collector = name,
+                ?timeout,
+                "Collector timed out, serving partial metrics"
+            );
+            if !suppresses_transitions() {
+                COLLECTOR_TIMEOUTS.with_label_values(&[name]).inc();
+            }
+            None
+        }
+    }
+}
+
+/// Prefix of the experimental metric names.
+const EXPERIMENTAL_PREFIX: &str = "experimental";
+
+/// Label of the metrics whose node name fell back to the hostname.
+const IDENTITY_LABEL: &str = "identity";
+
+/// Prepend the configured metric prefix, and the experimental prefix for experimental metrics,
+/// to the namespace of the metric options. Every collector builds its metrics through this, so
+/// that the prefix is applied uniformly, along with the `identity="fallback"` label when the
+/// node name fell back to the hostname.
+pub(crate) fn prefixed_opts(mut opts: Opts, experimental: bool) -> Opts {
+    let config = ExporterConfig::get_config();
+    let prefixes = [
+        config.metric_prefix(),
+        experimental.then_some(EXPERIMENTAL_PREFIX),
+        Some(opts.namespace.as_str()),
+    ];
+    opts.namespace = prefixes
+        .into_iter()
+        .flatten()
+        .filter(|prefix| !prefix.is_empty())
+        .collect::<Vec<_>>()
+        .join("_");
+    if is_identity_fallback() {
+        opts = opts.const_label(IDENTITY_LABEL, "fallback");
+    }
+    opts
+}
+
+/// Validate the metric prefix, which must be a valid prometheus metric name.
+pub(crate) fn validate_metric_prefix(prefix: &str) -> Result<(), String> {
+    let mut chars = prefix.chars();
+    let valid = chars
+        .next()
+        .map_or(false, |c| c.is_ascii_alphabetic() || c == '_' || c == ':')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid metric prefix '{prefix}'"))
+    }
+}
+
+/// Collect the gauges declared by the resource collector, bounded by the collector timeout.
+/// Returns `None` for experimental collectors unless the experimental metrics are enabled.
+pub(crate) async fn collect_gauges<C: ResourceCollector>(
+    name: &'static str,
+) -> Option<Box<dyn Collector>> {
+    if C::EXPERIMENTAL && !ExporterConfig::get_config().experimental_metrics() {
+        return None;
+    }
+    collect_with_timeout(name, Box::new(GaugeCollector::<C>::new())).await
+}
+
+/// Trait to be implemented for every kind of cached resource whose metrics are exported as
+/// gauges. The `GaugeCollector` takes care of creating the gauges and collecting them.
+pub(crate) trait ResourceCollector: Send + Sync + 'static {
+    /// The cached resource the metrics are collected for.
+    type Resource;
+    /// Names of the labels which identify a resource.
+    const LABELS: &'static [&'static str];
+    /// Experimental collectors are only exposed with `--enable-experimental-metrics`, and their
+    /// metric names are prefixed with `experimental_`. Once the names are settled, a collector
+    /// graduates by dropping this flag.
+    const EXPERIMENTAL: bool = false;
+    /// Declare the gauges exported for each resource.
+    fn gauges() -> Vec<GaugeSpec<Self::Resource>>;
+    /// Call `f` with the label values of every cached resource.
+    fn for_each_resource(cache: &Cache, f: &mut dyn FnMut(&[&str], &Self::Resource));
+}
+
+/// Declaration of a gauge exported for each resource, along with how to get its value.
+pub(crate) struct GaugeSpec<R> {
+    opts: Opts,
+    value: fn(&R) -> f64,
+}
+
+impl<R> GaugeSpec<R> {
+    /// Declare a gauge with the given options whose value is returned by `value`.
+    pub(crate) fn new(opts: Opts, value: fn(&R) -> f64) -> Self {
+        Self { opts, value }
+    }
+}
+
+/// Collects the gauges declared by a `ResourceCollector` from cache.
+pub(crate) struct GaugeCollector<C: ResourceCollector> {
+    gauges: Vec<(GaugeVec, fn(&C::Resource) -> f64)>,
+    descs: Vec<Desc>,
+    collector: PhantomData<C>,
+}
+
+impl<C: ResourceCollector> Default for GaugeCollector<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: ResourceCollector> GaugeCollector<C> {
+    /// Initialize all the gauges declared by the resource collector.
+    pub(crate) fn new() -> Self {
+        let labels = C::LABELS
+            .iter()
+            .map(|label| label.to_string())
+            .collect::<Vec<String>>();
+        let mut descs = Vec::new();
+        let gauges = C::gauges()
+            .into_iter()
+            .map(|spec| {
+                let opts = prefixed_opts(spec.opts, C::EXPERIMENTAL);
+                let name = opts.fq_name();
+                let gauge = GaugeVec::new(opts.variable_labels(labels.clone()), C::LABELS)
+                    .unwrap_or_else(|_| panic!("Unable to create gauge metric type for {name}"));
+                descs.extend(gauge.desc().into_iter().cloned());
+                (gauge, spec.value)
+            })
+            .collect();
+        Self {
+            gauges,
+            descs,
+            collector: PhantomData,
+        }
+    }
+}
+
+impl<C: ResourceCollector> Collector for GaugeCollector<C> {
+    fn desc(&self) -> Vec<&Desc> {
+        self.descs.iter().collect()
+    }
+
+    fn collect(&self) -> Vec<MetricFamily> {
+        let cache = Cache::lock();
+        let mut metric_family = Vec::new();
+        C::for_each_resource(&cache, &mut |labels, resource| {
+            for (gauge, value) in &self.gauges {
+                match gauge.get_metric_with_label_values(labels) {
+                    Ok(metric) => {
+                        metric.set(value(resource));
+                        metric_family.extend(metric.collect().pop());
+                    }
+                    Err(error) => {
+                        error!(%error, ?labels, "Error while creating metrics with label values");
+                    }
+                }
+            }
+        });
+        metric_family
+    }
+}
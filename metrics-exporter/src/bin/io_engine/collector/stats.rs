@@ -0,0 +1,91 @@
+use crate::{
+    cache::{Cache, ReplicaCounters},
+    collector::prefixed_opts,
+};
+use prometheus::{
+    core::{Collector, Desc},
+    proto::MetricFamily,
+    IntCounterVec, Opts,
+};
+use tracing::error;
+
+/// Reset-safe replica io counters tracked by the exporter from the v0 stats, which only
+/// expose cumulative counters that are reset when the io-engine restarts.
+pub(crate) struct ReplicaStatsCollector {
+    counters: Vec<(IntCounterVec, fn(&ReplicaCounters) -> u64)>,
+    descs: Vec<Desc>,
+}
+
+impl Default for ReplicaStatsCollector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ReplicaStatsCollector {
+    /// Initialize all the replica counters.
+    pub(crate) fn new() -> Self {
+        let specs: [(&str, &str, fn(&ReplicaCounters) -> u64); 4] = [
+            (
+                "num_read_ops_total",
+                "Number of read operations",
+                |replica| replica.num_read_ops.total(),
+            ),
+            (
+                "num_write_ops_total",
+                "Number of write operations",
+                |replica| replica.num_write_ops.total(),
+            ),
+            ("bytes_read_total", "Number of bytes read", |replica| {
+                replica.bytes_read.total()
+            }),
+            (
+                "bytes_written_total",
+                "Number of bytes written",
+                |replica| replica.bytes_written.total(),
+            ),
+        ];
+        let mut descs = Vec::new();
+        let counters = specs
+            .into_iter()
+            .map(|(name, help, value)| {
+                let counter = IntCounterVec::new(
+                    prefixed_opts(Opts::new(name, help).subsystem("replica"), false),
+                    &["node", "pool", "uuid"],
+                )
+                .unwrap_or_else(|_| panic!("Unable to create counter metric type for {name}"));
+                descs.extend(counter.desc().into_iter().cloned());
+                (counter, value)
+            })
+            .collect();
+        Self { counters, descs }
+    }
+}
+
+impl Collector for ReplicaStatsCollector {
+    fn desc(&self) -> Vec<&Desc> {
+        self.descs.iter().collect()
+    }
+
+    fn collect(&self) -> Vec<MetricFamily> {
+        let cache = Cache::lock();
+        let mut metric_family = Vec::new();
+        for (node_name, data) in cache.nodes() {
+            for replica in data.replica_stats().iter() {
+                let labels = [node_name.as_ref(), replica.pool(), replica.uuid()];
+                for (counter, value) in &self.counters {
+                    match counter.get_metric_with_label_values(&labels) {
+                        Ok(metric) => {
+                            metric.inc_by(value(replica));
+                            metric_family.extend(metric.collect().pop());
+                        }
+                        Err(error) => {
+                            error!(%error, ?labels, "Error while creating metrics with label values");
+                        }
+                    }
+                }
+            }
+        }
+        metric_family
+    }
+}
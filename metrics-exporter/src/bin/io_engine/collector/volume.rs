@@ -0,0 +1,65 @@
+use crate::{
+    cache::Cache,
+    client::volume::VolumeInfo,
+    collector::{GaugeSpec, ResourceCollector},
+};
+use prometheus::Opts;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Volume capacity and snapshot metrics, aggregated from the replicas, nexuses and snapshots of
+/// each node.
+pub(crate) struct VolumeCapacity;
+
+impl ResourceCollector for VolumeCapacity {
+    type Resource = VolumeInfo;
+    const LABELS: &'static [&'static str] = &["node", "uuid"];
+
+    fn gauges() -> Vec<GaugeSpec<VolumeInfo>> {
+        vec![
+            GaugeSpec::new(
+                Opts::new(
+                    "allocated_size_bytes",
+                    "Size allocated by the replicas of the volume on the node in bytes",
+                )
+                .subsystem("volume"),
+                |volume| volume.allocated() as f64,
+            ),
+            GaugeSpec::new(
+                Opts::new("capacity_size_bytes", "Capacity of the volume in bytes")
+                    .subsystem("volume"),
+                |volume| volume.capacity() as f64,
+            ),
+            GaugeSpec::new(
+                Opts::new(
+                    "snapshot_count",
+                    "Number of snapshots of the volume on the node",
+                )
+                .subsystem("volume"),
+                |volume| volume.snapshot_count() as f64,
+            ),
+            GaugeSpec::new(
+                Opts::new(
+                    "oldest_snapshot_age_seconds",
+                    "Age of the oldest snapshot of the volume on the node in seconds",
+                )
+                .subsystem("volume"),
+                |volume| oldest_snapshot_age(volume).unwrap_or_default(),
+            ),
+        ]
+    }
+
+    fn for_each_resource(cache: &Cache, f: &mut dyn FnMut(&[&str], &VolumeInfo)) {
+        for (node_name, data) in cache.nodes() {
+            for volume in data.volumes().iter() {
+                f(&[node_name.as_ref(), volume.uuid().as_ref()], volume);
+            }
+        }
+    }
+}
+
+/// Age of the oldest snapshot of the volume, in seconds.
+fn oldest_snapshot_age(volume: &VolumeInfo) -> Option<f64> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs() as i64;
+    let timestamp = volume.oldest_snapshot_timestamp()?;
+    Some(now.saturating_sub(timestamp).max(0) as f64)
+}
@@ -1,24 +1,92 @@
-use std::{net::SocketAddr, time::Duration};
+use crate::{
+    client::{
+        grpc_client::{NodeEndpoint, RefreshedResource, RequestTimeout, RequestTimeouts},
+        ApiVersion,
+    },
+    error::ExporterError,
+    memory::MemorySize,
+};
+use std::{fmt::Display, net::SocketAddr, path::Path, str::FromStr, sync::RwLock, time::Duration};
 
 use once_cell::sync::OnceCell;
+use serde::{Deserialize, Deserializer};
 
 static CONFIG: OnceCell<ExporterConfig> = OnceCell::new();
 
 /// Exporter config that can be passed through arguments.
 pub struct ExporterConfig {
-    /// Network address where the prometheus metrics endpoint will listen (example: 9502).
-    metrics_endpoint: SocketAddr,
+    /// Network addresses where the prometheus metrics endpoint will listen (example: 9502).
+    metrics_endpoints: Vec<SocketAddr>,
 
+    /// Settings which can be reloaded at runtime.
+    tunables: RwLock<Tunables>,
+
+    /// Whether the experimental metrics are exposed.
+    experimental_metrics: bool,
+
+    /// Whether the v0 replica stats are tracked as reset-safe counters.
+    v0_stats: bool,
+
+    /// Prefix of the names of the metrics exported by the collectors.
+    metric_prefix: Option<String>,
+
+    /// Request timeouts of the refreshes of the resources from the io-engine.
+    request_timeouts: RequestTimeouts,
+}
+
+/// Exporter settings which can be reloaded at runtime, on SIGHUP.
+#[derive(Debug, Clone)]
+pub struct Tunables {
     /// polling time to do grpc calls to get data from the server.(Default: 30s).
-    polling_time: Duration,
+    pub polling_time: Duration,
+
+    /// Minimum polling time, to which the polling time is shortened when the data changes.
+    pub min_polling_time: Duration,
+
+    /// Maximum polling time, up to which the polling time is lengthened while the data doesn't
+    /// change.
+    pub max_polling_time: Duration,
+
+    /// Maximum age of the cached data for the exporter to be considered ready.
+    pub ready_max_age: Duration,
+
+    /// Maximum time each collector may take within a single scrape.
+    pub collector_timeout: Duration,
+
+    /// Number of consecutive failed refreshes of an io-engine after which its refreshes are
+    /// suspended.
+    pub circuit_failure_threshold: u32,
+
+    /// Interval between the probing refreshes of an io-engine whose refreshes are suspended.
+    pub circuit_probe_interval: Duration,
+
+    /// Resident memory above which the collectors are shed, if any.
+    pub max_memory: Option<MemorySize>,
+
+    /// Whether the io-engine is under planned maintenance.
+    pub maintenance_mode: bool,
+
+    /// Whether the state transition counters are suppressed while under maintenance.
+    pub maintenance_suppress_counters: bool,
 }
 
 impl ExporterConfig {
     /// Initialize metrics-exporter configs.
-    pub fn initialize(addr: SocketAddr, polling_time: Duration) {
+    pub fn initialize(
+        addrs: Vec<SocketAddr>,
+        tunables: Tunables,
+        experimental_metrics: bool,
+        v0_stats: bool,
+        metric_prefix: Option<String>,
+        request_timeouts: RequestTimeouts,
+    ) {
         CONFIG.get_or_init(|| Self {
-            metrics_endpoint: addr,
-            polling_time,
+            metrics_endpoints: addrs,
+            tunables: RwLock::new(tunables),
+            experimental_metrics,
+            v0_stats,
+            metric_prefix,
+            request_timeouts,
         });
     }
 
@@ -27,13 +95,212 @@ impl ExporterConfig {
         CONFIG.get().expect("Exporter config is not initialized")
     }
 
-    /// Get metrics endpoint.
-    pub fn metrics_endpoint(&self) -> &SocketAddr {
-        &self.metrics_endpoint
+    /// Replace the settings which can be reloaded at runtime.
+    pub fn reload(&self, tunables: Tunables) {
+        match self.tunables.write() {
+            Ok(mut current) => *current = tunables,
+            Err(error) => *error.into_inner() = tunables,
+        }
+    }
+
+    /// Get the settings which can be reloaded at runtime.
+    fn tunables(&self) -> Tunables {
+        match self.tunables.read() {
+            Ok(tunables) => tunables.clone(),
+            Err(error) => error.into_inner().clone(),
+        }
+    }
+
+    /// Get metrics endpoints.
+    pub fn metrics_endpoints(&self) -> &[SocketAddr] {
+        &self.metrics_endpoints
     }
 
     /// Get polling time.
     pub fn polling_time(&self) -> Duration {
-        self.polling_time
+        self.tunables().polling_time
+    }
+
+    /// Get the minimum polling time.
+    pub fn min_polling_time(&self) -> Duration {
+        self.tunables().min_polling_time
+    }
+
+    /// Get the maximum polling time.
+    pub fn max_polling_time(&self) -> Duration {
+        self.tunables().max_polling_time
+    }
+
+    /// Get maximum age of the cached data for readiness.
+    pub fn ready_max_age(&self) -> Duration {
+        self.tunables().ready_max_age
+    }
+
+    /// Get the timeout of each collector within a scrape.
+    pub fn collector_timeout(&self) -> Duration {
+        self.tunables().collector_timeout
+    }
+
+    /// Get the number of consecutive failed refreshes after which they are suspended.
+    pub fn circuit_failure_threshold(&self) -> u32 {
+        self.tunables().circuit_failure_threshold
+    }
+
+    /// Get the interval between the probing refreshes while they are suspended.
+    pub fn circuit_probe_interval(&self) -> Duration {
+        self.tunables().circuit_probe_interval
+    }
+
+    /// Get the resident memory above which the collectors are shed, if any.
+    pub fn max_memory(&self) -> Option<MemorySize> {
+        self.tunables().max_memory
+    }
+
+    /// Whether the io-engine is under planned maintenance according to the configuration.
+    pub fn maintenance_mode(&self) -> bool {
+        self.tunables().maintenance_mode
+    }
+
+    /// Whether the state transition counters are suppressed while under maintenance.
+    pub fn maintenance_suppress_counters(&self) -> bool {
+        self.tunables().maintenance_suppress_counters
+    }
+
+    /// Whether the experimental metrics are exposed.
+    pub fn experimental_metrics(&self) -> bool {
+        self.experimental_metrics
+    }
+
+    /// Whether the v0 replica stats are tracked as reset-safe counters.
+    pub fn v0_stats(&self) -> bool {
+        self.v0_stats
+    }
+
+    /// Get the prefix of the names of the metrics exported by the collectors.
+    pub fn metric_prefix(&self) -> Option<&str> {
+        self.metric_prefix.as_deref()
+    }
+
+    /// Get the request timeout of the refresh of the resource from the io-engine.
+    pub(crate) fn request_timeout(&self, resource: RefreshedResource) -> Duration {
+        self.request_timeouts.get(resource)
+    }
+}
+
+/// Exporter settings loaded from a yaml config file. Every setting is optional, the ones given
+/// through arguments or env variables take precedence.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub(crate) struct ConfigFile {
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub(crate) metrics_endpoint: Vec<SocketAddr>,
+    #[serde(default, deserialize_with = "from_str")]
+    pub(crate) polling_time: Option<humantime::Duration>,
+    #[serde(default, deserialize_with = "from_str")]
+    pub(crate) min_polling_time: Option<humantime::Duration>,
+    #[serde(default, deserialize_with = "from_str")]
+    pub(crate) max_polling_time: Option<humantime::Duration>,
+    #[serde(default, deserialize_with = "from_str")]
+    pub(crate) ready_max_age: Option<humantime::Duration>,
+    #[serde(default, deserialize_with = "from_str")]
+    pub(crate) collector_timeout: Option<humantime::Duration>,
+    #[serde(default)]
+    pub(crate) circuit_failure_threshold: Option<u32>,
+    #[serde(default, deserialize_with = "from_str")]
+    pub(crate) circuit_probe_interval: Option<humantime::Duration>,
+    #[serde(default, deserialize_with = "from_str")]
+    pub(crate) max_memory: Option<MemorySize>,
+    #[serde(default)]
+    pub(crate) maintenance_mode: bool,
+    #[serde(default)]
+    pub(crate) maintenance_suppress_counters: bool,
+    #[serde(default, deserialize_with = "vec_from_str")]
+    pub(crate) api_versions: Vec<ApiVersion>,
+    #[serde(default, deserialize_with = "vec_from_str")]
+    pub(crate) io_engine_endpoints: Vec<NodeEndpoint>,
+    #[serde(default)]
+    pub(crate) node_name: Option<String>,
+    #[serde(default)]
+    pub(crate) endpoint: Option<String>,
+    #[serde(default)]
+    pub(crate) grpc_port: Option<u16>,
+    #[serde(default, deserialize_with = "from_str")]
+    pub(crate) connect_timeout: Option<humantime::Duration>,
+    #[serde(default, deserialize_with = "from_str")]
+    pub(crate) request_timeout: Option<humantime::Duration>,
+    #[serde(default, deserialize_with = "vec_from_str")]
+    pub(crate) request_timeouts: Vec<RequestTimeout>,
+    #[serde(default)]
+    pub(crate) otlp_endpoint: Option<String>,
+    #[serde(default)]
+    pub(crate) enable_experimental_metrics: bool,
+    #[serde(default)]
+    pub(crate) v0_stats: bool,
+    #[serde(default)]
+    pub(crate) metric_prefix: Option<String>,
+    #[serde(default)]
+    pub(crate) pushgateway_url: Option<String>,
+    #[serde(default)]
+    pub(crate) remote_write_url: Option<String>,
+}
+
+impl ConfigFile {
+    /// Load the config file from the given path.
+    pub(crate) fn load(path: &Path) -> Result<Self, ExporterError> {
+        let file = std::fs::File::open(path).map_err(|error| {
+            ExporterError::ConfigError(format!(
+                "Failed to open config file {}: {error}",
+                path.display()
+            ))
+        })?;
+        serde_yaml::from_reader(file).map_err(|error| {
+            ExporterError::ConfigError(format!(
+                "Failed to parse config file {}: {error}",
+                path.display()
+            ))
+        })
+    }
+}
+
+/// Deserialize an optional value from its string representation.
+fn from_str<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|value| value.parse().map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+/// Deserialize a list of values from their string representation.
+fn vec_from_str<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|value| value.parse().map_err(serde::de::Error::custom))
+        .collect()
+}
+
+/// Deserialize a list of values which may also be given as a single value.
+fn one_or_many<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany<T> {
+        One(T),
+        Many(Vec<T>),
     }
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(value) => vec![value],
+        OneOrMany::Many(values) => values,
+    })
 }
@@ -8,4 +8,17 @@ pub enum ExporterError {
     GrpcClientError(String),
     HttpServerError(String),
     HttpBindError(String),
+    OtlpError(String),
+    ConfigError(String),
+    PushError(String),
+    DeadlineExceeded(String),
+}
+
+impl From<io_engine_client::Error> for ExporterError {
+    fn from(error: io_engine_client::Error) -> Self {
+        match error {
+            io_engine_client::Error::Response { .. } => Self::GrpcResponseError(error.to_string()),
+            _ => Self::GrpcClientError(error.to_string()),
+        }
+    }
 }
@@ -0,0 +1,74 @@
+use once_cell::sync::Lazy;
+use prometheus::{IntGauge, IntGaugeVec, Opts};
+use std::{
+    collections::hash_map::DefaultHasher,
+    fmt::Debug,
+    hash::{Hash, Hasher},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// Build information of the exporter, always 1.
+pub(crate) static EXPORTER_BUILD_INFO: Lazy<IntGaugeVec> = Lazy::new(|| {
+    let gauge = IntGaugeVec::new(
+        Opts::new(
+            "exporter_build_info",
+            "Build information of the exporter, always 1",
+        ),
+        &["version", "commit"],
+    )
+    .expect("Unable to create gauge metric type for exporter_build_info");
+    let version_info = utils::version_info!();
+    gauge
+        .with_label_values(&[&exporter_version(), version_info.commit_hash.as_str()])
+        .set(1);
+    gauge
+});
+
+/// Version of the exporter, i.e the version tag it was built from, or the package version.
+pub(crate) fn exporter_version() -> String {
+    utils::version_info!()
+        .version_tag
+        .unwrap_or_else(|| env!("CARGO_PKG_VERSION").to_string())
+}
+
+/// Hash of the effective exporter configuration.
+pub(crate) static EXPORTER_CONFIG_HASH: Lazy<IntGauge> = Lazy::new(|| {
+    IntGauge::new(
+        "exporter_config_hash",
+        "Hash of the effective exporter configuration",
+    )
+    .expect("Unable to create gauge metric type for exporter_config_hash")
+});
+
+/// Set the config hash gauge from the effective configuration. The hash is truncated to 32 bits
+/// so that it is exactly representable as a sample value.
+pub(crate) fn set_config_hash(config: &impl Debug) {
+    let mut hasher = DefaultHasher::new();
+    format!("{config:?}").hash(&mut hasher);
+    EXPORTER_CONFIG_HASH.set((hasher.finish() as u32).into());
+}
+
+/// Whether the node name of the exporter is the hostname, as the node name env variable is unset.
+static NODE_IDENTITY_FALLBACK: AtomicBool = AtomicBool::new(false);
+
+/// Whether the node name env variable is unset, so that operators notice the misconfiguration
+/// instead of the metrics being attributed to the hostname silently.
+pub(crate) static NODE_IDENTITY_MISCONFIGURED: Lazy<IntGauge> = Lazy::new(|| {
+    IntGauge::new(
+        "exporter_node_identity_misconfigured",
+        "Whether the node name env variable is unset, the hostname being used as node name instead",
+    )
+    .expect("Unable to create gauge metric type for exporter_node_identity_misconfigured")
+});
+
+/// Record that the node name fell back to the hostname, the resource metrics are then labelled
+/// with `identity="fallback"`.
+pub(crate) fn set_identity_fallback() {
+    NODE_IDENTITY_FALLBACK.store(true, Ordering::Relaxed);
+    NODE_IDENTITY_MISCONFIGURED.set(1);
+}
+
+/// Whether the node name fell back to the hostname.
+pub(crate) fn is_identity_fallback() -> bool {
+    NODE_IDENTITY_FALLBACK.load(Ordering::Relaxed)
+}
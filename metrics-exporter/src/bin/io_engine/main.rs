@@ -1,16 +1,32 @@
 use crate::{
     cache::store_data,
-    client::{grpc_client::init_client, ApiVersion},
-    config::ExporterConfig,
+    capabilities::initialize_capabilities,
+    client::{
+        grpc_client::{init_contexts, NodeEndpoint, RequestTimeout, RequestTimeouts, Timeouts},
+        ApiVersion,
+    },
+    collector::validate_metric_prefix,
+    config::{ConfigFile, ExporterConfig, Tunables},
     error::ExporterError,
-    serve::metric_route,
+    info::{set_config_hash, set_identity_fallback},
+    memory::MemorySize,
+    otlp::init_otlp_export,
+    push::{push_metrics, PushTarget},
+    serve::{
+        bind_listeners, capabilities_route, dashboard_route, health_route, maintenance_route,
+        metric_route,
+    },
 };
 use actix_web::{middleware, HttpServer};
 use clap::Parser;
-use std::{env, net::SocketAddr};
+use std::{env, net::SocketAddr, path::PathBuf, time::Duration};
+use tokio::signal::unix::{signal, SignalKind};
+use tracing::{error, info, warn};
 
 /// Cache module for exporter.
 pub(crate) mod cache;
+/// Collector capabilities module.
+pub(crate) mod capabilities;
 /// Grpc client module.
 pub(crate) mod client;
 /// Collector module.
@@ -19,17 +35,92 @@ pub(crate) mod collector;
 pub(crate) mod config;
 /// Error module.
 pub(crate) mod error;
+/// Exporter build and config info module.
+pub(crate) mod info;
+/// Io-engine maintenance mode module.
+pub(crate) mod maintenance;
+/// Exporter memory limit module.
+pub(crate) mod memory;
+/// OTLP metrics export module.
+pub(crate) mod otlp;
+/// Metrics push module.
+pub(crate) mod push;
 /// Prometheus metrics handler module.
 pub(crate) mod serve;
 
+/// Default address where the prometheus endpoint listens to.
+const DEFAULT_METRICS_ENDPOINT: &str = "0.0.0.0:9502";
+/// Default polling time to get pools data through gRPC calls.
+const DEFAULT_POLLING_TIME: Duration = Duration::from_secs(300);
+/// Default maximum time each collector may take within a single scrape.
+const DEFAULT_COLLECTOR_TIMEOUT: Duration = Duration::from_secs(2);
+/// Default number of consecutive failed refreshes after which they are suspended.
+const DEFAULT_CIRCUIT_FAILURE_THRESHOLD: u32 = 3;
+/// Default interval between the probing refreshes while they are suspended.
+const DEFAULT_CIRCUIT_PROBE_INTERVAL: Duration = Duration::from_secs(60);
+/// Default io-engine gRPC port.
+const DEFAULT_GRPC_PORT: u16 = 10124;
+/// Default timeout to establish the gRPC connection to the io-engine.
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+/// Default timeout of the gRPC requests to the io-engine.
+const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// Initialize metrics-exporter config that are passed through arguments.
-fn initialize_exporter(args: &Cli) {
-    ExporterConfig::initialize(args.metrics_endpoint, args.polling_time.into());
+fn initialize_exporter(args: &Cli) -> Result<(), ExporterError> {
+    let metrics_endpoints = if args.metrics_endpoint.is_empty() {
+        vec![DEFAULT_METRICS_ENDPOINT
+            .parse()
+            .map_err(|_| ExporterError::ConfigError("Invalid metrics endpoint".to_string()))?]
+    } else {
+        args.metrics_endpoint.clone()
+    };
+    let metric_prefix = match &args.metric_prefix {
+        Some(prefix) => {
+            let prefix = prefix.trim_end_matches('_');
+            validate_metric_prefix(prefix).map_err(ExporterError::ConfigError)?;
+            Some(prefix.to_string())
+        }
+        None => None,
+    };
+    ExporterConfig::initialize(
+        metrics_endpoints,
+        args.tunables(),
+        args.enable_experimental_metrics,
+        args.v0_stats,
+        metric_prefix,
+        args.request_timeouts(),
+    );
+    Ok(())
+}
+
+/// Reload the settings which can be changed at runtime from the config file, on SIGHUP.
+async fn reload_on_sighup(cli: Cli) {
+    let mut hangup = match signal(SignalKind::hangup()) {
+        Ok(hangup) => hangup,
+        Err(error) => {
+            error!(%error, "Failed to register the SIGHUP handler, config reload is disabled");
+            return;
+        }
+    };
+    while hangup.recv().await.is_some() {
+        match cli.clone().with_config_file() {
+            Ok(args) => {
+                let tunables = args.tunables();
+                info!(?tunables, "Reloaded exporter config");
+                set_config_hash(&args);
+                ExporterConfig::get_config().reload(tunables);
+            }
+            Err(error) => error!(
+                ?error,
+                "Failed to reload exporter config, keeping the current one"
+            ),
+        }
+    }
 }
 
 /// Initialize cache.
 async fn initialize_cache() {
-    cache::Cache::initialize(cache::Data::default());
+    cache::Cache::initialize();
 }
 
 /// Get pod ip from env.
@@ -37,63 +128,384 @@ fn get_pod_ip() -> Result<String, ExporterError> {
     env::var("MY_POD_IP").map_err(|_| ExporterError::PodIPError("Unable to get pod ip".to_string()))
 }
 
-/// Get node name from env.
+/// Get node name from env, falling back to the hostname when not running in kubernetes. The
+/// fallback is exported as a misconfiguration, as the hostname may not match the node name.
 fn get_node_name() -> Result<String, ExporterError> {
-    env::var("MY_NODE_NAME")
-        .map_err(|_| ExporterError::GetNodeError("Unable to get node name".to_string()))
+    if let Ok(node_name) = env::var("MY_NODE_NAME") {
+        return Ok(node_name);
+    }
+    let hostname = get_hostname()
+        .map_err(|_| ExporterError::GetNodeError("Unable to get node name".to_string()))?;
+    warn!(
+        %hostname,
+        "MY_NODE_NAME is not set, falling back to the hostname as node name"
+    );
+    set_identity_fallback();
+    Ok(hostname)
 }
 
-#[derive(Parser, Debug)]
+/// Get the hostname of the machine the exporter is running on.
+fn get_hostname() -> Result<String, std::io::Error> {
+    let hostname = std::fs::read_to_string("/proc/sys/kernel/hostname")?;
+    match hostname.trim() {
+        "" => Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "Empty hostname",
+        )),
+        hostname => Ok(hostname.to_string()),
+    }
+}
+
+#[derive(Parser, Debug, Clone)]
 #[clap(name = utils::package_description!(), version = utils::version_info_str!())]
 struct Cli {
-    /// TCP address where prometheus endpoint will listen to
-    #[clap(long, short, default_value = "0.0.0.0:9502")]
-    metrics_endpoint: SocketAddr,
+    /// Path to a yaml config file with the exporter settings. The settings given through
+    /// arguments or env variables take precedence over the ones from the file.
+    /// The polling times, ready max age, collector timeout, circuit breaker settings, memory
+    /// limit and maintenance settings are reloaded from it on SIGHUP.
+    #[clap(long, env = "METRICS_EXPORTER_CONFIG")]
+    config: Option<PathBuf>,
+
+    /// TCP addresses where prometheus endpoint will listen to, can be given multiple times, eg:
+    /// `[::]:9502` and `0.0.0.0:9502` on dual-stack nodes [default: 0.0.0.0:9502]
+    #[clap(
+        long,
+        short,
+        alias = "metrics-bind",
+        value_delimiter = ',',
+        env = "METRICS_EXPORTER_METRICS_ENDPOINT"
+    )]
+    metrics_endpoint: Vec<SocketAddr>,
+
+    /// Polling time in seconds to get pools data through gRPC calls [default: 300s]
+    #[clap(short, long, env = "METRICS_EXPORTER_POLLING_TIME")]
+    polling_time: Option<humantime::Duration>,
 
-    /// Polling time in seconds to get pools data through gRPC calls
-    #[clap(short, long, default_value = "300s")]
-    polling_time: humantime::Duration,
+    /// Minimum polling time. The polling time is shortened down to it as soon as the pools or
+    /// volumes change [default: the polling time]
+    #[clap(long, env = "METRICS_EXPORTER_MIN_POLLING_TIME")]
+    min_polling_time: Option<humantime::Duration>,
 
-    /// Io engine api versions
-    #[clap(short, long, value_delimiter = ',', required = true)]
+    /// Maximum polling time. The polling time is doubled, up to it, every time the pools and
+    /// volumes are unchanged, to reduce the load on idle io-engines [default: the polling time]
+    #[clap(long, env = "METRICS_EXPORTER_MAX_POLLING_TIME")]
+    max_polling_time: Option<humantime::Duration>,
+
+    /// Maximum age of the cached io-engine data for /readyz to report ready.
+    /// Defaults to twice the polling time, or the maximum polling time if larger.
+    #[clap(long, env = "METRICS_EXPORTER_READY_MAX_AGE")]
+    ready_max_age: Option<humantime::Duration>,
+
+    /// Maximum time each collector may take within a single scrape. The metrics of the collectors
+    /// which time out are left out of the scrape [default: 2s]
+    #[clap(long, env = "METRICS_EXPORTER_COLLECTOR_TIMEOUT")]
+    collector_timeout: Option<humantime::Duration>,
+
+    /// Number of consecutive failed refreshes of an io-engine after which its refreshes are
+    /// suspended, the cached data being served meanwhile [default: 3]
+    #[clap(
+        long,
+        env = "METRICS_EXPORTER_CIRCUIT_FAILURE_THRESHOLD",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    circuit_failure_threshold: Option<u32>,
+
+    /// Interval between the single refreshes probing whether an io-engine recovered, while its
+    /// refreshes are suspended [default: 60s]
+    #[clap(long, env = "METRICS_EXPORTER_CIRCUIT_PROBE_INTERVAL")]
+    circuit_probe_interval: Option<humantime::Duration>,
+
+    /// Resident memory above which the exporter sheds its collectors, one per scrape from the
+    /// highest cardinality, i.e. the replica stats, volumes, pool device errors and pool info,
+    /// until it is back below 90% of it, eg: 128Mi. No limit is applied by default.
+    #[clap(long, env = "METRICS_EXPORTER_MAX_MEMORY")]
+    max_memory: Option<MemorySize>,
+
+    /// Report the io-engine as under planned maintenance through the io_engine_maintenance_mode
+    /// gauge, eg: while the upgrade job restarts the dataplane. It can also be switched at
+    /// runtime with `PUT /maintenance/on` and `PUT /maintenance/off`.
+    #[clap(long, env = "METRICS_EXPORTER_MAINTENANCE_MODE")]
+    maintenance_mode: bool,
+
+    /// Don't increment the state transition counters, i.e. the grpc reconnects and the collector
+    /// timeouts, while the io-engine is under maintenance.
+    #[clap(long, env = "METRICS_EXPORTER_MAINTENANCE_SUPPRESS_COUNTERS")]
+    maintenance_suppress_counters: bool,
+
+    /// Io engine api versions which may be used, all of them by default. The latest one which
+    /// the io-engine serves is detected, and detected again when the calls to it fail, eg: after
+    /// an upgrade of the io-engine changed its api version
+    #[clap(
+        short,
+        long,
+        alias = "api-version",
+        value_delimiter = ',',
+        env = "METRICS_EXPORTER_API_VERSIONS"
+    )]
     api_versions: Vec<ApiVersion>,
+
+    /// Io engine gRPC endpoints to scrape, as <node-name>=<host>:<port>. When set, a single
+    /// exporter instance scrapes all the given nodes, otherwise only the io-engine of its own pod
+    /// is scraped.
+    #[clap(
+        long,
+        value_delimiter = ',',
+        env = "METRICS_EXPORTER_IO_ENGINE_ENDPOINTS",
+        conflicts_with_all = ["node_name", "endpoint"]
+    )]
+    io_engine_endpoints: Vec<NodeEndpoint>,
+
+    /// Name of the node the io-engine runs on. Defaults to the MY_NODE_NAME env variable, or to
+    /// the hostname when running outside of kubernetes, e.g. in docker or as a systemd service.
+    #[clap(long, env = "METRICS_EXPORTER_NODE_NAME")]
+    node_name: Option<String>,
+
+    /// Io engine gRPC endpoint to scrape, as <host>:<port>. Defaults to the gRPC port on the
+    /// MY_POD_IP env variable.
+    #[clap(long, alias = "io-engine-endpoint", env = "METRICS_EXPORTER_ENDPOINT")]
+    endpoint: Option<String>,
+
+    /// Io engine gRPC port, used when the endpoint is not given [default: 10124]
+    #[clap(long, env = "METRICS_EXPORTER_GRPC_PORT")]
+    grpc_port: Option<u16>,
+
+    /// Timeout to establish the gRPC connection to the io-engine [default: 1s]
+    #[clap(long, env = "METRICS_EXPORTER_CONNECT_TIMEOUT")]
+    connect_timeout: Option<humantime::Duration>,
+
+    /// Timeout of the gRPC requests to the io-engine [default: 5s]
+    #[clap(long, env = "METRICS_EXPORTER_REQUEST_TIMEOUT")]
+    request_timeout: Option<humantime::Duration>,
+
+    /// Timeouts of the refreshes of the resources from the io-engine, overriding the request
+    /// timeout, as <resource>=<duration> where the resource is one of pools, volumes,
+    /// replica_stats or pool_device_errors, eg: `replica_stats=30s,pools=2s`. A refresh which
+    /// times out only leaves its own resource stale.
+    #[clap(long, value_delimiter = ',', env = "METRICS_EXPORTER_REQUEST_TIMEOUTS")]
+    request_timeouts: Vec<RequestTimeout>,
+
+    /// OpenTelemetry collector endpoint to push the metrics to via OTLP/gRPC, in addition to
+    /// serving them on the prometheus endpoint, eg: http://otel-collector:4317.
+    #[clap(long, env = "METRICS_EXPORTER_OTLP_ENDPOINT")]
+    otlp_endpoint: Option<String>,
+
+    /// Expose the experimental metrics, prefixed with `experimental_`. Their names and labels
+    /// may change between releases until they graduate to stable metrics.
+    #[clap(long, env = "METRICS_EXPORTER_ENABLE_EXPERIMENTAL_METRICS")]
+    enable_experimental_metrics: bool,
+
+    /// Track the replica io stats of the v0 api, which are reset whenever the io-engine restarts,
+    /// and export them as counters which keep increasing across the resets.
+    #[clap(long, env = "METRICS_EXPORTER_V0_STATS")]
+    v0_stats: bool,
+
+    /// Prefix of the names of the exported metrics, eg: `mayastor` to export
+    /// `mayastor_disk_pool_total_size_bytes`, to avoid collisions with other storage exporters.
+    /// The exporter_* health metrics are not prefixed.
+    #[clap(long, env = "METRICS_EXPORTER_METRIC_PREFIX")]
+    metric_prefix: Option<String>,
+
+    /// Prometheus Pushgateway to push the metrics to every polling period, in addition to serving
+    /// them on the prometheus endpoint, eg: http://pushgateway:9091.
+    #[clap(
+        long,
+        env = "METRICS_EXPORTER_PUSHGATEWAY_URL",
+        conflicts_with = "remote_write_url"
+    )]
+    pushgateway_url: Option<String>,
+
+    /// Prometheus remote-write endpoint to push the metrics to every polling period, in addition
+    /// to serving them on the prometheus endpoint, eg: http://prometheus:9090/api/v1/write.
+    #[clap(long, env = "METRICS_EXPORTER_REMOTE_WRITE_URL")]
+    remote_write_url: Option<String>,
 }
 
 impl Cli {
     fn args() -> Self {
         Cli::parse()
     }
+
+    /// Fill in the settings which were not given through arguments or env variables from the
+    /// config file, if any.
+    fn with_config_file(mut self) -> Result<Self, ExporterError> {
+        let Some(path) = &self.config else {
+            return Ok(self);
+        };
+        let file = ConfigFile::load(path)?;
+        if self.metrics_endpoint.is_empty() {
+            self.metrics_endpoint = file.metrics_endpoint;
+        }
+        self.polling_time = self.polling_time.or(file.polling_time);
+        self.min_polling_time = self.min_polling_time.or(file.min_polling_time);
+        self.max_polling_time = self.max_polling_time.or(file.max_polling_time);
+        self.ready_max_age = self.ready_max_age.or(file.ready_max_age);
+        self.collector_timeout = self.collector_timeout.or(file.collector_timeout);
+        self.circuit_failure_threshold = self
+            .circuit_failure_threshold
+            .or(file.circuit_failure_threshold);
+        self.circuit_probe_interval = self.circuit_probe_interval.or(file.circuit_probe_interval);
+        self.max_memory = self.max_memory.or(file.max_memory);
+        self.maintenance_mode |= file.maintenance_mode;
+        self.maintenance_suppress_counters |= file.maintenance_suppress_counters;
+        if self.api_versions.is_empty() {
+            self.api_versions = file.api_versions;
+        }
+        if self.io_engine_endpoints.is_empty() {
+            self.io_engine_endpoints = file.io_engine_endpoints;
+        }
+        self.node_name = self.node_name.or(file.node_name);
+        self.endpoint = self.endpoint.or(file.endpoint);
+        self.grpc_port = self.grpc_port.or(file.grpc_port);
+        self.connect_timeout = self.connect_timeout.or(file.connect_timeout);
+        self.request_timeout = self.request_timeout.or(file.request_timeout);
+        if self.request_timeouts.is_empty() {
+            self.request_timeouts = file.request_timeouts;
+        }
+        self.otlp_endpoint = self.otlp_endpoint.or(file.otlp_endpoint);
+        self.enable_experimental_metrics |= file.enable_experimental_metrics;
+        self.v0_stats |= file.v0_stats;
+        self.metric_prefix = self.metric_prefix.or(file.metric_prefix);
+        self.pushgateway_url = self.pushgateway_url.or(file.pushgateway_url);
+        self.remote_write_url = self.remote_write_url.or(file.remote_write_url);
+        Ok(self)
+    }
+
+    /// Get the io-engine gRPC timeouts. The requests of the channel may take up to the longest
+    /// of the request timeouts, each refresh being bound by its own one.
+    fn grpc_timeouts(&self) -> Timeouts {
+        Timeouts::new(
+            self.connect_timeout
+                .map(Into::into)
+                .unwrap_or(DEFAULT_CONNECT_TIMEOUT),
+            self.request_timeouts().max(),
+        )
+    }
+
+    /// Get the request timeouts of the refreshes of the resources.
+    fn request_timeouts(&self) -> RequestTimeouts {
+        let default = self
+            .request_timeout
+            .map(Into::into)
+            .unwrap_or(DEFAULT_REQUEST_TIMEOUT);
+        RequestTimeouts::new(default, &self.request_timeouts)
+    }
+
+    /// Get the target to push the metrics to, if any.
+    fn push_target(&self) -> Result<Option<PushTarget>, ExporterError> {
+        match (&self.pushgateway_url, &self.remote_write_url) {
+            (Some(url), None) => Ok(Some(PushTarget::Pushgateway {
+                url: url.clone(),
+                instance: get_node_name()?,
+            })),
+            (None, Some(url)) => Ok(Some(PushTarget::RemoteWrite { url: url.clone() })),
+            (None, None) => Ok(None),
+            (Some(_), Some(_)) => Err(ExporterError::ConfigError(
+                "Only one of the pushgateway and remote-write urls can be set".to_string(),
+            )),
+        }
+    }
+
+    /// Get the settings which can be reloaded at runtime.
+    fn tunables(&self) -> Tunables {
+        let polling_time = self
+            .polling_time
+            .map(Into::into)
+            .unwrap_or(DEFAULT_POLLING_TIME);
+        let max_polling_time = self
+            .max_polling_time
+            .map(Into::into)
+            .unwrap_or(polling_time);
+        Tunables {
+            polling_time,
+            min_polling_time: self
+                .min_polling_time
+                .map(Into::into)
+                .unwrap_or(polling_time),
+            max_polling_time,
+            // By default allow for one failed poll before reporting as not ready.
+            ready_max_age: self
+                .ready_max_age
+                .map(Into::into)
+                .unwrap_or(2 * polling_time.max(max_polling_time)),
+            collector_timeout: self
+                .collector_timeout
+                .map(Into::into)
+                .unwrap_or(DEFAULT_COLLECTOR_TIMEOUT),
+            circuit_failure_threshold: self
+                .circuit_failure_threshold
+                .unwrap_or(DEFAULT_CIRCUIT_FAILURE_THRESHOLD),
+            circuit_probe_interval: self
+                .circuit_probe_interval
+                .map(Into::into)
+                .unwrap_or(DEFAULT_CIRCUIT_PROBE_INTERVAL),
+            max_memory: self.max_memory,
+            maintenance_mode: self.maintenance_mode,
+            maintenance_suppress_counters: self.maintenance_suppress_counters,
+        }
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<(), ExporterError> {
-    let args = Cli::args();
-
+    let cli = Cli::args();
+    let args = cli.clone().with_config_file()?;
     utils::print_package_info!();
 
     utils::tracing_telemetry::init_tracing("metrics-exporter-io_engine", vec![], None);
 
-    initialize_exporter(&args);
+    initialize_exporter(&args)?;
+    set_config_hash(&args);
+
+    if cli.config.is_some() {
+        tokio::spawn(reload_on_sighup(cli));
+    }
 
     initialize_cache().await;
 
-    // sort to get the latest api version
-    let mut api_versions = args.api_versions;
-    api_versions.sort_by(|a, b| b.cmp(a));
+    // The capabilities of the latest api version are served until the api version is detected.
+    let api_version = args.api_versions.iter().max().unwrap_or(&ApiVersion::V1);
+    initialize_capabilities(api_version.clone());
 
-    let client = init_client(api_versions.get(0).unwrap_or(&ApiVersion::V0).clone()).await?;
+    let timeouts = args.grpc_timeouts();
+    let contexts = init_contexts(
+        args.api_versions,
+        &args.io_engine_endpoints,
+        args.node_name,
+        args.endpoint,
+        args.grpc_port.unwrap_or(DEFAULT_GRPC_PORT),
+        timeouts,
+    )?;
+
+    store_data(contexts).await;
+
+    if let Some(target) = args.push_target()? {
+        tokio::spawn(push_metrics(target));
+    }
+
+    let _meter_provider = match &args.otlp_endpoint {
+        Some(endpoint) => Some(init_otlp_export(
+            endpoint,
+            ExporterConfig::get_config().polling_time(),
+        )?),
+        None => None,
+    };
 
-    store_data(client).await;
     let app = move || {
         actix_web::App::new()
             .wrap(middleware::Logger::default())
             .configure(metric_route)
+            .configure(health_route)
+            .configure(dashboard_route)
+            .configure(capabilities_route)
+            .configure(maintenance_route)
     };
-    HttpServer::new(app)
-        .bind(ExporterConfig::get_config().metrics_endpoint())
-        .map_err(|_| {
+    let mut server = HttpServer::new(app);
+    for listener in bind_listeners(ExporterConfig::get_config().metrics_endpoints())? {
+        server = server.listen(listener).map_err(|_| {
             ExporterError::HttpBindError("Failed to bind endpoint to http server".to_string())
-        })?
+        })?;
+    }
+    server
         .workers(1)
         .run()
         .await
@@ -0,0 +1,85 @@
+use crate::config::ExporterConfig;
+use once_cell::sync::Lazy;
+use prometheus::IntGauge;
+use std::{
+    str::FromStr,
+    sync::atomic::{AtomicBool, Ordering},
+};
+use tracing::info;
+
+/// Whether the io-engine is under planned maintenance, so that alerting pipelines can tell the
+/// planned restarts of the dataplane, eg: by the upgrade job, from real failures.
+pub(crate) static IO_ENGINE_MAINTENANCE_MODE: Lazy<IntGauge> = Lazy::new(|| {
+    IntGauge::new(
+        "io_engine_maintenance_mode",
+        "Whether the io-engine is under planned maintenance",
+    )
+    .expect("Unable to create gauge metric type for io_engine_maintenance_mode")
+});
+
+/// Whether the maintenance mode was switched on through the maintenance endpoint.
+static MAINTENANCE_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Maintenance mode, as given to the maintenance endpoint.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum MaintenanceMode {
+    On,
+    Off,
+}
+
+impl FromStr for MaintenanceMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "on" => Ok(Self::On),
+            "off" => Ok(Self::Off),
+            other => Err(format!(
+                "Invalid maintenance mode '{other}', must be on or off"
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for MaintenanceMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::On => write!(f, "on"),
+            Self::Off => write!(f, "off"),
+        }
+    }
+}
+
+/// Switch the maintenance mode on or off through the maintenance endpoint. It stays on while it
+/// is set in the configuration regardless.
+pub(crate) fn set_maintenance_mode(mode: MaintenanceMode) {
+    let requested = mode == MaintenanceMode::On;
+    if MAINTENANCE_REQUESTED.swap(requested, Ordering::Relaxed) != requested {
+        info!(%mode, "Switched the maintenance mode");
+    }
+    update_maintenance_mode();
+}
+
+/// Get the current maintenance mode, requested either through the maintenance endpoint or the
+/// configuration.
+pub(crate) fn maintenance_mode() -> MaintenanceMode {
+    if MAINTENANCE_REQUESTED.load(Ordering::Relaxed)
+        || ExporterConfig::get_config().maintenance_mode()
+    {
+        MaintenanceMode::On
+    } else {
+        MaintenanceMode::Off
+    }
+}
+
+/// Set the maintenance mode gauge from the current maintenance mode.
+pub(crate) fn update_maintenance_mode() {
+    IO_ENGINE_MAINTENANCE_MODE.set((maintenance_mode() == MaintenanceMode::On).into());
+}
+
+/// Whether the state transition counters, i.e. the grpc reconnects and the collector timeouts,
+/// are not incremented as the io-engine is under maintenance and their suppression is enabled.
+pub(crate) fn suppresses_transitions() -> bool {
+    ExporterConfig::get_config().maintenance_suppress_counters()
+        && maintenance_mode() == MaintenanceMode::On
+}
@@ -0,0 +1,141 @@
+use once_cell::sync::Lazy;
+use prometheus::{IntGaugeVec, Opts};
+use std::{fmt::Display, str::FromStr, sync::Mutex};
+use tracing::{info, warn};
+
+/// Collectors which are shed when the exporter exceeds its memory limit, from the highest to the
+/// lowest cardinality, i.e. the replica stats are shed first. The pool capacity and status are
+/// never shed.
+const SHED_ORDER: [&str; 4] = [
+    "replica_stats",
+    "volume_capacity",
+    "pool_device_errors",
+    "pool_info",
+];
+
+/// The shed collectors are restored once the resident memory is back below this percentage of
+/// the limit, so that they aren't shed and restored on every other scrape.
+const RESTORE_PERCENTAGE: u64 = 90;
+
+/// Whether the collector is shed as the exporter exceeds its memory limit.
+pub(crate) static EXPORTER_OVERLIMIT_SHEDDING: Lazy<IntGaugeVec> = Lazy::new(|| {
+    let gauge = IntGaugeVec::new(
+        Opts::new(
+            "exporter_overlimit_shedding",
+            "Whether the collector is shed as the exporter exceeds its memory limit",
+        ),
+        &["collector"],
+    )
+    .expect("Unable to create gauge metric type for exporter_overlimit_shedding");
+    for collector in SHED_ORDER {
+        gauge.with_label_values(&[collector]).set(0);
+    }
+    gauge
+});
+
+/// Number of collectors of the shed order which are currently shed.
+static SHED_COLLECTORS: Mutex<usize> = Mutex::new(0);
+
+/// Size of memory in bytes, given either in bytes or with a binary suffix, eg: 128Mi.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct MemorySize(u64);
+
+impl MemorySize {
+    /// Get the size in bytes.
+    pub(crate) fn bytes(&self) -> u64 {
+        self.0
+    }
+}
+
+impl FromStr for MemorySize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (number, multiplier) = [("Ki", 1 << 10), ("Mi", 1 << 20), ("Gi", 1 << 30)]
+            .into_iter()
+            .find_map(|(suffix, multiplier)| Some((s.strip_suffix(suffix)?, multiplier)))
+            .unwrap_or((s, 1));
+        number
+            .trim()
+            .parse::<u64>()
+            .ok()
+            .and_then(|number| number.checked_mul(multiplier))
+            .map(Self)
+            .ok_or_else(|| format!("Invalid memory size '{s}', expected eg: 128Mi"))
+    }
+}
+
+impl Display for MemorySize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 % (1 << 20) {
+            0 => write!(f, "{}Mi", self.0 >> 20),
+            _ => write!(f, "{}", self.0),
+        }
+    }
+}
+
+/// Get the resident memory of the exporter in bytes, from the VmRSS of /proc/self/status.
+fn resident_memory() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let kilobytes = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))?
+        .trim()
+        .strip_suffix("kB")?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(kilobytes * 1024)
+}
+
+/// Shed one more collector, from the highest cardinality, when the resident memory of the
+/// exporter exceeds the limit, and restore them all once it is back well below it. This is done
+/// once per scrape, so that the collectors are only shed as far as necessary.
+pub(crate) fn update_shedding(max_memory: Option<MemorySize>) {
+    let mut shed = match SHED_COLLECTORS.lock() {
+        Ok(shed) => shed,
+        Err(error) => error.into_inner(),
+    };
+    let (limit, rss) = match (max_memory, resident_memory()) {
+        (Some(limit), Some(rss)) => (limit, rss),
+        // The limit was removed on reload, or the memory can't be measured.
+        _ => (MemorySize(u64::MAX), 0),
+    };
+
+    if rss > limit.bytes() && *shed < SHED_ORDER.len() {
+        let collector = SHED_ORDER[*shed];
+        warn!(
+            collector,
+            rss,
+            %limit,
+            "The exporter exceeds its memory limit, shedding collector"
+        );
+        EXPORTER_OVERLIMIT_SHEDDING
+            .with_label_values(&[collector])
+            .set(1);
+        *shed += 1;
+    } else if *shed > 0 && rss <= limit.bytes() / 100 * RESTORE_PERCENTAGE {
+        let restored = &SHED_ORDER[.. *shed];
+        info!(
+            ?restored,
+            rss,
+            %limit,
+            "The exporter is back below its memory limit, restoring collectors"
+        );
+        for collector in restored {
+            EXPORTER_OVERLIMIT_SHEDDING
+                .with_label_values(&[collector])
+                .set(0);
+        }
+        *shed = 0;
+    }
+}
+
+/// Whether the collector is shed as the exporter exceeds its memory limit.
+pub(crate) fn is_shed(collector: &str) -> bool {
+    let shed = match SHED_COLLECTORS.lock() {
+        Ok(shed) => *shed,
+        Err(error) => *error.into_inner(),
+    };
+    SHED_ORDER[.. shed].contains(&collector)
+}
@@ -0,0 +1,83 @@
+use crate::{cache::Cache, collector::prefixed_opts, error::ExporterError};
+use opentelemetry::{
+    metrics::{MeterProvider as _, Unit},
+    KeyValue, StringValue,
+};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{metrics::MeterProvider, runtime};
+use prometheus::Opts;
+use std::time::Duration;
+
+/// Name of the meter used to report the io-engine metrics.
+const METER_NAME: &str = "metrics-exporter-io-engine";
+
+/// Start pushing the pool metrics to an OpenTelemetry collector via OTLP/gRPC.
+/// The metrics are read from the shared cache, the same one which backs the prometheus
+/// endpoint, so the io-engine is not queried twice. The returned provider must be kept
+/// alive for as long as the metrics should be exported.
+pub(crate) fn init_otlp_export(
+    endpoint: &str,
+    period: Duration,
+) -> Result<MeterProvider, ExporterError> {
+    let provider = opentelemetry_otlp::new_pipeline()
+        .metrics(runtime::Tokio)
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .tonic()
+                .with_endpoint(endpoint),
+        )
+        .with_period(period)
+        .build()
+        .map_err(|error| ExporterError::OtlpError(error.to_string()))?;
+
+    let meter = provider.meter(METER_NAME);
+    // The same names as the prometheus metrics, including the configured metric prefix.
+    let name = |name: &str| prefixed_opts(Opts::new(name, name), false).fq_name();
+    let pool_total_size = meter
+        .u64_observable_gauge(name("disk_pool_total_size_bytes"))
+        .with_description("Total size of the pool in bytes")
+        .with_unit(Unit::new("By"))
+        .init();
+    let pool_used_size = meter
+        .u64_observable_gauge(name("disk_pool_used_size_bytes"))
+        .with_description("Used size of the pool in bytes")
+        .with_unit(Unit::new("By"))
+        .init();
+    let pool_committed_size = meter
+        .u64_observable_gauge(name("disk_pool_committed_size_bytes"))
+        .with_description("Committed size of the pool in bytes")
+        .with_unit(Unit::new("By"))
+        .init();
+    let pool_status = meter
+        .u64_observable_gauge(name("disk_pool_status"))
+        .with_description("Status of the pool")
+        .init();
+
+    meter
+        .register_callback(
+            &[
+                pool_total_size.as_any(),
+                pool_used_size.as_any(),
+                pool_committed_size.as_any(),
+                pool_status.as_any(),
+            ],
+            move |observer| {
+                let cache = Cache::lock();
+                for (node_name, data) in cache.nodes() {
+                    for pool in data.pools().iter() {
+                        let labels = [
+                            KeyValue::new("node", StringValue::from(node_name.clone())),
+                            KeyValue::new("name", StringValue::from(pool.name().clone())),
+                        ];
+                        observer.observe_u64(&pool_total_size, pool.capacity(), &labels);
+                        observer.observe_u64(&pool_used_size, pool.used(), &labels);
+                        observer.observe_u64(&pool_committed_size, pool.committed(), &labels);
+                        observer.observe_u64(&pool_status, pool.state(), &labels);
+                    }
+                }
+            },
+        )
+        .map_err(|error| ExporterError::OtlpError(error.to_string()))?;
+
+    Ok(provider)
+}
@@ -0,0 +1,149 @@
+use crate::{config::ExporterConfig, error::ExporterError, serve::gather_metrics};
+use prometheus::{
+    proto::{MetricFamily, MetricType},
+    Encoder, TextEncoder,
+};
+use prost::Message;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::time::sleep;
+use tracing::{debug, error};
+
+/// Job name under which the metrics are pushed to the Pushgateway.
+const PUSHGATEWAY_JOB: &str = "metrics-exporter-io-engine";
+
+/// Endpoint the metrics are pushed to, for environments where prometheus cannot scrape the
+/// exporter.
+#[derive(Debug, Clone)]
+pub(crate) enum PushTarget {
+    /// Prometheus Pushgateway base url, eg: http://pushgateway:9091.
+    Pushgateway { url: String, instance: String },
+    /// Prometheus remote-write url, eg: http://prometheus:9090/api/v1/write.
+    RemoteWrite { url: String },
+}
+
+/// Periodically push the metrics to the target, every polling period.
+pub(crate) async fn push_metrics(target: PushTarget) {
+    let client = reqwest::Client::new();
+    loop {
+        let metric_families = gather_metrics().await;
+        match target.push(&client, &metric_families).await {
+            Ok(()) => debug!(?target, "Pushed metrics"),
+            Err(error) => error!(?error, ?target, "Failed to push metrics"),
+        }
+        sleep(ExporterConfig::get_config().polling_time()).await;
+    }
+}
+
+impl PushTarget {
+    /// Push the metric families to the target.
+    async fn push(
+        &self,
+        client: &reqwest::Client,
+        metric_families: &[MetricFamily],
+    ) -> Result<(), ExporterError> {
+        let request = match self {
+            Self::Pushgateway { url, instance } => {
+                let mut buffer = Vec::new();
+                let encoder = TextEncoder::new();
+                encoder
+                    .encode(metric_families, &mut buffer)
+                    .map_err(|error| ExporterError::PushError(error.to_string()))?;
+                // PUT replaces all the metrics of the group, which drops the deleted resources.
+                client
+                    .put(format!(
+                        "{}/metrics/job/{PUSHGATEWAY_JOB}/instance/{instance}",
+                        url.trim_end_matches('/')
+                    ))
+                    .header(reqwest::header::CONTENT_TYPE, encoder.format_type())
+                    .body(buffer)
+            }
+            Self::RemoteWrite { url } => {
+                let body = snap::raw::Encoder::new()
+                    .compress_vec(&write_request(metric_families).encode_to_vec())
+                    .map_err(|error| ExporterError::PushError(error.to_string()))?;
+                client
+                    .post(url)
+                    .header(reqwest::header::CONTENT_TYPE, "application/x-protobuf")
+                    .header(reqwest::header::CONTENT_ENCODING, "snappy")
+                    .header("X-Prometheus-Remote-Write-Version", "0.1.0")
+                    .body(body)
+            }
+        };
+        request
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|error| ExporterError::PushError(error.to_string()))?;
+        Ok(())
+    }
+}
+
+/// Convert the metric families to a remote-write request. Only counters and gauges are
+/// converted, as the exporter does not expose any other metric types.
+fn write_request(metric_families: &[MetricFamily]) -> WriteRequest {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_millis() as i64)
+        .unwrap_or_default();
+    let timeseries = metric_families
+        .iter()
+        .flat_map(|family| {
+            family.get_metric().iter().filter_map(move |metric| {
+                let value = match family.get_field_type() {
+                    MetricType::COUNTER => metric.get_counter().get_value(),
+                    MetricType::GAUGE => metric.get_gauge().get_value(),
+                    _ => return None,
+                };
+                let labels = std::iter::once(Label {
+                    name: "__name__".to_string(),
+                    value: family.get_name().to_string(),
+                })
+                .chain(metric.get_label().iter().map(|label| Label {
+                    name: label.get_name().to_string(),
+                    value: label.get_value().to_string(),
+                }))
+                .collect();
+                Some(TimeSeries {
+                    labels,
+                    samples: vec![Sample { value, timestamp }],
+                })
+            })
+        })
+        .collect();
+    WriteRequest { timeseries }
+}
+
+/// Prometheus remote-write request, see
+/// https://github.com/prometheus/prometheus/blob/main/prompb/remote.proto.
+#[derive(Clone, PartialEq, Message)]
+struct WriteRequest {
+    #[prost(message, repeated, tag = "1")]
+    timeseries: Vec<TimeSeries>,
+}
+
+/// Samples of a single series.
+#[derive(Clone, PartialEq, Message)]
+struct TimeSeries {
+    #[prost(message, repeated, tag = "1")]
+    labels: Vec<Label>,
+    #[prost(message, repeated, tag = "2")]
+    samples: Vec<Sample>,
+}
+
+/// Label of a series, the metric name is given as the `__name__` label.
+#[derive(Clone, PartialEq, Message)]
+struct Label {
+    #[prost(string, tag = "1")]
+    name: String,
+    #[prost(string, tag = "2")]
+    value: String,
+}
+
+/// Value of a series at the given timestamp in milliseconds.
+#[derive(Clone, PartialEq, Message)]
+struct Sample {
+    #[prost(double, tag = "1")]
+    value: f64,
+    #[prost(int64, tag = "2")]
+    timestamp: i64,
+}
@@ -0,0 +1,235 @@
+use crate::{collector::prefixed_opts, info::exporter_version};
+use actix_web::{web, HttpResponse, Responder};
+use prometheus::Opts;
+use serde_json::{json, Value};
+
+/// Number of panels per row of a dashboard.
+const PANELS_PER_ROW: usize = 2;
+/// Width of a panel, the grafana grid is 24 columns wide.
+const PANEL_WIDTH: usize = 24 / PANELS_PER_ROW;
+/// Height of a panel.
+const PANEL_HEIGHT: usize = 8;
+
+/// A time series panel of a dashboard.
+struct Panel {
+    title: &'static str,
+    expr: String,
+    legend: &'static str,
+    unit: &'static str,
+}
+
+impl Panel {
+    fn new(title: &'static str, expr: String, legend: &'static str, unit: &'static str) -> Self {
+        Self {
+            title,
+            expr,
+            legend,
+            unit,
+        }
+    }
+}
+
+/// A dashboard served under `/dashboards/{uid}`.
+struct Dashboard {
+    uid: &'static str,
+    title: &'static str,
+    panels: Vec<Panel>,
+}
+
+/// Full name of an exported metric, built the same way as the collectors do so that the
+/// dashboards always query the exposed metric names, including the configured prefix.
+fn metric(subsystem: &str, name: &str) -> String {
+    prefixed_opts(Opts::new(name, name).subsystem(subsystem), false).fq_name()
+}
+
+/// The dashboards generated for the metrics of this exporter version.
+fn dashboards() -> Vec<Dashboard> {
+    let pool_total = metric("disk_pool", "total_size_bytes");
+    let pool_used = metric("disk_pool", "used_size_bytes");
+    let pool_committed = metric("disk_pool", "committed_size_bytes");
+    let volume_allocated = metric("volume", "allocated_size_bytes");
+    let replica_written = metric("replica", "bytes_written_total");
+    let replica_writes = metric("replica", "num_write_ops_total");
+    vec![
+        Dashboard {
+            uid: "mayastor-pools",
+            title: "Mayastor Pools",
+            panels: vec![
+                Panel::new(
+                    "Pool usage",
+                    format!("{pool_used} / {pool_total}"),
+                    "{{node}}/{{name}}",
+                    "percentunit",
+                ),
+                Panel::new(
+                    "Pool commitment",
+                    format!("{pool_committed} / {pool_total}"),
+                    "{{node}}/{{name}}",
+                    "percentunit",
+                ),
+                Panel::new(
+                    "Pool size",
+                    pool_total.clone(),
+                    "{{node}}/{{name}}",
+                    "bytes",
+                ),
+                Panel::new("Pool used size", pool_used, "{{node}}/{{name}}", "bytes"),
+                Panel::new(
+                    "Pool status",
+                    metric("disk_pool", "status"),
+                    "{{node}}/{{name}}",
+                    "none",
+                ),
+                Panel::new(
+                    "Pool device errors",
+                    format!(
+                        "increase({}[5m]) + increase({}[5m])",
+                        metric("disk_pool_device", "io_errors_total"),
+                        metric("disk_pool_device", "aborts_total"),
+                    ),
+                    "{{node}}/{{pool}} {{device}}",
+                    "none",
+                ),
+            ],
+        },
+        Dashboard {
+            uid: "mayastor-volumes",
+            title: "Mayastor Volumes",
+            panels: vec![
+                Panel::new(
+                    "Volume capacity",
+                    format!(
+                        "max by (uuid) ({})",
+                        metric("volume", "capacity_size_bytes")
+                    ),
+                    "{{uuid}}",
+                    "bytes",
+                ),
+                Panel::new(
+                    "Volume allocated size",
+                    format!("sum by (node, uuid) ({volume_allocated})"),
+                    "{{node}}/{{uuid}}",
+                    "bytes",
+                ),
+                Panel::new(
+                    "Volume snapshots",
+                    format!("max by (uuid) ({})", metric("volume", "snapshot_count")),
+                    "{{uuid}}",
+                    "none",
+                ),
+                Panel::new(
+                    "Oldest volume snapshot age",
+                    format!(
+                        "max by (uuid) ({})",
+                        metric("volume", "oldest_snapshot_age_seconds")
+                    ),
+                    "{{uuid}}",
+                    "s",
+                ),
+            ],
+        },
+        // Rebuilds show up as writes to the new replica, and as the growth of the size it
+        // allocates, the replica throughput requires the v0 stats.
+        Dashboard {
+            uid: "mayastor-rebuilds",
+            title: "Mayastor Rebuilds",
+            panels: vec![
+                Panel::new(
+                    "Replica allocation rate",
+                    format!("deriv({volume_allocated}[5m]) > 0"),
+                    "{{node}}/{{uuid}}",
+                    "Bps",
+                ),
+                Panel::new(
+                    "Replica write throughput",
+                    format!("rate({replica_written}[5m])"),
+                    "{{node}}/{{pool}} {{uuid}}",
+                    "Bps",
+                ),
+                Panel::new(
+                    "Replica write operations",
+                    format!("rate({replica_writes}[5m])"),
+                    "{{node}}/{{pool}} {{uuid}}",
+                    "iops",
+                ),
+            ],
+        },
+    ]
+}
+
+/// Grafana model of the panel, laid out at the given index of the dashboard grid.
+fn panel_json(index: usize, panel: &Panel) -> Value {
+    json!({
+        "id": index + 1,
+        "type": "timeseries",
+        "title": panel.title,
+        "datasource": { "type": "prometheus", "uid": "${datasource}" },
+        "gridPos": {
+            "h": PANEL_HEIGHT,
+            "w": PANEL_WIDTH,
+            "x": (index % PANELS_PER_ROW) * PANEL_WIDTH,
+            "y": (index / PANELS_PER_ROW) * PANEL_HEIGHT,
+        },
+        "fieldConfig": { "defaults": { "unit": panel.unit }, "overrides": [] },
+        "targets": [{
+            "refId": "A",
+            "expr": panel.expr,
+            "legendFormat": panel.legend,
+        }],
+    })
+}
+
+/// Grafana model of the dashboard, tagged with the exporter version it was generated by.
+fn dashboard_json(dashboard: &Dashboard, version: &str) -> Value {
+    json!({
+        "uid": dashboard.uid,
+        "title": dashboard.title,
+        "description": format!("Generated by the metrics exporter {version}"),
+        "tags": ["mayastor", format!("exporter-{version}")],
+        "editable": true,
+        "schemaVersion": 38,
+        "time": { "from": "now-6h", "to": "now" },
+        "refresh": "1m",
+        "templating": {
+            "list": [{
+                "name": "datasource",
+                "label": "Data source",
+                "type": "datasource",
+                "query": "prometheus",
+            }],
+        },
+        "panels": dashboard
+            .panels
+            .iter()
+            .enumerate()
+            .map(|(index, panel)| panel_json(index, panel))
+            .collect::<Vec<_>>(),
+    })
+}
+
+/// Handler listing the dashboards served by the exporter.
+pub(crate) async fn dashboards_handler() -> impl Responder {
+    let version = exporter_version();
+    let dashboards = dashboards()
+        .iter()
+        .map(|dashboard| {
+            json!({
+                "uid": dashboard.uid,
+                "title": dashboard.title,
+                "version": version,
+                "url": format!("/dashboards/{}", dashboard.uid),
+            })
+        })
+        .collect::<Vec<_>>();
+    HttpResponse::Ok().json(dashboards)
+}
+
+/// Handler serving the grafana JSON model of a single dashboard.
+pub(crate) async fn dashboard_handler(uid: web::Path<String>) -> impl Responder {
+    let uid = uid.into_inner();
+    let uid = uid.trim_end_matches(".json");
+    match dashboards().iter().find(|dashboard| dashboard.uid == uid) {
+        Some(dashboard) => HttpResponse::Ok().json(dashboard_json(dashboard, &exporter_version())),
+        None => HttpResponse::NotFound().body(format!("Dashboard '{uid}' not found")),
+    }
+}
@@ -1,27 +1,54 @@
-use crate::collector::pool::{PoolCapacityCollector, PoolStatusCollector};
-use actix_web::{http::header, HttpResponse, Responder};
-use prometheus::{Encoder, Registry};
+use crate::{
+    cache::{
+        Cache, CACHE_POISON_RECOVERIES, CIRCUIT_OPEN, POOL_RESOURCES_CACHED,
+        REFRESH_INTERVAL_SECONDS, VOLUME_RESOURCES_CACHED,
+    },
+    capabilities::{Capabilities, EXPORTER_COLLECTOR_ENABLED},
+    client::grpc_client::{
+        GRPC_RECONNECTS_TOTAL, IO_ENGINE_API_VERSION, LAST_SUCCESSFUL_RPC_TIMESTAMP_SECONDS,
+    },
+    collector::{
+        bdev::PoolDeviceErrorsCollector,
+        collect_gauges, collect_with_timeout,
+        pool::{PoolCapacity, PoolInformation, PoolStatus},
+        stats::ReplicaStatsCollector,
+        volume::VolumeCapacity,
+        COLLECTOR_TIMEOUTS,
+    },
+    config::ExporterConfig,
+    info::{EXPORTER_BUILD_INFO, EXPORTER_CONFIG_HASH, NODE_IDENTITY_MISCONFIGURED},
+    maintenance::{
+        maintenance_mode, set_maintenance_mode, update_maintenance_mode, MaintenanceMode,
+        IO_ENGINE_MAINTENANCE_MODE,
+    },
+    memory::{update_shedding, EXPORTER_OVERLIMIT_SHEDDING},
+    serve::openmetrics,
+};
+use actix_web::{http::header, web, HttpRequest, HttpResponse, Responder};
+use prometheus::{proto::MetricFamily, Encoder, Registry};
 use tracing::{error, warn};
 
 /// Handler for metrics. Initializes all collector and serves data over Http.
-pub(crate) async fn metrics_handler() -> impl Responder {
-    let pools_collector = PoolCapacityCollector::default();
-    let pool_status_collector = PoolStatusCollector::default();
-    // Create a new registry for prometheus
-    let registry = Registry::default();
-    // Register pools collector in the registry
-    if let Err(error) = Registry::register(&registry, Box::new(pools_collector)) {
-        warn!(%error, "Pools collector already registered");
-    }
-    if let Err(error) = Registry::register(&registry, Box::new(pool_status_collector)) {
-        warn!(%error, "Pools status collector already registered");
+/// The OpenMetrics format is served if requested through the Accept header, otherwise the
+/// prometheus text format is used.
+pub(crate) async fn metrics_handler(request: HttpRequest) -> impl Responder {
+    let metric_families = gather_metrics().await;
+
+    let accept = request
+        .headers()
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok());
+    if openmetrics::accepts_openmetrics(accept) {
+        return HttpResponse::Ok()
+            .insert_header((header::CONTENT_TYPE, openmetrics::OPENMETRICS_CONTENT_TYPE))
+            .body(openmetrics::encode(&metric_families));
     }
 
     let mut buffer = Vec::new();
 
     let encoder = prometheus::TextEncoder::new();
     // Starts collecting metrics via calling gatherers
-    if let Err(error) = encoder.encode(&registry.gather(), &mut buffer) {
+    if let Err(error) = encoder.encode(&metric_families, &mut buffer) {
         error!(%error, "Could not encode custom metrics");
     };
 
@@ -36,3 +63,160 @@ pub(crate) async fn metrics_handler() -> impl Responder {
         .insert_header(header::ContentType(mime::TEXT_PLAIN))
         .body(res_custom)
 }
+
+/// Initializes all collectors and gathers their metric families.
+pub(crate) async fn gather_metrics() -> Vec<MetricFamily> {
+    update_shedding(ExporterConfig::get_config().max_memory());
+    update_maintenance_mode();
+    // Each collector is bounded by the collector timeout, the ones which timed out are skipped
+    let pools_collector = collect_gauges::<PoolCapacity>("pool_capacity").await;
+    let pool_status_collector = collect_gauges::<PoolStatus>("pool_status").await;
+    let pool_info_collector = collect_gauges::<PoolInformation>("pool_info").await;
+    let volume_capacity_collector = collect_gauges::<VolumeCapacity>("volume_capacity").await;
+    let replica_stats_collector =
+        collect_with_timeout("replica_stats", Box::new(ReplicaStatsCollector::default())).await;
+    let pool_device_errors_collector = collect_with_timeout(
+        "pool_device_errors",
+        Box::new(PoolDeviceErrorsCollector::default()),
+    )
+    .await;
+    // Create a new registry for prometheus
+    let registry = Registry::default();
+    // Register pools collector in the registry
+    if let Some(pools_collector) = pools_collector {
+        if let Err(error) = Registry::register(&registry, pools_collector) {
+            warn!(%error, "Pools collector already registered");
+        }
+    }
+    if let Some(pool_status_collector) = pool_status_collector {
+        if let Err(error) = Registry::register(&registry, pool_status_collector) {
+            warn!(%error, "Pools status collector already registered");
+        }
+    }
+    if let Some(pool_info_collector) = pool_info_collector {
+        if let Err(error) = Registry::register(&registry, pool_info_collector) {
+            warn!(%error, "Pools info collector already registered");
+        }
+    }
+    if let Some(volume_capacity_collector) = volume_capacity_collector {
+        if let Err(error) = Registry::register(&registry, volume_capacity_collector) {
+            warn!(%error, "Volumes capacity collector already registered");
+        }
+    }
+    if let Some(replica_stats_collector) = replica_stats_collector {
+        if let Err(error) = Registry::register(&registry, replica_stats_collector) {
+            warn!(%error, "Replica stats collector already registered");
+        }
+    }
+    if let Some(pool_device_errors_collector) = pool_device_errors_collector {
+        if let Err(error) = Registry::register(&registry, pool_device_errors_collector) {
+            warn!(%error, "Pool device errors collector already registered");
+        }
+    }
+    if let Err(error) = Registry::register(&registry, Box::new(COLLECTOR_TIMEOUTS.clone())) {
+        warn!(%error, "Collector timeouts counter already registered");
+    }
+    if let Err(error) = Registry::register(&registry, Box::new(CACHE_POISON_RECOVERIES.clone())) {
+        warn!(%error, "Cache poison recoveries counter already registered");
+    }
+    if let Err(error) = Registry::register(&registry, Box::new(POOL_RESOURCES_CACHED.clone())) {
+        warn!(%error, "Cached pools gauge already registered");
+    }
+    if let Err(error) = Registry::register(&registry, Box::new(VOLUME_RESOURCES_CACHED.clone())) {
+        warn!(%error, "Cached volumes gauge already registered");
+    }
+    if let Err(error) = Registry::register(&registry, Box::new(REFRESH_INTERVAL_SECONDS.clone())) {
+        warn!(%error, "Refresh interval gauge already registered");
+    }
+    if let Err(error) = Registry::register(&registry, Box::new(CIRCUIT_OPEN.clone())) {
+        warn!(%error, "Circuit open gauge already registered");
+    }
+    if let Err(error) = Registry::register(
+        &registry,
+        Box::new(LAST_SUCCESSFUL_RPC_TIMESTAMP_SECONDS.clone()),
+    ) {
+        warn!(%error, "Last successful rpc timestamp gauge already registered");
+    }
+    if let Err(error) = Registry::register(&registry, Box::new(IO_ENGINE_API_VERSION.clone())) {
+        warn!(%error, "Io-engine api version gauge already registered");
+    }
+    if let Err(error) = Registry::register(&registry, Box::new(GRPC_RECONNECTS_TOTAL.clone())) {
+        warn!(%error, "Grpc reconnects counter already registered");
+    }
+    if let Err(error) = Registry::register(&registry, Box::new(EXPORTER_BUILD_INFO.clone())) {
+        warn!(%error, "Build info gauge already registered");
+    }
+    if let Err(error) = Registry::register(&registry, Box::new(EXPORTER_CONFIG_HASH.clone())) {
+        warn!(%error, "Config hash gauge already registered");
+    }
+    if let Err(error) = Registry::register(&registry, Box::new(NODE_IDENTITY_MISCONFIGURED.clone()))
+    {
+        warn!(%error, "Node identity gauge already registered");
+    }
+    if let Err(error) = Registry::register(&registry, Box::new(EXPORTER_OVERLIMIT_SHEDDING.clone()))
+    {
+        warn!(%error, "Overlimit shedding gauge already registered");
+    }
+    if let Err(error) = Registry::register(&registry, Box::new(EXPORTER_COLLECTOR_ENABLED.clone()))
+    {
+        warn!(%error, "Collector enabled gauge already registered");
+    }
+    if let Err(error) = Registry::register(&registry, Box::new(IO_ENGINE_MAINTENANCE_MODE.clone()))
+    {
+        warn!(%error, "Maintenance mode gauge already registered");
+    }
+
+    registry.gather()
+}
+
+/// Handler for liveness. The exporter is alive as long as it can serve requests.
+pub(crate) async fn health_handler() -> impl Responder {
+    HttpResponse::Ok()
+        .insert_header(header::ContentType(mime::TEXT_PLAIN))
+        .body("ok")
+}
+
+/// Handler for readiness. The exporter is ready when the data of all the io-engines has been
+/// refreshed recently, which requires their gRPC connection to be established.
+pub(crate) async fn ready_handler() -> impl Responder {
+    let cache = Cache::lock();
+    let stale_nodes = cache.stale_nodes(ExporterConfig::get_config().ready_max_age());
+    if !stale_nodes.is_empty() {
+        return HttpResponse::ServiceUnavailable()
+            .insert_header(header::ContentType(mime::TEXT_PLAIN))
+            .body(format!("stale data for nodes: {}", stale_nodes.join(",")));
+    }
+    HttpResponse::Ok()
+        .insert_header(header::ContentType(mime::TEXT_PLAIN))
+        .body("ok")
+}
+
+/// Handler for the capabilities. Serves the metric families of every collector along with the
+/// io-engine api versions they require, and whether they are enabled, as JSON.
+pub(crate) async fn capabilities_handler() -> impl Responder {
+    HttpResponse::Ok().json(Capabilities::current())
+}
+
+/// Handler for the maintenance mode. Serves whether the io-engine is under planned maintenance.
+pub(crate) async fn maintenance_handler() -> impl Responder {
+    HttpResponse::Ok()
+        .insert_header(header::ContentType(mime::TEXT_PLAIN))
+        .body(maintenance_mode().to_string())
+}
+
+/// Handler to switch the maintenance mode on or off, eg: by the upgrade job before and after it
+/// restarts the dataplane. Serves the resulting maintenance mode, which stays on while it is set
+/// in the configuration.
+pub(crate) async fn set_maintenance_handler(mode: web::Path<String>) -> impl Responder {
+    match mode.parse::<MaintenanceMode>() {
+        Ok(mode) => {
+            set_maintenance_mode(mode);
+            HttpResponse::Ok()
+                .insert_header(header::ContentType(mime::TEXT_PLAIN))
+                .body(maintenance_mode().to_string())
+        }
+        Err(error) => HttpResponse::BadRequest()
+            .insert_header(header::ContentType(mime::TEXT_PLAIN))
+            .body(error),
+    }
+}
@@ -0,0 +1,42 @@
+use crate::error::ExporterError;
+use socket2::{Domain, Protocol, Socket, Type};
+use std::net::{SocketAddr, TcpListener};
+
+/// Backlog of the metrics server listeners.
+const LISTEN_BACKLOG: i32 = 1024;
+
+/// Create the listeners of the metrics server for the given addresses.
+/// An IPv6 address is bound as IPv6-only when an IPv4 address is bound on the same port, eg:
+/// `[::]:9502` and `0.0.0.0:9502` on dual-stack nodes, as they would conflict otherwise.
+/// A single IPv6 wildcard address keeps the system default, which usually also accepts IPv4.
+pub(crate) fn bind_listeners(addrs: &[SocketAddr]) -> Result<Vec<TcpListener>, ExporterError> {
+    addrs
+        .iter()
+        .map(|addr| {
+            let only_v6 = addr.is_ipv6()
+                && addrs
+                    .iter()
+                    .any(|other| other.is_ipv4() && other.port() == addr.port());
+            bind_listener(addr, only_v6).map_err(|error| {
+                ExporterError::HttpBindError(format!("Failed to bind {addr}: {error}"))
+            })
+        })
+        .collect()
+}
+
+/// Create a listener for the given address.
+fn bind_listener(addr: &SocketAddr, only_v6: bool) -> std::io::Result<TcpListener> {
+    let socket = Socket::new(
+        Domain::for_address(*addr),
+        Type::STREAM,
+        Some(Protocol::TCP),
+    )?;
+    if only_v6 {
+        socket.set_only_v6(true)?;
+    }
+    socket.set_reuse_address(true)?;
+    socket.set_nonblocking(true)?;
+    socket.bind(&(*addr).into())?;
+    socket.listen(LISTEN_BACKLOG)?;
+    Ok(socket.into())
+}
@@ -1,7 +1,44 @@
 use actix_web::web;
+/// module for the generated grafana dashboards.
+mod dashboards;
 /// module for prometheus handlers.
 mod handler;
+/// module for the metrics server listeners.
+mod listener;
+/// module for the OpenMetrics text exposition format.
+mod openmetrics;
+
+pub(crate) use handler::gather_metrics;
+pub(crate) use listener::bind_listeners;
 
 pub(crate) fn metric_route(cfg: &mut web::ServiceConfig) {
     cfg.route("/metrics", web::get().to(handler::metrics_handler));
 }
+
+pub(crate) fn health_route(cfg: &mut web::ServiceConfig) {
+    cfg.route("/healthz", web::get().to(handler::health_handler))
+        .route("/readyz", web::get().to(handler::ready_handler));
+}
+
+pub(crate) fn dashboard_route(cfg: &mut web::ServiceConfig) {
+    cfg.route("/dashboards", web::get().to(dashboards::dashboards_handler))
+        .route(
+            "/dashboards/{uid}",
+            web::get().to(dashboards::dashboard_handler),
+        );
+}
+
+pub(crate) fn capabilities_route(cfg: &mut web::ServiceConfig) {
+    cfg.route(
+        "/capabilities",
+        web::get().to(handler::capabilities_handler),
+    );
+}
+
+pub(crate) fn maintenance_route(cfg: &mut web::ServiceConfig) {
+    cfg.route("/maintenance", web::get().to(handler::maintenance_handler))
+        .route(
+            "/maintenance/{mode}",
+            web::put().to(handler::set_maintenance_handler),
+        );
+}
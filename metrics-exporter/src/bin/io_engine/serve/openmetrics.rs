@@ -0,0 +1,184 @@
+use prometheus::proto::{LabelPair, MetricFamily, MetricType};
+use std::fmt::Write;
+
+/// Content type of the OpenMetrics text exposition format.
+pub(crate) const OPENMETRICS_CONTENT_TYPE: &str =
+    "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Units recognised from the metric name suffix, which are exposed with a `# UNIT` line.
+const UNITS: [&str; 2] = ["bytes", "seconds"];
+
+/// Check whether the given Accept header value asks for the OpenMetrics format.
+pub(crate) fn accepts_openmetrics(accept: Option<&str>) -> bool {
+    accept
+        .map(|accept| {
+            accept
+                .split(',')
+                .any(|media| media.trim().starts_with("application/openmetrics-text"))
+        })
+        .unwrap_or(false)
+}
+
+/// Encode the metric families in the OpenMetrics text format.
+/// Counters are exposed with the `_total` suffix on their samples only, as the family name must
+/// not carry it. Exemplars are not exposed as the prometheus registry does not record them.
+pub(crate) fn encode(families: &[MetricFamily]) -> String {
+    let mut out = String::new();
+    for family in families {
+        let name = family.get_name();
+        let metric_type = family.get_field_type();
+        let (family_name, type_name) = match metric_type {
+            MetricType::COUNTER => (name.strip_suffix("_total").unwrap_or(name), "counter"),
+            MetricType::GAUGE => (name, "gauge"),
+            MetricType::HISTOGRAM => (name, "histogram"),
+            MetricType::SUMMARY => (name, "summary"),
+            MetricType::UNTYPED => (name, "unknown"),
+        };
+
+        let _ = writeln!(out, "# TYPE {family_name} {type_name}");
+        if let Some(unit) = UNITS
+            .iter()
+            .find(|unit| family_name.ends_with(&format!("_{unit}")))
+        {
+            let _ = writeln!(out, "# UNIT {family_name} {unit}");
+        }
+        if !family.get_help().is_empty() {
+            let _ = writeln!(out, "# HELP {family_name} {}", escape(family.get_help()));
+        }
+
+        for metric in family.get_metric() {
+            let labels = metric.get_label();
+            match metric_type {
+                MetricType::COUNTER => write_sample(
+                    &mut out,
+                    &format!("{family_name}_total"),
+                    labels,
+                    None,
+                    metric.get_counter().get_value(),
+                ),
+                MetricType::GAUGE => write_sample(
+                    &mut out,
+                    family_name,
+                    labels,
+                    None,
+                    metric.get_gauge().get_value(),
+                ),
+                MetricType::UNTYPED => write_sample(
+                    &mut out,
+                    family_name,
+                    labels,
+                    None,
+                    metric.get_untyped().get_value(),
+                ),
+                MetricType::HISTOGRAM => {
+                    let histogram = metric.get_histogram();
+                    let bucket_name = format!("{family_name}_bucket");
+                    for bucket in histogram.get_bucket() {
+                        write_sample(
+                            &mut out,
+                            &bucket_name,
+                            labels,
+                            Some(("le", bucket.get_upper_bound())),
+                            bucket.get_cumulative_count() as f64,
+                        );
+                    }
+                    write_sample(
+                        &mut out,
+                        &bucket_name,
+                        labels,
+                        Some(("le", f64::INFINITY)),
+                        histogram.get_sample_count() as f64,
+                    );
+                    write_sample(
+                        &mut out,
+                        &format!("{family_name}_count"),
+                        labels,
+                        None,
+                        histogram.get_sample_count() as f64,
+                    );
+                    write_sample(
+                        &mut out,
+                        &format!("{family_name}_sum"),
+                        labels,
+                        None,
+                        histogram.get_sample_sum(),
+                    );
+                }
+                MetricType::SUMMARY => {
+                    let summary = metric.get_summary();
+                    for quantile in summary.get_quantile() {
+                        write_sample(
+                            &mut out,
+                            family_name,
+                            labels,
+                            Some(("quantile", quantile.get_quantile())),
+                            quantile.get_value(),
+                        );
+                    }
+                    write_sample(
+                        &mut out,
+                        &format!("{family_name}_count"),
+                        labels,
+                        None,
+                        summary.get_sample_count() as f64,
+                    );
+                    write_sample(
+                        &mut out,
+                        &format!("{family_name}_sum"),
+                        labels,
+                        None,
+                        summary.get_sample_sum(),
+                    );
+                }
+            }
+        }
+    }
+    out.push_str("# EOF\n");
+    out
+}
+
+/// Write a single sample line, with an optional extra label such as `le` or `quantile`.
+fn write_sample(
+    out: &mut String,
+    name: &str,
+    labels: &[LabelPair],
+    extra_label: Option<(&str, f64)>,
+    value: f64,
+) {
+    let mut pairs = labels
+        .iter()
+        .map(|label| format!("{}=\"{}\"", label.get_name(), escape(label.get_value())))
+        .collect::<Vec<String>>();
+    if let Some((label, bound)) = extra_label {
+        pairs.push(format!("{label}=\"{}\"", format_float(bound)));
+    }
+    if pairs.is_empty() {
+        let _ = writeln!(out, "{name} {}", format_float(value));
+    } else {
+        let _ = writeln!(out, "{name}{{{}}} {}", pairs.join(","), format_float(value));
+    }
+}
+
+/// Format a float value as expected by OpenMetrics.
+fn format_float(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value.is_sign_positive() {
+            "+Inf"
+        } else {
+            "-Inf"
+        }
+        .to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// Escape backslashes, double quotes and line feeds in help texts and label values.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -60,6 +60,7 @@ let
     "metrics-exporter"
     "rpc"
     "console-logger"
+    "io-engine-client"
     "call-home"
     "upgrade"
     "dependencies/control-plane/openapi/Cargo.toml"